@@ -149,7 +149,7 @@ fn main() {
         json_approx += id.len();
         json_approx += node.to_json_value().to_string().len();
     }
-    json_approx += state.sequence_order.iter().map(|s| s.len() + 3).sum::<usize>();
+    json_approx += state.generations.order().iter().map(|s| s.len() + 3).sum::<usize>();
 
     println!("| Format | Size (10 nodes, 200 char prompts) |");
     println!("|--------|-----------------------------------|");
@@ -0,0 +1,227 @@
+//! Role-based permissions.
+//!
+//! Both roots carry an `acl` section listing owners, editors and commenters
+//! by user ID. A `PermissionContext` names the local user and how strictly
+//! the manager enforces the ACL before each local edit:
+//!
+//! - `off` (default): no checks.
+//! - `warn`: disallowed edits go through and are recorded
+//!   (`take_permission_warnings()`).
+//! - `deny`: disallowed edits fail with `PermissionDenied`.
+//!
+//! Changes received from peers are never checked. Any peer holding the
+//! document can write to it, so these checks keep honest clients in line;
+//! the relay has to apply the same rules to reject forged changes.
+//!
+//! A document without owners is unclaimed and allows everything, so
+//! enabling enforcement does not lock users out of existing documents. The
+//! first `grant_role(user, Owner)` claims it.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use autosurgeon::{Hydrate, Reconcile};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CollabError, CollabResult};
+
+/// A user's role in a document.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Edits, comments and manages roles.
+    Owner,
+    /// Edits and comments.
+    Editor,
+    /// Comments only.
+    Commenter,
+}
+
+impl Role {
+    /// Returns true if this role may perform `action`.
+    pub fn allows(self, action: Action) -> bool {
+        match action {
+            Action::Manage => self == Role::Owner,
+            Action::Edit => self != Role::Commenter,
+            Action::Comment => true,
+        }
+    }
+}
+
+/// A kind of local mutation checked against the ACL.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    /// Any change to document content.
+    Edit,
+    /// Adding or resolving comments.
+    Comment,
+    /// Granting and revoking roles.
+    Manage,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Action::Edit => "edit",
+            Action::Comment => "comment",
+            Action::Manage => "manage",
+        })
+    }
+}
+
+/// Users by role. Each map is keyed by user ID (values are always true);
+/// maps rather than lists so concurrent grants merge.
+#[derive(Debug, Clone, Default, Reconcile, Hydrate, Serialize, Deserialize, PartialEq)]
+pub struct Acl {
+    pub owners: HashMap<String, bool>,
+    pub editors: HashMap<String, bool>,
+    pub commenters: HashMap<String, bool>,
+}
+
+impl Acl {
+    /// Returns true if the document has no owner yet.
+    pub fn is_unclaimed(&self) -> bool {
+        self.owners.is_empty()
+    }
+
+    /// Returns the highest role held by `user_id`.
+    pub fn role_of(&self, user_id: &str) -> Option<Role> {
+        if self.owners.contains_key(user_id) {
+            Some(Role::Owner)
+        } else if self.editors.contains_key(user_id) {
+            Some(Role::Editor)
+        } else if self.commenters.contains_key(user_id) {
+            Some(Role::Commenter)
+        } else {
+            None
+        }
+    }
+
+    /// Gives `user_id` exactly `role`, replacing any other role.
+    pub fn grant(&mut self, user_id: &str, role: Role) {
+        self.revoke(user_id);
+        let users = match role {
+            Role::Owner => &mut self.owners,
+            Role::Editor => &mut self.editors,
+            Role::Commenter => &mut self.commenters,
+        };
+        users.insert(user_id.to_string(), true);
+    }
+
+    /// Removes every role of `user_id`. Returns false if it had none.
+    pub fn revoke(&mut self, user_id: &str) -> bool {
+        let removed = [&mut self.owners, &mut self.editors, &mut self.commenters]
+            .into_iter()
+            .filter_map(|users| users.remove(user_id))
+            .count();
+        removed > 0
+    }
+
+    /// Returns true if `user_id` may perform `action` (always true while
+    /// the document is unclaimed).
+    pub fn allows(&self, user_id: &str, action: Action) -> bool {
+        self.is_unclaimed() || self.role_of(user_id).is_some_and(|role| role.allows(action))
+    }
+
+    /// Fails with `PermissionDenied` unless `user_id` may perform `action`.
+    pub fn check(&self, user_id: &str, action: Action) -> CollabResult<()> {
+        if self.allows(user_id, action) {
+            Ok(())
+        } else {
+            Err(CollabError::permission_denied(user_id, action.to_string()))
+        }
+    }
+}
+
+/// How strictly the ACL is enforced.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Enforcement {
+    #[default]
+    Off,
+    Warn,
+    Deny,
+}
+
+/// The local user and the enforcement mode applied to their edits.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct PermissionContext {
+    pub user_id: String,
+    pub enforcement: Enforcement,
+}
+
+impl PermissionContext {
+    /// Creates a context for `user_id`.
+    pub fn new(user_id: impl Into<String>, enforcement: Enforcement) -> Self {
+        Self {
+            user_id: user_id.into(),
+            enforcement,
+        }
+    }
+
+    /// Checks `action` against `acl` according to the enforcement mode,
+    /// pushing the denial onto `warnings` in warn mode.
+    pub(crate) fn enforce(
+        &self,
+        acl: &Acl,
+        action: Action,
+        warnings: &mut Vec<String>,
+    ) -> CollabResult<()> {
+        match (self.enforcement, acl.check(&self.user_id, action)) {
+            (Enforcement::Off, _) | (_, Ok(())) => Ok(()),
+            (Enforcement::Warn, Err(e)) => {
+                warnings.push(e.to_string());
+                Ok(())
+            }
+            (Enforcement::Deny, Err(e)) => Err(e),
+        }
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roles_and_unclaimed_documents() {
+        let mut acl = Acl::default();
+        assert!(acl.allows("anyone", Action::Manage));
+
+        acl.grant("alice", Role::Owner);
+        acl.grant("bob", Role::Commenter);
+        assert!(acl.allows("alice", Action::Manage));
+        assert!(acl.allows("bob", Action::Comment));
+        assert!(!acl.allows("bob", Action::Edit));
+        assert!(!acl.allows("eve", Action::Comment));
+
+        acl.grant("bob", Role::Editor);
+        assert_eq!(acl.role_of("bob"), Some(Role::Editor));
+        assert!(acl.commenters.is_empty());
+        assert!(acl.revoke("bob"));
+        assert!(!acl.revoke("bob"));
+    }
+
+    #[test]
+    fn test_enforcement_modes() {
+        let mut acl = Acl::default();
+        acl.grant("alice", Role::Owner);
+        let mut warnings = Vec::new();
+
+        let off = PermissionContext::new("eve", Enforcement::Off);
+        assert!(off.enforce(&acl, Action::Edit, &mut warnings).is_ok());
+        let warn = PermissionContext::new("eve", Enforcement::Warn);
+        assert!(warn.enforce(&acl, Action::Edit, &mut warnings).is_ok());
+        assert_eq!(warnings.len(), 1);
+        let deny = PermissionContext::new("eve", Enforcement::Deny);
+        assert!(matches!(
+            deny.enforce(&acl, Action::Edit, &mut warnings),
+            Err(CollabError::PermissionDenied { .. })
+        ));
+    }
+}
@@ -0,0 +1,359 @@
+//! Debounced autosave scheduling.
+//!
+//! `AutosaveTracker` decides *when* to save: it watches the document heads
+//! and reports a save as due once edits have paused for `debounce_ms`, or
+//! once changes have been pending for `max_wait_ms` during continuous
+//! editing. It never touches the document itself, so it works with either
+//! manager and any transport.
+//!
+//! The WASM `AutosaveController` wraps a tracker and performs the save: it
+//! hands the changes since the last successful save (`save_since()` of the
+//! saved heads) to a JS callback, or flushes the manager's attached store
+//! when no callback is given. A callback that throws leaves the changes
+//! pending for the next save, and other `saveIncremental()` calls by the app
+//! do not take changes away from it. Drive it from a single timer:
+//!
+//! ```js
+//! const autosave = new AutosaveController(2000, 10000, (bytes) => api.append(id, bytes));
+//! setInterval(() => autosave.poll(manager, Date.now()), 500);
+//! window.addEventListener('beforeunload', () => autosave.flush(manager));
+//! ```
+
+use automerge::ChangeHash;
+use serde::{Deserialize, Serialize};
+
+/// Timing rules for autosave.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AutosaveConfig {
+    /// Quiet period after the last change before saving (milliseconds).
+    pub debounce_ms: i64,
+    /// Longest time changes may stay unsaved while edits keep coming
+    /// (milliseconds).
+    pub max_wait_ms: i64,
+}
+
+impl Default for AutosaveConfig {
+    fn default() -> Self {
+        Self {
+            debounce_ms: 2_000,
+            max_wait_ms: 10_000,
+        }
+    }
+}
+
+impl AutosaveConfig {
+    /// Creates a config; `max_wait_ms` is raised to at least `debounce_ms`.
+    pub fn new(debounce_ms: i64, max_wait_ms: i64) -> Self {
+        let debounce_ms = debounce_ms.max(0);
+        Self {
+            debounce_ms,
+            max_wait_ms: max_wait_ms.max(debounce_ms),
+        }
+    }
+}
+
+/// Tracks unsaved changes by comparing document heads over time.
+#[derive(Debug, Clone)]
+pub struct AutosaveTracker {
+    config: AutosaveConfig,
+    /// Heads at the last save; None until the first observation.
+    saved_heads: Option<Vec<ChangeHash>>,
+    /// Heads at the last observation.
+    seen_heads: Vec<ChangeHash>,
+    /// When the first unsaved change was observed.
+    dirty_since: Option<i64>,
+    /// When the heads last changed.
+    last_change_at: i64,
+}
+
+impl AutosaveTracker {
+    /// Creates a tracker. The heads seen by the first `observe()` count as
+    /// saved.
+    pub fn new(config: AutosaveConfig) -> Self {
+        Self {
+            config,
+            saved_heads: None,
+            seen_heads: Vec::new(),
+            dirty_since: None,
+            last_change_at: 0,
+        }
+    }
+
+    /// Returns the timing rules.
+    pub fn config(&self) -> AutosaveConfig {
+        self.config
+    }
+
+    /// Records the document heads at `now` (milliseconds). Returns true if
+    /// there are unsaved changes.
+    pub fn observe(&mut self, heads: &[ChangeHash], now: i64) -> bool {
+        let Some(saved) = &self.saved_heads else {
+            self.mark_saved(heads);
+            return false;
+        };
+        if heads != self.seen_heads.as_slice() {
+            if self.dirty_since.is_none() && heads != saved.as_slice() {
+                self.dirty_since = Some(now);
+            }
+            self.seen_heads = heads.to_vec();
+            self.last_change_at = now;
+        }
+        self.is_dirty()
+    }
+
+    /// Returns true if changes were observed since the last save.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty_since.is_some()
+    }
+
+    /// Returns true if a save should happen at `now`.
+    pub fn is_due(&self, now: i64) -> bool {
+        match self.dirty_since {
+            Some(since) => {
+                now - self.last_change_at >= self.config.debounce_ms
+                    || now - since >= self.config.max_wait_ms
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the heads covered by the last save (empty before the first
+    /// observation).
+    pub fn saved_heads(&self) -> &[ChangeHash] {
+        self.saved_heads.as_deref().unwrap_or_default()
+    }
+
+    /// Saves through `write` and, only if it succeeds, records a save
+    /// covering `heads`. `write` receives the heads of the previous save, so
+    /// it can upload exactly the changes since then. Returns true if there
+    /// were unsaved changes.
+    pub fn save_with<E>(
+        &mut self,
+        heads: &[ChangeHash],
+        write: impl FnOnce(&[ChangeHash]) -> Result<(), E>,
+    ) -> Result<bool, E> {
+        write(self.saved_heads())?;
+        let saved = self.is_dirty();
+        self.mark_saved(heads);
+        Ok(saved)
+    }
+
+    /// Records a completed save covering `heads`.
+    pub fn mark_saved(&mut self, heads: &[ChangeHash]) {
+        self.saved_heads = Some(heads.to_vec());
+        self.seen_heads = heads.to_vec();
+        self.dirty_since = None;
+    }
+}
+
+// =============================================================================
+// WASM BINDINGS
+// =============================================================================
+
+#[cfg(feature = "wasm")]
+mod wasm {
+    use js_sys::{Function, Uint8Array};
+    use wasm_bindgen::prelude::*;
+
+    use super::{AutosaveConfig, AutosaveTracker};
+    use crate::error::CollabResult;
+    use crate::sequence::{JsSequenceManager, SequenceManager};
+    #[cfg(feature = "storyboard")]
+    use crate::storyboard::{wasm::JsStoryboardManager, StoryboardManager};
+
+    /// The manager operations autosave needs.
+    trait Autosave {
+        fn heads(&mut self) -> Vec<automerge::ChangeHash>;
+        fn save_since(&mut self, heads: &[automerge::ChangeHash]) -> Vec<u8>;
+        fn flush_store(&mut self) -> CollabResult<usize>;
+    }
+
+    macro_rules! impl_autosave {
+        ($manager:ty) => {
+            impl Autosave for $manager {
+                fn heads(&mut self) -> Vec<automerge::ChangeHash> {
+                    self.get_heads()
+                }
+                fn save_since(&mut self, heads: &[automerge::ChangeHash]) -> Vec<u8> {
+                    <$manager>::save_since(self, heads)
+                }
+                fn flush_store(&mut self) -> CollabResult<usize> {
+                    <$manager>::flush_store(self)
+                }
+            }
+        };
+    }
+
+    impl_autosave!(SequenceManager);
+    #[cfg(feature = "storyboard")]
+    impl_autosave!(StoryboardManager);
+
+    /// Debounced autosave for either manager (see `crate::autosave`).
+    #[wasm_bindgen(js_name = AutosaveController)]
+    pub struct JsAutosaveController {
+        tracker: AutosaveTracker,
+        callback: Option<Function>,
+    }
+
+    #[wasm_bindgen(js_class = AutosaveController)]
+    impl JsAutosaveController {
+        /// Creates a controller.
+        ///
+        /// # Arguments
+        /// * `debounce_ms` - Quiet period after the last edit before saving
+        /// * `max_wait_ms` - Longest time edits may stay unsaved
+        /// * `callback` - Receives incremental bytes (Uint8Array); when omitted
+        ///   the manager's attached store is flushed instead
+        #[wasm_bindgen(constructor)]
+        pub fn new(debounce_ms: f64, max_wait_ms: f64, callback: Option<Function>) -> Self {
+            Self {
+                tracker: AutosaveTracker::new(AutosaveConfig::new(
+                    debounce_ms as i64,
+                    max_wait_ms as i64,
+                )),
+                callback,
+            }
+        }
+
+        /// Returns true if edits were seen since the last save.
+        #[wasm_bindgen(js_name = isDirty)]
+        pub fn is_dirty(&self) -> bool {
+            self.tracker.is_dirty()
+        }
+
+        /// Checks a sequence manager for edits and saves if due.
+        /// Returns true if a save happened.
+        pub fn poll(&mut self, manager: &mut JsSequenceManager, now: f64) -> Result<bool, JsValue> {
+            self.poll_manager(&mut manager.inner, now)
+        }
+
+        /// Saves pending sequence edits immediately (e.g. on `beforeunload`).
+        pub fn flush(&mut self, manager: &mut JsSequenceManager) -> Result<bool, JsValue> {
+            self.save(&mut manager.inner)
+        }
+
+        /// Checks a storyboard manager for edits and saves if due.
+        #[cfg(feature = "storyboard")]
+        #[wasm_bindgen(js_name = pollStoryboard)]
+        pub fn poll_storyboard(
+            &mut self,
+            manager: &mut JsStoryboardManager,
+            now: f64,
+        ) -> Result<bool, JsValue> {
+            self.poll_manager(&mut manager.inner, now)
+        }
+
+        /// Saves pending storyboard edits immediately.
+        #[cfg(feature = "storyboard")]
+        #[wasm_bindgen(js_name = flushStoryboard)]
+        pub fn flush_storyboard(&mut self, manager: &mut JsStoryboardManager) -> Result<bool, JsValue> {
+            self.save(&mut manager.inner)
+        }
+    }
+
+    impl JsAutosaveController {
+        fn poll_manager(&mut self, manager: &mut impl Autosave, now: f64) -> Result<bool, JsValue> {
+            let now = now as i64;
+            self.tracker.observe(&manager.heads(), now);
+            if !self.tracker.is_due(now) {
+                return Ok(false);
+            }
+            self.save(manager)
+        }
+
+        fn save(&mut self, manager: &mut impl Autosave) -> Result<bool, JsValue> {
+            let heads = manager.heads();
+            let callback = &self.callback;
+            self.tracker.save_with(&heads, |saved_heads| {
+                match callback {
+                    Some(callback) => {
+                        let bytes = manager.save_since(saved_heads);
+                        if !bytes.is_empty() {
+                            callback.call1(&JsValue::NULL, &Uint8Array::from(&bytes[..]))?;
+                        }
+                    }
+                    None => {
+                        manager.flush_store().map_err(JsValue::from)?;
+                    }
+                }
+                Ok(())
+            })
+        }
+    }
+}
+
+#[cfg(feature = "wasm")]
+pub use wasm::JsAutosaveController;
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequence::{GenerationNode, SequenceManager};
+
+    #[test]
+    fn test_autosave_debounce_and_max_wait() {
+        let mut manager = SequenceManager::new();
+        let mut tracker = AutosaveTracker::new(AutosaveConfig::new(1_000, 5_000));
+
+        // First observation is the baseline
+        assert!(!tracker.observe(&manager.get_heads(), 0));
+        assert!(!tracker.is_due(10_000));
+
+        // Continuous editing: debounce keeps resetting, max wait kicks in
+        for t in (100..=5_100).step_by(500) {
+            let id = format!("gen-{}", t);
+            manager.create_and_append(&id, GenerationNode::new(&id, "t2i")).unwrap();
+            assert!(tracker.observe(&manager.get_heads(), t));
+            if t < 5_100 {
+                assert!(!tracker.is_due(t), "not due at {}", t);
+            }
+        }
+        assert!(tracker.is_due(5_100));
+        tracker.mark_saved(&manager.get_heads());
+        assert!(!tracker.is_dirty());
+
+        // A single edit saves once the debounce period passes
+        manager.create_and_append("late", GenerationNode::new("late", "t2i")).unwrap();
+        tracker.observe(&manager.get_heads(), 6_000);
+        assert!(!tracker.is_due(6_500));
+        tracker.observe(&manager.get_heads(), 6_500);
+        assert!(tracker.is_due(7_000));
+    }
+
+    #[test]
+    fn test_failed_save_keeps_changes_pending() {
+        let mut manager = SequenceManager::new();
+        let mut tracker = AutosaveTracker::new(AutosaveConfig::new(0, 0));
+        tracker.observe(&manager.get_heads(), 0);
+        let base = manager.save();
+        manager.create_and_append("a", GenerationNode::new("a", "t2i")).unwrap();
+        tracker.observe(&manager.get_heads(), 100);
+
+        // The upload fails, and the app saves incrementally on its own
+        let heads = manager.get_heads();
+        let failed = tracker.save_with(&heads, |since| {
+            assert!(!manager.save_since(since).is_empty());
+            Err("offline")
+        });
+        assert_eq!(failed, Err("offline"));
+        assert!(tracker.is_dirty());
+        manager.save_incremental();
+
+        // The retry still carries the change
+        let mut uploaded = Vec::new();
+        let saved = tracker.save_with(&heads, |since| {
+            uploaded = manager.save_since(since);
+            Ok::<_, ()>(())
+        });
+        assert_eq!(saved, Ok(true));
+        let mut replica = SequenceManager::from_bytes(&base).unwrap();
+        replica.load_incremental(&uploaded).unwrap();
+        assert!(replica.get_node("a").unwrap().is_some());
+        assert_eq!(tracker.saved_heads(), heads.as_slice());
+        assert!(!tracker.is_dirty());
+    }
+}
@@ -0,0 +1,114 @@
+//! CLI tool to convert an Automerge storyboard back to TypeScript JSON.
+//!
+//! Usage:
+//!   automerge2json --input storyboard.automerge [--output storyboard.json] [--pretty] [--validate]
+
+#[path = "../json2automerge/input.rs"]
+mod input;
+mod output;
+#[path = "../json2automerge/transform.rs"]
+mod transform;
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+
+use heyocollab::naming;
+use heyocollab::storyboard::{assets, StoryboardManager, StoryboardRoot};
+use input::InputStoryboard;
+use output::OutputStoryboard;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "automerge2json",
+    about = "Convert an Automerge storyboard to TypeScript storyboard JSON",
+    version
+)]
+struct Args {
+    /// Input Automerge file path
+    #[arg(short, long)]
+    input: PathBuf,
+
+    /// Output file path (defaults to input path with .json extension)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Pretty-print the JSON output
+    #[arg(long, default_value = "false")]
+    pretty: bool,
+
+    /// Validate output by converting it back and comparing the JSON it produces
+    #[arg(long, default_value = "false")]
+    validate: bool,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    // 1. Validate input exists
+    let input_path = &args.input;
+    if !input_path.exists() {
+        anyhow::bail!("Input file does not exist: {}", input_path.display());
+    }
+
+    // 2. Load and hydrate the document
+    let binary = std::fs::read(input_path).context("Failed to read input file")?;
+    let mut manager =
+        StoryboardManager::from_bytes(&binary).context("Failed to load Automerge document")?;
+    let mut root = manager
+        .get_state()
+        .context("Failed to hydrate storyboard state")?;
+    // The TypeScript shape has no blob map, so put the data URLs back inline
+    assets::inline_blobs(&mut root);
+    // It has no order keys either, so the list carries the display order
+    root.scene_order = root.ordered_scene_ids();
+
+    // 3. Transform to TypeScript shape
+    let output: OutputStoryboard = root.into();
+
+    // 4. Serialize
+    let json = if args.pretty {
+        serde_json::to_string_pretty(&output)
+    } else {
+        serde_json::to_string(&output)
+    }
+    .context("Failed to serialize JSON")?;
+
+    // 5. Optional round-trip validation. Both sides go through the output
+    // structs, so CRDT-only fields never take part in the comparison.
+    if args.validate {
+        let parsed: InputStoryboard =
+            serde_json::from_str(&json).context("Failed to parse generated JSON")?;
+        let round_tripped = OutputStoryboard::from(StoryboardRoot::from(parsed));
+        let expected = serde_json::to_value(&output).context("Failed to serialize JSON")?;
+        let actual = serde_json::to_value(&round_tripped).context("Failed to serialize JSON")?;
+        if actual != expected {
+            anyhow::bail!(
+                "Validation failed: JSON does not round-trip to the same storyboard \
+                 (check for fields json2automerge does not read back)"
+            );
+        }
+        println!("✓ Validation passed!");
+    }
+
+    // 6. Determine output path
+    let output_path = args.output.unwrap_or_else(|| {
+        let stem = input_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        input_path.with_file_name(naming::file_name(&stem, "json"))
+    });
+
+    // 7. Write output
+    std::fs::write(&output_path, &json).context("Failed to write output file")?;
+
+    println!(
+        "Successfully converted {} → {}",
+        input_path.display(),
+        output_path.display()
+    );
+
+    Ok(())
+}
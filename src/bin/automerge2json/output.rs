@@ -0,0 +1,797 @@
+//! Output structs for emitting TypeScript storyboard JSON.
+//!
+//! The inverse of `json2automerge`'s input structs:
+//! - HashMap + order Vec → arrays (in document order)
+//! - Same field naming as storyboard.ts (camelCase root, snake_case data)
+//!
+//! Entries missing from an order list are appended sorted by ID so no data
+//! is dropped. Nested maps become `BTreeMap`s for stable output.
+
+use std::collections::{BTreeMap, HashMap};
+
+use heyocollab::storyboard::model::*;
+use heyocollab::{DocRef, OrderedMap, RawFields};
+use serde::Serialize;
+
+// =============================================================================
+// HELPER FUNCTIONS
+// =============================================================================
+
+/// Converts a HashMap + order vector back to an array.
+///
+/// Items follow `order`; items not referenced by `order` come last, sorted
+/// by key. Order entries without an item are skipped.
+fn hashmap_to_array<T, U>(mut map: HashMap<String, T>, order: &[String]) -> Vec<U>
+where
+    U: From<T>,
+{
+    let mut items = Vec::with_capacity(map.len());
+    for id in order {
+        if let Some(item) = map.remove(id) {
+            items.push(item.into());
+        }
+    }
+    let mut rest: Vec<(String, T)> = map.into_iter().collect();
+    rest.sort_by(|a, b| a.0.cmp(&b.0));
+    items.extend(rest.into_iter().map(|(_, item)| item.into()));
+    items
+}
+
+/// Converts an `OrderedMap` to an array, like `hashmap_to_array`.
+fn ordered_to_array<T, U>(map: OrderedMap<T>) -> Vec<U>
+where
+    U: From<T>,
+{
+    let (items, order) = map.into_parts();
+    hashmap_to_array(items, &order)
+}
+
+/// Converts a HashMap to a key-sorted BTreeMap, converting values.
+fn sorted_map<T, U>(map: HashMap<String, T>) -> BTreeMap<String, U>
+where
+    U: From<T>,
+{
+    map.into_iter().map(|(k, v)| (k, v.into())).collect()
+}
+
+fn convert_vec<T, U>(items: Vec<T>) -> Vec<U>
+where
+    U: From<T>,
+{
+    items.into_iter().map(U::from).collect()
+}
+
+// =============================================================================
+// ROOT STORYBOARD
+// =============================================================================
+
+/// Root storyboard structure for TypeScript.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputStoryboard {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub script_content: String,
+    pub script_files: Vec<String>,
+    pub drive_file_ids: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail_image: Option<String>,
+
+    pub created_at: i64,
+    pub last_updated: i64,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_shots: Option<i32>,
+
+    pub status: String,
+    pub current_stage: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_synced_sha: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encrypted_by_email: Option<String>,
+
+    pub data: OutputStoryData,
+}
+
+/// Story data container.
+#[derive(Debug, Serialize)]
+pub struct OutputStoryData {
+    pub processing_stages: OutputProcessingStages,
+    pub scenes: Vec<OutputScene>,
+    pub metadata: OutputMetadata,
+    #[serde(rename = "uploadedAssets")]
+    pub uploaded_assets: Vec<OutputUploadedAsset>,
+}
+
+/// Processing stages with arrays.
+#[derive(Debug, Serialize)]
+pub struct OutputProcessingStages {
+    pub characters: Vec<OutputCharacter>,
+    pub props: Vec<OutputProp>,
+    pub sets: Vec<OutputSetLocation>,
+}
+
+/// Story metadata.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_shots: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aspect_ratio: Option<String>,
+}
+
+impl From<StoryboardRoot> for OutputStoryboard {
+    fn from(root: StoryboardRoot) -> Self {
+        let stages = root.processing_stages;
+        let characters = ordered_to_array(stages.characters);
+        let props = ordered_to_array(stages.props);
+        let sets = ordered_to_array(stages.sets);
+        let scenes = hashmap_to_array(root.scenes, &root.scene_order);
+        // Uploaded assets have no order list in the document
+        let uploaded_assets = hashmap_to_array(root.uploaded_assets, &[]);
+
+        Self {
+            id: root.id,
+            title: root.title,
+            description: root.description,
+            script_content: root.script_content,
+            script_files: root.script_files,
+            drive_file_ids: root.drive_file_ids,
+            thumbnail_image: root.thumbnail_image,
+            created_at: root.created_at,
+            last_updated: root.last_updated,
+            num_shots: root.num_shots,
+            status: root.status,
+            current_stage: root.current_stage,
+            last_synced_sha: root.last_synced_sha,
+            encrypted_by_email: root.encrypted_by_email,
+            data: OutputStoryData {
+                processing_stages: OutputProcessingStages {
+                    characters,
+                    props,
+                    sets,
+                },
+                scenes,
+                metadata: root.metadata.into(),
+                uploaded_assets,
+            },
+        }
+    }
+}
+
+impl From<StoryboardMetadata> for OutputMetadata {
+    fn from(model: StoryboardMetadata) -> Self {
+        Self {
+            num_shots: model.num_shots,
+            aspect_ratio: model.aspect_ratio,
+        }
+    }
+}
+
+// =============================================================================
+// ENTITIES (Character, Prop, SetLocation)
+// =============================================================================
+
+/// Character entity.
+#[derive(Debug, Serialize)]
+pub struct OutputCharacter {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub image_prompt: String,
+    pub attributes: BTreeMap<String, String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enhanced: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation_status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description_status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description_error: Option<String>,
+    #[serde(rename = "loraModelId", skip_serializing_if = "Option::is_none")]
+    pub lora_model_id: Option<String>,
+    pub history: Vec<OutputAssetHistory>,
+}
+
+impl From<Character> for OutputCharacter {
+    fn from(model: Character) -> Self {
+        Self {
+            id: model.id,
+            name: model.name,
+            description: model.description,
+            image_prompt: model.image_prompt,
+            attributes: model.attributes.into_iter().collect(),
+            tag: model.tag,
+            caption: model.caption,
+            image: model.image,
+            enhanced: model.enhanced,
+            generation_id: model.generation_id,
+            generation_status: model.generation_status,
+            description_status: model.description_status,
+            description_error: model.description_error,
+            lora_model_id: model.lora_model_id,
+            history: convert_vec(model.history),
+        }
+    }
+}
+
+/// Prop entity.
+#[derive(Debug, Serialize)]
+pub struct OutputProp {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub image_prompt: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enhanced: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation_status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description_status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description_error: Option<String>,
+    #[serde(rename = "loraModelId", skip_serializing_if = "Option::is_none")]
+    pub lora_model_id: Option<String>,
+    pub history: Vec<OutputAssetHistory>,
+}
+
+impl From<Prop> for OutputProp {
+    fn from(model: Prop) -> Self {
+        Self {
+            id: model.id,
+            name: model.name,
+            description: model.description,
+            image_prompt: model.image_prompt,
+            tag: model.tag,
+            caption: model.caption,
+            image: model.image,
+            original_image: model.original_image,
+            enhanced: model.enhanced,
+            generation_id: model.generation_id,
+            generation_status: model.generation_status,
+            description_status: model.description_status,
+            description_error: model.description_error,
+            lora_model_id: model.lora_model_id,
+            history: convert_vec(model.history),
+        }
+    }
+}
+
+/// Set/Location entity.
+#[derive(Debug, Serialize)]
+pub struct OutputSetLocation {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub image_prompt: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enhanced: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation_status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description_status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description_error: Option<String>,
+    #[serde(rename = "loraModelId", skip_serializing_if = "Option::is_none")]
+    pub lora_model_id: Option<String>,
+    pub history: Vec<OutputAssetHistory>,
+}
+
+impl From<SetLocation> for OutputSetLocation {
+    fn from(model: SetLocation) -> Self {
+        Self {
+            id: model.id,
+            name: model.name,
+            description: model.description,
+            image_prompt: model.image_prompt,
+            tag: model.tag,
+            caption: model.caption,
+            image: model.image,
+            enhanced: model.enhanced,
+            generation_id: model.generation_id,
+            generation_status: model.generation_status,
+            description_status: model.description_status,
+            description_error: model.description_error,
+            lora_model_id: model.lora_model_id,
+            history: convert_vec(model.history),
+        }
+    }
+}
+
+// =============================================================================
+// SCENE
+// =============================================================================
+
+/// Scene with shots array.
+#[derive(Debug, Serialize)]
+pub struct OutputScene {
+    pub id: String,
+    pub scene_number: i32,
+    pub title: String,
+    pub header: String,
+    pub content: String,
+
+    pub visual_density_score: i32,
+    pub predicted_shots: i32,
+    pub reasoning: String,
+
+    pub characters_present: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub set_ref: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub synopsis: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw_text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub looks_description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outfit_description: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub known_entities: Option<OutputKnownEntities>,
+    pub character_looks: BTreeMap<String, OutputCharacterLook>,
+    pub character_outfits: BTreeMap<String, OutputCharacterOutfit>,
+    pub looks_with_outfit: BTreeMap<String, OutputLooksWithOutfit>,
+    pub outfits: BTreeMap<String, OutputOutfitEntry>,
+
+    pub shots: Vec<OutputShot>,
+}
+
+impl From<Scene> for OutputScene {
+    fn from(model: Scene) -> Self {
+        let shots = ordered_to_array(model.shots);
+        Self {
+            id: model.id,
+            scene_number: model.scene_number,
+            title: model.title,
+            header: model.header,
+            content: model.content,
+            visual_density_score: model.visual_density_score,
+            predicted_shots: model.predicted_shots,
+            reasoning: model.reasoning,
+            characters_present: model.characters_present,
+            set_ref: model.set_ref,
+            synopsis: model.synopsis,
+            time: model.time,
+            raw_text: model.raw_text,
+            looks_description: model.looks_description,
+            outfit_description: model.outfit_description,
+            known_entities: model.known_entities.map(|ke| ke.into()),
+            character_looks: sorted_map(model.character_looks),
+            character_outfits: sorted_map(model.character_outfits),
+            looks_with_outfit: sorted_map(model.looks_with_outfit),
+            outfits: sorted_map(model.outfits),
+            shots,
+        }
+    }
+}
+
+/// Entity references for a scene.
+#[derive(Debug, Serialize)]
+pub struct OutputKnownEntities {
+    pub characters: Vec<OutputEntityRef>,
+    pub sets: Vec<OutputEntityRef>,
+    pub props: Vec<OutputEntityRef>,
+}
+
+impl From<KnownEntities> for OutputKnownEntities {
+    fn from(model: KnownEntities) -> Self {
+        Self {
+            characters: convert_vec(model.characters),
+            sets: convert_vec(model.sets),
+            props: convert_vec(model.props),
+        }
+    }
+}
+
+/// Entity reference.
+#[derive(Debug, Serialize)]
+pub struct OutputEntityRef {
+    pub tag: String,
+    pub name: String,
+}
+
+impl From<EntityRef> for OutputEntityRef {
+    fn from(model: EntityRef) -> Self {
+        Self {
+            tag: model.tag,
+            name: model.name,
+        }
+    }
+}
+
+/// Character look for a scene.
+#[derive(Debug, Serialize)]
+pub struct OutputCharacterLook {
+    pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_prompt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enhanced: Option<bool>,
+    pub history: Vec<OutputAssetHistory>,
+}
+
+impl From<CharacterLook> for OutputCharacterLook {
+    fn from(model: CharacterLook) -> Self {
+        Self {
+            description: model.description,
+            image: model.image,
+            image_prompt: model.image_prompt,
+            generation_id: model.generation_id,
+            caption: model.caption,
+            enhanced: model.enhanced,
+            history: convert_vec(model.history),
+        }
+    }
+}
+
+/// Character outfit for a scene.
+#[derive(Debug, Serialize)]
+pub struct OutputCharacterOutfit {
+    pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_prompt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+    pub history: Vec<OutputAssetHistory>,
+}
+
+impl From<CharacterOutfit> for OutputCharacterOutfit {
+    fn from(model: CharacterOutfit) -> Self {
+        Self {
+            description: model.description,
+            image: model.image,
+            image_prompt: model.image_prompt,
+            generation_id: model.generation_id,
+            caption: model.caption,
+            history: convert_vec(model.history),
+        }
+    }
+}
+
+/// Combined looks + outfit.
+#[derive(Debug, Serialize)]
+pub struct OutputLooksWithOutfit {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+}
+
+impl From<LooksWithOutfit> for OutputLooksWithOutfit {
+    fn from(model: LooksWithOutfit) -> Self {
+        Self {
+            image: model.image,
+            generation_id: model.generation_id,
+            prompt: model.prompt,
+            caption: model.caption,
+        }
+    }
+}
+
+/// Legacy outfit entry.
+#[derive(Debug, Serialize)]
+pub struct OutputOutfitEntry {
+    pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_prompt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation_id: Option<String>,
+}
+
+impl From<OutfitEntry> for OutputOutfitEntry {
+    fn from(model: OutfitEntry) -> Self {
+        Self {
+            description: model.description,
+            image: model.image,
+            image_prompt: model.image_prompt,
+            generation_id: model.generation_id,
+        }
+    }
+}
+
+// =============================================================================
+// SHOT
+// =============================================================================
+
+/// Shot with all fields.
+///
+/// CRDT-only fields (comments, provider, job_id, status timestamps) have no
+/// TypeScript counterpart and are not emitted.
+#[derive(Debug, Serialize)]
+pub struct OutputShot {
+    pub id: String,
+    pub shot_number: i32,
+    pub image_prompt: String,
+
+    // Phase 1 fields
+    pub size: String,
+    pub angle: String,
+    pub visual_description: String,
+    pub assets_used: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation_status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation_ref: Option<DocRef>,
+
+    // Phase 2 fields
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assets: Option<Vec<OutputAssetRef>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub environment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub camera: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub additional_instructions: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub known_assets: Option<OutputShotKnownAssets>,
+
+    // Deprecated fields
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visual_prompt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub camera_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub camera_angle: Option<String>,
+
+    // Phase 3 fields
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ref_shot_id: Option<i32>,
+
+    pub history: Vec<OutputShotHistory>,
+
+    /// Fields from a newer schema, carried through unchanged
+    #[serde(flatten)]
+    pub extra: RawFields,
+}
+
+impl From<Shot> for OutputShot {
+    fn from(model: Shot) -> Self {
+        Self {
+            id: model.id,
+            shot_number: model.shot_number,
+            image_prompt: model.image_prompt,
+            size: model.size,
+            angle: model.angle,
+            visual_description: model.visual_description,
+            assets_used: model.assets_used,
+            image: model.image,
+            generation_status: model.generation_status,
+            generation_ref: model.generation_ref,
+            assets: model.assets.map(convert_vec),
+            environment: model.environment,
+            action: model.action,
+            camera: model.camera,
+            additional_instructions: model.additional_instructions,
+            known_assets: model.known_assets.map(|ka| ka.into()),
+            title: model.title,
+            visual_prompt: model.visual_prompt,
+            camera_type: model.camera_type,
+            camera_angle: model.camera_angle,
+            subject: model.subject,
+            ref_shot_id: model.ref_shot_id,
+            history: convert_vec(model.history),
+            extra: model.extra,
+        }
+    }
+}
+
+/// Asset reference.
+#[derive(Debug, Serialize)]
+pub struct OutputAssetRef {
+    pub tag: String,
+    pub name: String,
+}
+
+impl From<AssetRef> for OutputAssetRef {
+    fn from(model: AssetRef) -> Self {
+        Self {
+            tag: model.tag,
+            name: model.name,
+        }
+    }
+}
+
+/// Known assets for a shot.
+#[derive(Debug, Serialize)]
+pub struct OutputShotKnownAssets {
+    pub characters: BTreeMap<String, OutputShotCharacterRef>,
+    pub sets: Vec<OutputShotAssetRef>,
+    pub props: Vec<OutputShotAssetRef>,
+}
+
+impl From<ShotKnownAssets> for OutputShotKnownAssets {
+    fn from(model: ShotKnownAssets) -> Self {
+        Self {
+            characters: sorted_map(model.characters),
+            sets: convert_vec(model.sets),
+            props: convert_vec(model.props),
+        }
+    }
+}
+
+/// Character reference in a shot.
+#[derive(Debug, Serialize)]
+pub struct OutputShotCharacterRef {
+    pub description: String,
+    pub outfit: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub looks_with_outfit_image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub looks_image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outfit_image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub character_image: Option<String>,
+}
+
+impl From<ShotCharacterRef> for OutputShotCharacterRef {
+    fn from(model: ShotCharacterRef) -> Self {
+        Self {
+            description: model.description,
+            outfit: model.outfit,
+            looks_with_outfit_image: model.looks_with_outfit_image,
+            looks_image: model.looks_image,
+            outfit_image: model.outfit_image,
+            character_image: model.character_image,
+        }
+    }
+}
+
+/// Asset reference in a shot.
+#[derive(Debug, Serialize)]
+pub struct OutputShotAssetRef {
+    pub tag: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+}
+
+impl From<ShotAssetRef> for OutputShotAssetRef {
+    fn from(model: ShotAssetRef) -> Self {
+        Self {
+            tag: model.tag,
+            name: model.name,
+            image: model.image,
+        }
+    }
+}
+
+// =============================================================================
+// HISTORY
+// =============================================================================
+
+/// Asset history entry.
+#[derive(Debug, Serialize)]
+pub struct OutputAssetHistory {
+    pub id: String,
+    pub image: String,
+    pub image_prompt: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation_id: Option<String>,
+    #[serde(rename = "loraModelId", skip_serializing_if = "Option::is_none")]
+    pub lora_model_id: Option<String>,
+    pub timestamp: i64,
+}
+
+impl From<AssetHistory> for OutputAssetHistory {
+    fn from(model: AssetHistory) -> Self {
+        Self {
+            id: model.id,
+            image: model.image,
+            image_prompt: model.image_prompt,
+            generation_id: model.generation_id,
+            lora_model_id: model.lora_model_id,
+            timestamp: model.timestamp,
+        }
+    }
+}
+
+/// Shot history entry.
+#[derive(Debug, Serialize)]
+pub struct OutputShotHistory {
+    pub id: String,
+    pub image: String,
+    pub prompt: String,
+    pub timestamp: i64,
+}
+
+impl From<ShotHistory> for OutputShotHistory {
+    fn from(model: ShotHistory) -> Self {
+        Self {
+            id: model.id,
+            image: model.image,
+            prompt: model.prompt,
+            timestamp: model.timestamp,
+        }
+    }
+}
+
+// =============================================================================
+// UPLOADED ASSET
+// =============================================================================
+
+/// Uploaded asset.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputUploadedAsset {
+    pub id: String,
+    pub name: String,
+    pub image: String,
+    pub file_type: String,
+    pub file_size: i64,
+    pub uploaded_at: i64,
+}
+
+impl From<UploadedAsset> for OutputUploadedAsset {
+    fn from(model: UploadedAsset) -> Self {
+        Self {
+            id: model.id,
+            name: model.name,
+            image: model.image,
+            file_type: model.file_type,
+            file_size: model.file_size,
+            uploaded_at: model.uploaded_at,
+        }
+    }
+}
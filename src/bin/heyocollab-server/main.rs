@@ -0,0 +1,150 @@
+//! Sync relay server for heyocollab documents.
+//!
+//! Peers connect to `/sync/{doc_id}` over WebSocket and exchange binary
+//! Automerge sync messages; `GET /docs/{doc_id}` downloads the full document.
+//! Documents are persisted through a `FileStore`; IDs it would refuse
+//! (see `validate_doc_id()`) get 400 Bad Request.
+//!
+//! Usage:
+//!   heyocollab-server --data-dir ./docs [--bind 127.0.0.1:8080]
+
+mod room;
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use clap::Parser;
+use futures_util::{SinkExt, StreamExt};
+use tokio::sync::mpsc;
+
+use heyocollab::storage::{validate_doc_id, FileStore};
+use room::{Rooms, OUTBOX_CAPACITY};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "heyocollab-server",
+    about = "Relay Automerge sync messages between peers and persist documents",
+    version
+)]
+struct Args {
+    /// Directory holding document snapshots and change logs
+    #[arg(short, long, env = "HEYOCOLLAB_DATA_DIR")]
+    data_dir: PathBuf,
+
+    /// Address to listen on
+    #[arg(short, long, default_value = "127.0.0.1:8080")]
+    bind: SocketAddr,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let store = FileStore::new(&args.data_dir).context("Failed to open data directory")?;
+    let rooms = Arc::new(Rooms::new(store));
+
+    let app = Router::new()
+        .route("/sync/:doc_id", get(sync_handler))
+        .route("/docs/:doc_id", get(download_handler))
+        .with_state(rooms);
+
+    let listener = tokio::net::TcpListener::bind(args.bind)
+        .await
+        .with_context(|| format!("Failed to bind {}", args.bind))?;
+    println!("Listening on {}", args.bind);
+    axum::serve(listener, app).await.context("Server error")?;
+    Ok(())
+}
+
+// =============================================================================
+// HANDLERS
+// =============================================================================
+
+async fn sync_handler(
+    ws: WebSocketUpgrade,
+    Path(doc_id): Path<String>,
+    State(rooms): State<Arc<Rooms>>,
+) -> Response {
+    if let Err(e) = validate_doc_id(&doc_id) {
+        return (StatusCode::BAD_REQUEST, e.to_string()).into_response();
+    }
+    ws.on_upgrade(move |socket| async move {
+        if let Err(e) = run_peer(socket, doc_id.clone(), rooms).await {
+            eprintln!("[{}] connection closed with error: {:#}", doc_id, e);
+        }
+    })
+}
+
+/// Returns the live room's document, or the stored one if no room is open.
+async fn download_handler(Path(doc_id): Path<String>, State(rooms): State<Arc<Rooms>>) -> Response {
+    if let Err(e) = validate_doc_id(&doc_id) {
+        return (StatusCode::BAD_REQUEST, e.to_string()).into_response();
+    }
+
+    match blocking(move || rooms.load(&doc_id)).await {
+        Ok(Some(bytes)) => {
+            ([(header::CONTENT_TYPE, "application/octet-stream")], bytes).into_response()
+        }
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)).into_response(),
+    }
+}
+
+/// Runs room and store work (locks, file I/O) off the async worker threads.
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T> {
+    tokio::task::spawn_blocking(f).await.context("Blocking task failed")?
+}
+
+// =============================================================================
+// PEER CONNECTION
+// =============================================================================
+
+/// Pumps one WebSocket: outgoing sync messages from the room's outbox,
+/// incoming messages into the room. The peer leaves the room on return.
+async fn run_peer(socket: WebSocket, doc_id: String, rooms: Arc<Rooms>) -> Result<()> {
+    let (mut sink, mut stream) = socket.split();
+    let (outbox, mut inbox) = mpsc::channel::<Vec<u8>>(OUTBOX_CAPACITY);
+    let (room, peer) = {
+        let (rooms, doc_id) = (rooms.clone(), doc_id.clone());
+        blocking(move || rooms.join(&doc_id, outbox)).await?
+    };
+
+    let writer = tokio::spawn(async move {
+        while let Some(message) = inbox.recv().await {
+            if sink.send(Message::Binary(message)).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut result = Ok(());
+    while let Some(message) = stream.next().await {
+        match message {
+            Ok(Message::Binary(bytes)) => {
+                let (room, rooms) = (room.clone(), rooms.clone());
+                if let Err(e) = blocking(move || room.receive(peer, &bytes, rooms.store())).await {
+                    result = Err(e);
+                    break;
+                }
+            }
+            Ok(Message::Close(_)) => break,
+            Ok(_) => {}
+            Err(e) => {
+                result = Err(e).context("WebSocket error");
+                break;
+            }
+        }
+    }
+
+    writer.abort();
+    blocking(move || rooms.leave(&doc_id, &room, peer)).await?;
+    result
+}
@@ -0,0 +1,310 @@
+//! Live documents and their connected peers.
+//!
+//! The server is itself an Automerge peer: every room holds the document and
+//! one sync `State` per connection. A message from one peer is applied to the
+//! room document, then each peer gets whatever it is now missing — this is
+//! how changes are relayed. New changes are appended to the store right away;
+//! a snapshot is written when the last peer leaves.
+//!
+//! Everything here blocks (locks and file I/O); the async handlers call it
+//! through `spawn_blocking`.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use anyhow::{Context, Result};
+use automerge::sync::{self, SyncDoc};
+use automerge::AutoCommit;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::mpsc::Sender;
+
+use heyocollab::storage::{DocumentStore, FileStore};
+
+/// Identifies one WebSocket connection within a room.
+pub type PeerId = u64;
+
+/// Sync messages queued per peer. A peer that falls this far behind is
+/// dropped from the room instead of buffering without bound.
+pub const OUTBOX_CAPACITY: usize = 256;
+
+struct Peer {
+    sync: sync::State,
+    outbox: Sender<Vec<u8>>,
+}
+
+struct RoomState {
+    doc: AutoCommit,
+    peers: HashMap<PeerId, Peer>,
+    next_peer: PeerId,
+}
+
+/// A document with its connected peers.
+pub struct Room {
+    doc_id: String,
+    state: Mutex<RoomState>,
+}
+
+impl Room {
+    fn lock(&self) -> std::sync::MutexGuard<'_, RoomState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Adds a peer and queues the opening sync message for it.
+    fn join(&self, outbox: Sender<Vec<u8>>) -> PeerId {
+        let mut state = self.lock();
+        let id = state.next_peer;
+        state.next_peer += 1;
+        state.peers.insert(
+            id,
+            Peer {
+                sync: sync::State::new(),
+                outbox,
+            },
+        );
+        let RoomState { doc, peers, .. } = &mut *state;
+        flush_peer(doc, peers.get_mut(&id).expect("peer just inserted"));
+        id
+    }
+
+    /// Applies a message from `peer`, persists new changes and relays them.
+    pub fn receive(&self, peer: PeerId, message: &[u8], store: &Mutex<FileStore>) -> Result<()> {
+        let message = sync::Message::decode(message).context("Invalid sync message")?;
+        let mut state = self.lock();
+        let RoomState { doc, peers, .. } = &mut *state;
+
+        let sender = peers.get_mut(&peer).context("Unknown peer")?;
+        doc.sync()
+            .receive_sync_message(&mut sender.sync, message)
+            .context("Failed to apply sync message")?;
+
+        let changes = doc.save_incremental();
+        if !changes.is_empty() {
+            lock_store(store).append_changes(&self.doc_id, &changes)?;
+        }
+
+        peers.retain(|_, peer| flush_peer(doc, peer));
+        Ok(())
+    }
+
+    /// Removes a peer. Returns true if the room is now empty.
+    fn leave(&self, peer: PeerId) -> bool {
+        let mut state = self.lock();
+        state.peers.remove(&peer);
+        state.peers.is_empty()
+    }
+
+    /// Saves the full document.
+    pub fn save(&self) -> Vec<u8> {
+        self.lock().doc.save()
+    }
+}
+
+/// Sends `peer` every message it is missing. Returns false if the peer's
+/// outbox is closed or full; it should then be removed, and its connection
+/// fails on its next message.
+fn flush_peer(doc: &mut AutoCommit, peer: &mut Peer) -> bool {
+    while let Some(message) = doc.sync().generate_sync_message(&mut peer.sync) {
+        match peer.outbox.try_send(message.encode()) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) | Err(TrySendError::Closed(_)) => return false,
+        }
+    }
+    true
+}
+
+fn lock_store(store: &Mutex<FileStore>) -> std::sync::MutexGuard<'_, FileStore> {
+    store.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+// =============================================================================
+// ROOM REGISTRY
+// =============================================================================
+
+/// Shared server state: the store and all live rooms.
+pub struct Rooms {
+    store: Mutex<FileStore>,
+    rooms: Mutex<HashMap<String, Arc<Room>>>,
+}
+
+impl Rooms {
+    pub fn new(store: FileStore) -> Self {
+        Self {
+            store: Mutex::new(store),
+            rooms: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn store(&self) -> &Mutex<FileStore> {
+        &self.store
+    }
+
+    /// Adds a peer to the room for `doc_id`, loading the document from the
+    /// store if the room is not live. Joining under the registry lock keeps
+    /// `leave()` from closing a room a peer is about to enter.
+    pub fn join(
+        &self,
+        doc_id: &str,
+        outbox: Sender<Vec<u8>>,
+    ) -> Result<(Arc<Room>, PeerId)> {
+        let mut rooms = self.rooms.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(room) = rooms.get(doc_id) {
+            let peer = room.join(outbox);
+            return Ok((room.clone(), peer));
+        }
+
+        let mut doc = match lock_store(&self.store).load(doc_id)? {
+            Some(bytes) => AutoCommit::load(&bytes)
+                .with_context(|| format!("Stored document '{}' is corrupt", doc_id))?,
+            None => AutoCommit::new(),
+        };
+        // A loaded document's save cursor starts empty; move it past the
+        // stored history so receive() only appends new changes to the log
+        doc.save_incremental();
+        let room = Arc::new(Room {
+            doc_id: doc_id.to_string(),
+            state: Mutex::new(RoomState {
+                doc,
+                peers: HashMap::new(),
+                next_peer: 0,
+            }),
+        });
+        rooms.insert(doc_id.to_string(), room.clone());
+        let peer = room.join(outbox);
+        Ok((room, peer))
+    }
+
+    /// Returns the live room for `doc_id`, if any.
+    pub fn get(&self, doc_id: &str) -> Option<Arc<Room>> {
+        let rooms = self.rooms.lock().unwrap_or_else(PoisonError::into_inner);
+        rooms.get(doc_id).cloned()
+    }
+
+    /// Returns the live room's document, or the stored one if no room is open.
+    pub fn load(&self, doc_id: &str) -> Result<Option<Vec<u8>>> {
+        match self.get(doc_id) {
+            Some(room) => Ok(Some(room.save())),
+            None => Ok(lock_store(&self.store).load(doc_id)?),
+        }
+    }
+
+    /// Removes `peer`; when the room empties it is snapshotted and closed.
+    pub fn leave(&self, doc_id: &str, room: &Room, peer: PeerId) -> Result<()> {
+        let mut rooms = self.rooms.lock().unwrap_or_else(PoisonError::into_inner);
+        if room.leave(peer) {
+            rooms.remove(doc_id);
+            let snapshot = room.save();
+            lock_store(&self.store).save_snapshot(doc_id, &snapshot)?;
+        }
+        Ok(())
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use automerge::transaction::Transactable;
+    use automerge::{ReadDoc, ROOT};
+    use tokio::sync::mpsc::{self, Receiver};
+
+    fn temp_rooms() -> (Rooms, std::path::PathBuf) {
+        let dir = std::env::temp_dir().join(format!("heyocollab-rooms-{}", uuid::Uuid::new_v4()));
+        (Rooms::new(FileStore::new(&dir).unwrap()), dir)
+    }
+
+    /// Syncs `client` with the room until neither side has anything to send.
+    fn sync_client(
+        client: &mut AutoCommit,
+        state: &mut sync::State,
+        room: &Room,
+        peer: PeerId,
+        inbox: &mut Receiver<Vec<u8>>,
+        store: &Mutex<FileStore>,
+    ) {
+        loop {
+            let mut progressed = false;
+            while let Ok(bytes) = inbox.try_recv() {
+                let message = sync::Message::decode(&bytes).unwrap();
+                client.sync().receive_sync_message(state, message).unwrap();
+                progressed = true;
+            }
+            if let Some(message) = client.sync().generate_sync_message(state) {
+                room.receive(peer, &message.encode(), store).unwrap();
+                progressed = true;
+            }
+            if !progressed {
+                break;
+            }
+        }
+    }
+
+    #[test]
+    fn test_room_round_trip() {
+        let (rooms, dir) = temp_rooms();
+
+        let mut client = AutoCommit::new();
+        client.put(ROOT, "title", "Opening").unwrap();
+        client.commit();
+
+        let (outbox, mut inbox) = mpsc::channel(OUTBOX_CAPACITY);
+        let (room, peer) = rooms.join("doc-1", outbox).unwrap();
+        let mut state = sync::State::new();
+        sync_client(&mut client, &mut state, &room, peer, &mut inbox, rooms.store());
+
+        // Changes are logged while the room is live
+        assert!(dir.join("doc-1.changes").exists());
+        rooms.leave("doc-1", &room, peer).unwrap();
+        assert!(rooms.get("doc-1").is_none());
+        assert!(!dir.join("doc-1.changes").exists());
+
+        // Reopening loads the snapshot, and new edits sync on top of it
+        let (outbox, mut inbox) = mpsc::channel(OUTBOX_CAPACITY);
+        let (room, peer) = rooms.join("doc-1", outbox).unwrap();
+        let loaded = AutoCommit::load(&room.save()).unwrap();
+        assert_eq!(loaded.get_heads(), client.get_heads());
+
+        client.put(ROOT, "title", "Revised").unwrap();
+        client.commit();
+        let mut state = sync::State::new();
+        sync_client(&mut client, &mut state, &room, peer, &mut inbox, rooms.store());
+        rooms.leave("doc-1", &room, peer).unwrap();
+
+        let stored = AutoCommit::load(&rooms.load("doc-1").unwrap().unwrap()).unwrap();
+        let (title, _) = stored.get(ROOT, "title").unwrap().unwrap();
+        assert_eq!(title.to_str(), Some("Revised"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_room_rejects_unsafe_doc_id() {
+        let (rooms, dir) = temp_rooms();
+        let (outbox, _inbox) = mpsc::channel(OUTBOX_CAPACITY);
+        assert!(rooms.join("a.b", outbox).is_err());
+        assert!(rooms.get("a.b").is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_full_outbox_drops_peer() {
+        let (rooms, dir) = temp_rooms();
+        let (slow_outbox, _slow_inbox) = mpsc::channel(1);
+        let (room, slow) = rooms.join("doc-1", slow_outbox).unwrap();
+        let (outbox, mut inbox) = mpsc::channel(OUTBOX_CAPACITY);
+        let (_, peer) = rooms.join("doc-1", outbox).unwrap();
+
+        let mut client = AutoCommit::new();
+        client.put(ROOT, "title", "Opening").unwrap();
+        client.commit();
+        let mut state = sync::State::new();
+        sync_client(&mut client, &mut state, &room, peer, &mut inbox, rooms.store());
+
+        // The slow peer never read its opening message, so it was dropped
+        assert!(!room.lock().peers.contains_key(&slow));
+        assert!(room.lock().peers.contains_key(&peer));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -5,6 +5,7 @@
 //! - Uses arrays instead of HashMaps (transformed later)
 //! - Uses camelCase for some root-level fields
 
+use heyocollab::{DocRef, RawFields};
 use serde::Deserialize;
 use std::collections::HashMap;
 
@@ -353,6 +354,7 @@ pub struct InputShot {
 
     pub image: Option<String>,
     pub generation_status: Option<String>,
+    pub generation_ref: Option<DocRef>,
 
     // Phase 2 fields
     pub assets: Option<Vec<InputAssetRef>>,
@@ -373,6 +375,10 @@ pub struct InputShot {
     pub ref_shot_id: Option<i32>,
 
     pub history: Vec<InputShotHistory>,
+
+    /// Fields from a newer schema, carried through unchanged
+    #[serde(flatten)]
+    pub extra: RawFields,
 }
 
 impl Default for InputShot {
@@ -387,6 +393,7 @@ impl Default for InputShot {
             assets_used: Vec::new(),
             image: None,
             generation_status: None,
+            generation_ref: None,
             assets: None,
             environment: None,
             action: None,
@@ -400,6 +407,7 @@ impl Default for InputShot {
             subject: None,
             ref_shot_id: None,
             history: Vec::new(),
+            extra: RawFields::new(),
         }
     }
 }
@@ -2,6 +2,7 @@
 //!
 //! Usage:
 //!   json2automerge --input storyboard.json [--output storyboard.automerge] [--validate] [--stats]
+//!                  [--fountain script.fountain]
 
 mod input;
 mod transform;
@@ -11,6 +12,7 @@ use std::path::PathBuf;
 use anyhow::{Context, Result};
 use clap::Parser;
 
+use heyocollab::naming;
 use heyocollab::storyboard::{StoryboardManager, StoryboardRoot};
 use input::InputStoryboard;
 
@@ -36,6 +38,10 @@ struct Args {
     /// Print statistics about the conversion
     #[arg(long, default_value = "false")]
     stats: bool,
+
+    /// Fountain screenplay whose scenes are appended after conversion
+    #[arg(long)]
+    fountain: Option<PathBuf>,
 }
 
 fn main() -> Result<()> {
@@ -58,10 +64,10 @@ fn main() -> Result<()> {
     // Store some stats before transformation
     let input_id = input.id.clone();
     let input_title = input.title.clone();
-    let num_characters = input.data.processing_stages.characters.len();
+    let mut num_characters = input.data.processing_stages.characters.len();
     let num_props = input.data.processing_stages.props.len();
     let num_sets = input.data.processing_stages.sets.len();
-    let num_scenes = input.data.scenes.len();
+    let mut num_scenes = input.data.scenes.len();
     let total_shots: usize = input.data.scenes.iter().map(|s| s.shots.len()).sum();
 
     // 4. Transform to Rust model
@@ -75,14 +81,33 @@ fn main() -> Result<()> {
         })
         .context("Failed to update Automerge document state")?;
 
+    // Append scenes (and new characters) from the screenplay, if given
+    let mut imported_scenes = 0;
+    if let Some(fountain_path) = &args.fountain {
+        let script =
+            std::fs::read_to_string(fountain_path).context("Failed to read Fountain file")?;
+        imported_scenes = manager
+            .import_fountain(&script)
+            .context("Failed to import Fountain script")?
+            .len();
+        num_scenes += imported_scenes;
+        num_characters = manager
+            .hydrate_entities_only()
+            .context("Failed to read characters after import")?
+            .characters
+            .len();
+    }
+
     // 6. Save to binary
     let binary = manager.save();
 
     // 7. Determine output path
     let output_path = args.output.unwrap_or_else(|| {
-        let mut path = input_path.clone();
-        path.set_extension("automerge");
-        path
+        let stem = input_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        input_path.with_file_name(naming::file_name(&stem, "automerge"))
     });
 
     // 8. Write output
@@ -157,6 +182,9 @@ fn main() -> Result<()> {
         println!("  Props:      {}", num_props);
         println!("  Sets:       {}", num_sets);
         println!("  Scenes:     {}", num_scenes);
+        if args.fountain.is_some() {
+            println!("    (from Fountain: {})", imported_scenes);
+        }
         println!("  Shots:      {}", total_shots);
     }
 
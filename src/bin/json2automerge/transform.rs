@@ -8,6 +8,7 @@ use std::collections::HashMap;
 
 use crate::input::*;
 use heyocollab::storyboard::model::*;
+use heyocollab::OrderedMap;
 
 // =============================================================================
 // HELPER FUNCTIONS
@@ -73,18 +74,24 @@ impl From<InputStoryboard> for StoryboardRoot {
             encrypted_by_email: input.encrypted_by_email,
 
             processing_stages: ProcessingStages {
-                characters,
-                character_order,
-                props,
-                prop_order,
-                sets,
-                set_order,
+                characters: OrderedMap::from_parts(characters, character_order),
+                props: OrderedMap::from_parts(props, prop_order),
+                sets: OrderedMap::from_parts(sets, set_order),
+                custom_collections: Default::default(),
             },
 
             scene_order,
+            fractional_order: false,
             scenes,
             uploaded_assets,
+            blobs: Default::default(),
             metadata: input.data.metadata.map(|m| m.into()).unwrap_or_default(),
+            trash: Default::default(),
+            actor_names: Default::default(),
+            acl: Default::default(),
+            snapshots: Default::default(),
+            scene_locks: Default::default(),
+            frozen: Default::default(),
         }
     }
 }
@@ -237,8 +244,10 @@ impl From<InputScene> for Scene {
                 .into_iter()
                 .map(|(k, v)| (k, v.into()))
                 .collect(),
-            shot_order,
-            shots,
+            shots: OrderedMap::from_parts(shots, shot_order),
+            comments: None,
+            thumbnail: None,
+            order_key: None,
         }
     }
 }
@@ -327,6 +336,10 @@ impl From<InputShot> for Shot {
             assets_used: input.assets_used,
             image: input.image,
             generation_status: input.generation_status,
+            generation_status_updated_at: None,
+            provider: None,
+            job_id: None,
+            generation_ref: input.generation_ref,
             assets: input.assets.map(|v| v.into_iter().map(|a| a.into()).collect()),
             environment: input.environment,
             action: input.action,
@@ -340,6 +353,8 @@ impl From<InputShot> for Shot {
             subject: input.subject,
             ref_shot_id: input.ref_shot_id,
             history: input.history.into_iter().map(|h| h.into()).collect(),
+            comments: None,
+            extra: input.extra,
         }
     }
 }
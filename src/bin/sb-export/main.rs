@@ -0,0 +1,150 @@
+//! CLI tool to export a storyboard shot list as CSV or Markdown, or as a
+//! PDF contact sheet.
+//!
+//! Usage:
+//!   sb-export --input storyboard.automerge [--format csv|markdown|pdf] [--output shots.csv]
+//!             [--image-dir images/] [--columns 3]
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+
+use heyocollab::naming;
+use heyocollab::storyboard::{PdfOptions, ShotListFormat, StoryboardManager};
+
+/// Output format: a text shot list or a PDF contact sheet.
+#[derive(Debug, Clone, Copy)]
+enum ExportFormat {
+    ShotList(ShotListFormat),
+    Pdf,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::ShotList(format) => format.extension(),
+            ExportFormat::Pdf => "pdf",
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        if s.eq_ignore_ascii_case("pdf") {
+            return Ok(ExportFormat::Pdf);
+        }
+        s.parse()
+            .map(ExportFormat::ShotList)
+            .map_err(|_| format!("unknown format '{}' (expected csv, markdown or pdf)", s))
+    }
+}
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "sb-export",
+    about = "Export an Automerge storyboard as a CSV or Markdown shot list",
+    version
+)]
+struct Args {
+    /// Input Automerge file path
+    #[arg(short, long)]
+    input: PathBuf,
+
+    /// Output format: csv, markdown or pdf
+    #[arg(short, long, default_value = "csv")]
+    format: ExportFormat,
+
+    /// Output file path (defaults to input path with .csv, .md or .pdf
+    /// extension, "-" for stdout)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// PDF only: directory of JPEG shot images, matched to each shot's image
+    /// URL by file name (other shots get a placeholder)
+    #[arg(long)]
+    image_dir: Option<PathBuf>,
+
+    /// PDF only: thumbnails per row
+    #[arg(long, default_value = "3")]
+    columns: usize,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    // 1. Validate input exists
+    let input_path = &args.input;
+    if !input_path.exists() {
+        anyhow::bail!("Input file does not exist: {}", input_path.display());
+    }
+
+    // 2. Load the document and render the export
+    let binary = std::fs::read(input_path).context("Failed to read input file")?;
+    let mut manager =
+        StoryboardManager::from_bytes(&binary).context("Failed to load Automerge document")?;
+    let exported = match args.format {
+        ExportFormat::ShotList(format) => manager
+            .export_shot_list(format)
+            .context("Failed to render shot list")?
+            .into_bytes(),
+        ExportFormat::Pdf => {
+            let mut options = PdfOptions {
+                columns: args.columns,
+                ..Default::default()
+            };
+            if let Some(image_dir) = &args.image_dir {
+                let root = manager.get_state().context("Failed to hydrate storyboard state")?;
+                let urls = root
+                    .scenes
+                    .values()
+                    .flat_map(|scene| scene.shots.values())
+                    .filter_map(|shot| shot.image.clone());
+                for url in urls {
+                    if let Some(bytes) = read_image(image_dir, &url) {
+                        options.images.insert(url, bytes);
+                    }
+                }
+            }
+            manager.export_pdf(&options).context("Failed to render PDF")?
+        }
+    };
+
+    // 3. Determine output path
+    let output_path = args.output.unwrap_or_else(|| {
+        let stem = input_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        input_path.with_file_name(naming::file_name(&stem, args.format.extension()))
+    });
+
+    // 4. Write output
+    if output_path.as_os_str() == "-" {
+        std::io::stdout()
+            .write_all(&exported)
+            .context("Failed to write to stdout")?;
+        return Ok(());
+    }
+    std::fs::write(&output_path, &exported).context("Failed to write output file")?;
+
+    println!(
+        "Successfully exported {} → {}",
+        input_path.display(),
+        output_path.display()
+    );
+
+    Ok(())
+}
+
+/// Reads the file in `dir` named like the last path segment of `url`
+/// (ignoring any query string), if it exists.
+fn read_image(dir: &Path, url: &str) -> Option<Vec<u8>> {
+    let path = url.split(['?', '#']).next()?;
+    let name = path.rsplit('/').next().filter(|name| !name.is_empty())?;
+    std::fs::read(dir.join(name)).ok()
+}
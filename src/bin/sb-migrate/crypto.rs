@@ -1,24 +1,12 @@
-//! AES-256-GCM decryption with PBKDF2 key derivation for storyboard data.
+//! Decryption of base64-encoded storyboard payloads.
 //!
-//! Matches TypeScript implementation in storyboardCrypto.ts
+//! The AES-256-GCM + PBKDF2 implementation lives in `heyocollab::crypto`;
+//! this module only handles the base64 transport encoding used by the API.
 
-use aes_gcm::aead::{Aead, KeyInit};
-use aes_gcm::{Aes256Gcm, Key, Nonce};
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
-use pbkdf2::pbkdf2_hmac;
-use sha2::Sha256;
+use heyocollab::crypto::{self, IV_LENGTH};
 
-const HARDCODED_SALT: &str = "dheyo-storyboard-salt-v1";
-const PBKDF2_ITERATIONS: u32 = 100_000;
-const IV_LENGTH: usize = 12;
-const KEY_LENGTH: usize = 32; // 256 bits
-
-/// Parameters needed for key derivation
-#[derive(Debug, Clone)]
-pub struct KeyParams {
-    pub email: String,
-    pub created_at: i64,
-}
+pub use heyocollab::crypto::KeyParams;
 
 /// Crypto errors
 #[derive(Debug, thiserror::Error)]
@@ -33,33 +21,6 @@ pub enum CryptoError {
     InvalidData(String),
 }
 
-impl From<aes_gcm::Error> for CryptoError {
-    fn from(e: aes_gcm::Error) -> Self {
-        CryptoError::Decryption(e.to_string())
-    }
-}
-
-/// Derive encryption key using PBKDF2
-///
-/// Key material format: "{email}:{salt}:{createdAt}"
-fn derive_key(params: &KeyParams) -> [u8; KEY_LENGTH] {
-    let key_material = format!(
-        "{}:{}:{}",
-        params.email, HARDCODED_SALT, params.created_at
-    );
-    let salt = HARDCODED_SALT.as_bytes();
-
-    let mut derived_key = [0u8; KEY_LENGTH];
-    pbkdf2_hmac::<Sha256>(
-        key_material.as_bytes(),
-        salt,
-        PBKDF2_ITERATIONS,
-        &mut derived_key,
-    );
-
-    derived_key
-}
-
 /// Decrypt storyboard data from encrypted format
 ///
 /// Input format: base64([12-byte IV][ciphertext with auth tag])
@@ -74,17 +35,8 @@ pub fn decrypt_data(encrypted_base64: &str, params: &KeyParams) -> Result<String
         )));
     }
 
-    // Extract IV and ciphertext
-    let iv = &combined[..IV_LENGTH];
-    let ciphertext = &combined[IV_LENGTH..];
-
-    // Derive key
-    let key = derive_key(params);
-    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
-    let nonce = Nonce::from_slice(iv);
-
-    // Decrypt
-    let plaintext = cipher.decrypt(nonce, ciphertext)?;
+    let plaintext =
+        crypto::decrypt(&combined, params).map_err(|e| CryptoError::Decryption(e.to_string()))?;
 
     String::from_utf8(plaintext).map_err(CryptoError::from)
 }
@@ -94,30 +46,11 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_key_derivation_deterministic() {
-        let params = KeyParams {
-            email: "test@example.com".to_string(),
-            created_at: 1700000000000,
-        };
-        let key1 = derive_key(&params);
-        let key2 = derive_key(&params);
-        assert_eq!(key1, key2);
-        assert_eq!(key1.len(), 32);
-    }
-
-    #[test]
-    fn test_key_derivation_different_emails() {
-        let params1 = KeyParams {
-            email: "test1@example.com".to_string(),
-            created_at: 1700000000000,
-        };
-        let params2 = KeyParams {
-            email: "test2@example.com".to_string(),
-            created_at: 1700000000000,
-        };
-        let key1 = derive_key(&params1);
-        let key2 = derive_key(&params2);
-        assert_ne!(key1, key2);
+    fn test_decrypt_roundtrip() {
+        let params = KeyParams::new("test@example.com", 1700000000000);
+        let encrypted = crypto::encrypt(b"{\"scenes\":{}}", &params).unwrap();
+        let decrypted = decrypt_data(&BASE64.encode(&encrypted), &params).unwrap();
+        assert_eq!(decrypted, "{\"scenes\":{}}");
     }
 
     #[test]
@@ -6,7 +6,6 @@
 //!   sb-migrate --base-url https://api.heyo.com --token "..." [OPTIONS]
 
 mod client;
-mod compression;
 mod crypto;
 mod migration;
 
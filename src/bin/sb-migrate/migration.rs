@@ -1,8 +1,9 @@
 //! Migration orchestration
 
 use crate::client::{ClientError, HeyoClient};
-use crate::compression::maybe_decompress;
 use crate::crypto::{decrypt_data, CryptoError, KeyParams};
+use heyocollab::compression::maybe_decompress;
+use heyocollab::naming;
 use heyocollab::storyboard::{StoryboardManager, StoryboardRoot};
 use serde::Deserialize;
 use serde_json::Value;
@@ -140,7 +141,7 @@ pub async fn migrate_storyboard(
     result.input_size = raw_data.len();
 
     // 3. Decompress if gzipped
-    let decompressed = match maybe_decompress(raw_data) {
+    let decompressed = match maybe_decompress(&raw_data) {
         Ok(data) => data,
         Err(e) => {
             result.error = Some(format!("Decompression failed: {}", e));
@@ -239,7 +240,7 @@ pub async fn migrate_storyboard(
 
     // 11. Save locally if output_dir specified
     if let Some(dir) = output_dir {
-        let filename = format!("{}.automerge", storyboard_id);
+        let filename = naming::file_name(storyboard_id, "automerge");
         let path = dir.join(&filename);
         if let Err(e) = std::fs::write(&path, &automerge_binary) {
             result.error = Some(format!("Failed to write local file: {}", e));
@@ -249,12 +250,7 @@ pub async fn migrate_storyboard(
 
     // 12. Upload if not skip_upload
     if !skip_upload {
-        let timestamp = chrono_lite_timestamp();
-        let filename = format!(
-            "{}_{}.automerge",
-            sanitize_title(&bin_file.title),
-            timestamp
-        );
+        let filename = naming::timestamped_file_name(&bin_file.title, "automerge");
         if let Err(e) = client
             .upload_sb_file(storyboard_id, automerge_binary, &filename)
             .await
@@ -294,21 +290,3 @@ fn reconstruct_storyboard_json(
 
     serde_json::to_string(&full)
 }
-
-/// Sanitize title for filename
-fn sanitize_title(title: &str) -> String {
-    title
-        .chars()
-        .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
-        .collect::<String>()
-        .to_lowercase()
-}
-
-/// Generate a simple timestamp string
-fn chrono_lite_timestamp() -> String {
-    use std::time::{SystemTime, UNIX_EPOCH};
-    let duration = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default();
-    format!("{}", duration.as_millis())
-}
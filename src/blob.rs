@@ -0,0 +1,274 @@
+//! External storage for large inline images.
+//!
+//! An uploaded asset whose `image` is a base64 data URL keeps the whole
+//! payload in the CRDT, and every peer downloads it forever.
+//! `StoryboardManager::externalize_assets()` moves such payloads into a
+//! `BlobStore` and puts a content-hash reference in their place:
+//!
+//! ```text
+//! data:image/png;base64,iVBORw0KG...   ->   blob:sha256:9f86d081884c7d65...
+//! ```
+//!
+//! `resolve_blob()` turns a reference back into bytes. Blobs are keyed by
+//! their SHA-256, so storing the same image twice keeps one copy and a
+//! corrupted blob is detected on read.
+//!
+//! `StoryboardManager::dedup_images()` does the same inside the document:
+//! each distinct payload is kept once, as a data URL in
+//! `StoryboardRoot::blobs`, under the same reference format. With this
+//! feature, new uploaded assets and history entries are deduplicated as
+//! they are added, and `resolve_image()` turns any reference back into a
+//! data URL.
+//!
+//! `FileBlobStore` keeps one file per blob under a two-character fan-out
+//! directory (`<root>/9f/9f86d081...`); `MemoryBlobStore` is for tests and
+//! short-lived tools.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::{CollabError, CollabResult};
+
+/// Result of `StoryboardManager::externalize_assets()`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ExternalizeReport {
+    /// IDs of the uploaded assets moved out, sorted
+    pub assets: Vec<String>,
+    /// Other image fields that held the same data URLs and now hold the references
+    pub fields: usize,
+    /// Data-URL characters removed from the document
+    pub bytes: usize,
+}
+
+/// Result of `StoryboardManager::dedup_images()`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DedupReport {
+    /// Image fields (uploaded assets included) switched to blob references
+    pub fields: usize,
+    /// Payloads added to the document's blob map
+    pub blobs: usize,
+    /// Size of the replaced data URLs
+    pub bytes_before: usize,
+    /// Size of the new blobs plus the references that replaced the data URLs
+    pub bytes_after: usize,
+}
+
+impl DedupReport {
+    /// Returns the number of bytes saved.
+    pub fn saved_bytes(&self) -> usize {
+        self.bytes_before.saturating_sub(self.bytes_after)
+    }
+}
+
+/// Prefix of blob references stored in the document.
+pub const BLOB_REF_PREFIX: &str = "blob:sha256:";
+
+/// Content-addressed storage for image payloads.
+pub trait BlobStore {
+    /// Stores `bytes` under `hash` (lowercase hex SHA-256 of `bytes`).
+    /// Storing a hash that already exists is a no-op.
+    fn put(&mut self, hash: &str, bytes: &[u8]) -> CollabResult<()>;
+
+    /// Returns the bytes stored under `hash`, or None if unknown.
+    fn get(&self, hash: &str) -> CollabResult<Option<Vec<u8>>>;
+
+    /// Returns true if `hash` is stored.
+    fn contains(&self, hash: &str) -> CollabResult<bool> {
+        Ok(self.get(hash)?.is_some())
+    }
+}
+
+/// Returns the lowercase hex SHA-256 of `bytes`.
+pub fn content_hash(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Returns the document reference for a blob hash.
+pub fn blob_ref(hash: &str) -> String {
+    format!("{}{}", BLOB_REF_PREFIX, hash)
+}
+
+/// Returns the hash in a blob reference, or None if `value` is not one.
+pub fn parse_blob_ref(value: &str) -> Option<&str> {
+    let hash = value.strip_prefix(BLOB_REF_PREFIX)?;
+    let valid = hash.len() == 64 && hash.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'));
+    valid.then_some(hash)
+}
+
+/// Loads the blob behind `reference` from `store`. Returns None if the
+/// store does not have it, and an error if `reference` is malformed or the
+/// stored bytes do not match their hash.
+pub fn resolve_blob(store: &dyn BlobStore, reference: &str) -> CollabResult<Option<Vec<u8>>> {
+    let hash = parse_blob_ref(reference).ok_or_else(|| {
+        CollabError::schema_violation(format!("'{}' is not a blob reference", reference))
+    })?;
+    let Some(bytes) = store.get(hash)? else {
+        return Ok(None);
+    };
+    if content_hash(&bytes) != hash {
+        return Err(CollabError::storage(format!("blob {} is corrupted", hash)));
+    }
+    Ok(Some(bytes))
+}
+
+/// Splits a base64 data URL into its MIME type and decoded payload.
+/// Returns None for URLs and non-base64 data URLs.
+pub(crate) fn decode_data_url(url: &str) -> Option<(&str, Vec<u8>)> {
+    let (header, payload) = url.strip_prefix("data:")?.split_once(',')?;
+    let mime = header.strip_suffix(";base64")?;
+    let bytes = STANDARD.decode(payload.trim()).ok()?;
+    Some((mime, bytes))
+}
+
+// =============================================================================
+// MEMORY BACKEND
+// =============================================================================
+
+/// Keeps blobs in a map.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryBlobStore {
+    blobs: HashMap<String, Vec<u8>>,
+}
+
+impl MemoryBlobStore {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of stored blobs.
+    pub fn len(&self) -> usize {
+        self.blobs.len()
+    }
+
+    /// Returns true if nothing is stored.
+    pub fn is_empty(&self) -> bool {
+        self.blobs.is_empty()
+    }
+}
+
+impl BlobStore for MemoryBlobStore {
+    fn put(&mut self, hash: &str, bytes: &[u8]) -> CollabResult<()> {
+        self.blobs.entry(hash.to_string()).or_insert_with(|| bytes.to_vec());
+        Ok(())
+    }
+
+    fn get(&self, hash: &str) -> CollabResult<Option<Vec<u8>>> {
+        Ok(self.blobs.get(hash).cloned())
+    }
+}
+
+// =============================================================================
+// FILESYSTEM BACKEND
+// =============================================================================
+
+/// Stores each blob as a file named by its hash.
+#[derive(Debug, Clone)]
+pub struct FileBlobStore {
+    root: PathBuf,
+}
+
+impl FileBlobStore {
+    /// Opens (and creates if needed) a store rooted at `root`.
+    pub fn new(root: impl Into<PathBuf>) -> CollabResult<Self> {
+        let root = root.into();
+        fs::create_dir_all(&root).map_err(|e| io_error(&root, e))?;
+        Ok(Self { root })
+    }
+
+    /// Returns the store's directory.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn blob_path(&self, hash: &str) -> CollabResult<PathBuf> {
+        // Only real hashes reach the filesystem, so a crafted reference
+        // cannot name a path outside the store
+        parse_blob_ref(&blob_ref(hash))
+            .ok_or_else(|| CollabError::storage(format!("invalid blob hash '{}'", hash)))?;
+        Ok(self.root.join(&hash[..2]).join(hash))
+    }
+}
+
+impl BlobStore for FileBlobStore {
+    fn put(&mut self, hash: &str, bytes: &[u8]) -> CollabResult<()> {
+        let path = self.blob_path(hash)?;
+        if path.exists() {
+            return Ok(());
+        }
+        let dir = path.parent().expect("blob paths have a fan-out directory");
+        fs::create_dir_all(dir).map_err(|e| io_error(dir, e))?;
+        // Write-then-rename so readers never see a partial blob
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, bytes).map_err(|e| io_error(&tmp, e))?;
+        fs::rename(&tmp, &path).map_err(|e| io_error(&path, e))
+    }
+
+    fn get(&self, hash: &str) -> CollabResult<Option<Vec<u8>>> {
+        let path = self.blob_path(hash)?;
+        match fs::read(&path) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(io_error(&path, e)),
+        }
+    }
+
+    fn contains(&self, hash: &str) -> CollabResult<bool> {
+        Ok(self.blob_path(hash)?.exists())
+    }
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> CollabError {
+    CollabError::storage(format!("{}: {}", path.display(), e))
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_url_and_refs() {
+        let (mime, bytes) = decode_data_url("data:image/png;base64,aGVsbG8=").unwrap();
+        assert_eq!(mime, "image/png");
+        assert_eq!(bytes, b"hello");
+        assert!(decode_data_url("https://cdn/a.png").is_none());
+        assert!(decode_data_url("data:text/plain,hello").is_none());
+
+        let hash = content_hash(b"hello");
+        assert_eq!(hash, "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824");
+        assert_eq!(parse_blob_ref(&blob_ref(&hash)), Some(hash.as_str()));
+        assert!(parse_blob_ref("blob:sha256:../../etc/passwd").is_none());
+    }
+
+    #[test]
+    fn test_file_blob_store_round_trip() {
+        let dir = std::env::temp_dir().join(format!("heyocollab-blobs-{}", uuid::Uuid::new_v4()));
+        let mut store = FileBlobStore::new(&dir).unwrap();
+        let hash = content_hash(b"hello");
+        store.put(&hash, b"hello").unwrap();
+        store.put(&hash, b"hello").unwrap();
+
+        assert!(store.contains(&hash).unwrap());
+        assert_eq!(resolve_blob(&store, &blob_ref(&hash)).unwrap().unwrap(), b"hello");
+
+        // Tampered blobs are rejected
+        fs::write(dir.join(&hash[..2]).join(&hash), b"jello").unwrap();
+        assert!(resolve_blob(&store, &blob_ref(&hash)).is_err());
+
+        let missing = content_hash(b"missing");
+        assert!(resolve_blob(&store, &blob_ref(&missing)).unwrap().is_none());
+        assert!(store.get("../escape").is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,139 @@
+//! Runtime report of the features compiled into this build.
+//!
+//! Host apps ship different builds for different tiers (e.g. sequence-only vs.
+//! sequence + storyboard). `capabilities()` lets the UI adapt without guessing
+//! from bundle names.
+
+use serde::Serialize;
+
+/// Features available in the current build.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Crate version (e.g. "0.2.0").
+    pub version: &'static str,
+    /// Storyboard documents (`storyboard` feature).
+    pub storyboard: bool,
+    /// WASM bindings (`wasm` feature).
+    pub wasm: bool,
+    /// Encrypted save/load (`crypto` feature).
+    pub crypto: bool,
+    /// Gzip-compressed save/load (`compression` feature).
+    pub compression: bool,
+    /// Full-text search (`search` feature).
+    pub search: bool,
+    /// OTIO/EDL timeline export (`export` feature).
+    pub export: bool,
+    /// Fountain screenplay import (`script-import` feature).
+    pub script_import: bool,
+    /// PDF contact sheet export (`pdf-export` feature).
+    pub pdf_export: bool,
+    /// External storage for inline images (`blob-store` feature).
+    pub blob_store: bool,
+    /// Native WebSocket sync client (`sync-client` feature).
+    pub sync_client: bool,
+    /// Incremental change sync (always available).
+    pub sync: bool,
+}
+
+impl Capabilities {
+    /// Returns the names of all enabled features.
+    pub fn enabled(&self) -> Vec<&'static str> {
+        let mut names = Vec::new();
+        if self.sync {
+            names.push("sync");
+        }
+        if self.storyboard {
+            names.push("storyboard");
+        }
+        if self.wasm {
+            names.push("wasm");
+        }
+        if self.crypto {
+            names.push("crypto");
+        }
+        if self.compression {
+            names.push("compression");
+        }
+        if self.search {
+            names.push("search");
+        }
+        if self.export {
+            names.push("export");
+        }
+        if self.script_import {
+            names.push("script-import");
+        }
+        if self.pdf_export {
+            names.push("pdf-export");
+        }
+        if self.blob_store {
+            names.push("blob-store");
+        }
+        if self.sync_client {
+            names.push("sync-client");
+        }
+        names
+    }
+
+    /// Returns true if the named feature is enabled.
+    pub fn has(&self, feature: &str) -> bool {
+        self.enabled().contains(&feature)
+    }
+}
+
+/// Reports which features were compiled into this build.
+pub fn capabilities() -> Capabilities {
+    Capabilities {
+        version: env!("CARGO_PKG_VERSION"),
+        storyboard: cfg!(feature = "storyboard"),
+        wasm: cfg!(feature = "wasm"),
+        crypto: cfg!(feature = "crypto"),
+        compression: cfg!(feature = "compression"),
+        search: cfg!(feature = "search"),
+        export: cfg!(feature = "export"),
+        script_import: cfg!(feature = "script-import"),
+        pdf_export: cfg!(feature = "pdf-export"),
+        blob_store: cfg!(feature = "blob-store"),
+        sync_client: cfg!(feature = "sync-client"),
+        sync: true,
+    }
+}
+
+/// WASM binding for `capabilities()`.
+///
+/// # Example (JavaScript)
+/// ```js
+/// const caps = capabilities();
+/// if (!caps.storyboard) hideStoryboardTab();
+/// ```
+#[cfg(feature = "wasm")]
+#[wasm_bindgen::prelude::wasm_bindgen(js_name = capabilities)]
+pub fn js_capabilities() -> Result<wasm_bindgen::JsValue, wasm_bindgen::JsValue> {
+    Ok(serde_wasm_bindgen::to_value(&capabilities())?)
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capabilities_match_build() {
+        let caps = capabilities();
+        assert_eq!(caps.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(caps.storyboard, cfg!(feature = "storyboard"));
+        assert!(caps.has("sync"));
+        assert_eq!(caps.has("storyboard"), cfg!(feature = "storyboard"));
+        assert_eq!(caps.has("crypto"), cfg!(feature = "crypto"));
+        assert_eq!(caps.has("compression"), cfg!(feature = "compression"));
+        assert_eq!(caps.has("search"), cfg!(feature = "search"));
+        assert_eq!(caps.has("export"), cfg!(feature = "export"));
+        assert_eq!(caps.has("script-import"), cfg!(feature = "script-import"));
+        assert_eq!(caps.has("pdf-export"), cfg!(feature = "pdf-export"));
+        assert_eq!(caps.has("blob-store"), cfg!(feature = "blob-store"));
+        assert!(!caps.has("unknown"));
+    }
+}
@@ -0,0 +1,147 @@
+//! Change metadata shared by the sequence and storyboard managers.
+//!
+//! Every Automerge change carries an actor, a sequence number, an optional
+//! commit message and a timestamp. This module exposes them as plain structs
+//! so host apps can build activity feeds without touching Automerge types.
+//!
+//! `field_authors()` attributes each field of an object to the change that
+//! last wrote it, for "edited by" hints. Actor IDs are opaque; documents keep
+//! an `actor_names` registry so UIs can show display names instead.
+
+use std::collections::{BTreeMap, HashMap};
+
+use automerge::{AutoCommit, ObjId, ObjType, ReadDoc, Value};
+use serde::{Deserialize, Serialize};
+
+use crate::error::CollabResult;
+
+/// Metadata for a single change in the document history.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ChangeInfo {
+    /// Hex-encoded change hash.
+    pub hash: String,
+    /// Hex-encoded actor ID that authored the change.
+    pub actor: String,
+    /// Per-actor sequence number (starts at 1).
+    pub seq: u64,
+    /// Commit message, if one was recorded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Timestamp recorded with the change (milliseconds since epoch, 0 if unset).
+    pub timestamp: i64,
+    /// Number of operations in the change.
+    pub num_ops: usize,
+}
+
+/// Collects metadata for every change in the document, in causal order.
+///
+/// Closes any open transaction first so pending operations are included.
+pub(crate) fn collect_change_info(doc: &mut AutoCommit) -> Vec<ChangeInfo> {
+    doc.get_changes(&[])
+        .iter()
+        .map(|change| ChangeInfo {
+            hash: change.hash().to_string(),
+            actor: change.actor_id().to_hex_string(),
+            seq: change.seq(),
+            message: change.message().cloned(),
+            timestamp: change.timestamp(),
+            num_ops: change.len(),
+        })
+        .collect()
+}
+
+/// The last writer of a field.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FieldAuthor {
+    /// Hex-encoded actor ID.
+    pub actor: String,
+    /// Display name from the document's actor registry, if registered.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Timestamp of the change that wrote the field (milliseconds since epoch, 0 if unset).
+    pub timestamp: i64,
+}
+
+/// Returns the last writer of every field of the map `obj`, keyed by field
+/// name. Fields holding maps are reported per nested field with dotted keys
+/// (`settings.cfg`); a list is attributed to its most recently written
+/// element. Removals are not tracked.
+pub(crate) fn field_authors(
+    doc: &mut AutoCommit,
+    obj: &ObjId,
+    names: &HashMap<String, String>,
+) -> CollabResult<BTreeMap<String, FieldAuthor>> {
+    // Op counter range and timestamp of each change, per actor
+    let mut ranges: HashMap<String, Vec<(u64, u64, i64)>> = HashMap::new();
+    for change in doc.get_changes(&[]) {
+        ranges
+            .entry(change.actor_id().to_hex_string())
+            .or_default()
+            .push((change.start_op().get(), change.max_op(), change.timestamp()));
+    }
+
+    let mut ops = BTreeMap::new();
+    collect_map_ops(doc, obj, "", &mut ops)?;
+    Ok(ops
+        .into_iter()
+        .map(|(field, (counter, actor))| {
+            let timestamp = ranges
+                .get(&actor)
+                .and_then(|changes| {
+                    changes
+                        .iter()
+                        .find(|(start, max, _)| (*start..=*max).contains(&counter))
+                })
+                .map_or(0, |(_, _, timestamp)| *timestamp);
+            let author = FieldAuthor {
+                name: names.get(&actor).cloned(),
+                actor,
+                timestamp,
+            };
+            (field, author)
+        })
+        .collect())
+}
+
+/// Records the winning op (counter, actor) of each field under `obj`.
+fn collect_map_ops(
+    doc: &AutoCommit,
+    obj: &ObjId,
+    prefix: &str,
+    ops: &mut BTreeMap<String, (u64, String)>,
+) -> CollabResult<()> {
+    for key in doc.keys(obj).collect::<Vec<_>>() {
+        let Some((value, id)) = doc.get(obj, key.as_str())? else {
+            continue;
+        };
+        let field = format!("{}{}", prefix, key);
+        match value {
+            Value::Object(ObjType::Map) => collect_map_ops(doc, &id, &format!("{}.", field), ops)?,
+            Value::Object(ObjType::List) => {
+                let mut latest = op_of(&id);
+                for index in 0..doc.length(&id) {
+                    if let Some((_, element)) = doc.get(&id, index)? {
+                        latest = latest.max(op_of(&element));
+                    }
+                }
+                if let Some(op) = latest {
+                    ops.insert(field, op);
+                }
+            }
+            _ => {
+                if let Some(op) = op_of(&id) {
+                    ops.insert(field, op);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the (counter, hex actor) of an op ID; None for the root.
+fn op_of(id: &ObjId) -> Option<(u64, String)> {
+    match id {
+        ObjId::Root => None,
+        ObjId::Id(counter, actor, _) => Some((*counter, actor.to_hex_string())),
+    }
+}
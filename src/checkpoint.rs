@@ -0,0 +1,114 @@
+//! Upload checkpoints.
+//!
+//! A `SyncCheckpoint` records the heads a client last uploaded. It is plain
+//! serializable data, so a browser can keep it in localStorage next to the
+//! cached document and, after a refresh, upload only what the server has not
+//! acknowledged yet:
+//!
+//! ```ignore
+//! let next = manager.mark_synced();
+//! let changes = manager.changes_since_checkpoint(&checkpoint);
+//! upload(&changes)?;
+//! checkpoint = next; // only once the upload is acknowledged
+//! ```
+//!
+//! Taking the new checkpoint before the upload keeps edits made while the
+//! request is in flight in the next batch.
+
+use std::str::FromStr;
+
+use automerge::{AutoCommit, ChangeHash};
+use serde::{Deserialize, Serialize};
+
+/// Heads of the last acknowledged upload. The default (no heads) means
+/// nothing has been uploaded yet.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SyncCheckpoint {
+    /// Hex-encoded change hashes, sorted.
+    pub heads: Vec<String>,
+}
+
+impl SyncCheckpoint {
+    /// Creates a checkpoint at `heads`.
+    pub fn from_heads(heads: &[ChangeHash]) -> Self {
+        let mut heads: Vec<String> = heads.iter().map(ChangeHash::to_string).collect();
+        heads.sort();
+        Self { heads }
+    }
+
+    /// Returns true if nothing has been uploaded yet.
+    pub fn is_empty(&self) -> bool {
+        self.heads.is_empty()
+    }
+
+    /// Parses the recorded heads, skipping malformed entries.
+    pub fn change_hashes(&self) -> Vec<ChangeHash> {
+        self.heads
+            .iter()
+            .filter_map(|head| ChangeHash::from_str(head).ok())
+            .collect()
+    }
+}
+
+/// Returns the checkpoint heads that `doc` knows. Unknown heads (e.g. from a
+/// compacted or unrelated document) are dropped, so the changes since the
+/// result are a superset of what is really missing, never a subset.
+pub(crate) fn known_heads(doc: &mut AutoCommit, checkpoint: &SyncCheckpoint) -> Vec<ChangeHash> {
+    checkpoint
+        .change_hashes()
+        .into_iter()
+        .filter(|hash| doc.get_change_by_hash(hash).is_some())
+        .collect()
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequence::{GenerationNode, SequenceManager};
+
+    #[test]
+    fn test_checkpoint_resumes_upload() {
+        let mut client = SequenceManager::new();
+        let mut server = SequenceManager::from_bytes(&client.save()).unwrap();
+        let mut checkpoint = SyncCheckpoint::default();
+
+        client.create_and_append("a", GenerationNode::new("a", "t2i")).unwrap();
+        let next = client.mark_synced();
+        let changes = client.changes_since_checkpoint(&checkpoint);
+        server.load_incremental(&changes).unwrap();
+        checkpoint = next;
+
+        // Survives a round trip through JSON (e.g. localStorage)
+        let json = serde_json::to_string(&checkpoint).unwrap();
+        let checkpoint: SyncCheckpoint = serde_json::from_str(&json).unwrap();
+        assert!(client.changes_since_checkpoint(&checkpoint).is_empty());
+
+        // After a "refresh" only the new change is uploaded
+        let mut client = SequenceManager::from_bytes(&client.save()).unwrap();
+        client.create_and_append("b", GenerationNode::new("b", "t2i")).unwrap();
+        let changes = client.changes_since_checkpoint(&checkpoint);
+        assert!(!changes.is_empty());
+        assert!(changes.len() < client.save_since(&[]).len());
+        server.load_incremental(&changes).unwrap();
+        assert_eq!(server.get_state().unwrap(), client.get_state().unwrap());
+    }
+
+    #[test]
+    fn test_unknown_heads_fall_back_to_full_upload() {
+        let mut other = SequenceManager::new();
+        other.create_and_append("x", GenerationNode::new("x", "t2i")).unwrap();
+        let mut checkpoint = other.mark_synced();
+        checkpoint.heads.push("not-a-hash".to_string());
+
+        let mut manager = SequenceManager::new();
+        manager.create_and_append("a", GenerationNode::new("a", "t2i")).unwrap();
+        assert_eq!(
+            manager.changes_since_checkpoint(&checkpoint),
+            manager.save_since(&[])
+        );
+    }
+}
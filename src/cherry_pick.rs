@@ -0,0 +1,115 @@
+//! Selective change transfer between documents.
+//!
+//! `cherry_pick()` applies chosen changes from another copy of a document
+//! (e.g. a fork used to try out a scene rewrite) without merging the rest.
+//! Automerge changes cannot apply without their dependencies, so every
+//! ancestor of a picked change that this document lacks is applied too; the
+//! report lists them all.
+
+use std::collections::HashSet;
+
+use automerge::{AutoCommit, Change, ChangeHash};
+use serde::{Deserialize, Serialize};
+
+use crate::error::CollabResult;
+
+/// Outcome of a `cherry_pick()`. All hashes are hex-encoded.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct CherryPickReport {
+    /// Changes applied, including pulled-in dependencies, in causal order.
+    pub applied: Vec<String>,
+    /// Requested changes this document already had.
+    pub already_present: Vec<String>,
+    /// Requested changes the other document does not have.
+    pub not_found: Vec<String>,
+}
+
+/// Applies `hashes` and their missing dependencies from `other` to `doc`.
+pub(crate) fn cherry_pick(
+    doc: &mut AutoCommit,
+    other: &mut AutoCommit,
+    hashes: &[ChangeHash],
+) -> CollabResult<CherryPickReport> {
+    let mut report = CherryPickReport::default();
+    let mut seen = HashSet::new();
+    let mut picked = Vec::new();
+    for hash in hashes {
+        if doc.get_change_by_hash(hash).is_some() {
+            report.already_present.push(hash.to_string());
+        } else if other.get_change_by_hash(hash).is_none() {
+            report.not_found.push(hash.to_string());
+        } else {
+            collect(doc, other, *hash, &mut seen, &mut picked);
+        }
+    }
+    report.applied = picked.iter().map(|change| change.hash().to_string()).collect();
+    doc.apply_changes(picked)?;
+    Ok(report)
+}
+
+/// Pushes `hash` and its ancestors missing from `doc` onto `picked`,
+/// dependencies first.
+fn collect(
+    doc: &mut AutoCommit,
+    other: &mut AutoCommit,
+    hash: ChangeHash,
+    seen: &mut HashSet<ChangeHash>,
+    picked: &mut Vec<Change>,
+) {
+    // Iterative post-order walk; `true` marks a change whose dependencies
+    // are already on the stack above it
+    let mut stack = vec![(hash, false)];
+    while let Some((hash, expanded)) = stack.pop() {
+        if expanded {
+            if let Some(change) = other.get_change_by_hash(&hash) {
+                picked.push(change.clone());
+            }
+            continue;
+        }
+        if !seen.insert(hash) || doc.get_change_by_hash(&hash).is_some() {
+            continue;
+        }
+        let Some(change) = other.get_change_by_hash(&hash) else {
+            continue;
+        };
+        let deps = change.deps().to_vec();
+        stack.push((hash, true));
+        stack.extend(deps.into_iter().map(|dep| (dep, false)));
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use automerge::{transaction::Transactable, ReadDoc, ROOT};
+
+    #[test]
+    fn test_cherry_pick_applies_only_selected_branch() {
+        let mut base = AutoCommit::new();
+        base.put(&ROOT, "title", "Draft").unwrap();
+        base.commit();
+
+        let mut fix = base.fork();
+        fix.put(&ROOT, "scene_3", "fixed").unwrap();
+        fix.commit();
+        fix.put(&ROOT, "scene_3_notes", "checked").unwrap();
+        let fix_head = fix.get_heads()[0];
+        let mut other = base.fork();
+        other.put(&ROOT, "scene_5", "rewrite").unwrap();
+        other.merge(&mut fix).unwrap();
+
+        let base_head = base.get_heads()[0];
+        let unknown = ChangeHash([7; 32]);
+        let report = cherry_pick(&mut base, &mut other, &[fix_head, base_head, unknown]).unwrap();
+        assert_eq!(report.applied.len(), 2);
+        assert_eq!(report.applied[1], fix_head.to_string());
+        assert_eq!(report.already_present, vec![base_head.to_string()]);
+        assert_eq!(report.not_found, vec![unknown.to_string()]);
+        assert!(base.get(&ROOT, "scene_3_notes").unwrap().is_some());
+        assert!(base.get(&ROOT, "scene_5").unwrap().is_none());
+    }
+}
@@ -0,0 +1,247 @@
+//! Byte-level chunking of sync messages.
+//!
+//! `crate::sync` frames split a catch-up at change boundaries, so a single
+//! huge change (e.g. a pasted script) still produces an oversized frame.
+//! Chunking instead cuts any message into pieces of at most `max_bytes`,
+//! each carrying a header:
+//!
+//! ```text
+//! CHK1 | message id (u32) | index (u32) | count (u32) | crc32 (u32) | payload
+//! ```
+//!
+//! All integers are big-endian; the CRC-32 covers the whole message and is
+//! checked once every chunk has arrived. A `ChunkAssembler` collects chunks
+//! in any order, ignores duplicates and returns the message when complete.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::error::{CollabError, CollabResult};
+
+/// Prefix identifying a sync chunk.
+pub const CHUNK_MAGIC: &[u8] = b"CHK1";
+
+/// Size of the header in front of every chunk's payload.
+pub const CHUNK_HEADER_BYTES: usize = CHUNK_MAGIC.len() + 16;
+
+/// Smallest accepted chunk size; smaller values are raised to this.
+pub const MIN_CHUNK_BYTES: usize = 256;
+
+/// Messages an assembler keeps partially received before dropping the
+/// oldest one.
+pub const MAX_PENDING_MESSAGES: usize = 16;
+
+/// Returns true if `bytes` looks like a sync chunk.
+pub fn is_chunk(bytes: &[u8]) -> bool {
+    bytes.starts_with(CHUNK_MAGIC)
+}
+
+/// Splits `message` into chunks of at most `max_bytes` (header included).
+/// An empty message yields no chunks.
+pub fn split_message(message: &[u8], max_bytes: usize) -> Vec<Vec<u8>> {
+    if message.is_empty() {
+        return Vec::new();
+    }
+    let payload_bytes = max_bytes.max(MIN_CHUNK_BYTES) - CHUNK_HEADER_BYTES;
+    let id = uuid::Uuid::new_v4().as_u128() as u32;
+    let checksum = crc32(message);
+    let count = message.len().div_ceil(payload_bytes) as u32;
+
+    message
+        .chunks(payload_bytes)
+        .enumerate()
+        .map(|(index, payload)| {
+            let mut chunk = Vec::with_capacity(CHUNK_HEADER_BYTES + payload.len());
+            chunk.extend_from_slice(CHUNK_MAGIC);
+            chunk.extend_from_slice(&id.to_be_bytes());
+            chunk.extend_from_slice(&(index as u32).to_be_bytes());
+            chunk.extend_from_slice(&count.to_be_bytes());
+            chunk.extend_from_slice(&checksum.to_be_bytes());
+            chunk.extend_from_slice(payload);
+            chunk
+        })
+        .collect()
+}
+
+/// A message whose chunks are still arriving.
+#[derive(Debug)]
+struct Partial {
+    checksum: u32,
+    parts: Vec<Option<Vec<u8>>>,
+    received: usize,
+}
+
+/// Reassembles chunked messages.
+#[derive(Debug, Default)]
+pub struct ChunkAssembler {
+    pending: HashMap<u32, Partial>,
+    /// Message ids in arrival order, for eviction.
+    order: VecDeque<u32>,
+}
+
+impl ChunkAssembler {
+    /// Creates an empty assembler.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of partially received messages.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Adds a chunk. Returns the complete message once its last chunk has
+    /// arrived, or None while chunks are missing.
+    ///
+    /// Fails on malformed headers, on chunks that disagree with earlier
+    /// chunks of the same message, and on checksum mismatches; the partial
+    /// message is dropped in the latter two cases.
+    pub fn push(&mut self, chunk: &[u8]) -> CollabResult<Option<Vec<u8>>> {
+        let header = chunk
+            .strip_prefix(CHUNK_MAGIC)
+            .filter(|rest| rest.len() >= CHUNK_HEADER_BYTES - CHUNK_MAGIC.len())
+            .ok_or_else(|| CollabError::serialization("not a sync chunk"))?;
+        let field = |i: usize| u32::from_be_bytes(header[i * 4..i * 4 + 4].try_into().unwrap());
+        let (id, index, count, checksum) = (field(0), field(1), field(2), field(3));
+        let payload = &header[16..];
+        if count == 0 || index >= count {
+            return Err(CollabError::serialization(format!(
+                "chunk index {} out of range for {} chunks",
+                index, count
+            )));
+        }
+
+        if !self.pending.contains_key(&id) {
+            if self.order.len() >= MAX_PENDING_MESSAGES {
+                if let Some(oldest) = self.order.pop_front() {
+                    self.pending.remove(&oldest);
+                }
+            }
+            self.order.push_back(id);
+            self.pending.insert(
+                id,
+                Partial {
+                    checksum,
+                    parts: vec![None; count as usize],
+                    received: 0,
+                },
+            );
+        }
+
+        let partial = self.pending.get_mut(&id).expect("partial just inserted");
+        if partial.checksum != checksum || partial.parts.len() != count as usize {
+            self.drop_message(id);
+            return Err(CollabError::serialization(format!(
+                "chunk header mismatch for message {:08x}",
+                id
+            )));
+        }
+        let slot = &mut partial.parts[index as usize];
+        if slot.is_none() {
+            *slot = Some(payload.to_vec());
+            partial.received += 1;
+        }
+        if partial.received < partial.parts.len() {
+            return Ok(None);
+        }
+
+        let partial = self.drop_message(id).expect("message is pending");
+        let message: Vec<u8> = partial.parts.into_iter().flatten().flatten().collect();
+        if crc32(&message) != partial.checksum {
+            return Err(CollabError::serialization(format!(
+                "checksum mismatch for message {:08x}",
+                id
+            )));
+        }
+        Ok(Some(message))
+    }
+
+    fn drop_message(&mut self, id: u32) -> Option<Partial> {
+        self.order.retain(|&pending| pending != id);
+        self.pending.remove(&id)
+    }
+}
+
+/// CRC-32 (IEEE) of `data`.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequence::{GenerationNode, SequenceManager};
+
+    #[test]
+    fn test_crc32_known_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_chunks_reassemble_out_of_order() {
+        let message: Vec<u8> = (0..5_000u32).map(|i| (i % 251) as u8).collect();
+        let chunks = split_message(&message, 1_000);
+        assert_eq!(chunks.len(), 6);
+        assert!(chunks.iter().all(|c| c.len() <= 1_000 && is_chunk(c)));
+
+        let mut assembler = ChunkAssembler::new();
+        for chunk in chunks.iter().rev().skip(1) {
+            assert_eq!(assembler.push(chunk).unwrap(), None);
+        }
+        // Duplicates are ignored
+        assert_eq!(assembler.push(&chunks[1]).unwrap(), None);
+        assert_eq!(assembler.push(&chunks[5]).unwrap(), Some(message));
+        assert_eq!(assembler.pending(), 0);
+    }
+
+    #[test]
+    fn test_corrupt_chunk_is_rejected() {
+        let message = vec![7u8; 2_000];
+        let mut chunks = split_message(&message, 512);
+        let last = chunks.len() - 1;
+        *chunks[last].last_mut().unwrap() ^= 0xFF;
+
+        let mut assembler = ChunkAssembler::new();
+        let mut result = Ok(None);
+        for chunk in &chunks {
+            result = assembler.push(chunk);
+        }
+        assert!(result.is_err());
+        assert_eq!(assembler.pending(), 0);
+        assert!(assembler.push(b"garbage").is_err());
+    }
+
+    #[test]
+    fn test_manager_sync_chunks() {
+        let mut source = SequenceManager::new();
+        let mut target = SequenceManager::from_bytes(&source.save()).unwrap();
+        let their_heads = target.get_heads();
+
+        // A single change far larger than the chunk size
+        let node = GenerationNode::new("big", "t2i").with_prompt("x".repeat(20_000));
+        source.create_and_append("big", node).unwrap();
+
+        let chunks = source.generate_sync_chunks(&their_heads, 4_096);
+        assert!(chunks.len() > 1);
+        let (last, rest) = chunks.split_last().unwrap();
+        for chunk in rest {
+            assert!(!target.apply_sync_chunk(chunk).unwrap());
+        }
+        assert!(target.apply_sync_chunk(last).unwrap());
+        assert_eq!(target.get_state().unwrap(), source.get_state().unwrap());
+    }
+}
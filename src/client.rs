@@ -0,0 +1,280 @@
+//! WebSocket sync client for native (tokio) peers.
+//!
+//! Headless render workers need live documents, not snapshots. `SyncClient`
+//! keeps a shared manager in sync with a relay (e.g. `heyocollab-server`) by
+//! running the Automerge sync protocol over a WebSocket:
+//!
+//! - every binary frame is one protocol message (see `SyncDocument`),
+//! - the endpoint is `<url>/<doc_id>`,
+//! - dropped connections are retried with exponential backoff and a fresh
+//!   `PeerState`,
+//! - local edits are picked up on every `poll_interval` tick, or immediately
+//!   after `notify_local_change()`.
+//!
+//! Progress is reported as `SyncEvent`s; `Converged` fires whenever both
+//! sides reach the same heads.
+//!
+//! ```ignore
+//! let manager = Arc::new(Mutex::new(StoryboardManager::new()));
+//! let mut client = SyncClient::connect("wss://sync.example.com/docs", "board-42", manager.clone());
+//! while let Some(event) = client.next_event().await {
+//!     if let SyncEvent::Converged { .. } = event {
+//!         render(&mut manager.lock().unwrap())?;
+//!     }
+//! }
+//! ```
+
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use automerge::ChangeHash;
+use futures_util::{SinkExt, StreamExt};
+use tokio::sync::{mpsc, Notify};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+use crate::sync::{PeerState, SyncDocument};
+
+/// Connection and polling settings for `SyncClient`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncClientConfig {
+    /// Delay before the first reconnect attempt.
+    pub initial_backoff: Duration,
+    /// Upper bound for the reconnect delay.
+    pub max_backoff: Duration,
+    /// How often local edits are checked for.
+    pub poll_interval: Duration,
+}
+
+impl Default for SyncClientConfig {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            poll_interval: Duration::from_millis(250),
+        }
+    }
+}
+
+impl SyncClientConfig {
+    /// Builder: Set the reconnect delay range.
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self
+    }
+
+    /// Builder: Set the local edit polling interval.
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Delay before reconnect attempt `attempt` (0-based): doubles each time,
+    /// capped at `max_backoff`.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff)
+    }
+}
+
+/// Progress reported by a `SyncClient`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncEvent {
+    /// The WebSocket connection was established.
+    Connected,
+    /// The connection failed or was closed.
+    Disconnected { reason: String },
+    /// A reconnect is scheduled after `delay`.
+    Reconnecting { attempt: u32, delay: Duration },
+    /// Both sides have the same heads.
+    Converged { heads: Vec<ChangeHash> },
+}
+
+/// A background task syncing one document over WebSocket.
+///
+/// Dropping the client stops the task.
+pub struct SyncClient {
+    events: mpsc::UnboundedReceiver<SyncEvent>,
+    notify: Arc<Notify>,
+    task: JoinHandle<()>,
+}
+
+impl SyncClient {
+    /// Starts syncing `manager` with `<url>/<doc_id>` using default settings.
+    ///
+    /// Must be called from within a tokio runtime.
+    pub fn connect<M>(url: &str, doc_id: &str, manager: Arc<Mutex<M>>) -> Self
+    where
+        M: SyncDocument + Send + 'static,
+    {
+        Self::connect_with_config(url, doc_id, manager, SyncClientConfig::default())
+    }
+
+    /// Like `connect()`, with custom settings.
+    pub fn connect_with_config<M>(
+        url: &str,
+        doc_id: &str,
+        manager: Arc<Mutex<M>>,
+        config: SyncClientConfig,
+    ) -> Self
+    where
+        M: SyncDocument + Send + 'static,
+    {
+        let endpoint = format!("{}/{}", url.trim_end_matches('/'), doc_id);
+        let (events_tx, events) = mpsc::unbounded_channel();
+        let notify = Arc::new(Notify::new());
+        let task = tokio::spawn(run(endpoint, manager, config, events_tx, notify.clone()));
+        Self {
+            events,
+            notify,
+            task,
+        }
+    }
+
+    /// Waits for the next event. Returns None once the task has stopped.
+    pub async fn next_event(&mut self) -> Option<SyncEvent> {
+        self.events.recv().await
+    }
+
+    /// Sends local edits now instead of at the next poll tick.
+    pub fn notify_local_change(&self) {
+        self.notify.notify_one();
+    }
+}
+
+impl Drop for SyncClient {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+// =============================================================================
+// CONNECTION LOOP
+// =============================================================================
+
+async fn run<M>(
+    endpoint: String,
+    manager: Arc<Mutex<M>>,
+    config: SyncClientConfig,
+    events: mpsc::UnboundedSender<SyncEvent>,
+    notify: Arc<Notify>,
+) where
+    M: SyncDocument + Send + 'static,
+{
+    let mut attempt = 0;
+    loop {
+        let reason = match connect_async(endpoint.as_str()).await {
+            Ok((socket, _)) => {
+                attempt = 0;
+                let _ = events.send(SyncEvent::Connected);
+                match session(socket, &manager, &config, &events, &notify).await {
+                    Ok(()) => "closed by peer".to_string(),
+                    Err(reason) => reason,
+                }
+            }
+            Err(e) => e.to_string(),
+        };
+        if events.send(SyncEvent::Disconnected { reason }).is_err() {
+            return; // Client dropped
+        }
+
+        let delay = config.backoff(attempt);
+        attempt = attempt.saturating_add(1);
+        let _ = events.send(SyncEvent::Reconnecting { attempt, delay });
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Runs the sync protocol on one connection until it closes.
+async fn session<M, S>(
+    socket: tokio_tungstenite::WebSocketStream<S>,
+    manager: &Mutex<M>,
+    config: &SyncClientConfig,
+    events: &mpsc::UnboundedSender<SyncEvent>,
+    notify: &Notify,
+) -> Result<(), String>
+where
+    M: SyncDocument,
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+{
+    let (mut sink, mut stream) = socket.split();
+    let mut peer = PeerState::new();
+    let mut ticker = tokio::time::interval(config.poll_interval);
+    let mut converged = false;
+
+    loop {
+        while let Some(message) = generate(manager, &mut peer) {
+            sink.send(Message::Binary(message))
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+
+        tokio::select! {
+            incoming = stream.next() => match incoming {
+                Some(Ok(Message::Binary(bytes))) => {
+                    let heads = receive(manager, &mut peer, &bytes)?;
+                    let in_sync = peer.their_heads.as_ref().map(|theirs| same_heads(theirs, &heads));
+                    let in_sync = in_sync.unwrap_or(false);
+                    if in_sync && !converged {
+                        let _ = events.send(SyncEvent::Converged { heads });
+                    }
+                    converged = in_sync;
+                }
+                Some(Ok(Message::Close(_))) | None => return Ok(()),
+                Some(Ok(_)) => {} // Ping/pong are handled by tungstenite; text is ignored
+                Some(Err(e)) => return Err(e.to_string()),
+            },
+            _ = ticker.tick() => {}
+            _ = notify.notified() => {}
+        }
+    }
+}
+
+// The lock is taken inside these helpers so no guard is held across an await.
+
+fn generate<M: SyncDocument>(manager: &Mutex<M>, peer: &mut PeerState) -> Option<Vec<u8>> {
+    let mut manager = manager.lock().unwrap_or_else(PoisonError::into_inner);
+    manager.generate_protocol_message(peer)
+}
+
+fn receive<M: SyncDocument>(
+    manager: &Mutex<M>,
+    peer: &mut PeerState,
+    message: &[u8],
+) -> Result<Vec<ChangeHash>, String> {
+    let mut manager = manager.lock().unwrap_or_else(PoisonError::into_inner);
+    manager
+        .receive_protocol_message(peer, message)
+        .map_err(|e| e.to_string())?;
+    Ok(manager.heads())
+}
+
+fn same_heads(a: &[ChangeHash], b: &[ChangeHash]) -> bool {
+    let mut a = a.to_vec();
+    let mut b = b.to_vec();
+    a.sort();
+    b.sort();
+    a == b
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_and_caps() {
+        let config = SyncClientConfig::default()
+            .with_backoff(Duration::from_millis(100), Duration::from_secs(1));
+        assert_eq!(config.backoff(0), Duration::from_millis(100));
+        assert_eq!(config.backoff(1), Duration::from_millis(200));
+        assert_eq!(config.backoff(3), Duration::from_millis(800));
+        assert_eq!(config.backoff(4), Duration::from_secs(1));
+        assert_eq!(config.backoff(u32::MAX), Duration::from_secs(1));
+    }
+}
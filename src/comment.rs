@@ -0,0 +1,346 @@
+//! Threaded review comments shared by sequence and storyboard documents.
+//!
+//! Threads are flat lists: a reply points at its parent via `parent_id`.
+//! Keeping the list flat means appending a reply never rewrites the thread.
+//! Lists are capped at `MAX_COMMENTS`; the oldest threads are dropped first,
+//! whole, so a reply never outlives its parent (see `trim_indices()`).
+
+use std::collections::HashMap;
+
+use autosurgeon::{Hydrate, Reconcile};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CollabError, CollabResult};
+
+/// Maximum number of comments kept per thread list.
+pub const MAX_COMMENTS: usize = 200;
+
+/// A single comment or reply.
+#[derive(Debug, Clone, Default, Reconcile, Hydrate, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Comment {
+    /// Unique identifier within the thread list.
+    pub id: String,
+    /// Author identifier (user ID or email).
+    pub author: String,
+    /// Comment text.
+    pub body: String,
+    /// Creation time (milliseconds since epoch).
+    pub created_at: i64,
+    /// Whether the comment (thread) has been resolved.
+    pub resolved: bool,
+    /// ID of the comment this replies to (None for thread roots).
+    pub parent_id: Option<String>,
+    /// Mentioned users or entity tags (e.g., "@richie"). Missing on comments
+    /// written before mentions existed.
+    #[autosurgeon(missing = "Default::default")]
+    pub mentions: Vec<String>,
+}
+
+impl Comment {
+    /// Creates a new top-level comment.
+    pub fn new(id: impl Into<String>, author: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            author: author.into(),
+            body: body.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Builder: Set creation timestamp.
+    pub fn with_created_at(mut self, created_at: i64) -> Self {
+        self.created_at = created_at;
+        self
+    }
+
+    /// Builder: Make this a reply to `parent_id`.
+    pub fn with_parent(mut self, parent_id: impl Into<String>) -> Self {
+        self.parent_id = Some(parent_id.into());
+        self
+    }
+
+    /// Builder: Set mentions explicitly.
+    pub fn with_mentions<I, S>(mut self, mentions: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.mentions = mentions.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Builder: Fill mentions from `@name` tokens in the body.
+    pub fn with_mentions_from_body(mut self) -> Self {
+        self.mentions = extract_mentions(&self.body);
+        self
+    }
+
+    /// Returns true if this comment is a reply.
+    pub fn is_reply(&self) -> bool {
+        self.parent_id.is_some()
+    }
+}
+
+/// Extracts unique `@name` tokens from text, in order of first appearance.
+pub fn extract_mentions(text: &str) -> Vec<String> {
+    let mut mentions: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        let Some(rest) = word.strip_prefix('@') else {
+            continue;
+        };
+        let name: String = rest
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '-')
+            .collect();
+        if name.is_empty() {
+            continue;
+        }
+        let mention = format!("@{}", name);
+        if !mentions.contains(&mention) {
+            mentions.push(mention);
+        }
+    }
+    mentions
+}
+
+/// Checks that `comment` can be appended to `existing` (unique ID, known parent).
+pub(crate) fn validate_new_comment(existing: &[Comment], comment: &Comment) -> CollabResult<()> {
+    if existing.iter().any(|c| c.id == comment.id) {
+        return Err(CollabError::schema_violation(format!(
+            "comment '{}' already exists",
+            comment.id
+        )));
+    }
+    if let Some(parent) = &comment.parent_id {
+        if !existing.iter().any(|c| &c.id == parent) {
+            return Err(CollabError::comment_not_found(parent.as_str()));
+        }
+    }
+    Ok(())
+}
+
+/// Appends `comment` to a thread list and trims it to `MAX_COMMENTS`.
+pub(crate) fn push_comment(comments: &mut Vec<Comment>, comment: Comment) -> CollabResult<()> {
+    validate_new_comment(comments, &comment)?;
+    let id = comment.id.clone();
+    comments.push(comment);
+    for index in trim_indices(comments, &id).into_iter().rev() {
+        comments.remove(index);
+    }
+    Ok(())
+}
+
+/// Returns the indices (ascending) of comments to drop so `comments` fits in
+/// `MAX_COMMENTS`.
+///
+/// Whole threads go first, oldest root first, sparing the thread of `keep`
+/// (the comment just added). If that thread alone is over the cap, its
+/// oldest unanswered replies go next. A comment is never dropped while a
+/// reply to it is kept, so a thread that is one long reply chain may stay
+/// over the cap.
+pub(crate) fn trim_indices(comments: &[Comment], keep: &str) -> Vec<usize> {
+    let mut excess = comments.len().saturating_sub(MAX_COMMENTS);
+    if excess == 0 {
+        return Vec::new();
+    }
+
+    let index_of: HashMap<&str, usize> =
+        comments.iter().enumerate().map(|(i, c)| (c.id.as_str(), i)).collect();
+    let root_of = |mut i: usize| {
+        // Bounded walk: a malformed list could contain a parent cycle
+        for _ in 0..comments.len() {
+            match comments[i].parent_id.as_deref().and_then(|p| index_of.get(p)) {
+                Some(&parent) => i = parent,
+                None => break,
+            }
+        }
+        i
+    };
+    let roots: Vec<usize> = (0..comments.len()).map(root_of).collect();
+    let keep_root = index_of.get(keep).map(|&i| roots[i]);
+
+    let mut doomed = vec![false; comments.len()];
+    let mut thread_roots: Vec<usize> = roots.clone();
+    thread_roots.sort_unstable();
+    thread_roots.dedup();
+    for root in thread_roots {
+        if excess == 0 {
+            break;
+        }
+        if Some(root) == keep_root {
+            continue;
+        }
+        for (i, &r) in roots.iter().enumerate() {
+            if r == root {
+                doomed[i] = true;
+                excess = excess.saturating_sub(1);
+            }
+        }
+    }
+
+    // Still over: drop the oldest comments nothing kept replies to
+    let mut progressed = true;
+    while excess > 0 && progressed {
+        progressed = false;
+        for i in 0..comments.len() {
+            if excess == 0 {
+                break;
+            }
+            let id = comments[i].id.as_str();
+            let answered = comments
+                .iter()
+                .zip(&doomed)
+                .any(|(c, &gone)| !gone && c.parent_id.as_deref() == Some(id));
+            if !doomed[i] && id != keep && !answered {
+                doomed[i] = true;
+                excess -= 1;
+                progressed = true;
+            }
+        }
+    }
+
+    (0..comments.len()).filter(|&i| doomed[i]).collect()
+}
+
+/// Sets the resolved flag on a comment.
+pub(crate) fn resolve_comment(
+    comments: &mut [Comment],
+    comment_id: &str,
+    resolved: bool,
+) -> CollabResult<()> {
+    let comment = comments
+        .iter_mut()
+        .find(|c| c.id == comment_id)
+        .ok_or_else(|| CollabError::comment_not_found(comment_id))?;
+    comment.resolved = resolved;
+    Ok(())
+}
+
+/// Removes a comment and all of its (transitive) replies.
+pub(crate) fn remove_comment(comments: &mut Vec<Comment>, comment_id: &str) -> CollabResult<()> {
+    if !comments.iter().any(|c| c.id == comment_id) {
+        return Err(CollabError::comment_not_found(comment_id));
+    }
+    let mut doomed = vec![comment_id.to_string()];
+    let mut i = 0;
+    while i < doomed.len() {
+        let parent = doomed[i].clone();
+        for c in comments.iter() {
+            if c.parent_id.as_deref() == Some(parent.as_str()) && !doomed.contains(&c.id) {
+                doomed.push(c.id.clone());
+            }
+        }
+        i += 1;
+    }
+    comments.retain(|c| !doomed.contains(&c.id));
+    Ok(())
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thread_operations() {
+        let mut thread = Vec::new();
+        push_comment(&mut thread, Comment::new("c1", "alice", "Too dark")).unwrap();
+        push_comment(&mut thread, Comment::new("c2", "bob", "Agreed").with_parent("c1")).unwrap();
+        push_comment(&mut thread, Comment::new("c3", "alice", "Fixed").with_parent("c2")).unwrap();
+        push_comment(&mut thread, Comment::new("c4", "carol", "Unrelated")).unwrap();
+
+        assert!(push_comment(&mut thread, Comment::new("c1", "x", "dup")).is_err());
+        assert!(matches!(
+            push_comment(&mut thread, Comment::new("c5", "x", "y").with_parent("nope")),
+            Err(CollabError::CommentNotFound(_))
+        ));
+
+        resolve_comment(&mut thread, "c1", true).unwrap();
+        assert!(thread[0].resolved);
+
+        remove_comment(&mut thread, "c1").unwrap();
+        let ids: Vec<&str> = thread.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["c4"]);
+    }
+
+    #[test]
+    fn test_thread_trimmed_to_max() {
+        let mut thread = Vec::new();
+        for i in 0..MAX_COMMENTS + 5 {
+            push_comment(&mut thread, Comment::new(format!("c{}", i), "a", "b")).unwrap();
+        }
+        assert_eq!(thread.len(), MAX_COMMENTS);
+        assert_eq!(thread[0].id, "c5");
+    }
+
+    #[test]
+    fn test_trim_keeps_replies_with_parents() {
+        // "old" is the oldest root and has a reply near the end of the list
+        let mut thread = vec![Comment::new("old", "a", "b")];
+        for i in 0..MAX_COMMENTS - 2 {
+            push_comment(&mut thread, Comment::new(format!("c{}", i), "a", "b")).unwrap();
+        }
+        push_comment(&mut thread, Comment::new("old-reply", "a", "b").with_parent("old")).unwrap();
+        assert_eq!(thread.len(), MAX_COMMENTS);
+
+        // Overflowing drops "old" together with its reply
+        push_comment(&mut thread, Comment::new("new", "a", "b")).unwrap();
+        assert!(thread.iter().all(|c| c.id != "old" && c.id != "old-reply"));
+        assert_eq!(thread.len(), MAX_COMMENTS - 1);
+
+        // Replying to the oldest thread drops the next oldest instead
+        thread.push(Comment::new("filler", "a", "b"));
+        push_comment(&mut thread, Comment::new("r", "a", "b").with_parent("c0")).unwrap();
+        assert_eq!(thread[0].id, "c0");
+        assert!(thread.iter().all(|c| c.id != "c1"));
+        for c in &thread {
+            if let Some(parent) = &c.parent_id {
+                assert!(thread.iter().any(|p| &p.id == parent));
+            }
+        }
+    }
+
+    #[test]
+    fn test_trim_long_reply_chain() {
+        let mut thread = vec![Comment::new("c0", "a", "b")];
+        for i in 1..MAX_COMMENTS + 3 {
+            let reply = Comment::new(format!("c{}", i), "a", "b");
+            push_comment(&mut thread, reply.with_parent(format!("c{}", i - 1))).unwrap();
+        }
+        // Nothing can go without orphaning a reply
+        assert_eq!(thread.len(), MAX_COMMENTS + 3);
+    }
+
+    #[test]
+    fn test_hydrate_comment_without_mentions() {
+        use automerge::transaction::Transactable;
+        use automerge::{AutoCommit, ObjType, ROOT};
+
+        // Comments written before `mentions` existed
+        let mut doc = AutoCommit::new();
+        let list = doc.put_object(ROOT, "comments", ObjType::List).unwrap();
+        let obj = doc.insert_object(&list, 0, ObjType::Map).unwrap();
+        doc.put(&obj, "id", "c1").unwrap();
+        doc.put(&obj, "author", "alice").unwrap();
+        doc.put(&obj, "body", "Too dark").unwrap();
+        doc.put(&obj, "created_at", 5_i64).unwrap();
+        doc.put(&obj, "resolved", false).unwrap();
+
+        let comments: Vec<Comment> = autosurgeon::hydrate_prop(&doc, ROOT, "comments").unwrap();
+        assert_eq!(comments, vec![Comment::new("c1", "alice", "Too dark").with_created_at(5)]);
+    }
+
+    #[test]
+    fn test_extract_mentions() {
+        assert_eq!(
+            extract_mentions("@richie, check with @anna-k and @richie. email@x"),
+            vec!["@richie", "@anna-k"]
+        );
+        let comment = Comment::new("c1", "a", "ping @bob").with_mentions_from_body();
+        assert_eq!(comment.mentions, vec!["@bob"]);
+    }
+}
@@ -0,0 +1,52 @@
+//! History compaction.
+//!
+//! Long-lived documents accumulate change histories far larger than their
+//! current state. `save_compact()` writes the current state into a fresh
+//! document (new actor, single change), so the saved bytes only grow with the
+//! content. Unlike `squash_history`, nothing of the old history is kept.
+//!
+//! The compacted document is a new baseline: its heads are unrelated to the
+//! original, so peers must reload it rather than sync against it.
+
+use automerge::{transaction::CommitOptions, AutoCommit};
+use autosurgeon::{reconcile, Reconcile};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::error::CollabResult;
+
+/// Commit message of the single change in a compacted document.
+pub const COMPACT_MESSAGE: &str = "Compact history";
+
+/// Size of a document's change history, passed to `compact_if()` policies.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HistoryStats {
+    /// Number of changes.
+    pub changes: usize,
+    /// Number of operations across all changes.
+    pub ops: usize,
+    /// Number of distinct actors that made changes.
+    pub actors: usize,
+    /// Total encoded size of all changes in bytes (before save compression).
+    pub change_bytes: usize,
+}
+
+/// Collects history statistics for a document.
+pub(crate) fn history_stats(doc: &mut AutoCommit) -> HistoryStats {
+    let changes = doc.get_changes(&[]);
+    let actors: HashSet<_> = changes.iter().map(|c| c.actor_id()).collect();
+    HistoryStats {
+        changes: changes.len(),
+        ops: changes.iter().map(|c| c.len()).sum(),
+        actors: actors.len(),
+        change_bytes: changes.iter().map(|c| c.raw_bytes().len()).sum(),
+    }
+}
+
+/// Saves `state` as a fresh single-change document.
+pub(crate) fn compact_state<R: Reconcile>(state: &R) -> CollabResult<Vec<u8>> {
+    let mut doc = AutoCommit::new();
+    reconcile(&mut doc, state)?;
+    doc.commit_with(CommitOptions::default().with_message(COMPACT_MESSAGE));
+    Ok(doc.save())
+}
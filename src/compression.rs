@@ -0,0 +1,91 @@
+//! Gzip compression for saved documents.
+//!
+//! `save_compressed()` wraps the Automerge binary in gzip; `from_bytes()`
+//! detects the gzip magic bytes and decompresses transparently, so callers
+//! that store or upload snapshots don't need to know which format they hold.
+//! Plain Automerge documents start with `85 6f 4a 83` and never collide with
+//! the gzip header.
+//!
+//! Gzip (via the pure-Rust miniz backend) is used instead of zstd because it
+//! builds for `wasm32-unknown-unknown` without a C toolchain.
+
+use std::borrow::Cow;
+use std::io::{Read, Write};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+/// Default gzip level used when callers have no preference.
+pub const DEFAULT_LEVEL: u32 = 6;
+
+/// Check if data is gzip compressed (magic bytes: 0x1f 0x8b)
+pub fn is_gzipped(data: &[u8]) -> bool {
+    data.len() >= 2 && data[0] == 0x1f && data[1] == 0x8b
+}
+
+/// Compresses `data` with gzip at `level` (0-9, clamped).
+pub fn compress(data: &[u8], level: u32) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::new(level.min(9)));
+    encoder
+        .write_all(data)
+        .expect("writing to a Vec cannot fail");
+    encoder.finish().expect("writing to a Vec cannot fail")
+}
+
+/// Decompress gzip data if compressed, otherwise return as-is
+pub fn maybe_decompress(data: &[u8]) -> std::io::Result<Cow<'_, [u8]>> {
+    if is_gzipped(data) {
+        let mut decoder = GzDecoder::new(data);
+        let mut decompressed = Vec::new();
+        decoder.read_to_end(&mut decompressed)?;
+        Ok(Cow::Owned(decompressed))
+    } else {
+        Ok(Cow::Borrowed(data))
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_gzipped_true() {
+        let data = [0x1f, 0x8b, 0x08, 0x00]; // gzip magic + compression method
+        assert!(is_gzipped(&data));
+    }
+
+    #[test]
+    fn test_is_gzipped_false() {
+        let data = b"Hello, World!";
+        assert!(!is_gzipped(data));
+    }
+
+    #[test]
+    fn test_is_gzipped_empty() {
+        let data: &[u8] = &[];
+        assert!(!is_gzipped(data));
+    }
+
+    #[test]
+    fn test_maybe_decompress_plain() {
+        let data = b"Hello, World!".to_vec();
+        let result = maybe_decompress(&data).unwrap();
+        assert!(matches!(result, Cow::Borrowed(_)));
+        assert_eq!(result, &data[..]);
+    }
+
+    #[test]
+    fn test_maybe_decompress_gzipped() {
+        let original = b"Hello, World!";
+        let compressed = compress(original, DEFAULT_LEVEL);
+        assert!(is_gzipped(&compressed));
+
+        let result = maybe_decompress(&compressed).unwrap();
+        assert_eq!(result, &original[..]);
+    }
+}
@@ -0,0 +1,126 @@
+//! Inspection and resolution of concurrent writes.
+//!
+//! When two peers set the same field without seeing each other's change,
+//! Automerge keeps both values and picks a winner deterministically; the
+//! managers only ever read the winner. `get_conflicts()` exposes every
+//! value with the actor that wrote it so a UI can show "Alice set
+//! completed, Bob set failed", and `resolve_conflict()` writes the chosen
+//! value back over all of them.
+
+use automerge::{transaction::Transactable, AutoCommit, ObjId, ReadDoc, Value};
+use autosurgeon::reconcile_prop;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CollabError, CollabResult};
+use crate::raw::{read_value, RawValue};
+
+/// One of the concurrent values of a field.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConflictValue {
+    /// Hex-encoded actor ID that wrote the value.
+    pub actor: String,
+    pub value: RawValue,
+}
+
+/// Returns every concurrent value of `field` under `obj`, winner first.
+/// A field without a conflict has one value; a missing field has none.
+/// Dotted fields (`settings.cfg`) address nested maps.
+pub(crate) fn get_conflicts(
+    doc: &AutoCommit,
+    obj: &ObjId,
+    field: &str,
+) -> CollabResult<Vec<ConflictValue>> {
+    let Some((parent, key)) = resolve_field(doc, obj, field)? else {
+        return Ok(Vec::new());
+    };
+    let mut values = Vec::new();
+    for (value, id) in doc.get_all(&parent, key)? {
+        let actor = match &id {
+            ObjId::Id(_, actor, _) => actor.to_hex_string(),
+            ObjId::Root => continue,
+        };
+        let value = read_value(doc, value, &id)?;
+        values.push(ConflictValue { actor, value });
+    }
+    // get_all() lists the winner last
+    values.reverse();
+    Ok(values)
+}
+
+/// Keeps the value `chosen_actor` wrote to `field`, replacing every
+/// concurrent value. Fails with `FieldNotFound` if that actor has no value
+/// there.
+pub(crate) fn resolve_conflict(
+    doc: &mut AutoCommit,
+    obj: &ObjId,
+    field: &str,
+    chosen_actor: &str,
+) -> CollabResult<()> {
+    let chosen = get_conflicts(doc, obj, field)?
+        .into_iter()
+        .find(|value| value.actor == chosen_actor)
+        .ok_or_else(|| {
+            CollabError::field_not_found(format!("{} (actor {})", field, chosen_actor))
+        })?;
+    let (parent, key) = resolve_field(doc, obj, field)?.expect("get_conflicts() found the field");
+    // Deleting first supersedes every concurrent value, including the
+    // current winner if it is the one being kept
+    doc.delete(&parent, key)?;
+    reconcile_prop(doc, &parent, key, &chosen.value)?;
+    Ok(())
+}
+
+/// Splits a dotted field into the map holding its last segment and that
+/// segment. Returns None if an intermediate map is missing.
+pub(crate) fn resolve_field<'a>(
+    doc: &AutoCommit,
+    obj: &ObjId,
+    field: &'a str,
+) -> CollabResult<Option<(ObjId, &'a str)>> {
+    let mut parent = obj.clone();
+    let mut segments = field.split('.').peekable();
+    while let Some(segment) = segments.next() {
+        if segments.peek().is_none() {
+            return Ok(Some((parent, segment)));
+        }
+        parent = match doc.get(&parent, segment)? {
+            Some((Value::Object(_), id)) => id,
+            _ => return Ok(None),
+        };
+    }
+    Ok(None)
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use automerge::ROOT;
+
+    #[test]
+    fn test_conflicting_puts_are_listed_and_resolved() {
+        let mut alice = AutoCommit::new();
+        let settings = alice.put_object(&ROOT, "settings", automerge::ObjType::Map).unwrap();
+        alice.put(&settings, "status", "completed").unwrap();
+        let mut bob = alice.fork();
+        alice.put(&settings, "status", "failed").unwrap();
+        bob.put(&settings, "status", "cancelled").unwrap();
+        alice.merge(&mut bob).unwrap();
+
+        let conflicts = get_conflicts(&alice, &ROOT, "settings.status").unwrap();
+        assert_eq!(conflicts.len(), 2);
+        let bob_actor = bob.get_actor().to_hex_string();
+        let bobs = conflicts.iter().find(|c| c.actor == bob_actor).unwrap();
+        assert_eq!(bobs.value, RawValue::Str("cancelled".to_string()));
+
+        resolve_conflict(&mut alice, &ROOT, "settings.status", &bob_actor).unwrap();
+        let resolved = get_conflicts(&alice, &ROOT, "settings.status").unwrap();
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].value, RawValue::Str("cancelled".to_string()));
+        assert!(resolve_conflict(&mut alice, &ROOT, "settings.status", "00").is_err());
+        assert!(get_conflicts(&alice, &ROOT, "missing.status").unwrap().is_empty());
+    }
+}
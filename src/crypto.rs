@@ -0,0 +1,149 @@
+//! AES-256-GCM encryption with PBKDF2 key derivation.
+//!
+//! Lets clients persist snapshots to untrusted storage. The format matches
+//! the TypeScript implementation in storyboardCrypto.ts (and the encrypted
+//! payloads read by `sb-migrate`):
+//!
+//! ```text
+//! [12-byte IV][ciphertext with 16-byte auth tag]
+//! ```
+//!
+//! The key is derived from the owner's email and the document creation time,
+//! so the same `KeyParams` always decrypt what they encrypted.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use pbkdf2::pbkdf2_hmac;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::error::{CollabError, CollabResult};
+
+const HARDCODED_SALT: &str = "dheyo-storyboard-salt-v1";
+const PBKDF2_ITERATIONS: u32 = 100_000;
+const KEY_LENGTH: usize = 32; // 256 bits
+
+/// Length of the IV prefix of an encrypted payload.
+pub const IV_LENGTH: usize = 12;
+
+/// Parameters needed for key derivation.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyParams {
+    pub email: String,
+    /// Document creation time (milliseconds since epoch).
+    pub created_at: i64,
+}
+
+impl KeyParams {
+    /// Creates key parameters.
+    pub fn new(email: impl Into<String>, created_at: i64) -> Self {
+        Self {
+            email: email.into(),
+            created_at,
+        }
+    }
+}
+
+/// Derive encryption key using PBKDF2
+///
+/// Key material format: "{email}:{salt}:{createdAt}"
+fn derive_key(params: &KeyParams) -> [u8; KEY_LENGTH] {
+    let key_material = format!(
+        "{}:{}:{}",
+        params.email, HARDCODED_SALT, params.created_at
+    );
+    let salt = HARDCODED_SALT.as_bytes();
+
+    let mut derived_key = [0u8; KEY_LENGTH];
+    pbkdf2_hmac::<Sha256>(
+        key_material.as_bytes(),
+        salt,
+        PBKDF2_ITERATIONS,
+        &mut derived_key,
+    );
+
+    derived_key
+}
+
+/// Encrypts `plaintext` with a fresh random IV.
+pub fn encrypt(plaintext: &[u8], params: &KeyParams) -> CollabResult<Vec<u8>> {
+    let key = derive_key(params);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|e| CollabError::crypto(e.to_string()))?;
+
+    let mut combined = Vec::with_capacity(IV_LENGTH + ciphertext.len());
+    combined.extend_from_slice(&nonce);
+    combined.extend_from_slice(&ciphertext);
+    Ok(combined)
+}
+
+/// Decrypts a payload produced by `encrypt()` (or storyboardCrypto.ts).
+///
+/// Fails if the data is truncated, was tampered with, or the key params
+/// don't match.
+pub fn decrypt(data: &[u8], params: &KeyParams) -> CollabResult<Vec<u8>> {
+    if data.len() < IV_LENGTH {
+        return Err(CollabError::crypto(format!(
+            "Data too short for IV: {} bytes",
+            data.len()
+        )));
+    }
+
+    // Extract IV and ciphertext
+    let (iv, ciphertext) = data.split_at(IV_LENGTH);
+
+    let key = derive_key(params);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    cipher
+        .decrypt(Nonce::from_slice(iv), ciphertext)
+        .map_err(|e| CollabError::crypto(format!("Decryption failed: {}", e)))
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_derivation_deterministic() {
+        let params = KeyParams::new("test@example.com", 1700000000000);
+        let key1 = derive_key(&params);
+        let key2 = derive_key(&params);
+        assert_eq!(key1, key2);
+        assert_eq!(key1.len(), 32);
+    }
+
+    #[test]
+    fn test_key_derivation_different_emails() {
+        let key1 = derive_key(&KeyParams::new("test1@example.com", 1700000000000));
+        let key2 = derive_key(&KeyParams::new("test2@example.com", 1700000000000));
+        assert_ne!(key1, key2);
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        let params = KeyParams::new("test@example.com", 1700000000000);
+        let encrypted = encrypt(b"storyboard bytes", &params).unwrap();
+        assert_eq!(encrypted.len(), IV_LENGTH + 16 + 16);
+        assert_eq!(decrypt(&encrypted, &params).unwrap(), b"storyboard bytes");
+
+        // Fresh IV per call
+        assert_ne!(encrypt(b"storyboard bytes", &params).unwrap(), encrypted);
+
+        // Wrong key, tampering and truncation are all rejected
+        let other = KeyParams::new("other@example.com", 1700000000000);
+        assert!(decrypt(&encrypted, &other).is_err());
+        let mut tampered = encrypted.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(decrypt(&tampered, &params).is_err());
+        assert!(decrypt(&encrypted[..8], &params).is_err());
+    }
+}
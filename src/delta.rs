@@ -0,0 +1,123 @@
+//! Binary deltas between saved snapshots.
+//!
+//! A delta is the concatenation of the raw Automerge changes present in the
+//! newer snapshot but not in the older one. Backends can keep one full
+//! snapshot per day plus a delta per save, and rebuild any save with
+//! `apply_delta`. These helpers work on bytes alone and do not need a manager,
+//! so they apply equally to sequence and storyboard documents.
+
+use automerge::AutoCommit;
+
+use crate::error::{CollabError, CollabResult};
+
+/// Returns the changes in `new_bytes` that are missing from `old_bytes`.
+///
+/// Fails if `new_bytes` does not contain every change of `old_bytes`, i.e.
+/// when the snapshots have diverged rather than one extending the other.
+pub fn delta_between(old_bytes: &[u8], new_bytes: &[u8]) -> CollabResult<Vec<u8>> {
+    let mut old = AutoCommit::load(old_bytes)?;
+    let mut new = AutoCommit::load(new_bytes)?;
+
+    let old_heads = old.get_heads();
+    if let Some(missing) = old_heads
+        .iter()
+        .find(|h| new.get_change_by_hash(h).is_none())
+    {
+        return Err(CollabError::schema_violation(format!(
+            "new snapshot is missing change {} from the old snapshot",
+            missing
+        )));
+    }
+
+    let mut bytes = Vec::new();
+    for change in new.get_changes(&old_heads) {
+        bytes.extend(change.raw_bytes());
+    }
+    Ok(bytes)
+}
+
+/// Applies a delta to a snapshot and returns the resulting snapshot.
+pub fn apply_delta(snapshot: &[u8], delta: &[u8]) -> CollabResult<Vec<u8>> {
+    apply_deltas(snapshot, [delta])
+}
+
+/// Applies a series of deltas (oldest first) to a snapshot.
+pub fn apply_deltas<'a, I>(snapshot: &[u8], deltas: I) -> CollabResult<Vec<u8>>
+where
+    I: IntoIterator<Item = &'a [u8]>,
+{
+    let mut doc = AutoCommit::load(snapshot)?;
+    for delta in deltas {
+        doc.load_incremental(delta)?;
+    }
+    Ok(doc.save())
+}
+
+/// WASM binding for `delta_between()`.
+///
+/// # Example (JavaScript)
+/// ```js
+/// const delta = deltaBetween(lastSnapshot, manager.toBytes());
+/// await storeDelta(docId, delta);
+/// ```
+#[cfg(feature = "wasm")]
+#[wasm_bindgen::prelude::wasm_bindgen(js_name = deltaBetween)]
+pub fn js_delta_between(
+    old_bytes: &[u8],
+    new_bytes: &[u8],
+) -> Result<js_sys::Uint8Array, wasm_bindgen::JsValue> {
+    let delta = delta_between(old_bytes, new_bytes).map_err(wasm_bindgen::JsValue::from)?;
+    Ok(js_sys::Uint8Array::from(&delta[..]))
+}
+
+/// WASM binding for `apply_delta()`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen::prelude::wasm_bindgen(js_name = applyDelta)]
+pub fn js_apply_delta(
+    snapshot: &[u8],
+    delta: &[u8],
+) -> Result<js_sys::Uint8Array, wasm_bindgen::JsValue> {
+    let bytes = apply_delta(snapshot, delta).map_err(wasm_bindgen::JsValue::from)?;
+    Ok(js_sys::Uint8Array::from(&bytes[..]))
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequence::{GenerationNode, SequenceManager};
+
+    #[test]
+    fn test_delta_roundtrip() {
+        let mut manager = SequenceManager::new();
+        manager
+            .create_and_append("a", GenerationNode::new("a", "t2i"))
+            .unwrap();
+        let day_start = manager.save();
+
+        manager.set_status("a", "completed").unwrap();
+        let save_1 = manager.save();
+        manager
+            .create_and_append("b", GenerationNode::new("b", "i2v"))
+            .unwrap();
+        let save_2 = manager.save();
+
+        let delta_1 = delta_between(&day_start, &save_1).unwrap();
+        let delta_2 = delta_between(&save_1, &save_2).unwrap();
+        assert!(delta_between(&save_2, &save_2).unwrap().is_empty());
+
+        let rebuilt = apply_deltas(&day_start, [&delta_1[..], &delta_2[..]]).unwrap();
+        let mut loaded = SequenceManager::from_bytes(&rebuilt).unwrap();
+        assert_eq!(loaded.get_state().unwrap(), manager.get_state().unwrap());
+    }
+
+    #[test]
+    fn test_delta_rejects_diverged_snapshots() {
+        let mut a = SequenceManager::new();
+        let mut b = SequenceManager::new();
+        assert!(delta_between(&a.save(), &b.save()).is_err());
+    }
+}
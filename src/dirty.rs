@@ -0,0 +1,346 @@
+//! Incremental reconcile for `update_state()`.
+//!
+//! A full `reconcile()` reads back every object in the document, even when
+//! the closure changed a single field. The managers' cached state mirrors
+//! the document, so `update_state()` instead compares the state before and
+//! after the closure and reconciles only what differs: plain fields as a
+//! whole, keyed collections (generations, scenes, entities) entry by entry.
+//! Comparing unchanged values in memory is far cheaper than walking them in
+//! the document.
+//!
+//! The per-root functions destructure the new state, so adding a root field
+//! without reconciling it here fails to compile.
+
+use std::collections::HashMap;
+
+use automerge::{transaction::Transactable, AutoCommit, ObjId, ObjType, ReadDoc, Value, ROOT};
+use autosurgeon::{reconcile_prop, Reconcile};
+
+use crate::error::CollabResult;
+use crate::ordered_map::OrderedMap;
+use crate::sequence::model::{output_display_order, outputs_in_list_order, stored_output_keys};
+use crate::sequence::{DocumentRoot, GenerationNode};
+#[cfg(feature = "storyboard")]
+use crate::storyboard::model::{ProcessingStages, StoryboardRoot, Trash};
+
+/// Reconciles `obj[key]` if `new` differs from `old`.
+fn reconcile_field<T: Reconcile + PartialEq>(
+    doc: &mut AutoCommit,
+    obj: &ObjId,
+    key: &str,
+    old: &T,
+    new: &T,
+) -> CollabResult<()> {
+    if old != new {
+        reconcile_prop(doc, obj, key, new)?;
+    }
+    Ok(())
+}
+
+/// Reconciles only the added, changed and removed entries of the map at
+/// `obj[key]`. Falls back to reconciling the whole map if it is missing.
+fn reconcile_map<V: Reconcile + PartialEq>(
+    doc: &mut AutoCommit,
+    obj: &ObjId,
+    key: &str,
+    old: &HashMap<String, V>,
+    new: &HashMap<String, V>,
+) -> CollabResult<()> {
+    reconcile_map_with(doc, obj, key, old, new, |doc, map, id, _, value| {
+        reconcile_prop(doc, map, id, value)?;
+        Ok(())
+    })
+}
+
+/// `reconcile_map()` with `write(doc, map, id, old, new)` writing each
+/// added or changed entry.
+fn reconcile_map_with<V: Reconcile + PartialEq>(
+    doc: &mut AutoCommit,
+    obj: &ObjId,
+    key: &str,
+    old: &HashMap<String, V>,
+    new: &HashMap<String, V>,
+    write: impl Fn(&mut AutoCommit, &ObjId, &str, Option<&V>, &V) -> CollabResult<()>,
+) -> CollabResult<()> {
+    if old == new {
+        return Ok(());
+    }
+    let map = match doc.get(obj, key)? {
+        Some((Value::Object(ObjType::Map), map)) => map,
+        _ => {
+            reconcile_prop(doc, obj, key, new)?;
+            return Ok(());
+        }
+    };
+    for id in old.keys().filter(|id| !new.contains_key(*id)) {
+        doc.delete(&map, id.as_str())?;
+    }
+    for (id, value) in new {
+        let before = old.get(id);
+        if before != Some(value) {
+            write(doc, &map, id.as_str(), before, value)?;
+        }
+    }
+    Ok(())
+}
+
+/// Reconciles an ordered map stored as the sibling keys `items_key` and
+/// `order_key`: changed entries one by one, the order list as a whole.
+fn reconcile_ordered<V: Reconcile + PartialEq>(
+    doc: &mut AutoCommit,
+    obj: &ObjId,
+    items_key: &str,
+    order_key: &str,
+    old: &OrderedMap<V>,
+    new: &OrderedMap<V>,
+) -> CollabResult<()> {
+    reconcile_map(doc, obj, items_key, old, new)?;
+    reconcile_order(doc, obj, order_key, old, new)
+}
+
+/// Reconciles the order list of an ordered map if it changed.
+fn reconcile_order<V>(
+    doc: &mut AutoCommit,
+    obj: &ObjId,
+    order_key: &str,
+    old: &OrderedMap<V>,
+    new: &OrderedMap<V>,
+) -> CollabResult<()> {
+    if old.order() != new.order() {
+        reconcile_prop(doc, obj, order_key, new.order().to_vec())?;
+    }
+    Ok(())
+}
+
+/// Writes a changed generation node. Outputs are hydrated in order-key
+/// order, so they are laid back over the elements they came from instead
+/// of being rewritten by position (see `outputs_in_list_order()`).
+fn reconcile_generation(
+    doc: &mut AutoCommit,
+    map: &ObjId,
+    id: &str,
+    old: Option<&GenerationNode>,
+    new: &GenerationNode,
+) -> CollabResult<()> {
+    let stored = match doc.get(map, id)? {
+        Some((Value::Object(ObjType::Map), node)) => match doc.get(&node, "outputs")? {
+            Some((Value::Object(ObjType::List), list)) => Some(stored_output_keys(&*doc, &list)?),
+            _ => None,
+        },
+        _ => None,
+    };
+    let outputs = match (old, stored) {
+        (Some(old), Some(keys)) => {
+            outputs_in_list_order(&output_display_order(&keys), &old.outputs, &new.outputs)
+        }
+        _ => None,
+    };
+    match outputs {
+        Some(outputs) => {
+            reconcile_prop(doc, map, id, &GenerationNode { outputs, ..new.clone() })?
+        }
+        None => reconcile_prop(doc, map, id, new)?,
+    }
+    Ok(())
+}
+
+/// Writes the difference between two sequence states.
+pub(crate) fn reconcile_sequence(
+    doc: &mut AutoCommit,
+    old: &DocumentRoot,
+    new: &DocumentRoot,
+) -> CollabResult<()> {
+    let DocumentRoot {
+        generations,
+        fractional_order,
+        defaults,
+        presets,
+        actor_names,
+        acl,
+        snapshots,
+        queue,
+    } = new;
+    reconcile_map_with(
+        doc,
+        &ROOT,
+        "generations",
+        &old.generations,
+        generations,
+        reconcile_generation,
+    )?;
+    reconcile_order(doc, &ROOT, "sequence_order", &old.generations, generations)?;
+    reconcile_field(doc, &ROOT, "fractional_order", &old.fractional_order, fractional_order)?;
+    reconcile_field(doc, &ROOT, "defaults", &old.defaults, defaults)?;
+    reconcile_map(doc, &ROOT, "presets", &old.presets, presets)?;
+    reconcile_map(doc, &ROOT, "actor_names", &old.actor_names, actor_names)?;
+    reconcile_field(doc, &ROOT, "acl", &old.acl, acl)?;
+    reconcile_map(doc, &ROOT, "snapshots", &old.snapshots, snapshots)?;
+    reconcile_field(doc, &ROOT, "queue", &old.queue, queue)
+}
+
+/// Writes the difference between two storyboard states.
+#[cfg(feature = "storyboard")]
+pub(crate) fn reconcile_storyboard(
+    doc: &mut AutoCommit,
+    old: &StoryboardRoot,
+    new: &StoryboardRoot,
+) -> CollabResult<()> {
+    let StoryboardRoot {
+        id,
+        title,
+        description,
+        script_content,
+        script_files,
+        drive_file_ids,
+        status,
+        current_stage,
+        created_at,
+        last_updated,
+        num_shots,
+        thumbnail_image,
+        last_synced_sha,
+        encrypted_by_email,
+        processing_stages,
+        scene_order,
+        fractional_order,
+        scenes,
+        uploaded_assets,
+        blobs,
+        metadata,
+        trash,
+        actor_names,
+        acl,
+        snapshots,
+        scene_locks,
+        frozen,
+    } = new;
+    reconcile_field(doc, &ROOT, "id", &old.id, id)?;
+    reconcile_field(doc, &ROOT, "title", &old.title, title)?;
+    reconcile_field(doc, &ROOT, "description", &old.description, description)?;
+    reconcile_field(doc, &ROOT, "script_content", &old.script_content, script_content)?;
+    reconcile_field(doc, &ROOT, "script_files", &old.script_files, script_files)?;
+    reconcile_field(doc, &ROOT, "drive_file_ids", &old.drive_file_ids, drive_file_ids)?;
+    reconcile_field(doc, &ROOT, "status", &old.status, status)?;
+    reconcile_field(doc, &ROOT, "current_stage", &old.current_stage, current_stage)?;
+    reconcile_field(doc, &ROOT, "created_at", &old.created_at, created_at)?;
+    reconcile_field(doc, &ROOT, "last_updated", &old.last_updated, last_updated)?;
+    reconcile_field(doc, &ROOT, "num_shots", &old.num_shots, num_shots)?;
+    reconcile_field(doc, &ROOT, "thumbnail_image", &old.thumbnail_image, thumbnail_image)?;
+    reconcile_field(doc, &ROOT, "last_synced_sha", &old.last_synced_sha, last_synced_sha)?;
+    reconcile_field(
+        doc,
+        &ROOT,
+        "encrypted_by_email",
+        &old.encrypted_by_email,
+        encrypted_by_email,
+    )?;
+    reconcile_stages(doc, &old.processing_stages, processing_stages)?;
+    reconcile_field(doc, &ROOT, "scene_order", &old.scene_order, scene_order)?;
+    reconcile_field(doc, &ROOT, "fractional_order", &old.fractional_order, fractional_order)?;
+    reconcile_map(doc, &ROOT, "scenes", &old.scenes, scenes)?;
+    reconcile_map(doc, &ROOT, "uploaded_assets", &old.uploaded_assets, uploaded_assets)?;
+    reconcile_map(doc, &ROOT, "blobs", &old.blobs, blobs)?;
+    reconcile_field(doc, &ROOT, "metadata", &old.metadata, metadata)?;
+    reconcile_trash(doc, &old.trash, trash)?;
+    reconcile_map(doc, &ROOT, "actor_names", &old.actor_names, actor_names)?;
+    reconcile_field(doc, &ROOT, "acl", &old.acl, acl)?;
+    reconcile_map(doc, &ROOT, "snapshots", &old.snapshots, snapshots)?;
+    reconcile_map(doc, &ROOT, "scene_locks", &old.scene_locks, scene_locks)?;
+    reconcile_field(doc, &ROOT, "frozen", &old.frozen, frozen)
+}
+
+#[cfg(feature = "storyboard")]
+fn reconcile_stages(
+    doc: &mut AutoCommit,
+    old: &ProcessingStages,
+    new: &ProcessingStages,
+) -> CollabResult<()> {
+    if old == new {
+        return Ok(());
+    }
+    let stages = match doc.get(ROOT, "processing_stages")? {
+        Some((Value::Object(ObjType::Map), stages)) => stages,
+        _ => {
+            reconcile_prop(doc, ROOT, "processing_stages", new)?;
+            return Ok(());
+        }
+    };
+    let ProcessingStages {
+        characters,
+        props,
+        sets,
+        custom_collections,
+    } = new;
+    reconcile_ordered(
+        doc,
+        &stages,
+        "characters",
+        "character_order",
+        &old.characters,
+        characters,
+    )?;
+    reconcile_ordered(doc, &stages, "props", "prop_order", &old.props, props)?;
+    reconcile_ordered(doc, &stages, "sets", "set_order", &old.sets, sets)?;
+    reconcile_map(
+        doc,
+        &stages,
+        "custom_collections",
+        &old.custom_collections,
+        custom_collections,
+    )
+}
+
+#[cfg(feature = "storyboard")]
+fn reconcile_trash(doc: &mut AutoCommit, old: &Trash, new: &Trash) -> CollabResult<()> {
+    if old == new {
+        return Ok(());
+    }
+    let trash = match doc.get(ROOT, "trash")? {
+        Some((Value::Object(ObjType::Map), trash)) => trash,
+        _ => {
+            reconcile_prop(doc, ROOT, "trash", new)?;
+            return Ok(());
+        }
+    };
+    let Trash { scenes, shots } = new;
+    reconcile_map(doc, &trash, "scenes", &old.scenes, scenes)?;
+    reconcile_map(doc, &trash, "shots", &old.shots, shots)
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::sequence::{GenerationNode, SequenceManager};
+
+    #[test]
+    fn test_incremental_reconcile_matches_state() {
+        let mut manager = SequenceManager::new();
+        for i in 0..20 {
+            let id = format!("gen-{}", i);
+            manager.create_and_append(&id, GenerationNode::new(&id, "t2i")).unwrap();
+        }
+
+        // Edit, add and remove in one update
+        manager
+            .update_state(|state| {
+                state.generations.get_mut("gen-3").unwrap().prompt = "edited".to_string();
+                state.generations.remove("gen-7");
+                state.generations.insert("new".to_string(), GenerationNode::new("new", "i2v"));
+            })
+            .unwrap();
+
+        let expected = manager.get_state().unwrap();
+        let mut loaded = SequenceManager::from_bytes(&manager.save()).unwrap();
+        assert_eq!(loaded.get_state().unwrap(), expected);
+        assert_eq!(expected.generations["gen-3"].prompt, "edited");
+        assert!(!expected.generations.contains_key("gen-7"));
+
+        // A no-op update writes nothing
+        let heads = manager.get_heads();
+        manager.update_state(|_| {}).unwrap();
+        assert_eq!(manager.get_heads(), heads);
+    }
+}
@@ -0,0 +1,257 @@
+//! Versioned save envelope.
+//!
+//! Bare Automerge bytes, gzip and AES-GCM output look nothing alike on the
+//! wire, but only the first two can be told apart by sniffing. The envelope
+//! written by `save_enveloped()` prefixes the payload with a small header:
+//!
+//! ```text
+//! "HCDF" | u8 format version | u8 flags | payload
+//! ```
+//!
+//! Flags: bit 0 = gzip-compressed, bit 1 = encrypted (applied after
+//! compression). `from_bytes()` and `from_encrypted_bytes()` read enveloped
+//! and bare input alike, so documents saved before the envelope keep
+//! loading. `detect_format()` reports what a blob holds without decoding it.
+
+use std::borrow::Cow;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "compression")]
+use crate::compression;
+#[cfg(feature = "crypto")]
+use crate::crypto::{self, KeyParams};
+use crate::error::{CollabError, CollabResult};
+
+const MAGIC: &[u8; 4] = b"HCDF";
+const HEADER_LEN: usize = MAGIC.len() + 2;
+const FLAG_COMPRESSED: u8 = 0b01;
+const FLAG_ENCRYPTED: u8 = 0b10;
+const AUTOMERGE_MAGIC: &[u8; 4] = &[0x85, 0x6f, 0x4a, 0x83];
+
+/// Envelope format version written by this crate.
+pub const ENVELOPE_VERSION: u8 = 1;
+
+/// What a saved blob holds, as reported by `detect_format()`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "format", rename_all = "lowercase")]
+pub enum SaveFormat {
+    /// Bare Automerge document (`save()`).
+    Automerge,
+    /// Bare gzip (`save_compressed()`).
+    Gzip,
+    /// Enveloped payload (`save_enveloped()`).
+    Envelope {
+        version: u8,
+        compressed: bool,
+        encrypted: bool,
+    },
+    /// Anything else, including bare `save_encrypted()` output (which
+    /// starts with a random IV and cannot be recognized).
+    Unknown,
+}
+
+/// Sniffs the format of saved bytes.
+pub fn detect_format(bytes: &[u8]) -> SaveFormat {
+    if bytes.len() >= HEADER_LEN && bytes.starts_with(MAGIC) {
+        let flags = bytes[MAGIC.len() + 1];
+        SaveFormat::Envelope {
+            version: bytes[MAGIC.len()],
+            compressed: flags & FLAG_COMPRESSED != 0,
+            encrypted: flags & FLAG_ENCRYPTED != 0,
+        }
+    } else if bytes.starts_with(AUTOMERGE_MAGIC) {
+        SaveFormat::Automerge
+    } else if bytes.starts_with(&[0x1f, 0x8b]) {
+        SaveFormat::Gzip
+    } else {
+        SaveFormat::Unknown
+    }
+}
+
+/// How `save_enveloped()` encodes the document.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SaveOptions {
+    /// Gzip level (0-9); None stores the document uncompressed. Requires
+    /// the `compression` feature.
+    pub compression_level: Option<u32>,
+    /// Encrypts the payload with these key params (see `crate::crypto`).
+    #[cfg(feature = "crypto")]
+    pub key_params: Option<KeyParams>,
+}
+
+/// Wraps saved Automerge bytes in an envelope according to `options`.
+pub(crate) fn encode(doc: &[u8], options: &SaveOptions) -> CollabResult<Vec<u8>> {
+    let (flags, payload) = match options.compression_level {
+        Some(level) => (FLAG_COMPRESSED, compress(doc, level)?),
+        None => (0, doc.to_vec()),
+    };
+    #[cfg(feature = "crypto")]
+    let (flags, payload) = match &options.key_params {
+        Some(key_params) => (flags | FLAG_ENCRYPTED, crypto::encrypt(&payload, key_params)?),
+        None => (flags, payload),
+    };
+    Ok(wrap(flags, &payload))
+}
+
+/// Returns the bare Automerge bytes of unencrypted input: enveloped, gzip
+/// (with the `compression` feature) or bare. Encrypted envelopes are
+/// rejected; they go through `decrypt()` first.
+pub(crate) fn decode(bytes: &[u8]) -> CollabResult<Cow<'_, [u8]>> {
+    match detect_format(bytes) {
+        SaveFormat::Envelope {
+            version,
+            compressed,
+            encrypted,
+        } => {
+            if version > ENVELOPE_VERSION {
+                return Err(CollabError::serialization(format!(
+                    "Unsupported envelope version {}",
+                    version
+                )));
+            }
+            if encrypted {
+                return Err(CollabError::crypto(
+                    "Document is encrypted; load it with from_encrypted_bytes()",
+                ));
+            }
+            let payload = &bytes[HEADER_LEN..];
+            if compressed {
+                decompress(payload).map(Cow::Owned)
+            } else {
+                Ok(Cow::Borrowed(payload))
+            }
+        }
+        #[cfg(feature = "compression")]
+        SaveFormat::Gzip => decompress(bytes).map(Cow::Owned),
+        _ => Ok(Cow::Borrowed(bytes)),
+    }
+}
+
+/// Decrypts an encrypted envelope into an unencrypted one, or bare
+/// `save_encrypted()` output into the saved bytes. Either result goes to
+/// `decode()`.
+#[cfg(feature = "crypto")]
+pub(crate) fn decrypt(bytes: &[u8], key_params: &KeyParams) -> CollabResult<Vec<u8>> {
+    if let SaveFormat::Envelope {
+        encrypted: true, ..
+    } = detect_format(bytes)
+    {
+        let flags = bytes[MAGIC.len() + 1] & !FLAG_ENCRYPTED;
+        // A bare payload whose random IV happens to start with the magic
+        // fails here and is retried below
+        if let Ok(plain) = crypto::decrypt(&bytes[HEADER_LEN..], key_params) {
+            let mut out = wrap(flags, &plain);
+            out[MAGIC.len()] = bytes[MAGIC.len()];
+            return Ok(out);
+        }
+    }
+    crypto::decrypt(bytes, key_params)
+}
+
+fn wrap(flags: u8, payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(HEADER_LEN + payload.len());
+    out.extend_from_slice(MAGIC);
+    out.push(ENVELOPE_VERSION);
+    out.push(flags);
+    out.extend_from_slice(payload);
+    out
+}
+
+fn compress(doc: &[u8], level: u32) -> CollabResult<Vec<u8>> {
+    #[cfg(feature = "compression")]
+    {
+        Ok(compression::compress(doc, level))
+    }
+    #[cfg(not(feature = "compression"))]
+    {
+        let _ = (doc, level);
+        Err(CollabError::serialization(
+            "Compression requested but the compression feature is disabled",
+        ))
+    }
+}
+
+fn decompress(payload: &[u8]) -> CollabResult<Vec<u8>> {
+    #[cfg(feature = "compression")]
+    {
+        compression::maybe_decompress(payload)
+            .map(Cow::into_owned)
+            .map_err(|e| CollabError::serialization(format!("Decompression failed: {}", e)))
+    }
+    #[cfg(not(feature = "compression"))]
+    {
+        let _ = payload;
+        Err(CollabError::serialization(
+            "Document is compressed but the compression feature is disabled",
+        ))
+    }
+}
+
+/// WASM binding for `detect_format()`. Returns `{ format, ... }`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen::prelude::wasm_bindgen(js_name = detectFormat)]
+pub fn js_detect_format(bytes: &[u8]) -> Result<wasm_bindgen::JsValue, wasm_bindgen::JsValue> {
+    Ok(serde_wasm_bindgen::to_value(&detect_format(bytes))?)
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequence::{GenerationNode, SequenceManager};
+
+    #[test]
+    fn test_detect_format() {
+        let mut manager = SequenceManager::new();
+        manager.create_and_append("a", GenerationNode::new("a", "t2i")).unwrap();
+        let bare = manager.save();
+        assert_eq!(detect_format(&bare), SaveFormat::Automerge);
+        assert_eq!(detect_format(&[0x1f, 0x8b, 0x08]), SaveFormat::Gzip);
+        assert_eq!(detect_format(b"HCD"), SaveFormat::Unknown);
+
+        let enveloped = manager.save_enveloped(&SaveOptions::default()).unwrap();
+        assert_eq!(
+            detect_format(&enveloped),
+            SaveFormat::Envelope {
+                version: ENVELOPE_VERSION,
+                compressed: false,
+                encrypted: false,
+            }
+        );
+        assert_eq!(decode(&enveloped).unwrap(), &bare[..]);
+        assert_eq!(decode(&bare).unwrap(), &bare[..]);
+
+        let mut future = enveloped.clone();
+        future[MAGIC.len()] = ENVELOPE_VERSION + 1;
+        assert!(decode(&future).is_err());
+    }
+
+    #[cfg(all(feature = "compression", feature = "crypto"))]
+    #[test]
+    fn test_compressed_encrypted_envelope() {
+        let mut manager = SequenceManager::new();
+        manager.create_and_append("a", GenerationNode::new("a", "t2i")).unwrap();
+        let key_params = KeyParams::new("owner@example.com", 1700000000000);
+        let options = SaveOptions {
+            compression_level: Some(compression::DEFAULT_LEVEL),
+            key_params: Some(key_params.clone()),
+        };
+        let bytes = manager.save_enveloped(&options).unwrap();
+        assert!(matches!(
+            detect_format(&bytes),
+            SaveFormat::Envelope {
+                compressed: true,
+                encrypted: true,
+                ..
+            }
+        ));
+        assert!(SequenceManager::from_bytes(&bytes).is_err());
+        let mut loaded = SequenceManager::from_encrypted_bytes(&bytes, &key_params).unwrap();
+        assert!(loaded.get_node("a").unwrap().is_some());
+    }
+}
@@ -51,6 +51,58 @@ pub enum CollabError {
     /// Serialization/deserialization error.
     #[error("Serialization error: {0}")]
     Serialization(String),
+
+    /// Comment not found in a thread.
+    #[error("Comment not found: {0}")]
+    CommentNotFound(String),
+
+    /// Settings preset not found in the document.
+    #[error("Preset not found: {0}")]
+    PresetNotFound(String),
+
+    /// Named snapshot not found in the document.
+    #[error("Snapshot not found: {0}")]
+    SnapshotNotFound(String),
+
+    /// Setting value rejected by `SettingsLimits`.
+    #[error("Invalid setting '{field}': {reason}")]
+    InvalidSetting { field: String, reason: String },
+
+    /// Encryption or decryption failure (bad key, tampered or truncated data).
+    #[error("Crypto error: {0}")]
+    Crypto(String),
+
+    /// Document store failure (I/O error in a `DocumentStore` backend).
+    #[error("Storage error: {0}")]
+    Storage(String),
+
+    /// Document ID that cannot be used as a storage key.
+    #[error("Invalid document ID: {0}")]
+    InvalidDocId(String),
+
+    /// Change hash that is not 64 hex characters.
+    #[error("Invalid change hash: {0}")]
+    InvalidChangeHash(String),
+
+    /// Node inputs that depend on each other in a loop.
+    #[error("Dependency cycle: {0}")]
+    DependencyCycle(String),
+
+    /// Status change not allowed by the manager's `StatusTransitions`.
+    #[error("Illegal status transition: {from} -> {to}")]
+    IllegalTransition { from: String, to: String },
+
+    /// Settings change on a completed node while `freeze_completed` is on.
+    #[error("Node is frozen: {0}")]
+    FrozenNode(String),
+
+    /// Local edit not allowed by the document's ACL.
+    #[error("Permission denied: {user} may not {action}")]
+    PermissionDenied { user: String, action: String },
+
+    /// Local edit on a document frozen with `freeze()`.
+    #[error("Document is frozen")]
+    DocumentFrozen,
 }
 
 impl CollabError {
@@ -92,4 +144,78 @@ impl CollabError {
     pub fn serialization(msg: impl Into<String>) -> Self {
         Self::Serialization(msg.into())
     }
+
+    /// Creates a CommentNotFound error.
+    pub fn comment_not_found(id: impl Into<String>) -> Self {
+        Self::CommentNotFound(id.into())
+    }
+
+    /// Creates a PresetNotFound error.
+    pub fn preset_not_found(id: impl Into<String>) -> Self {
+        Self::PresetNotFound(id.into())
+    }
+
+    /// Creates a SnapshotNotFound error.
+    pub fn snapshot_not_found(name: impl Into<String>) -> Self {
+        Self::SnapshotNotFound(name.into())
+    }
+
+    /// Creates an InvalidSetting error.
+    pub fn invalid_setting(field: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self::InvalidSetting {
+            field: field.into(),
+            reason: reason.into(),
+        }
+    }
+
+    /// Creates a Crypto error.
+    pub fn crypto(msg: impl Into<String>) -> Self {
+        Self::Crypto(msg.into())
+    }
+
+    /// Creates a Storage error.
+    pub fn storage(msg: impl Into<String>) -> Self {
+        Self::Storage(msg.into())
+    }
+
+    /// Creates an InvalidDocId error.
+    pub fn invalid_doc_id(id: impl Into<String>) -> Self {
+        Self::InvalidDocId(id.into())
+    }
+
+    /// Creates an InvalidChangeHash error.
+    pub fn invalid_change_hash(hash: impl Into<String>) -> Self {
+        Self::InvalidChangeHash(hash.into())
+    }
+
+    /// Creates a DependencyCycle error.
+    pub fn dependency_cycle(msg: impl Into<String>) -> Self {
+        Self::DependencyCycle(msg.into())
+    }
+
+    /// Creates an IllegalTransition error.
+    pub fn illegal_transition(from: impl Into<String>, to: impl Into<String>) -> Self {
+        Self::IllegalTransition {
+            from: from.into(),
+            to: to.into(),
+        }
+    }
+
+    /// Creates a FrozenNode error.
+    pub fn frozen_node(id: impl Into<String>) -> Self {
+        Self::FrozenNode(id.into())
+    }
+
+    /// Creates a PermissionDenied error.
+    pub fn permission_denied(user: impl Into<String>, action: impl Into<String>) -> Self {
+        Self::PermissionDenied {
+            user: user.into(),
+            action: action.into(),
+        }
+    }
+
+    /// Creates a DocumentFrozen error.
+    pub fn document_frozen() -> Self {
+        Self::DocumentFrozen
+    }
 }
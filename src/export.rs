@@ -0,0 +1,258 @@
+//! Timeline export for editing software.
+//!
+//! `to_otio()` and `to_edl()` turn the timeline placements of a sequence
+//! (see `TimelinePlacement`) into OpenTimelineIO JSON and a CMX 3600 EDL, so
+//! a cut can be finished in Resolve or Premiere. Each clip references the
+//! node's selected output (or its first one) by URL.
+//!
+//! Times are converted from milliseconds to frames at the given rate. Tracks
+//! whose name starts with "A" are exported as audio, all others as video.
+//! Neither format allows clips on one track to overlap; where placements
+//! overlap (e.g. after concurrent moves), the later clip's head is trimmed
+//! to the end of the earlier one and fully covered clips are dropped.
+//! Archived nodes are skipped.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use serde_json::{json, Value};
+
+use crate::sequence::{DocumentRoot, GenerationNode};
+
+/// A clip ready for export, in frames.
+struct Clip<'a> {
+    node: &'a GenerationNode,
+    url: Option<&'a str>,
+    start: i64,
+    duration: i64,
+    source_start: i64,
+}
+
+impl Clip<'_> {
+    fn name(&self) -> &str {
+        if self.node.title.is_empty() {
+            &self.node.id
+        } else {
+            &self.node.title
+        }
+    }
+
+    fn end(&self) -> i64 {
+        self.start + self.duration
+    }
+}
+
+/// Converts the timeline of `root` to an OpenTimelineIO `Timeline.1` object.
+pub fn to_otio(root: &DocumentRoot, name: &str, fps: u32) -> Value {
+    let rate = fps as f64;
+    let time = |frames: i64| {
+        json!({ "OTIO_SCHEMA": "RationalTime.1", "rate": rate, "value": frames as f64 })
+    };
+    let range = |start: i64, duration: i64| {
+        json!({
+            "OTIO_SCHEMA": "TimeRange.1",
+            "start_time": time(start),
+            "duration": time(duration),
+        })
+    };
+
+    let tracks: Vec<Value> = collect_tracks(root, fps)
+        .into_iter()
+        .map(|(track, clips)| {
+            let mut children = Vec::new();
+            let mut position = 0;
+            for clip in clips {
+                if clip.start > position {
+                    children.push(json!({
+                        "OTIO_SCHEMA": "Gap.1",
+                        "name": "",
+                        "source_range": range(0, clip.start - position),
+                    }));
+                }
+                let reference = match clip.url {
+                    Some(url) => json!({ "OTIO_SCHEMA": "ExternalReference.1", "target_url": url }),
+                    None => json!({ "OTIO_SCHEMA": "MissingReference.1" }),
+                };
+                children.push(json!({
+                    "OTIO_SCHEMA": "Clip.2",
+                    "name": clip.name(),
+                    "source_range": range(clip.source_start, clip.duration),
+                    "media_references": { "DEFAULT_MEDIA": reference },
+                    "active_media_reference_key": "DEFAULT_MEDIA",
+                    "metadata": { "heyocollab": { "node_id": clip.node.id } },
+                }));
+                position = clip.end();
+            }
+            let kind = if is_audio(track) { "Audio" } else { "Video" };
+            json!({
+                "OTIO_SCHEMA": "Track.1",
+                "name": track,
+                "kind": kind,
+                "children": children,
+            })
+        })
+        .collect();
+
+    json!({
+        "OTIO_SCHEMA": "Timeline.1",
+        "name": name,
+        "global_start_time": null,
+        "tracks": { "OTIO_SCHEMA": "Stack.1", "name": "tracks", "children": tracks },
+    })
+}
+
+/// Converts the timeline of `root` to a CMX 3600 EDL (non-drop frame),
+/// events ordered by record-in across all tracks.
+pub fn to_edl(root: &DocumentRoot, title: &str, fps: u32) -> String {
+    let mut events: Vec<(&str, Clip)> = collect_tracks(root, fps)
+        .into_iter()
+        .flat_map(|(track, clips)| clips.into_iter().map(move |clip| (track, clip)))
+        .collect();
+    events.sort_by(|(a_track, a), (b_track, b)| {
+        a.start.cmp(&b.start).then_with(|| a_track.cmp(b_track))
+    });
+
+    let mut out = format!("TITLE: {}\nFCM: NON-DROP FRAME\n\n", title);
+    for (number, (track, clip)) in events.iter().enumerate() {
+        let channel = if is_audio(track) { "A" } else { "V" };
+        // Writing to a String cannot fail
+        let _ = writeln!(
+            out,
+            "{:03}  AX       {:<4} C        {} {} {} {}",
+            number + 1,
+            channel,
+            timecode(clip.source_start, fps),
+            timecode(clip.source_start + clip.duration, fps),
+            timecode(clip.start, fps),
+            timecode(clip.end(), fps),
+        );
+        let _ = writeln!(out, "* FROM CLIP NAME: {}", clip.name());
+        if let Some(url) = clip.url {
+            let _ = writeln!(out, "* SOURCE FILE: {}", url);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Groups placed nodes by track, sorted by start, with overlaps resolved.
+fn collect_tracks(root: &DocumentRoot, fps: u32) -> BTreeMap<&str, Vec<Clip<'_>>> {
+    let frames = |ms: i64| (ms * fps as i64 + 500).div_euclid(1000);
+    let mut tracks: BTreeMap<&str, Vec<Clip>> = BTreeMap::new();
+    for node in root.generations.values().filter(|node| !node.archived) {
+        let Some(placement) = &node.timeline else {
+            continue;
+        };
+        let url = node
+            .outputs
+            .iter()
+            .find(|output| output.is_selected)
+            .or_else(|| node.outputs.first())
+            .map(|output| output.url.as_str());
+        let start = frames(placement.start_ms);
+        tracks.entry(placement.track.as_str()).or_default().push(Clip {
+            node,
+            url,
+            start,
+            duration: frames(placement.end_ms()) - start,
+            source_start: frames(placement.offset_ms),
+        });
+    }
+
+    for clips in tracks.values_mut() {
+        clips.sort_by(|a, b| a.start.cmp(&b.start).then_with(|| a.node.id.cmp(&b.node.id)));
+        let mut position = i64::MIN;
+        clips.retain_mut(|clip| {
+            if clip.start < position {
+                let trim = position - clip.start;
+                clip.start = position;
+                clip.source_start += trim;
+                clip.duration -= trim;
+            }
+            if clip.duration <= 0 {
+                return false;
+            }
+            position = clip.end();
+            true
+        });
+    }
+    tracks
+}
+
+fn is_audio(track: &str) -> bool {
+    track.starts_with('A')
+}
+
+/// Formats a frame count as `HH:MM:SS:FF`.
+fn timecode(frames: i64, fps: u32) -> String {
+    let fps = fps.max(1) as i64;
+    let seconds = frames / fps;
+    format!(
+        "{:02}:{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        frames % fps
+    )
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequence::{OutputAsset, TimelinePlacement};
+
+    fn root() -> DocumentRoot {
+        let mut root = DocumentRoot::new();
+        let mut add = |id: &str, placement: TimelinePlacement, url: Option<&str>| {
+            let mut node = GenerationNode::new(id, "i2v");
+            node.timeline = Some(placement);
+            node.outputs.extend(url.map(OutputAsset::new));
+            root.generations.insert(id.to_string(), node);
+        };
+        add("a", TimelinePlacement::new("V1", 1000, 2000), Some("https://cdn/a.mp4"));
+        add("b", TimelinePlacement::new("V1", 2500, 1000), None);
+        add("m", TimelinePlacement::new("A1", 0, 500), Some("https://cdn/m.wav"));
+        root
+    }
+
+    #[test]
+    fn test_otio_tracks_gaps_and_overlaps() {
+        let otio = to_otio(&root(), "Cut 1", 24);
+        let tracks = otio["tracks"]["children"].as_array().unwrap();
+        assert_eq!(tracks[0]["name"], "A1");
+        assert_eq!(tracks[0]["kind"], "Audio");
+
+        let v1 = tracks[1]["children"].as_array().unwrap();
+        let schemas: Vec<&str> = v1.iter().map(|c| c["OTIO_SCHEMA"].as_str().unwrap()).collect();
+        assert_eq!(schemas, vec!["Gap.1", "Clip.2", "Clip.2"]);
+        assert_eq!(v1[0]["source_range"]["duration"]["value"], 24.0);
+        assert_eq!(
+            v1[1]["media_references"]["DEFAULT_MEDIA"]["target_url"],
+            "https://cdn/a.mp4"
+        );
+        // "b" overlaps "a" by 500 ms and is trimmed at the head
+        assert_eq!(v1[2]["source_range"]["start_time"]["value"], 12.0);
+        assert_eq!(v1[2]["source_range"]["duration"]["value"], 12.0);
+        assert_eq!(v1[2]["media_references"]["DEFAULT_MEDIA"]["OTIO_SCHEMA"], "MissingReference.1");
+    }
+
+    #[test]
+    fn test_edl_events() {
+        let edl = to_edl(&root(), "Cut 1", 24);
+        let events: Vec<&str> = edl.lines().filter(|line| line.starts_with('0')).collect();
+        assert_eq!(
+            events,
+            vec![
+                "001  AX       A    C        00:00:00:00 00:00:00:12 00:00:00:00 00:00:00:12",
+                "002  AX       V    C        00:00:00:00 00:00:02:00 00:00:01:00 00:00:03:00",
+                "003  AX       V    C        00:00:00:12 00:00:01:00 00:00:03:00 00:00:03:12",
+            ]
+        );
+        assert!(edl.starts_with("TITLE: Cut 1\nFCM: NON-DROP FRAME\n"));
+        assert!(edl.contains("* SOURCE FILE: https://cdn/a.mp4"));
+    }
+}
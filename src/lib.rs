@@ -32,14 +32,68 @@
 //! let bytes = manager.save();
 //! ```
 
+pub mod acl;
+pub mod autosave;
+pub mod bundle;
+pub mod capabilities;
+pub mod changes;
+pub mod checkpoint;
+pub mod cherry_pick;
+pub mod chunk;
+pub mod comment;
+pub mod compact;
+pub mod conflict;
+pub mod delta;
+pub(crate) mod dirty;
+pub mod envelope;
 pub mod error;
+pub mod link;
+pub mod naming;
+pub mod offline;
+pub mod order_key;
+pub mod ordered_map;
+pub mod presence;
+pub mod raw;
+pub mod repair;
+pub mod sanitize;
+pub mod snapshot;
+pub mod squash;
+pub mod storage;
+pub mod sweep;
+pub mod sync;
+pub mod validation;
 
 // Sequence module
 pub mod sequence;
 
 // Re-exports for convenience
+pub use acl::{Acl, Action, Enforcement, PermissionContext, Role};
+pub use autosave::{AutosaveConfig, AutosaveTracker};
+pub use bundle::{Bundle, BundleMetadata};
+pub use capabilities::{capabilities, Capabilities};
+pub use changes::{ChangeInfo, FieldAuthor};
+pub use checkpoint::SyncCheckpoint;
+pub use cherry_pick::CherryPickReport;
+pub use chunk::ChunkAssembler;
+pub use comment::Comment;
+pub use compact::HistoryStats;
+pub use conflict::ConflictValue;
+pub use delta::{apply_delta, apply_deltas, delta_between};
+pub use envelope::{detect_format, SaveFormat, SaveOptions};
 pub use error::{CollabError, CollabResult};
-pub use sequence::{DocumentRoot, GenerationNode, GenerationSettings, OutputAsset, SequenceManager};
+pub use link::{DocRef, DocResolver};
+pub use offline::OfflineQueue;
+pub use ordered_map::OrderedMap;
+pub use presence::{Awareness, Cursor, PeerPresence};
+pub use raw::{RawFields, RawValue};
+pub use repair::{RepairAction, RepairKind, RepairReport};
+pub use snapshot::NamedSnapshot;
+pub use squash::{squash_history, SquashReport};
+pub use storage::{DocumentStore, FileStore};
+pub use sweep::{SweepConfig, SweepReport};
+pub use sync::{parse_change_hash, PeerState, PrioritizedFrames, SyncDocument};
+pub use validation::{Severity, ValidationIssue, ValidationReport};
+pub use sequence::{ActivityEvent, ActivityKind, DocumentRoot, DocumentSeed, GenerationNode, GenerationSettings, GenerationStatus, InputAsset, JobClaim, JobQueue, Keyframe, LoraRef, ManagerOptions, NodeFilter, NodeInputRef, NodeSummary, OutputAsset, SequenceManager, SettingValue, SettingsLimits, StatusTransitions, TimelinePlacement, VariationTree};
 
 #[cfg(feature = "wasm")]
 pub use sequence::JsSequenceManager;
@@ -53,3 +107,43 @@ pub use storyboard::{StoryboardManager, StoryboardRoot};
 
 #[cfg(all(feature = "wasm", feature = "storyboard"))]
 pub use storyboard::wasm::JsStoryboardManager;
+
+// Fountain screenplay import (only compiled when script-import feature enabled)
+#[cfg(feature = "script-import")]
+pub mod script;
+
+// External image blobs (only compiled when blob-store feature enabled)
+#[cfg(feature = "blob-store")]
+pub mod blob;
+
+#[cfg(feature = "blob-store")]
+pub use blob::{BlobStore, DedupReport, ExternalizeReport, FileBlobStore, MemoryBlobStore};
+
+// Encrypted snapshots (only compiled when crypto feature enabled)
+#[cfg(feature = "crypto")]
+pub mod crypto;
+
+#[cfg(feature = "crypto")]
+pub use crypto::KeyParams;
+
+// Full-text search (only compiled when search feature enabled)
+#[cfg(feature = "search")]
+pub mod search;
+
+#[cfg(feature = "search")]
+pub use search::SearchIndex;
+
+// OTIO/EDL timeline export (only compiled when export feature enabled)
+#[cfg(feature = "export")]
+pub mod export;
+
+// Compressed snapshots (only compiled when compression feature enabled)
+#[cfg(feature = "compression")]
+pub mod compression;
+
+// WebSocket sync client (only compiled when sync-client feature enabled)
+#[cfg(feature = "sync-client")]
+pub mod client;
+
+#[cfg(feature = "sync-client")]
+pub use client::{SyncClient, SyncClientConfig, SyncEvent};
@@ -0,0 +1,73 @@
+//! Links between sequence and storyboard documents.
+//!
+//! A shot's image usually comes from a generation node in a sequence
+//! document. `Shot::generation_ref` names that node and
+//! `GenerationNode::storyboard_ref` points back at the shot, each as a
+//! `DocRef` (document ID plus the node or shot ID inside it).
+//!
+//! The crate never loads other documents itself: apps pass a `DocResolver`
+//! to `SequenceManager::resolve_storyboard_ref()` or
+//! `StoryboardManager::resolve_generation_ref()`, which looks the target up
+//! however the app stores documents. Any
+//! `FnMut(&DocRef) -> CollabResult<Option<T>>` closure is a resolver.
+
+use autosurgeon::{Hydrate, Reconcile};
+use serde::{Deserialize, Serialize};
+
+use crate::error::CollabResult;
+
+/// A node or shot in another document.
+#[derive(Debug, Clone, Default, Reconcile, Hydrate, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct DocRef {
+    /// ID of the document, as the app stores it.
+    pub doc_id: String,
+    /// ID of the generation node or shot within that document.
+    pub node_id: String,
+}
+
+impl DocRef {
+    /// Creates a reference to `node_id` in document `doc_id`.
+    pub fn new(doc_id: impl Into<String>, node_id: impl Into<String>) -> Self {
+        Self {
+            doc_id: doc_id.into(),
+            node_id: node_id.into(),
+        }
+    }
+}
+
+/// Looks up the target of a `DocRef`.
+pub trait DocResolver<T> {
+    /// Returns the referenced item, or None if the document or the item in
+    /// it no longer exists.
+    fn resolve(&mut self, reference: &DocRef) -> CollabResult<Option<T>>;
+}
+
+impl<T, F> DocResolver<T> for F
+where
+    F: FnMut(&DocRef) -> CollabResult<Option<T>>,
+{
+    fn resolve(&mut self, reference: &DocRef) -> CollabResult<Option<T>> {
+        self(reference)
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_closure_resolver() {
+        let mut calls = 0;
+        let mut resolver = |r: &DocRef| {
+            calls += 1;
+            Ok((r.doc_id == "seq-1").then(|| r.node_id.clone()))
+        };
+        assert_eq!(resolver.resolve(&DocRef::new("seq-1", "gen-1")).unwrap().as_deref(), Some("gen-1"));
+        assert_eq!(resolver.resolve(&DocRef::new("seq-2", "gen-1")).unwrap(), None);
+        assert_eq!(calls, 2);
+    }
+}
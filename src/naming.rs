@@ -0,0 +1,156 @@
+//! Cross-platform, path-safe file naming.
+//!
+//! Shared by the CLIs and storage backends so a document title produces the
+//! same file name everywhere. Names are safe on Linux, macOS and Windows: no
+//! path separators or control characters, no Windows reserved device names,
+//! no trailing dots or spaces, and stems are capped at `MAX_STEM_BYTES`.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Maximum stem length in bytes (leaves room for timestamps and extensions
+/// under the common 255-byte file name limit).
+pub const MAX_STEM_BYTES: usize = 120;
+
+/// Stem used when sanitizing leaves nothing.
+pub const FALLBACK_STEM: &str = "untitled";
+
+/// Windows device names that cannot be used as a file stem (case-insensitive).
+const WINDOWS_RESERVED: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Returns true if `stem` is a Windows reserved device name.
+pub fn is_windows_reserved(stem: &str) -> bool {
+    WINDOWS_RESERVED
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(stem))
+}
+
+/// Makes an arbitrary string safe to use as a file stem, preserving case.
+///
+/// Unicode letters and digits, `-` and `_` are kept; whitespace and other
+/// separators become `-`; everything else is dropped. Runs of `-` collapse
+/// and leading/trailing `-`/`_` are trimmed.
+pub fn sanitize_file_stem(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for c in input.chars() {
+        let mapped = if c.is_alphanumeric() || c == '_' {
+            Some(c)
+        } else if c == '-' || c.is_whitespace() || matches!(c, '.' | '/' | '\\' | ':') {
+            Some('-')
+        } else {
+            None
+        };
+        if let Some(c) = mapped {
+            if c == '-' && out.ends_with('-') {
+                continue;
+            }
+            out.push(c);
+        }
+    }
+
+    let trimmed = out.trim_matches(['-', '_']);
+    let mut stem = truncate_bytes(trimmed, MAX_STEM_BYTES)
+        .trim_end_matches(['-', '_'])
+        .to_string();
+
+    if stem.is_empty() {
+        stem = FALLBACK_STEM.to_string();
+    }
+    if is_windows_reserved(&stem) {
+        stem.push('_');
+    }
+    stem
+}
+
+/// Sanitizes a document title for use in file names (lowercased).
+pub fn sanitize_title(title: &str) -> String {
+    sanitize_file_stem(&title.to_lowercase())
+}
+
+/// Builds `<sanitized stem>.<extension>`.
+pub fn file_name(stem: &str, extension: &str) -> String {
+    format!("{}.{}", sanitize_file_stem(stem), extension)
+}
+
+/// Current time as milliseconds since the Unix epoch, as a string.
+pub fn timestamp_millis() -> String {
+    let duration = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    duration.as_millis().to_string()
+}
+
+/// Builds `<sanitized title>_<millis>.<extension>` for the given timestamp.
+pub fn timestamped_file_name_at(title: &str, millis: u128, extension: &str) -> String {
+    format!("{}_{}.{}", sanitize_title(title), millis, extension)
+}
+
+/// Builds `<sanitized title>_<now millis>.<extension>`.
+pub fn timestamped_file_name(title: &str, extension: &str) -> String {
+    format!("{}_{}.{}", sanitize_title(title), timestamp_millis(), extension)
+}
+
+/// Truncates to at most `max` bytes without splitting a character.
+fn truncate_bytes(s: &str, max: usize) -> &str {
+    if s.len() <= max {
+        return s;
+    }
+    let mut end = max;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_basic() {
+        assert_eq!(sanitize_title("My Film: Part 2/3"), "my-film-part-2-3");
+        assert_eq!(sanitize_title("  --hello__  "), "hello");
+        assert_eq!(sanitize_title("a   b...c"), "a-b-c");
+        assert_eq!(sanitize_file_stem("Keep_Case"), "Keep_Case");
+        assert_eq!(sanitize_title("<>|?*\""), FALLBACK_STEM);
+        assert_eq!(sanitize_title(""), FALLBACK_STEM);
+    }
+
+    #[test]
+    fn test_sanitize_unicode() {
+        assert_eq!(sanitize_title("Ünïcödé Film"), "ünïcödé-film");
+        assert_eq!(sanitize_title("東京 物語"), "東京-物語");
+        assert_eq!(sanitize_title("emoji 🎬 scene"), "emoji-scene");
+    }
+
+    #[test]
+    fn test_windows_reserved_names() {
+        assert_eq!(sanitize_title("CON"), "con_");
+        assert_eq!(sanitize_title("lpt1"), "lpt1_");
+        assert_eq!(sanitize_title("console"), "console");
+        assert_eq!(file_name("nul", "automerge"), "nul_.automerge");
+    }
+
+    #[test]
+    fn test_length_limit() {
+        let long = "é".repeat(200); // 2 bytes each
+        let stem = sanitize_title(&long);
+        assert!(stem.len() <= MAX_STEM_BYTES);
+        assert_eq!(stem.chars().count(), MAX_STEM_BYTES / 2);
+    }
+
+    #[test]
+    fn test_timestamped_file_name() {
+        assert_eq!(
+            timestamped_file_name_at("My Board", 1700000000000, "automerge"),
+            "my-board_1700000000000.automerge"
+        );
+        assert!(timestamped_file_name("x", "bin").starts_with("x_"));
+    }
+}
@@ -0,0 +1,325 @@
+//! Offline change queue.
+//!
+//! `OfflineQueue` holds local edits while the connection is down and sends
+//! them once it is back. It never stores change bytes of its own: pending
+//! edits live in the document (and in its attached store, which `queue()`
+//! flushes), and the queue only tracks the `SyncCheckpoint` the server last
+//! acknowledged. A flush therefore sends every unacknowledged change exactly
+//! once, however many edits piled up, and changes the server already has
+//! (e.g. acknowledged just before the connection dropped) are never resent.
+//!
+//! Persist `checkpoint()` next to the cached document to keep the queue
+//! across page reloads:
+//!
+//! ```js
+//! const queue = new OfflineQueue(JSON.parse(localStorage.getItem(key) ?? 'null'));
+//! ws.onclose = () => queue.setOnline(false);
+//! ws.onopen = () => {
+//!   queue.setOnline(true);
+//!   const bytes = queue.flush(manager);
+//!   if (bytes) ws.send(bytes);
+//! };
+//! ws.onmessage = (e) => {
+//!   if (isAck(e)) {
+//!     queue.acknowledge(null);
+//!     localStorage.setItem(key, JSON.stringify(queue.checkpoint()));
+//!   }
+//! };
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::checkpoint::SyncCheckpoint;
+use crate::error::CollabResult;
+use crate::sequence::SequenceManager;
+#[cfg(feature = "storyboard")]
+use crate::storyboard::StoryboardManager;
+
+/// The manager operations the queue needs.
+pub trait QueueDocument {
+    /// See `SequenceManager::changes_since_checkpoint()`.
+    fn changes_since_checkpoint(&mut self, checkpoint: &SyncCheckpoint) -> Vec<u8>;
+
+    /// See `SequenceManager::mark_synced()`.
+    fn mark_synced(&mut self) -> SyncCheckpoint;
+
+    /// See `SequenceManager::flush_store()`.
+    fn flush_store(&mut self) -> CollabResult<usize>;
+}
+
+macro_rules! impl_queue_document {
+    ($manager:ty) => {
+        impl QueueDocument for $manager {
+            fn changes_since_checkpoint(&mut self, checkpoint: &SyncCheckpoint) -> Vec<u8> {
+                <$manager>::changes_since_checkpoint(self, checkpoint)
+            }
+
+            fn mark_synced(&mut self) -> SyncCheckpoint {
+                <$manager>::mark_synced(self)
+            }
+
+            fn flush_store(&mut self) -> CollabResult<usize> {
+                <$manager>::flush_store(self)
+            }
+        }
+    };
+}
+
+impl_queue_document!(SequenceManager);
+#[cfg(feature = "storyboard")]
+impl_queue_document!(StoryboardManager);
+
+/// Serializable queue state (see `OfflineQueue::checkpoint()`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct OfflineQueue {
+    /// Heads the server has acknowledged.
+    acked: SyncCheckpoint,
+    /// Heads covered by the last flush, until it is acknowledged.
+    #[serde(skip)]
+    in_flight: Option<SyncCheckpoint>,
+    /// Whether flushes may send. Starts offline.
+    #[serde(skip)]
+    online: bool,
+}
+
+impl OfflineQueue {
+    /// Creates an offline queue that resumes from `acked` (the default
+    /// checkpoint if nothing was ever acknowledged).
+    pub fn new(acked: SyncCheckpoint) -> Self {
+        Self {
+            acked,
+            in_flight: None,
+            online: false,
+        }
+    }
+
+    /// Returns the last acknowledged checkpoint; persist it to resume later.
+    pub fn checkpoint(&self) -> &SyncCheckpoint {
+        &self.acked
+    }
+
+    /// Returns true if flushes may send.
+    pub fn is_online(&self) -> bool {
+        self.online
+    }
+
+    /// Switches connectivity. Going offline forgets the in-flight flush, so
+    /// its changes are sent again on reconnect.
+    pub fn set_online(&mut self, online: bool) {
+        self.online = online;
+        if !online {
+            self.in_flight = None;
+        }
+    }
+
+    /// Returns true if `doc` has changes the server has not acknowledged.
+    pub fn has_pending(&self, doc: &mut impl QueueDocument) -> bool {
+        !doc.changes_since_checkpoint(&self.acked).is_empty()
+    }
+
+    /// Persists pending local changes to the document's attached store so
+    /// they survive a reload while offline. Returns true if anything is
+    /// waiting to be sent.
+    pub fn queue(&mut self, doc: &mut impl QueueDocument) -> CollabResult<bool> {
+        doc.flush_store()?;
+        Ok(self.has_pending(doc))
+    }
+
+    /// Returns every unacknowledged change as one message, or None when
+    /// offline, when nothing is pending, or while a flush awaits its
+    /// acknowledgement.
+    pub fn flush(&mut self, doc: &mut impl QueueDocument) -> Option<Vec<u8>> {
+        if !self.online || self.in_flight.is_some() {
+            return None;
+        }
+        let next = doc.mark_synced();
+        let bytes = doc.changes_since_checkpoint(&self.acked);
+        if bytes.is_empty() {
+            return None;
+        }
+        self.in_flight = Some(next);
+        Some(bytes)
+    }
+
+    /// Records an acknowledgement. With `heads`, the server reported what
+    /// it has; without, the in-flight flush arrived. Either way the next
+    /// `flush()` may send again.
+    pub fn acknowledge(&mut self, heads: Option<SyncCheckpoint>) {
+        let in_flight = self.in_flight.take();
+        if let Some(acked) = heads.or(in_flight) {
+            self.acked = acked;
+        }
+    }
+}
+
+// =============================================================================
+// WASM BINDINGS
+// =============================================================================
+
+#[cfg(feature = "wasm")]
+mod wasm {
+    use js_sys::Uint8Array;
+    use serde_wasm_bindgen::{from_value, to_value};
+    use wasm_bindgen::prelude::*;
+
+    use super::{OfflineQueue, QueueDocument};
+    use crate::checkpoint::SyncCheckpoint;
+    use crate::sequence::JsSequenceManager;
+    #[cfg(feature = "storyboard")]
+    use crate::storyboard::wasm::JsStoryboardManager;
+
+    /// Offline change queue for either manager (see `crate::offline`).
+    #[wasm_bindgen(js_name = OfflineQueue)]
+    pub struct JsOfflineQueue {
+        inner: OfflineQueue,
+    }
+
+    #[wasm_bindgen(js_class = OfflineQueue)]
+    impl JsOfflineQueue {
+        /// Creates an offline queue.
+        ///
+        /// # Arguments
+        /// * `checkpoint` - `{ heads }` from a previous `checkpoint()`, or null
+        #[wasm_bindgen(constructor)]
+        pub fn new(checkpoint: JsValue) -> Result<JsOfflineQueue, JsValue> {
+            let checkpoint: Option<SyncCheckpoint> = from_value(checkpoint)?;
+            Ok(Self {
+                inner: OfflineQueue::new(checkpoint.unwrap_or_default()),
+            })
+        }
+
+        /// Returns the acknowledged `{ heads }`; persist it to resume later.
+        pub fn checkpoint(&self) -> Result<JsValue, JsValue> {
+            Ok(to_value(self.inner.checkpoint())?)
+        }
+
+        /// Returns true if flushes may send.
+        #[wasm_bindgen(js_name = isOnline)]
+        pub fn is_online(&self) -> bool {
+            self.inner.is_online()
+        }
+
+        /// Switches connectivity (call from the socket's open/close events).
+        #[wasm_bindgen(js_name = setOnline)]
+        pub fn set_online(&mut self, online: bool) {
+            self.inner.set_online(online);
+        }
+
+        /// Records an acknowledgement: the server's `{ heads }`, or null
+        /// when the last flush arrived.
+        pub fn acknowledge(&mut self, heads: JsValue) -> Result<(), JsValue> {
+            let heads: Option<SyncCheckpoint> = from_value(heads)?;
+            self.inner.acknowledge(heads);
+            Ok(())
+        }
+
+        /// Persists pending sequence edits; returns true if any await sending.
+        pub fn queue(&mut self, manager: &mut JsSequenceManager) -> Result<bool, JsValue> {
+            self.queue_manager(&mut manager.inner)
+        }
+
+        /// Returns unacknowledged sequence changes (Uint8Array), or undefined
+        /// if there is nothing to send right now.
+        pub fn flush(&mut self, manager: &mut JsSequenceManager) -> Option<Uint8Array> {
+            self.flush_manager(&mut manager.inner)
+        }
+
+        /// Persists pending storyboard edits; returns true if any await sending.
+        #[cfg(feature = "storyboard")]
+        #[wasm_bindgen(js_name = queueStoryboard)]
+        pub fn queue_storyboard(&mut self, manager: &mut JsStoryboardManager) -> Result<bool, JsValue> {
+            self.queue_manager(&mut manager.inner)
+        }
+
+        /// Returns unacknowledged storyboard changes, or undefined.
+        #[cfg(feature = "storyboard")]
+        #[wasm_bindgen(js_name = flushStoryboard)]
+        pub fn flush_storyboard(&mut self, manager: &mut JsStoryboardManager) -> Option<Uint8Array> {
+            self.flush_manager(&mut manager.inner)
+        }
+    }
+
+    impl JsOfflineQueue {
+        fn queue_manager(&mut self, manager: &mut impl QueueDocument) -> Result<bool, JsValue> {
+            self.inner.queue(manager).map_err(JsValue::from)
+        }
+
+        fn flush_manager(&mut self, manager: &mut impl QueueDocument) -> Option<Uint8Array> {
+            self.inner
+                .flush(manager)
+                .map(|bytes| Uint8Array::from(&bytes[..]))
+        }
+    }
+}
+
+#[cfg(feature = "wasm")]
+pub use wasm::JsOfflineQueue;
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequence::GenerationNode;
+
+    #[test]
+    fn test_offline_queue_flushes_once_on_reconnect() {
+        let mut client = SequenceManager::new();
+        let mut server = SequenceManager::from_bytes(&client.save()).unwrap();
+        let mut queue = OfflineQueue::new(client.mark_synced());
+
+        // Edits while offline accumulate but are not sent
+        for i in 0..3 {
+            let id = format!("gen-{}", i);
+            client.create_and_append(&id, GenerationNode::new(&id, "t2i")).unwrap();
+            assert!(queue.queue(&mut client).unwrap());
+        }
+        assert!(queue.flush(&mut client).is_none());
+
+        // Reconnect: one message with everything, nothing more until acked
+        queue.set_online(true);
+        let bytes = queue.flush(&mut client).unwrap();
+        assert!(queue.flush(&mut client).is_none());
+        server.load_incremental(&bytes).unwrap();
+        queue.acknowledge(None);
+        assert_eq!(server.get_state().unwrap(), client.get_state().unwrap());
+        assert!(!queue.has_pending(&mut client));
+        assert!(queue.flush(&mut client).is_none());
+
+        // The checkpoint survives a reload
+        let json = serde_json::to_string(&queue).unwrap();
+        let queue: OfflineQueue = serde_json::from_str(&json).unwrap();
+        assert!(!queue.is_online());
+        assert!(!queue.has_pending(&mut client));
+    }
+
+    #[test]
+    fn test_offline_queue_skips_acknowledged_changes() {
+        let mut client = SequenceManager::new();
+        let mut server = SequenceManager::from_bytes(&client.save()).unwrap();
+        let mut queue = OfflineQueue::new(client.mark_synced());
+        queue.set_online(true);
+
+        client.create_and_append("a", GenerationNode::new("a", "t2i")).unwrap();
+        let first = queue.flush(&mut client).unwrap();
+
+        // Connection drops before the ack; the in-flight batch is resent
+        queue.set_online(false);
+        client.create_and_append("b", GenerationNode::new("b", "t2i")).unwrap();
+        queue.set_online(true);
+        let resent = queue.flush(&mut client).unwrap();
+        assert!(resent.len() > first.len());
+
+        // The server reports it already had "a": only "b" goes out next time
+        queue.set_online(false);
+        server.load_incremental(&first).unwrap();
+        queue.acknowledge(Some(server.mark_synced()));
+        queue.set_online(true);
+        let rest = queue.flush(&mut client).unwrap();
+        assert!(rest.len() < resent.len());
+        server.load_incremental(&rest).unwrap();
+        assert_eq!(server.get_order(true).unwrap(), vec!["a", "b"]);
+    }
+}
@@ -0,0 +1,345 @@
+//! Fractional-index ordering shared by sequence and storyboard documents.
+//!
+//! By default `sequence_order` and `scene_order` are lists, and moving an
+//! item rewrites its position in the list. Two peers moving items at the
+//! same time can merge into duplicates or an order neither of them chose.
+//! A document with `fractional_order` set instead orders items by a string
+//! key stored on each item (`order_key`), compared byte-wise:
+//!
+//! ```text
+//! "V" < "j" < "jV" < "k"        key_between(Some("j"), Some("k")) == "jV"
+//! ```
+//!
+//! Moving an item writes only its own key, so concurrent moves of different
+//! items always merge, and concurrent moves of one item resolve to one of
+//! them. The order list still says which items are in the order; only the
+//! position of an ID in it stops mattering. Keys use the digits `0-9A-Za-z`
+//! and never end in `0`, so there is always room between two keys. Items
+//! with equal keys (concurrent inserts at one spot) sort by ID.
+//!
+//! `SequenceManager::set_fractional_order()` and
+//! `StoryboardManager::set_fractional_order()` switch a document either way:
+//! turning it on gives every item a key in the current list order, turning
+//! it off writes the key order back to the list.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::error::{CollabError, CollabResult};
+
+/// Key digits in ascending byte order.
+const DIGITS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// An item ordered by a fractional key when its document uses
+/// `fractional_order`.
+pub(crate) trait Keyed {
+    fn order_key(&self) -> Option<&str>;
+    fn set_order_key(&mut self, key: Option<String>);
+}
+
+/// A bare key, for sorting without hydrating the items.
+impl Keyed for Option<String> {
+    fn order_key(&self) -> Option<&str> {
+        self.as_deref()
+    }
+
+    fn set_order_key(&mut self, key: Option<String>) {
+        *self = key;
+    }
+}
+
+/// Returns true if `key` is a usable order key: non-empty, only key digits,
+/// and not ending in `0`.
+pub fn is_valid_key(key: &str) -> bool {
+    !key.ends_with('0') && !key.is_empty() && key.bytes().all(|b| DIGITS.contains(&b))
+}
+
+/// Returns a key that sorts after `before` and before `after` (None means
+/// no bound). Fails if a key is invalid or `before` does not sort before
+/// `after`.
+pub fn key_between(before: Option<&str>, after: Option<&str>) -> CollabResult<String> {
+    for key in before.into_iter().chain(after) {
+        if !is_valid_key(key) {
+            return Err(CollabError::schema_violation(format!("invalid order key '{}'", key)));
+        }
+    }
+    if let (Some(before), Some(after)) = (before, after) {
+        if before >= after {
+            return Err(CollabError::schema_violation(format!(
+                "order key '{}' does not sort before '{}'",
+                before, after
+            )));
+        }
+    }
+    Ok(midpoint(before.unwrap_or(""), after))
+}
+
+/// Returns the items of `list` sorted by key (ties by ID), followed by the
+/// IDs without a valid key in list order. Duplicate IDs are dropped.
+pub(crate) fn sorted<T: Keyed>(list: &[String], items: &HashMap<String, T>) -> Vec<String> {
+    let key_of = |id: &str| valid_key(items, id);
+    let mut seen = HashSet::new();
+    let (mut keyed, unkeyed): (Vec<&String>, Vec<&String>) = list
+        .iter()
+        .filter(|id| seen.insert(id.as_str()))
+        .partition(|id| key_of(id).is_some());
+    keyed.sort_by(|a, b| (key_of(a), a).cmp(&(key_of(b), b)));
+    keyed.into_iter().chain(unkeyed).cloned().collect()
+}
+
+/// Gives every item in `list` without a valid key one: right after the
+/// nearest keyed item before it in the list, or after all keyed items if
+/// none follows it in the list.
+pub(crate) fn fill<T: Keyed>(list: &[String], items: &mut HashMap<String, T>) {
+    let ids = unique_items(list, items);
+    let mut keys: Vec<&str> = ids.iter().filter_map(|id| valid_key(items, id)).collect();
+    keys.sort_unstable();
+
+    let mut assigned = Vec::new();
+    let mut anchor: Option<&str> = None;
+    let mut pending: Vec<&str> = Vec::new();
+    for &id in &ids {
+        let Some(key) = valid_key(items, id) else {
+            pending.push(id);
+            continue;
+        };
+        if !pending.is_empty() {
+            let next = anchor.map_or(0, |anchor| keys.partition_point(|k| *k <= anchor));
+            assign(&mut assigned, &pending, anchor, keys.get(next).copied());
+            pending.clear();
+        }
+        anchor = Some(key);
+    }
+    assign(&mut assigned, &pending, keys.last().copied(), None);
+    apply(items, assigned);
+}
+
+/// Keys the items so they sort as in `order`, keeping the keys of the
+/// longest run already in that order. Items missing from `order` keep
+/// their keys.
+pub(crate) fn reorder<T: Keyed>(items: &mut HashMap<String, T>, order: &[String]) {
+    let ids = unique_items(order, items);
+    let keys: Vec<Option<&str>> = ids.iter().map(|id| valid_key(items, id)).collect();
+    let keep = longest_increasing(&keys);
+
+    let mut assigned = Vec::new();
+    let mut lo: Option<&str> = None;
+    let mut pending: Vec<&str> = Vec::new();
+    for (i, &id) in ids.iter().enumerate() {
+        if keep[i] {
+            assign(&mut assigned, &pending, lo, keys[i]);
+            pending.clear();
+            lo = keys[i];
+        } else {
+            pending.push(id);
+        }
+    }
+    assign(&mut assigned, &pending, lo, None);
+    apply(items, assigned);
+}
+
+/// Keys `id` so it sorts at `index` among the other items of `list`
+/// (clamped to the end).
+pub(crate) fn place<T: Keyed>(list: &[String], items: &mut HashMap<String, T>, id: &str, index: usize) {
+    let others: Vec<String> = list.iter().filter(|other| *other != id).cloned().collect();
+    fill(&others, items);
+    let mut order = sorted(&others, items);
+    order.insert(index.min(order.len()), id.to_string());
+    reorder(items, &order);
+}
+
+/// Switches between list and fractional ordering. Turning fractional
+/// ordering on keys the items in list order; turning it off rewrites the
+/// list in key order and clears the keys.
+pub(crate) fn migrate<T: Keyed>(list: &mut Vec<String>, items: &mut HashMap<String, T>, fractional: bool) {
+    if fractional {
+        let ids = unique_items(list, items);
+        let keys = keys_between(None, None, ids.len());
+        let assigned = ids.into_iter().map(String::from).zip(keys).collect();
+        apply(items, assigned);
+    } else {
+        *list = sorted(list, items);
+        for item in items.values_mut() {
+            item.set_order_key(None);
+        }
+    }
+}
+
+fn valid_key<'a, T: Keyed>(items: &'a HashMap<String, T>, id: &str) -> Option<&'a str> {
+    items.get(id)?.order_key().filter(|key| is_valid_key(key))
+}
+
+/// Returns the IDs in `list` that have an item, without duplicates.
+fn unique_items<'a, T>(list: &'a [String], items: &HashMap<String, T>) -> Vec<&'a str> {
+    let mut seen = HashSet::new();
+    list.iter()
+        .map(String::as_str)
+        .filter(|id| items.contains_key(*id) && seen.insert(*id))
+        .collect()
+}
+
+/// Marks the longest run of strictly increasing keys (missing keys never
+/// count).
+fn longest_increasing(keys: &[Option<&str>]) -> Vec<bool> {
+    // tails[n] = index of the smallest last key of an increasing run of n + 1
+    let mut tails: Vec<usize> = Vec::new();
+    let mut prev: Vec<Option<usize>> = vec![None; keys.len()];
+    for (i, key) in keys.iter().enumerate() {
+        if key.is_none() {
+            continue;
+        }
+        let len = tails.partition_point(|&t| keys[t] < *key);
+        prev[i] = len.checked_sub(1).map(|n| tails[n]);
+        if len == tails.len() {
+            tails.push(i);
+        } else {
+            tails[len] = i;
+        }
+    }
+    let mut keep = vec![false; keys.len()];
+    let mut next = tails.last().copied();
+    while let Some(i) = next {
+        keep[i] = true;
+        next = prev[i];
+    }
+    keep
+}
+
+fn assign(assigned: &mut Vec<(String, String)>, ids: &[&str], lo: Option<&str>, hi: Option<&str>) {
+    let keys = keys_between(lo, hi, ids.len());
+    assigned.extend(ids.iter().map(|id| id.to_string()).zip(keys));
+}
+
+fn apply<T: Keyed>(items: &mut HashMap<String, T>, assigned: Vec<(String, String)>) {
+    for (id, key) in assigned {
+        if let Some(item) = items.get_mut(&id) {
+            item.set_order_key(Some(key));
+        }
+    }
+}
+
+/// Returns `n` ascending keys between `lo` and `hi`, bisecting so key
+/// length grows with log(n).
+fn keys_between(lo: Option<&str>, hi: Option<&str>, n: usize) -> Vec<String> {
+    if n == 0 {
+        return Vec::new();
+    }
+    let mid = midpoint(lo.unwrap_or(""), hi);
+    let half = n / 2;
+    let mut keys = keys_between(lo, Some(&mid), half);
+    let right = keys_between(Some(&mid), hi, n - half - 1);
+    keys.push(mid);
+    keys.extend(right);
+    keys
+}
+
+/// Returns a key between `a` ("" for no lower bound) and `b`. Both must be
+/// valid and `a < b`.
+fn midpoint(a: &str, b: Option<&str>) -> String {
+    let key = midpoint_digits(a.as_bytes(), b.map(str::as_bytes));
+    String::from_utf8(key).expect("key digits are ASCII")
+}
+
+fn midpoint_digits(a: &[u8], b: Option<&[u8]>) -> Vec<u8> {
+    if let Some(b) = b {
+        // Keep the common prefix (a is padded with zeros)
+        let n = (0..b.len())
+            .take_while(|&i| a.get(i).copied().unwrap_or(b'0') == b[i])
+            .count();
+        if n > 0 {
+            let mut key = b[..n].to_vec();
+            key.extend(midpoint_digits(a.get(n..).unwrap_or_default(), Some(&b[n..])));
+            return key;
+        }
+    }
+    let digit_a = a.first().map_or(0, |&d| digit(d));
+    let digit_b = b.map_or(DIGITS.len(), |b| digit(b[0]));
+    if digit_b - digit_a > 1 {
+        vec![DIGITS[(digit_a + digit_b).div_ceil(2)]]
+    } else if let Some(b) = b.filter(|b| b.len() > 1) {
+        vec![b[0]]
+    } else {
+        let mut key = vec![DIGITS[digit_a]];
+        key.extend(midpoint_digits(a.get(1..).unwrap_or_default(), None));
+        key
+    }
+}
+
+fn digit(d: u8) -> usize {
+    DIGITS.iter().position(|&c| c == d).expect("validated key digit")
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Item(Option<String>);
+
+    impl Keyed for Item {
+        fn order_key(&self) -> Option<&str> {
+            self.0.as_deref()
+        }
+
+        fn set_order_key(&mut self, key: Option<String>) {
+            self.0 = key;
+        }
+    }
+
+    fn ids(list: &[&str]) -> Vec<String> {
+        list.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn test_key_between() {
+        assert_eq!(key_between(None, None).unwrap(), "V");
+        assert_eq!(key_between(Some("j"), Some("k")).unwrap(), "jV");
+        assert_eq!(key_between(None, Some("1")).unwrap(), "0V");
+        assert_eq!(key_between(Some("z"), None).unwrap(), "zV");
+        assert!(key_between(Some("k"), Some("j")).is_err());
+        assert!(key_between(Some("a0"), None).is_err());
+
+        // Repeated inserts at the front stay ordered
+        let mut first = key_between(None, None).unwrap();
+        for _ in 0..100 {
+            let key = key_between(None, Some(&first)).unwrap();
+            assert!(is_valid_key(&key) && key < first);
+            first = key;
+        }
+    }
+
+    #[test]
+    fn test_reorder_and_migrate() {
+        let mut list = ids(&["a", "b", "c", "d"]);
+        let mut items: HashMap<String, Item> =
+            list.iter().map(|id| (id.clone(), Item::default())).collect();
+        migrate(&mut list, &mut items, true);
+        assert_eq!(sorted(&list, &items), list);
+        let before: Vec<String> = list.iter().map(|id| items[id].0.clone().unwrap()).collect();
+
+        // Moving one item rekeys only that item
+        reorder(&mut items, &ids(&["a", "c", "d", "b"]));
+        assert_eq!(sorted(&list, &items), ids(&["a", "c", "d", "b"]));
+        assert_eq!(items["a"].0.as_deref(), Some(before[0].as_str()));
+        assert_eq!(items["c"].0.as_deref(), Some(before[2].as_str()));
+        assert_eq!(items["d"].0.as_deref(), Some(before[3].as_str()));
+
+        place(&list, &mut items, "b", 0);
+        assert_eq!(sorted(&list, &items), ids(&["b", "a", "c", "d"]));
+
+        // New items are keyed after everything, or after their predecessor
+        list.push("e".to_string());
+        list.insert(2, "f".to_string());
+        items.insert("e".to_string(), Item::default());
+        items.insert("f".to_string(), Item::default());
+        fill(&list, &mut items);
+        assert_eq!(sorted(&list, &items), ids(&["b", "f", "a", "c", "d", "e"]));
+
+        migrate(&mut list, &mut items, false);
+        assert_eq!(list, ids(&["b", "f", "a", "c", "d", "e"]));
+        assert!(items.values().all(|item| item.0.is_none()));
+    }
+}
@@ -0,0 +1,392 @@
+//! Keyed items with a display order.
+//!
+//! Sequences, scenes and entity lists store their items twice: a map keyed
+//! by ID and a list of IDs giving the order (`generations` and
+//! `sequence_order`, `shots` and `shot_order`, ...). Kept as two loose
+//! fields, every caller had to update both, and the ones that forgot left
+//! duplicate IDs or IDs of deleted items in the list. `OrderedMap<T>` owns
+//! both and only offers operations that keep the list free of duplicates and
+//! of IDs with no item. Items may be unlisted: a sequence can hold nodes
+//! that are not in `sequence_order`.
+//!
+//! Reads go through `Deref` to the underlying `HashMap`. Documents still
+//! store the two halves as sibling keys of the parent object, so parents
+//! reconcile and hydrate them with `reconcile_fields()` and
+//! `hydrate_fields()`, and serialize them with a module defined by
+//! `ordered_map_serde!`. A hydrated list is taken as-is, drift included, so
+//! `repair()` can report it.
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Deref;
+
+use automerge::ObjId;
+use autosurgeon::reconcile::MapReconciler;
+use autosurgeon::{hydrate_prop, Hydrate, HydrateError, ReadDoc, Reconcile};
+
+use crate::repair::{self, RepairReport};
+
+/// A map of items keyed by ID plus the order of the listed IDs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderedMap<T> {
+    items: HashMap<String, T>,
+    order: Vec<String>,
+}
+
+impl<T> Default for OrderedMap<T> {
+    fn default() -> Self {
+        Self {
+            items: HashMap::new(),
+            order: Vec::new(),
+        }
+    }
+}
+
+impl<T> Deref for OrderedMap<T> {
+    type Target = HashMap<String, T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.items
+    }
+}
+
+impl<'a, T> IntoIterator for &'a OrderedMap<T> {
+    type Item = (&'a String, &'a T);
+    type IntoIter = std::collections::hash_map::Iter<'a, String, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+impl<T> OrderedMap<T> {
+    /// Creates an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds a map from stored parts. `order` is kept as given; call
+    /// `repair()` to drop duplicates and unknown IDs.
+    pub fn from_parts(items: HashMap<String, T>, order: Vec<String>) -> Self {
+        Self { items, order }
+    }
+
+    /// Splits the map into its items and order list.
+    pub fn into_parts(self) -> (HashMap<String, T>, Vec<String>) {
+        (self.items, self.order)
+    }
+
+    /// Returns the listed IDs in order.
+    pub fn order(&self) -> &[String] {
+        &self.order
+    }
+
+    /// Returns true if `id` is in the order list.
+    pub fn is_listed(&self, id: &str) -> bool {
+        self.order.iter().any(|o| o == id)
+    }
+
+    /// Returns the position of `id` in the order list.
+    pub fn position(&self, id: &str) -> Option<usize> {
+        self.order.iter().position(|o| o == id)
+    }
+
+    /// Returns the listed items in order, skipping IDs with no item.
+    pub fn iter_ordered(&self) -> impl Iterator<Item = (&String, &T)> {
+        self.order
+            .iter()
+            .filter_map(|id| self.items.get_key_value(id))
+    }
+
+    /// Returns a mutable reference to an item.
+    pub fn get_mut(&mut self, id: &str) -> Option<&mut T> {
+        self.items.get_mut(id)
+    }
+
+    /// Returns mutable references to all items, in no particular order.
+    pub fn values_mut(&mut self) -> std::collections::hash_map::ValuesMut<'_, String, T> {
+        self.items.values_mut()
+    }
+
+    /// Returns all items with mutable references, in no particular order.
+    pub fn iter_mut(&mut self) -> std::collections::hash_map::IterMut<'_, String, T> {
+        self.items.iter_mut()
+    }
+
+    /// Inserts or replaces an item, appending it to the order if unlisted.
+    /// Returns the replaced item.
+    pub fn insert(&mut self, id: impl Into<String>, item: T) -> Option<T> {
+        let id = id.into();
+        if !self.is_listed(&id) {
+            self.order.push(id.clone());
+        }
+        self.items.insert(id, item)
+    }
+
+    /// Inserts or replaces an item without listing it. A listed item keeps
+    /// its position.
+    pub fn insert_unlisted(&mut self, id: impl Into<String>, item: T) -> Option<T> {
+        self.items.insert(id.into(), item)
+    }
+
+    /// Inserts or replaces an item and moves it to `index` in the order
+    /// (clamped to the end). Returns the replaced item.
+    pub fn insert_at(&mut self, index: usize, id: impl Into<String>, item: T) -> Option<T> {
+        let id = id.into();
+        let replaced = self.items.insert(id.clone(), item);
+        self.move_to(&id, index);
+        replaced
+    }
+
+    /// Removes an item and its order entry.
+    pub fn remove(&mut self, id: &str) -> Option<T> {
+        self.order.retain(|o| o != id);
+        self.items.remove(id)
+    }
+
+    /// Keeps only the items for which `keep` returns true.
+    pub fn retain(&mut self, mut keep: impl FnMut(&String, &mut T) -> bool) {
+        self.items.retain(|id, item| keep(id, item));
+        let items = &self.items;
+        self.order.retain(|id| items.contains_key(id));
+    }
+
+    /// Appends an unlisted item to the order. Returns false if there is no
+    /// such item or it is already listed.
+    pub fn list(&mut self, id: &str) -> bool {
+        if !self.items.contains_key(id) || self.is_listed(id) {
+            return false;
+        }
+        self.order.push(id.to_string());
+        true
+    }
+
+    /// Removes an item from the order, keeping the item. Returns false if
+    /// it was not listed.
+    pub fn unlist(&mut self, id: &str) -> bool {
+        let len = self.order.len();
+        self.order.retain(|o| o != id);
+        self.order.len() != len
+    }
+
+    /// Moves an item to `index` in the order (clamped to the end), listing
+    /// it if needed. The index counts the other listed items. Returns false
+    /// if there is no such item.
+    pub fn move_to(&mut self, id: &str, index: usize) -> bool {
+        if !self.items.contains_key(id) {
+            return false;
+        }
+        self.order.retain(|o| o != id);
+        let index = index.min(self.order.len());
+        self.order.insert(index, id.to_string());
+        true
+    }
+
+    /// Puts the IDs in `ids` first, in that order, followed by the other
+    /// listed items in their current order. IDs with no item and repeats
+    /// are skipped; named unlisted items become listed.
+    pub fn reorder<I, S>(&mut self, ids: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut seen = HashSet::new();
+        let mut order = Vec::with_capacity(self.order.len());
+        let named = ids.into_iter().map(Into::into);
+        for id in named.chain(std::mem::take(&mut self.order)) {
+            if self.items.contains_key(&id) && seen.insert(id.clone()) {
+                order.push(id);
+            }
+        }
+        self.order = order;
+    }
+
+    /// Drops duplicate and unknown IDs from the order and appends every
+    /// unlisted item, recording each fix in `report` under `path`. See
+    /// `crate::repair`.
+    pub(crate) fn repair(&mut self, report: &mut RepairReport, path: &[&str], rank: impl Fn(&T) -> i64) {
+        repair::repair_order(report, path, &mut self.order, &self.items, rank);
+    }
+
+    /// Splits the map into its order list and items, for helpers that
+    /// rewrite both (`crate::order_key`). Callers must not leave duplicate
+    /// or unknown IDs in the list.
+    pub(crate) fn parts_mut(&mut self) -> (&mut Vec<String>, &mut HashMap<String, T>) {
+        (&mut self.order, &mut self.items)
+    }
+}
+
+impl<T: Reconcile> OrderedMap<T> {
+    /// Writes the items and the order list as the keys `items_key` and
+    /// `order_key` of the parent map.
+    pub fn reconcile_fields<M: MapReconciler>(
+        &self,
+        m: &mut M,
+        items_key: &str,
+        order_key: &str,
+    ) -> Result<(), M::Error> {
+        m.put(items_key, &self.items)?;
+        m.put(order_key, &self.order)
+    }
+}
+
+impl<T: Hydrate> OrderedMap<T> {
+    /// Reads the items and the order list from the keys `items_key` and
+    /// `order_key` of `obj`. Missing keys read as empty.
+    pub fn hydrate_fields<D: ReadDoc>(
+        doc: &D,
+        obj: &ObjId,
+        items_key: &str,
+        order_key: &str,
+    ) -> Result<Self, HydrateError> {
+        let items: Option<HashMap<String, T>> = hydrate_prop(doc, obj, items_key)?;
+        let order: Option<Vec<String>> = hydrate_prop(doc, obj, order_key)?;
+        Ok(Self::from_parts(
+            items.unwrap_or_default(),
+            order.unwrap_or_default(),
+        ))
+    }
+}
+
+/// Defines a serde module that writes an `OrderedMap` field as two keys of
+/// its parent, keeping the JSON layout of the separate fields. Use it with
+/// `#[serde(flatten, with = "module")]`.
+macro_rules! ordered_map_serde {
+    ($module:ident, $items:literal, $order:literal) => {
+        mod $module {
+            use std::collections::HashMap;
+
+            use serde::ser::SerializeMap;
+            use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+            use $crate::ordered_map::OrderedMap;
+
+            pub fn serialize<T, S>(map: &OrderedMap<T>, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                T: Serialize,
+                S: Serializer,
+            {
+                let mut m = serializer.serialize_map(Some(2))?;
+                m.serialize_entry($order, map.order())?;
+                m.serialize_entry($items, &**map)?;
+                m.end()
+            }
+
+            pub fn deserialize<'de, T, D>(deserializer: D) -> Result<OrderedMap<T>, D::Error>
+            where
+                T: Deserialize<'de>,
+                D: Deserializer<'de>,
+            {
+                #[derive(Deserialize)]
+                struct Fields<T> {
+                    #[serde(rename = $items, default = "HashMap::new")]
+                    items: HashMap<String, T>,
+                    #[serde(rename = $order, default)]
+                    order: Vec<String>,
+                }
+                let fields = Fields::deserialize(deserializer)?;
+                Ok(OrderedMap::from_parts(fields.items, fields.order))
+            }
+        }
+    };
+}
+pub(crate) use ordered_map_serde;
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(ids: &[&str]) -> OrderedMap<i32> {
+        let mut map = OrderedMap::new();
+        for (i, id) in ids.iter().enumerate() {
+            map.insert(*id, i as i32);
+        }
+        map
+    }
+
+    #[test]
+    fn test_operations_keep_order_consistent() {
+        let mut m = map(&["a", "b", "c"]);
+
+        // Replacing keeps the position and never duplicates
+        assert_eq!(m.insert("a", 9), Some(0));
+        assert_eq!(m.order(), ["a", "b", "c"]);
+
+        m.insert_unlisted("x", 5);
+        assert!(!m.is_listed("x"));
+        assert!(m.list("x"));
+        assert!(!m.list("x"));
+        assert!(!m.list("missing"));
+
+        assert!(m.move_to("x", 1));
+        assert!(!m.move_to("missing", 0));
+        assert_eq!(m.order(), ["a", "x", "b", "c"]);
+        m.insert_at(99, "b", 1);
+        assert_eq!(m.order(), ["a", "x", "c", "b"]);
+
+        assert_eq!(m.remove("x"), Some(5));
+        assert!(m.unlist("c"));
+        assert!(!m.unlist("c"));
+        assert!(m.contains_key("c"));
+        assert_eq!(m.order(), ["a", "b"]);
+
+        m.retain(|id, _| id != "a");
+        assert_eq!(m.order(), ["b"]);
+        assert_eq!(m.len(), 2);
+    }
+
+    #[test]
+    fn test_reorder_skips_unknown_and_repeats() {
+        let mut m = map(&["a", "b", "c", "d"]);
+        m.insert_unlisted("u", 0);
+
+        m.reorder(["c", "gone", "a", "c", "u"]);
+        assert_eq!(m.order(), ["c", "a", "u", "b", "d"]);
+
+        let ordered: Vec<&String> = m.iter_ordered().map(|(id, _)| id).collect();
+        assert_eq!(ordered, ["c", "a", "u", "b", "d"]);
+    }
+
+    #[test]
+    fn test_repair_hydrated_drift() {
+        let items: HashMap<String, i32> = [("a", 2), ("b", 1)]
+            .into_iter()
+            .map(|(id, n)| (id.to_string(), n))
+            .collect();
+        let mut m = OrderedMap::from_parts(items, vec!["a".into(), "gone".into(), "a".into()]);
+
+        let mut report = RepairReport::default();
+        m.repair(&mut report, &["order"], |n| *n as i64);
+        assert_eq!(m.order(), ["a", "b"]);
+        assert_eq!(report.actions.len(), 3);
+    }
+
+    #[test]
+    fn test_serde_keeps_flat_layout() {
+        #[derive(serde::Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Parent {
+            title: String,
+            #[serde(flatten, with = "fields")]
+            shots: OrderedMap<i32>,
+        }
+        ordered_map_serde!(fields, "shots", "shot_order");
+
+        let parent = Parent {
+            title: "t".into(),
+            shots: map(&["b", "a"]),
+        };
+        let json = serde_json::to_value(&parent).unwrap();
+        assert_eq!(json["shot_order"], serde_json::json!(["b", "a"]));
+        assert_eq!(json["shots"]["a"], 1);
+
+        let back: Parent = serde_json::from_value(json).unwrap();
+        assert_eq!(back, parent);
+
+        // Missing keys read as empty
+        let empty: Parent = serde_json::from_str(r#"{"title": "t"}"#).unwrap();
+        assert!(empty.shots.is_empty());
+    }
+}
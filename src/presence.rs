@@ -0,0 +1,324 @@
+//! Ephemeral presence/awareness shared by the sequence and storyboard managers.
+//!
+//! Presence is deliberately kept out of the CRDT: cursors and focus change
+//! many times per second and have no value once a peer disconnects. Each peer
+//! broadcasts its whole local state with a monotonically increasing clock;
+//! receivers keep the latest state per actor and ignore stale messages. The
+//! clock never falls behind wall-clock milliseconds, so a peer that reloads
+//! under the same actor ID still outranks what it sent before the reload.
+//!
+//! Messages are JSON prefixed with `PRESENCE_MAGIC`, so they can share a
+//! WebSocket with Automerge sync messages (use `is_presence_message` to route).
+//!
+//! Peers that disconnect without sending a "left" message are dropped by
+//! `prune(now)` once they have been silent longer than the peer timeout.
+//! Clients should re-broadcast their state (`encode()`) periodically.
+
+use std::collections::HashMap;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::error::{CollabError, CollabResult};
+
+/// Prefix identifying presence messages on a shared transport.
+pub const PRESENCE_MAGIC: &[u8] = b"PRS1";
+
+/// Default time after which a silent peer is pruned (30 seconds).
+pub const DEFAULT_PEER_TIMEOUT_MS: i64 = 30_000;
+
+/// Returns true if `bytes` is a presence message rather than a sync message.
+pub fn is_presence_message(bytes: &[u8]) -> bool {
+    bytes.starts_with(PRESENCE_MAGIC)
+}
+
+/// WASM binding for `is_presence_message()`.
+///
+/// # Example (JavaScript)
+/// ```js
+/// ws.onmessage = (e) => {
+///   const bytes = new Uint8Array(e.data);
+///   if (isPresenceMessage(bytes)) manager.applyPresenceMessage(bytes, Date.now());
+///   else manager.applySyncMessage(bytes);
+/// };
+/// ```
+#[cfg(feature = "wasm")]
+#[wasm_bindgen::prelude::wasm_bindgen(js_name = isPresenceMessage)]
+pub fn js_is_presence_message(bytes: &[u8]) -> bool {
+    is_presence_message(bytes)
+}
+
+/// Text cursor within a field of the focused item.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Cursor {
+    /// Field being edited (e.g., "prompt").
+    pub field: String,
+    /// Selection anchor (character offset).
+    pub anchor: u32,
+    /// Selection head (character offset); equals `anchor` for a caret.
+    pub head: u32,
+}
+
+impl Cursor {
+    /// Creates a caret (empty selection) at `offset`.
+    pub fn caret(field: impl Into<String>, offset: u32) -> Self {
+        Self {
+            field: field.into(),
+            anchor: offset,
+            head: offset,
+        }
+    }
+}
+
+/// Latest known state of a remote peer.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PeerPresence<S> {
+    /// Actor ID of the peer's document.
+    pub actor: String,
+    /// Clock of the last applied message.
+    pub clock: u64,
+    /// When the last message was received (ms since epoch), if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_seen: Option<i64>,
+    /// Peer-provided state.
+    pub state: S,
+}
+
+/// Wire format. `state: None` means the peer left.
+#[derive(Serialize, Deserialize)]
+struct PresenceMessage<S> {
+    actor: String,
+    clock: u64,
+    state: Option<S>,
+}
+
+/// Local presence state plus the latest state of each remote peer.
+#[derive(Debug, Clone)]
+pub struct Awareness<S> {
+    actor: String,
+    clock: u64,
+    local: Option<S>,
+    peers: HashMap<String, PeerPresence<S>>,
+    peer_timeout_ms: i64,
+    wall_clock: fn() -> u64,
+}
+
+impl<S: Clone + Serialize + DeserializeOwned> Awareness<S> {
+    /// Creates an empty awareness for the given local actor ID.
+    pub fn new(actor: impl Into<String>) -> Self {
+        Self {
+            actor: actor.into(),
+            clock: 0,
+            local: None,
+            peers: HashMap::new(),
+            peer_timeout_ms: DEFAULT_PEER_TIMEOUT_MS,
+            wall_clock: wall_clock_ms,
+        }
+    }
+
+    /// Replaces the wall clock the local clock keeps up with.
+    #[cfg(test)]
+    pub(crate) fn set_wall_clock(&mut self, wall_clock: fn() -> u64) {
+        self.wall_clock = wall_clock;
+    }
+
+    /// Changes the local actor ID stamped on outgoing presence.
+    pub fn set_actor(&mut self, actor: impl Into<String>) {
+        self.actor = actor.into();
+    }
+
+    /// Returns the timeout after which silent peers are pruned.
+    pub fn peer_timeout_ms(&self) -> i64 {
+        self.peer_timeout_ms
+    }
+
+    /// Sets the timeout after which silent peers are pruned.
+    pub fn set_peer_timeout_ms(&mut self, timeout_ms: i64) {
+        self.peer_timeout_ms = timeout_ms;
+    }
+
+    /// Returns the local state, if set.
+    pub fn local(&self) -> Option<&S> {
+        self.local.as_ref()
+    }
+
+    /// Replaces the local state and returns the message to broadcast.
+    pub fn set_local(&mut self, state: S) -> Vec<u8> {
+        self.local = Some(state);
+        self.tick();
+        self.encode()
+    }
+
+    /// Clears the local state and returns a "left" message to broadcast.
+    pub fn clear_local(&mut self) -> Vec<u8> {
+        self.local = None;
+        self.tick();
+        self.encode()
+    }
+
+    /// Advances the clock to at least the current wall-clock time.
+    fn tick(&mut self) {
+        self.clock = (self.clock + 1).max((self.wall_clock)());
+    }
+
+    /// Encodes the current local state (e.g., to answer a newly joined peer).
+    pub fn encode(&self) -> Vec<u8> {
+        let msg = PresenceMessage {
+            actor: self.actor.clone(),
+            clock: self.clock,
+            state: self.local.clone(),
+        };
+        let mut bytes = PRESENCE_MAGIC.to_vec();
+        // Serializing plain data structs to a Vec cannot fail
+        serde_json::to_writer(&mut bytes, &msg).expect("presence state must serialize");
+        bytes
+    }
+
+    /// Applies a message from a peer.
+    ///
+    /// Returns true if the peer set changed. Messages from the local actor and
+    /// messages older than the last one seen from that peer are ignored.
+    /// Peers applied without a timestamp are never pruned.
+    pub fn apply(&mut self, bytes: &[u8]) -> CollabResult<bool> {
+        self.apply_inner(bytes, None)
+    }
+
+    /// Applies a message from a peer, recording `now` as its last-seen time.
+    pub fn apply_at(&mut self, bytes: &[u8], now: i64) -> CollabResult<bool> {
+        self.apply_inner(bytes, Some(now))
+    }
+
+    fn apply_inner(&mut self, bytes: &[u8], now: Option<i64>) -> CollabResult<bool> {
+        let payload = bytes
+            .strip_prefix(PRESENCE_MAGIC)
+            .ok_or_else(|| CollabError::serialization("not a presence message"))?;
+        let msg: PresenceMessage<S> = serde_json::from_slice(payload)
+            .map_err(|e| CollabError::serialization(e.to_string()))?;
+
+        if msg.actor == self.actor {
+            return Ok(false);
+        }
+        if let Some(peer) = self.peers.get(&msg.actor) {
+            if msg.clock <= peer.clock {
+                return Ok(false);
+            }
+        }
+
+        match msg.state {
+            Some(state) => {
+                self.peers.insert(
+                    msg.actor.clone(),
+                    PeerPresence {
+                        actor: msg.actor,
+                        clock: msg.clock,
+                        last_seen: now,
+                        state,
+                    },
+                );
+            }
+            None => {
+                self.peers.remove(&msg.actor);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Returns remote peers sorted by actor ID.
+    pub fn peers(&self) -> Vec<PeerPresence<S>> {
+        let mut peers: Vec<PeerPresence<S>> = self.peers.values().cloned().collect();
+        peers.sort_by(|a, b| a.actor.cmp(&b.actor));
+        peers
+    }
+
+    /// Forgets a peer (e.g., when its connection closes).
+    pub fn remove_peer(&mut self, actor: &str) -> bool {
+        self.peers.remove(actor).is_some()
+    }
+
+    /// Removes peers silent for longer than the peer timeout.
+    /// Returns the removed actor IDs, sorted.
+    pub fn prune(&mut self, now: i64) -> Vec<String> {
+        let timeout = self.peer_timeout_ms;
+        let mut expired: Vec<String> = self
+            .peers
+            .values()
+            .filter(|p| p.last_seen.is_some_and(|seen| now - seen > timeout))
+            .map(|p| p.actor.clone())
+            .collect();
+        expired.sort();
+        for actor in &expired {
+            self.peers.remove(actor);
+        }
+        expired
+    }
+}
+
+/// Milliseconds since the Unix epoch. Bare `wasm32-unknown-unknown` builds
+/// (without the `wasm` feature) have no clock and return 0, leaving the
+/// presence clock a plain counter.
+fn wall_clock_ms() -> u64 {
+    #[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+    {
+        js_sys::Date::now() as u64
+    }
+    #[cfg(all(target_arch = "wasm32", target_os = "unknown", not(feature = "wasm")))]
+    {
+        0
+    }
+    #[cfg(not(any(
+        all(target_arch = "wasm32", feature = "wasm"),
+        all(target_arch = "wasm32", target_os = "unknown")
+    )))]
+    {
+        use std::time::{SystemTime, UNIX_EPOCH};
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64)
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_keeps_latest_state() {
+        let mut alice: Awareness<String> = Awareness::new("a");
+        let mut bob: Awareness<String> = Awareness::new("b");
+
+        let first = alice.set_local("gen-1".to_string());
+        let second = alice.set_local("gen-2".to_string());
+        assert!(is_presence_message(&second));
+
+        assert!(bob.apply(&second).unwrap());
+        assert!(!bob.apply(&first).unwrap()); // stale
+        assert_eq!(bob.peers()[0].state, "gen-2");
+
+        // Own messages are ignored
+        assert!(!alice.apply(&second).unwrap());
+
+        assert!(bob.apply(&alice.clear_local()).unwrap());
+        assert!(bob.peers().is_empty());
+
+        assert!(bob.apply(b"not presence").is_err());
+    }
+
+    #[test]
+    fn test_prune_silent_peers() {
+        let mut alice: Awareness<String> = Awareness::new("a");
+        let mut carol: Awareness<String> = Awareness::new("c");
+        let mut bob: Awareness<String> = Awareness::new("b");
+        bob.set_peer_timeout_ms(1000);
+
+        bob.apply_at(&alice.set_local("x".to_string()), 1000).unwrap();
+        bob.apply(&carol.set_local("y".to_string())).unwrap();
+
+        assert!(bob.prune(2000).is_empty());
+        assert_eq!(bob.prune(2001), vec!["a".to_string()]);
+        // Peers without a timestamp are kept
+        assert_eq!(bob.peers().len(), 1);
+    }
+}
@@ -0,0 +1,250 @@
+//! Schema-agnostic values for forward compatibility.
+//!
+//! Newer clients may write fields that this version of the crate does not
+//! know about. Structs that can receive such fields (`GenerationNode`,
+//! `Shot`) keep them in an `extra: RawFields` side-map: unknown keys are
+//! captured on hydrate and written back on reconcile, so an older client
+//! editing other parts of the document (or re-importing it from JSON) does
+//! not drop data written by a newer one.
+//!
+//! Text objects are preserved as their string content.
+
+use std::collections::BTreeMap;
+
+use automerge::{ObjId, ObjType, ScalarValue, Value};
+use autosurgeon::reconcile::{CounterReconciler, MapReconciler, NoKey};
+use autosurgeon::{hydrate_prop, Hydrate, HydrateError, ReadDoc, Reconcile, Reconciler};
+use serde::{Deserialize, Serialize};
+
+/// Unknown fields of a struct, keyed by field name.
+pub type RawFields = BTreeMap<String, RawValue>;
+
+/// An arbitrary Automerge value.
+///
+/// Serializes to plain JSON. Bytes, counters and timestamps are written as
+/// their JSON equivalents and read back as lists and integers.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum RawValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Uint(u64),
+    F64(f64),
+    Str(String),
+    List(Vec<RawValue>),
+    Map(BTreeMap<String, RawValue>),
+    Bytes(Vec<u8>),
+    Counter(i64),
+    Timestamp(i64),
+}
+
+impl RawValue {
+    fn from_scalar(scalar: &ScalarValue) -> Self {
+        match scalar {
+            ScalarValue::Bytes(b) => RawValue::Bytes(b.clone()),
+            ScalarValue::Str(s) => RawValue::Str(s.to_string()),
+            ScalarValue::Int(i) => RawValue::Int(*i),
+            ScalarValue::Uint(u) => RawValue::Uint(*u),
+            ScalarValue::F64(f) => RawValue::F64(*f),
+            ScalarValue::Counter(_) => RawValue::Counter(scalar.to_i64().unwrap_or_default()),
+            ScalarValue::Timestamp(t) => RawValue::Timestamp(*t),
+            ScalarValue::Boolean(b) => RawValue::Bool(*b),
+            _ => RawValue::Null,
+        }
+    }
+}
+
+impl Reconcile for RawValue {
+    type Key<'a> = NoKey;
+
+    fn reconcile<R: Reconciler>(&self, mut reconciler: R) -> Result<(), R::Error> {
+        match self {
+            RawValue::Null => reconciler.none(),
+            RawValue::Bool(b) => reconciler.boolean(*b),
+            RawValue::Int(i) => reconciler.i64(*i),
+            RawValue::Uint(u) => reconciler.u64(*u),
+            RawValue::F64(f) => reconciler.f64(*f),
+            RawValue::Str(s) => reconciler.str(s),
+            RawValue::Bytes(b) => reconciler.bytes(b),
+            RawValue::Counter(c) => reconciler.counter()?.set(*c),
+            RawValue::Timestamp(t) => reconciler.timestamp(*t),
+            RawValue::List(items) => items.reconcile(reconciler),
+            RawValue::Map(fields) => {
+                let mut m = reconciler.map()?;
+                reconcile_unknown_fields(&mut m, fields)
+            }
+        }
+    }
+}
+
+/// Reads the value at `obj[prop]`, recursing into nested objects.
+fn read_prop<D: ReadDoc, P: Into<automerge::Prop>>(
+    doc: &D,
+    obj: &ObjId,
+    prop: P,
+) -> Result<Option<RawValue>, HydrateError> {
+    match doc.get(obj, prop)? {
+        Some((value, id)) => read_value(doc, value, &id).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Reads a value returned by `get()` or `get_all()`, recursing into
+/// nested objects.
+pub(crate) fn read_value<D: ReadDoc>(
+    doc: &D,
+    value: Value<'_>,
+    id: &ObjId,
+) -> Result<RawValue, HydrateError> {
+    Ok(match value {
+        Value::Scalar(s) => RawValue::from_scalar(s.as_ref()),
+        Value::Object(ObjType::Map) | Value::Object(ObjType::Table) => {
+            RawValue::Map(hydrate_unknown_fields(doc, id, &[])?)
+        }
+        Value::Object(ObjType::List) => {
+            let mut items = Vec::with_capacity(doc.length(id));
+            for index in 0..doc.length(id) {
+                items.push(read_prop(doc, id, index)?.unwrap_or(RawValue::Null));
+            }
+            RawValue::List(items)
+        }
+        Value::Object(ObjType::Text) => RawValue::Str(doc.text(id)?),
+    })
+}
+
+/// Collects every key of the map `obj` that is not in `known`.
+pub(crate) fn hydrate_unknown_fields<D: ReadDoc>(
+    doc: &D,
+    obj: &ObjId,
+    known: &[&str],
+) -> Result<RawFields, HydrateError> {
+    let keys: Vec<String> = doc
+        .map_range(obj, ..)
+        .map(|item| item.key.to_string())
+        .filter(|key| !known.contains(&key.as_str()))
+        .collect();
+
+    let mut fields = RawFields::new();
+    for key in keys {
+        if let Some(value) = read_prop(doc, obj, key.as_str())? {
+            fields.insert(key, value);
+        }
+    }
+    Ok(fields)
+}
+
+/// Hydrates `obj[key]`, or the default value if the key is absent, as in
+/// documents saved before the field existed. `hydrate_prop` already gives
+/// `None` for a missing `Option` key, but errors for other types such as
+/// `Vec`, `bool` or maps.
+pub(crate) fn hydrate_optional<D: ReadDoc, T: Hydrate + Default>(
+    doc: &D,
+    obj: &ObjId,
+    key: &str,
+) -> Result<T, HydrateError> {
+    match doc.get(obj, key)? {
+        Some(_) => hydrate_prop(doc, obj, key),
+        None => Ok(T::default()),
+    }
+}
+
+/// Writes unknown fields back into a map being reconciled.
+pub(crate) fn reconcile_unknown_fields<M: MapReconciler>(
+    m: &mut M,
+    fields: &RawFields,
+) -> Result<(), M::Error> {
+    for (key, value) in fields {
+        m.put(key, value)?;
+    }
+    Ok(())
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequence::{DocumentRoot, DocumentSeed, GenerationNode};
+    use automerge::{transaction::Transactable, AutoCommit, ROOT};
+    use autosurgeon::{hydrate, reconcile};
+
+    fn node_obj(doc: &AutoCommit, id: &str) -> ObjId {
+        let (_, generations) = doc.get(&ROOT, "generations").unwrap().unwrap();
+        let (_, node) = doc.get(&generations, id).unwrap().unwrap();
+        node
+    }
+
+    #[test]
+    fn test_unknown_node_fields_round_trip() {
+        let root = DocumentSeed::new()
+            .with_node(GenerationNode::new("a", "t2v-future"))
+            .into_root();
+        let mut doc = AutoCommit::new();
+        reconcile(&mut doc, &root).unwrap();
+
+        // A newer client writes fields this version does not know
+        let node = node_obj(&doc, "a");
+        doc.put(&node, "lipsync", true).unwrap();
+        let style = doc.put_object(&node, "style_ref", ObjType::Map).unwrap();
+        doc.put(&style, "strength", 0.5).unwrap();
+        let tags = doc.put_object(&style, "tags", ObjType::List).unwrap();
+        doc.insert(&tags, 0, "noir").unwrap();
+
+        let mut state: DocumentRoot = hydrate(&doc).unwrap();
+        let node = state.generations.get_mut("a").unwrap();
+        assert_eq!(node.type_, "t2v-future");
+        assert_eq!(node.extra.get("lipsync"), Some(&RawValue::Bool(true)));
+
+        // An old client edits a known field and writes a fresh document
+        node.prompt = "edited".to_string();
+        let mut copy = AutoCommit::new();
+        reconcile(&mut copy, &state).unwrap();
+        let rehydrated: DocumentRoot = hydrate(&copy).unwrap();
+        assert_eq!(rehydrated, state);
+
+        // JSON keeps unknown fields at the top level of the node
+        let json = serde_json::to_value(&state.generations["a"]).unwrap();
+        assert_eq!(json["style_ref"]["tags"][0], "noir");
+        let back: GenerationNode = serde_json::from_value(json).unwrap();
+        assert_eq!(back, state.generations["a"]);
+    }
+
+    #[test]
+    fn test_unknown_counter_round_trip() {
+        let root = DocumentSeed::new()
+            .with_node(GenerationNode::new("a", "t2i"))
+            .into_root();
+        let mut doc = AutoCommit::new();
+        reconcile(&mut doc, &root).unwrap();
+        let node = node_obj(&doc, "a");
+        doc.put(&node, "views", ScalarValue::counter(3)).unwrap();
+
+        let state: DocumentRoot = hydrate(&doc).unwrap();
+        assert_eq!(state.generations["a"].extra.get("views"), Some(&RawValue::Counter(3)));
+        let mut copy = AutoCommit::new();
+        reconcile(&mut copy, &state).unwrap();
+        let (value, _) = copy.get(&node_obj(&copy, "a"), "views").unwrap().unwrap();
+        assert_eq!(value.to_i64(), Some(3));
+    }
+
+    #[test]
+    fn test_hydrate_optional_fields_missing_from_older_documents() {
+        let root = DocumentSeed::new()
+            .with_node(GenerationNode::new("a", "t2i"))
+            .into_root();
+        let mut doc = AutoCommit::new();
+        reconcile(&mut doc, &root).unwrap();
+
+        // Written before these fields existed
+        let node = node_obj(&doc, "a");
+        for key in ["status_updated_at", "provider", "job_id", "comments"] {
+            doc.delete(&node, key).unwrap();
+        }
+        let state: DocumentRoot = hydrate(&doc).unwrap();
+        let node = &state.generations["a"];
+        assert_eq!((node.provider.as_ref(), node.comments.as_ref()), (None, None));
+    }
+}
@@ -0,0 +1,140 @@
+//! Order-list repair shared by the sequence and storyboard managers.
+//!
+//! Documents keep items in a map plus a separate order list. Concurrent
+//! edits (one peer deletes a scene while another reorders) can leave the two
+//! out of step. `repair()` makes every order list match its map:
+//!
+//! 1. duplicate IDs are dropped (first occurrence wins),
+//! 2. IDs with no item in the map are dropped,
+//! 3. items missing from the list are appended, ordered by their number
+//!    (scene/shot number) and then by ID.
+//!
+//! The result only depends on the document state, so peers that repair the
+//! same state independently converge on the same order.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+/// What was done to an order list entry.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RepairKind {
+    /// A repeated ID was removed.
+    RemovedDuplicate,
+    /// An ID without a matching item was removed.
+    RemovedMissing,
+    /// An item absent from the order list was appended.
+    AppendedUnordered,
+}
+
+/// A single fix applied by `repair()`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RepairAction {
+    /// Location of the order list, e.g. `["scene_order"]` or `["scenes", "s1", "shot_order"]`.
+    pub path: Vec<String>,
+    /// The affected ID.
+    pub id: String,
+    pub kind: RepairKind,
+}
+
+/// Summary of what `repair()` changed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RepairReport {
+    pub actions: Vec<RepairAction>,
+}
+
+impl RepairReport {
+    /// Returns true if nothing needed repair.
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+}
+
+/// Repairs `order` against `items`, recording fixes in `report`.
+///
+/// `rank` orders appended items (lower first); ties are broken by ID.
+pub(crate) fn repair_order<T>(
+    report: &mut RepairReport,
+    path: &[&str],
+    order: &mut Vec<String>,
+    items: &HashMap<String, T>,
+    rank: impl Fn(&T) -> i64,
+) {
+    let mut record = |id: &str, kind: RepairKind| {
+        report.actions.push(RepairAction {
+            path: path.iter().map(|p| p.to_string()).collect(),
+            id: id.to_string(),
+            kind,
+        });
+    };
+
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut repaired = Vec::with_capacity(items.len());
+    for id in order.iter() {
+        if seen.contains(id) {
+            record(id, RepairKind::RemovedDuplicate);
+        } else if !items.contains_key(id) {
+            seen.insert(id.clone());
+            record(id, RepairKind::RemovedMissing);
+        } else {
+            seen.insert(id.clone());
+            repaired.push(id.clone());
+        }
+    }
+
+    let mut unordered: Vec<(i64, &String)> = items
+        .iter()
+        .filter(|(id, _)| !seen.contains(*id))
+        .map(|(id, item)| (rank(item), id))
+        .collect();
+    unordered.sort();
+    for (_, id) in unordered {
+        record(id, RepairKind::AppendedUnordered);
+        repaired.push(id.clone());
+    }
+
+    *order = repaired;
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repair_order() {
+        let items: HashMap<String, i64> = [("a", 1), ("b", 2), ("c", 3), ("d", 0)]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect();
+        let mut order: Vec<String> = ["b", "x", "b", "a"].iter().map(|s| s.to_string()).collect();
+
+        let mut report = RepairReport::default();
+        repair_order(&mut report, &["order"], &mut order, &items, |n| *n);
+
+        assert_eq!(order, vec!["b", "a", "d", "c"]);
+        let kinds: Vec<(&str, RepairKind)> = report
+            .actions
+            .iter()
+            .map(|a| (a.id.as_str(), a.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("x", RepairKind::RemovedMissing),
+                ("b", RepairKind::RemovedDuplicate),
+                ("d", RepairKind::AppendedUnordered),
+                ("c", RepairKind::AppendedUnordered),
+            ]
+        );
+
+        // Repairing again is a no-op
+        let mut again = RepairReport::default();
+        repair_order(&mut again, &["order"], &mut order, &items, |n| *n);
+        assert!(again.is_empty());
+    }
+}
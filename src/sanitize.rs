@@ -0,0 +1,186 @@
+//! Sanitized export for erasure requests.
+//!
+//! `export_sanitized()` copies the current state of a document into a fresh
+//! one, replacing every string whose current value was written by one of the
+//! given actors with `REDACTED`. The copy has a single change by a new actor
+//! and no history, so the original values are gone from it entirely; the
+//! structure (keys, lists, numbers, flags) is kept as-is. Text objects are
+//! redacted whole if any character in them came from a removed actor.
+//!
+//! `redact_field()` overwrites a single string with `REDACTED`; the managers
+//! follow it with a compaction so the old value leaves the history too.
+//!
+//! Either result is a new baseline like `save_compact()`: peers must reload
+//! it, since syncing with an old copy would bring the history back.
+
+use std::collections::HashSet;
+
+use automerge::{
+    transaction::Transactable, ActorId, AutoCommit, ObjId, ObjType, Prop, ReadDoc, ScalarValue,
+    Value, ROOT,
+};
+
+use crate::conflict::resolve_field;
+use crate::error::{CollabError, CollabResult};
+
+/// Replacement for redacted strings.
+pub const REDACTED: &str = "[redacted]";
+
+/// Returns a new document with the strings written by `remove_actors`
+/// redacted.
+pub(crate) fn export_sanitized(
+    doc: &AutoCommit,
+    remove_actors: &[ActorId],
+) -> CollabResult<Vec<u8>> {
+    let mut copier = Copier {
+        doc,
+        removed: remove_actors.iter().collect(),
+        out: AutoCommit::new(),
+    };
+    copier.copy_map(&ROOT, &ROOT)?;
+    let mut out = copier.out;
+    out.commit();
+    Ok(out.save())
+}
+
+/// Overwrites the string or text at `field` under `obj` (dotted for nested
+/// maps) with `REDACTED`, replacing any concurrent values. Other field types
+/// are rejected since redacting them would break the schema.
+pub(crate) fn redact_field(doc: &mut AutoCommit, obj: &ObjId, field: &str) -> CollabResult<()> {
+    let (parent, key) =
+        resolve_field(doc, obj, field)?.ok_or_else(|| CollabError::field_not_found(field))?;
+    let is_text = match doc.get(&parent, key)? {
+        Some((Value::Scalar(scalar), _)) if matches!(scalar.as_ref(), ScalarValue::Str(_)) => false,
+        Some((Value::Object(ObjType::Text), _)) => true,
+        Some(_) => {
+            return Err(CollabError::schema_violation(format!(
+                "{} is not a string field and cannot be redacted",
+                field
+            )))
+        }
+        None => return Err(CollabError::field_not_found(field)),
+    };
+    if is_text {
+        let text = doc.put_object(&parent, key, ObjType::Text)?;
+        doc.splice_text(&text, 0, 0, REDACTED)?;
+    } else {
+        doc.put(&parent, key, REDACTED)?;
+    }
+    Ok(())
+}
+
+/// Copies objects from `doc` into `out`, redacting as it goes.
+struct Copier<'a> {
+    doc: &'a AutoCommit,
+    removed: HashSet<&'a ActorId>,
+    out: AutoCommit,
+}
+
+impl Copier<'_> {
+    fn copy_map(&mut self, obj: &ObjId, target: &ObjId) -> CollabResult<()> {
+        let doc = self.doc;
+        for key in doc.keys(obj) {
+            if let Some((value, id)) = doc.get(obj, key.as_str())? {
+                self.copy_value(value, &id, target, key.as_str().into(), false)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn copy_list(&mut self, obj: &ObjId, target: &ObjId) -> CollabResult<()> {
+        let doc = self.doc;
+        for index in 0..doc.length(obj) {
+            if let Some((value, id)) = doc.get(obj, index)? {
+                self.copy_value(value, &id, target, index.into(), true)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes `value` (with op ID `id`) to `target[prop]`, inserting if
+    /// `insert` is set (lists) and putting otherwise (maps).
+    fn copy_value(
+        &mut self,
+        value: Value<'_>,
+        id: &ObjId,
+        target: &ObjId,
+        prop: Prop,
+        insert: bool,
+    ) -> CollabResult<()> {
+        let obj_type = match value {
+            Value::Scalar(scalar) => {
+                let scalar = match scalar.as_ref() {
+                    ScalarValue::Str(_) if self.written_by_removed(id) => {
+                        ScalarValue::Str(REDACTED.into())
+                    }
+                    other => other.clone(),
+                };
+                match prop {
+                    Prop::Seq(index) if insert => self.out.insert(target, index, scalar)?,
+                    prop => self.out.put(target, prop, scalar)?,
+                }
+                return Ok(());
+            }
+            Value::Object(ObjType::Table) => ObjType::Map,
+            Value::Object(obj_type) => obj_type,
+        };
+        let child = match prop {
+            Prop::Seq(index) if insert => self.out.insert_object(target, index, obj_type)?,
+            prop => self.out.put_object(target, prop, obj_type)?,
+        };
+        match obj_type {
+            ObjType::List => self.copy_list(id, &child),
+            ObjType::Text => {
+                let doc = self.doc;
+                let tainted = (0..doc.length(id)).any(|index| {
+                    matches!(doc.get(id, index), Ok(Some((_, char_id))) if self.written_by_removed(&char_id))
+                });
+                let text = if tainted { REDACTED.to_string() } else { doc.text(id)? };
+                self.out.splice_text(&child, 0, 0, &text)?;
+                Ok(())
+            }
+            _ => self.copy_map(id, &child),
+        }
+    }
+
+    fn written_by_removed(&self, id: &ObjId) -> bool {
+        matches!(id, ObjId::Id(_, actor, _) if self.removed.contains(actor))
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redacts_only_removed_actors_strings() {
+        let mut alice = AutoCommit::new();
+        let node = alice.put_object(&ROOT, "node", ObjType::Map).unwrap();
+        alice.put(&node, "title", "Sunset").unwrap();
+        let tags = alice.put_object(&node, "tags", ObjType::List).unwrap();
+        alice.insert(&tags, 0, "sky").unwrap();
+        let mut bob = alice.fork();
+        bob.put(&node, "prompt", "bob@example.com's prompt").unwrap();
+        bob.put(&node, "seed", 42).unwrap();
+        bob.insert(&tags, 1, "private").unwrap();
+        alice.merge(&mut bob).unwrap();
+
+        let bytes = export_sanitized(&alice, &[bob.get_actor().clone()]).unwrap();
+        let mut clean = AutoCommit::load(&bytes).unwrap();
+        let (_, node) = clean.get(&ROOT, "node").unwrap().unwrap();
+        let str_at = |doc: &AutoCommit, obj: &ObjId, prop: Prop| match doc.get(obj, prop).unwrap() {
+            Some((Value::Scalar(s), _)) => s.into_owned(),
+            other => panic!("expected a scalar, got {:?}", other),
+        };
+        assert_eq!(str_at(&clean, &node, "title".into()), ScalarValue::Str("Sunset".into()));
+        assert_eq!(str_at(&clean, &node, "prompt".into()), ScalarValue::Str(REDACTED.into()));
+        assert_eq!(str_at(&clean, &node, "seed".into()), ScalarValue::Int(42));
+        let (_, tags) = clean.get(&node, "tags").unwrap().unwrap();
+        assert_eq!(str_at(&clean, &tags, 1.into()), ScalarValue::Str(REDACTED.into()));
+        assert_eq!(clean.get_changes(&[]).len(), 1);
+    }
+}
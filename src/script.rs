@@ -0,0 +1,209 @@
+//! Fountain screenplay import.
+//!
+//! `parse_fountain()` splits a Fountain screenplay (https://fountain.io) into
+//! storyboard scenes: one per scene heading, holding the text up to the next
+//! heading and the characters who speak in it.
+//! `StoryboardManager::import_fountain()` appends them to a storyboard.
+//!
+//! Only what scenes need is recognized: scene headings (standard prefixes,
+//! or forced with "."), scene numbers ("#12#"), character cues (an all-caps
+//! line before dialogue, or forced with "@"; extensions such as "(V.O.)" and
+//! the dual-dialogue "^" are dropped), notes ("[[...]]") and boneyard
+//! ("/* ... */"). Text before the first heading, such as the title page, is
+//! skipped.
+
+use crate::storyboard::Scene;
+
+/// Scene heading prefixes, matched case-insensitively before "." or " ".
+const HEADING_PREFIXES: &[&str] = &["INT./EXT", "INT/EXT", "I/E", "INT", "EXT", "EST"];
+
+/// Parses a Fountain screenplay into scenes, in script order.
+///
+/// Scenes have an empty `id`. `scene_number` is the forced number if it is
+/// an integer and the position in the script otherwise. `characters_present`
+/// holds character names as written in the cues (e.g. "JOHN"), each once.
+pub fn parse_fountain(text: &str) -> Vec<Scene> {
+    let text = strip_comments(text);
+    let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    let mut scenes: Vec<Scene> = Vec::new();
+    let mut body: Vec<&str> = Vec::new();
+
+    for (i, &line) in lines.iter().enumerate() {
+        let after_blank = i == 0 || lines[i - 1].trim().is_empty();
+        if after_blank {
+            if let Some((header, number)) = scene_heading(line) {
+                finish(scenes.last_mut(), &mut body);
+                let mut scene = Scene::new("", number.unwrap_or(scenes.len() as i32 + 1));
+                scene.time = header.rsplit_once(" - ").map(|(_, time)| time.trim().to_string());
+                scene.header = header;
+                scenes.push(scene);
+                continue;
+            }
+        }
+        let Some(scene) = scenes.last_mut() else {
+            continue;
+        };
+        body.push(line);
+        let before_dialogue = lines.get(i + 1).is_some_and(|next| !next.trim().is_empty());
+        if after_blank && before_dialogue {
+            if let Some(name) = character_cue(line) {
+                if !scene.characters_present.contains(&name) {
+                    scene.characters_present.push(name);
+                }
+            }
+        }
+    }
+    finish(scenes.last_mut(), &mut body);
+    scenes
+}
+
+/// Stores the collected body lines as the scene's content.
+fn finish(scene: Option<&mut Scene>, body: &mut Vec<&str>) {
+    if let Some(scene) = scene {
+        scene.content = body.join("\n").trim().to_string();
+    }
+    body.clear();
+}
+
+/// Returns the header and forced scene number if `line` is a scene heading.
+fn scene_heading(line: &str) -> Option<(String, Option<i32>)> {
+    let line = line.trim();
+    let heading = match line.strip_prefix('.') {
+        // ".." starts an ellipsis, not a forced heading
+        Some(forced) if !forced.is_empty() && !forced.starts_with('.') => forced,
+        Some(_) => return None,
+        None => {
+            let is_heading = HEADING_PREFIXES.iter().any(|prefix| {
+                line.get(..prefix.len())
+                    .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+                    && line[prefix.len()..].starts_with(['.', ' '])
+            });
+            if !is_heading {
+                return None;
+            }
+            line
+        }
+    };
+    let (heading, number) = match heading.strip_suffix('#').and_then(|h| h.rsplit_once('#')) {
+        Some((heading, number)) => (heading.trim(), number.trim().parse().ok()),
+        None => (heading, None),
+    };
+    Some((heading.to_string(), number))
+}
+
+/// Returns the character name if `line` is a character cue. The caller
+/// checks that it follows a blank line and precedes dialogue.
+fn character_cue(line: &str) -> Option<String> {
+    let line = line.trim();
+    let (name, forced) = match line.strip_prefix('@') {
+        Some(name) => (name, true),
+        None => (line, false),
+    };
+    let name = name.trim_end_matches('^').trim_end();
+    let name = match name.find('(') {
+        Some(index) => name[..index].trim(),
+        None => name,
+    };
+    if name.is_empty() {
+        return None;
+    }
+    if !forced {
+        let all_caps =
+            name.chars().any(char::is_alphabetic) && !name.chars().any(char::is_lowercase);
+        // Transitions ("CUT TO:") and other forced elements
+        let other_element = name.ends_with(':') || name.starts_with(['!', '>', '~', '=', '#']);
+        if !all_caps || other_element {
+            return None;
+        }
+    }
+    Some(name.to_string())
+}
+
+/// Removes boneyard ("/* ... */") and note ("[[ ... ]]") spans.
+fn strip_comments(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    loop {
+        let next = [("/*", "*/"), ("[[", "]]")]
+            .into_iter()
+            .filter_map(|(open, close)| rest.find(open).map(|start| (start, open, close)))
+            .min();
+        let Some((start, open, close)) = next else {
+            out.push_str(rest);
+            return out;
+        };
+        out.push_str(&rest[..start]);
+        let inner = &rest[start + open.len()..];
+        rest = match inner.find(close) {
+            Some(end) => &inner[end + close.len()..],
+            None => "",
+        };
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = "\
+Title: The Heist
+Author: Someone
+
+INT. BANK VAULT - NIGHT
+
+Dust hangs in the flashlight beam. [[check lighting]]
+
+RICHIE (V.O.)
+We had one shot.
+
+MAYA ^
+Then don't miss.
+
+CUT TO:
+
+.ROOFTOP #12#
+
+@McAllister
+You're late.
+
+/* Cut scene
+EXT. STREET - DAY
+*/
+RICHIE
+Traffic.
+";
+
+    #[test]
+    fn test_parse_fountain_scenes() {
+        let scenes = parse_fountain(SCRIPT);
+        assert_eq!(scenes.len(), 2);
+
+        let vault = &scenes[0];
+        assert_eq!(vault.header, "INT. BANK VAULT - NIGHT");
+        assert_eq!(vault.scene_number, 1);
+        assert_eq!(vault.time.as_deref(), Some("NIGHT"));
+        assert_eq!(vault.characters_present, vec!["RICHIE", "MAYA"]);
+        assert!(vault.content.starts_with("Dust hangs in the flashlight beam."));
+        assert!(!vault.content.contains("check lighting"));
+        assert!(vault.content.ends_with("CUT TO:"));
+
+        let roof = &scenes[1];
+        assert_eq!(roof.header, "ROOFTOP");
+        assert_eq!(roof.scene_number, 12);
+        assert_eq!(roof.characters_present, vec!["McAllister", "RICHIE"]);
+        assert!(!roof.content.contains("STREET"));
+    }
+
+    #[test]
+    fn test_scene_heading_rules() {
+        assert!(scene_heading("int. office - day").is_some());
+        assert!(scene_heading("I/E CAR - MOVING").is_some());
+        assert!(scene_heading("INTERIOR DESIGN").is_none());
+        assert!(scene_heading("...and then").is_none());
+        assert_eq!(scene_heading("EXT. PARK #4A#"), Some(("EXT. PARK".to_string(), None)));
+    }
+}
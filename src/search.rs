@@ -0,0 +1,198 @@
+//! Full-text search over document text (`search` feature).
+//!
+//! `SearchIndex` is an in-memory inverted index from lowercase terms to the
+//! entries that contain them. Each manager keeps one and syncs it with the
+//! current state before searching: entries whose text is unchanged (by hash)
+//! are skipped, so only nodes, scenes and shots edited since the last search
+//! are re-tokenized. The index is local and never written to the document.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+/// Inverted index over entries of text, keyed by entry ID.
+#[derive(Debug, Clone, Default)]
+pub struct SearchIndex {
+    /// Term -> entry ID -> occurrences of the term in the entry.
+    postings: HashMap<String, HashMap<String, u32>>,
+    /// Indexed entries by ID.
+    entries: HashMap<String, IndexedEntry>,
+}
+
+#[derive(Debug, Clone)]
+struct IndexedEntry {
+    /// Hash of the indexed text, to skip unchanged entries.
+    hash: u64,
+    /// Occurrences per term.
+    terms: HashMap<String, u32>,
+    /// Total number of terms.
+    length: u32,
+}
+
+impl SearchIndex {
+    /// Creates an empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of indexed entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if nothing is indexed.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Indexes `text` under `id`, replacing what was indexed before.
+    /// Returns false (and does nothing) if the text is unchanged.
+    pub fn update(&mut self, id: &str, text: &str) -> bool {
+        let hash = hash_text(text);
+        if self.entries.get(id).is_some_and(|entry| entry.hash == hash) {
+            return false;
+        }
+        self.remove(id);
+
+        let mut terms: HashMap<String, u32> = HashMap::new();
+        let mut length = 0;
+        for term in tokenize(text) {
+            *terms.entry(term).or_default() += 1;
+            length += 1;
+        }
+        for (term, count) in &terms {
+            self.postings
+                .entry(term.clone())
+                .or_default()
+                .insert(id.to_string(), *count);
+        }
+        self.entries.insert(id.to_string(), IndexedEntry { hash, terms, length });
+        true
+    }
+
+    /// Removes `id` from the index. Returns false if it was not indexed.
+    pub fn remove(&mut self, id: &str) -> bool {
+        let Some(entry) = self.entries.remove(id) else {
+            return false;
+        };
+        for term in entry.terms.keys() {
+            if let Some(ids) = self.postings.get_mut(term) {
+                ids.remove(id);
+                if ids.is_empty() {
+                    self.postings.remove(term);
+                }
+            }
+        }
+        true
+    }
+
+    /// Removes every entry.
+    pub fn clear(&mut self) {
+        self.postings.clear();
+        self.entries.clear();
+    }
+
+    /// Makes the index hold exactly `entries` (ID, text): changed entries
+    /// are re-indexed and entries not listed are removed.
+    pub fn sync(&mut self, entries: impl IntoIterator<Item = (String, String)>) {
+        let mut seen = HashSet::new();
+        for (id, text) in entries {
+            self.update(&id, &text);
+            seen.insert(id);
+        }
+        if seen.len() < self.entries.len() {
+            let stale: Vec<String> = self
+                .entries
+                .keys()
+                .filter(|id| !seen.contains(*id))
+                .cloned()
+                .collect();
+            for id in stale {
+                self.remove(&id);
+            }
+        }
+    }
+
+    /// Returns the entries containing every term of `query` with a
+    /// relevance score, best first (ties by ID). Matching is
+    /// case-insensitive on whole words.
+    pub fn search(&self, query: &str) -> Vec<(String, f64)> {
+        let terms: HashSet<String> = tokenize(query).collect();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+        let total = self.entries.len() as f64;
+        let mut scores: HashMap<&str, (f64, usize)> = HashMap::new();
+        for term in &terms {
+            let Some(ids) = self.postings.get(term) else {
+                return Vec::new();
+            };
+            // Rarer terms weigh more; long entries are normalized down
+            let idf = (1.0 + total / ids.len() as f64).ln();
+            for (id, count) in ids {
+                let length = self.entries[id].length.max(1) as f64;
+                let score = scores.entry(id.as_str()).or_default();
+                score.0 += *count as f64 / length.sqrt() * idf;
+                score.1 += 1;
+            }
+        }
+        let mut hits: Vec<(String, f64)> = scores
+            .into_iter()
+            .filter(|(_, (_, matched))| *matched == terms.len())
+            .map(|(id, (score, _))| (id.to_string(), score))
+            .collect();
+        hits.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        hits
+    }
+}
+
+/// Splits text into lowercase alphanumeric words.
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
+fn hash_text(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_ranks_and_requires_all_terms() {
+        let mut index = SearchIndex::new();
+        index.update("a", "A sunset over the ocean");
+        index.update("b", "Sunset, sunset city skyline");
+        index.update("c", "Forest at dawn");
+
+        let hits = index.search("SUNSET");
+        assert_eq!(hits.iter().map(|(id, _)| id.as_str()).collect::<Vec<_>>(), vec!["b", "a"]);
+        assert_eq!(index.search("sunset ocean").len(), 1);
+        assert!(index.search("sunset forest").is_empty());
+        assert!(index.search("  ").is_empty());
+    }
+
+    #[test]
+    fn test_update_and_sync_replace_entries() {
+        let mut index = SearchIndex::new();
+        assert!(index.update("a", "red car"));
+        assert!(!index.update("a", "red car"));
+        assert!(index.update("a", "blue car"));
+        assert!(index.search("red").is_empty());
+
+        index.update("b", "blue boat");
+        index.sync(vec![("b".to_string(), "blue boat".to_string())]);
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.search("blue")[0].0, "b");
+        assert!(index.search("car").is_empty());
+    }
+}
@@ -0,0 +1,172 @@
+//! Activity feed derived from the change history.
+//!
+//! `SequenceManager::activity()` walks the changes made since a set of heads
+//! and compares each node before and after every change, turning the raw
+//! operations into typed events for the sidebar ("Alice queued node-3").
+//! Nothing extra is written to the document: events are re-derived from
+//! history on each call, so they are as complete as the history itself
+//! (compacted documents start their feed at the compaction).
+
+use std::fmt;
+
+use automerge::{AutoCommit, ChangeHash, ObjId, Prop, ReadDoc, ScalarValue, Value, ROOT};
+use serde::{Deserialize, Serialize};
+
+use crate::error::CollabResult;
+
+/// What happened in an activity event.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type")]
+pub enum ActivityKind {
+    /// A node was added to the document.
+    NodeCreated { id: String },
+    /// A node's status changed.
+    StatusChanged { id: String, from: String, to: String },
+    /// An output was appended to a node.
+    OutputAdded { id: String, url: String },
+}
+
+impl fmt::Display for ActivityKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActivityKind::NodeCreated { id } => write!(f, "created {}", id),
+            ActivityKind::StatusChanged { id, from, to } => {
+                write!(f, "changed {} from {} to {}", id, from, to)
+            }
+            ActivityKind::OutputAdded { id, url } => write!(f, "added output {} to {}", url, id),
+        }
+    }
+}
+
+/// An activity event with the change that caused it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ActivityEvent {
+    #[serde(flatten)]
+    pub kind: ActivityKind,
+    /// Hex-encoded actor ID that authored the change.
+    pub actor: String,
+    /// Timestamp recorded with the change (milliseconds since epoch, 0 if unset).
+    pub timestamp: i64,
+    /// Hex-encoded change hash.
+    pub hash: String,
+}
+
+/// Collects the events of every change not covered by `since_heads`, in
+/// causal order. Empty `since_heads` covers the whole history.
+pub(crate) fn collect(
+    doc: &mut AutoCommit,
+    since_heads: &[ChangeHash],
+) -> CollabResult<Vec<ActivityEvent>> {
+    let changes: Vec<(ChangeHash, Vec<ChangeHash>, String, i64)> = doc
+        .get_changes(since_heads)
+        .iter()
+        .map(|change| {
+            (
+                change.hash(),
+                change.deps().to_vec(),
+                change.actor_id().to_hex_string(),
+                change.timestamp(),
+            )
+        })
+        .collect();
+
+    let mut events = Vec::new();
+    for (hash, before, actor, timestamp) in changes {
+        let after = [hash];
+        for kind in change_events(doc, &before, &after)? {
+            events.push(ActivityEvent {
+                kind,
+                actor: actor.clone(),
+                timestamp,
+                hash: hash.to_string(),
+            });
+        }
+    }
+    Ok(events)
+}
+
+/// Compares every node between two sets of heads.
+fn change_events(
+    doc: &AutoCommit,
+    before: &[ChangeHash],
+    after: &[ChangeHash],
+) -> CollabResult<Vec<ActivityKind>> {
+    let mut events = Vec::new();
+    let Some(gens_after) = object_at(doc, &ROOT, "generations", after)? else {
+        return Ok(events);
+    };
+    let gens_before = object_at(doc, &ROOT, "generations", before)?;
+
+    let mut ids: Vec<String> = doc.keys_at(&gens_after, after).collect();
+    ids.sort();
+    for id in ids {
+        let Some(node_after) = object_at(doc, &gens_after, id.as_str(), after)? else {
+            continue;
+        };
+        let node_before = match &gens_before {
+            Some(gens) => object_at(doc, gens, id.as_str(), before)?,
+            None => None,
+        };
+        let Some(node_before) = node_before else {
+            events.push(ActivityKind::NodeCreated { id });
+            continue;
+        };
+
+        let from = string_at(doc, &node_before, "status", before)?.unwrap_or_default();
+        let to = string_at(doc, &node_after, "status", after)?.unwrap_or_default();
+        if from != to {
+            events.push(ActivityKind::StatusChanged {
+                id: id.clone(),
+                from,
+                to,
+            });
+        }
+
+        let Some(outputs) = object_at(doc, &node_after, "outputs", after)? else {
+            continue;
+        };
+        let old_len = match object_at(doc, &node_before, "outputs", before)? {
+            Some(old) if old == outputs => doc.length_at(&outputs, before),
+            _ => 0,
+        };
+        for index in old_len..doc.length_at(&outputs, after) {
+            if let Some(output) = object_at(doc, &outputs, index, after)? {
+                let url = string_at(doc, &output, "url", after)?.unwrap_or_default();
+                events.push(ActivityKind::OutputAdded { id: id.clone(), url });
+            }
+        }
+    }
+    Ok(events)
+}
+
+/// Returns the object at `obj[prop]` as of `heads`.
+fn object_at(
+    doc: &AutoCommit,
+    obj: &ObjId,
+    prop: impl Into<Prop>,
+    heads: &[ChangeHash],
+) -> CollabResult<Option<ObjId>> {
+    if heads.is_empty() {
+        return Ok(None);
+    }
+    Ok(match doc.get_at(obj, prop, heads)? {
+        Some((Value::Object(_), id)) => Some(id),
+        _ => None,
+    })
+}
+
+/// Returns the string at `obj[key]` as of `heads`.
+fn string_at(
+    doc: &AutoCommit,
+    obj: &ObjId,
+    key: &str,
+    heads: &[ChangeHash],
+) -> CollabResult<Option<String>> {
+    Ok(match doc.get_at(obj, key, heads)? {
+        Some((Value::Scalar(value), _)) => match value.as_ref() {
+            ScalarValue::Str(s) => Some(s.to_string()),
+            _ => None,
+        },
+        _ => None,
+    })
+}
@@ -0,0 +1,141 @@
+//! Dependency graph over node inputs.
+//!
+//! A node's `inputs` name the upstream nodes whose outputs it consumes
+//! (t2i → i2v → upscale). Workers call `SequenceManager::topological_order()`
+//! to run nodes after everything they depend on. `link_input()` refuses
+//! links that would close a loop, but concurrent links from two peers can
+//! still merge into one, so ordering reports cycles instead of assuming
+//! there are none. Inputs naming missing nodes are ignored.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::error::{CollabError, CollabResult};
+use super::model::DocumentRoot;
+
+/// Returns true if `node_id` depends on `target`, directly or through other
+/// nodes (or is `target` itself).
+pub fn depends_on(root: &DocumentRoot, node_id: &str, target: &str) -> bool {
+    let mut stack = vec![node_id];
+    let mut seen = HashSet::new();
+    while let Some(id) = stack.pop() {
+        if id == target {
+            return true;
+        }
+        if !seen.insert(id) {
+            continue;
+        }
+        if let Some(node) = root.generations.get(id) {
+            stack.extend(node.inputs.iter().map(|input| input.node_id.as_str()));
+        }
+    }
+    false
+}
+
+/// Orders every node after the nodes it takes inputs from. Independent
+/// nodes keep their sequence order, followed by nodes missing from the
+/// order (by ID). Fails with `DependencyCycle` naming the nodes involved.
+pub fn topological_order(root: &DocumentRoot) -> CollabResult<Vec<String>> {
+    let order = root.ordered_ids();
+    let ordered: HashSet<&String> = order.iter().collect();
+    let mut unordered: Vec<&String> = root
+        .generations
+        .keys()
+        .filter(|id| !ordered.contains(id))
+        .collect();
+    unordered.sort();
+    let mut seen = HashSet::new();
+    let nodes: Vec<&str> = order
+        .iter()
+        .chain(unordered)
+        .map(String::as_str)
+        .filter(|id| root.generations.contains_key(*id) && seen.insert(*id))
+        .collect();
+
+    // Remaining upstream count per node, and downstream nodes per node
+    let mut pending: HashMap<&str, usize> = HashMap::new();
+    let mut downstream: HashMap<&str, Vec<&str>> = HashMap::new();
+    for &id in &nodes {
+        let upstream: HashSet<&str> = root.generations[id]
+            .inputs
+            .iter()
+            .map(|input| input.node_id.as_str())
+            .filter(|up| root.generations.contains_key(*up))
+            .collect();
+        pending.insert(id, upstream.len());
+        for up in upstream {
+            downstream.entry(up).or_default().push(id);
+        }
+    }
+
+    // Repeatedly take the first ready node in sequence order
+    let mut result = Vec::with_capacity(nodes.len());
+    let mut done = HashSet::new();
+    while result.len() < nodes.len() {
+        let next = nodes
+            .iter()
+            .find(|id| !done.contains(*id) && pending[*id] == 0)
+            .copied();
+        let Some(id) = next else {
+            let stuck: Vec<&str> = nodes.into_iter().filter(|id| !done.contains(id)).collect();
+            return Err(CollabError::dependency_cycle(stuck.join(", ")));
+        };
+        done.insert(id);
+        result.push(id.to_string());
+        for down in downstream.get(id).into_iter().flatten() {
+            if let Some(count) = pending.get_mut(down) {
+                *count -= 1;
+            }
+        }
+    }
+    Ok(result)
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequence::model::{GenerationNode, NodeInputRef};
+
+    fn node(id: &str, inputs: &[&str]) -> GenerationNode {
+        let mut node = GenerationNode::new(id, "t2i");
+        node.inputs = inputs.iter().map(|up| NodeInputRef::new(*up, 0)).collect();
+        node
+    }
+
+    fn root(nodes: Vec<GenerationNode>) -> DocumentRoot {
+        let mut root = DocumentRoot::new();
+        for node in nodes {
+            root.generations.insert(node.id.clone(), node);
+        }
+        root
+    }
+
+    #[test]
+    fn test_topological_order_respects_inputs() {
+        // Listed downstream-first; "other" is independent
+        let root = root(vec![
+            node("upscale", &["i2v"]),
+            node("i2v", &["t2i", "missing"]),
+            node("other", &[]),
+            node("t2i", &[]),
+        ]);
+        assert_eq!(
+            topological_order(&root).unwrap(),
+            vec!["other", "t2i", "i2v", "upscale"]
+        );
+        assert!(depends_on(&root, "upscale", "t2i"));
+        assert!(!depends_on(&root, "t2i", "upscale"));
+    }
+
+    #[test]
+    fn test_cycle_is_reported() {
+        let root = root(vec![node("a", &["b"]), node("b", &["a"]), node("c", &[])]);
+        match topological_order(&root) {
+            Err(CollabError::DependencyCycle(nodes)) => assert_eq!(nodes, "a, b"),
+            other => panic!("expected a cycle, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! Range validation for generation settings.
+//!
+//! Setting setters check values against `SettingsLimits` before writing, so
+//! out-of-range values from buggy clients are rejected locally instead of
+//! being synced to every peer.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CollabError, CollabResult};
+use super::model::GenerationSettings;
+
+/// Allowed ranges for generation settings. `None` disables a check.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SettingsLimits {
+    /// Inclusive (min, max) for `cfg`.
+    pub cfg: Option<(f64, f64)>,
+    /// Inclusive (min, max) for `num_steps`.
+    pub num_steps: Option<(i32, i32)>,
+    /// Inclusive (min, max) for `fps`.
+    pub fps: Option<(i32, i32)>,
+    /// `width` and `height` must be positive multiples of this value.
+    pub dimension_multiple: Option<i32>,
+}
+
+impl SettingsLimits {
+    /// Limits that accept any value.
+    pub fn unrestricted() -> Self {
+        Self {
+            cfg: None,
+            num_steps: None,
+            fps: None,
+            dimension_multiple: None,
+        }
+    }
+
+    /// Checks a cfg value.
+    pub fn check_cfg(&self, cfg: f64) -> CollabResult<()> {
+        match self.cfg {
+            Some((min, max)) if !(min..=max).contains(&cfg) => Err(CollabError::invalid_setting(
+                "cfg",
+                format!("{} is outside {}..={}", cfg, min, max),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks a num_steps value.
+    pub fn check_num_steps(&self, steps: i32) -> CollabResult<()> {
+        check_int_range("num_steps", steps, self.num_steps)
+    }
+
+    /// Checks an fps value.
+    pub fn check_fps(&self, fps: i32) -> CollabResult<()> {
+        check_int_range("fps", fps, self.fps)
+    }
+
+    /// Checks a width or height value.
+    pub fn check_dimension(&self, field: &str, value: i32) -> CollabResult<()> {
+        match self.dimension_multiple {
+            Some(multiple) if value <= 0 || value % multiple != 0 => {
+                Err(CollabError::invalid_setting(
+                    field,
+                    format!("{} is not a positive multiple of {}", value, multiple),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks every set field of `settings`.
+    pub fn validate(&self, settings: &GenerationSettings) -> CollabResult<()> {
+        if let Some(cfg) = settings.cfg {
+            self.check_cfg(cfg)?;
+        }
+        if let Some(steps) = settings.num_steps {
+            self.check_num_steps(steps)?;
+        }
+        if let Some(fps) = settings.fps {
+            self.check_fps(fps)?;
+        }
+        if let Some(width) = settings.width {
+            self.check_dimension("width", width)?;
+        }
+        if let Some(height) = settings.height {
+            self.check_dimension("height", height)?;
+        }
+        Ok(())
+    }
+
+    /// Checks the fields that differ between `old` and `new`, so values that
+    /// were already out of range (e.g. from before the limits were
+    /// tightened) don't block unrelated edits.
+    pub fn validate_changes(
+        &self,
+        old: &GenerationSettings,
+        new: &GenerationSettings,
+    ) -> CollabResult<()> {
+        let changed = GenerationSettings {
+            cfg: new.cfg.filter(|_| new.cfg != old.cfg),
+            num_steps: new.num_steps.filter(|_| new.num_steps != old.num_steps),
+            fps: new.fps.filter(|_| new.fps != old.fps),
+            width: new.width.filter(|_| new.width != old.width),
+            height: new.height.filter(|_| new.height != old.height),
+            ..Default::default()
+        };
+        self.validate(&changed)
+    }
+}
+
+impl Default for SettingsLimits {
+    /// cfg 0–30, steps 1–150, fps 1–120, width/height multiples of 8.
+    fn default() -> Self {
+        Self {
+            cfg: Some((0.0, 30.0)),
+            num_steps: Some((1, 150)),
+            fps: Some((1, 120)),
+            dimension_multiple: Some(8),
+        }
+    }
+}
+
+fn check_int_range(field: &str, value: i32, range: Option<(i32, i32)>) -> CollabResult<()> {
+    match range {
+        Some((min, max)) if !(min..=max).contains(&value) => Err(CollabError::invalid_setting(
+            field,
+            format!("{} is outside {}..={}", value, min, max),
+        )),
+        _ => Ok(()),
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_limits() {
+        let limits = SettingsLimits::default();
+        assert!(limits.check_cfg(7.5).is_ok());
+        assert!(limits.check_cfg(31.0).is_err());
+        assert!(limits.check_num_steps(0).is_err());
+        assert!(limits.check_fps(120).is_ok());
+        assert!(limits.check_dimension("width", 1024).is_ok());
+        assert!(limits.check_dimension("width", 1000).is_ok());
+        assert!(limits.check_dimension("height", 1001).is_err());
+        assert!(limits.check_dimension("height", 0).is_err());
+    }
+
+    #[test]
+    fn test_unrestricted_limits() {
+        let limits = SettingsLimits::unrestricted();
+        let settings = GenerationSettings::new().with_cfg(100.0).with_width(7);
+        assert!(limits.validate(&settings).is_ok());
+    }
+
+    #[test]
+    fn test_validate_changes_ignores_untouched_fields() {
+        let limits = SettingsLimits::default();
+        let legacy = GenerationSettings::new().with_cfg(45.0).with_width(1001);
+        assert!(limits.validate(&legacy).is_err());
+        assert!(limits.validate_changes(&legacy, &legacy.clone().with_seed(7)).is_ok());
+        assert!(limits.validate_changes(&legacy, &legacy.clone().with_cfg(50.0)).is_err());
+        assert!(limits.validate_changes(&legacy, &legacy.clone().with_num_steps(0)).is_err());
+    }
+
+    #[test]
+    fn test_validate_reports_field() {
+        let settings = GenerationSettings::new().with_num_steps(500);
+        match SettingsLimits::default().validate(&settings) {
+            Err(CollabError::InvalidSetting { field, .. }) => assert_eq!(field, "num_steps"),
+            other => panic!("expected InvalidSetting, got {:?}", other),
+        }
+    }
+}
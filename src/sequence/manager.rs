@@ -5,14 +5,65 @@
 //! - High-level operations via autosurgeon (hydrate/reconcile) for bulk updates
 //! - Targeted settings updates via direct put operations (O(1) instead of O(N))
 
+use std::collections::BTreeMap;
+
 use automerge::{
-    transaction::Transactable, AutoCommit, ChangeHash, ObjId, ReadDoc, ScalarValue, Value,
-    ROOT,
+    sync::SyncDoc,
+    transaction::{CommitOptions, Transactable},
+    ActorId, AutoCommit, ChangeHash, ObjId, ObjType, ReadDoc, ScalarValue, Value, ROOT,
 };
-use autosurgeon::{hydrate, reconcile};
+use autosurgeon::{hydrate, hydrate_prop, reconcile, reconcile_insert, reconcile_prop};
 
+use crate::changes::{collect_change_info, field_authors, ChangeInfo, FieldAuthor};
+use crate::bundle::{Bundle, BundleMetadata};
+use crate::checkpoint::{self, SyncCheckpoint};
+use crate::cherry_pick::{self, CherryPickReport};
+use crate::acl::{Acl, Action, Enforcement, PermissionContext, Role};
+use crate::chunk::{self, ChunkAssembler};
+use crate::conflict::{self, ConflictValue};
+use crate::compact::{self, HistoryStats};
+use crate::dirty;
+use crate::comment::{self, Comment};
+#[cfg(feature = "compression")]
+use crate::compression;
+#[cfg(feature = "crypto")]
+use crate::crypto::{self, KeyParams};
+use crate::envelope::{self, SaveOptions};
 use crate::error::{CollabError, CollabResult};
-use super::model::{DocumentRoot, GenerationNode, GenerationSettings, OutputAsset};
+use crate::link::{DocRef, DocResolver};
+#[cfg(feature = "export")]
+use crate::export;
+use crate::order_key;
+use crate::repair::RepairReport;
+use crate::sanitize;
+use crate::snapshot::NamedSnapshot;
+#[cfg(feature = "search")]
+use crate::search::SearchIndex;
+use crate::storage::{AttachedStore, DocumentStore};
+use crate::sync::{self, PeerState, PrioritizedFrames};
+use crate::sweep::{StalledItem, SweepConfig, SweepReport, PROCESSING_STATUS, STALLED_STATUS};
+use crate::presence::{Awareness, Cursor, PeerPresence};
+use crate::raw::hydrate_optional;
+use super::activity::{self, ActivityEvent};
+use super::graph;
+use super::limits::SettingsLimits;
+use super::options::ManagerOptions;
+use super::presence::SequencePresence;
+use super::queue::JobClaim;
+use super::status::{GenerationStatus, StatusTransitions};
+use super::units::{DurationSecs, ResolutionPreset};
+use super::model::{
+    DocumentRoot, DocumentSeed, GenerationNode, GenerationSettings, InputAsset, LoraRef,
+    Keyframe, NodeFilter, NodeInputRef, NodeSummary, OutputAsset, SettingValue,
+    TimelinePlacement, VariationTree,
+};
+use super::model::{output_display_order, output_keys_for, stored_output_keys};
+
+/// Keys whose subtrees are sent last by prioritized sync.
+const HEAVY_SYNC_KEYS: &[&str] = &["outputs", "comments"];
+
+/// Document type recorded in backup bundles.
+const BUNDLE_KIND: &str = "sequence";
 
 /// The main collaborative document manager for AI generation sequences.
 ///
@@ -32,6 +83,27 @@ pub struct SequenceManager {
     /// Cached ObjId for the "generations" map - saves 2 lookups per operation.
     /// Invalidated on from_bytes() and merge().
     cached_generations_obj: Option<ObjId>,
+    /// Ranges enforced by the settings setters.
+    settings_limits: SettingsLimits,
+    /// Transitions allowed by `set_status_checked()`.
+    status_transitions: StatusTransitions,
+    /// Enforcement modes (see `ManagerOptions`).
+    options: ManagerOptions,
+    /// Completed nodes exempted from `freeze_completed` via `unfreeze()`.
+    unfrozen: std::collections::HashSet<String>,
+    /// Local user and ACL enforcement mode (see `crate::acl`).
+    permissions: PermissionContext,
+    /// Denials recorded in warn mode (see `take_permission_warnings()`).
+    permission_warnings: Vec<String>,
+    /// Local full-text index over titles and prompts (see `search_prompts()`).
+    #[cfg(feature = "search")]
+    search_index: SearchIndex,
+    /// Ephemeral peer presence (not part of the document).
+    awareness: Awareness<SequencePresence>,
+    /// Store that receives every committed change (see `attach_store()`).
+    store: Option<AttachedStore>,
+    /// Partially received sync chunks (see `apply_sync_chunk()`).
+    chunks: ChunkAssembler,
 }
 
 impl SequenceManager {
@@ -44,28 +116,321 @@ impl SequenceManager {
         let mut doc = AutoCommit::new();
         let root = DocumentRoot::default();
         reconcile(&mut doc, &root).expect("Failed to initialize document");
+        let awareness = Awareness::new(doc.get_actor().to_hex_string());
         Self {
             doc,
             cached_state: Some(root),
             cached_generations_obj: None, // Will be lazily populated
+            settings_limits: SettingsLimits::default(),
+            status_transitions: StatusTransitions::default(),
+            options: ManagerOptions::default(),
+            unfrozen: std::collections::HashSet::new(),
+            permissions: PermissionContext::default(),
+            permission_warnings: Vec::new(),
+            #[cfg(feature = "search")]
+            search_index: SearchIndex::new(),
+            awareness,
+            store: None,
+            chunks: ChunkAssembler::new(),
+        }
+    }
+
+    /// Creates a new SequenceManager pre-populated from a seed.
+    ///
+    /// The schema and all seed content are written as a single change.
+    pub fn new_with_seed(seed: DocumentSeed) -> CollabResult<Self> {
+        Self::from_root(seed.into_root(), "Initialize document")
+    }
+
+    /// Creates a SequenceManager from a JSON document produced by `to_json()`.
+    ///
+    /// The imported content is written as a single change. Fails if the JSON
+    /// does not match the schema or `sequence_order` references unknown or
+    /// duplicate node IDs.
+    pub fn from_json(json: &str) -> CollabResult<Self> {
+        let root: DocumentRoot =
+            serde_json::from_str(json).map_err(|e| CollabError::serialization(e.to_string()))?;
+
+        let mut seen = std::collections::HashSet::new();
+        for id in root.generations.order() {
+            if !root.generations.contains_key(id) {
+                return Err(CollabError::node_not_found(id.as_str()));
+            }
+            if !seen.insert(id) {
+                return Err(CollabError::schema_violation(format!(
+                    "duplicate id '{}' in sequence_order",
+                    id
+                )));
+            }
         }
+
+        Self::from_root(root, "Import JSON")
+    }
+
+    /// Writes `root` into a fresh document as a single change.
+    fn from_root(root: DocumentRoot, message: &str) -> CollabResult<Self> {
+        let mut doc = AutoCommit::new();
+        reconcile(&mut doc, &root)?;
+        doc.commit_with(CommitOptions::default().with_message(message));
+        let awareness = Awareness::new(doc.get_actor().to_hex_string());
+        Ok(Self {
+            doc,
+            cached_state: Some(root),
+            cached_generations_obj: None,
+            settings_limits: SettingsLimits::default(),
+            status_transitions: StatusTransitions::default(),
+            options: ManagerOptions::default(),
+            unfrozen: std::collections::HashSet::new(),
+            permissions: PermissionContext::default(),
+            permission_warnings: Vec::new(),
+            #[cfg(feature = "search")]
+            search_index: SearchIndex::new(),
+            awareness,
+            store: None,
+            chunks: ChunkAssembler::new(),
+        })
     }
 
     /// Creates a SequenceManager from saved binary data.
+    ///
+    /// Reads `save_enveloped()` output as well as bare bytes (see
+    /// `crate::envelope`). With the `compression` feature, gzip output of
+    /// `save_compressed()` is detected and decompressed transparently.
     pub fn from_bytes(bytes: &[u8]) -> CollabResult<Self> {
-        let doc = AutoCommit::load(bytes)?;
+        let doc = AutoCommit::load(&envelope::decode(bytes)?)?;
+        let awareness = Awareness::new(doc.get_actor().to_hex_string());
         Ok(Self {
             doc,
             cached_state: None,
             cached_generations_obj: None, // Must re-discover after load
+            settings_limits: SettingsLimits::default(),
+            status_transitions: StatusTransitions::default(),
+            options: ManagerOptions::default(),
+            unfrozen: std::collections::HashSet::new(),
+            permissions: PermissionContext::default(),
+            permission_warnings: Vec::new(),
+            #[cfg(feature = "search")]
+            search_index: SearchIndex::new(),
+            awareness,
+            store: None,
+            chunks: ChunkAssembler::new(),
         })
     }
 
+    /// Loads a document saved with `save_encrypted()` or with
+    /// `save_enveloped()` and key params.
+    #[cfg(feature = "crypto")]
+    pub fn from_encrypted_bytes(bytes: &[u8], key_params: &KeyParams) -> CollabResult<Self> {
+        Self::from_bytes(&envelope::decrypt(bytes, key_params)?)
+    }
+
+    /// Exports the full document as JSON.
+    ///
+    /// `sequence_order` keeps the document order; `generations` keys are
+    /// sorted so the output is stable across calls.
+    pub fn to_json(&mut self) -> CollabResult<String> {
+        let state = self.get_state_ref()?;
+        // Going through Value sorts the generations map keys
+        let value =
+            serde_json::to_value(state).map_err(|e| CollabError::serialization(e.to_string()))?;
+        serde_json::to_string(&value).map_err(|e| CollabError::serialization(e.to_string()))
+    }
+
     /// Saves the document to binary format.
     pub fn save(&mut self) -> Vec<u8> {
         self.doc.save()
     }
 
+    /// Saves only the changes made since the last `save()` or
+    /// `save_incremental()`. Returns an empty Vec if nothing changed.
+    ///
+    /// Append the result to a stored snapshot (or pass it to
+    /// `load_incremental()`); autosave loops upload kilobytes instead of the
+    /// whole document.
+    ///
+    /// A document opened with `from_bytes()` has not been saved yet, so the
+    /// first call after loading returns its whole history. Call
+    /// `save_incremental()` once right after loading and discard the result
+    /// to start from the loaded state.
+    pub fn save_incremental(&mut self) -> Vec<u8> {
+        self.doc.save_incremental()
+    }
+
+    /// Saves the changes not covered by `heads` (e.g. the heads recorded at
+    /// the last upload). Returns an empty Vec if there are none.
+    pub fn save_since(&mut self, heads: &[ChangeHash]) -> Vec<u8> {
+        self.generate_sync_message(heads).unwrap_or_default()
+    }
+
+    /// Returns the changes not covered by `checkpoint` (see
+    /// `crate::checkpoint`). Returns an empty Vec if there are none.
+    pub fn changes_since_checkpoint(&mut self, checkpoint: &SyncCheckpoint) -> Vec<u8> {
+        let heads = checkpoint::known_heads(&mut self.doc, checkpoint);
+        self.save_since(&heads)
+    }
+
+    /// Returns a checkpoint at the current heads. Take it right before
+    /// sending `changes_since_checkpoint()` and keep it once the upload is
+    /// acknowledged.
+    pub fn mark_synced(&mut self) -> SyncCheckpoint {
+        SyncCheckpoint::from_heads(&self.get_heads())
+    }
+
+    /// Loads changes produced by `save_incremental()` / `save_since()` into
+    /// this document. Returns the number of operations applied.
+    pub fn load_incremental(&mut self, data: &[u8]) -> CollabResult<usize> {
+        self.invalidate_all_caches(); // Must invalidate topology cache on load
+        let ops = self.doc.load_incremental(data)?;
+        self.flush_store()?;
+        Ok(ops)
+    }
+
+    /// Returns the size of the change history (see `compact_if()`).
+    pub fn history_stats(&mut self) -> HistoryStats {
+        compact::history_stats(&mut self.doc)
+    }
+
+    /// Saves the current state as a fresh document with a single change and
+    /// a new actor, dropping the change history (see `crate::compact`).
+    ///
+    /// The result is a new baseline: peers must reload it instead of syncing.
+    /// This manager keeps its full history.
+    pub fn save_compact(&mut self) -> CollabResult<Vec<u8>> {
+        let state = self.get_state_ref()?;
+        compact::compact_state(state)
+    }
+
+    /// Runs `save_compact()` if `policy` approves the current history stats.
+    ///
+    /// # Example
+    /// ```ignore
+    /// if let Some(bytes) = manager.compact_if(|stats| stats.changes > 10_000)? {
+    ///     store.replace_snapshot(&bytes)?;
+    /// }
+    /// ```
+    pub fn compact_if<F>(&mut self, policy: F) -> CollabResult<Option<Vec<u8>>>
+    where
+        F: FnOnce(&HistoryStats) -> bool,
+    {
+        if policy(&self.history_stats()) {
+            self.save_compact().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Saves the current state as a fresh document like `save_compact()`,
+    /// with every string last written by one of `remove_actors` replaced by
+    /// `[redacted]` (see `crate::sanitize`). Use it to honour erasure
+    /// requests; the result must replace every stored copy of the document.
+    pub fn export_sanitized(&self, remove_actors: &[ActorId]) -> CollabResult<Vec<u8>> {
+        sanitize::export_sanitized(&self.doc, remove_actors)
+    }
+
+    /// Replaces a node field (dotted for settings, e.g. `settings.prompt`)
+    /// with `[redacted]` and returns the current state compacted so the old
+    /// value exists in no change (see `save_compact()`).
+    ///
+    /// The manager switches to the compacted document, keeping its actor,
+    /// and re-snapshots any attached store. Every other copy must be
+    /// replaced with the returned bytes and peers must reload it: syncing
+    /// with an old copy would bring the value back.
+    pub fn redact_field(&mut self, node_id: &str, field: &str) -> CollabResult<Vec<u8>> {
+        self.begin_edit(Action::Edit)?;
+        let node_obj = self.get_node_obj(node_id)?;
+        sanitize::redact_field(&mut self.doc, &node_obj, field)?;
+        let bytes = self.save_compact()?;
+        let actor = self.doc.get_actor().clone();
+        self.doc = AutoCommit::load(&bytes)?.with_actor(actor);
+        self.invalidate_all_caches();
+        self.snapshot_store()?;
+        Ok(bytes)
+    }
+
+    /// Packs the snapshot, the full change history, `checkpoints` and
+    /// metadata (schema version, `doc_id`) into one backup archive (see
+    /// `crate::bundle`).
+    pub fn export_bundle(
+        &mut self,
+        doc_id: &str,
+        checkpoints: &BTreeMap<String, SyncCheckpoint>,
+    ) -> CollabResult<Vec<u8>> {
+        Bundle::from_doc(&mut self.doc, BUNDLE_KIND, doc_id, checkpoints).encode()
+    }
+
+    /// Restores a manager from `export_bundle()` output. Returns the bundle
+    /// metadata with it, including the checkpoints to resume uploads from.
+    pub fn import_bundle(bytes: &[u8]) -> CollabResult<(Self, BundleMetadata)> {
+        let bundle = Bundle::decode(bytes, BUNDLE_KIND)?;
+        let mut manager = Self::from_bytes(&bundle.snapshot)?;
+        manager.load_incremental(&bundle.changes)?;
+        bundle.check_heads(&manager.get_heads())?;
+        Ok((manager, bundle.metadata))
+    }
+
+    /// Attaches a store under `doc_id` and writes a snapshot now (see
+    /// `crate::storage`). Replaces any store attached before.
+    ///
+    /// Changes committed through `transaction()`, batches and
+    /// `update_state_with_message()` are appended as they commit.
+    /// `update_state()` and direct setters leave their operations pending;
+    /// call `flush_store()` to append them. If a store write fails, the
+    /// committing call returns the error but the change stays in the
+    /// document, and the next flush retries it.
+    pub fn attach_store(&mut self, doc_id: &str, store: Box<dyn DocumentStore>) -> CollabResult<()> {
+        self.store = Some(AttachedStore::new(store, doc_id, &mut self.doc)?);
+        Ok(())
+    }
+
+    /// Detaches and returns the attached store, if any.
+    pub fn detach_store(&mut self) -> Option<Box<dyn DocumentStore>> {
+        self.store.take().map(AttachedStore::into_store)
+    }
+
+    /// Appends all changes not yet in the attached store, committing any
+    /// pending operations first (see `attach_store()` for which calls do this
+    /// automatically). Returns the number of changes written (0 without a
+    /// store).
+    pub fn flush_store(&mut self) -> CollabResult<usize> {
+        match self.store.as_mut() {
+            Some(store) => store.persist(&mut self.doc),
+            None => Ok(0),
+        }
+    }
+
+    /// Replaces the stored snapshot with the current document and clears the
+    /// change log. Does nothing without a store.
+    pub fn snapshot_store(&mut self) -> CollabResult<()> {
+        if let Some(store) = self.store.as_mut() {
+            store.snapshot(&mut self.doc)?;
+        }
+        Ok(())
+    }
+
+    /// Saves the document as gzip-compressed binary (`level` 0-9).
+    ///
+    /// `from_bytes()` detects and decompresses the result transparently.
+    #[cfg(feature = "compression")]
+    pub fn save_compressed(&mut self, level: u32) -> Vec<u8> {
+        compression::compress(&self.save(), level)
+    }
+
+    /// Saves the document encrypted with AES-256-GCM (see `crate::crypto`).
+    ///
+    /// Use for snapshots kept on untrusted storage; load them with
+    /// `from_encrypted_bytes()` and the same key params.
+    #[cfg(feature = "crypto")]
+    pub fn save_encrypted(&mut self, key_params: &KeyParams) -> CollabResult<Vec<u8>> {
+        crypto::encrypt(&self.save(), key_params)
+    }
+
+    /// Saves the document in the versioned envelope (see `crate::envelope`),
+    /// optionally compressed and encrypted. `from_bytes()` (or
+    /// `from_encrypted_bytes()`) reads the result.
+    pub fn save_enveloped(&mut self, options: &SaveOptions) -> CollabResult<Vec<u8>> {
+        envelope::encode(&self.save(), options)
+    }
+
     /// Returns the current heads (for sync protocol).
     pub fn get_heads(&mut self) -> Vec<ChangeHash> {
         self.doc.get_heads()
@@ -76,72 +441,409 @@ impl SequenceManager {
         self.doc.get_actor().to_hex_string()
     }
 
+    /// Sets the actor ID for changes made from now on, so they carry a
+    /// stable user/device identity instead of a random per-instance one.
+    /// Pending operations are committed under the previous actor first.
+    ///
+    /// Two instances must never write concurrently with the same actor ID
+    /// (e.g. one user in two tabs), so include a per-device or per-tab part.
+    pub fn set_actor(&mut self, actor: &[u8]) {
+        self.doc.set_actor(ActorId::from(actor));
+        let actor = self.actor_id();
+        self.awareness.set_actor(actor);
+    }
+
+    /// Builder: Sets the actor ID (see `set_actor()`).
+    pub fn with_actor(mut self, actor: &[u8]) -> Self {
+        self.set_actor(actor);
+        self
+    }
+
+    /// Returns the underlying Automerge document for custom queries.
+    pub fn as_automerge(&self) -> &AutoCommit {
+        &self.doc
+    }
+
+    /// Returns the underlying Automerge document for low-level operations.
+    ///
+    /// Cached state is dropped, so edits made through the returned document
+    /// are seen by later reads. Call `flush_store()` afterwards if a store is
+    /// attached. Writes must keep the document schema intact.
+    pub fn as_automerge_mut(&mut self) -> &mut AutoCommit {
+        self.invalidate_all_caches();
+        &mut self.doc
+    }
+
+    /// Consumes the manager and returns the underlying Automerge document
+    /// (e.g. to hand it to automerge-repo storage). Pending operations are
+    /// committed first; presence and any attached store are dropped.
+    pub fn into_automerge(mut self) -> AutoCommit {
+        self.doc.commit();
+        self.doc
+    }
+
+    /// Returns the ranges enforced by the settings setters.
+    pub fn settings_limits(&self) -> &SettingsLimits {
+        &self.settings_limits
+    }
+
+    /// Replaces the ranges enforced by the settings setters.
+    /// Use `SettingsLimits::unrestricted()` to disable validation.
+    pub fn set_settings_limits(&mut self, limits: SettingsLimits) {
+        self.settings_limits = limits;
+    }
+
+    /// Returns the transitions allowed by `set_status_checked()`.
+    pub fn status_transitions(&self) -> &StatusTransitions {
+        &self.status_transitions
+    }
+
+    /// Replaces the transitions allowed by `set_status_checked()`.
+    pub fn set_status_transitions(&mut self, transitions: StatusTransitions) {
+        self.status_transitions = transitions;
+    }
+
+    /// Returns the enforcement modes in effect.
+    pub fn options(&self) -> &ManagerOptions {
+        &self.options
+    }
+
+    /// Replaces the enforcement modes.
+    pub fn set_options(&mut self, options: ManagerOptions) {
+        self.options = options;
+    }
+
+    /// Allows settings changes on `node_id` from this manager even while it
+    /// is completed and `freeze_completed` is on.
+    pub fn unfreeze(&mut self, node_id: &str) {
+        self.unfrozen.insert(node_id.to_string());
+    }
+
+    /// Returns true if settings changes on `node_id` are currently rejected.
+    pub fn is_frozen(&mut self, node_id: &str) -> CollabResult<bool> {
+        let node_obj = self.get_node_obj(node_id)?;
+        self.is_frozen_at(&node_obj, node_id)
+    }
+
+    fn is_frozen_at(&self, node_obj: &ObjId, node_id: &str) -> CollabResult<bool> {
+        if !self.options.freeze_completed || self.unfrozen.contains(node_id) {
+            return Ok(false);
+        }
+        let status: String = hydrate_prop(&self.doc, node_obj, "status")?;
+        Ok(status == GenerationStatus::Completed.as_str())
+    }
+
     /// Invalidates all caches. Call after any operation that might change document structure.
     fn invalidate_all_caches(&mut self) {
         self.cached_state = None;
         self.cached_generations_obj = None;
     }
 
+    // =========================================================================
+    // BATCHING (Single change for multiple operations)
+    // =========================================================================
+
+    /// Runs several operations and records them as a single Automerge change.
+    ///
+    /// Any pending operations are committed first so the batch only contains
+    /// the work done inside `f`. If `f` returns an error, the batch is rolled
+    /// back and the document is left as it was before the call.
+    ///
+    /// Note: `save()` and `get_heads()` close the open change, so calling them
+    /// inside `f` splits the batch.
+    ///
+    /// # Example
+    /// ```rust
+    /// use heyocollab::{SequenceManager, GenerationNode};
+    ///
+    /// let mut manager = SequenceManager::new();
+    /// manager.transaction(Some("Bulk create"), |txn| {
+    ///     txn.create_and_append("gen-1", GenerationNode::new("gen-1", "t2i"))?;
+    ///     txn.create_and_append("gen-2", GenerationNode::new("gen-2", "t2i"))?;
+    ///     txn.set_status("gen-1", "queued")
+    /// }).unwrap();
+    /// ```
+    pub fn transaction<F, T>(&mut self, message: Option<&str>, f: F) -> CollabResult<T>
+    where
+        F: FnOnce(&mut Self) -> CollabResult<T>,
+    {
+        self.begin_batch();
+        match f(self) {
+            Ok(value) => {
+                self.commit_batch(message)?;
+                Ok(value)
+            }
+            Err(e) => {
+                self.rollback_batch();
+                Err(e)
+            }
+        }
+    }
+
+    /// Starts a batch by committing any pending operations.
+    ///
+    /// Every operation until the next `commit_batch()` ends up in one change.
+    pub fn begin_batch(&mut self) {
+        self.doc.commit();
+    }
+
+    /// Commits all operations since `begin_batch()` as one change and
+    /// appends it to the attached store. Returns the hash of the new change,
+    /// or None if nothing was pending.
+    pub fn commit_batch(&mut self, message: Option<&str>) -> CollabResult<Option<ChangeHash>> {
+        let hash = match message {
+            Some(msg) => self.doc.commit_with(CommitOptions::default().with_message(msg)),
+            None => self.doc.commit(),
+        };
+        self.flush_store()?;
+        Ok(hash)
+    }
+
+    /// Discards all operations since `begin_batch()`.
+    /// Returns the number of operations rolled back.
+    pub fn rollback_batch(&mut self) -> usize {
+        self.invalidate_all_caches(); // Rolled-back ops may have created objects
+        self.doc.rollback()
+    }
+
     // =========================================================================
     // HIGH-LEVEL OPERATIONS (via Hydrate/Reconcile)
     // =========================================================================
 
     /// Hydrates the entire document state to Rust structs.
     pub fn get_state(&mut self) -> CollabResult<DocumentRoot> {
-        if let Some(ref cached) = self.cached_state {
-            return Ok(cached.clone());
+        self.get_state_ref().cloned()
+    }
+
+    /// Borrows the hydrated state instead of cloning it, hydrating first if
+    /// the cache is cold.
+    pub fn get_state_ref(&mut self) -> CollabResult<&DocumentRoot> {
+        if self.cached_state.is_none() {
+            self.cached_state = Some(hydrate(&self.doc)?);
         }
-        let state: DocumentRoot = hydrate(&self.doc)?;
-        self.cached_state = Some(state.clone());
-        Ok(state)
+        Ok(self.cached_state.as_ref().expect("state just cached"))
+    }
+
+    /// Runs `f` with a borrowed state and returns its result.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let count = manager.with_state(|state| state.generations.len())?;
+    /// ```
+    pub fn with_state<F, R>(&mut self, f: F) -> CollabResult<R>
+    where
+        F: FnOnce(&DocumentRoot) -> R,
+    {
+        Ok(f(self.get_state_ref()?))
     }
 
     /// Applies a function to mutate the state, then reconciles back to the document.
     /// Use this for bulk updates where text performance isn't critical.
+    ///
+    /// Only the fields and nodes the closure changed are written back.
     pub fn update_state<F>(&mut self, f: F) -> CollabResult<()>
+    where
+        F: FnOnce(&mut DocumentRoot),
+    {
+        self.check_permission(Action::Edit)?;
+        self.write_state(f)
+    }
+
+    /// `update_state()` without the permission check, for callers that
+    /// check a different action.
+    fn write_state<F>(&mut self, f: F) -> CollabResult<()>
     where
         F: FnOnce(&mut DocumentRoot),
     {
         let mut state = self.get_state()?;
         f(&mut state);
-        reconcile(&mut self.doc, &state)?;
+        let old = self.cached_state.as_ref().expect("get_state() fills the cache");
+        dirty::reconcile_sequence(&mut self.doc, old, &state)?;
         self.cached_state = Some(state);
         // Note: Don't invalidate cached_generations_obj - reconcile doesn't change ObjIds
         Ok(())
     }
 
+    /// Like `update_state()`, but records the result as its own change with a
+    /// commit message and timestamp (milliseconds since epoch).
+    ///
+    /// Use for user-visible actions, e.g. "Generate clicked" or "Bulk import".
+    pub fn update_state_with_message<F>(
+        &mut self,
+        message: &str,
+        timestamp: i64,
+        f: F,
+    ) -> CollabResult<()>
+    where
+        F: FnOnce(&mut DocumentRoot),
+    {
+        self.doc.commit();
+        if let Err(e) = self.update_state(f) {
+            self.rollback_batch();
+            return Err(e);
+        }
+        self.doc.commit_with(
+            CommitOptions::default()
+                .with_message(message)
+                .with_time(timestamp),
+        );
+        self.flush_store()?;
+        Ok(())
+    }
+
+    /// Returns metadata (hash, actor, message, timestamp) for every change.
+    pub fn get_change_history(&mut self) -> Vec<ChangeInfo> {
+        collect_change_info(&mut self.doc)
+    }
+
+    /// Registers a display name for a hex actor ID.
+    pub fn set_actor_name(&mut self, actor: &str, name: &str) -> CollabResult<()> {
+        self.update_state(|state| {
+            state.actor_names.insert(actor.to_string(), name.to_string());
+        })
+    }
+
+    /// Returns the registered display names keyed by hex actor ID.
+    pub fn get_actor_names(&mut self) -> CollabResult<std::collections::HashMap<String, String>> {
+        Ok(self.get_state_ref()?.actor_names.clone())
+    }
+
+    /// Returns who last changed each field of a node, keyed by field name
+    /// (nested settings as `settings.cfg`).
+    pub fn get_node_authors(&mut self, node_id: &str) -> CollabResult<BTreeMap<String, FieldAuthor>> {
+        let names = self.get_state_ref()?.actor_names.clone();
+        let node_obj = self.get_node_obj(node_id)?;
+        field_authors(&mut self.doc, &node_obj, &names)
+    }
+
+    /// Returns every concurrent value of a node field with the actor that
+    /// wrote it, winner first (dotted fields like `settings.cfg` allowed).
+    pub fn get_conflicts(&mut self, node_id: &str, field: &str) -> CollabResult<Vec<ConflictValue>> {
+        let node_obj = self.get_node_obj(node_id)?;
+        conflict::get_conflicts(&self.doc, &node_obj, field)
+    }
+
+    /// Keeps the value `chosen_actor` wrote to a node field, replacing the
+    /// concurrent ones.
+    pub fn resolve_conflict(
+        &mut self,
+        node_id: &str,
+        field: &str,
+        chosen_actor: &str,
+    ) -> CollabResult<()> {
+        self.begin_edit(Action::Edit)?;
+        let node_obj = self.get_node_obj(node_id)?;
+        conflict::resolve_conflict(&mut self.doc, &node_obj, field, chosen_actor)
+    }
+
+    /// Returns typed events (node created, status changed, output added)
+    /// for every change since `since_heads`, oldest first. Empty heads
+    /// return the whole history.
+    pub fn activity(&mut self, since_heads: &[ChangeHash]) -> CollabResult<Vec<ActivityEvent>> {
+        activity::collect(&mut self.doc, since_heads)
+    }
+
     /// Creates a new generation node.
     pub fn create_node(&mut self, id: &str, node: GenerationNode) -> CollabResult<()> {
         self.update_state(|state| {
-            state.generations.insert(id.to_string(), node);
+            state.generations.insert_unlisted(id, node);
+            state.fill_order_keys();
         })
     }
 
     /// Appends a generation ID to the sequence order.
     pub fn append_generation(&mut self, id: &str) -> CollabResult<()> {
         self.update_state(|state| {
-            let id_str = id.to_string();
-            if !state.sequence_order.contains(&id_str) {
-                state.sequence_order.push(id_str);
-            }
+            state.generations.list(id);
+            state.fill_order_keys();
         })
     }
 
     /// Creates a node and appends it to the sequence order in one operation.
     pub fn create_and_append(&mut self, id: &str, node: GenerationNode) -> CollabResult<()> {
         self.update_state(|state| {
-            let id_str = id.to_string();
-            state.generations.insert(id_str.clone(), node);
-            if !state.sequence_order.contains(&id_str) {
-                state.sequence_order.push(id_str);
+            state.generations.insert(id, node);
+            state.fill_order_keys();
+        })
+    }
+
+    /// Creates many nodes and appends them to the sequence order, in the
+    /// given order, as a single change. Use for importing existing
+    /// generation histories instead of calling `create_and_append()` in a
+    /// loop. IDs already in the order keep their position.
+    pub fn create_many(&mut self, nodes: Vec<(String, GenerationNode)>) -> CollabResult<()> {
+        if nodes.is_empty() {
+            return Ok(());
+        }
+        let count = nodes.len();
+        self.doc.commit();
+        let result = self.update_state(|state| {
+            for (id, node) in nodes {
+                state.generations.insert(id, node);
             }
+            state.fill_order_keys();
+        });
+        if let Err(e) = result {
+            self.rollback_batch();
+            return Err(e);
+        }
+        self.doc.commit_with(
+            CommitOptions::default().with_message(format!("Import {} nodes", count)),
+        );
+        self.flush_store()?;
+        Ok(())
+    }
+
+    /// Creates `new_id` as a variation of `src_id`: prompts, settings and
+    /// notes are copied, the status is reset to "pending", and outputs,
+    /// comments and render job fields are cleared. `derived_from` records
+    /// the source. The copy is placed right after the source in the order.
+    pub fn duplicate_node(&mut self, src_id: &str, new_id: &str) -> CollabResult<()> {
+        let state = self.get_state_ref()?;
+        if state.generations.contains_key(new_id) {
+            return Err(CollabError::schema_violation(format!(
+                "node '{}' already exists",
+                new_id
+            )));
+        }
+        let src = state
+            .generations
+            .get(src_id)
+            .ok_or_else(|| CollabError::node_not_found(src_id))?;
+        let node = GenerationNode {
+            id: new_id.to_string(),
+            status: "pending".to_string(),
+            status_updated_at: None,
+            provider: None,
+            job_id: None,
+            derived_from: Some(src_id.to_string()),
+            seen: Default::default(),
+            timeline: None,
+            outputs: Vec::new(),
+            comments: None,
+            order_key: None,
+            ..src.clone()
+        };
+
+        self.update_state(|state| {
+            state.generations.insert(new_id.to_string(), node);
+            state.place_after(new_id, src_id);
         })
     }
 
     /// Gets a node by ID.
     pub fn get_node(&mut self, id: &str) -> CollabResult<Option<GenerationNode>> {
-        let state = self.get_state()?;
-        Ok(state.generations.get(id).cloned())
+        self.hydrate_node(id)
+    }
+
+    /// Hydrates a single node through its map entry instead of the whole
+    /// document. Uses the cached state when it is warm.
+    pub fn hydrate_node(&mut self, id: &str) -> CollabResult<Option<GenerationNode>> {
+        if let Some(ref cached) = self.cached_state {
+            return Ok(cached.generations.get(id).cloned());
+        }
+        let gens_obj = self.get_generations_obj()?;
+        Ok(hydrate_prop(&self.doc, &gens_obj, id)?)
     }
 
     /// Updates a node's fields.
@@ -156,15 +858,79 @@ impl SequenceManager {
         })
     }
 
+    /// Makes `node_id` consume `input` (an output of another node). Linking
+    /// the same input twice is a no-op. Fails with `DependencyCycle` if the
+    /// upstream node already depends on `node_id`.
+    pub fn link_input(&mut self, node_id: &str, input: NodeInputRef) -> CollabResult<()> {
+        let state = self.get_state_ref()?;
+        let node = state
+            .generations
+            .get(node_id)
+            .ok_or_else(|| CollabError::node_not_found(node_id))?;
+        if node.inputs.contains(&input) {
+            return Ok(());
+        }
+        if !state.generations.contains_key(&input.node_id) {
+            return Err(CollabError::node_not_found(input.node_id));
+        }
+        if graph::depends_on(state, &input.node_id, node_id) {
+            return Err(CollabError::dependency_cycle(format!(
+                "'{}' already depends on '{}'",
+                input.node_id, node_id
+            )));
+        }
+        self.update_node(node_id, |node| node.inputs.push(input))
+    }
+
+    /// Removes `input` from `node_id`'s inputs. Returns false if it was not
+    /// linked.
+    pub fn unlink_input(&mut self, node_id: &str, input: &NodeInputRef) -> CollabResult<bool> {
+        let linked = self
+            .hydrate_node(node_id)?
+            .ok_or_else(|| CollabError::node_not_found(node_id))?
+            .inputs
+            .contains(input);
+        if linked {
+            self.update_node(node_id, |node| node.inputs.retain(|i| i != input))?;
+        }
+        Ok(linked)
+    }
+
+    /// Returns every node ID ordered so that each node comes after the
+    /// nodes it takes inputs from (see `sequence::graph`).
+    pub fn topological_order(&mut self) -> CollabResult<Vec<String>> {
+        graph::topological_order(self.get_state_ref()?)
+    }
+
     /// Updates a node's settings (full reconcile version).
     /// For single-field updates, prefer `set_setting_*` methods.
+    ///
+    /// Fields the closure changed are checked against `settings_limits()`
+    /// (values already stored are left alone); on failure nothing is written
+    /// and `InvalidSetting` is returned. Fails with
+    /// `FrozenNode` for completed nodes when `freeze_completed` is on.
     pub fn update_settings<F>(&mut self, id: &str, f: F) -> CollabResult<()>
     where
         F: FnOnce(&mut GenerationSettings),
     {
+        let mut settings = match self.hydrate_node(id)? {
+            Some(node) => {
+                if self.options.freeze_completed
+                    && node.status == GenerationStatus::Completed.as_str()
+                    && !self.unfrozen.contains(id)
+                {
+                    return Err(CollabError::frozen_node(id));
+                }
+                node.settings
+            }
+            None => return Ok(()),
+        };
+        let old = settings.clone();
+        f(&mut settings);
+        self.settings_limits.validate_changes(&old, &settings)?;
         self.update_state(|state| {
             if let Some(node) = state.generations.get_mut(id) {
-                f(&mut node.settings);
+                node.settings = settings;
             }
         })
     }
@@ -178,47 +944,256 @@ impl SequenceManager {
         })
     }
 
+    /// Archives a node directly (O(1)): it stays in the document and the
+    /// sequence order but is hidden from `get_order(false)` and
+    /// `get_summaries(false)`. Prefer this over `delete_node()` to keep
+    /// provenance.
+    pub fn archive_node(&mut self, id: &str) -> CollabResult<()> {
+        self.set_archived(id, true)
+    }
+
+    /// Restores an archived node directly (O(1)).
+    pub fn unarchive_node(&mut self, id: &str) -> CollabResult<()> {
+        self.set_archived(id, false)
+    }
+
+    fn set_archived(&mut self, id: &str, archived: bool) -> CollabResult<()> {
+        self.begin_edit(Action::Edit)?;
+        let node_obj = self.get_node_obj(id)?;
+        self.doc.put(&node_obj, "archived", ScalarValue::Boolean(archived))?;
+        Ok(())
+    }
+
     /// Removes a node from the document.
     pub fn delete_node(&mut self, id: &str) -> CollabResult<()> {
         self.update_state(|state| {
             state.generations.remove(id);
-            state.sequence_order.retain(|s| s != id);
         })
     }
 
     /// Removes a generation from the sequence order (by ID).
     pub fn remove_from_order(&mut self, id: &str) -> CollabResult<()> {
         self.update_state(|state| {
-            state.sequence_order.retain(|s| s != id);
+            state.generations.unlist(id);
         })
     }
 
     /// Inserts a generation at a specific position in the sequence order.
     pub fn insert_at_position(&mut self, index: usize, id: &str) -> CollabResult<()> {
         self.update_state(|state| {
-            let id_str = id.to_string();
-            if index <= state.sequence_order.len() && !state.sequence_order.contains(&id_str) {
-                state.sequence_order.insert(index, id_str);
+            let order = state.generations.order();
+            if index <= order.len() && !state.generations.is_listed(id) {
+                state.place_node(id, index);
             }
         })
     }
 
-    /// Moves a generation from one position to another.
+    /// Moves a generation from one position to another (positions in
+    /// display order).
     pub fn move_generation(&mut self, from: usize, to: usize) -> CollabResult<()> {
         self.update_state(|state| {
-            let len = state.sequence_order.len();
-            if from < len && to <= len && from != to {
-                let id = state.sequence_order.remove(from);
+            if state.fractional_order {
+                let order = state.ordered_ids();
+                if from < order.len() && to <= order.len() && from != to {
+                    let adjusted_to = if from < to { to - 1 } else { to };
+                    state.place_node(&order[from], adjusted_to);
+                }
+                return;
+            }
+            let order = state.generations.order();
+            if from < order.len() && to <= order.len() && from != to {
+                let id = order[from].clone();
                 let adjusted_to = if from < to { to - 1 } else { to };
-                state.sequence_order.insert(adjusted_to, id);
+                state.generations.move_to(&id, adjusted_to);
             }
         })
     }
 
-    /// Returns the ordered list of generation IDs.
-    pub fn get_order(&mut self) -> CollabResult<Vec<String>> {
-        let state = self.get_state()?;
-        Ok(state.sequence_order.clone())
+    /// Moves a node between two neighbours in display order (None at
+    /// either end). With fractional order this writes only the node's
+    /// order key (O(1)), so it merges with concurrent moves of other nodes;
+    /// it fails if `prev` does not sort before `next`.
+    pub fn move_node_between(
+        &mut self,
+        node_id: &str,
+        prev: Option<&str>,
+        next: Option<&str>,
+    ) -> CollabResult<()> {
+        let state = self.get_state_ref()?;
+        let mut keys = [None, None];
+        for (key, id) in keys.iter_mut().zip([prev, next]) {
+            if let Some(id) = id {
+                let node = state.generations.get(id).ok_or_else(|| CollabError::node_not_found(id))?;
+                *key = Some(node.order_key.clone().unwrap_or_default());
+            }
+        }
+        if !state.generations.contains_key(node_id) {
+            return Err(CollabError::node_not_found(node_id));
+        }
+        if !state.fractional_order {
+            let index = prev.map_or(0, |prev| {
+                let mut others = state.generations.order().iter().filter(|s| *s != node_id);
+                others.position(|s| s == prev).map_or(0, |i| i + 1)
+            });
+            return self.update_state(|state| state.place_node(node_id, index));
+        }
+
+        let [prev_key, next_key] = keys;
+        let key = order_key::key_between(prev_key.as_deref(), next_key.as_deref())?;
+        self.begin_edit(Action::Edit)?;
+        let node_obj = self.get_node_obj(node_id)?;
+        self.doc.put(&node_obj, "order_key", ScalarValue::Str(key.into()))?;
+        Ok(())
+    }
+
+    /// Switches between list and fractional node ordering, as one change
+    /// (see `crate::order_key`). Turning it on keys the nodes in their
+    /// current order; turning it off writes the key order to
+    /// `sequence_order`.
+    pub fn set_fractional_order(&mut self, enabled: bool) -> CollabResult<()> {
+        let message = if enabled {
+            "Use fractional node order"
+        } else {
+            "Use list node order"
+        };
+        self.transaction(Some(message), |txn| {
+            txn.update_state(|state| state.use_fractional_order(enabled))
+        })
+    }
+
+    /// Returns the ordered list of generation IDs, leaving out archived
+    /// nodes unless `include_archived` is set.
+    pub fn get_order(&mut self, include_archived: bool) -> CollabResult<Vec<String>> {
+        let state = self.get_state_ref()?;
+        let order = state.ordered_ids();
+        if include_archived {
+            return Ok(order);
+        }
+        Ok(order
+            .into_iter()
+            .filter(|id| !state.generations.get(id).is_some_and(|node| node.archived))
+            .collect())
+    }
+
+    /// Returns the IDs (sorted) of nodes matching `filter`. Without a cached
+    /// state, only the fields the filter checks are read from the document,
+    /// so schedulers can find queued work without hydrating every node.
+    pub fn query(&mut self, filter: &NodeFilter) -> CollabResult<Vec<String>> {
+        if let Some(ref cached) = self.cached_state {
+            let mut ids: Vec<String> = cached
+                .generations
+                .iter()
+                .filter(|(_, node)| filter.matches(node))
+                .map(|(id, _)| id.clone())
+                .collect();
+            ids.sort();
+            return Ok(ids);
+        }
+
+        let gens_obj = self.get_generations_obj()?;
+        let keys: Vec<String> = self.doc.keys(&gens_obj).collect();
+        let mut ids = Vec::new();
+        for id in keys {
+            let node_obj = match self.doc.get(&gens_obj, id.as_str())? {
+                Some((Value::Object(_), obj)) => obj,
+                _ => continue,
+            };
+            if let Some(status) = &filter.status {
+                let value: String = hydrate_prop(&self.doc, &node_obj, "status")?;
+                if value != *status {
+                    continue;
+                }
+            }
+            if let Some(type_) = &filter.type_ {
+                let value: String = hydrate_prop(&self.doc, &node_obj, "type_")?;
+                if value != *type_ {
+                    continue;
+                }
+            }
+            if let Some(after) = filter.created_after {
+                let created_at: Option<i64> = hydrate_optional(&self.doc, &node_obj, "created_at")?;
+                if !created_at.is_some_and(|at| at > after) {
+                    continue;
+                }
+            }
+            if let Some(tag) = &filter.tag {
+                let tags: Vec<String> = hydrate_optional(&self.doc, &node_obj, "tags")?;
+                if !tags.contains(tag) {
+                    continue;
+                }
+            }
+            ids.push(id);
+        }
+        Ok(ids)
+    }
+
+    /// Searches node titles and prompts. Returns `(node_id, score)` for
+    /// nodes containing every word of `query`, best match first. Only nodes
+    /// changed since the last search are re-indexed.
+    #[cfg(feature = "search")]
+    pub fn search_prompts(&mut self, query: &str) -> CollabResult<Vec<(String, f64)>> {
+        self.get_state_ref()?;
+        let state = self.cached_state.as_ref().expect("get_state_ref() fills the cache");
+        self.search_index.sync(
+            state
+                .generations
+                .iter()
+                .map(|(id, node)| (id.clone(), format!("{}\n{}", node.title, node.prompt))),
+        );
+        Ok(self.search_index.search(query))
+    }
+
+    /// Returns a summary of every node in sequence order, reading only the
+    /// summarized fields from the document. IDs in the order without a node
+    /// are skipped, as are archived nodes unless `include_archived` is set.
+    pub fn get_summaries(&mut self, include_archived: bool) -> CollabResult<Vec<NodeSummary>> {
+        if let Some(ref cached) = self.cached_state {
+            return Ok(cached
+                .ordered_ids()
+                .iter()
+                .filter_map(|id| Some(NodeSummary::from_node(id, cached.generations.get(id)?)))
+                .filter(|summary| include_archived || !summary.archived)
+                .collect());
+        }
+
+        let mut order: Vec<String> = hydrate_prop(&self.doc, ROOT, "sequence_order")?;
+        let gens_obj = self.get_generations_obj()?;
+        let fractional: Option<bool> = hydrate_prop(&self.doc, ROOT, "fractional_order")?;
+        if fractional.unwrap_or_default() {
+            let mut keys = std::collections::HashMap::new();
+            for id in &order {
+                if let Some((Value::Object(_), obj)) = self.doc.get(&gens_obj, id.as_str())? {
+                    let key: Option<String> = hydrate_prop(&self.doc, &obj, "order_key")?;
+                    keys.insert(id.clone(), key);
+                }
+            }
+            order = order_key::sorted(&order, &keys);
+        }
+        let mut summaries = Vec::with_capacity(order.len());
+        for id in order {
+            let node_obj = match self.doc.get(&gens_obj, id.as_str())? {
+                Some((Value::Object(_), obj)) => obj,
+                _ => continue,
+            };
+            let archived: Option<bool> = hydrate_prop(&self.doc, &node_obj, "archived")?;
+            let archived = archived.unwrap_or_default();
+            if archived && !include_archived {
+                continue;
+            }
+            let output_count = match self.doc.get(&node_obj, "outputs")? {
+                Some((Value::Object(_), obj)) => self.doc.length(&obj),
+                _ => 0,
+            };
+            summaries.push(NodeSummary {
+                type_: hydrate_prop(&self.doc, &node_obj, "type_")?,
+                status: hydrate_prop(&self.doc, &node_obj, "status")?,
+                title: hydrate_prop(&self.doc, &node_obj, "title")?,
+                output_count,
+                archived,
+                id,
+            });
+        }
+        Ok(summaries)
     }
 
     // =========================================================================
@@ -233,7 +1208,7 @@ impl SequenceManager {
         key: &str,
         value: ScalarValue,
     ) -> CollabResult<()> {
-        self.cached_state = None; // Invalidate state cache
+        self.begin_edit(Action::Edit)?; // Invalidate state cache
         let settings_obj = self.get_settings_obj(node_id)?;
         self.doc.put(&settings_obj, key, value)?;
         Ok(())
@@ -242,7 +1217,7 @@ impl SequenceManager {
     /// Clears a setting (for Option::None).
     /// OPTIMIZATION: Use delete() instead of put(Null) - saves space.
     fn set_setting_null(&mut self, node_id: &str, key: &str) -> CollabResult<()> {
-        self.cached_state = None;
+        self.begin_edit(Action::Edit)?;
         let settings_obj = self.get_settings_obj(node_id)?;
         self.doc.delete(&settings_obj, key)?;
         Ok(())
@@ -259,7 +1234,10 @@ impl SequenceManager {
     /// Sets the cfg (guidance scale) setting directly (O(1)).
     pub fn set_setting_cfg(&mut self, node_id: &str, cfg: Option<f64>) -> CollabResult<()> {
         match cfg {
-            Some(v) => self.set_setting_value(node_id, "cfg", ScalarValue::F64(v)),
+            Some(v) => {
+                self.settings_limits.check_cfg(v)?;
+                self.set_setting_value(node_id, "cfg", ScalarValue::F64(v))
+            }
             None => self.set_setting_null(node_id, "cfg"),
         }
     }
@@ -267,7 +1245,10 @@ impl SequenceManager {
     /// Sets the num_steps setting directly (O(1)).
     pub fn set_setting_num_steps(&mut self, node_id: &str, steps: Option<i32>) -> CollabResult<()> {
         match steps {
-            Some(v) => self.set_setting_value(node_id, "num_steps", ScalarValue::Int(v as i64)),
+            Some(v) => {
+                self.settings_limits.check_num_steps(v)?;
+                self.set_setting_value(node_id, "num_steps", ScalarValue::Int(v as i64))
+            }
             None => self.set_setting_null(node_id, "num_steps"),
         }
     }
@@ -280,14 +1261,14 @@ impl SequenceManager {
         }
     }
 
-    /// Sets the resolution setting directly (O(1)).
+    /// Sets the resolution preset directly (O(1)).
     pub fn set_setting_resolution(
         &mut self,
         node_id: &str,
-        resolution: Option<i32>,
+        resolution: Option<ResolutionPreset>,
     ) -> CollabResult<()> {
         match resolution {
-            Some(v) => self.set_setting_value(node_id, "resolution", ScalarValue::Int(v as i64)),
+            Some(v) => self.set_setting_value(node_id, "resolution", ScalarValue::Int(v.0 as i64)),
             None => self.set_setting_null(node_id, "resolution"),
         }
     }
@@ -295,7 +1276,10 @@ impl SequenceManager {
     /// Sets the width setting directly (O(1)).
     pub fn set_setting_width(&mut self, node_id: &str, width: Option<i32>) -> CollabResult<()> {
         match width {
-            Some(v) => self.set_setting_value(node_id, "width", ScalarValue::Int(v as i64)),
+            Some(v) => {
+                self.settings_limits.check_dimension("width", v)?;
+                self.set_setting_value(node_id, "width", ScalarValue::Int(v as i64))
+            }
             None => self.set_setting_null(node_id, "width"),
         }
     }
@@ -303,19 +1287,22 @@ impl SequenceManager {
     /// Sets the height setting directly (O(1)).
     pub fn set_setting_height(&mut self, node_id: &str, height: Option<i32>) -> CollabResult<()> {
         match height {
-            Some(v) => self.set_setting_value(node_id, "height", ScalarValue::Int(v as i64)),
+            Some(v) => {
+                self.settings_limits.check_dimension("height", v)?;
+                self.set_setting_value(node_id, "height", ScalarValue::Int(v as i64))
+            }
             None => self.set_setting_null(node_id, "height"),
         }
     }
 
-    /// Sets the duration setting directly (O(1)).
+    /// Sets the duration setting directly (O(1)). Stored as whole seconds.
     pub fn set_setting_duration(
         &mut self,
         node_id: &str,
-        duration: Option<i32>,
+        duration: Option<DurationSecs>,
     ) -> CollabResult<()> {
         match duration {
-            Some(v) => self.set_setting_value(node_id, "duration", ScalarValue::Int(v as i64)),
+            Some(v) => self.set_setting_value(node_id, "duration", ScalarValue::Int(v.0 as i64)),
             None => self.set_setting_null(node_id, "duration"),
         }
     }
@@ -323,214 +1310,2787 @@ impl SequenceManager {
     /// Sets the fps setting directly (O(1)).
     pub fn set_setting_fps(&mut self, node_id: &str, fps: Option<i32>) -> CollabResult<()> {
         match fps {
-            Some(v) => self.set_setting_value(node_id, "fps", ScalarValue::Int(v as i64)),
+            Some(v) => {
+                self.settings_limits.check_fps(v)?;
+                self.set_setting_value(node_id, "fps", ScalarValue::Int(v as i64))
+            }
             None => self.set_setting_null(node_id, "fps"),
         }
     }
 
-    /// Sets the node status directly (O(1)).
-    pub fn set_status(&mut self, node_id: &str, status: &str) -> CollabResult<()> {
-        self.cached_state = None;
-        let node_obj = self.get_node_obj(node_id)?;
-        self.doc
-            .put(&node_obj, "status", ScalarValue::Str(status.into()))?;
+    /// Sets the sampler setting directly (O(1)).
+    pub fn set_setting_sampler(&mut self, node_id: &str, sampler: Option<&str>) -> CollabResult<()> {
+        match sampler {
+            Some(v) => self.set_setting_value(node_id, "sampler", ScalarValue::Str(v.into())),
+            None => self.set_setting_null(node_id, "sampler"),
+        }
+    }
+
+    /// Sets the scheduler setting directly (O(1)).
+    pub fn set_setting_scheduler(&mut self, node_id: &str, scheduler: Option<&str>) -> CollabResult<()> {
+        match scheduler {
+            Some(v) => self.set_setting_value(node_id, "scheduler", ScalarValue::Str(v.into())),
+            None => self.set_setting_null(node_id, "scheduler"),
+        }
+    }
+
+    /// Sets the denoising strength setting directly (O(1)).
+    pub fn set_setting_strength(&mut self, node_id: &str, strength: Option<f64>) -> CollabResult<()> {
+        match strength {
+            Some(v) => self.set_setting_value(node_id, "strength", ScalarValue::F64(v)),
+            None => self.set_setting_null(node_id, "strength"),
+        }
+    }
+
+    /// Replaces the LoRA list directly, touching only the settings object.
+    /// An empty list removes it.
+    pub fn set_setting_loras(&mut self, node_id: &str, loras: &[LoraRef]) -> CollabResult<()> {
+        if loras.is_empty() {
+            return self.set_setting_null(node_id, "loras");
+        }
+        self.begin_edit(Action::Edit)?;
+        let settings_obj = self.get_settings_obj(node_id)?;
+        reconcile_prop(&mut self.doc, &settings_obj, "loras", loras)?;
+        Ok(())
+    }
+
+    /// Sets one extra model parameter directly (O(1)), creating the
+    /// `extra` map if needed. `None` removes the parameter.
+    pub fn set_setting_extra(
+        &mut self,
+        node_id: &str,
+        key: &str,
+        value: Option<SettingValue>,
+    ) -> CollabResult<()> {
+        self.begin_edit(Action::Edit)?;
+        let settings_obj = self.get_settings_obj(node_id)?;
+        let extra_obj = match self.doc.get(&settings_obj, "extra")? {
+            Some((Value::Object(ObjType::Map), obj)) => obj,
+            _ if value.is_none() => return Ok(()),
+            _ => self.doc.put_object(&settings_obj, "extra", ObjType::Map)?,
+        };
+        match value {
+            Some(v) => self.doc.put(&extra_obj, key, v.to_scalar())?,
+            None => self.doc.delete(&extra_obj, key)?,
+        }
+        Ok(())
+    }
+
+    /// Sets the node status directly (O(1)).
+    pub fn set_status(&mut self, node_id: &str, status: &str) -> CollabResult<()> {
+        self.begin_edit(Action::Edit)?;
+        let node_obj = self.get_node_obj(node_id)?;
+        self.doc
+            .put(&node_obj, "status", ScalarValue::Str(status.into()))?;
+        Ok(())
+    }
+
+    /// Sets the node status (O(1)) if `status_transitions()` allows moving
+    /// there from the current one; fails with `IllegalTransition` otherwise.
+    /// Nodes whose current status is not a `GenerationStatus` may move to
+    /// any status.
+    pub fn set_status_checked(&mut self, node_id: &str, status: GenerationStatus) -> CollabResult<()> {
+        let node_obj = self.get_node_obj(node_id)?;
+        let current: String = hydrate_prop(&self.doc, &node_obj, "status")?;
+        if let Ok(current) = current.parse::<GenerationStatus>() {
+            self.status_transitions.check(current, status)?;
+        }
+        self.set_status(node_id, status.as_str())
+    }
+
+    /// Sets the node status and records when it was set (O(1)).
+    /// Workers should use this so `sweep()` can detect abandoned jobs.
+    pub fn set_status_at(&mut self, node_id: &str, status: &str, timestamp: i64) -> CollabResult<()> {
+        self.begin_edit(Action::Edit)?;
+        let node_obj = self.get_node_obj(node_id)?;
+        self.doc
+            .put(&node_obj, "status", ScalarValue::Str(status.into()))?;
+        self.doc
+            .put(&node_obj, "status_updated_at", ScalarValue::Int(timestamp))?;
+        Ok(())
+    }
+
+    /// Sets the render provider directly (O(1)). `None` clears it.
+    pub fn set_provider(&mut self, node_id: &str, provider: Option<&str>) -> CollabResult<()> {
+        self.set_node_field_opt_str(node_id, "provider", provider)
+    }
+
+    /// Sets the provider job ID directly (O(1)). `None` clears it.
+    pub fn set_job_id(&mut self, node_id: &str, job_id: Option<&str>) -> CollabResult<()> {
+        self.set_node_field_opt_str(node_id, "job_id", job_id)
+    }
+
+    /// Finds the node submitted as `job_id`, if any.
+    /// Used to map render queue callbacks back to document nodes.
+    pub fn find_node_by_job_id(&mut self, job_id: &str) -> CollabResult<Option<String>> {
+        let state = self.get_state_ref()?;
+        Ok(state
+            .generations
+            .iter()
+            .find(|(_, node)| node.job_id.as_deref() == Some(job_id))
+            .map(|(id, _)| id.clone()))
+    }
+
+    /// Sets which node this one was derived from directly (O(1)). `None`
+    /// detaches it from its lineage.
+    pub fn set_derived_from(&mut self, node_id: &str, derived_from: Option<&str>) -> CollabResult<()> {
+        self.set_node_field_opt_str(node_id, "derived_from", derived_from)
+    }
+
+    /// Links a node to the storyboard shot it was generated for (O(1)).
+    /// `None` removes the link.
+    pub fn set_storyboard_ref(&mut self, node_id: &str, reference: Option<&DocRef>) -> CollabResult<()> {
+        self.begin_edit(Action::Edit)?;
+        let node_obj = self.get_node_obj(node_id)?;
+        match reference {
+            Some(r) => reconcile_prop(&mut self.doc, &node_obj, "storyboard_ref", r)?,
+            None => self.doc.delete(&node_obj, "storyboard_ref")?,
+        }
+        Ok(())
+    }
+
+    /// Loads the storyboard shot a node is linked to through `resolver`.
+    /// Returns None if the node has no link or the resolver cannot find
+    /// its target.
+    pub fn resolve_storyboard_ref<T>(
+        &mut self,
+        node_id: &str,
+        resolver: &mut impl DocResolver<T>,
+    ) -> CollabResult<Option<T>> {
+        let state = self.get_state_ref()?;
+        let node = state
+            .generations
+            .get(node_id)
+            .ok_or_else(|| CollabError::node_not_found(node_id))?;
+        match node.storyboard_ref.clone() {
+            Some(reference) => resolver.resolve(&reference),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the tree of variations regenerated from `id` (see
+    /// `duplicate_node()`), or None if there is no such node.
+    pub fn get_variations(&mut self, id: &str) -> CollabResult<Option<VariationTree>> {
+        Ok(self.get_state_ref()?.variation_tree(id))
+    }
+
+    /// Tags a node (O(1) in document size). Returns false if it already had
+    /// the tag.
+    pub fn add_tag(&mut self, node_id: &str, tag: &str) -> CollabResult<bool> {
+        let node_obj = self.get_node_obj(node_id)?;
+        let tags: Vec<String> = hydrate_optional(&self.doc, &node_obj, "tags")?;
+        if tags.iter().any(|t| t == tag) {
+            return Ok(false);
+        }
+        self.begin_edit(Action::Edit)?;
+        let tags_obj = match self.doc.get(&node_obj, "tags")? {
+            Some((Value::Object(ObjType::List), obj)) => obj,
+            _ => self.doc.put_object(&node_obj, "tags", ObjType::List)?,
+        };
+        self.doc.insert(&tags_obj, tags.len(), ScalarValue::Str(tag.into()))?;
+        Ok(true)
+    }
+
+    /// Removes a tag from a node, including duplicates added concurrently by
+    /// other peers. Returns false if the node did not have it.
+    pub fn remove_tag(&mut self, node_id: &str, tag: &str) -> CollabResult<bool> {
+        let node_obj = self.get_node_obj(node_id)?;
+        let tags: Vec<String> = hydrate_optional(&self.doc, &node_obj, "tags")?;
+        if !tags.iter().any(|t| t == tag) {
+            return Ok(false);
+        }
+        self.begin_edit(Action::Edit)?;
+        let tags_obj = self.get_obj_at_key(&node_obj, "tags")?;
+        for (i, _) in tags.iter().enumerate().rev().filter(|(_, t)| *t == tag) {
+            self.doc.delete(&tags_obj, i)?;
+        }
+        Ok(true)
+    }
+
+    /// Returns the IDs of nodes tagged `tag`, in sequence order.
+    pub fn get_nodes_by_tag(&mut self, tag: &str) -> CollabResult<Vec<String>> {
+        Ok(self.get_state_ref()?.nodes_by_tag(tag))
+    }
+
+    /// Records that `user` looked at a node at `timestamp` (O(1)). An older
+    /// timestamp than the one stored is ignored.
+    pub fn mark_seen(&mut self, node_id: &str, user: &str, timestamp: i64) -> CollabResult<()> {
+        let node_obj = self.get_node_obj(node_id)?;
+        let seen_obj = match self.doc.get(&node_obj, "seen")? {
+            Some((Value::Object(ObjType::Map), obj)) => Some(obj),
+            _ => None,
+        };
+        if let Some(obj) = &seen_obj {
+            let last: Option<i64> = hydrate_prop(&self.doc, obj, user)?;
+            if last.is_some_and(|last| last >= timestamp) {
+                return Ok(());
+            }
+        }
+        self.begin_edit(Action::Edit)?;
+        let seen_obj = match seen_obj {
+            Some(obj) => obj,
+            None => self.doc.put_object(&node_obj, "seen", ObjType::Map)?,
+        };
+        self.doc.put(&seen_obj, user, ScalarValue::Int(timestamp))?;
+        Ok(())
+    }
+
+    /// Counts the unarchived nodes `user` has not seen since they were
+    /// created or their status last changed (`status_updated_at`).
+    pub fn unseen_count(&mut self, user: &str) -> CollabResult<usize> {
+        let state = self.get_state_ref()?;
+        Ok(state
+            .generations
+            .iter_ordered()
+            .map(|(_, node)| node)
+            .filter(|node| !node.archived)
+            .filter(|node| match node.seen.get(user) {
+                Some(&seen) => node.status_updated_at.is_some_and(|updated| updated > seen),
+                None => true,
+            })
+            .count())
+    }
+
+    /// O(1) optional string field setter on a node (deletes the key for `None`).
+    fn set_node_field_opt_str(
+        &mut self,
+        node_id: &str,
+        key: &str,
+        value: Option<&str>,
+    ) -> CollabResult<()> {
+        self.begin_edit(Action::Edit)?;
+        let node_obj = self.get_node_obj(node_id)?;
+        let value = match value {
+            Some(v) => ScalarValue::Str(v.into()),
+            // Null rather than a delete, matching what reconcile writes for None
+            None => ScalarValue::Null,
+        };
+        self.doc.put(&node_obj, key, value)?;
         Ok(())
     }
 
     // =========================================================================
-    // LOW-LEVEL TEXT OPERATIONS (Direct Automerge API for performance)
+    // DOCUMENT DEFAULTS
+    // =========================================================================
+
+    /// Returns the project-wide default settings.
+    pub fn get_defaults(&mut self) -> CollabResult<GenerationSettings> {
+        Ok(self.get_state_ref()?.defaults.clone())
+    }
+
+    /// Updates the default settings (full reconcile version), checked
+    /// against `settings_limits()` like `update_settings()`.
+    pub fn update_defaults<F>(&mut self, f: F) -> CollabResult<()>
+    where
+        F: FnOnce(&mut GenerationSettings),
+    {
+        let mut defaults = self.get_defaults()?;
+        let old = defaults.clone();
+        f(&mut defaults);
+        self.settings_limits.validate_changes(&old, &defaults)?;
+        self.update_state(|state| state.defaults = defaults)
+    }
+
+    /// Creates a node whose settings are the document defaults and appends
+    /// it to the sequence order.
+    pub fn create_with_defaults(&mut self, id: &str, type_: &str) -> CollabResult<()> {
+        let mut node = GenerationNode::new(id, type_);
+        node.settings.inherit(&self.get_state_ref()?.defaults);
+        self.create_and_append(id, node)
+    }
+
+    /// Sets or clears one default setting directly (O(1)), creating the
+    /// defaults map on documents saved before it existed.
+    fn set_default_value(&mut self, key: &str, value: Option<ScalarValue>) -> CollabResult<()> {
+        self.begin_edit(Action::Edit)?;
+        let defaults_obj = match self.doc.get(ROOT, "defaults")? {
+            Some((Value::Object(ObjType::Map), obj)) => obj,
+            _ if value.is_none() => return Ok(()),
+            _ => self.doc.put_object(ROOT, "defaults", ObjType::Map)?,
+        };
+        match value {
+            Some(v) => self.doc.put(&defaults_obj, key, v)?,
+            None => self.doc.delete(&defaults_obj, key)?,
+        }
+        Ok(())
+    }
+
+    /// Sets the default cfg directly (O(1)).
+    pub fn set_default_setting_cfg(&mut self, cfg: Option<f64>) -> CollabResult<()> {
+        if let Some(v) = cfg {
+            self.settings_limits.check_cfg(v)?;
+        }
+        self.set_default_value("cfg", cfg.map(ScalarValue::F64))
+    }
+
+    /// Sets the default num_steps directly (O(1)).
+    pub fn set_default_setting_num_steps(&mut self, steps: Option<i32>) -> CollabResult<()> {
+        if let Some(v) = steps {
+            self.settings_limits.check_num_steps(v)?;
+        }
+        self.set_default_value("num_steps", steps.map(|v| ScalarValue::Int(v as i64)))
+    }
+
+    /// Sets the default model directly (O(1)).
+    pub fn set_default_setting_model(&mut self, model: Option<&str>) -> CollabResult<()> {
+        self.set_default_value("model", model.map(|v| ScalarValue::Str(v.into())))
+    }
+
+    /// Sets the default resolution preset directly (O(1)).
+    pub fn set_default_setting_resolution(
+        &mut self,
+        resolution: Option<ResolutionPreset>,
+    ) -> CollabResult<()> {
+        self.set_default_value("resolution", resolution.map(|v| ScalarValue::Int(v.0 as i64)))
+    }
+
+    /// Sets the default width directly (O(1)).
+    pub fn set_default_setting_width(&mut self, width: Option<i32>) -> CollabResult<()> {
+        if let Some(v) = width {
+            self.settings_limits.check_dimension("width", v)?;
+        }
+        self.set_default_value("width", width.map(|v| ScalarValue::Int(v as i64)))
+    }
+
+    /// Sets the default height directly (O(1)).
+    pub fn set_default_setting_height(&mut self, height: Option<i32>) -> CollabResult<()> {
+        if let Some(v) = height {
+            self.settings_limits.check_dimension("height", v)?;
+        }
+        self.set_default_value("height", height.map(|v| ScalarValue::Int(v as i64)))
+    }
+
+    /// Sets the default duration directly (O(1)). Stored as whole seconds.
+    pub fn set_default_setting_duration(&mut self, duration: Option<DurationSecs>) -> CollabResult<()> {
+        self.set_default_value("duration", duration.map(|v| ScalarValue::Int(v.0 as i64)))
+    }
+
+    /// Sets the default fps directly (O(1)).
+    pub fn set_default_setting_fps(&mut self, fps: Option<i32>) -> CollabResult<()> {
+        if let Some(v) = fps {
+            self.settings_limits.check_fps(v)?;
+        }
+        self.set_default_value("fps", fps.map(|v| ScalarValue::Int(v as i64)))
+    }
+
+    /// Sets the default sampler directly (O(1)).
+    pub fn set_default_setting_sampler(&mut self, sampler: Option<&str>) -> CollabResult<()> {
+        self.set_default_value("sampler", sampler.map(|v| ScalarValue::Str(v.into())))
+    }
+
+    /// Sets the default scheduler directly (O(1)).
+    pub fn set_default_setting_scheduler(&mut self, scheduler: Option<&str>) -> CollabResult<()> {
+        self.set_default_value("scheduler", scheduler.map(|v| ScalarValue::Str(v.into())))
+    }
+
+    /// Sets the default denoising strength directly (O(1)).
+    pub fn set_default_setting_strength(&mut self, strength: Option<f64>) -> CollabResult<()> {
+        self.set_default_value("strength", strength.map(ScalarValue::F64))
+    }
+
     // =========================================================================
+    // SETTINGS PRESETS
+    // =========================================================================
+
+    /// Returns all settings presets, keyed by preset ID.
+    pub fn get_presets(&mut self) -> CollabResult<std::collections::HashMap<String, GenerationSettings>> {
+        Ok(self.get_state_ref()?.presets.clone())
+    }
+
+    /// Creates or replaces the preset `preset_id`, checked against
+    /// `settings_limits()`.
+    pub fn create_preset(&mut self, preset_id: &str, settings: GenerationSettings) -> CollabResult<()> {
+        self.settings_limits.validate(&settings)?;
+        self.update_state(|state| {
+            state.presets.insert(preset_id.to_string(), settings);
+        })
+    }
+
+    /// Applies a preset to a node: fields set in the preset overwrite the
+    /// node's, everything else is kept. Fails with `PresetNotFound` if there
+    /// is no such preset, and like `update_settings()` otherwise.
+    pub fn apply_preset(&mut self, node_id: &str, preset_id: &str) -> CollabResult<()> {
+        let preset = self
+            .get_state_ref()?
+            .presets
+            .get(preset_id)
+            .cloned()
+            .ok_or_else(|| CollabError::preset_not_found(preset_id))?;
+        self.update_settings(node_id, |settings| {
+            let mut merged = preset;
+            merged.inherit(settings);
+            *settings = merged;
+        })
+    }
+
+    /// Deletes a preset. Returns false if it did not exist.
+    pub fn delete_preset(&mut self, preset_id: &str) -> CollabResult<bool> {
+        if !self.get_state_ref()?.presets.contains_key(preset_id) {
+            return Ok(false);
+        }
+        self.update_state(|state| {
+            state.presets.remove(preset_id);
+        })?;
+        Ok(true)
+    }
 
     // =========================================================================
-    // SYNC OPERATIONS
+    // NAMED SNAPSHOTS
     // =========================================================================
 
-    /// Merges another document into this one.
-    pub fn merge(&mut self, other: &mut Self) -> CollabResult<()> {
-        self.invalidate_all_caches(); // Must invalidate topology cache on merge
-        self.doc.merge(&mut other.doc)?;
+    /// Records the current heads under `name`, replacing any snapshot with
+    /// that name (see `crate::snapshot`).
+    pub fn create_snapshot(&mut self, name: &str) -> CollabResult<()> {
+        let snapshot = NamedSnapshot::capture(&mut self.doc);
+        self.update_state(|state| {
+            state.snapshots.insert(name.to_string(), snapshot);
+        })
+    }
+
+    /// Returns the named snapshots, sorted by name.
+    pub fn list_snapshots(&mut self) -> CollabResult<BTreeMap<String, NamedSnapshot>> {
+        let snapshots = &self.get_state_ref()?.snapshots;
+        Ok(snapshots.iter().map(|(name, s)| (name.clone(), s.clone())).collect())
+    }
+
+    /// Deletes a named snapshot. Returns false if it did not exist.
+    pub fn delete_snapshot(&mut self, name: &str) -> CollabResult<bool> {
+        if !self.get_state_ref()?.snapshots.contains_key(name) {
+            return Ok(false);
+        }
+        self.update_state(|state| {
+            state.snapshots.remove(name);
+        })?;
+        Ok(true)
+    }
+
+    /// Writes the state recorded by snapshot `name` back as a new change.
+    /// The ACL, actor names and snapshot list keep their current values.
+    /// Fails with `SnapshotNotFound` for an unknown name and
+    /// `InvalidChangeHash` if its heads were compacted away.
+    pub fn restore_snapshot(&mut self, name: &str) -> CollabResult<()> {
+        let snapshot = self
+            .get_state_ref()?
+            .snapshots
+            .get(name)
+            .cloned()
+            .ok_or_else(|| CollabError::snapshot_not_found(name))?;
+        let mut restored: DocumentRoot = snapshot.state(&mut self.doc)?;
+        self.doc.commit();
+        let result = self.update_state(|state| {
+            restored.acl = std::mem::take(&mut state.acl);
+            restored.actor_names = std::mem::take(&mut state.actor_names);
+            restored.snapshots = std::mem::take(&mut state.snapshots);
+            *state = restored;
+        });
+        if let Err(e) = result {
+            self.rollback_batch();
+            return Err(e);
+        }
+        self.doc.commit_with(
+            CommitOptions::default().with_message(format!("Restore snapshot {}", name)),
+        );
+        self.flush_store()?;
+        Ok(())
+    }
+
+    // =========================================================================
+    // PERMISSIONS
+    // =========================================================================
+
+    /// Returns the local user and ACL enforcement mode.
+    pub fn permission_context(&self) -> &PermissionContext {
+        &self.permissions
+    }
+
+    /// Sets the local user and ACL enforcement mode checked before local
+    /// edits. Remote changes are never checked.
+    pub fn set_permission_context(&mut self, context: PermissionContext) {
+        self.permissions = context;
+    }
+
+    /// Returns and clears the denials recorded in warn mode.
+    pub fn take_permission_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.permission_warnings)
+    }
+
+    /// Returns the document's ACL.
+    pub fn get_acl(&mut self) -> CollabResult<Acl> {
+        Ok(self.get_state_ref()?.acl.clone())
+    }
+
+    /// Gives `user_id` exactly `role`. Requires the owner role once the
+    /// document is claimed.
+    pub fn grant_role(&mut self, user_id: &str, role: Role) -> CollabResult<()> {
+        self.check_permission(Action::Manage)?;
+        self.write_state(|state| state.acl.grant(user_id, role))
+    }
+
+    /// Removes every role of `user_id`. Returns false if it had none.
+    pub fn revoke_role(&mut self, user_id: &str) -> CollabResult<bool> {
+        self.check_permission(Action::Manage)?;
+        let mut revoked = false;
+        self.write_state(|state| revoked = state.acl.revoke(user_id))?;
+        Ok(revoked)
+    }
+
+    /// Checks `action` against the ACL according to the permission context.
+    fn check_permission(&mut self, action: Action) -> CollabResult<()> {
+        if self.permissions.enforcement == Enforcement::Off {
+            return Ok(());
+        }
+        let stored;
+        let acl = match &self.cached_state {
+            Some(state) => &state.acl,
+            None => {
+                let hydrated: Option<Acl> = hydrate_prop(&self.doc, ROOT, "acl")?;
+                stored = hydrated.unwrap_or_default();
+                &stored
+            }
+        };
+        self.permissions
+            .enforce(acl, action, &mut self.permission_warnings)
+    }
+
+    /// Checks `action`, then drops the cached state ahead of a direct write.
+    fn begin_edit(&mut self, action: Action) -> CollabResult<()> {
+        self.check_permission(action)?;
+        self.cached_state = None;
+        Ok(())
+    }
+
+    // =========================================================================
+    // COMMENTS
+    // =========================================================================
+
+    /// Adds a comment (or a reply, if `parent_id` is set) to a node.
+    pub fn add_comment(&mut self, node_id: &str, comment: Comment) -> CollabResult<()> {
+        self.update_comments(node_id, |comments| comment::push_comment(comments, comment))
+    }
+
+    /// Marks a comment as resolved (or reopens it).
+    pub fn resolve_comment(
+        &mut self,
+        node_id: &str,
+        comment_id: &str,
+        resolved: bool,
+    ) -> CollabResult<()> {
+        self.update_comments(node_id, |comments| {
+            comment::resolve_comment(comments, comment_id, resolved)
+        })
+    }
+
+    /// Deletes a comment and all of its replies.
+    pub fn delete_comment(&mut self, node_id: &str, comment_id: &str) -> CollabResult<()> {
+        self.update_comments(node_id, |comments| comment::remove_comment(comments, comment_id))
+    }
+
+    /// Returns all comments on a node.
+    pub fn get_comments(&mut self, node_id: &str) -> CollabResult<Vec<Comment>> {
+        let node = self
+            .hydrate_node(node_id)?
+            .ok_or_else(|| CollabError::node_not_found(node_id))?;
+        Ok(node.comments().to_vec())
+    }
+
+    /// Applies `f` to a node's comment list and reconciles only if it succeeds.
+    fn update_comments<F>(&mut self, node_id: &str, f: F) -> CollabResult<()>
+    where
+        F: FnOnce(&mut Vec<Comment>) -> CollabResult<()>,
+    {
+        self.check_permission(Action::Comment)?;
+        let mut comments = self.get_comments(node_id)?;
+        f(&mut comments)?;
+        self.write_state(|state| {
+            if let Some(node) = state.generations.get_mut(node_id) {
+                node.comments = Some(comments);
+            }
+        })
+    }
+
+    // =========================================================================
+    // TARGETED OUTPUT OPERATIONS (Direct list ops, O(1) in document size)
+    // =========================================================================
+
+    /// Appends an output via a direct list insert, bypassing full reconcile.
+    pub fn add_output_direct(&mut self, node_id: &str, output: OutputAsset) -> CollabResult<()> {
+        self.begin_edit(Action::Edit)?;
+        let outputs_obj = self.get_outputs_obj(node_id)?;
+        let len = self.doc.length(&outputs_obj);
+        reconcile_insert(&mut self.doc, outputs_obj.clone(), len, &output)?;
+        Ok(())
+    }
+
+    /// Appends a source asset via a direct list insert (O(1)), creating the
+    /// list on nodes saved before input assets existed.
+    pub fn add_input_asset(&mut self, node_id: &str, asset: InputAsset) -> CollabResult<()> {
+        self.begin_edit(Action::Edit)?;
+        let node_obj = self.get_node_obj(node_id)?;
+        let assets_obj = match self.doc.get(&node_obj, "input_assets")? {
+            Some((Value::Object(ObjType::List), obj)) => obj,
+            _ => self.doc.put_object(&node_obj, "input_assets", ObjType::List)?,
+        };
+        let len = self.doc.length(&assets_obj);
+        reconcile_insert(&mut self.doc, assets_obj, len, &asset)?;
+        Ok(())
+    }
+
+    /// Removes the source asset at `index`.
+    pub fn remove_input_asset(&mut self, node_id: &str, index: usize) -> CollabResult<()> {
+        self.begin_edit(Action::Edit)?;
+        let node_obj = self.get_node_obj(node_id)?;
+        let assets_obj = match self.doc.get(&node_obj, "input_assets")? {
+            Some((Value::Object(ObjType::List), obj)) => obj,
+            _ => return Err(CollabError::index_out_of_bounds(index, 0)),
+        };
+        let len = self.doc.length(&assets_obj);
+        if index >= len {
+            return Err(CollabError::index_out_of_bounds(index, len));
+        }
+        self.doc.delete(&assets_obj, index)?;
+        Ok(())
+    }
+
+    /// Removes the output at `index`.
+    pub fn remove_output(&mut self, node_id: &str, index: usize) -> CollabResult<()> {
+        self.begin_edit(Action::Edit)?;
+        let outputs_obj = self.get_outputs_obj(node_id)?;
+        let at = self.output_list_index(&outputs_obj, index)?;
+        self.doc.delete(&outputs_obj, at)?;
+        Ok(())
+    }
+
+    /// Marks the output at `index` as selected and clears all other selections.
+    /// Only outputs whose flag actually changes are written.
+    pub fn set_selected_output(&mut self, node_id: &str, index: usize) -> CollabResult<()> {
+        self.begin_edit(Action::Edit)?;
+        let outputs_obj = self.get_outputs_obj(node_id)?;
+        let at = self.output_list_index(&outputs_obj, index)?;
+        for i in 0..self.doc.length(&outputs_obj) {
+            let output_obj = match self.doc.get(&outputs_obj, i)? {
+                Some((Value::Object(_), obj)) => obj,
+                _ => return Err(CollabError::schema_violation("output is not an object")),
+            };
+            let selected = i == at;
+            let current = matches!(
+                self.doc.get(&output_obj, "is_selected")?,
+                Some((Value::Scalar(s), _)) if matches!(s.as_ref(), ScalarValue::Boolean(true))
+            );
+            if current != selected {
+                self.doc
+                    .put(&output_obj, "is_selected", ScalarValue::Boolean(selected))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves an output from one position to another (same semantics as `move_generation`).
+    ///
+    /// Only the outputs' order keys are written, never the list itself, so
+    /// concurrent edits to the moved outputs survive a merge.
+    pub fn reorder_outputs(&mut self, node_id: &str, from: usize, to: usize) -> CollabResult<()> {
+        self.begin_edit(Action::Edit)?;
+        let outputs_obj = self.get_outputs_obj(node_id)?;
+        let keys = stored_output_keys(&self.doc, &outputs_obj)?;
+        let len = keys.len();
+        if from >= len {
+            return Err(CollabError::index_out_of_bounds(from, len));
+        }
+        if to > len {
+            return Err(CollabError::index_out_of_bounds(to, len));
+        }
+        if from == to {
+            return Ok(());
+        }
+        let mut display = output_display_order(&keys);
+        let moved = display.remove(from);
+        let adjusted_to = if from < to { to - 1 } else { to };
+        display.insert(adjusted_to, moved);
+        for (i, key) in output_keys_for(&keys, &display).into_iter().enumerate() {
+            if key != keys[i] {
+                let output_obj = match self.doc.get(&outputs_obj, i)? {
+                    Some((Value::Object(_), obj)) => obj,
+                    _ => return Err(CollabError::schema_violation("output is not an object")),
+                };
+                let value = key.map_or(ScalarValue::Null, |k| ScalarValue::Str(k.into()));
+                self.doc.put(&output_obj, "order_key", value)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Updates the output at `index` with a closure, reconciling only that
+    /// output. For single-field updates, prefer the `set_output_*` methods.
+    pub fn update_output<F>(&mut self, node_id: &str, index: usize, f: F) -> CollabResult<()>
+    where
+        F: FnOnce(&mut OutputAsset),
+    {
+        self.begin_edit(Action::Edit)?;
+        let outputs_obj = self.get_outputs_obj(node_id)?;
+        let at = self.output_list_index(&outputs_obj, index)?;
+        let mut output: OutputAsset = hydrate_prop(&self.doc, &outputs_obj, at)?;
+        f(&mut output);
+        reconcile_prop(&mut self.doc, &outputs_obj, at, &output)?;
+        Ok(())
+    }
+
+    /// Sets one field of the output at `index` directly (O(1)).
+    fn set_output_field(
+        &mut self,
+        node_id: &str,
+        index: usize,
+        key: &str,
+        value: ScalarValue,
+    ) -> CollabResult<()> {
+        self.begin_edit(Action::Edit)?;
+        let output_obj = self.get_output_obj(node_id, index)?;
+        self.doc.put(&output_obj, key, value)?;
         Ok(())
     }
 
-    /// Generates sync message for incremental sync.
-    /// Returns None if there are no changes since their_heads.
-    pub fn generate_sync_message(&mut self, their_heads: &[ChangeHash]) -> Option<Vec<u8>> {
-        let changes = self.doc.get_changes(their_heads);
-        if changes.is_empty() {
-            return None;
-        }
-        let mut bytes = Vec::new();
-        for change in changes {
-            bytes.extend_from_slice(change.raw_bytes());
+    /// Sets an output's MIME type directly (O(1)). `None` clears it.
+    pub fn set_output_mime_type(
+        &mut self,
+        node_id: &str,
+        index: usize,
+        mime_type: Option<&str>,
+    ) -> CollabResult<()> {
+        let value = mime_type.map_or(ScalarValue::Null, |v| ScalarValue::Str(v.into()));
+        self.set_output_field(node_id, index, "mime_type", value)
+    }
+
+    /// Sets an output's width and height directly (O(1)). `None` clears both.
+    pub fn set_output_dimensions(
+        &mut self,
+        node_id: &str,
+        index: usize,
+        dimensions: Option<(i32, i32)>,
+    ) -> CollabResult<()> {
+        let (width, height) = match dimensions {
+            Some((w, h)) => (ScalarValue::Int(w as i64), ScalarValue::Int(h as i64)),
+            None => (ScalarValue::Null, ScalarValue::Null),
+        };
+        self.set_output_field(node_id, index, "width", width)?;
+        self.set_output_field(node_id, index, "height", height)
+    }
+
+    /// Sets an output's duration directly (O(1)). `None` clears it.
+    pub fn set_output_duration_ms(
+        &mut self,
+        node_id: &str,
+        index: usize,
+        duration_ms: Option<i64>,
+    ) -> CollabResult<()> {
+        let value = duration_ms.map_or(ScalarValue::Null, ScalarValue::Int);
+        self.set_output_field(node_id, index, "duration_ms", value)
+    }
+
+    /// Sets an output's file size directly (O(1)). `None` clears it.
+    pub fn set_output_file_size(
+        &mut self,
+        node_id: &str,
+        index: usize,
+        file_size: Option<i64>,
+    ) -> CollabResult<()> {
+        let value = file_size.map_or(ScalarValue::Null, ScalarValue::Int);
+        self.set_output_field(node_id, index, "file_size", value)
+    }
+
+    /// Sets an output's thumbnail URL directly (O(1)). `None` clears it.
+    pub fn set_output_thumbnail_url(
+        &mut self,
+        node_id: &str,
+        index: usize,
+        thumbnail_url: Option<&str>,
+    ) -> CollabResult<()> {
+        let value = thumbnail_url.map_or(ScalarValue::Null, |v| ScalarValue::Str(v.into()));
+        self.set_output_field(node_id, index, "thumbnail_url", value)
+    }
+
+    /// Stars an output for `user`. Returns false if `user` already starred it.
+    pub fn star_output(&mut self, node_id: &str, index: usize, user: &str) -> CollabResult<bool> {
+        let output_obj = self.get_output_obj(node_id, index)?;
+        let starred: Option<Vec<String>> = hydrate_prop(&self.doc, &output_obj, "starred_by")?;
+        let starred = starred.unwrap_or_default();
+        if starred.iter().any(|u| u == user) {
+            return Ok(false);
+        }
+        self.begin_edit(Action::Edit)?;
+        let starred_obj = match self.doc.get(&output_obj, "starred_by")? {
+            Some((Value::Object(ObjType::List), obj)) => obj,
+            _ => self.doc.put_object(&output_obj, "starred_by", ObjType::List)?,
+        };
+        self.doc.insert(&starred_obj, starred.len(), ScalarValue::Str(user.into()))?;
+        Ok(true)
+    }
+
+    /// Removes `user`'s star from an output, including duplicates added
+    /// concurrently from other devices. Returns false if there was none.
+    pub fn unstar_output(&mut self, node_id: &str, index: usize, user: &str) -> CollabResult<bool> {
+        let output_obj = self.get_output_obj(node_id, index)?;
+        let starred: Option<Vec<String>> = hydrate_prop(&self.doc, &output_obj, "starred_by")?;
+        let starred = starred.unwrap_or_default();
+        if !starred.iter().any(|u| u == user) {
+            return Ok(false);
+        }
+        self.begin_edit(Action::Edit)?;
+        let starred_obj = self.get_obj_at_key(&output_obj, "starred_by")?;
+        for (i, _) in starred.iter().enumerate().rev().filter(|(_, u)| *u == user) {
+            self.doc.delete(&starred_obj, i)?;
+        }
+        Ok(true)
+    }
+
+    /// Returns the outputs `user` starred as `(node_id, index)` pairs, in
+    /// sequence order.
+    pub fn get_starred(&mut self, user: &str) -> CollabResult<Vec<(String, usize)>> {
+        let state = self.get_state_ref()?;
+        let order = state.ordered_ids();
+        Ok(order
+            .iter()
+            .filter_map(|id| state.generations.get(id).map(|node| (id, node)))
+            .flat_map(|(id, node)| {
+                node.outputs
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, output)| output.starred_by.iter().any(|u| u == user))
+                    .map(move |(index, _)| (id.clone(), index))
+            })
+            .collect())
+    }
+
+    // =========================================================================
+    // TIMELINE
+    // =========================================================================
+
+    /// Places a node's clip on the timeline, replacing any earlier placement.
+    pub fn place_on_timeline(
+        &mut self,
+        node_id: &str,
+        placement: &TimelinePlacement,
+    ) -> CollabResult<()> {
+        if placement.start_ms < 0 || placement.offset_ms < 0 {
+            return Err(CollabError::invalid_setting("timeline", "positions must not be negative"));
+        }
+        if placement.duration_ms <= 0 {
+            return Err(CollabError::invalid_setting("timeline", "duration must be positive"));
+        }
+        self.begin_edit(Action::Edit)?;
+        let node_obj = self.get_node_obj(node_id)?;
+        reconcile_prop(&mut self.doc, &node_obj, "timeline", placement)?;
+        Ok(())
+    }
+
+    /// Takes a node's clip off the timeline.
+    pub fn remove_from_timeline(&mut self, node_id: &str) -> CollabResult<()> {
+        self.begin_edit(Action::Edit)?;
+        let node_obj = self.get_node_obj(node_id)?;
+        self.doc.delete(&node_obj, "timeline")?;
+        Ok(())
+    }
+
+    /// Moves a placed clip to `start_ms` on `track` (O(1)). The duration and
+    /// trim are untouched, so a concurrent trim merges with the move.
+    pub fn move_clip(&mut self, node_id: &str, track: &str, start_ms: i64) -> CollabResult<()> {
+        if start_ms < 0 {
+            return Err(CollabError::invalid_setting("timeline", "positions must not be negative"));
+        }
+        self.begin_edit(Action::Edit)?;
+        let timeline_obj = self.get_timeline_obj(node_id)?;
+        self.doc.put(&timeline_obj, "track", ScalarValue::Str(track.into()))?;
+        self.doc.put(&timeline_obj, "start_ms", ScalarValue::Int(start_ms))?;
+        Ok(())
+    }
+
+    /// Splits a placed clip at timeline position `at_ms`. The node keeps the
+    /// left part; `new_id` is created as a copy of it (outputs included,
+    /// `derived_from` set) holding the right part, placed after it in the
+    /// sequence order.
+    pub fn split_clip(&mut self, node_id: &str, new_id: &str, at_ms: i64) -> CollabResult<()> {
+        let state = self.get_state_ref()?;
+        if state.generations.contains_key(new_id) {
+            return Err(CollabError::schema_violation(format!(
+                "node '{}' already exists",
+                new_id
+            )));
+        }
+        let src = state
+            .generations
+            .get(node_id)
+            .ok_or_else(|| CollabError::node_not_found(node_id))?;
+        let placement = src.timeline.clone().ok_or_else(|| {
+            CollabError::schema_violation(format!("node '{}' is not on the timeline", node_id))
+        })?;
+        if at_ms <= placement.start_ms || at_ms >= placement.end_ms() {
+            return Err(CollabError::invalid_setting(
+                "timeline",
+                format!("split point {} is outside the clip", at_ms),
+            ));
+        }
+        let left = at_ms - placement.start_ms;
+        let right = TimelinePlacement {
+            start_ms: at_ms,
+            duration_ms: placement.duration_ms - left,
+            offset_ms: placement.offset_ms + left,
+            ..placement
+        };
+        let node = GenerationNode {
+            id: new_id.to_string(),
+            derived_from: Some(node_id.to_string()),
+            comments: None,
+            timeline: Some(right),
+            order_key: None,
+            ..src.clone()
+        };
+
+        self.update_state(|state| {
+            if let Some(src) = state.generations.get_mut(node_id) {
+                if let Some(timeline) = &mut src.timeline {
+                    timeline.duration_ms = left;
+                }
+            }
+            state.generations.insert(new_id.to_string(), node);
+            state.place_after(new_id, node_id);
+        })
+    }
+
+    /// Returns the IDs of the unarchived nodes placed on `track`, ordered by
+    /// start (then ID).
+    pub fn get_track(&mut self, track: &str) -> CollabResult<Vec<String>> {
+        let state = self.get_state_ref()?;
+        let mut clips: Vec<(i64, &String)> = state
+            .generations
+            .iter()
+            .filter(|(_, node)| !node.archived)
+            .filter_map(|(id, node)| match &node.timeline {
+                Some(placement) if placement.track == track => Some((placement.start_ms, id)),
+                _ => None,
+            })
+            .collect();
+        clips.sort();
+        Ok(clips.into_iter().map(|(_, id)| id.clone()).collect())
+    }
+
+    /// Exports the timeline as OpenTimelineIO JSON (see `crate::export`).
+    #[cfg(feature = "export")]
+    pub fn export_otio(&mut self, name: &str, fps: u32) -> CollabResult<String> {
+        let otio = export::to_otio(self.get_state_ref()?, name, fps);
+        serde_json::to_string_pretty(&otio).map_err(|e| CollabError::serialization(e.to_string()))
+    }
+
+    /// Exports the timeline as a CMX 3600 EDL (see `crate::export`).
+    #[cfg(feature = "export")]
+    pub fn export_edl(&mut self, title: &str, fps: u32) -> CollabResult<String> {
+        Ok(export::to_edl(self.get_state_ref()?, title, fps))
+    }
+
+    // =========================================================================
+    // KEYFRAMES
+    // =========================================================================
+
+    /// Sets `param` to `value` at time `t` (ms), updating the keyframe at `t`
+    /// or inserting one in time order. Only that keyframe is written, so
+    /// concurrent edits to other keyframes merge.
+    pub fn set_keyframe(&mut self, node_id: &str, param: &str, t: i64, value: f64) -> CollabResult<()> {
+        self.begin_edit(Action::Edit)?;
+        let node_obj = self.get_node_obj(node_id)?;
+        let keyframes_obj = match self.doc.get(&node_obj, "keyframes")? {
+            Some((Value::Object(ObjType::Map), obj)) => obj,
+            _ => self.doc.put_object(&node_obj, "keyframes", ObjType::Map)?,
+        };
+        let list_obj = match self.doc.get(&keyframes_obj, param)? {
+            Some((Value::Object(ObjType::List), obj)) => obj,
+            _ => self.doc.put_object(&keyframes_obj, param, ObjType::List)?,
+        };
+        let keyframes: Vec<Keyframe> = hydrate_prop(&self.doc, &keyframes_obj, param)?;
+        let matching: Vec<usize> = keyframes
+            .iter()
+            .enumerate()
+            .filter(|(_, k)| k.t == t)
+            .map(|(i, _)| i)
+            .collect();
+        if matching.is_empty() {
+            let index = keyframes.partition_point(|k| k.t < t);
+            reconcile_insert(&mut self.doc, list_obj, index, Keyframe::new(t, value))?;
+            return Ok(());
+        }
+        // Duplicates at `t` can come from concurrent inserts; keep them in step
+        for index in matching {
+            let keyframe_obj = match self.doc.get(&list_obj, index)? {
+                Some((Value::Object(_), obj)) => obj,
+                _ => return Err(CollabError::schema_violation("keyframe is not an object")),
+            };
+            self.doc.put(&keyframe_obj, "value", ScalarValue::F64(value))?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the keyframe of `param` at time `t`. The parameter is dropped
+    /// once its last keyframe is gone. Returns false if there was none.
+    pub fn delete_keyframe(&mut self, node_id: &str, param: &str, t: i64) -> CollabResult<bool> {
+        let node_obj = self.get_node_obj(node_id)?;
+        let keyframes_obj = match self.doc.get(&node_obj, "keyframes")? {
+            Some((Value::Object(ObjType::Map), obj)) => obj,
+            _ => return Ok(false),
+        };
+        let list_obj = match self.doc.get(&keyframes_obj, param)? {
+            Some((Value::Object(ObjType::List), obj)) => obj,
+            _ => return Ok(false),
+        };
+        let keyframes: Vec<Keyframe> = hydrate_prop(&self.doc, &keyframes_obj, param)?;
+        if !keyframes.iter().any(|k| k.t == t) {
+            return Ok(false);
+        }
+        self.begin_edit(Action::Edit)?;
+        if keyframes.iter().all(|k| k.t == t) {
+            self.doc.delete(&keyframes_obj, param)?;
+            if self.doc.length(&keyframes_obj) == 0 {
+                self.doc.delete(&node_obj, "keyframes")?;
+            }
+            return Ok(true);
+        }
+        for (i, _) in keyframes.iter().enumerate().rev().filter(|(_, k)| k.t == t) {
+            self.doc.delete(&list_obj, i)?;
+        }
+        Ok(true)
+    }
+
+    // =========================================================================
+    // JOB QUEUE
+    // =========================================================================
+
+    /// Sets a node's status to "queued" and appends it to the job queue
+    /// (see `super::queue`), dropping any earlier claims on it. A node
+    /// already in the queue keeps its place.
+    pub fn enqueue(&mut self, node_id: &str) -> CollabResult<()> {
+        if !self.get_state_ref()?.generations.contains_key(node_id) {
+            return Err(CollabError::node_not_found(node_id));
+        }
+        self.update_state(|state| {
+            if let Some(node) = state.generations.get_mut(node_id) {
+                node.status = GenerationStatus::Queued.as_str().to_string();
+            }
+            state.queue.claims.insert(node_id.to_string(), Default::default());
+            if !state.queue.order.iter().any(|id| id == node_id) {
+                state.queue.order.push(node_id.to_string());
+            }
+        })
+    }
+
+    /// Claims the first queued node nobody holds for `worker_id` until
+    /// `now + lease_ms`, as a single change: the node moves to "processing"
+    /// and leaves the queue order. Nodes set to "queued" without
+    /// `enqueue()` come after the ordered ones, in sequence order. Returns
+    /// None if nothing is waiting.
+    pub fn claim_next(
+        &mut self,
+        worker_id: &str,
+        lease_ms: i64,
+        now: i64,
+    ) -> CollabResult<Option<String>> {
+        let state = self.get_state_ref()?;
+        let queued = GenerationStatus::Queued.as_str();
+        let order = state.ordered_ids();
+        let next = state
+            .queue
+            .order
+            .iter()
+            .chain(&order)
+            .find(|id| {
+                state.generations.get(*id).is_some_and(|node| node.status == queued)
+                    && state.queue.holder(id, now).is_none()
+            })
+            .cloned();
+        let Some(node_id) = next else {
+            return Ok(None);
+        };
+        let claim = JobClaim {
+            claimed_at: now,
+            lease_expires_at: now + lease_ms,
+        };
+        let message = format!("Claim {}", node_id);
+        self.transaction(Some(&message), |txn| {
+            txn.update_state(|state| {
+                state.queue.order.retain(|id| id != &node_id);
+                state
+                    .queue
+                    .claims
+                    .entry(node_id.clone())
+                    .or_default()
+                    .insert(worker_id.to_string(), claim);
+                if let Some(node) = state.generations.get_mut(&node_id) {
+                    node.status = GenerationStatus::Processing.as_str().to_string();
+                    node.status_updated_at = Some(now);
+                }
+            })
+        })?;
+        Ok(Some(node_id))
+    }
+
+    /// Extends `worker_id`'s lease on a node to `now + lease_ms` and
+    /// refreshes its `status_updated_at`, so `sweep()` does not mark a node
+    /// with a live worker "stalled". Returns false if the worker no longer
+    /// holds the node (its lease expired or a concurrent claim won); the
+    /// worker should then drop the job.
+    pub fn renew_lease(
+        &mut self,
+        node_id: &str,
+        worker_id: &str,
+        lease_ms: i64,
+        now: i64,
+    ) -> CollabResult<bool> {
+        if self.get_state_ref()?.queue.holder(node_id, now) != Some(worker_id) {
+            return Ok(false);
+        }
+        self.update_state(|state| {
+            if let Some(claim) = state
+                .queue
+                .claims
+                .get_mut(node_id)
+                .and_then(|claims| claims.get_mut(worker_id))
+            {
+                claim.lease_expires_at = now + lease_ms;
+            }
+            if let Some(node) = state.generations.get_mut(node_id) {
+                node.status_updated_at = Some(now);
+            }
+        })?;
+        Ok(true)
+    }
+
+    /// Marks a node "completed" at `now` and removes it and its claims from
+    /// the queue, as a single change.
+    pub fn complete(&mut self, node_id: &str, now: i64) -> CollabResult<()> {
+        if !self.get_state_ref()?.generations.contains_key(node_id) {
+            return Err(CollabError::node_not_found(node_id));
+        }
+        let message = format!("Complete {}", node_id);
+        self.transaction(Some(&message), |txn| {
+            txn.update_state(|state| {
+                if let Some(node) = state.generations.get_mut(node_id) {
+                    node.status = GenerationStatus::Completed.as_str().to_string();
+                    node.status_updated_at = Some(now);
+                }
+                state.queue.claims.remove(node_id);
+                state.queue.order.retain(|id| id != node_id);
+            })
+        })
+    }
+
+    /// Returns the worker holding a node at `now`, if any.
+    pub fn claim_holder(&mut self, node_id: &str, now: i64) -> CollabResult<Option<String>> {
+        Ok(self.get_state_ref()?.queue.holder(node_id, now).map(String::from))
+    }
+
+    /// Returns the IDs of nodes `worker_id` has a claim record on, sorted.
+    /// Expired claims are included until `expire_stale_claims()` removes
+    /// them; `claim_holder()` tells whether the worker still holds a node.
+    pub fn get_claimed_by(&mut self, worker_id: &str) -> CollabResult<Vec<String>> {
+        let mut ids: Vec<String> = self
+            .get_state_ref()?
+            .queue
+            .claims
+            .iter()
+            .filter(|(_, claims)| claims.contains_key(worker_id))
+            .map(|(id, _)| id.clone())
+            .collect();
+        ids.sort();
+        Ok(ids)
+    }
+
+    /// Removes claims whose lease ended before `now`, as a single change,
+    /// and returns the IDs of the nodes they were on, sorted. Nodes left
+    /// "processing" without an active claim (their worker crashed) go back
+    /// to "queued" at the front of the queue.
+    pub fn expire_stale_claims(&mut self, now: i64) -> CollabResult<Vec<String>> {
+        let state = self.get_state_ref()?;
+        let mut expired: Vec<String> = state
+            .queue
+            .claims
+            .iter()
+            .filter(|(_, claims)| claims.values().any(|claim| !claim.is_active(now)))
+            .map(|(id, _)| id.clone())
+            .collect();
+        expired.sort();
+        if expired.is_empty() {
+            return Ok(expired);
+        }
+
+        self.transaction(Some("Expire stale claims"), |txn| {
+            txn.update_state(|state| {
+                let processing = GenerationStatus::Processing.as_str();
+                let mut orphaned = Vec::new();
+                for id in &expired {
+                    let Some(claims) = state.queue.claims.get_mut(id) else {
+                        continue;
+                    };
+                    claims.retain(|_, claim| claim.is_active(now));
+                    let Some(node) = state.generations.get_mut(id) else {
+                        continue;
+                    };
+                    if claims.is_empty() && node.status == processing {
+                        node.status = GenerationStatus::Queued.as_str().to_string();
+                        node.status_updated_at = Some(now);
+                        orphaned.push(id.clone());
+                    }
+                }
+                state.queue.order.retain(|id| !orphaned.contains(id));
+                orphaned.append(&mut state.queue.order);
+                state.queue.order = orphaned;
+            })
+        })?;
+        Ok(expired)
+    }
+
+    // =========================================================================
+    // MAINTENANCE
+    // =========================================================================
+
+    /// Downgrades nodes stuck in "processing" longer than the default TTL to "stalled".
+    pub fn sweep(&mut self, now: i64) -> CollabResult<SweepReport> {
+        self.sweep_with_config(now, &SweepConfig::default())
+    }
+
+    /// Downgrades nodes stuck in "processing" longer than `config.processing_ttl_ms`
+    /// to "stalled", as a single change. Nodes without `status_updated_at`
+    /// and nodes a worker holds an active claim on are skipped; those are
+    /// recovered by `expire_stale_claims()` once the lease runs out.
+    pub fn sweep_with_config(&mut self, now: i64, config: &SweepConfig) -> CollabResult<SweepReport> {
+        let state = self.get_state()?;
+        let mut stale: Vec<(String, i64)> = state
+            .generations
+            .iter()
+            .filter(|(id, _)| state.queue.holder(id, now).is_none())
+            .filter_map(|(id, node)| match node.status_updated_at {
+                Some(since) if node.status == PROCESSING_STATUS && config.is_expired(since, now) => {
+                    Some((id.clone(), since))
+                }
+                _ => None,
+            })
+            .collect();
+        stale.sort();
+
+        if !stale.is_empty() {
+            self.transaction(Some("Sweep stale statuses"), |txn| {
+                for (id, _) in &stale {
+                    txn.set_status_at(id, STALLED_STATUS, now)?;
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(SweepReport {
+            stalled: stale
+                .into_iter()
+                .map(|(id, since)| StalledItem { path: vec![id], since })
+                .collect(),
+            ..Default::default()
+        })
+    }
+
+    /// Makes `sequence_order` match `generations` after concurrent edits:
+    /// drops duplicate and dangling IDs and appends orphaned nodes (sorted by
+    /// ID), as a single change. See `crate::repair` for the rules.
+    pub fn repair(&mut self) -> CollabResult<RepairReport> {
+        let mut state = self.get_state()?;
+        let mut report = RepairReport::default();
+        state
+            .generations
+            .repair(&mut report, &["sequence_order"], |_| 0);
+
+        if !report.is_empty() {
+            let generations = state.generations;
+            self.transaction(Some("Repair order lists"), |txn| {
+                txn.update_state(|s| {
+                    s.generations = generations;
+                    s.fill_order_keys();
+                })
+            })?;
+        }
+        Ok(report)
+    }
+
+    // =========================================================================
+    // PRESENCE (ephemeral, not stored in the document)
+    // =========================================================================
+
+    /// Sets this peer's presence and returns the message to broadcast.
+    pub fn set_local_presence(
+        &mut self,
+        user: &str,
+        focused_node: Option<&str>,
+        cursor: Option<Cursor>,
+    ) -> Vec<u8> {
+        self.awareness.set_local(SequencePresence {
+            user: user.to_string(),
+            focused_node: focused_node.map(String::from),
+            cursor,
+        })
+    }
+
+    /// Clears this peer's presence and returns a "left" message to broadcast.
+    pub fn clear_local_presence(&mut self) -> Vec<u8> {
+        self.awareness.clear_local()
+    }
+
+    /// Encodes the current local presence (send periodically as a heartbeat).
+    pub fn encode_presence(&self) -> Vec<u8> {
+        self.awareness.encode()
+    }
+
+    /// Applies a presence message received at `now` (ms since epoch).
+    /// Returns true if peers changed.
+    pub fn apply_presence_message(&mut self, msg: &[u8], now: i64) -> CollabResult<bool> {
+        self.awareness.apply_at(msg, now)
+    }
+
+    /// Sets how long a silent peer is kept before `prune_peers()` drops it.
+    pub fn set_peer_timeout(&mut self, timeout_ms: i64) {
+        self.awareness.set_peer_timeout_ms(timeout_ms);
+    }
+
+    /// Drops peers silent longer than the peer timeout. Returns their actor IDs.
+    pub fn prune_peers(&mut self, now: i64) -> Vec<String> {
+        self.awareness.prune(now)
+    }
+
+    /// Returns the latest presence of each remote peer.
+    pub fn get_peers(&self) -> Vec<PeerPresence<SequencePresence>> {
+        self.awareness.peers()
+    }
+
+    /// Forgets a peer by actor ID (e.g., on disconnect).
+    pub fn remove_peer(&mut self, actor: &str) -> bool {
+        self.awareness.remove_peer(actor)
+    }
+
+    // =========================================================================
+    // LOW-LEVEL TEXT OPERATIONS (Direct Automerge API for performance)
+    // =========================================================================
+
+    // =========================================================================
+    // SYNC OPERATIONS
+    // =========================================================================
+
+    /// Merges another document into this one.
+    pub fn merge(&mut self, other: &mut Self) -> CollabResult<()> {
+        self.invalidate_all_caches(); // Must invalidate topology cache on merge
+        self.doc.merge(&mut other.doc)?;
+        self.flush_store()?;
+        Ok(())
+    }
+
+    /// Applies only the given changes from `other`, plus whichever of their
+    /// dependencies this document lacks (see `crate::cherry_pick`).
+    pub fn cherry_pick(
+        &mut self,
+        other: &mut Self,
+        change_hashes: &[ChangeHash],
+    ) -> CollabResult<CherryPickReport> {
+        self.invalidate_all_caches();
+        let report = cherry_pick::cherry_pick(&mut self.doc, &mut other.doc, change_hashes)?;
+        self.flush_store()?;
+        Ok(report)
+    }
+
+    /// Generates sync message for incremental sync.
+    /// Returns None if there are no changes since their_heads.
+    pub fn generate_sync_message(&mut self, their_heads: &[ChangeHash]) -> Option<Vec<u8>> {
+        let changes = self.doc.get_changes(their_heads);
+        if changes.is_empty() {
+            return None;
+        }
+        let mut bytes = Vec::new();
+        for change in changes {
+            bytes.extend_from_slice(change.raw_bytes());
+        }
+        Some(bytes)
+    }
+
+    /// Like `generate_sync_message()`, but splits the payload into frames of
+    /// at most `max_bytes` that can be applied one by one (see `crate::sync`).
+    /// Returns an empty Vec if there are no changes since their_heads.
+    pub fn generate_sync_messages_chunked(
+        &mut self,
+        their_heads: &[ChangeHash],
+        max_bytes: usize,
+    ) -> Vec<Vec<u8>> {
+        sync::chunked_changes(&mut self.doc, their_heads, max_bytes)
+    }
+
+    /// Like `generate_sync_messages_chunked()`, but sends node structure
+    /// before output and comment changes (see `crate::sync`).
+    pub fn generate_sync_messages_prioritized(
+        &mut self,
+        their_heads: &[ChangeHash],
+        max_bytes: usize,
+    ) -> PrioritizedFrames {
+        sync::prioritized_changes(&mut self.doc, their_heads, max_bytes, HEAVY_SYNC_KEYS)
+    }
+
+    /// Generates the next Automerge sync protocol message for `peer`.
+    /// Returns None when there is nothing to send until the peer replies.
+    ///
+    /// Unlike `generate_sync_message()`, the protocol negotiates what each
+    /// side is missing, so neither side needs to know the other's heads.
+    pub fn generate_protocol_message(&mut self, peer: &mut PeerState) -> Option<Vec<u8>> {
+        self.doc
+            .sync()
+            .generate_sync_message(peer)
+            .map(|message| message.encode())
+    }
+
+    /// Applies an Automerge sync protocol message received from `peer`.
+    pub fn receive_protocol_message(&mut self, peer: &mut PeerState, message: &[u8]) -> CollabResult<()> {
+        let message = automerge::sync::Message::decode(message)
+            .map_err(|e| CollabError::serialization(format!("Invalid sync message: {}", e)))?;
+        self.invalidate_all_caches(); // Must invalidate topology cache on sync
+        self.doc.sync().receive_sync_message(peer, message)?;
+        self.flush_store()?;
+        Ok(())
+    }
+
+    /// Applies sync message from peer.
+    pub fn apply_sync_message(&mut self, msg: &[u8]) -> CollabResult<()> {
+        self.invalidate_all_caches(); // Must invalidate topology cache on sync
+        self.doc.load_incremental(msg)?;
+        self.flush_store()?;
+        Ok(())
+    }
+
+    /// Generates the changes since `their_heads` as one message cut into
+    /// chunks of at most `max_bytes` (see `crate::chunk`). Unlike
+    /// `generate_sync_messages_chunked()`, this also splits single changes
+    /// larger than the limit. Returns an empty Vec if there are no changes.
+    pub fn generate_sync_chunks(&mut self, their_heads: &[ChangeHash], max_bytes: usize) -> Vec<Vec<u8>> {
+        match self.generate_sync_message(their_heads) {
+            Some(message) => chunk::split_message(&message, max_bytes),
+            None => Vec::new(),
+        }
+    }
+
+    /// Adds a chunk from `generate_sync_chunks()`. Once the last chunk of a
+    /// message arrives, the message is verified and applied; returns true
+    /// if that happened.
+    pub fn apply_sync_chunk(&mut self, chunk: &[u8]) -> CollabResult<bool> {
+        match self.chunks.push(chunk)? {
+            Some(message) => {
+                self.apply_sync_message(&message)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    // =========================================================================
+    // COMPRESSION METHODS
+    // =========================================================================
+
+    // =========================================================================
+    // INTERNAL HELPERS - WITH TOPOLOGY CACHING
+    // =========================================================================
+
+    /// Gets the cached "generations" map ObjId, or discovers it.
+    fn get_generations_obj(&mut self) -> CollabResult<ObjId> {
+        if let Some(ref obj) = self.cached_generations_obj {
+            return Ok(obj.clone());
+        }
+        let obj = self.get_obj_at_key(&ROOT, "generations")?;
+        self.cached_generations_obj = Some(obj.clone());
+        Ok(obj)
+    }
+
+    /// Gets a node's ObjId using the cached generations map.
+    fn get_node_obj(&mut self, node_id: &str) -> CollabResult<ObjId> {
+        let gens_obj = self.get_generations_obj()?;
+        self.get_obj_at_key(&gens_obj, node_id)
+    }
+
+    /// Gets the settings ObjId for a node.
+    /// Fails with `FrozenNode` if the node's settings may not be changed.
+    fn get_settings_obj(&mut self, node_id: &str) -> CollabResult<ObjId> {
+        let node_obj = self.get_node_obj(node_id)?;
+        if self.is_frozen_at(&node_obj, node_id)? {
+            return Err(CollabError::frozen_node(node_id));
+        }
+        self.get_obj_at_key(&node_obj, "settings")
+    }
+
+    /// Gets the outputs list ObjId for a node.
+    fn get_outputs_obj(&mut self, node_id: &str) -> CollabResult<ObjId> {
+        let node_obj = self.get_node_obj(node_id)?;
+        self.get_obj_at_key(&node_obj, "outputs")
+    }
+
+    /// Gets the ObjId of the output at `index`.
+    fn get_output_obj(&mut self, node_id: &str, index: usize) -> CollabResult<ObjId> {
+        let outputs_obj = self.get_outputs_obj(node_id)?;
+        let at = self.output_list_index(&outputs_obj, index)?;
+        match self.doc.get(&outputs_obj, at)? {
+            Some((Value::Object(_), obj)) => Ok(obj),
+            _ => Err(CollabError::schema_violation("output is not an object")),
+        }
+    }
+
+    /// Maps an output's display index (the order `get_node()` returns) to
+    /// its index in the stored list, which differ once outputs are reordered.
+    fn output_list_index(&self, outputs_obj: &ObjId, index: usize) -> CollabResult<usize> {
+        let keys = stored_output_keys(&self.doc, outputs_obj)?;
+        output_display_order(&keys)
+            .get(index)
+            .copied()
+            .ok_or_else(|| CollabError::index_out_of_bounds(index, keys.len()))
+    }
+
+    /// Gets the ObjId of a node's timeline placement.
+    fn get_timeline_obj(&mut self, node_id: &str) -> CollabResult<ObjId> {
+        let node_obj = self.get_node_obj(node_id)?;
+        match self.doc.get(&node_obj, "timeline")? {
+            Some((Value::Object(ObjType::Map), obj)) => Ok(obj),
+            _ => Err(CollabError::schema_violation(format!(
+                "node '{}' is not on the timeline",
+                node_id
+            ))),
+        }
+    }
+
+    /// Gets an object ID at a map key.
+    fn get_obj_at_key(&self, parent: &ObjId, key: &str) -> CollabResult<ObjId> {
+        match self.doc.get(parent, key) {
+            Ok(Some((Value::Object(_), obj_id))) => Ok(obj_id),
+            Ok(Some(_)) => Err(CollabError::schema_violation(format!(
+                "'{}' is not an object",
+                key
+            ))),
+            Ok(None) => {
+                if key.len() == 36 {
+                    // Likely a UUID - node not found
+                    Err(CollabError::node_not_found(key))
+                } else {
+                    Err(CollabError::field_not_found(key))
+                }
+            }
+            Err(e) => Err(CollabError::Automerge(e)),
+        }
+    }
+}
+
+impl Default for SequenceManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_manager() {
+        let mut manager = SequenceManager::new();
+        let state = manager.get_state().unwrap();
+        assert!(state.is_empty());
+    }
+
+    #[test]
+    fn test_hydrate_node_without_cache() {
+        let mut manager = SequenceManager::new();
+        for i in 0..5 {
+            let id = format!("gen-{}", i);
+            let node = GenerationNode::new(&id, "t2i").with_prompt(format!("prompt {}", i));
+            manager.create_and_append(&id, node).unwrap();
+        }
+
+        let mut loaded = SequenceManager::from_bytes(&manager.save()).unwrap();
+        assert!(loaded.cached_state.is_none());
+        let node = loaded.hydrate_node("gen-3").unwrap().unwrap();
+        assert_eq!(node.prompt, "prompt 3");
+        assert_eq!(node, manager.get_node("gen-3").unwrap().unwrap());
+        assert!(loaded.hydrate_node("missing").unwrap().is_none());
+        // Single-node reads leave the full-state cache cold
+        assert!(loaded.cached_state.is_none());
+    }
+
+    #[test]
+    fn test_get_summaries_matches_state() {
+        let mut manager = SequenceManager::new();
+        for i in 0..3 {
+            let id = format!("gen-{}", i);
+            let mut node = GenerationNode::new(&id, "t2i").with_title(format!("Shot {}", i));
+            node.outputs.push(OutputAsset::new("https://cdn/a.png"));
+            manager.create_and_append(&id, node).unwrap();
+        }
+        manager.set_status("gen-1", "completed").unwrap();
+        manager.move_generation(2, 0).unwrap();
+
+        let mut loaded = SequenceManager::from_bytes(&manager.save()).unwrap();
+        let summaries = loaded.get_summaries(true).unwrap();
+        assert!(loaded.cached_state.is_none());
+        assert_eq!(summaries, manager.get_summaries(true).unwrap());
+
+        let ids: Vec<&str> = summaries.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["gen-2", "gen-0", "gen-1"]);
+        assert_eq!(summaries[2].status, "completed");
+        assert_eq!(summaries[1].title, "Shot 0");
+        assert_eq!(summaries[0].output_count, 1);
+    }
+
+    #[test]
+    fn test_state_borrowing() {
+        let mut manager = SequenceManager::new();
+        manager.create_and_append("a", GenerationNode::new("a", "t2i")).unwrap();
+
+        let mut loaded = SequenceManager::from_bytes(&manager.save()).unwrap();
+        assert_eq!(loaded.with_state(|state| state.generations.len()).unwrap(), 1);
+        assert_eq!(loaded.get_state_ref().unwrap(), &manager.get_state().unwrap());
+
+        // Mutations are visible through the borrowed view
+        loaded.set_status("a", "queued").unwrap();
+        assert_eq!(loaded.get_state_ref().unwrap().generations["a"].status, "queued");
+    }
+
+    #[test]
+    fn test_raw_automerge_access() {
+        let mut manager = SequenceManager::new();
+        manager.create_and_append("a", GenerationNode::new("a", "t2i")).unwrap();
+        assert!(manager.get_state().unwrap().generations["a"].prompt.is_empty());
+
+        // Low-level write through the raw document is seen by later reads
+        let doc = manager.as_automerge_mut();
+        let (_, generations) = doc.get(ROOT, "generations").unwrap().unwrap();
+        let (_, node) = doc.get(&generations, "a").unwrap().unwrap();
+        doc.put(&node, "prompt", "raw").unwrap();
+        assert_eq!(manager.get_state().unwrap().generations["a"].prompt, "raw");
+
+        let heads = manager.get_heads();
+        let mut doc = manager.into_automerge();
+        assert_eq!(doc.get_heads(), heads);
+    }
+
+    #[test]
+    fn test_create_and_append() {
+        let mut manager = SequenceManager::new();
+        let node = GenerationNode::new("test-id", "t2i").with_prompt("A beautiful sunset");
+
+        manager.create_and_append("test-id", node).unwrap();
+
+        let state = manager.get_state().unwrap();
+        assert_eq!(state.len(), 1);
+        assert_eq!(state.generations.order().len(), 1);
+        assert_eq!(state.generations.order()[0], "test-id");
+    }
+
+    #[test]
+    fn test_create_many_preserves_order() {
+        let mut manager = SequenceManager::new();
+        manager.create_and_append("gen-5", GenerationNode::new("gen-5", "t2i")).unwrap();
+        let changes = manager.get_change_history().len();
+
+        let nodes = (0..300)
+            .map(|i| {
+                let id = format!("gen-{}", i);
+                (id.clone(), GenerationNode::new(&id, "t2i"))
+            })
+            .collect();
+        manager.create_many(nodes).unwrap();
+
+        assert_eq!(manager.get_change_history().len(), changes + 1);
+        let order = manager.get_order(true).unwrap();
+        assert_eq!(order.len(), 300);
+        assert_eq!(&order[..3], ["gen-5", "gen-0", "gen-1"]);
+        assert_eq!(order[299], "gen-299");
+        let loaded = SequenceManager::from_bytes(&manager.save()).unwrap().get_state().unwrap();
+        assert_eq!(loaded, manager.get_state().unwrap());
+    }
+
+    #[test]
+    fn test_duplicate_node_resets_generation() {
+        let mut manager = SequenceManager::new();
+        let node = GenerationNode::new("a", "t2i")
+            .with_prompt("A lighthouse")
+            .with_status("completed")
+            .with_job_id("job-1")
+            .with_output(OutputAsset::new("https://example.com/1.png"));
+        manager.create_and_append("a", node).unwrap();
+        manager.create_and_append("b", GenerationNode::new("b", "t2i")).unwrap();
+        manager.set_setting_seed("a", Some(42)).unwrap();
+
+        manager.duplicate_node("a", "a2").unwrap();
+        assert_eq!(manager.get_order(true).unwrap(), vec!["a", "a2", "b"]);
+        let copy = manager.get_node("a2").unwrap().unwrap();
+        assert_eq!(copy.id, "a2");
+        assert_eq!(copy.prompt, "A lighthouse");
+        assert_eq!(copy.settings.seed, Some(42));
+        assert_eq!(copy.status, "pending");
+        assert_eq!(copy.job_id, None);
+        assert!(copy.outputs.is_empty());
+        assert_eq!(copy.derived_from, Some("a".to_string()));
+
+        assert!(manager.duplicate_node("a", "b").is_err());
+        assert!(manager.duplicate_node("missing", "c").is_err());
+    }
+
+    #[test]
+    fn test_variation_tree() {
+        let mut manager = SequenceManager::new();
+        manager.create_and_append("root", GenerationNode::new("root", "t2i")).unwrap();
+        manager.duplicate_node("root", "v1").unwrap();
+        manager.duplicate_node("v1", "v1a").unwrap();
+        manager.duplicate_node("root", "v2").unwrap();
+
+        let tree = manager.get_variations("root").unwrap().unwrap();
+        // v2 is inserted right after root, so it comes first in the order
+        let ids: Vec<&str> = tree.children.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["v2", "v1"]);
+        assert_eq!(tree.children[1].children[0].id, "v1a");
+
+        // Re-parenting and detaching via the O(1) setter
+        manager.set_derived_from("v1a", Some("v2")).unwrap();
+        manager.set_derived_from("v1", None).unwrap();
+        let tree = manager.get_variations("root").unwrap().unwrap();
+        assert_eq!(tree.children.len(), 1);
+        assert_eq!(tree.children[0].children[0].id, "v1a");
+
+        // Cycles from concurrent edits terminate
+        manager.set_derived_from("root", Some("v1a")).unwrap();
+        let tree = manager.get_variations("root").unwrap().unwrap();
+        assert_eq!(tree.children[0].children[0].children.len(), 0);
+        assert!(manager.get_variations("missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_pipeline_inputs() {
+        let mut manager = SequenceManager::new();
+        for (id, type_) in [("upscale", "upscale"), ("i2v", "i2v"), ("t2i", "t2i")] {
+            manager.create_and_append(id, GenerationNode::new(id, type_)).unwrap();
+        }
+        manager.link_input("i2v", NodeInputRef::new("t2i", 0)).unwrap();
+        manager.link_input("upscale", NodeInputRef::new("i2v", 0)).unwrap();
+        manager.link_input("upscale", NodeInputRef::new("i2v", 0)).unwrap();
+        assert_eq!(manager.get_node("upscale").unwrap().unwrap().inputs.len(), 1);
+        assert_eq!(manager.topological_order().unwrap(), vec!["t2i", "i2v", "upscale"]);
+
+        // Closing the loop is refused
+        let err = manager.link_input("t2i", NodeInputRef::new("upscale", 0)).unwrap_err();
+        assert!(matches!(err, CollabError::DependencyCycle(_)));
+        assert!(manager.link_input("t2i", NodeInputRef::new("missing", 0)).is_err());
+
+        let input = NodeInputRef::new("t2i", 0);
+        assert!(manager.unlink_input("i2v", &input).unwrap());
+        assert!(!manager.unlink_input("i2v", &input).unwrap());
+        assert_eq!(manager.topological_order().unwrap(), vec!["i2v", "upscale", "t2i"]);
+    }
+
+    #[test]
+    fn test_new_with_seed_single_change() {
+        let cinematic = GenerationSettings::new().with_seed(7).with_cfg(4.5);
+        let defaults = GenerationSettings::new().with_num_steps(30);
+        let seed = DocumentSeed::new()
+            .with_placeholder("act-1", "group", "Act 1")
+            .with_placeholder("style", "style_guide", "Style guide")
+            .with_node(GenerationNode::new("gen-1", "t2i"))
+            .with_preset("cinematic", cinematic.clone())
+            .with_defaults(defaults.clone());
+        let mut manager = SequenceManager::new_with_seed(seed).unwrap();
+
+        assert_eq!(manager.get_change_history().len(), 1);
+        assert_eq!(manager.get_order(true).unwrap(), vec!["act-1", "style", "gen-1"]);
+
+        let mut loaded = SequenceManager::from_bytes(&manager.save()).unwrap();
+        assert_eq!(loaded.get_change_history().len(), 1);
+        assert_eq!(loaded.get_state().unwrap().len(), 3);
+        assert_eq!(loaded.get_node("act-1").unwrap().unwrap().title_str(), "Act 1");
+        assert_eq!(loaded.get_presets().unwrap().get("cinematic"), Some(&cinematic));
+        assert_eq!(loaded.get_defaults().unwrap(), defaults);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let mut manager = SequenceManager::new();
+        let node = GenerationNode::new("test-id", "t2i");
+        manager.create_and_append("test-id", node).unwrap();
+
+        let bytes = manager.save();
+        let mut loaded = SequenceManager::from_bytes(&bytes).unwrap();
+
+        let state = loaded.get_state().unwrap();
+        assert_eq!(state.len(), 1);
+        assert!(state.generations.contains_key("test-id"));
+    }
+
+    #[test]
+    fn test_update_settings() {
+        let mut manager = SequenceManager::new();
+        let node = GenerationNode::new("test-id", "t2i");
+        manager.create_and_append("test-id", node).unwrap();
+
+        manager
+            .update_settings("test-id", |settings| {
+                settings.seed = Some(42);
+                settings.cfg = Some(7.5);
+            })
+            .unwrap();
+
+        let node = manager.get_node("test-id").unwrap().unwrap();
+        assert_eq!(node.settings.seed, Some(42));
+        assert_eq!(node.settings.cfg, Some(7.5));
+    }
+
+    #[test]
+    fn test_targeted_settings_update() {
+        let mut manager = SequenceManager::new();
+        let node = GenerationNode::new("test-id", "t2i");
+        manager.create_and_append("test-id", node).unwrap();
+
+        // Use direct O(1) setting updates
+        manager.set_setting_seed("test-id", Some(123)).unwrap();
+        manager.set_setting_cfg("test-id", Some(8.5)).unwrap();
+        manager
+            .set_setting_model("test-id", Some("sdxl-turbo"))
+            .unwrap();
+        manager.set_setting_width("test-id", Some(1024)).unwrap();
+        manager.set_setting_height("test-id", Some(768)).unwrap();
+
+        let node = manager.get_node("test-id").unwrap().unwrap();
+        assert_eq!(node.settings.seed, Some(123));
+        assert_eq!(node.settings.cfg, Some(8.5));
+        assert_eq!(node.settings.model, Some("sdxl-turbo".to_string()));
+        assert_eq!(node.settings.width, Some(1024));
+        assert_eq!(node.settings.height, Some(768));
+
+        // Test deletion
+        manager.set_setting_seed("test-id", None).unwrap();
+        let node = manager.get_node("test-id").unwrap().unwrap();
+        assert_eq!(node.settings.seed, None);
+    }
+
+    #[test]
+    fn test_setting_extra_merges_per_key() {
+        let mut alice = SequenceManager::new();
+        alice.create_and_append("a", GenerationNode::new("a", "t2i")).unwrap();
+        let mut bob = SequenceManager::from_bytes(&alice.save()).unwrap();
+
+        // Concurrent edits to different keys both survive the merge
+        alice.set_setting_extra("a", "sampler", Some("euler".into())).unwrap();
+        alice.set_setting_extra("a", "clip_skip", Some(2i64.into())).unwrap();
+        bob.set_setting_extra("a", "eta", Some(0.5.into())).unwrap();
+        alice.merge(&mut bob).unwrap();
+
+        let settings = alice.get_node("a").unwrap().unwrap().settings;
+        assert_eq!(settings.extra("sampler").and_then(SettingValue::as_str), Some("euler"));
+        assert_eq!(settings.extra("clip_skip").and_then(SettingValue::as_i64), Some(2));
+        assert_eq!(settings.extra("eta").and_then(SettingValue::as_f64), Some(0.5));
+
+        // Removed keys are gone from the state and its JSON
+        for key in ["sampler", "clip_skip", "eta"] {
+            alice.set_setting_extra("a", key, None).unwrap();
+        }
+        alice.update_settings("a", |s| s.seed = Some(1)).unwrap();
+        let state = alice.get_state().unwrap();
+        assert!(state.generations["a"].settings.extra.is_empty());
+        let json = serde_json::to_value(&state.generations["a"].settings).unwrap();
+        assert!(json.get("extra").is_none());
+    }
+
+    #[test]
+    fn test_diffusion_settings_setters() {
+        let mut manager = SequenceManager::new();
+        manager.create_and_append("a", GenerationNode::new("a", "i2i")).unwrap();
+
+        manager.set_setting_sampler("a", Some("euler_a")).unwrap();
+        manager.set_setting_scheduler("a", Some("karras")).unwrap();
+        manager.set_setting_strength("a", Some(0.6)).unwrap();
+        manager
+            .set_setting_loras("a", &[LoraRef::new("film-grain", 0.8), LoraRef::new("anime", 0.3)])
+            .unwrap();
+
+        let mut loaded = SequenceManager::from_bytes(&manager.save()).unwrap();
+        let settings = loaded.get_node("a").unwrap().unwrap().settings;
+        assert_eq!(settings.sampler.as_deref(), Some("euler_a"));
+        assert_eq!(settings.scheduler.as_deref(), Some("karras"));
+        assert_eq!(settings.strength, Some(0.6));
+        assert_eq!(settings.loras[1], LoraRef::new("anime", 0.3));
+
+        // Clearing removes the keys
+        manager.set_setting_sampler("a", None).unwrap();
+        manager.set_setting_loras("a", &[]).unwrap();
+        let settings = manager.get_node("a").unwrap().unwrap().settings;
+        assert_eq!(settings.sampler, None);
+        assert!(settings.loras.is_empty());
+    }
+
+    #[test]
+    fn test_input_assets_kept_apart_from_outputs() {
+        let mut manager = SequenceManager::new();
+        manager.create_and_append("a", GenerationNode::new("a", "inpaint")).unwrap();
+
+        let source = InputAsset::new("https://cdn/src.png", "image").with_mask_url("https://cdn/mask.png");
+        manager.add_input_asset("a", source).unwrap();
+        manager.add_input_asset("a", InputAsset::new("https://cdn/ref.png", "image")).unwrap();
+        manager.remove_input_asset("a", 1).unwrap();
+        assert!(matches!(
+            manager.remove_input_asset("a", 1),
+            Err(CollabError::IndexOutOfBounds { .. })
+        ));
+
+        let mut loaded = SequenceManager::from_bytes(&manager.save()).unwrap();
+        let node = loaded.get_node("a").unwrap().unwrap();
+        assert_eq!(node.input_assets.len(), 1);
+        assert_eq!(node.input_assets[0].mask_url.as_deref(), Some("https://cdn/mask.png"));
+        assert!(node.outputs.is_empty());
+    }
+
+    #[test]
+    fn test_output_metadata_setters() {
+        let mut manager = SequenceManager::new();
+        manager.create_and_append("a", GenerationNode::new("a", "i2v")).unwrap();
+        manager.add_output_direct("a", OutputAsset::new("https://cdn/a.mp4")).unwrap();
+
+        manager.set_output_mime_type("a", 0, Some("video/mp4")).unwrap();
+        manager.set_output_dimensions("a", 0, Some((1280, 720))).unwrap();
+        manager.set_output_thumbnail_url("a", 0, Some("https://cdn/a.jpg")).unwrap();
+        manager
+            .update_output("a", 0, |o| {
+                o.duration_ms = Some(5000);
+                o.file_size = Some(2_000_000);
+            })
+            .unwrap();
+        assert!(manager.set_output_file_size("a", 1, Some(1)).is_err());
+
+        let mut loaded = SequenceManager::from_bytes(&manager.save()).unwrap();
+        let node = loaded.get_node("a").unwrap().unwrap();
+        let output = &node.outputs[0];
+        assert_eq!(output.mime_type.as_deref(), Some("video/mp4"));
+        assert_eq!((output.width, output.height), (Some(1280), Some(720)));
+        assert_eq!(output.duration_ms, Some(5000));
+        assert_eq!(output.file_size, Some(2_000_000));
+        assert_eq!(output.thumbnail_url.as_deref(), Some("https://cdn/a.jpg"));
+    }
+
+    #[test]
+    fn test_set_status_checked() {
+        let mut manager = SequenceManager::new();
+        manager.create_and_append("a", GenerationNode::new("a", "t2i")).unwrap();
+
+        manager.set_status_checked("a", GenerationStatus::Processing).unwrap();
+        manager.set_status_checked("a", GenerationStatus::Completed).unwrap();
+        assert!(matches!(
+            manager.set_status_checked("a", GenerationStatus::Pending),
+            Err(CollabError::IllegalTransition { .. })
+        ));
+        assert_eq!(manager.get_node("a").unwrap().unwrap().status, "completed");
+
+        // The raw setter and a custom table both bypass the default rules
+        manager.set_status("a", "archived").unwrap();
+        manager.set_status_checked("a", GenerationStatus::Pending).unwrap();
+        manager.set_status_transitions(StatusTransitions::permissive());
+        manager.set_status_checked("a", GenerationStatus::Completed).unwrap();
+        manager.set_status_checked("a", GenerationStatus::Pending).unwrap();
+    }
+
+    #[test]
+    fn test_freeze_completed() {
+        let mut manager = SequenceManager::new();
+        manager.create_and_append("a", GenerationNode::new("a", "t2i")).unwrap();
+        manager.set_status("a", "completed").unwrap();
+
+        // Off by default
+        manager.set_setting_seed("a", Some(1)).unwrap();
+
+        manager.set_options(ManagerOptions::new().with_freeze_completed(true));
+        assert!(manager.is_frozen("a").unwrap());
+        assert!(matches!(
+            manager.set_setting_seed("a", Some(2)),
+            Err(CollabError::FrozenNode(_))
+        ));
+        assert!(matches!(
+            manager.update_settings("a", |s| s.cfg = Some(5.0)),
+            Err(CollabError::FrozenNode(_))
+        ));
+        assert_eq!(manager.get_node("a").unwrap().unwrap().settings.seed, Some(1));
+
+        manager.unfreeze("a");
+        manager.set_setting_seed("a", Some(2)).unwrap();
+        assert!(!manager.is_frozen("a").unwrap());
+    }
+
+    #[test]
+    fn test_create_with_defaults() {
+        let mut manager = SequenceManager::new();
+        manager.set_default_setting_model(Some("flux-dev")).unwrap();
+        manager.set_default_setting_resolution(Some(ResolutionPreset::FULL_HD)).unwrap();
+        assert!(manager.set_default_setting_fps(Some(0)).is_err());
+
+        manager.create_with_defaults("a", "t2i").unwrap();
+        manager.update_defaults(|d| d.model = Some("sdxl".to_string())).unwrap();
+
+        // Existing nodes keep the defaults they were created with
+        let mut loaded = SequenceManager::from_bytes(&manager.save()).unwrap();
+        let settings = loaded.get_node("a").unwrap().unwrap().settings;
+        assert_eq!(settings.model.as_deref(), Some("flux-dev"));
+        assert_eq!(settings.resolution, Some(ResolutionPreset::FULL_HD));
+        assert_eq!(loaded.get_defaults().unwrap().model.as_deref(), Some("sdxl"));
+    }
+
+    #[test]
+    fn test_settings_presets() {
+        let mut alice = SequenceManager::new();
+        alice.create_and_append("a", GenerationNode::new("a", "t2i")).unwrap();
+        alice
+            .create_preset(
+                "cinematic",
+                GenerationSettings::new().with_model("flux-pro").with_width(3840).with_height(2160),
+            )
+            .unwrap();
+        alice.set_setting_seed("a", Some(7)).unwrap();
+
+        // Presets sync to other peers
+        let mut bob = SequenceManager::from_bytes(&alice.save()).unwrap();
+        bob.apply_preset("a", "cinematic").unwrap();
+        let settings = bob.get_node("a").unwrap().unwrap().settings;
+        assert_eq!(settings.model.as_deref(), Some("flux-pro"));
+        assert_eq!(settings.width, Some(3840));
+        assert_eq!(settings.seed, Some(7));
+
+        assert!(matches!(
+            bob.apply_preset("a", "missing"),
+            Err(CollabError::PresetNotFound(_))
+        ));
+        assert!(bob.delete_preset("cinematic").unwrap());
+        assert!(!bob.delete_preset("cinematic").unwrap());
+        assert!(bob.get_presets().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_tags() {
+        let mut alice = SequenceManager::new();
+        for id in ["a", "b", "c"] {
+            alice.create_and_append(id, GenerationNode::new(id, "t2i")).unwrap();
+        }
+        let mut bob = SequenceManager::from_bytes(&alice.save()).unwrap();
+
+        // The same tag added concurrently is removed in one call
+        assert!(alice.add_tag("c", "hero").unwrap());
+        assert!(!alice.add_tag("c", "hero").unwrap());
+        alice.add_tag("a", "hero").unwrap();
+        bob.add_tag("a", "hero").unwrap();
+        bob.add_tag("b", "rejected").unwrap();
+        alice.merge(&mut bob).unwrap();
+
+        assert_eq!(alice.get_nodes_by_tag("hero").unwrap(), vec!["a", "c"]);
+        assert!(alice.remove_tag("a", "hero").unwrap());
+        assert!(!alice.remove_tag("a", "hero").unwrap());
+        assert_eq!(alice.get_nodes_by_tag("hero").unwrap(), vec!["c"]);
+        assert_eq!(alice.get_nodes_by_tag("rejected").unwrap(), vec!["b"]);
+    }
+
+    #[test]
+    fn test_starred_outputs_merge() {
+        let mut alice = SequenceManager::new();
+        for id in ["a", "b"] {
+            alice.create_and_append(id, GenerationNode::new(id, "t2i")).unwrap();
+            alice.add_output_direct(id, OutputAsset::new("https://cdn/1.png")).unwrap();
+            alice.add_output_direct(id, OutputAsset::new("https://cdn/2.png")).unwrap();
+        }
+        let mut bob = SequenceManager::from_bytes(&alice.save()).unwrap();
+
+        assert!(alice.star_output("b", 1, "alice").unwrap());
+        assert!(!alice.star_output("b", 1, "alice").unwrap());
+        alice.star_output("a", 0, "alice").unwrap();
+        bob.star_output("a", 0, "bob").unwrap();
+        alice.merge(&mut bob).unwrap();
+
+        let node = alice.get_node("a").unwrap().unwrap();
+        let mut starred_by = node.outputs[0].starred_by.clone();
+        starred_by.sort();
+        assert_eq!(starred_by, vec!["alice", "bob"]);
+        assert_eq!(
+            alice.get_starred("alice").unwrap(),
+            vec![("a".to_string(), 0), ("b".to_string(), 1)]
+        );
+        assert!(alice.unstar_output("a", 0, "alice").unwrap());
+        assert!(!alice.unstar_output("a", 0, "alice").unwrap());
+        assert_eq!(alice.get_starred("bob").unwrap(), vec![("a".to_string(), 0)]);
+        assert!(alice.star_output("a", 5, "bob").is_err());
+    }
+
+    #[test]
+    fn test_seen_receipts() {
+        let mut manager = SequenceManager::new();
+        for id in ["a", "b", "c"] {
+            manager.create_and_append(id, GenerationNode::new(id, "t2i")).unwrap();
+        }
+        manager.archive_node("c").unwrap();
+        assert_eq!(manager.unseen_count("alice").unwrap(), 2);
+
+        manager.mark_seen("a", "alice", 100).unwrap();
+        manager.mark_seen("a", "alice", 50).unwrap();
+        manager.mark_seen("b", "alice", 100).unwrap();
+        assert_eq!(manager.get_node("a").unwrap().unwrap().seen["alice"], 100);
+        assert_eq!(manager.unseen_count("alice").unwrap(), 0);
+        assert_eq!(manager.unseen_count("bob").unwrap(), 2);
+
+        manager.set_status_at("b", "completed", 200).unwrap();
+        assert_eq!(manager.unseen_count("alice").unwrap(), 1);
+        assert!(manager.mark_seen("missing", "alice", 100).is_err());
+    }
+
+    #[test]
+    fn test_timeline_clips() {
+        let mut manager = SequenceManager::new();
+        for id in ["a", "b"] {
+            manager.create_and_append(id, GenerationNode::new(id, "i2v")).unwrap();
+        }
+        manager.add_output_direct("a", OutputAsset::new("https://cdn/a.mp4")).unwrap();
+        manager.place_on_timeline("a", &TimelinePlacement::new("V1", 0, 4000)).unwrap();
+        manager.place_on_timeline("b", &TimelinePlacement::new("V2", 1000, 2000)).unwrap();
+        assert!(manager.place_on_timeline("b", &TimelinePlacement::new("V1", 0, 0)).is_err());
+
+        manager.move_clip("b", "V1", 5000).unwrap();
+        manager.split_clip("a", "a2", 1500).unwrap();
+        assert_eq!(manager.get_track("V1").unwrap(), vec!["a", "a2", "b"]);
+        let right = manager.get_node("a2").unwrap().unwrap();
+        let expected = TimelinePlacement {
+            offset_ms: 1500,
+            ..TimelinePlacement::new("V1", 1500, 2500)
+        };
+        assert_eq!(right.timeline, Some(expected));
+        assert_eq!(right.outputs.len(), 1);
+        assert_eq!(manager.get_node("a").unwrap().unwrap().timeline.unwrap().duration_ms, 1500);
+        assert!(manager.split_clip("a", "a3", 1500).is_err());
+
+        manager.remove_from_timeline("b").unwrap();
+        assert_eq!(manager.get_track("V1").unwrap(), vec!["a", "a2"]);
+        assert!(manager.move_clip("b", "V1", 0).is_err());
+    }
+
+    #[test]
+    fn test_keyframes() {
+        let mut alice = SequenceManager::new();
+        alice.create_and_append("a", GenerationNode::new("a", "i2v")).unwrap();
+        let node_obj = alice.get_node_obj("a").unwrap();
+        assert!(alice.doc.get(&node_obj, "keyframes").unwrap().is_none());
+
+        alice.set_keyframe("a", "pan", 1000, 0.5).unwrap();
+        alice.set_keyframe("a", "pan", 0, 0.0).unwrap();
+        let mut bob = SequenceManager::from_bytes(&alice.save()).unwrap();
+        alice.set_keyframe("a", "pan", 2000, 1.0).unwrap();
+        bob.set_keyframe("a", "pan", 1000, 0.8).unwrap();
+        alice.merge(&mut bob).unwrap();
+
+        let node = alice.get_node("a").unwrap().unwrap();
+        assert_eq!(
+            node.keyframes["pan"],
+            vec![Keyframe::new(0, 0.0), Keyframe::new(1000, 0.8), Keyframe::new(2000, 1.0)]
+        );
+        assert!(alice.delete_keyframe("a", "pan", 1000).unwrap());
+        assert!(!alice.delete_keyframe("a", "pan", 1000).unwrap());
+        assert!(!alice.delete_keyframe("a", "zoom", 0).unwrap());
+        alice.delete_keyframe("a", "pan", 0).unwrap();
+        alice.delete_keyframe("a", "pan", 2000).unwrap();
+        assert!(alice.get_node("a").unwrap().unwrap().keyframes.is_empty());
+        assert!(alice.doc.get(&node_obj, "keyframes").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_query() {
+        let mut manager = SequenceManager::new();
+        let nodes = [
+            GenerationNode::new("a", "t2i").with_status("queued").with_created_at(100),
+            GenerationNode::new("b", "i2v").with_status("queued").with_tag("hero"),
+            GenerationNode::new("c", "t2i").with_created_at(300),
+        ];
+        for node in nodes {
+            manager.create_and_append(&node.id.clone(), node).unwrap();
+        }
+
+        // Same answers from the document and from the cached state
+        let mut loaded = SequenceManager::from_bytes(&manager.save()).unwrap();
+        for m in [&mut loaded, &mut manager] {
+            assert_eq!(m.query(&NodeFilter::new().with_status("queued")).unwrap(), vec!["a", "b"]);
+            assert_eq!(
+                m.query(&NodeFilter::new().with_status("queued").with_type("t2i")).unwrap(),
+                vec!["a"]
+            );
+            assert_eq!(m.query(&NodeFilter::new().with_tag("hero")).unwrap(), vec!["b"]);
+            assert_eq!(m.query(&NodeFilter::new().with_created_after(100)).unwrap(), vec!["c"]);
+            assert_eq!(m.query(&NodeFilter::new()).unwrap().len(), 3);
+        }
+    }
+
+    #[cfg(feature = "search")]
+    #[test]
+    fn test_search_prompts_follows_edits() {
+        let mut manager = SequenceManager::new();
+        let a = GenerationNode::new("a", "t2i").with_title("Opening").with_prompt("sunset over the ocean");
+        let b = GenerationNode::new("b", "t2i").with_prompt("city at night");
+        manager.create_and_append("a", a).unwrap();
+        manager.create_and_append("b", b).unwrap();
+
+        assert_eq!(manager.search_prompts("opening sunset").unwrap()[0].0, "a");
+        manager.update_node("b", |node| node.prompt = "sunset city".to_string()).unwrap();
+        manager.delete_node("a").unwrap();
+        let hits = manager.search_prompts("sunset").unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].0, "b");
+    }
+
+    #[test]
+    fn test_archive_node() {
+        let mut manager = SequenceManager::new();
+        for id in ["a", "b", "c"] {
+            manager.create_and_append(id, GenerationNode::new(id, "t2i")).unwrap();
+        }
+        manager.archive_node("b").unwrap();
+
+        let mut loaded = SequenceManager::from_bytes(&manager.save()).unwrap();
+        for m in [&mut loaded, &mut manager] {
+            assert_eq!(m.get_summaries(false).unwrap().len(), 2);
+            assert!(m.get_summaries(true).unwrap()[1].archived);
+            assert_eq!(m.get_order(false).unwrap(), vec!["a", "c"]);
+            assert_eq!(m.get_order(true).unwrap(), vec!["a", "b", "c"]);
+        }
+
+        manager.unarchive_node("b").unwrap();
+        assert_eq!(manager.get_order(false).unwrap(), vec!["a", "b", "c"]);
+        assert!(manager.get_node("b").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_settings_validation() {
+        let mut manager = SequenceManager::new();
+        manager
+            .create_and_append("id", GenerationNode::new("id", "t2i"))
+            .unwrap();
+
+        assert!(matches!(
+            manager.set_setting_cfg("id", Some(45.0)),
+            Err(CollabError::InvalidSetting { .. })
+        ));
+        assert!(manager.set_setting_width("id", Some(1023)).is_err());
+        assert!(manager
+            .update_settings("id", |s| s.num_steps = Some(0))
+            .is_err());
+
+        let node = manager.get_node("id").unwrap().unwrap();
+        assert_eq!(node.settings, GenerationSettings::default());
+
+        manager.set_settings_limits(SettingsLimits::unrestricted());
+        manager.set_setting_cfg("id", Some(45.0)).unwrap();
+        let node = manager.get_node("id").unwrap().unwrap();
+        assert_eq!(node.settings.cfg, Some(45.0));
+
+        // A stored out-of-range value doesn't block unrelated edits
+        manager.set_settings_limits(SettingsLimits::default());
+        manager.update_settings("id", |s| s.seed = Some(7)).unwrap();
+        assert!(manager.update_settings("id", |s| s.cfg = Some(50.0)).is_err());
+        let node = manager.get_node("id").unwrap().unwrap();
+        assert_eq!((node.settings.seed, node.settings.cfg), (Some(7), Some(45.0)));
+    }
+
+    #[test]
+    fn test_set_status() {
+        let mut manager = SequenceManager::new();
+        let node = GenerationNode::new("test-id", "t2i");
+        manager.create_and_append("test-id", node).unwrap();
+
+        manager.set_status("test-id", "processing").unwrap();
+        let node = manager.get_node("test-id").unwrap().unwrap();
+        assert_eq!(node.status, "processing");
+
+        manager.set_status("test-id", "completed").unwrap();
+        let node = manager.get_node("test-id").unwrap().unwrap();
+        assert_eq!(node.status, "completed");
+    }
+
+    #[test]
+    fn test_transaction_single_change() {
+        let mut manager = SequenceManager::new();
+        let before = manager.doc.get_changes(&[]).len();
+
+        manager
+            .transaction(Some("Bulk create"), |txn| {
+                txn.create_and_append("a", GenerationNode::new("a", "t2i"))?;
+                txn.create_and_append("b", GenerationNode::new("b", "t2i"))?;
+                txn.set_status("a", "queued")
+            })
+            .unwrap();
+
+        let changes = manager.doc.get_changes(&[]);
+        assert_eq!(changes.len(), before + 1);
+        assert_eq!(
+            changes.last().unwrap().message().map(|m| m.as_str()),
+            Some("Bulk create")
+        );
+
+        let state = manager.get_state().unwrap();
+        assert_eq!(state.generations.order(), vec!["a", "b"]);
+        assert_eq!(state.generations["a"].status, "queued");
+    }
+
+    #[test]
+    fn test_transaction_rollback_on_error() {
+        let mut manager = SequenceManager::new();
+        manager
+            .create_and_append("a", GenerationNode::new("a", "t2i"))
+            .unwrap();
+
+        let result = manager.transaction(None, |txn| {
+            txn.create_and_append("b", GenerationNode::new("b", "t2i"))?;
+            txn.set_status("missing", "queued")
+        });
+        assert!(result.is_err());
+
+        let state = manager.get_state().unwrap();
+        assert_eq!(state.generations.order(), vec!["a"]);
+        assert!(!state.generations.contains_key("b"));
+    }
+
+    #[test]
+    fn test_update_state_with_message() {
+        let mut manager = SequenceManager::new();
+        manager
+            .update_state_with_message("Generate clicked", 1_700_000_000_000, |state| {
+                state
+                    .generations
+                    .insert("gen-1".to_string(), GenerationNode::new("gen-1", "t2i"));
+            })
+            .unwrap();
+
+        let history = manager.get_change_history();
+        let last = history.last().unwrap();
+        assert_eq!(last.message.as_deref(), Some("Generate clicked"));
+        assert_eq!(last.timestamp, 1_700_000_000_000);
+        assert_eq!(last.actor, manager.actor_id());
+    }
+
+    #[test]
+    fn test_direct_output_operations() {
+        let mut manager = SequenceManager::new();
+        manager
+            .create_and_append("id", GenerationNode::new("id", "t2i"))
+            .unwrap();
+
+        for i in 0..3 {
+            let output = OutputAsset::new(format!("https://example.com/{}.png", i)).with_seed(i);
+            manager.add_output_direct("id", output).unwrap();
+        }
+        let urls = |m: &mut SequenceManager| -> Vec<String> {
+            m.get_node("id").unwrap().unwrap().outputs.into_iter().map(|o| o.url).collect()
+        };
+        assert_eq!(urls(&mut manager).len(), 3);
+
+        manager.set_selected_output("id", 1).unwrap();
+        manager.set_selected_output("id", 2).unwrap();
+        let node = manager.get_node("id").unwrap().unwrap();
+        let selected: Vec<bool> = node.outputs.iter().map(|o| o.is_selected).collect();
+        assert_eq!(selected, vec![false, false, true]);
+
+        manager.reorder_outputs("id", 2, 0).unwrap();
+        let node = manager.get_node("id").unwrap().unwrap();
+        assert_eq!(node.outputs[0].url, "https://example.com/2.png");
+        assert!(node.outputs[0].is_selected);
+        assert_eq!(node.outputs[0].seed, Some(2));
+
+        manager.remove_output("id", 1).unwrap();
+        assert_eq!(
+            urls(&mut manager),
+            vec!["https://example.com/2.png", "https://example.com/1.png"]
+        );
+
+        assert!(matches!(
+            manager.remove_output("id", 5),
+            Err(CollabError::IndexOutOfBounds { index: 5, length: 2 })
+        ));
+
+        // Index-based ops follow the reordered positions.
+        manager.set_selected_output("id", 1).unwrap();
+        manager.set_output_thumbnail_url("id", 1, Some("https://example.com/1-thumb.png")).unwrap();
+        let node = manager.get_node("id").unwrap().unwrap();
+        assert!(node.outputs[1].is_selected);
+        assert_eq!(node.outputs[1].url, "https://example.com/1.png");
+        assert_eq!(
+            node.outputs[1].thumbnail_url.as_deref(),
+            Some("https://example.com/1-thumb.png")
+        );
+
+        // Closure edits keep the display order.
+        manager
+            .update_node("id", |node| node.outputs[0].seed = Some(20))
+            .unwrap();
+        let node = manager.get_node("id").unwrap().unwrap();
+        assert_eq!(node.outputs[0].url, "https://example.com/2.png");
+        assert_eq!(node.outputs[0].seed, Some(20));
+        assert_eq!(
+            urls(&mut manager),
+            vec!["https://example.com/2.png", "https://example.com/1.png"]
+        );
+    }
+
+    #[test]
+    fn test_reorder_outputs_keeps_concurrent_edits() {
+        let mut alice = SequenceManager::new();
+        alice.create_and_append("id", GenerationNode::new("id", "t2i")).unwrap();
+        for i in 0..3 {
+            let output = OutputAsset::new(format!("https://cdn/{}.png", i));
+            alice.add_output_direct("id", output).unwrap();
         }
-        Some(bytes)
+        let mut bob = SequenceManager::from_bytes(&alice.save()).unwrap();
+
+        alice.reorder_outputs("id", 2, 0).unwrap();
+        bob.set_output_mime_type("id", 2, Some("image/png")).unwrap();
+        bob.star_output("id", 2, "bob").unwrap();
+        alice.merge(&mut bob).unwrap();
+
+        let node = alice.get_node("id").unwrap().unwrap();
+        let urls: Vec<&str> = node.outputs.iter().map(|o| o.url.as_str()).collect();
+        assert_eq!(urls, vec!["https://cdn/2.png", "https://cdn/0.png", "https://cdn/1.png"]);
+        assert_eq!(node.outputs[0].mime_type.as_deref(), Some("image/png"));
+        assert_eq!(node.outputs[0].starred_by, vec!["bob"]);
     }
 
-    /// Applies sync message from peer.
-    pub fn apply_sync_message(&mut self, msg: &[u8]) -> CollabResult<()> {
-        self.invalidate_all_caches(); // Must invalidate topology cache on sync
-        self.doc.load_incremental(msg)?;
-        Ok(())
+    #[test]
+    fn test_comment_threads() {
+        let mut manager = SequenceManager::new();
+        manager
+            .create_and_append("id", GenerationNode::new("id", "t2i"))
+            .unwrap();
+
+        manager
+            .add_comment("id", Comment::new("c1", "alice", "Too dark").with_created_at(1))
+            .unwrap();
+        manager
+            .add_comment("id", Comment::new("c2", "bob", "Agreed").with_parent("c1"))
+            .unwrap();
+        manager.resolve_comment("id", "c1", true).unwrap();
+
+        let bytes = manager.save();
+        let mut loaded = SequenceManager::from_bytes(&bytes).unwrap();
+        let node = loaded.get_node("id").unwrap().unwrap();
+        assert_eq!(node.comments().len(), 2);
+        assert!(node.comments()[0].resolved);
+        assert_eq!(node.replies("c1")[0].author, "bob");
+
+        loaded.delete_comment("id", "c1").unwrap();
+        assert!(loaded.get_comments("id").unwrap().is_empty());
+
+        assert!(matches!(
+            loaded.resolve_comment("id", "missing", true),
+            Err(CollabError::CommentNotFound(_))
+        ));
+        assert!(loaded.add_comment("nope", Comment::new("c", "a", "b")).is_err());
     }
 
-    // =========================================================================
-    // COMPRESSION METHODS
-    // =========================================================================
+    #[test]
+    fn test_provider_and_job_id() {
+        let mut manager = SequenceManager::new();
+        manager
+            .create_and_append("a", GenerationNode::new("a", "t2i"))
+            .unwrap();
+        manager
+            .create_and_append("b", GenerationNode::new("b", "t2i").with_provider("fal"))
+            .unwrap();
 
-    // =========================================================================
-    // INTERNAL HELPERS - WITH TOPOLOGY CACHING
-    // =========================================================================
+        manager.set_job_id("b", Some("job-123")).unwrap();
+        assert_eq!(
+            manager.find_node_by_job_id("job-123").unwrap().as_deref(),
+            Some("b")
+        );
 
-    /// Gets the cached "generations" map ObjId, or discovers it.
-    fn get_generations_obj(&mut self) -> CollabResult<ObjId> {
-        if let Some(ref obj) = self.cached_generations_obj {
-            return Ok(obj.clone());
+        manager.set_provider("b", Some("replicate")).unwrap();
+        manager.set_job_id("b", None).unwrap();
+        let node = manager.get_node("b").unwrap().unwrap();
+        assert_eq!(node.provider.as_deref(), Some("replicate"));
+        assert_eq!(node.job_id, None);
+        assert_eq!(manager.find_node_by_job_id("job-123").unwrap(), None);
+
+        // Nodes saved before these fields existed still load
+        let node_obj = manager.get_node_obj("a").unwrap();
+        for key in ["status_updated_at", "provider", "job_id"] {
+            manager.doc.delete(&node_obj, key).unwrap();
         }
-        let obj = self.get_obj_at_key(&ROOT, "generations")?;
-        self.cached_generations_obj = Some(obj.clone());
-        Ok(obj)
+        manager.cached_state = None;
+        assert_eq!(manager.get_node("a").unwrap().unwrap().provider, None);
     }
 
-    /// Gets a node's ObjId using the cached generations map.
-    fn get_node_obj(&mut self, node_id: &str) -> CollabResult<ObjId> {
-        let gens_obj = self.get_generations_obj()?;
-        self.get_obj_at_key(&gens_obj, node_id)
-    }
+    #[test]
+    fn test_storyboard_ref() {
+        let mut manager = SequenceManager::new();
+        manager
+            .create_and_append("a", GenerationNode::new("a", "t2i"))
+            .unwrap();
+        let shot = DocRef::new("sb-1", "shot-1");
+        manager.set_storyboard_ref("a", Some(&shot)).unwrap();
 
-    /// Gets the settings ObjId for a node.
-    fn get_settings_obj(&mut self, node_id: &str) -> CollabResult<ObjId> {
-        let node_obj = self.get_node_obj(node_id)?;
-        self.get_obj_at_key(&node_obj, "settings")
+        let mut loaded = SequenceManager::from_bytes(&manager.save()).unwrap();
+        let node = loaded.get_node("a").unwrap().unwrap();
+        assert_eq!(node.storyboard_ref.as_ref(), Some(&shot));
+        let resolved = loaded
+            .resolve_storyboard_ref("a", &mut |r: &DocRef| Ok(Some(r.node_id.clone())))
+            .unwrap();
+        assert_eq!(resolved.as_deref(), Some("shot-1"));
+
+        loaded.set_storyboard_ref("a", None).unwrap();
+        let mut never = |_: &DocRef| -> CollabResult<Option<()>> { panic!("no link to resolve") };
+        assert_eq!(loaded.resolve_storyboard_ref("a", &mut never).unwrap(), None);
+        assert!(loaded.resolve_storyboard_ref("missing", &mut never).is_err());
     }
 
-    /// Gets an object ID at a map key.
-    fn get_obj_at_key(&self, parent: &ObjId, key: &str) -> CollabResult<ObjId> {
-        match self.doc.get(parent, key) {
-            Ok(Some((Value::Object(_), obj_id))) => Ok(obj_id),
-            Ok(Some(_)) => Err(CollabError::schema_violation(format!(
-                "'{}' is not an object",
-                key
-            ))),
-            Ok(None) => {
-                if key.len() == 36 {
-                    // Likely a UUID - node not found
-                    Err(CollabError::node_not_found(key))
-                } else {
-                    Err(CollabError::field_not_found(key))
-                }
-            }
-            Err(e) => Err(CollabError::Automerge(e)),
+    #[test]
+    fn test_json_roundtrip() {
+        let mut manager = SequenceManager::new();
+        for id in ["c", "a", "b"] {
+            manager
+                .create_and_append(
+                    id,
+                    GenerationNode::new(id, "t2i")
+                        .with_prompt(format!("prompt {}", id))
+                        .with_settings(GenerationSettings::new().with_seed(7).with_duration(5)),
+                )
+                .unwrap();
         }
+        manager
+            .add_output("a", OutputAsset::new("https://example.com/a.png").with_selected(true))
+            .unwrap();
+
+        let json = manager.to_json().unwrap();
+        assert_eq!(json, manager.to_json().unwrap());
+
+        let mut imported = SequenceManager::from_json(&json).unwrap();
+        assert_eq!(imported.get_state().unwrap(), manager.get_state().unwrap());
+        assert_eq!(imported.get_order(true).unwrap(), vec!["c", "a", "b"]);
+        assert_eq!(imported.get_change_history().len(), 1);
+
+        assert!(SequenceManager::from_json("{").is_err());
+        assert!(SequenceManager::from_json(
+            r#"{"sequence_order": ["missing"], "generations": {}}"#
+        )
+        .is_err());
     }
-}
 
-impl Default for SequenceManager {
-    fn default() -> Self {
-        Self::new()
+    #[test]
+    fn test_presence_roundtrip() {
+        let mut alice = SequenceManager::new();
+        let mut bob = SequenceManager::new();
+        let heads = bob.get_heads();
+
+        let msg = alice.set_local_presence("Alice", Some("gen-42"), Some(Cursor::caret("prompt", 7)));
+        assert!(bob.apply_presence_message(&msg, 1_000).unwrap());
+
+        let peers = bob.get_peers();
+        assert_eq!(peers.len(), 1);
+        assert_eq!(peers[0].actor, alice.actor_id());
+        assert_eq!(peers[0].state.focused_node.as_deref(), Some("gen-42"));
+        assert_eq!(peers[0].state.cursor.as_ref().unwrap().head, 7);
+
+        // Presence never touches the document
+        assert_eq!(bob.get_heads(), heads);
+
+        bob.apply_presence_message(&alice.clear_local_presence(), 2_000).unwrap();
+        assert!(bob.get_peers().is_empty());
+
+        // Silent peers are pruned once the timeout passes
+        bob.set_peer_timeout(1_000);
+        bob.apply_presence_message(&alice.set_local_presence("Alice", None, None), 3_000)
+            .unwrap();
+        assert!(bob.prune_peers(4_000).is_empty());
+        assert_eq!(bob.prune_peers(4_001), vec![alice.actor_id()]);
     }
-}
 
-// =============================================================================
-// TESTS
-// =============================================================================
+    #[test]
+    fn test_presence_after_reload_with_stable_actor() {
+        let mut alice = SequenceManager::new().with_actor(b"alice-tab-1");
+        alice.awareness.set_wall_clock(|| 1_000);
+        let mut bob = SequenceManager::new();
+        for node in ["gen-1", "gen-2", "gen-3"] {
+            let msg = alice.set_local_presence("Alice", Some(node), None);
+            bob.apply_presence_message(&msg, 1_000).unwrap();
+        }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        // Alice reloads a second later under the same actor; her clock must not restart
+        let mut reloaded = SequenceManager::from_bytes(&alice.save())
+            .unwrap()
+            .with_actor(b"alice-tab-1");
+        reloaded.awareness.set_wall_clock(|| 2_000);
+        let msg = reloaded.set_local_presence("Alice", Some("gen-9"), None);
+        assert!(bob.apply_presence_message(&msg, 2_000).unwrap());
+        assert_eq!(bob.get_peers()[0].state.focused_node.as_deref(), Some("gen-9"));
+
+        assert!(bob.apply_presence_message(&reloaded.clear_local_presence(), 3_000).unwrap());
+        assert!(bob.get_peers().is_empty());
+    }
 
     #[test]
-    fn test_new_manager() {
+    fn test_sweep_stalls_old_processing() {
         let mut manager = SequenceManager::new();
+        for id in ["old", "fresh", "done"] {
+            manager
+                .create_and_append(id, GenerationNode::new(id, "t2i"))
+                .unwrap();
+        }
+        manager.set_status_at("old", "processing", 1_000).unwrap();
+        manager.set_status_at("fresh", "processing", 9_000).unwrap();
+        manager.set_status_at("done", "completed", 1_000).unwrap();
+
+        let report = manager
+            .sweep_with_config(10_000, &SweepConfig::new(5_000))
+            .unwrap();
+        assert_eq!(report.stalled.len(), 1);
+        assert_eq!(report.stalled[0].path, vec!["old"]);
+        assert_eq!(report.stalled[0].since, 1_000);
+
         let state = manager.get_state().unwrap();
-        assert!(state.is_empty());
+        assert_eq!(state.generations["old"].status, "stalled");
+        assert_eq!(state.generations["fresh"].status, "processing");
+        assert_eq!(state.generations["done"].status, "completed");
+
+        // Second sweep is a no-op
+        assert!(manager.sweep_with_config(10_000, &SweepConfig::new(5_000)).unwrap().is_empty());
     }
 
     #[test]
-    fn test_create_and_append() {
+    fn test_repair_sequence_order() {
         let mut manager = SequenceManager::new();
-        let node = GenerationNode::new("test-id", "t2i").with_prompt("A beautiful sunset");
+        manager
+            .update_state(|state| {
+                for id in ["b", "a"] {
+                    state.generations.insert_unlisted(id, GenerationNode::new(id, "t2i"));
+                }
+            })
+            .unwrap();
+        // Concurrent edits left the stored list out of step with the map
+        reconcile_prop(&mut manager.doc, ROOT, "sequence_order", vec!["b", "gone", "b"]).unwrap();
+        let mut manager = SequenceManager::from_bytes(&manager.save()).unwrap();
 
-        manager.create_and_append("test-id", node).unwrap();
+        let report = manager.repair().unwrap();
+        assert_eq!(report.actions.len(), 3);
+        assert_eq!(manager.get_order(true).unwrap(), vec!["b", "a"]);
+        assert!(manager.repair().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_fractional_node_order() {
+        let mut manager = SequenceManager::new();
+        for id in ["a", "b", "c"] {
+            manager.create_and_append(id, GenerationNode::new(id, "t2i")).unwrap();
+        }
+        manager.set_fractional_order(true).unwrap();
+
+        // Concurrent moves of different nodes both survive the merge
+        let mut peer = SequenceManager::from_bytes(&manager.save()).unwrap();
+        manager.move_node_between("c", None, Some("a")).unwrap();
+        peer.move_node_between("a", Some("b"), None).unwrap();
+        manager.merge(&mut peer).unwrap();
+        assert_eq!(manager.get_order(true).unwrap(), vec!["c", "b", "a"]);
+        assert_eq!(manager.get_state().unwrap().generations.order(), vec!["a", "b", "c"]);
+
+        // Duplicates land after their source, also in uncached summaries
+        manager.duplicate_node("b", "b2").unwrap();
+        let mut loaded = SequenceManager::from_bytes(&manager.save()).unwrap();
+        let ids: Vec<String> = loaded.get_summaries(true).unwrap().into_iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec!["c", "b", "b2", "a"]);
+        assert!(manager.move_node_between("b", Some("a"), Some("c")).is_err());
 
+        manager.move_generation(0, 4).unwrap();
+        manager.set_fractional_order(false).unwrap();
         let state = manager.get_state().unwrap();
-        assert_eq!(state.len(), 1);
-        assert_eq!(state.sequence_order.len(), 1);
-        assert_eq!(state.sequence_order[0], "test-id");
+        assert_eq!(state.generations.order(), vec!["b", "b2", "a", "c"]);
+        assert!(state.generations.values().all(|node| node.order_key.is_none()));
     }
 
     #[test]
-    fn test_save_and_load() {
+    fn test_incremental_save_and_load() {
         let mut manager = SequenceManager::new();
-        let node = GenerationNode::new("test-id", "t2i");
-        manager.create_and_append("test-id", node).unwrap();
+        manager.create_and_append("gen-1", GenerationNode::new("gen-1", "t2i")).unwrap();
+        let snapshot = manager.save();
+        let uploaded_heads = manager.get_heads();
+        assert!(manager.save_incremental().is_empty());
 
-        let bytes = manager.save();
-        let mut loaded = SequenceManager::from_bytes(&bytes).unwrap();
+        manager.create_and_append("gen-2", GenerationNode::new("gen-2", "t2i")).unwrap();
+        let incremental = manager.save_incremental();
+        assert!(!incremental.is_empty());
+        assert!(manager.save_incremental().is_empty());
 
-        let state = loaded.get_state().unwrap();
-        assert_eq!(state.len(), 1);
-        assert!(state.generations.contains_key("test-id"));
+        let mut restored = SequenceManager::from_bytes(&snapshot).unwrap();
+        restored.load_incremental(&incremental).unwrap();
+        assert_eq!(restored.get_order(true).unwrap(), vec!["gen-1", "gen-2"]);
+
+        // save_since covers everything after the given heads
+        let mut restored = SequenceManager::from_bytes(&snapshot).unwrap();
+        restored.load_incremental(&manager.save_since(&uploaded_heads)).unwrap();
+        assert_eq!(restored.get_state().unwrap(), manager.get_state().unwrap());
+        let heads = manager.get_heads();
+        assert!(manager.save_since(&heads).is_empty());
     }
 
     #[test]
-    fn test_update_settings() {
+    fn test_save_compact_drops_history() {
         let mut manager = SequenceManager::new();
-        let node = GenerationNode::new("test-id", "t2i");
-        manager.create_and_append("test-id", node).unwrap();
+        manager.create_and_append("gen-1", GenerationNode::new("gen-1", "t2i")).unwrap();
+        for i in 0..50 {
+            manager
+                .update_state_with_message("Edit prompt", i, |state| {
+                    state.generations.get_mut("gen-1").unwrap().prompt = format!("take {}", i);
+                })
+                .unwrap();
+        }
+        let stats = manager.history_stats();
+        assert!(stats.changes > 50);
+        assert_eq!(stats.actors, 1);
 
-        manager
-            .update_settings("test-id", |settings| {
-                settings.seed = Some(42);
-                settings.cfg = Some(7.5);
-            })
-            .unwrap();
+        assert!(manager.compact_if(|s| s.changes > 1_000).unwrap().is_none());
+        let bytes = manager.compact_if(|s| s.changes > 50).unwrap().unwrap();
+        assert!(bytes.len() < manager.save().len());
 
-        let node = manager.get_node("test-id").unwrap().unwrap();
-        assert_eq!(node.settings.seed, Some(42));
-        assert_eq!(node.settings.cfg, Some(7.5));
+        let mut compacted = SequenceManager::from_bytes(&bytes).unwrap();
+        assert_eq!(compacted.get_state().unwrap(), manager.get_state().unwrap());
+        let history = compacted.get_change_history();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].message.as_deref(), Some(crate::compact::COMPACT_MESSAGE));
+        assert_ne!(history[0].actor, manager.actor_id());
     }
 
+    #[cfg(feature = "compression")]
     #[test]
-    fn test_targeted_settings_update() {
+    fn test_compressed_save_roundtrip() {
         let mut manager = SequenceManager::new();
-        let node = GenerationNode::new("test-id", "t2i");
-        manager.create_and_append("test-id", node).unwrap();
-
-        // Use direct O(1) setting updates
-        manager.set_setting_seed("test-id", Some(123)).unwrap();
-        manager.set_setting_cfg("test-id", Some(8.5)).unwrap();
-        manager
-            .set_setting_model("test-id", Some("sdxl-turbo"))
-            .unwrap();
-        manager.set_setting_width("test-id", Some(1024)).unwrap();
-        manager.set_setting_height("test-id", Some(768)).unwrap();
+        for i in 0..20 {
+            let id = format!("gen-{}", i);
+            let node = GenerationNode::new(&id, "t2i").with_prompt("a misty forest at dawn");
+            manager.create_and_append(&id, node).unwrap();
+        }
 
-        let node = manager.get_node("test-id").unwrap().unwrap();
-        assert_eq!(node.settings.seed, Some(123));
-        assert_eq!(node.settings.cfg, Some(8.5));
-        assert_eq!(node.settings.model, Some("sdxl-turbo".to_string()));
-        assert_eq!(node.settings.width, Some(1024));
-        assert_eq!(node.settings.height, Some(768));
+        let plain = manager.save();
+        let compressed = manager.save_compressed(crate::compression::DEFAULT_LEVEL);
+        assert!(crate::compression::is_gzipped(&compressed));
 
-        // Test deletion
-        manager.set_setting_seed("test-id", None).unwrap();
-        let node = manager.get_node("test-id").unwrap().unwrap();
-        assert_eq!(node.settings.seed, None);
+        // from_bytes accepts both formats
+        let mut loaded = SequenceManager::from_bytes(&compressed).unwrap();
+        assert_eq!(loaded.get_state().unwrap(), manager.get_state().unwrap());
+        assert!(SequenceManager::from_bytes(&plain).is_ok());
     }
 
+    #[cfg(feature = "crypto")]
     #[test]
-    fn test_set_status() {
+    fn test_encrypted_save_roundtrip() {
         let mut manager = SequenceManager::new();
-        let node = GenerationNode::new("test-id", "t2i");
-        manager.create_and_append("test-id", node).unwrap();
+        manager
+            .create_and_append("gen-1", GenerationNode::new("gen-1", "t2i").with_prompt("secret"))
+            .unwrap();
 
-        manager.set_status("test-id", "processing").unwrap();
-        let node = manager.get_node("test-id").unwrap().unwrap();
-        assert_eq!(node.status, "processing");
+        let params = KeyParams::new("owner@example.com", 1700000000000);
+        let encrypted = manager.save_encrypted(&params).unwrap();
+        assert!(SequenceManager::from_bytes(&encrypted).is_err());
 
-        manager.set_status("test-id", "completed").unwrap();
-        let node = manager.get_node("test-id").unwrap().unwrap();
-        assert_eq!(node.status, "completed");
+        let mut loaded = SequenceManager::from_encrypted_bytes(&encrypted, &params).unwrap();
+        assert_eq!(loaded.get_state().unwrap(), manager.get_state().unwrap());
+
+        let wrong = KeyParams::new("owner@example.com", 1700000000001);
+        assert!(SequenceManager::from_encrypted_bytes(&encrypted, &wrong).is_err());
     }
 
     #[test]
@@ -567,6 +4127,255 @@ mod tests {
         assert!(state_a.generations.contains_key("node-b"));
     }
 
+    #[test]
+    fn test_node_authors() {
+        let mut alice = SequenceManager::new();
+        alice.create_and_append("node-1", GenerationNode::new("node-1", "t2i")).unwrap();
+        alice
+            .update_state_with_message("Rename", 1234, |state| {
+                state.generations.get_mut("node-1").unwrap().title = "Sunset".to_string();
+            })
+            .unwrap();
+        let mut bob = SequenceManager::from_bytes(&alice.save()).unwrap();
+        bob.set_actor_name(&bob.actor_id(), "Bob").unwrap();
+        bob.set_setting_cfg("node-1", Some(7.5)).unwrap();
+        alice.merge(&mut bob).unwrap();
+
+        let authors = alice.get_node_authors("node-1").unwrap();
+        assert_eq!(authors["type_"].actor, alice.actor_id());
+        assert_eq!(authors["type_"].name, None);
+        assert_eq!(authors["settings.cfg"].actor, bob.actor_id());
+        assert_eq!(authors["settings.cfg"].name.as_deref(), Some("Bob"));
+        assert_eq!(authors["title"].actor, alice.actor_id());
+        assert_eq!(authors["title"].timestamp, 1234);
+        assert!(alice.get_node_authors("missing").is_err());
+    }
+
+    #[test]
+    fn test_permission_enforcement() {
+        use crate::acl::{Enforcement, PermissionContext, Role};
+
+        let mut manager = SequenceManager::new();
+        manager.create_and_append("node-1", GenerationNode::new("node-1", "t2i")).unwrap();
+        manager.grant_role("alice", Role::Owner).unwrap();
+        manager.grant_role("bob", Role::Commenter).unwrap();
+
+        manager.set_permission_context(PermissionContext::new("bob", Enforcement::Deny));
+        assert!(matches!(
+            manager.set_status("node-1", "queued"),
+            Err(CollabError::PermissionDenied { .. })
+        ));
+        assert!(manager.grant_role("bob", Role::Owner).is_err());
+        manager.add_comment("node-1", Comment::new("c1", "bob", "Looks good")).unwrap();
+
+        manager.set_permission_context(PermissionContext::new("bob", Enforcement::Warn));
+        manager.set_setting_cfg("node-1", Some(5.0)).unwrap();
+        assert_eq!(manager.take_permission_warnings().len(), 1);
+
+        manager.set_permission_context(PermissionContext::new("alice", Enforcement::Deny));
+        assert!(manager.revoke_role("bob").unwrap());
+        assert_eq!(manager.get_acl().unwrap().role_of("bob"), None);
+    }
+
+    #[test]
+    fn test_stable_actor_across_reloads() {
+        let mut manager = SequenceManager::new().with_actor(b"device-1");
+        assert_eq!(manager.actor_id(), "6465766963652d31");
+        manager.create_and_append("node-1", GenerationNode::new("node-1", "t2i")).unwrap();
+
+        let mut reloaded = SequenceManager::from_bytes(&manager.save()).unwrap();
+        assert_ne!(reloaded.actor_id(), manager.actor_id());
+        reloaded.set_actor(b"device-1");
+        reloaded.set_status("node-1", "queued").unwrap();
+        let history = reloaded.get_change_history();
+        let last = history.last().unwrap();
+        assert_eq!((last.actor.as_str(), last.seq), ("6465766963652d31", 2));
+    }
+
+    #[test]
+    fn test_redact_field_rewrites_history() {
+        let mut manager = SequenceManager::new().with_actor(b"device-1");
+        manager.create_and_append("node-1", GenerationNode::new("node-1", "t2i")).unwrap();
+        manager.update_node("node-1", |node| node.prompt = "portrait of jane@example.com".into()).unwrap();
+        manager.set_status("node-1", "queued").unwrap();
+
+        let bytes = manager.redact_field("node-1", "prompt").unwrap();
+        let reloaded = SequenceManager::from_bytes(&bytes).unwrap().get_node("node-1");
+        assert_eq!(reloaded.unwrap().unwrap().prompt, "[redacted]");
+        let history = automerge::Automerge::load(&bytes).unwrap();
+        for change in history.get_changes(&[]) {
+            let ops = format!("{:?}", change.decode().operations);
+            assert!(!ops.contains("jane@example.com"), "redacted value left in history: {}", ops);
+        }
+        assert_eq!(manager.get_change_history().len(), 1);
+        assert_eq!(manager.actor_id(), "6465766963652d31");
+        assert_eq!(manager.get_node("node-1").unwrap().unwrap().status, "queued");
+        assert!(matches!(
+            manager.redact_field("node-1", "archived"),
+            Err(CollabError::SchemaViolation(_))
+        ));
+    }
+
+    #[test]
+    fn test_bundle_export_import() {
+        let mut manager = SequenceManager::new();
+        manager.create_and_append("node-1", GenerationNode::new("node-1", "t2i")).unwrap();
+        let mut checkpoints = BTreeMap::new();
+        checkpoints.insert("server".to_string(), manager.mark_synced());
+        manager.set_status("node-1", "queued").unwrap();
+
+        let bytes = manager.export_bundle("doc-1", &checkpoints).unwrap();
+        let (mut restored, metadata) = SequenceManager::import_bundle(&bytes).unwrap();
+        assert_eq!(metadata.doc_id, "doc-1");
+        assert_eq!(metadata.checkpoints, checkpoints);
+        assert_eq!(restored.get_heads(), manager.get_heads());
+        assert_eq!(restored.get_node("node-1").unwrap().unwrap().status, "queued");
+        assert!(SequenceManager::import_bundle(&manager.save()).is_err());
+    }
+
+    #[test]
+    fn test_named_snapshot_restore() {
+        let mut manager = SequenceManager::new();
+        manager.create_and_append("node-1", GenerationNode::new("node-1", "t2i")).unwrap();
+        manager.create_snapshot("v1").unwrap();
+        manager.set_status("node-1", "queued").unwrap();
+        manager.create_and_append("node-2", GenerationNode::new("node-2", "i2v")).unwrap();
+        manager.create_snapshot("v2").unwrap();
+
+        manager.restore_snapshot("v1").unwrap();
+        assert_eq!(manager.get_state().unwrap().generations.order(), vec!["node-1"]);
+        assert_eq!(manager.get_node("node-1").unwrap().unwrap().status, "pending");
+        let names: Vec<String> = manager.list_snapshots().unwrap().into_keys().collect();
+        assert_eq!(names, vec!["v1", "v2"]);
+
+        manager.restore_snapshot("v2").unwrap();
+        assert!(manager.get_node("node-2").unwrap().is_some());
+        assert!(matches!(
+            manager.restore_snapshot("missing"),
+            Err(CollabError::SnapshotNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_job_queue_claims() {
+        let mut manager = SequenceManager::new();
+        for id in ["a", "b"] {
+            manager.create_and_append(id, GenerationNode::new(id, "t2i")).unwrap();
+        }
+        manager.enqueue("b").unwrap();
+        manager.enqueue("a").unwrap();
+
+        assert_eq!(manager.claim_next("w1", 1000, 0).unwrap().as_deref(), Some("b"));
+        assert_eq!(manager.get_node("b").unwrap().unwrap().status, "processing");
+        assert!(manager.renew_lease("b", "w1", 1000, 500).unwrap());
+        assert!(!manager.renew_lease("b", "w2", 1000, 500).unwrap());
+        manager.complete("b", 600).unwrap();
+        let node = manager.get_node("b").unwrap().unwrap();
+        assert_eq!((node.status.as_str(), node.status_updated_at), ("completed", Some(600)));
+        assert_eq!(manager.claim_holder("b", 600).unwrap(), None);
+
+        // Two workers claim "a" before syncing; both agree on the winner
+        let mut other = SequenceManager::from_bytes(&manager.save())
+            .unwrap()
+            .with_actor(b"other-peer");
+        assert_eq!(manager.claim_next("w1", 1000, 700).unwrap().as_deref(), Some("a"));
+        assert_eq!(other.claim_next("w2", 1000, 650).unwrap().as_deref(), Some("a"));
+        manager.merge(&mut other).unwrap();
+        other.merge(&mut manager).unwrap();
+        assert_eq!(manager.claim_holder("a", 800).unwrap().as_deref(), Some("w2"));
+        assert_eq!(other.claim_holder("a", 800).unwrap().as_deref(), Some("w2"));
+        assert!(!manager.renew_lease("a", "w1", 1000, 800).unwrap());
+        assert_eq!(manager.claim_next("w1", 1000, 800).unwrap(), None);
+    }
+
+    #[test]
+    fn test_sweep_skips_renewed_claims() {
+        let ttl = SweepConfig::default().processing_ttl_ms;
+        let mut manager = SequenceManager::new();
+        for id in ["a", "b"] {
+            manager.create_and_append(id, GenerationNode::new(id, "t2i")).unwrap();
+            manager.enqueue(id).unwrap();
+        }
+        manager.claim_next("w1", 60_000, 0).unwrap();
+        manager.claim_next("w2", 3 * ttl, 0).unwrap();
+
+        // "a" is renewed past the TTL; "b" holds one long lease
+        let mut now = 0;
+        while now <= ttl {
+            now += 50_000;
+            assert!(manager.renew_lease("a", "w1", 60_000, now).unwrap());
+        }
+        assert!(manager.sweep(2 * ttl).unwrap().is_empty());
+        assert_eq!(manager.get_node("a").unwrap().unwrap().status_updated_at, Some(now));
+        for id in ["a", "b"] {
+            assert_eq!(manager.get_node(id).unwrap().unwrap().status, "processing");
+        }
+
+        // Once the leases run out, the nodes are swept as before
+        let report = manager.sweep(3 * ttl + 1).unwrap();
+        assert_eq!(report.stalled.len(), 2);
+    }
+
+    #[test]
+    fn test_expire_stale_claims_requeues_orphans() {
+        let mut manager = SequenceManager::new();
+        for id in ["a", "b", "c"] {
+            manager.create_and_append(id, GenerationNode::new(id, "t2i")).unwrap();
+            manager.enqueue(id).unwrap();
+        }
+        manager.claim_next("w1", 1000, 0).unwrap();
+        manager.claim_next("w2", 5000, 0).unwrap();
+        assert_eq!(manager.get_claimed_by("w1").unwrap(), vec!["a"]);
+        assert!(manager.expire_stale_claims(500).unwrap().is_empty());
+
+        assert_eq!(manager.expire_stale_claims(2000).unwrap(), vec!["a"]);
+        assert!(manager.get_claimed_by("w1").unwrap().is_empty());
+        assert_eq!(manager.get_claimed_by("w2").unwrap(), vec!["b"]);
+        let node = manager.get_node("a").unwrap().unwrap();
+        assert_eq!((node.status.as_str(), node.status_updated_at), ("queued", Some(2000)));
+        assert_eq!(manager.get_state().unwrap().queue.order, vec!["a", "c"]);
+        assert_eq!(manager.claim_next("w3", 1000, 2000).unwrap().as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn test_activity_feed() {
+        use crate::sequence::ActivityKind;
+
+        let mut manager = SequenceManager::new();
+        manager.create_and_append("node-1", GenerationNode::new("node-1", "t2i")).unwrap();
+        let heads = manager.get_heads();
+        manager.set_status("node-1", "processing").unwrap();
+        manager.add_output_direct("node-1", OutputAsset::new("https://cdn/1.png")).unwrap();
+        manager.create_and_append("node-2", GenerationNode::new("node-2", "i2v")).unwrap();
+
+        let kinds: Vec<ActivityKind> = manager
+            .activity(&heads)
+            .unwrap()
+            .into_iter()
+            .map(|event| event.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ActivityKind::StatusChanged {
+                    id: "node-1".to_string(),
+                    from: "pending".to_string(),
+                    to: "processing".to_string(),
+                },
+                ActivityKind::OutputAdded {
+                    id: "node-1".to_string(),
+                    url: "https://cdn/1.png".to_string(),
+                },
+                ActivityKind::NodeCreated {
+                    id: "node-2".to_string(),
+                },
+            ]
+        );
+        let all = manager.activity(&[]).unwrap();
+        assert_eq!(all[0].kind.to_string(), "created node-1");
+        assert_eq!(all[0].actor, manager.actor_id());
+    }
+
     #[test]
     fn test_string_text_fields() {
         let mut manager = SequenceManager::new();
@@ -4,13 +4,28 @@
 
 pub mod model;
 pub mod manager;
+pub mod activity;
+pub mod graph;
+pub mod limits;
+pub mod options;
+pub mod presence;
+pub mod queue;
+pub mod status;
+pub mod units;
 
 #[cfg(feature = "wasm")]
 pub mod wasm;
 
 // Re-exports for convenience
-pub use model::{DocumentRoot, GenerationNode, GenerationSettings, OutputAsset};
+pub use model::{DocumentRoot, DocumentSeed, GenerationNode, GenerationSettings, InputAsset, Keyframe, LoraRef, NodeFilter, NodeInputRef, NodeSummary, OutputAsset, SettingValue, TimelinePlacement, VariationTree};
 pub use manager::SequenceManager;
+pub use activity::{ActivityEvent, ActivityKind};
+pub use limits::SettingsLimits;
+pub use options::ManagerOptions;
+pub use presence::SequencePresence;
+pub use queue::{JobClaim, JobQueue};
+pub use status::{GenerationStatus, StatusTransitions};
+pub use units::{Dimensions, DurationMs, DurationSecs, Resolution, ResolutionPreset};
 
 #[cfg(feature = "wasm")]
 pub use wasm::JsSequenceManager;
@@ -2,24 +2,111 @@
 //!
 //! These structs use autosurgeon derives for automatic CRDT serialization.
 
-use automerge::{ScalarValue, Value};
+use automerge::{ObjType, ScalarValue, Value};
 use autosurgeon::reconcile::{MapReconciler, NoKey};
-use autosurgeon::{Hydrate, HydrateError, ReadDoc, Reconcile, Reconciler};
+use autosurgeon::{hydrate_prop, Hydrate, HydrateError, ReadDoc, Reconcile, Reconciler};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+use crate::acl::Acl;
+use crate::comment::Comment;
+use crate::link::DocRef;
+use crate::order_key::{self, Keyed};
+use crate::ordered_map::{ordered_map_serde, OrderedMap};
+use crate::raw::{hydrate_optional, hydrate_unknown_fields, reconcile_unknown_fields, RawFields};
+use crate::snapshot::NamedSnapshot;
+use super::queue::JobQueue;
+use super::units::{Dimensions, DurationSecs, Resolution, ResolutionPreset};
 
 // =============================================================================
 // DOCUMENT ROOT
 // =============================================================================
 
 /// Root document structure for a collaborative sequence.
-#[derive(Debug, Clone, Default, Reconcile, Hydrate, Serialize, Deserialize, PartialEq)]
+/// Note: Reconcile and Hydrate are implemented manually because
+/// `generations` is stored as the `generations` map and `sequence_order`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct DocumentRoot {
-    /// Ordered list of generation UUIDs (as strings).
-    pub sequence_order: Vec<String>,
+    /// Generation nodes keyed by UUID string, in sequence order.
+    #[serde(flatten, with = "generation_fields")]
+    pub generations: OrderedMap<GenerationNode>,
+
+    /// Order nodes by `GenerationNode::order_key`; `sequence_order` then
+    /// only lists them (see `crate::order_key`).
+    #[serde(default)]
+    pub fractional_order: bool,
+
+    /// Project-wide settings inherited by nodes created with
+    /// `SequenceManager::create_with_defaults()`.
+    #[serde(default)]
+    pub defaults: GenerationSettings,
+
+    /// Named settings presets (e.g. "Cinematic 4K"), keyed by preset ID.
+    /// See `SequenceManager::apply_preset()`.
+    #[serde(default)]
+    pub presets: HashMap<String, GenerationSettings>,
+
+    /// Display names keyed by hex actor ID, for change attribution.
+    #[serde(default)]
+    pub actor_names: HashMap<String, String>,
+
+    /// Users by role (see `crate::acl`).
+    #[serde(default)]
+    pub acl: Acl,
+
+    /// Named points in the history, keyed by name (see `crate::snapshot`).
+    #[serde(default)]
+    pub snapshots: HashMap<String, NamedSnapshot>,
+
+    /// Render queue order and worker claims (see `super::queue`).
+    #[serde(default)]
+    pub queue: JobQueue,
+}
+
+ordered_map_serde!(generation_fields, "generations", "sequence_order");
+
+impl Reconcile for DocumentRoot {
+    type Key<'a> = NoKey;
+
+    fn reconcile<R: Reconciler>(&self, reconciler: R) -> Result<(), R::Error> {
+        let mut m = reconciler.map()?;
+        self.generations
+            .reconcile_fields(&mut m, "generations", "sequence_order")?;
+        m.put("fractional_order", self.fractional_order)?;
+        m.put("defaults", &self.defaults)?;
+        m.put("presets", &self.presets)?;
+        m.put("actor_names", &self.actor_names)?;
+        m.put("acl", &self.acl)?;
+        m.put("snapshots", &self.snapshots)?;
+        m.put("queue", &self.queue)?;
+        Ok(())
+    }
+}
 
-    /// Map of UUID string -> GenerationNode.
-    pub generations: HashMap<String, GenerationNode>,
+impl Hydrate for DocumentRoot {
+    fn hydrate_map<D: ReadDoc>(
+        doc: &D,
+        obj: &automerge::ObjId,
+    ) -> Result<Self, HydrateError> {
+        // Missing in documents saved before these fields existed
+        let fractional_order: Option<bool> = hydrate_prop(doc, obj, "fractional_order")?;
+        let defaults: Option<GenerationSettings> = hydrate_prop(doc, obj, "defaults")?;
+        let presets: Option<HashMap<String, GenerationSettings>> = hydrate_prop(doc, obj, "presets")?;
+        let actor_names: Option<HashMap<String, String>> = hydrate_prop(doc, obj, "actor_names")?;
+        let acl: Option<Acl> = hydrate_prop(doc, obj, "acl")?;
+        let snapshots: Option<HashMap<String, NamedSnapshot>> = hydrate_prop(doc, obj, "snapshots")?;
+        let queue: Option<JobQueue> = hydrate_prop(doc, obj, "queue")?;
+        Ok(DocumentRoot {
+            generations: OrderedMap::hydrate_fields(doc, obj, "generations", "sequence_order")?,
+            fractional_order: fractional_order.unwrap_or_default(),
+            defaults: defaults.unwrap_or_default(),
+            presets: presets.unwrap_or_default(),
+            actor_names: actor_names.unwrap_or_default(),
+            acl: acl.unwrap_or_default(),
+            snapshots: snapshots.unwrap_or_default(),
+            queue: queue.unwrap_or_default(),
+        })
+    }
 }
 
 impl DocumentRoot {
@@ -37,6 +124,204 @@ impl DocumentRoot {
     pub fn is_empty(&self) -> bool {
         self.generations.is_empty()
     }
+
+    /// Returns the node IDs in display order: `sequence_order`, or sorted
+    /// by order key if `fractional_order` is set.
+    pub fn ordered_ids(&self) -> Vec<String> {
+        if self.fractional_order {
+            order_key::sorted(self.generations.order(), &self.generations)
+        } else {
+            self.generations.order().to_vec()
+        }
+    }
+
+    /// Puts `id` at `index` in display order (clamped), adding it to
+    /// `sequence_order` if missing.
+    pub(crate) fn place_node(&mut self, id: &str, index: usize) {
+        if self.fractional_order {
+            self.generations.list(id);
+            let (order, items) = self.generations.parts_mut();
+            order_key::place(order, items, id, index);
+        } else {
+            self.generations.move_to(id, index);
+        }
+    }
+
+    /// Puts `id` right after `after` in display order, or last if `after`
+    /// is not ordered.
+    pub(crate) fn place_after(&mut self, id: &str, after: &str) {
+        let order: Vec<String> = self.ordered_ids().into_iter().filter(|s| s != id).collect();
+        let index = order.iter().position(|s| s == after).map_or(order.len(), |i| i + 1);
+        self.place_node(id, index);
+    }
+
+    /// Keys the nodes added to `sequence_order` without one (fractional
+    /// order only).
+    pub(crate) fn fill_order_keys(&mut self) {
+        if self.fractional_order {
+            let (order, items) = self.generations.parts_mut();
+            order_key::fill(order, items);
+        }
+    }
+
+    /// Switches node ordering (see `crate::order_key`).
+    pub(crate) fn use_fractional_order(&mut self, enabled: bool) {
+        if self.fractional_order != enabled {
+            let (order, items) = self.generations.parts_mut();
+            order_key::migrate(order, items, enabled);
+            self.fractional_order = enabled;
+        }
+    }
+
+    /// Returns the IDs of nodes tagged `tag`, in sequence order, followed by
+    /// matching nodes missing from the order (by ID).
+    pub fn nodes_by_tag(&self, tag: &str) -> Vec<String> {
+        let has_tag = |id: &String| {
+            self.generations
+                .get(id)
+                .is_some_and(|node| node.tags.iter().any(|t| t == tag))
+        };
+        let order = self.ordered_ids();
+        let mut ids: Vec<String> = order.iter().filter(|id| has_tag(id)).cloned().collect();
+        let ordered: HashSet<&String> = order.iter().collect();
+        let mut unordered: Vec<String> = self
+            .generations
+            .keys()
+            .filter(|id| !ordered.contains(id) && has_tag(id))
+            .cloned()
+            .collect();
+        unordered.sort();
+        ids.extend(unordered);
+        ids
+    }
+
+    /// Returns `id` and everything derived from it, recursively, or None if
+    /// there is no such node. Children appear in sequence order, followed by
+    /// nodes missing from the order (by ID). A node is listed once even if
+    /// concurrent edits made `derived_from` loop.
+    pub fn variation_tree(&self, id: &str) -> Option<VariationTree> {
+        if !self.generations.contains_key(id) {
+            return None;
+        }
+        let order = self.ordered_ids();
+        let ordered: HashSet<&String> = order.iter().collect();
+        let mut unordered: Vec<&String> = self
+            .generations
+            .keys()
+            .filter(|id| !ordered.contains(id))
+            .collect();
+        unordered.sort();
+
+        let mut children: HashMap<&str, Vec<&str>> = HashMap::new();
+        for node_id in order.iter().chain(unordered) {
+            if let Some(parent) = self
+                .generations
+                .get(node_id)
+                .and_then(|node| node.derived_from.as_deref())
+            {
+                children.entry(parent).or_default().push(node_id.as_str());
+            }
+        }
+
+        let mut seen = HashSet::new();
+        Some(build_variation_tree(id, &children, &mut seen))
+    }
+}
+
+fn build_variation_tree<'a>(
+    id: &'a str,
+    children: &HashMap<&'a str, Vec<&'a str>>,
+    seen: &mut HashSet<&'a str>,
+) -> VariationTree {
+    seen.insert(id);
+    let mut tree = VariationTree {
+        id: id.to_string(),
+        children: Vec::new(),
+    };
+    for &child in children.get(id).into_iter().flatten() {
+        if !seen.contains(child) {
+            tree.children.push(build_variation_tree(child, children, seen));
+        }
+    }
+    tree
+}
+
+/// A node and the variations derived from it (see
+/// `SequenceManager::get_variations()`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VariationTree {
+    pub id: String,
+    /// Nodes whose `derived_from` is `id`.
+    pub children: Vec<VariationTree>,
+}
+
+// =============================================================================
+// DOCUMENT SEED
+// =============================================================================
+
+/// Initial content for a brand-new document.
+///
+/// Passed to `SequenceManager::new_with_seed()` so "new project" setup is
+/// written as a single change instead of one reconcile per node.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct DocumentSeed {
+    /// Nodes to create, in sequence order (including group and style-guide
+    /// placeholders, see `with_placeholder()`).
+    pub nodes: Vec<GenerationNode>,
+
+    /// Named settings presets, keyed by preset ID.
+    pub presets: HashMap<String, GenerationSettings>,
+
+    /// Project-wide default settings.
+    pub defaults: GenerationSettings,
+}
+
+impl DocumentSeed {
+    /// Creates an empty seed.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builder: Add a node (appended to the sequence order).
+    pub fn with_node(mut self, node: GenerationNode) -> Self {
+        self.nodes.push(node);
+        self
+    }
+
+    /// Builder: Add an empty titled node (e.g. a group header or style guide)
+    /// for the app to fill in later.
+    pub fn with_placeholder(
+        self,
+        id: impl Into<String>,
+        type_: impl Into<String>,
+        title: impl Into<String>,
+    ) -> Self {
+        self.with_node(GenerationNode::new(id, type_).with_title(title))
+    }
+
+    /// Builder: Add a named settings preset.
+    pub fn with_preset(mut self, id: impl Into<String>, settings: GenerationSettings) -> Self {
+        self.presets.insert(id.into(), settings);
+        self
+    }
+
+    /// Builder: Set the project-wide default settings.
+    pub fn with_defaults(mut self, defaults: GenerationSettings) -> Self {
+        self.defaults = defaults;
+        self
+    }
+
+    /// Converts the seed into a document root.
+    pub fn into_root(self) -> DocumentRoot {
+        let mut root = DocumentRoot::new();
+        for node in self.nodes {
+            root.generations.insert(node.id.clone(), node);
+        }
+        root.presets = self.presets;
+        root.defaults = self.defaults;
+        root
+    }
 }
 
 // =============================================================================
@@ -47,7 +332,10 @@ impl DocumentRoot {
 ///
 /// Text fields (title, prompt, negative_prompt, notes) are local-first Strings.
 /// They are edited locally in the UI and only synced when the user clicks Generate.
-#[derive(Debug, Clone, Reconcile, Hydrate, Serialize, Deserialize, PartialEq)]
+///
+/// Note: Reconcile and Hydrate are implemented manually so fields written by
+/// newer clients survive in `extra` (see `crate::raw`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct GenerationNode {
     /// Unique identifier (stored for convenience, key in map is authoritative).
     pub id: String,
@@ -55,9 +343,77 @@ pub struct GenerationNode {
     /// Generation type: "t2i", "i2v", "text-to-image", etc.
     pub type_: String,
 
-    /// Status: "pending", "processing", "completed", "failed", "queued", "cancelled".
+    /// Status: "pending", "processing", "completed", "failed", "queued", "cancelled", "stalled".
     pub status: String,
 
+    /// When `status` was last set via `set_status_at()` (milliseconds since epoch).
+    /// Used by `sweep()` to detect jobs stuck in "processing".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_updated_at: Option<i64>,
+
+    /// When the node was created (milliseconds since epoch), if the creating
+    /// client recorded it. Used by `NodeFilter::created_after`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<i64>,
+
+    /// External render provider (e.g., "fal", "replicate").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+
+    /// Job ID in the provider's render queue.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+
+    /// ID of the node this one was duplicated from (see `duplicate_node()`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub derived_from: Option<String>,
+
+    /// Storyboard shot this node was generated for, if any. See
+    /// `SequenceManager::set_storyboard_ref()`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storyboard_ref: Option<DocRef>,
+
+    /// Outputs of other nodes this one consumes (e.g. the image an i2v node
+    /// animates). See `SequenceManager::link_input()`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inputs: Vec<NodeInputRef>,
+
+    /// Source assets uploaded for this node (e.g. the image an i2v node
+    /// animates, or an inpainting source and mask). Separate from `outputs`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub input_assets: Vec<InputAsset>,
+
+    /// Soft-deleted: hidden from `get_order(false)` and `get_summaries(false)`
+    /// but kept, with its outputs and lineage, for provenance.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub archived: bool,
+
+    /// Free-form labels ("hero shot", "rejected") for filtering large
+    /// sequences. See `SequenceManager::add_tag()`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+
+    /// When each user last looked at this node (user ID -> milliseconds
+    /// since epoch). See `SequenceManager::mark_seen()`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub seen: HashMap<String, i64>,
+
+    /// Where the node's output sits in the timeline view, if placed.
+    /// See `SequenceManager::place_on_timeline()`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeline: Option<TimelinePlacement>,
+
+    /// Animated parameters (e.g. "camera_pan", "strength"), each a list of
+    /// keyframes sorted by time. Not written while empty. See
+    /// `SequenceManager::set_keyframe()`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub keyframes: HashMap<String, Vec<Keyframe>>,
+
+    /// Fractional index key, used when the document has `fractional_order`
+    /// set. Not written while None.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order_key: Option<String>,
+
     /// Text fields - local-first, synced on Generate click.
     pub title: String,
     pub prompt: String,
@@ -72,6 +428,15 @@ pub struct GenerationNode {
 
     /// Extensible metadata as JSON string (blob approach).
     pub metadata: String,
+
+    /// Review comment threads (flat list, replies link via `parent_id`).
+    /// Optional so documents saved before comments existed still hydrate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comments: Option<Vec<Comment>>,
+
+    /// Fields from a newer schema, preserved as-is.
+    #[serde(flatten)]
+    pub extra: RawFields,
 }
 
 impl GenerationNode {
@@ -81,6 +446,20 @@ impl GenerationNode {
             id: id.into(),
             type_: type_.into(),
             status: "pending".to_string(),
+            status_updated_at: None,
+            created_at: None,
+            provider: None,
+            job_id: None,
+            derived_from: None,
+            storyboard_ref: None,
+            inputs: Vec::new(),
+            input_assets: Vec::new(),
+            archived: false,
+            tags: Vec::new(),
+            seen: HashMap::new(),
+            timeline: None,
+            keyframes: HashMap::new(),
+            order_key: None,
             title: String::new(),
             prompt: String::new(),
             negative_prompt: String::new(),
@@ -88,6 +467,8 @@ impl GenerationNode {
             settings: GenerationSettings::default(),
             outputs: Vec::new(),
             metadata: String::new(),
+            comments: None,
+            extra: RawFields::new(),
         }
     }
 
@@ -97,6 +478,24 @@ impl GenerationNode {
         self
     }
 
+    /// Builder: Set creation time (milliseconds since epoch).
+    pub fn with_created_at(mut self, created_at: i64) -> Self {
+        self.created_at = Some(created_at);
+        self
+    }
+
+    /// Builder: Set render provider.
+    pub fn with_provider(mut self, provider: impl Into<String>) -> Self {
+        self.provider = Some(provider.into());
+        self
+    }
+
+    /// Builder: Set provider job ID.
+    pub fn with_job_id(mut self, job_id: impl Into<String>) -> Self {
+        self.job_id = Some(job_id.into());
+        self
+    }
+
     /// Builder: Set title.
     pub fn with_title(mut self, title: impl Into<String>) -> Self {
         self.title = title.into();
@@ -127,6 +526,18 @@ impl GenerationNode {
         self
     }
 
+    /// Builder: Add a tag.
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Builder: Add an input asset.
+    pub fn with_input_asset(mut self, asset: InputAsset) -> Self {
+        self.input_assets.push(asset);
+        self
+    }
+
     /// Builder: Add an output.
     pub fn with_output(mut self, output: OutputAsset) -> Self {
         self.outputs.push(output);
@@ -139,6 +550,19 @@ impl GenerationNode {
         self
     }
 
+    /// Returns all comments (empty if none).
+    pub fn comments(&self) -> &[Comment] {
+        self.comments.as_deref().unwrap_or(&[])
+    }
+
+    /// Returns the direct replies to a comment.
+    pub fn replies(&self, parent_id: &str) -> Vec<&Comment> {
+        self.comments()
+            .iter()
+            .filter(|c| c.parent_id.as_deref() == Some(parent_id))
+            .collect()
+    }
+
     /// Gets the title as a string slice.
     pub fn title_str(&self) -> &str {
         &self.title
@@ -161,10 +585,24 @@ impl GenerationNode {
 
     /// Converts to a JSON-serializable representation.
     pub fn to_json_value(&self) -> serde_json::Value {
-        serde_json::json!({
+        let mut value = serde_json::json!({
             "id": self.id,
             "type_": self.type_,
             "status": self.status,
+            "status_updated_at": self.status_updated_at,
+            "created_at": self.created_at,
+            "provider": self.provider,
+            "job_id": self.job_id,
+            "derived_from": self.derived_from,
+            "storyboard_ref": self.storyboard_ref,
+            "inputs": self.inputs,
+            "input_assets": self.input_assets,
+            "archived": self.archived,
+            "tags": self.tags,
+            "seen": self.seen,
+            "timeline": self.timeline,
+            "keyframes": self.keyframes,
+            "order_key": self.order_key,
             "title": self.title,
             "prompt": self.prompt,
             "negative_prompt": self.negative_prompt,
@@ -172,7 +610,15 @@ impl GenerationNode {
             "settings": self.settings,
             "outputs": self.outputs,
             "metadata": self.metadata,
-        })
+            "comments": self.comments,
+        });
+        if let serde_json::Value::Object(map) = &mut value {
+            for (key, raw) in &self.extra {
+                map.entry(key.clone())
+                    .or_insert_with(|| serde_json::to_value(raw).unwrap_or_default());
+            }
+        }
+        value
     }
 }
 
@@ -182,6 +628,124 @@ impl Default for GenerationNode {
     }
 }
 
+impl Keyed for GenerationNode {
+    fn order_key(&self) -> Option<&str> {
+        self.order_key.as_deref()
+    }
+
+    fn set_order_key(&mut self, key: Option<String>) {
+        self.order_key = key;
+    }
+}
+
+/// Field names written by this version; anything else goes to `extra`.
+const GENERATION_NODE_FIELDS: &[&str] = &[
+    "id",
+    "type_",
+    "status",
+    "status_updated_at",
+    "created_at",
+    "provider",
+    "job_id",
+    "derived_from",
+    "storyboard_ref",
+    "inputs",
+    "input_assets",
+    "archived",
+    "tags",
+    "seen",
+    "timeline",
+    "keyframes",
+    "order_key",
+    "title",
+    "prompt",
+    "negative_prompt",
+    "notes",
+    "settings",
+    "outputs",
+    "metadata",
+    "comments",
+];
+
+impl Reconcile for GenerationNode {
+    type Key<'a> = NoKey;
+
+    fn reconcile<R: Reconciler>(&self, mut reconciler: R) -> Result<(), R::Error> {
+        let mut m = reconciler.map()?;
+        m.put("id", &self.id)?;
+        m.put("type_", &self.type_)?;
+        m.put("status", &self.status)?;
+        m.put("status_updated_at", self.status_updated_at)?;
+        m.put("created_at", self.created_at)?;
+        m.put("provider", &self.provider)?;
+        m.put("job_id", &self.job_id)?;
+        m.put("derived_from", &self.derived_from)?;
+        m.put("storyboard_ref", &self.storyboard_ref)?;
+        m.put("inputs", &self.inputs)?;
+        m.put("input_assets", &self.input_assets)?;
+        m.put("archived", self.archived)?;
+        m.put("tags", &self.tags)?;
+        m.put("seen", &self.seen)?;
+        m.put("timeline", &self.timeline)?;
+        if self.keyframes.is_empty() {
+            let _ = m.delete("keyframes");
+        } else {
+            m.put("keyframes", &self.keyframes)?;
+        }
+        match &self.order_key {
+            Some(key) => m.put("order_key", key)?,
+            None => {
+                let _ = m.delete("order_key");
+            }
+        }
+        m.put("title", &self.title)?;
+        m.put("prompt", &self.prompt)?;
+        m.put("negative_prompt", &self.negative_prompt)?;
+        m.put("notes", &self.notes)?;
+        m.put("settings", &self.settings)?;
+        m.put("outputs", &self.outputs)?;
+        m.put("metadata", &self.metadata)?;
+        m.put("comments", &self.comments)?;
+        reconcile_unknown_fields(&mut m, &self.extra)
+    }
+}
+
+impl Hydrate for GenerationNode {
+    fn hydrate_map<D: ReadDoc>(
+        doc: &D,
+        obj: &automerge::ObjId,
+    ) -> Result<Self, HydrateError> {
+        Ok(GenerationNode {
+            id: hydrate_prop(doc, obj, "id")?,
+            type_: hydrate_prop(doc, obj, "type_")?,
+            status: hydrate_prop(doc, obj, "status")?,
+            status_updated_at: hydrate_optional(doc, obj, "status_updated_at")?,
+            created_at: hydrate_optional(doc, obj, "created_at")?,
+            provider: hydrate_optional(doc, obj, "provider")?,
+            job_id: hydrate_optional(doc, obj, "job_id")?,
+            derived_from: hydrate_optional(doc, obj, "derived_from")?,
+            storyboard_ref: hydrate_optional(doc, obj, "storyboard_ref")?,
+            inputs: hydrate_optional(doc, obj, "inputs")?,
+            input_assets: hydrate_optional(doc, obj, "input_assets")?,
+            archived: hydrate_optional(doc, obj, "archived")?,
+            tags: hydrate_optional(doc, obj, "tags")?,
+            seen: hydrate_optional(doc, obj, "seen")?,
+            timeline: hydrate_optional(doc, obj, "timeline")?,
+            keyframes: hydrate_optional(doc, obj, "keyframes")?,
+            order_key: hydrate_optional(doc, obj, "order_key")?,
+            title: hydrate_prop(doc, obj, "title")?,
+            prompt: hydrate_prop(doc, obj, "prompt")?,
+            negative_prompt: hydrate_prop(doc, obj, "negative_prompt")?,
+            notes: hydrate_prop(doc, obj, "notes")?,
+            settings: hydrate_prop(doc, obj, "settings")?,
+            outputs: in_display_order(hydrate_prop(doc, obj, "outputs")?),
+            metadata: hydrate_prop(doc, obj, "metadata")?,
+            comments: hydrate_optional(doc, obj, "comments")?,
+            extra: hydrate_unknown_fields(doc, obj, GENERATION_NODE_FIELDS)?,
+        })
+    }
+}
+
 // =============================================================================
 // GENERATION SETTINGS
 // =============================================================================
@@ -210,11 +774,11 @@ pub struct GenerationSettings {
 
     /// Resolution preset (e.g., 720, 1080).
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub resolution: Option<i32>,
+    pub resolution: Option<ResolutionPreset>,
 
-    /// Duration in seconds (for video).
+    /// Duration in whole seconds (for video).
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub duration: Option<i32>,
+    pub duration: Option<DurationSecs>,
 
     /// Output width in pixels.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -227,6 +791,27 @@ pub struct GenerationSettings {
     /// Frames per second (for video).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub fps: Option<i32>,
+
+    /// Sampler name (e.g., "euler_a", "dpmpp_2m").
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sampler: Option<String>,
+
+    /// Noise scheduler name (e.g., "karras").
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheduler: Option<String>,
+
+    /// Denoising strength for image-to-image (0.0 keeps the input).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strength: Option<f64>,
+
+    /// LoRA adapters applied on top of the model, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub loras: Vec<LoraRef>,
+
+    /// Model-specific parameters without a dedicated field, keyed by name.
+    /// Each entry merges on its own, unlike JSON stuffed into `metadata`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub extra: HashMap<String, SettingValue>,
 }
 
 impl GenerationSettings {
@@ -259,15 +844,15 @@ impl GenerationSettings {
         self
     }
 
-    /// Builder: Set resolution.
-    pub fn with_resolution(mut self, resolution: i32) -> Self {
-        self.resolution = Some(resolution);
+    /// Builder: Set resolution preset.
+    pub fn with_resolution(mut self, resolution: impl Into<ResolutionPreset>) -> Self {
+        self.resolution = Some(resolution.into());
         self
     }
 
-    /// Builder: Set duration.
-    pub fn with_duration(mut self, duration: i32) -> Self {
-        self.duration = Some(duration);
+    /// Builder: Set duration (bare integers are seconds).
+    pub fn with_duration(mut self, duration: impl Into<DurationSecs>) -> Self {
+        self.duration = Some(duration.into());
         self
     }
 
@@ -288,6 +873,76 @@ impl GenerationSettings {
         self.fps = Some(fps);
         self
     }
+
+    /// Builder: Set sampler.
+    pub fn with_sampler(mut self, sampler: impl Into<String>) -> Self {
+        self.sampler = Some(sampler.into());
+        self
+    }
+
+    /// Builder: Set scheduler.
+    pub fn with_scheduler(mut self, scheduler: impl Into<String>) -> Self {
+        self.scheduler = Some(scheduler.into());
+        self
+    }
+
+    /// Builder: Set denoising strength.
+    pub fn with_strength(mut self, strength: f64) -> Self {
+        self.strength = Some(strength);
+        self
+    }
+
+    /// Builder: Add a LoRA adapter.
+    pub fn with_lora(mut self, id: impl Into<String>, weight: f64) -> Self {
+        self.loras.push(LoraRef::new(id, weight));
+        self
+    }
+
+    /// Builder: Set an extra model parameter.
+    pub fn with_extra(mut self, key: impl Into<String>, value: impl Into<SettingValue>) -> Self {
+        self.extra.insert(key.into(), value.into());
+        self
+    }
+
+    /// Returns an extra model parameter.
+    pub fn extra(&self, key: &str) -> Option<&SettingValue> {
+        self.extra.get(key)
+    }
+
+    /// Fills every unset field from `defaults`. LoRAs are inherited only if
+    /// none are set; extra parameters are merged per key.
+    pub fn inherit(&mut self, defaults: &GenerationSettings) {
+        self.seed = self.seed.or(defaults.seed);
+        self.cfg = self.cfg.or(defaults.cfg);
+        self.num_steps = self.num_steps.or(defaults.num_steps);
+        self.model = self.model.take().or_else(|| defaults.model.clone());
+        self.resolution = self.resolution.or(defaults.resolution);
+        self.duration = self.duration.or(defaults.duration);
+        self.width = self.width.or(defaults.width);
+        self.height = self.height.or(defaults.height);
+        self.fps = self.fps.or(defaults.fps);
+        self.sampler = self.sampler.take().or_else(|| defaults.sampler.clone());
+        self.scheduler = self.scheduler.take().or_else(|| defaults.scheduler.clone());
+        self.strength = self.strength.or(defaults.strength);
+        if self.loras.is_empty() {
+            self.loras = defaults.loras.clone();
+        }
+        for (key, value) in &defaults.extra {
+            self.extra.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+    }
+
+    /// Returns how the output resolution was specified.
+    /// Explicit `width`/`height` take precedence over a preset.
+    pub fn effective_resolution(&self) -> Option<Resolution> {
+        match (self.width, self.height, self.resolution) {
+            (Some(width), Some(height), _) => Some(Resolution::Explicit {
+                dimensions: Dimensions { width, height },
+            }),
+            (_, _, Some(preset)) => Some(Resolution::Preset { preset }),
+            _ => None,
+        }
+    }
 }
 
 /// Sparse Reconcile implementation: only writes Some() fields, deletes None fields.
@@ -314,11 +969,22 @@ impl Reconcile for GenerationSettings {
         reconcile_opt!(self.cfg, "cfg");
         reconcile_opt!(self.num_steps, "num_steps");
         reconcile_opt!(&self.model, "model");
-        reconcile_opt!(self.resolution, "resolution");
+        reconcile_opt!(self.resolution.map(|r| r.0), "resolution");
         reconcile_opt!(self.width, "width");
         reconcile_opt!(self.height, "height");
-        reconcile_opt!(self.duration, "duration");
+        reconcile_opt!(self.duration.map(|d| d.0), "duration");
         reconcile_opt!(self.fps, "fps");
+        reconcile_opt!(&self.sampler, "sampler");
+        reconcile_opt!(&self.scheduler, "scheduler");
+        reconcile_opt!(self.strength, "strength");
+        if self.loras.is_empty() {
+            let _ = m.delete("loras");
+        } else {
+            m.put("loras", &self.loras)?;
+        }
+        // Always written, even when empty, so peers adding their first extra
+        // parameter concurrently write into the same map.
+        m.put("extra", &self.extra)?;
 
         Ok(())
     }
@@ -390,20 +1056,296 @@ impl Hydrate for GenerationSettings {
             }
         }
 
+        // Extra parameters; values of unsupported types are skipped
+        let mut extra = HashMap::new();
+        if let Some((Value::Object(ObjType::Map), extra_obj)) = doc.get(obj, "extra")? {
+            for item in doc.map_range(&extra_obj, ..) {
+                if let Value::Scalar(s) = &item.value {
+                    if let Some(value) = SettingValue::from_scalar(s.as_ref()) {
+                        extra.insert(item.key.to_string(), value);
+                    }
+                }
+            }
+        }
+
         Ok(GenerationSettings {
             seed: hydrate_opt_i64(doc, obj, "seed")?,
             cfg: hydrate_opt_f64(doc, obj, "cfg")?,
             num_steps: hydrate_opt_i32(doc, obj, "num_steps")?,
             model: hydrate_opt_string(doc, obj, "model")?,
-            resolution: hydrate_opt_i32(doc, obj, "resolution")?,
+            resolution: hydrate_opt_i32(doc, obj, "resolution")?.map(ResolutionPreset),
             width: hydrate_opt_i32(doc, obj, "width")?,
             height: hydrate_opt_i32(doc, obj, "height")?,
-            duration: hydrate_opt_i32(doc, obj, "duration")?,
+            duration: hydrate_opt_i32(doc, obj, "duration")?.map(DurationSecs),
             fps: hydrate_opt_i32(doc, obj, "fps")?,
+            sampler: hydrate_opt_string(doc, obj, "sampler")?,
+            scheduler: hydrate_opt_string(doc, obj, "scheduler")?,
+            strength: hydrate_opt_f64(doc, obj, "strength")?,
+            loras: hydrate_optional(doc, obj, "loras")?,
+            extra,
         })
     }
 }
 
+// =============================================================================
+// LORA REF
+// =============================================================================
+
+/// A LoRA adapter and the weight it is applied with.
+#[derive(Debug, Clone, Reconcile, Hydrate, Serialize, Deserialize, PartialEq)]
+pub struct LoraRef {
+    /// Adapter identifier (model hub ID or URL).
+    pub id: String,
+
+    /// Blend weight, typically 0.0–1.0.
+    pub weight: f64,
+}
+
+impl LoraRef {
+    /// Creates a LoRA reference.
+    pub fn new(id: impl Into<String>, weight: f64) -> Self {
+        Self {
+            id: id.into(),
+            weight,
+        }
+    }
+}
+
+// =============================================================================
+// SETTING VALUE
+// =============================================================================
+
+/// Value of an extra model parameter (see `GenerationSettings::extra`).
+/// Serializes to a plain JSON string, number or boolean.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum SettingValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+}
+
+impl SettingValue {
+    /// Returns the string value, if this is a string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            SettingValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the integer value, if this is an integer.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            SettingValue::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a float, if this is a number.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            SettingValue::Float(f) => Some(*f),
+            SettingValue::Int(i) => Some(*i as f64),
+            _ => None,
+        }
+    }
+
+    /// Returns the boolean value, if this is a boolean.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            SettingValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Converts to the scalar stored in the document.
+    pub fn to_scalar(&self) -> ScalarValue {
+        match self {
+            SettingValue::Bool(b) => ScalarValue::Boolean(*b),
+            SettingValue::Int(i) => ScalarValue::Int(*i),
+            SettingValue::Float(f) => ScalarValue::F64(*f),
+            SettingValue::String(s) => ScalarValue::Str(s.as_str().into()),
+        }
+    }
+
+    /// Reads a stored scalar; None for types a setting cannot hold.
+    pub fn from_scalar(scalar: &ScalarValue) -> Option<Self> {
+        match scalar {
+            ScalarValue::Boolean(b) => Some(SettingValue::Bool(*b)),
+            ScalarValue::Int(i) => Some(SettingValue::Int(*i)),
+            ScalarValue::Uint(u) => Some(SettingValue::Int(*u as i64)),
+            ScalarValue::F64(f) => Some(SettingValue::Float(*f)),
+            ScalarValue::Str(s) => Some(SettingValue::String(s.to_string())),
+            _ => None,
+        }
+    }
+}
+
+impl Reconcile for SettingValue {
+    type Key<'a> = NoKey;
+
+    fn reconcile<R: Reconciler>(&self, reconciler: R) -> Result<(), R::Error> {
+        match self {
+            SettingValue::Bool(b) => reconciler.boolean(*b),
+            SettingValue::Int(i) => reconciler.i64(*i),
+            SettingValue::Float(f) => reconciler.f64(*f),
+            SettingValue::String(s) => reconciler.str(s),
+        }
+    }
+}
+
+impl From<bool> for SettingValue {
+    fn from(value: bool) -> Self {
+        SettingValue::Bool(value)
+    }
+}
+
+impl From<i64> for SettingValue {
+    fn from(value: i64) -> Self {
+        SettingValue::Int(value)
+    }
+}
+
+impl From<f64> for SettingValue {
+    fn from(value: f64) -> Self {
+        SettingValue::Float(value)
+    }
+}
+
+impl From<&str> for SettingValue {
+    fn from(value: &str) -> Self {
+        SettingValue::String(value.to_string())
+    }
+}
+
+impl From<String> for SettingValue {
+    fn from(value: String) -> Self {
+        SettingValue::String(value)
+    }
+}
+
+// =============================================================================
+// NODE INPUT REF
+// =============================================================================
+
+/// Reference to one output of another node, consumed as an input.
+#[derive(Debug, Clone, Reconcile, Hydrate, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct NodeInputRef {
+    /// ID of the upstream node.
+    pub node_id: String,
+
+    /// Index into the upstream node's `outputs`.
+    pub output_index: u32,
+}
+
+impl NodeInputRef {
+    /// Creates a reference to `output_index` of `node_id`.
+    pub fn new(node_id: impl Into<String>, output_index: u32) -> Self {
+        Self {
+            node_id: node_id.into(),
+            output_index,
+        }
+    }
+}
+
+// =============================================================================
+// TIMELINE PLACEMENT
+// =============================================================================
+
+/// A node's clip on a timeline track.
+#[derive(Debug, Clone, Reconcile, Hydrate, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TimelinePlacement {
+    /// Track name (e.g. "V1", "A1").
+    pub track: String,
+
+    /// Clip start on the timeline, in milliseconds.
+    pub start_ms: i64,
+
+    /// Clip length, in milliseconds.
+    pub duration_ms: i64,
+
+    /// Where the clip starts within the node's output (trimmed head), in
+    /// milliseconds. Set on the right half by `split_clip()`.
+    #[serde(default)]
+    #[autosurgeon(missing = "Default::default")]
+    pub offset_ms: i64,
+}
+
+impl TimelinePlacement {
+    /// Creates an untrimmed placement.
+    pub fn new(track: impl Into<String>, start_ms: i64, duration_ms: i64) -> Self {
+        Self {
+            track: track.into(),
+            start_ms,
+            duration_ms,
+            offset_ms: 0,
+        }
+    }
+
+    /// Returns the timeline position just past the clip.
+    pub fn end_ms(&self) -> i64 {
+        self.start_ms + self.duration_ms
+    }
+}
+
+// =============================================================================
+// KEYFRAME
+// =============================================================================
+
+/// A parameter value at a point in time.
+#[derive(Debug, Clone, Copy, Reconcile, Hydrate, Serialize, Deserialize, PartialEq)]
+pub struct Keyframe {
+    /// Time from the start of the generation, in milliseconds.
+    pub t: i64,
+
+    /// Parameter value at `t`.
+    pub value: f64,
+}
+
+impl Keyframe {
+    /// Creates a keyframe.
+    pub fn new(t: i64, value: f64) -> Self {
+        Self { t, value }
+    }
+}
+
+// =============================================================================
+// INPUT ASSET
+// =============================================================================
+
+/// A source asset a node is generated from (see `GenerationNode::input_assets`).
+#[derive(Debug, Clone, Reconcile, Hydrate, Serialize, Deserialize, PartialEq)]
+pub struct InputAsset {
+    /// The URL of the source asset.
+    pub url: String,
+
+    /// Asset kind: "image", "video", "audio", etc.
+    pub kind: String,
+
+    /// Inpainting mask applied to this asset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mask_url: Option<String>,
+}
+
+impl InputAsset {
+    /// Creates a new InputAsset.
+    pub fn new(url: impl Into<String>, kind: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            kind: kind.into(),
+            mask_url: None,
+        }
+    }
+
+    /// Builder: Set mask URL.
+    pub fn with_mask_url(mut self, mask_url: impl Into<String>) -> Self {
+        self.mask_url = Some(mask_url.into());
+        self
+    }
+}
+
 // =============================================================================
 // OUTPUT ASSET
 // =============================================================================
@@ -421,6 +1363,41 @@ pub struct OutputAsset {
     /// Whether this output is selected as the preview.
     #[serde(default)]
     pub is_selected: bool,
+
+    /// MIME type (e.g., "image/png", "video/mp4").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+
+    /// Width in pixels.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<i32>,
+
+    /// Height in pixels.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<i32>,
+
+    /// Duration in milliseconds (for video and audio).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<i64>,
+
+    /// File size in bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_size: Option<i64>,
+
+    /// URL of a smaller preview image.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail_url: Option<String>,
+
+    /// Users who starred this output. See `SequenceManager::star_output()`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[autosurgeon(missing = "Default::default")]
+    pub starred_by: Vec<String>,
+
+    /// Position among the node's outputs (see `crate::order_key`), set by
+    /// `SequenceManager::reorder_outputs()`. Outputs without one follow the
+    /// keyed ones in list order.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order_key: Option<String>,
 }
 
 impl OutputAsset {
@@ -430,6 +1407,14 @@ impl OutputAsset {
             url: url.into(),
             seed: None,
             is_selected: false,
+            mime_type: None,
+            width: None,
+            height: None,
+            duration_ms: None,
+            file_size: None,
+            thumbnail_url: None,
+            starred_by: Vec::new(),
+            order_key: None,
         }
     }
 
@@ -444,6 +1429,214 @@ impl OutputAsset {
         self.is_selected = is_selected;
         self
     }
+
+    /// Builder: Set MIME type.
+    pub fn with_mime_type(mut self, mime_type: impl Into<String>) -> Self {
+        self.mime_type = Some(mime_type.into());
+        self
+    }
+
+    /// Builder: Set width and height.
+    pub fn with_dimensions(mut self, width: i32, height: i32) -> Self {
+        self.width = Some(width);
+        self.height = Some(height);
+        self
+    }
+
+    /// Builder: Set duration in milliseconds.
+    pub fn with_duration_ms(mut self, duration_ms: i64) -> Self {
+        self.duration_ms = Some(duration_ms);
+        self
+    }
+
+    /// Builder: Set file size in bytes.
+    pub fn with_file_size(mut self, file_size: i64) -> Self {
+        self.file_size = Some(file_size);
+        self
+    }
+
+    /// Builder: Set thumbnail URL.
+    pub fn with_thumbnail_url(mut self, thumbnail_url: impl Into<String>) -> Self {
+        self.thumbnail_url = Some(thumbnail_url.into());
+        self
+    }
+}
+
+/// Reads the `order_key` of every output in a stored outputs list.
+pub(crate) fn stored_output_keys<D: ReadDoc>(
+    doc: &D,
+    outputs: &automerge::ObjId,
+) -> Result<Vec<Option<String>>, HydrateError> {
+    (0..doc.length(outputs))
+        .map(|i| match doc.get(outputs, i)? {
+            Some((Value::Object(ObjType::Map), output)) => hydrate_prop(doc, &output, "order_key"),
+            _ => Ok(None),
+        })
+        .collect()
+}
+
+/// List indices of a node's outputs in display order, given each stored
+/// output's `order_key`: keyed outputs by key, then the rest in list order.
+pub(crate) fn output_display_order(keys: &[Option<String>]) -> Vec<usize> {
+    if keys.iter().all(Option::is_none) {
+        return (0..keys.len()).collect();
+    }
+    let (ids, items) = output_items(keys);
+    let position: HashMap<&String, usize> = ids.iter().zip(0..).collect();
+    order_key::sorted(&ids, &items).iter().map(|id| position[id]).collect()
+}
+
+/// Order keys that put the stored outputs in `display` order (list indices),
+/// keeping every existing key that is already in order. Returns the key of
+/// each output in list order.
+pub(crate) fn output_keys_for(keys: &[Option<String>], display: &[usize]) -> Vec<Option<String>> {
+    let (ids, mut items) = output_items(keys);
+    let order: Vec<String> = display.iter().map(|&i| ids[i].clone()).collect();
+    order_key::reorder(&mut items, &order);
+    ids.iter().map(|id| items.remove(id).flatten()).collect()
+}
+
+fn output_items(keys: &[Option<String>]) -> (Vec<String>, HashMap<String, Option<String>>) {
+    // Zero-padded so that outputs with equal keys tie-break by list index.
+    let ids: Vec<String> = (0..keys.len()).map(|i| format!("{:010}", i)).collect();
+    let items = ids.iter().cloned().zip(keys.iter().cloned()).collect();
+    (ids, items)
+}
+
+/// Sorts hydrated outputs (in list order) into display order.
+fn in_display_order(outputs: Vec<OutputAsset>) -> Vec<OutputAsset> {
+    let keys: Vec<Option<String>> = outputs.iter().map(|o| o.order_key.clone()).collect();
+    let mut slots: Vec<Option<OutputAsset>> = outputs.into_iter().map(Some).collect();
+    output_display_order(&keys)
+        .into_iter()
+        .filter_map(|i| slots[i].take())
+        .collect()
+}
+
+/// Lays out `new`, a node's outputs in display order, over the stored list
+/// so that reconciling it writes each output into the element it was
+/// hydrated from rather than rewriting elements by display position.
+/// `display` is the stored order (see `output_display_order()`) and `old`
+/// the outputs as hydrated. Each element keeps its order key, so display
+/// positions survive; removed positions are deleted and extra outputs
+/// appended. Returns `None` when list and display order already agree.
+pub(crate) fn outputs_in_list_order(
+    display: &[usize],
+    old: &[OutputAsset],
+    new: &[OutputAsset],
+) -> Option<Vec<OutputAsset>> {
+    if display.len() != old.len() || display.iter().enumerate().all(|(i, &at)| i == at) {
+        return None;
+    }
+    let mut stored = old.to_vec();
+    for (i, &at) in display.iter().enumerate() {
+        let output = new.get(i).unwrap_or(&old[i]);
+        stored[at] = OutputAsset { order_key: old[i].order_key.clone(), ..output.clone() };
+    }
+    if new.len() < old.len() {
+        let mut removed = display[new.len()..].to_vec();
+        removed.sort_unstable();
+        for at in removed.into_iter().rev() {
+            stored.remove(at);
+        }
+    } else {
+        stored.extend(new[old.len()..].iter().cloned());
+    }
+    Some(stored)
+}
+
+// =============================================================================
+// NODE SUMMARY
+// =============================================================================
+
+/// The fields a sequence list view shows for one node, read without
+/// hydrating prompts, settings or outputs (see `get_summaries()`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NodeSummary {
+    pub id: String,
+    pub type_: String,
+    pub status: String,
+    pub title: String,
+    /// Number of output assets.
+    pub output_count: usize,
+    /// Whether the node is archived.
+    #[serde(default)]
+    pub archived: bool,
+}
+
+impl NodeSummary {
+    /// Summarizes an already hydrated node.
+    pub fn from_node(id: impl Into<String>, node: &GenerationNode) -> Self {
+        Self {
+            id: id.into(),
+            type_: node.type_.clone(),
+            status: node.status.clone(),
+            title: node.title.clone(),
+            output_count: node.outputs.len(),
+            archived: node.archived,
+        }
+    }
+}
+
+// =============================================================================
+// NODE FILTER
+// =============================================================================
+
+/// Criteria for `SequenceManager::query()`. Unset criteria match every node;
+/// set ones must all match.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct NodeFilter {
+    /// Exact status (e.g. "queued").
+    pub status: Option<String>,
+    /// Exact generation type (e.g. "i2v").
+    pub type_: Option<String>,
+    /// Tag the node must have.
+    pub tag: Option<String>,
+    /// Only nodes created strictly after this time (milliseconds since
+    /// epoch). Nodes without `created_at` never match.
+    pub created_after: Option<i64>,
+}
+
+impl NodeFilter {
+    /// Creates a filter that matches every node.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builder: Match status.
+    pub fn with_status(mut self, status: impl Into<String>) -> Self {
+        self.status = Some(status.into());
+        self
+    }
+
+    /// Builder: Match generation type.
+    pub fn with_type(mut self, type_: impl Into<String>) -> Self {
+        self.type_ = Some(type_.into());
+        self
+    }
+
+    /// Builder: Match tag.
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
+
+    /// Builder: Match nodes created after `created_after`.
+    pub fn with_created_after(mut self, created_after: i64) -> Self {
+        self.created_after = Some(created_after);
+        self
+    }
+
+    /// Returns true if `node` meets every set criterion.
+    pub fn matches(&self, node: &GenerationNode) -> bool {
+        self.status.as_ref().is_none_or(|s| *s == node.status)
+            && self.type_.as_ref().is_none_or(|t| *t == node.type_)
+            && self.tag.as_ref().is_none_or(|tag| node.tags.contains(tag))
+            && self
+                .created_after
+                .is_none_or(|after| node.created_at.is_some_and(|at| at > after))
+    }
 }
 
 // =============================================================================
@@ -461,6 +1654,33 @@ mod tests {
         assert_eq!(root.len(), 0);
     }
 
+    #[test]
+    fn test_outputs_in_list_order() {
+        let keyed = |url: &str, key: Option<&str>| OutputAsset {
+            order_key: key.map(String::from),
+            ..OutputAsset::new(url)
+        };
+        // Stored as [a (key "m"), b (key "a"), c (no key)]; shown as [b, a, c].
+        let keys = vec![Some("m".to_string()), Some("a".to_string()), None];
+        let display = output_display_order(&keys);
+        assert_eq!(display, vec![1, 0, 2]);
+        let old = vec![keyed("b", Some("a")), keyed("a", Some("m")), keyed("c", None)];
+
+        assert_eq!(outputs_in_list_order(&[0, 1, 2], &old, &old), None);
+
+        let mut new = old.clone();
+        new[0].seed = Some(1);
+        let stored = outputs_in_list_order(&display, &old, &new).unwrap();
+        assert_eq!(stored[1].url, "b");
+        assert_eq!(stored[1].seed, Some(1));
+
+        new.pop();
+        let stored = outputs_in_list_order(&display, &old, &new).unwrap();
+        let urls: Vec<&str> = stored.iter().map(|o| o.url.as_str()).collect();
+        assert_eq!(urls, vec!["a", "b"]);
+        assert_eq!(stored[1].order_key.as_deref(), Some("a"));
+    }
+
     #[test]
     fn test_generation_node_builder() {
         let settings = GenerationSettings::new()
@@ -483,6 +1703,43 @@ mod tests {
         assert_eq!(node.settings.cfg, Some(7.5));
     }
 
+    #[test]
+    fn test_document_seed_into_root() {
+        let root = DocumentSeed::new()
+            .with_node(GenerationNode::new("style", "style_guide"))
+            .with_node(GenerationNode::new("gen-1", "t2i"))
+            .with_preset("draft", GenerationSettings::new().with_num_steps(8))
+            .with_defaults(GenerationSettings::new().with_seed(1))
+            .into_root();
+
+        assert_eq!(root.generations.order(), ["style", "gen-1"]);
+        assert_eq!(root.generations["style"].type_, "style_guide");
+        assert_eq!(root.presets["draft"].num_steps, Some(8));
+        assert_eq!(root.defaults.seed, Some(1));
+    }
+
+    #[test]
+    fn test_settings_units_json_compat() {
+        let settings: GenerationSettings =
+            serde_json::from_str(r#"{"resolution": 720, "duration": 5}"#).unwrap();
+        assert_eq!(settings.resolution, Some(ResolutionPreset::HD));
+        assert_eq!(settings.duration, Some(DurationSecs(5)));
+        assert_eq!(
+            serde_json::to_value(&settings).unwrap(),
+            serde_json::json!({"resolution": 720, "duration": 5})
+        );
+        assert_eq!(
+            settings.effective_resolution(),
+            Some(Resolution::Preset { preset: ResolutionPreset::HD })
+        );
+
+        let explicit = settings.with_width(1024).with_height(576);
+        assert!(matches!(
+            explicit.effective_resolution(),
+            Some(Resolution::Explicit { .. })
+        ));
+    }
+
     #[test]
     fn test_output_asset_builder() {
         let output = OutputAsset::new("https://example.com/image.png")
@@ -0,0 +1,26 @@
+//! Behavior switches for `SequenceManager`.
+
+use serde::{Deserialize, Serialize};
+
+/// Optional enforcement modes. All are off by default.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct ManagerOptions {
+    /// Reject settings changes on "completed" nodes with `FrozenNode`, so a
+    /// finished output can always be reproduced from its settings.
+    /// `SequenceManager::unfreeze()` lifts this for a single node.
+    pub freeze_completed: bool,
+}
+
+impl ManagerOptions {
+    /// Creates options with every mode off.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builder: Set `freeze_completed`.
+    pub fn with_freeze_completed(mut self, freeze_completed: bool) -> Self {
+        self.freeze_completed = freeze_completed;
+        self
+    }
+}
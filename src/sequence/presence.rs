@@ -0,0 +1,16 @@
+//! Presence state broadcast by sequence document peers.
+
+use serde::{Deserialize, Serialize};
+
+use crate::presence::Cursor;
+
+/// What a sequence peer is looking at (e.g., "Alice is editing gen-42").
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SequencePresence {
+    /// Display name or user ID.
+    pub user: String,
+    /// Generation node the peer has focused.
+    pub focused_node: Option<String>,
+    /// Text cursor within the focused node.
+    pub cursor: Option<Cursor>,
+}
@@ -0,0 +1,89 @@
+//! Generation job queue.
+//!
+//! `DocumentRoot::queue` orders the nodes waiting for a render worker.
+//! Workers call `claim_next()` to take the first free node, `renew_lease()`
+//! while rendering and `complete()` when done.
+//!
+//! Two workers on different peers can claim the same node before their
+//! changes meet, so claims are not a single "owner" field that the last
+//! writer would silently overwrite. Each worker writes its own record under
+//! the node, concurrent claims both survive the merge, and `holder()` picks
+//! the same winner on every peer: the active claim made first, ties broken
+//! by worker ID. A worker that has synced since claiming should check it
+//! still holds the node (`claim_holder()`, or `renew_lease()` returning
+//! false) and drop the job otherwise. `expire_stale_claims()` removes
+//! claims whose lease ran out and puts nodes abandoned by a crashed worker
+//! back at the front of the queue.
+//!
+//! `enqueue()` creates the node's (empty) claim map up front. For a node
+//! set to "queued" some other way, concurrent first claims each create the
+//! map and only one survives the merge; peers still agree on the holder.
+
+use std::collections::HashMap;
+
+use autosurgeon::{Hydrate, Reconcile};
+use serde::{Deserialize, Serialize};
+
+/// One worker's claim on a node.
+#[derive(Debug, Clone, Default, Reconcile, Hydrate, Serialize, Deserialize, PartialEq)]
+pub struct JobClaim {
+    /// Timestamp when claimed (milliseconds since epoch)
+    pub claimed_at: i64,
+    /// Timestamp after which the claim is ignored (milliseconds since epoch)
+    pub lease_expires_at: i64,
+}
+
+impl JobClaim {
+    /// Returns true if the lease has not expired at `now`.
+    pub fn is_active(&self, now: i64) -> bool {
+        now < self.lease_expires_at
+    }
+}
+
+/// Queued node order and worker claims.
+#[derive(Debug, Clone, Default, Reconcile, Hydrate, Serialize, Deserialize, PartialEq)]
+pub struct JobQueue {
+    /// Node IDs waiting for a worker, first to claim first.
+    pub order: Vec<String>,
+    /// Claims keyed by node ID, then worker ID.
+    pub claims: HashMap<String, HashMap<String, JobClaim>>,
+}
+
+impl JobQueue {
+    /// Returns the worker holding `node_id` at `now`, if any.
+    pub fn holder(&self, node_id: &str, now: i64) -> Option<&str> {
+        self.claims
+            .get(node_id)?
+            .iter()
+            .filter(|(_, claim)| claim.is_active(now))
+            .min_by(|(a_worker, a), (b_worker, b)| {
+                a.claimed_at.cmp(&b.claimed_at).then_with(|| a_worker.cmp(b_worker))
+            })
+            .map(|(worker, _)| worker.as_str())
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_holder_prefers_earliest_active_claim() {
+        let claim = |claimed_at, lease_expires_at| JobClaim {
+            claimed_at,
+            lease_expires_at,
+        };
+        let mut queue = JobQueue::default();
+        let claims = queue.claims.entry("a".to_string()).or_default();
+        claims.insert("w2".to_string(), claim(100, 200));
+        claims.insert("w1".to_string(), claim(100, 200));
+        claims.insert("w0".to_string(), claim(50, 90));
+        assert_eq!(queue.holder("a", 95), Some("w1"));
+        assert_eq!(queue.holder("a", 200), None);
+        assert_eq!(queue.holder("b", 95), None);
+    }
+}
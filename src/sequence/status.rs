@@ -0,0 +1,188 @@
+//! Generation status values and the transitions allowed between them.
+//!
+//! `GenerationNode::status` is a plain string so unknown values written by
+//! other clients survive. `SequenceManager::set_status_checked()` parses it
+//! into a `GenerationStatus` and rejects moves the manager's
+//! `StatusTransitions` table does not allow (e.g. completed → pending).
+//! `set_status()` stays unchecked as an escape hatch.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CollabError, CollabResult};
+
+/// Lifecycle status of a generation node.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum GenerationStatus {
+    Pending,
+    Queued,
+    Processing,
+    Completed,
+    Failed,
+    Cancelled,
+    Stalled,
+}
+
+impl GenerationStatus {
+    /// Every status, in lifecycle order.
+    pub const ALL: [GenerationStatus; 7] = [
+        GenerationStatus::Pending,
+        GenerationStatus::Queued,
+        GenerationStatus::Processing,
+        GenerationStatus::Completed,
+        GenerationStatus::Failed,
+        GenerationStatus::Cancelled,
+        GenerationStatus::Stalled,
+    ];
+
+    /// Returns the string stored in the document.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GenerationStatus::Pending => "pending",
+            GenerationStatus::Queued => "queued",
+            GenerationStatus::Processing => "processing",
+            GenerationStatus::Completed => "completed",
+            GenerationStatus::Failed => "failed",
+            GenerationStatus::Cancelled => "cancelled",
+            GenerationStatus::Stalled => "stalled",
+        }
+    }
+}
+
+impl fmt::Display for GenerationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for GenerationStatus {
+    type Err = CollabError;
+
+    fn from_str(s: &str) -> CollabResult<Self> {
+        GenerationStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str() == s)
+            .ok_or_else(|| CollabError::serialization(format!("unknown status '{}'", s)))
+    }
+}
+
+/// Allowed status transitions: each status maps to the statuses it may move
+/// to. Setting a node to its current status is always allowed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(transparent)]
+pub struct StatusTransitions {
+    allowed: HashMap<GenerationStatus, HashSet<GenerationStatus>>,
+}
+
+impl StatusTransitions {
+    /// A table that allows no transitions.
+    pub fn empty() -> Self {
+        Self {
+            allowed: HashMap::new(),
+        }
+    }
+
+    /// A table that allows every transition.
+    pub fn permissive() -> Self {
+        let mut table = Self::empty();
+        for from in GenerationStatus::ALL {
+            for to in GenerationStatus::ALL {
+                table.allow(from, to);
+            }
+        }
+        table
+    }
+
+    /// Allows `from` → `to`.
+    pub fn allow(&mut self, from: GenerationStatus, to: GenerationStatus) -> &mut Self {
+        self.allowed.entry(from).or_default().insert(to);
+        self
+    }
+
+    /// Forbids `from` → `to`.
+    pub fn forbid(&mut self, from: GenerationStatus, to: GenerationStatus) -> &mut Self {
+        if let Some(targets) = self.allowed.get_mut(&from) {
+            targets.remove(&to);
+        }
+        self
+    }
+
+    /// Returns true if `from` → `to` is allowed.
+    pub fn is_allowed(&self, from: GenerationStatus, to: GenerationStatus) -> bool {
+        from == to || self.allowed.get(&from).is_some_and(|targets| targets.contains(&to))
+    }
+
+    /// Fails with `IllegalTransition` unless `from` → `to` is allowed.
+    pub fn check(&self, from: GenerationStatus, to: GenerationStatus) -> CollabResult<()> {
+        if self.is_allowed(from, to) {
+            Ok(())
+        } else {
+            Err(CollabError::illegal_transition(from.as_str(), to.as_str()))
+        }
+    }
+}
+
+impl Default for StatusTransitions {
+    /// The render lifecycle: work moves forward from pending to a terminal
+    /// status; failed, cancelled and stalled nodes can be retried; completed
+    /// nodes are final.
+    fn default() -> Self {
+        use GenerationStatus::*;
+        let mut table = Self::empty();
+        for (from, targets) in [
+            (Pending, &[Queued, Processing, Cancelled][..]),
+            (Queued, &[Pending, Processing, Cancelled][..]),
+            (Processing, &[Completed, Failed, Cancelled, Stalled][..]),
+            (Failed, &[Pending, Queued][..]),
+            (Cancelled, &[Pending, Queued][..]),
+            (Stalled, &[Pending, Queued, Processing, Completed, Failed][..]),
+        ] {
+            for &to in targets {
+                table.allow(from, to);
+            }
+        }
+        table
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_round_trip() {
+        for status in GenerationStatus::ALL {
+            assert_eq!(status.as_str().parse::<GenerationStatus>().unwrap(), status);
+            assert_eq!(serde_json::to_value(status).unwrap(), status.as_str());
+        }
+        assert!("done".parse::<GenerationStatus>().is_err());
+    }
+
+    #[test]
+    fn test_default_transitions() {
+        use GenerationStatus::*;
+        let table = StatusTransitions::default();
+        assert!(table.is_allowed(Pending, Processing));
+        assert!(table.is_allowed(Failed, Queued));
+        assert!(table.is_allowed(Completed, Completed));
+        match table.check(Completed, Pending) {
+            Err(CollabError::IllegalTransition { from, to }) => {
+                assert_eq!((from.as_str(), to.as_str()), ("completed", "pending"))
+            }
+            other => panic!("expected IllegalTransition, got {:?}", other),
+        }
+
+        let mut custom = StatusTransitions::default();
+        custom.allow(Completed, Queued).forbid(Stalled, Completed);
+        assert!(custom.is_allowed(Completed, Queued));
+        assert!(!custom.is_allowed(Stalled, Completed));
+    }
+}
@@ -0,0 +1,190 @@
+//! Unit-carrying types for duration and resolution settings.
+//!
+//! Shipped documents store `duration` as a bare integer, and clients have
+//! disagreed on whether it meant seconds or frames. These newtypes make the
+//! unit part of the Rust type while keeping the stored and serialized shape
+//! unchanged: `duration` is always whole seconds, `resolution` is always a
+//! preset height.
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+// =============================================================================
+// DURATION
+// =============================================================================
+
+/// Duration in whole seconds. This is the unit stored in documents.
+///
+/// Serializes as a plain number. Deserializes from a number (seconds) or from
+/// an explicit `{ "secs": n }` / `{ "ms": n }` object.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(transparent)]
+pub struct DurationSecs(pub i32);
+
+/// Duration in milliseconds.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(transparent)]
+pub struct DurationMs(pub i64);
+
+impl DurationSecs {
+    /// Converts a frame count at `fps` to seconds, rounded to the nearest second.
+    pub fn from_frames(frames: i64, fps: i32) -> Self {
+        DurationMs::from_frames(frames, fps).into()
+    }
+
+    /// Returns the number of frames at `fps`.
+    pub fn to_frames(self, fps: i32) -> i64 {
+        self.0 as i64 * fps as i64
+    }
+
+    /// Returns the duration in milliseconds.
+    pub fn as_ms(self) -> DurationMs {
+        self.into()
+    }
+}
+
+impl DurationMs {
+    /// Converts a frame count at `fps` to milliseconds (rounded).
+    pub fn from_frames(frames: i64, fps: i32) -> Self {
+        if fps <= 0 {
+            return Self(0);
+        }
+        Self((frames * 1000 + fps as i64 / 2) / fps as i64)
+    }
+}
+
+impl From<i32> for DurationSecs {
+    fn from(secs: i32) -> Self {
+        Self(secs)
+    }
+}
+
+impl From<DurationMs> for DurationSecs {
+    /// Rounds to the nearest whole second.
+    fn from(ms: DurationMs) -> Self {
+        Self(((ms.0 + 500).div_euclid(1000)) as i32)
+    }
+}
+
+impl From<DurationSecs> for DurationMs {
+    fn from(secs: DurationSecs) -> Self {
+        Self(secs.0 as i64 * 1000)
+    }
+}
+
+impl<'de> Deserialize<'de> for DurationSecs {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Secs(i32),
+            Tagged {
+                secs: Option<i32>,
+                ms: Option<i64>,
+            },
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Secs(secs) => Ok(Self(secs)),
+            Repr::Tagged { secs: Some(secs), ms: None } => Ok(Self(secs)),
+            Repr::Tagged { secs: None, ms: Some(ms) } => Ok(DurationMs(ms).into()),
+            Repr::Tagged { .. } => Err(serde::de::Error::custom(
+                "duration object must have exactly one of 'secs' or 'ms'",
+            )),
+        }
+    }
+}
+
+// =============================================================================
+// RESOLUTION
+// =============================================================================
+
+/// Resolution preset identified by its height (e.g., 720, 1080).
+///
+/// Distinct from explicit `width`/`height`, which are stored separately.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(transparent)]
+pub struct ResolutionPreset(pub i32);
+
+impl ResolutionPreset {
+    pub const SD: Self = Self(480);
+    pub const HD: Self = Self(720);
+    pub const FULL_HD: Self = Self(1080);
+    pub const UHD: Self = Self(2160);
+
+    /// Returns 16:9 dimensions for this preset, with width rounded to an even number.
+    pub fn dimensions_16x9(self) -> Dimensions {
+        let width = (self.0 as i64 * 16 / 9) as i32;
+        Dimensions {
+            width: width + width % 2,
+            height: self.0,
+        }
+    }
+}
+
+impl From<i32> for ResolutionPreset {
+    fn from(height: i32) -> Self {
+        Self(height)
+    }
+}
+
+/// Explicit output dimensions in pixels.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct Dimensions {
+    pub width: i32,
+    pub height: i32,
+}
+
+/// How a node's output resolution was specified.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Resolution {
+    /// A named preset (height only).
+    Preset { preset: ResolutionPreset },
+    /// Explicit width and height.
+    Explicit { dimensions: Dimensions },
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duration_conversions() {
+        assert_eq!(DurationSecs::from(DurationMs(1499)), DurationSecs(1));
+        assert_eq!(DurationSecs::from(DurationMs(1500)), DurationSecs(2));
+        assert_eq!(DurationSecs(3).as_ms(), DurationMs(3000));
+        assert_eq!(DurationSecs::from_frames(120, 24), DurationSecs(5));
+        assert_eq!(DurationSecs(5).to_frames(24), 120);
+    }
+
+    #[test]
+    fn test_duration_serde_compat() {
+        let secs: DurationSecs = serde_json::from_str("5").unwrap();
+        assert_eq!(secs, DurationSecs(5));
+        assert_eq!(serde_json::to_string(&secs).unwrap(), "5");
+
+        let from_ms: DurationSecs = serde_json::from_str(r#"{"ms": 4000}"#).unwrap();
+        assert_eq!(from_ms, DurationSecs(4));
+        let tagged: DurationSecs = serde_json::from_str(r#"{"secs": 2}"#).unwrap();
+        assert_eq!(tagged, DurationSecs(2));
+        assert!(serde_json::from_str::<DurationSecs>(r#"{"secs": 2, "ms": 2000}"#).is_err());
+    }
+
+    #[test]
+    fn test_resolution_preset() {
+        let preset: ResolutionPreset = serde_json::from_str("1080").unwrap();
+        assert_eq!(preset, ResolutionPreset::FULL_HD);
+        assert_eq!(
+            preset.dimensions_16x9(),
+            Dimensions {
+                width: 1920,
+                height: 1080
+            }
+        );
+        assert_eq!(ResolutionPreset::HD.dimensions_16x9().width, 1280);
+    }
+}
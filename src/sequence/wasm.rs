@@ -3,27 +3,80 @@
 //! This module provides JavaScript-friendly wrappers around the core
 //! SequenceManager and related types for use in browser environments.
 
+use std::collections::BTreeMap;
+
+use automerge::ActorId;
 use js_sys::{Array, Uint8Array};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_wasm_bindgen::{from_value, Serializer};
 use wasm_bindgen::prelude::*;
 
+use crate::acl::{PermissionContext, Role};
+use crate::checkpoint::SyncCheckpoint;
+use crate::comment::Comment;
 use crate::error::CollabError;
+use crate::link::DocRef;
+use crate::presence::Cursor;
+use crate::sweep::SweepConfig;
+use super::limits::SettingsLimits;
+use super::options::ManagerOptions;
+use super::status::{GenerationStatus, StatusTransitions};
+use super::units::{DurationMs, DurationSecs, ResolutionPreset};
 use super::manager::SequenceManager;
-use super::model::{GenerationNode, OutputAsset};
+use super::model::{DocumentSeed, GenerationNode, GenerationSettings, InputAsset, LoraRef, NodeFilter, NodeInputRef, OutputAsset, SettingValue, TimelinePlacement};
 
 /// Serialize a value to JsValue with HashMaps as plain JS objects (not Map).
 fn to_js_value<T: Serialize>(value: &T) -> Result<JsValue, serde_wasm_bindgen::Error> {
     value.serialize(&Serializer::new().serialize_maps_as_objects(true))
 }
 
+/// Options accepted by the `JsSequenceManager` constructor.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConstructorOptions {
+    actor_id: Option<String>,
+}
+
+/// Decodes a hex actor ID.
+fn decode_actor(actor_id: &str) -> Result<Vec<u8>, JsValue> {
+    let bytes = hex::decode(actor_id)
+        .map_err(|_| JsValue::from_str(&format!("Invalid actor ID: {}", actor_id)))?;
+    if bytes.is_empty() {
+        return Err(JsValue::from_str("Invalid actor ID: empty"));
+    }
+    Ok(bytes)
+}
+
+/// Decodes a JS array of hex-encoded actor IDs.
+pub(crate) fn decode_actors(actor_ids: &Array) -> Result<Vec<ActorId>, JsValue> {
+    actor_ids
+        .iter()
+        .map(|v| {
+            let hex = v
+                .as_string()
+                .ok_or_else(|| JsValue::from_str("Actor IDs must be strings"))?;
+            Ok(ActorId::from(decode_actor(&hex)?))
+        })
+        .collect()
+}
+
 // =============================================================================
 // ERROR CONVERSION
 // =============================================================================
 
+/// Converts to the error message string. Permission and freeze errors
+/// become an `Error` with `code: "PERMISSION_DENIED"` or
+/// `"DOCUMENT_FROZEN"` so callers can tell them apart.
 impl From<CollabError> for JsValue {
     fn from(err: CollabError) -> JsValue {
-        JsValue::from_str(&err.to_string())
+        let code = match err {
+            CollabError::PermissionDenied { .. } => "PERMISSION_DENIED",
+            CollabError::DocumentFrozen => "DOCUMENT_FROZEN",
+            _ => return JsValue::from_str(&err.to_string()),
+        };
+        let error = js_sys::Error::new(&err.to_string());
+        let _ = js_sys::Reflect::set(&error, &"code".into(), &code.into());
+        error.into()
     }
 }
 
@@ -44,22 +97,52 @@ macro_rules! js_result {
 /// that can be used from JavaScript/TypeScript in the browser.
 #[wasm_bindgen]
 pub struct JsSequenceManager {
-    inner: SequenceManager,
+    pub(crate) inner: SequenceManager,
 }
 
 #[wasm_bindgen]
 impl JsSequenceManager {
-    /// Creates a new empty sequence manager.
+    /// Creates a new empty sequence manager. `options.actorId` (hex) sets a
+    /// stable actor ID for this user and device.
     ///
     /// # Example (JavaScript)
     /// ```js
     /// const manager = new JsSequenceManager();
+    /// const mine = new JsSequenceManager({ actorId: deviceActorHex });
     /// ```
     #[wasm_bindgen(constructor)]
-    pub fn new() -> JsSequenceManager {
-        JsSequenceManager {
-            inner: SequenceManager::new()
+    pub fn new(options: JsValue) -> Result<JsSequenceManager, JsValue> {
+        let options: Option<ConstructorOptions> = from_value(options)?;
+        let mut inner = SequenceManager::new();
+        if let Some(actor_id) = options.and_then(|options| options.actor_id) {
+            inner.set_actor(&decode_actor(&actor_id)?);
         }
+        Ok(JsSequenceManager { inner })
+    }
+
+    /// Sets the actor ID (hex) for changes made from now on.
+    #[wasm_bindgen(js_name = setActor)]
+    pub fn set_actor(&mut self, actor_id: &str) -> Result<(), JsValue> {
+        self.inner.set_actor(&decode_actor(actor_id)?);
+        Ok(())
+    }
+
+    /// Creates a new manager pre-populated from a seed, in a single change.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// const manager = JsSequenceManager.withSeed({
+    ///   nodes: [{ id: 'style', type_: 'style_guide', status: 'pending', title: 'Style guide',
+    ///             prompt: '', negative_prompt: '', notes: '', settings: {}, outputs: [], metadata: '' }],
+    ///   presets: { cinematic: { cfg: 4.5 } },
+    ///   defaults: { num_steps: 30 },
+    /// });
+    /// ```
+    #[wasm_bindgen(js_name = withSeed)]
+    pub fn with_seed(seed: JsValue) -> Result<JsSequenceManager, JsValue> {
+        let seed: DocumentSeed = from_value(seed)?;
+        let inner = js_result!(SequenceManager::new_with_seed(seed))?;
+        Ok(JsSequenceManager { inner })
     }
 
     /// Loads from binary bytes (Uint8Array).
@@ -75,6 +158,48 @@ impl JsSequenceManager {
         Ok(JsSequenceManager { inner })
     }
 
+    /// Loads a snapshot saved with `saveEncrypted()`.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// const keyParams = { email: user.email, createdAt: board.createdAt };
+    /// const manager = JsSequenceManager.fromEncryptedBytes(bytes, keyParams);
+    /// ```
+    #[cfg(feature = "crypto")]
+    #[wasm_bindgen(js_name = fromEncryptedBytes)]
+    pub fn from_encrypted_bytes(bytes: &[u8], key_params: JsValue) -> Result<JsSequenceManager, JsValue> {
+        let key_params: crate::crypto::KeyParams = from_value(key_params)?;
+        let inner = js_result!(SequenceManager::from_encrypted_bytes(bytes, &key_params))?;
+        Ok(JsSequenceManager { inner })
+    }
+
+    /// Creates a manager from a JSON document produced by `toJSON()`.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// const json = await fetch('/api/sequences/42.json').then(r => r.text());
+    /// const manager = JsSequenceManager.fromJSON(json);
+    /// ```
+    #[wasm_bindgen(js_name = fromJSON)]
+    pub fn from_json(json: &str) -> Result<JsSequenceManager, JsValue> {
+        let inner = js_result!(SequenceManager::from_json(json))?;
+        Ok(JsSequenceManager { inner })
+    }
+
+    /// Exports the full document as a plain object (`{ sequence_order, generations }`).
+    ///
+    /// Because this is named `toJSON`, `JSON.stringify(manager)` yields the document JSON.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// await fetch('/api/sequences/42.json', { method: 'PUT', body: JSON.stringify(manager) });
+    /// ```
+    #[wasm_bindgen(js_name = toJSON)]
+    pub fn to_json(&mut self) -> Result<JsValue, JsValue> {
+        let json = js_result!(self.inner.to_json())?;
+        js_sys::JSON::parse(&json)
+    }
+
     /// Saves to binary bytes (returns Uint8Array).
     ///
     /// # Example (JavaScript)
@@ -88,6 +213,97 @@ impl JsSequenceManager {
         Uint8Array::from(&bytes[..])
     }
 
+    /// Saves only the changes since the last `toBytes()` / `saveIncremental()`
+    /// (returns Uint8Array, empty if nothing changed).
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// const chunk = manager.saveIncremental();
+    /// if (chunk.length > 0) await api.appendChanges(id, chunk);
+    /// ```
+    #[wasm_bindgen(js_name = saveIncremental)]
+    pub fn save_incremental(&mut self) -> Uint8Array {
+        let bytes = self.inner.save_incremental();
+        Uint8Array::from(&bytes[..])
+    }
+
+    /// Loads changes produced by `saveIncremental()`.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// const manager = JsSequenceManager.fromBytes(snapshot);
+    /// for (const chunk of chunks) manager.loadIncremental(chunk);
+    /// ```
+    #[wasm_bindgen(js_name = loadIncremental)]
+    pub fn load_incremental(&mut self, data: &[u8]) -> Result<usize, JsValue> {
+        js_result!(self.inner.load_incremental(data))
+    }
+
+    /// Returns the changes the server has not acknowledged yet (Uint8Array,
+    /// empty if none).
+    ///
+    /// # Arguments
+    /// * `checkpoint` - `{ heads }` from `markSynced()`, or null before the first upload
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// const checkpoint = JSON.parse(localStorage.getItem(key) ?? 'null');
+    /// const next = manager.markSynced();
+    /// await api.appendChanges(id, manager.changesSinceCheckpoint(checkpoint));
+    /// localStorage.setItem(key, JSON.stringify(next));
+    /// ```
+    #[wasm_bindgen(js_name = changesSinceCheckpoint)]
+    pub fn changes_since_checkpoint(&mut self, checkpoint: JsValue) -> Result<Uint8Array, JsValue> {
+        let checkpoint: Option<SyncCheckpoint> = from_value(checkpoint)?;
+        let bytes = self
+            .inner
+            .changes_since_checkpoint(&checkpoint.unwrap_or_default());
+        Ok(Uint8Array::from(&bytes[..]))
+    }
+
+    /// Returns a `{ heads }` checkpoint at the current heads. Store it once
+    /// the upload from `changesSinceCheckpoint()` is acknowledged.
+    #[wasm_bindgen(js_name = markSynced)]
+    pub fn mark_synced(&mut self) -> Result<JsValue, JsValue> {
+        Ok(to_js_value(&self.inner.mark_synced())?)
+    }
+
+    /// Saves gzip-compressed binary bytes (returns Uint8Array).
+    ///
+    /// `fromBytes` detects and decompresses the result automatically.
+    ///
+    /// # Arguments
+    /// * `level` - Compression level 0-9 (6 is a good default)
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// await idb.put('sequences', manager.saveCompressed(6), id);
+    /// ```
+    #[cfg(feature = "compression")]
+    #[wasm_bindgen(js_name = saveCompressed)]
+    pub fn save_compressed(&mut self, level: u32) -> Uint8Array {
+        let bytes = self.inner.save_compressed(level);
+        Uint8Array::from(&bytes[..])
+    }
+
+    /// Saves an AES-256-GCM encrypted snapshot (returns Uint8Array).
+    ///
+    /// # Arguments
+    /// * `key_params` - `{ email, createdAt }` used to derive the key
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// const bytes = manager.saveEncrypted({ email: user.email, createdAt: board.createdAt });
+    /// await drive.upload(bytes);
+    /// ```
+    #[cfg(feature = "crypto")]
+    #[wasm_bindgen(js_name = saveEncrypted)]
+    pub fn save_encrypted(&mut self, key_params: JsValue) -> Result<Uint8Array, JsValue> {
+        let key_params: crate::crypto::KeyParams = from_value(key_params)?;
+        let bytes = js_result!(self.inner.save_encrypted(&key_params))?;
+        Ok(Uint8Array::from(&bytes[..]))
+    }
+
     /// Gets the full document state as a JavaScript object.
     ///
     /// Returns an object with `sequence_order` (array of IDs) and
@@ -119,6 +335,143 @@ impl JsSequenceManager {
         self.inner.actor_id()
     }
 
+    /// Gets metadata for every change in the document history.
+    ///
+    /// Returns an array of `{ hash, actor, seq, message?, timestamp, num_ops }`.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// const feed = manager.getChangeHistory()
+    ///   .filter(c => c.message)
+    ///   .map(c => `${c.actor.slice(0, 8)}: ${c.message}`);
+    /// ```
+    #[wasm_bindgen(js_name = getChangeHistory)]
+    pub fn get_change_history(&mut self) -> Result<JsValue, JsValue> {
+        Ok(to_js_value(&self.inner.get_change_history())?)
+    }
+
+    /// Registers a display name for a hex actor ID.
+    #[wasm_bindgen(js_name = setActorName)]
+    pub fn set_actor_name(&mut self, actor: &str, name: &str) -> Result<(), JsValue> {
+        js_result!(self.inner.set_actor_name(actor, name))
+    }
+
+    /// Gets the registered display names keyed by hex actor ID.
+    #[wasm_bindgen(js_name = getActorNames)]
+    pub fn get_actor_names(&mut self) -> Result<JsValue, JsValue> {
+        let names = js_result!(self.inner.get_actor_names())?;
+        Ok(to_js_value(&names)?)
+    }
+
+    /// Gets activity events (`{ type, id, ..., actor, timestamp, hash }`)
+    /// for the changes since `sinceHeads` (all changes if empty).
+    #[wasm_bindgen(js_name = activity)]
+    pub fn activity(&mut self, since_heads: Array) -> Result<JsValue, JsValue> {
+        let heads = js_result!(crate::sync::heads_from_js(&since_heads))?;
+        let events = js_result!(self.inner.activity(&heads))?;
+        Ok(to_js_value(&events)?)
+    }
+
+    /// Gets every concurrent value (`{ actor, value }`, winner first) of a
+    /// node field.
+    #[wasm_bindgen(js_name = getConflicts)]
+    pub fn get_conflicts(&mut self, node_id: &str, field: &str) -> Result<JsValue, JsValue> {
+        let conflicts = js_result!(self.inner.get_conflicts(node_id, field))?;
+        Ok(to_js_value(&conflicts)?)
+    }
+
+    /// Keeps the value `chosenActor` wrote to a node field.
+    #[wasm_bindgen(js_name = resolveConflict)]
+    pub fn resolve_conflict(
+        &mut self,
+        node_id: &str,
+        field: &str,
+        chosen_actor: &str,
+    ) -> Result<(), JsValue> {
+        js_result!(self.inner.resolve_conflict(node_id, field, chosen_actor))
+    }
+
+    /// Gets `{ actor, name?, timestamp }` of the last writer of each node field.
+    #[wasm_bindgen(js_name = getNodeAuthors)]
+    pub fn get_node_authors(&mut self, node_id: &str) -> Result<JsValue, JsValue> {
+        let authors = js_result!(self.inner.get_node_authors(node_id))?;
+        Ok(to_js_value(&authors)?)
+    }
+
+    /// Returns `{ changes, ops, actors, change_bytes }` for the change history.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// if (manager.historyStats().changes > 10000) {
+    ///   await api.replaceSnapshot(id, manager.saveCompact());
+    /// }
+    /// ```
+    #[wasm_bindgen(js_name = historyStats)]
+    pub fn history_stats(&mut self) -> Result<JsValue, JsValue> {
+        Ok(to_js_value(&self.inner.history_stats())?)
+    }
+
+    /// Saves the current state as a fresh document without change history
+    /// (returns Uint8Array). Peers must reload the result instead of syncing.
+    #[wasm_bindgen(js_name = saveCompact)]
+    pub fn save_compact(&mut self) -> Result<Uint8Array, JsValue> {
+        let bytes = js_result!(self.inner.save_compact())?;
+        Ok(Uint8Array::from(&bytes[..]))
+    }
+
+    /// Saves the document in the versioned envelope (returns Uint8Array).
+    /// `options`: `{ compressionLevel?, keyParams? }`; `fromBytes` (or
+    /// `fromEncryptedBytes` with key params) reads the result, and
+    /// `detectFormat()` reports its flags.
+    #[wasm_bindgen(js_name = saveEnveloped)]
+    pub fn save_enveloped(&mut self, options: JsValue) -> Result<Uint8Array, JsValue> {
+        let options: Option<crate::envelope::SaveOptions> = from_value(options)?;
+        let bytes = js_result!(self.inner.save_enveloped(&options.unwrap_or_default()))?;
+        Ok(Uint8Array::from(&bytes[..]))
+    }
+
+    /// Packs the document, its full history, upload checkpoints
+    /// (`{ name: checkpoint }`, optional) and metadata into one backup
+    /// archive (returns Uint8Array).
+    #[wasm_bindgen(js_name = exportBundle)]
+    pub fn export_bundle(&mut self, doc_id: &str, checkpoints: JsValue) -> Result<Uint8Array, JsValue> {
+        let checkpoints: Option<BTreeMap<String, SyncCheckpoint>> = from_value(checkpoints)?;
+        let bytes = js_result!(self.inner.export_bundle(doc_id, &checkpoints.unwrap_or_default()))?;
+        Ok(Uint8Array::from(&bytes[..]))
+    }
+
+    /// Restores a manager from `exportBundle()` output.
+    /// Returns `{ manager, metadata }`; `metadata.checkpoints` holds the
+    /// exported upload checkpoints.
+    #[wasm_bindgen(js_name = importBundle)]
+    pub fn import_bundle(bytes: &[u8]) -> Result<JsValue, JsValue> {
+        let (inner, metadata) = js_result!(SequenceManager::import_bundle(bytes))?;
+        let result = js_sys::Object::new();
+        js_sys::Reflect::set(&result, &"metadata".into(), &to_js_value(&metadata)?)?;
+        js_sys::Reflect::set(&result, &"manager".into(), &JsSequenceManager { inner }.into())?;
+        Ok(result.into())
+    }
+
+    /// Saves the current state as a fresh document with every string last
+    /// written by one of `actorIds` (hex) redacted (returns Uint8Array).
+    /// The result must replace every stored copy of the document.
+    #[wasm_bindgen(js_name = exportSanitized)]
+    pub fn export_sanitized(&self, actor_ids: Array) -> Result<Uint8Array, JsValue> {
+        let actors = decode_actors(&actor_ids)?;
+        let bytes = js_result!(self.inner.export_sanitized(&actors))?;
+        Ok(Uint8Array::from(&bytes[..]))
+    }
+
+    /// Replaces a node field (e.g. `"prompt"`, `"settings.model"`) with
+    /// `[redacted]` and returns the compacted document without the old value
+    /// (Uint8Array). Upload it in place of the stored document; peers must
+    /// reload it.
+    #[wasm_bindgen(js_name = redactField)]
+    pub fn redact_field(&mut self, node_id: &str, field: &str) -> Result<Uint8Array, JsValue> {
+        let bytes = js_result!(self.inner.redact_field(node_id, field))?;
+        Ok(Uint8Array::from(&bytes[..]))
+    }
+
     /// Gets the current heads (for sync protocol).
     ///
     /// Heads represent the current state of the document and are used
@@ -184,6 +537,109 @@ impl JsSequenceManager {
         Ok(())
     }
 
+    /// Creates a node whose settings are the document defaults and appends it.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.createWithDefaults('gen-2', 't2i');
+    /// ```
+    #[wasm_bindgen(js_name = createWithDefaults)]
+    pub fn create_with_defaults(&mut self, id: &str, type_: &str) -> Result<(), JsValue> {
+        js_result!(self.inner.create_with_defaults(id, type_))
+    }
+
+    /// Returns the project-wide default settings.
+    #[wasm_bindgen(js_name = getDefaults)]
+    pub fn get_defaults(&mut self) -> Result<JsValue, JsValue> {
+        let defaults = js_result!(self.inner.get_defaults())?;
+        Ok(to_js_value(&defaults)?)
+    }
+
+    /// Replaces the project-wide default settings (validated against the
+    /// settings limits).
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.setDefaults({ model: 'flux-dev', resolution: 1080 });
+    /// ```
+    #[wasm_bindgen(js_name = setDefaults)]
+    pub fn set_defaults(&mut self, defaults: JsValue) -> Result<(), JsValue> {
+        let defaults: GenerationSettings = from_value(defaults)?;
+        js_result!(self.inner.update_defaults(|d| *d = defaults))
+    }
+
+    /// Returns all settings presets as `{ presetId: settings }`.
+    #[wasm_bindgen(js_name = getPresets)]
+    pub fn get_presets(&mut self) -> Result<JsValue, JsValue> {
+        let presets = js_result!(self.inner.get_presets())?;
+        Ok(to_js_value(&presets)?)
+    }
+
+    /// Creates or replaces a settings preset.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.createPreset('cinematic-4k', { model: 'flux-pro', width: 3840, height: 2160 });
+    /// ```
+    #[wasm_bindgen(js_name = createPreset)]
+    pub fn create_preset(&mut self, preset_id: &str, settings: JsValue) -> Result<(), JsValue> {
+        let settings: GenerationSettings = from_value(settings)?;
+        js_result!(self.inner.create_preset(preset_id, settings))
+    }
+
+    /// Applies a preset's fields to a node's settings.
+    #[wasm_bindgen(js_name = applyPreset)]
+    pub fn apply_preset(&mut self, node_id: &str, preset_id: &str) -> Result<(), JsValue> {
+        js_result!(self.inner.apply_preset(node_id, preset_id))
+    }
+
+    /// Deletes a preset. Returns false if it did not exist.
+    #[wasm_bindgen(js_name = deletePreset)]
+    pub fn delete_preset(&mut self, preset_id: &str) -> Result<bool, JsValue> {
+        js_result!(self.inner.delete_preset(preset_id))
+    }
+
+    /// Records the current state under `name` (replacing a snapshot with
+    /// that name).
+    #[wasm_bindgen(js_name = createSnapshot)]
+    pub fn create_snapshot(&mut self, name: &str) -> Result<(), JsValue> {
+        js_result!(self.inner.create_snapshot(name))
+    }
+
+    /// Gets the named snapshots (`{ [name]: { heads, created_by } }`).
+    #[wasm_bindgen(js_name = listSnapshots)]
+    pub fn list_snapshots(&mut self) -> Result<JsValue, JsValue> {
+        let snapshots = js_result!(self.inner.list_snapshots())?;
+        Ok(to_js_value(&snapshots)?)
+    }
+
+    /// Deletes a named snapshot. Returns false if it did not exist.
+    #[wasm_bindgen(js_name = deleteSnapshot)]
+    pub fn delete_snapshot(&mut self, name: &str) -> Result<bool, JsValue> {
+        js_result!(self.inner.delete_snapshot(name))
+    }
+
+    /// Restores the state recorded by snapshot `name` as a new change.
+    #[wasm_bindgen(js_name = restoreSnapshot)]
+    pub fn restore_snapshot(&mut self, name: &str) -> Result<(), JsValue> {
+        js_result!(self.inner.restore_snapshot(name))
+    }
+
+    /// Creates many nodes and appends them in order as a single change.
+    /// `nodes` is an array of `[id, node]`.
+    #[wasm_bindgen(js_name = createMany)]
+    pub fn create_many(&mut self, nodes: JsValue) -> Result<(), JsValue> {
+        let nodes: Vec<(String, GenerationNode)> = from_value(nodes)?;
+        js_result!(self.inner.create_many(nodes))
+    }
+
+    /// Creates `newId` as a variation of `srcId`: prompts and settings are
+    /// copied, status is reset to "pending" and outputs are cleared.
+    #[wasm_bindgen(js_name = duplicateNode)]
+    pub fn duplicate_node(&mut self, src_id: &str, new_id: &str) -> Result<(), JsValue> {
+        js_result!(self.inner.duplicate_node(src_id, new_id))
+    }
+
     /// Gets a node by ID, returns null if not found.
     ///
     /// # Example (JavaScript)
@@ -257,7 +713,46 @@ impl JsSequenceManager {
         Ok(())
     }
 
-    /// Gets the current sequence order as an array of IDs.
+    /// Moves a node between two neighbours in display order (null at
+    /// either end). With fractional order only the node's key is written.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.moveNodeBetween('gen-3', 'gen-1', 'gen-2');
+    /// ```
+    #[wasm_bindgen(js_name = moveNodeBetween)]
+    pub fn move_node_between(
+        &mut self,
+        node_id: &str,
+        prev: Option<String>,
+        next: Option<String>,
+    ) -> Result<(), JsValue> {
+        js_result!(self
+            .inner
+            .move_node_between(node_id, prev.as_deref(), next.as_deref()))
+    }
+
+    /// Switches between list and fractional node ordering.
+    #[wasm_bindgen(js_name = setFractionalOrder)]
+    pub fn set_fractional_order(&mut self, enabled: bool) -> Result<(), JsValue> {
+        js_result!(self.inner.set_fractional_order(enabled))
+    }
+
+    /// Archives a node: it is kept but hidden from `getOrder()` and
+    /// `getSummaries()` by default.
+    #[wasm_bindgen(js_name = archiveNode)]
+    pub fn archive_node(&mut self, id: &str) -> Result<(), JsValue> {
+        js_result!(self.inner.archive_node(id))
+    }
+
+    /// Restores an archived node.
+    #[wasm_bindgen(js_name = unarchiveNode)]
+    pub fn unarchive_node(&mut self, id: &str) -> Result<(), JsValue> {
+        js_result!(self.inner.unarchive_node(id))
+    }
+
+    /// Gets the current sequence order as an array of IDs. Archived nodes
+    /// are left out unless `includeArchived` is true.
     ///
     /// # Example (JavaScript)
     /// ```js
@@ -265,14 +760,43 @@ impl JsSequenceManager {
     /// console.log(order); // ['gen-1', 'gen-2', 'gen-3']
     /// ```
     #[wasm_bindgen(js_name = getOrder)]
-    pub fn get_order(&mut self) -> Result<Array, JsValue> {
-        let order = js_result!(self.inner.get_order())?;
+    pub fn get_order(&mut self, include_archived: Option<bool>) -> Result<Array, JsValue> {
+        let order = js_result!(self.inner.get_order(include_archived.unwrap_or(false)))?;
         let array = Array::new();
         for id in order {
             array.push(&JsValue::from_str(&id));
         }
         Ok(array)
     }
+
+    /// Gets `[{ id, type_, status, title, output_count, archived }]` in
+    /// sequence order without hydrating prompts, settings or outputs.
+    /// Archived nodes are left out unless `includeArchived` is true.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// const rows = manager.getSummaries();
+    /// renderList(rows.map(r => `${r.title} (${r.status})`));
+    /// ```
+    #[wasm_bindgen(js_name = getSummaries)]
+    pub fn get_summaries(&mut self, include_archived: Option<bool>) -> Result<JsValue, JsValue> {
+        let summaries = js_result!(self.inner.get_summaries(include_archived.unwrap_or(false)))?;
+        Ok(to_js_value(&summaries)?)
+    }
+
+    /// Searches node titles and prompts. Returns `[nodeId, score]` pairs
+    /// for nodes containing every word of the query, best match first.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// const ids = manager.searchPrompts('sunset ocean').map(([id]) => id);
+    /// ```
+    #[cfg(feature = "search")]
+    #[wasm_bindgen(js_name = searchPrompts)]
+    pub fn search_prompts(&mut self, query: &str) -> Result<JsValue, JsValue> {
+        let hits = js_result!(self.inner.search_prompts(query))?;
+        Ok(to_js_value(&hits)?)
+    }
 }
 
 // =============================================================================
@@ -281,6 +805,90 @@ impl JsSequenceManager {
 
 #[wasm_bindgen]
 impl JsSequenceManager {
+    /// Replaces the ranges enforced by the settings setters.
+    ///
+    /// Omitted fields keep their default; null disables that check. Defaults
+    /// are cfg 0–30, steps 1–150, fps 1–120, width/height multiples of 8.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.setSettingsLimits({ cfg: [0, 20], num_steps: [1, 100], fps: null, dimension_multiple: 64 });
+    /// ```
+    #[wasm_bindgen(js_name = setSettingsLimits)]
+    pub fn set_settings_limits(&mut self, limits: JsValue) -> Result<(), JsValue> {
+        let limits: SettingsLimits = from_value(limits)?;
+        self.inner.set_settings_limits(limits);
+        Ok(())
+    }
+
+    /// Replaces the enforcement modes. Omitted fields are off.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.setOptions({ freeze_completed: true });
+    /// ```
+    #[wasm_bindgen(js_name = setOptions)]
+    pub fn set_options(&mut self, options: JsValue) -> Result<(), JsValue> {
+        let options: ManagerOptions = from_value(options)?;
+        self.inner.set_options(options);
+        Ok(())
+    }
+
+    /// Sets the local user and ACL enforcement mode.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.setPermissionContext({ user_id: user.id, enforcement: 'deny' });
+    /// ```
+    #[wasm_bindgen(js_name = setPermissionContext)]
+    pub fn set_permission_context(&mut self, context: JsValue) -> Result<(), JsValue> {
+        let context: PermissionContext = from_value(context)?;
+        self.inner.set_permission_context(context);
+        Ok(())
+    }
+
+    /// Returns and clears the denials recorded in warn mode.
+    #[wasm_bindgen(js_name = takePermissionWarnings)]
+    pub fn take_permission_warnings(&mut self) -> Array {
+        self.inner
+            .take_permission_warnings()
+            .into_iter()
+            .map(|w| JsValue::from_str(&w))
+            .collect()
+    }
+
+    /// Gets the ACL (`{ owners, editors, commenters }` keyed by user ID).
+    #[wasm_bindgen(js_name = getAcl)]
+    pub fn get_acl(&mut self) -> Result<JsValue, JsValue> {
+        let acl = js_result!(self.inner.get_acl())?;
+        Ok(to_js_value(&acl)?)
+    }
+
+    /// Gives a user exactly `role` ('owner' | 'editor' | 'commenter').
+    #[wasm_bindgen(js_name = grantRole)]
+    pub fn grant_role(&mut self, user_id: &str, role: JsValue) -> Result<(), JsValue> {
+        let role: Role = from_value(role)?;
+        js_result!(self.inner.grant_role(user_id, role))
+    }
+
+    /// Removes every role of a user. Returns false if it had none.
+    #[wasm_bindgen(js_name = revokeRole)]
+    pub fn revoke_role(&mut self, user_id: &str) -> Result<bool, JsValue> {
+        js_result!(self.inner.revoke_role(user_id))
+    }
+
+    /// Allows settings changes on a completed node despite `freeze_completed`.
+    #[wasm_bindgen(js_name = unfreeze)]
+    pub fn unfreeze(&mut self, node_id: &str) {
+        self.inner.unfreeze(node_id);
+    }
+
+    /// Returns true if settings changes on the node are currently rejected.
+    #[wasm_bindgen(js_name = isFrozen)]
+    pub fn is_frozen(&mut self, node_id: &str) -> Result<bool, JsValue> {
+        js_result!(self.inner.is_frozen(node_id))
+    }
+
     /// Sets the seed setting (pass null to clear).
     ///
     /// # Example (JavaScript)
@@ -339,7 +947,9 @@ impl JsSequenceManager {
     /// ```
     #[wasm_bindgen(js_name = setSettingResolution)]
     pub fn set_setting_resolution(&mut self, node_id: &str, resolution: Option<i32>) -> Result<(), JsValue> {
-        js_result!(self.inner.set_setting_resolution(node_id, resolution))?;
+        js_result!(self
+            .inner
+            .set_setting_resolution(node_id, resolution.map(ResolutionPreset)))?;
         Ok(())
     }
 
@@ -367,7 +977,7 @@ impl JsSequenceManager {
         Ok(())
     }
 
-    /// Sets the duration setting (pass null to clear).
+    /// Sets the duration setting in seconds (pass null to clear).
     ///
     /// # Example (JavaScript)
     /// ```js
@@ -375,6 +985,19 @@ impl JsSequenceManager {
     /// ```
     #[wasm_bindgen(js_name = setSettingDuration)]
     pub fn set_setting_duration(&mut self, node_id: &str, duration: Option<i32>) -> Result<(), JsValue> {
+        js_result!(self.inner.set_setting_duration(node_id, duration.map(DurationSecs)))?;
+        Ok(())
+    }
+
+    /// Sets the duration setting from milliseconds (rounded to whole seconds).
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.setSettingDurationMs('gen-1', video.duration * 1000);
+    /// ```
+    #[wasm_bindgen(js_name = setSettingDurationMs)]
+    pub fn set_setting_duration_ms(&mut self, node_id: &str, duration_ms: Option<f64>) -> Result<(), JsValue> {
+        let duration = duration_ms.map(|ms| DurationSecs::from(DurationMs(ms.round() as i64)));
         js_result!(self.inner.set_setting_duration(node_id, duration))?;
         Ok(())
     }
@@ -390,6 +1013,50 @@ impl JsSequenceManager {
         js_result!(self.inner.set_setting_fps(node_id, fps))?;
         Ok(())
     }
+
+    /// Sets the sampler setting (pass null to clear).
+    #[wasm_bindgen(js_name = setSettingSampler)]
+    pub fn set_setting_sampler(&mut self, node_id: &str, sampler: Option<String>) -> Result<(), JsValue> {
+        js_result!(self.inner.set_setting_sampler(node_id, sampler.as_deref()))
+    }
+
+    /// Sets the scheduler setting (pass null to clear).
+    #[wasm_bindgen(js_name = setSettingScheduler)]
+    pub fn set_setting_scheduler(&mut self, node_id: &str, scheduler: Option<String>) -> Result<(), JsValue> {
+        js_result!(self.inner.set_setting_scheduler(node_id, scheduler.as_deref()))
+    }
+
+    /// Sets the denoising strength setting (pass null to clear).
+    #[wasm_bindgen(js_name = setSettingStrength)]
+    pub fn set_setting_strength(&mut self, node_id: &str, strength: Option<f64>) -> Result<(), JsValue> {
+        js_result!(self.inner.set_setting_strength(node_id, strength))
+    }
+
+    /// Replaces the LoRA list (pass an empty array to clear).
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.setSettingLoras('gen-1', [{ id: 'film-grain', weight: 0.8 }]);
+    /// ```
+    #[wasm_bindgen(js_name = setSettingLoras)]
+    pub fn set_setting_loras(&mut self, node_id: &str, loras: JsValue) -> Result<(), JsValue> {
+        let loras: Vec<LoraRef> = from_value(loras)?;
+        js_result!(self.inner.set_setting_loras(node_id, &loras))
+    }
+
+    /// Sets an extra model parameter (string, number or boolean) directly
+    /// (O(1)). Pass null to remove it.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.setSettingExtra('gen-1', 'sampler', 'euler_a');
+    /// manager.setSettingExtra('gen-1', 'clip_skip', 2);
+    /// ```
+    #[wasm_bindgen(js_name = setSettingExtra)]
+    pub fn set_setting_extra(&mut self, node_id: &str, key: &str, value: JsValue) -> Result<(), JsValue> {
+        let value: Option<SettingValue> = from_value(value)?;
+        js_result!(self.inner.set_setting_extra(node_id, key, value))
+    }
 }
 
 // =============================================================================
@@ -412,29 +1079,711 @@ impl JsSequenceManager {
         Ok(())
     }
 
-    /// Adds an output asset to a generation node.
-    ///
-    /// # Arguments
-    /// * `node_id` - ID of the generation node
-    /// * `output` - OutputAsset object with fields:
-    ///   - `url`: string
-    ///   - `seed`: number (optional)
-    ///   - `is_selected`: boolean
+    /// Sets the status only if the transition table allows moving there from
+    /// the current status; throws an IllegalTransition error otherwise.
     ///
     /// # Example (JavaScript)
     /// ```js
-    /// manager.addOutput('gen-1', {
-    ///   url: 'https://cdn.example.com/image.png',
-    ///   seed: 42,
-    ///   is_selected: true
-    /// });
+    /// manager.setStatusChecked('gen-1', 'processing');
     /// ```
-    #[wasm_bindgen(js_name = addOutput)]
-    pub fn add_output(&mut self, node_id: &str, output: JsValue) -> Result<(), JsValue> {
+    #[wasm_bindgen(js_name = setStatusChecked)]
+    pub fn set_status_checked(&mut self, node_id: &str, status: &str) -> Result<(), JsValue> {
+        let status: GenerationStatus = js_result!(status.parse())?;
+        js_result!(self.inner.set_status_checked(node_id, status))
+    }
+
+    /// Replaces the transition table used by `setStatusChecked`: each status
+    /// maps to the statuses it may move to.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.setStatusTransitions({ pending: ['processing'], processing: ['completed', 'failed'] });
+    /// ```
+    #[wasm_bindgen(js_name = setStatusTransitions)]
+    pub fn set_status_transitions(&mut self, transitions: JsValue) -> Result<(), JsValue> {
+        let transitions: StatusTransitions = from_value(transitions)?;
+        self.inner.set_status_transitions(transitions);
+        Ok(())
+    }
+
+    /// Sets the status and records when it was set (ms since epoch).
+    ///
+    /// Workers should use this instead of `setStatus` so `sweep()` can detect
+    /// jobs that were abandoned mid-render.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.setStatusAt('gen-1', 'processing', Date.now());
+    /// ```
+    #[wasm_bindgen(js_name = setStatusAt)]
+    pub fn set_status_at(&mut self, node_id: &str, status: &str, timestamp: f64) -> Result<(), JsValue> {
+        js_result!(self.inner.set_status_at(node_id, status, timestamp as i64))?;
+        Ok(())
+    }
+
+    /// Sets the external render provider (pass null to clear).
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.setProvider('gen-1', 'fal');
+    /// ```
+    #[wasm_bindgen(js_name = setProvider)]
+    pub fn set_provider(&mut self, node_id: &str, provider: Option<String>) -> Result<(), JsValue> {
+        js_result!(self.inner.set_provider(node_id, provider.as_deref()))?;
+        Ok(())
+    }
+
+    /// Sets the provider's job ID (pass null to clear).
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// const { request_id } = await fal.queue.submit(...);
+    /// manager.setJobId('gen-1', request_id);
+    /// ```
+    #[wasm_bindgen(js_name = setJobId)]
+    pub fn set_job_id(&mut self, node_id: &str, job_id: Option<String>) -> Result<(), JsValue> {
+        js_result!(self.inner.set_job_id(node_id, job_id.as_deref()))?;
+        Ok(())
+    }
+
+    /// Sets which node this one was derived from (O(1)); null detaches it.
+    #[wasm_bindgen(js_name = setDerivedFrom)]
+    pub fn set_derived_from(&mut self, node_id: &str, derived_from: Option<String>) -> Result<(), JsValue> {
+        js_result!(self.inner.set_derived_from(node_id, derived_from.as_deref()))
+    }
+
+    /// Links a node to the storyboard shot it was generated for (O(1));
+    /// null removes the link.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.setStoryboardRef('gen-1', { doc_id: 'storyboard-7', node_id: 'shot-3' });
+    /// ```
+    #[wasm_bindgen(js_name = setStoryboardRef)]
+    pub fn set_storyboard_ref(&mut self, node_id: &str, reference: JsValue) -> Result<(), JsValue> {
+        let reference: Option<DocRef> = from_value(reference)?;
+        js_result!(self.inner.set_storyboard_ref(node_id, reference.as_ref()))
+    }
+
+    /// Calls `resolver` with the node's storyboard link and returns its
+    /// result, or null if the node has no link.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// const shot = manager.resolveStoryboardRef('gen-1', (ref) =>
+    ///   storyboards.get(ref.doc_id)?.findShot(ref.node_id) ?? null);
+    /// ```
+    #[wasm_bindgen(js_name = resolveStoryboardRef)]
+    pub fn resolve_storyboard_ref(&mut self, node_id: &str, resolver: &js_sys::Function) -> Result<JsValue, JsValue> {
+        let node = js_result!(self.inner.get_node(node_id))?
+            .ok_or_else(|| JsValue::from(CollabError::node_not_found(node_id)))?;
+        match node.storyboard_ref {
+            Some(reference) => resolver.call1(&JsValue::NULL, &to_js_value(&reference)?),
+            None => Ok(JsValue::NULL),
+        }
+    }
+
+    /// Returns the IDs (sorted) of nodes matching a filter, reading only the
+    /// fields it checks. All criteria are optional.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// const queued = manager.query({ status: 'queued', type_: 'i2v' });
+    /// ```
+    #[wasm_bindgen(js_name = query)]
+    pub fn query(&mut self, filter: JsValue) -> Result<Array, JsValue> {
+        let filter: NodeFilter = from_value(filter)?;
+        let ids = js_result!(self.inner.query(&filter))?;
+        let array = Array::new();
+        for id in ids {
+            array.push(&JsValue::from_str(&id));
+        }
+        Ok(array)
+    }
+
+    /// Tags a node. Returns false if it already had the tag.
+    #[wasm_bindgen(js_name = addTag)]
+    pub fn add_tag(&mut self, node_id: &str, tag: &str) -> Result<bool, JsValue> {
+        js_result!(self.inner.add_tag(node_id, tag))
+    }
+
+    /// Removes a tag from a node. Returns false if it did not have it.
+    #[wasm_bindgen(js_name = removeTag)]
+    pub fn remove_tag(&mut self, node_id: &str, tag: &str) -> Result<bool, JsValue> {
+        js_result!(self.inner.remove_tag(node_id, tag))
+    }
+
+    /// Returns the IDs of nodes with a tag, in sequence order.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// const heroes = manager.getNodesByTag('hero shot');
+    /// ```
+    #[wasm_bindgen(js_name = getNodesByTag)]
+    pub fn get_nodes_by_tag(&mut self, tag: &str) -> Result<Array, JsValue> {
+        let ids = js_result!(self.inner.get_nodes_by_tag(tag))?;
+        let array = Array::new();
+        for id in ids {
+            array.push(&JsValue::from_str(&id));
+        }
+        Ok(array)
+    }
+
+    /// Records that a user looked at a node (ms since epoch).
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.markSeen('gen-1', userId, Date.now());
+    /// const badge = manager.unseenCount(userId);
+    /// ```
+    #[wasm_bindgen(js_name = markSeen)]
+    pub fn mark_seen(&mut self, node_id: &str, user: &str, timestamp: f64) -> Result<(), JsValue> {
+        js_result!(self.inner.mark_seen(node_id, user, timestamp as i64))
+    }
+
+    /// Counts the nodes a user has not seen since they last changed.
+    #[wasm_bindgen(js_name = unseenCount)]
+    pub fn unseen_count(&mut self, user: &str) -> Result<usize, JsValue> {
+        js_result!(self.inner.unseen_count(user))
+    }
+
+    /// Places a node's clip on the timeline.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.placeOnTimeline('gen-1', { track: 'V1', start_ms: 0, duration_ms: 4000 });
+    /// ```
+    #[wasm_bindgen(js_name = placeOnTimeline)]
+    pub fn place_on_timeline(&mut self, node_id: &str, placement: JsValue) -> Result<(), JsValue> {
+        let placement: TimelinePlacement = from_value(placement)?;
+        js_result!(self.inner.place_on_timeline(node_id, &placement))
+    }
+
+    /// Takes a node's clip off the timeline.
+    #[wasm_bindgen(js_name = removeFromTimeline)]
+    pub fn remove_from_timeline(&mut self, node_id: &str) -> Result<(), JsValue> {
+        js_result!(self.inner.remove_from_timeline(node_id))
+    }
+
+    /// Moves a placed clip to another track and/or start time.
+    #[wasm_bindgen(js_name = moveClip)]
+    pub fn move_clip(&mut self, node_id: &str, track: &str, start_ms: f64) -> Result<(), JsValue> {
+        js_result!(self.inner.move_clip(node_id, track, start_ms as i64))
+    }
+
+    /// Splits a placed clip at a timeline position; `newId` gets the right part.
+    #[wasm_bindgen(js_name = splitClip)]
+    pub fn split_clip(&mut self, node_id: &str, new_id: &str, at_ms: f64) -> Result<(), JsValue> {
+        js_result!(self.inner.split_clip(node_id, new_id, at_ms as i64))
+    }
+
+    /// Returns the IDs of the nodes on a track, ordered by start time.
+    #[wasm_bindgen(js_name = getTrack)]
+    pub fn get_track(&mut self, track: &str) -> Result<Array, JsValue> {
+        let ids = js_result!(self.inner.get_track(track))?;
+        let array = Array::new();
+        for id in ids {
+            array.push(&JsValue::from_str(&id));
+        }
+        Ok(array)
+    }
+
+    /// Exports the timeline as OpenTimelineIO JSON text.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// download('cut.otio', manager.exportOtio('Cut 1', 24));
+    /// ```
+    #[cfg(feature = "export")]
+    #[wasm_bindgen(js_name = exportOtio)]
+    pub fn export_otio(&mut self, name: &str, fps: u32) -> Result<String, JsValue> {
+        js_result!(self.inner.export_otio(name, fps))
+    }
+
+    /// Exports the timeline as a CMX 3600 EDL.
+    #[cfg(feature = "export")]
+    #[wasm_bindgen(js_name = exportEdl)]
+    pub fn export_edl(&mut self, title: &str, fps: u32) -> Result<String, JsValue> {
+        js_result!(self.inner.export_edl(title, fps))
+    }
+
+    /// Sets an animated parameter's value at `t` (ms), inserting or
+    /// updating that keyframe.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.setKeyframe('gen-1', 'camera_pan', 1500, 0.25);
+    /// ```
+    #[wasm_bindgen(js_name = setKeyframe)]
+    pub fn set_keyframe(&mut self, node_id: &str, param: &str, t: f64, value: f64) -> Result<(), JsValue> {
+        js_result!(self.inner.set_keyframe(node_id, param, t as i64, value))
+    }
+
+    /// Deletes a parameter's keyframe at `t` (ms). Returns false if there was none.
+    #[wasm_bindgen(js_name = deleteKeyframe)]
+    pub fn delete_keyframe(&mut self, node_id: &str, param: &str, t: f64) -> Result<bool, JsValue> {
+        js_result!(self.inner.delete_keyframe(node_id, param, t as i64))
+    }
+
+    /// Returns the variation tree `{ id, children: [...] }` rooted at `id`,
+    /// or null if the node does not exist.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// const tree = manager.getVariations('gen-1');
+    /// const count = (t) => t.children.reduce((n, c) => n + 1 + count(c), 0);
+    /// ```
+    #[wasm_bindgen(js_name = getVariations)]
+    pub fn get_variations(&mut self, id: &str) -> Result<JsValue, JsValue> {
+        let tree = js_result!(self.inner.get_variations(id))?;
+        Ok(to_js_value(&tree)?)
+    }
+
+    /// Makes `nodeId` consume output `outputIndex` of `inputNodeId`.
+    /// Throws if the link would create a dependency cycle.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.linkInput('i2v-1', 't2i-1', 0);
+    /// for (const id of manager.topologicalOrder()) await run(id);
+    /// ```
+    #[wasm_bindgen(js_name = linkInput)]
+    pub fn link_input(
+        &mut self,
+        node_id: &str,
+        input_node_id: &str,
+        output_index: u32,
+    ) -> Result<(), JsValue> {
+        let input = NodeInputRef::new(input_node_id, output_index);
+        js_result!(self.inner.link_input(node_id, input))
+    }
+
+    /// Removes an input link; returns false if it was not linked.
+    #[wasm_bindgen(js_name = unlinkInput)]
+    pub fn unlink_input(
+        &mut self,
+        node_id: &str,
+        input_node_id: &str,
+        output_index: u32,
+    ) -> Result<bool, JsValue> {
+        let input = NodeInputRef::new(input_node_id, output_index);
+        js_result!(self.inner.unlink_input(node_id, &input))
+    }
+
+    /// Returns all node IDs, each after the nodes it takes inputs from.
+    #[wasm_bindgen(js_name = topologicalOrder)]
+    pub fn topological_order(&mut self) -> Result<Array, JsValue> {
+        let order = js_result!(self.inner.topological_order())?;
+        let array = Array::new();
+        for id in order {
+            array.push(&JsValue::from_str(&id));
+        }
+        Ok(array)
+    }
+
+    /// Returns the ID of the node submitted as `job_id`, or null.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// const nodeId = manager.findNodeByJobId(webhook.request_id);
+    /// ```
+    #[wasm_bindgen(js_name = findNodeByJobId)]
+    pub fn find_node_by_job_id(&mut self, job_id: &str) -> Result<Option<String>, JsValue> {
+        js_result!(self.inner.find_node_by_job_id(job_id))
+    }
+
+    /// Downgrades nodes stuck in "processing" to "stalled".
+    ///
+    /// Returns a report `{ stalled: [{ path, since }] }`.
+    ///
+    /// # Arguments
+    /// * `now` - Current time (ms since epoch)
+    /// * `ttl_ms` - Max processing time before a node is stalled (default: 10 minutes)
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// const report = manager.sweep(Date.now(), 5 * 60 * 1000);
+    /// ```
+    pub fn sweep(&mut self, now: f64, ttl_ms: Option<f64>) -> Result<JsValue, JsValue> {
+        let config = match ttl_ms {
+            Some(ttl) => SweepConfig::new(ttl as i64),
+            None => SweepConfig::default(),
+        };
+        let report = js_result!(self.inner.sweep_with_config(now as i64, &config))?;
+        Ok(to_js_value(&report)?)
+    }
+
+    /// Makes the sequence order match the stored nodes after concurrent edits.
+    ///
+    /// Returns `{ actions: [{ path, id, kind }] }` where `kind` is
+    /// "removed_duplicate", "removed_missing" or "appended_unordered".
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// const { actions } = manager.repair();
+    /// if (actions.length) console.warn('Repaired order', actions);
+    /// ```
+    pub fn repair(&mut self) -> Result<JsValue, JsValue> {
+        let report = js_result!(self.inner.repair())?;
+        Ok(to_js_value(&report)?)
+    }
+
+    /// Adds an output asset to a generation node.
+    ///
+    /// # Arguments
+    /// * `node_id` - ID of the generation node
+    /// * `output` - OutputAsset object with fields:
+    ///   - `url`: string
+    ///   - `seed`: number (optional)
+    ///   - `is_selected`: boolean
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.addOutput('gen-1', {
+    ///   url: 'https://cdn.example.com/image.png',
+    ///   seed: 42,
+    ///   is_selected: true
+    /// });
+    /// ```
+    #[wasm_bindgen(js_name = addOutput)]
+    pub fn add_output(&mut self, node_id: &str, output: JsValue) -> Result<(), JsValue> {
         let output: OutputAsset = from_value(output)?;
         js_result!(self.inner.add_output(node_id, output))?;
         Ok(())
     }
+
+    /// Appends an output asset via a direct list insert (O(1)).
+    ///
+    /// Same arguments as `addOutput`, but avoids reconciling the whole document.
+    #[wasm_bindgen(js_name = addOutputDirect)]
+    pub fn add_output_direct(&mut self, node_id: &str, output: JsValue) -> Result<(), JsValue> {
+        let output: OutputAsset = from_value(output)?;
+        js_result!(self.inner.add_output_direct(node_id, output))?;
+        Ok(())
+    }
+
+    /// Appends a source asset (O(1)).
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.addInputAsset('gen-1', {
+    ///   url: 'https://cdn.example.com/source.png',
+    ///   kind: 'image',
+    ///   mask_url: 'https://cdn.example.com/mask.png'
+    /// });
+    /// ```
+    #[wasm_bindgen(js_name = addInputAsset)]
+    pub fn add_input_asset(&mut self, node_id: &str, asset: JsValue) -> Result<(), JsValue> {
+        let asset: InputAsset = from_value(asset)?;
+        js_result!(self.inner.add_input_asset(node_id, asset))?;
+        Ok(())
+    }
+
+    /// Removes the source asset at `index`.
+    #[wasm_bindgen(js_name = removeInputAsset)]
+    pub fn remove_input_asset(&mut self, node_id: &str, index: usize) -> Result<(), JsValue> {
+        js_result!(self.inner.remove_input_asset(node_id, index))?;
+        Ok(())
+    }
+
+    /// Removes the output at `index`.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.removeOutput('gen-1', 0);
+    /// ```
+    #[wasm_bindgen(js_name = removeOutput)]
+    pub fn remove_output(&mut self, node_id: &str, index: usize) -> Result<(), JsValue> {
+        js_result!(self.inner.remove_output(node_id, index))?;
+        Ok(())
+    }
+
+    /// Selects the output at `index` as the preview, clearing other selections.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.setSelectedOutput('gen-1', 2);
+    /// ```
+    #[wasm_bindgen(js_name = setSelectedOutput)]
+    pub fn set_selected_output(&mut self, node_id: &str, index: usize) -> Result<(), JsValue> {
+        js_result!(self.inner.set_selected_output(node_id, index))?;
+        Ok(())
+    }
+
+    /// Moves an output from one position to another.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.reorderOutputs('gen-1', 3, 0); // Move fourth output to the front
+    /// ```
+    #[wasm_bindgen(js_name = reorderOutputs)]
+    pub fn reorder_outputs(&mut self, node_id: &str, from: usize, to: usize) -> Result<(), JsValue> {
+        js_result!(self.inner.reorder_outputs(node_id, from, to))?;
+        Ok(())
+    }
+
+    /// Sets an output's MIME type (pass null to clear).
+    #[wasm_bindgen(js_name = setOutputMimeType)]
+    pub fn set_output_mime_type(
+        &mut self,
+        node_id: &str,
+        index: usize,
+        mime_type: Option<String>,
+    ) -> Result<(), JsValue> {
+        js_result!(self.inner.set_output_mime_type(node_id, index, mime_type.as_deref()))
+    }
+
+    /// Sets an output's width and height (pass nulls to clear).
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.setOutputDimensions('gen-1', 0, 1280, 720);
+    /// ```
+    #[wasm_bindgen(js_name = setOutputDimensions)]
+    pub fn set_output_dimensions(
+        &mut self,
+        node_id: &str,
+        index: usize,
+        width: Option<i32>,
+        height: Option<i32>,
+    ) -> Result<(), JsValue> {
+        let dimensions = width.zip(height);
+        js_result!(self.inner.set_output_dimensions(node_id, index, dimensions))
+    }
+
+    /// Sets an output's duration in milliseconds (pass null to clear).
+    #[wasm_bindgen(js_name = setOutputDurationMs)]
+    pub fn set_output_duration_ms(
+        &mut self,
+        node_id: &str,
+        index: usize,
+        duration_ms: Option<f64>,
+    ) -> Result<(), JsValue> {
+        let duration_ms = duration_ms.map(|ms| ms.round() as i64);
+        js_result!(self.inner.set_output_duration_ms(node_id, index, duration_ms))
+    }
+
+    /// Sets an output's file size in bytes (pass null to clear).
+    #[wasm_bindgen(js_name = setOutputFileSize)]
+    pub fn set_output_file_size(
+        &mut self,
+        node_id: &str,
+        index: usize,
+        file_size: Option<f64>,
+    ) -> Result<(), JsValue> {
+        let file_size = file_size.map(|size| size as i64);
+        js_result!(self.inner.set_output_file_size(node_id, index, file_size))
+    }
+
+    /// Sets an output's thumbnail URL (pass null to clear).
+    #[wasm_bindgen(js_name = setOutputThumbnailUrl)]
+    pub fn set_output_thumbnail_url(
+        &mut self,
+        node_id: &str,
+        index: usize,
+        thumbnail_url: Option<String>,
+    ) -> Result<(), JsValue> {
+        js_result!(self.inner.set_output_thumbnail_url(node_id, index, thumbnail_url.as_deref()))
+    }
+
+    /// Stars an output for a user. Returns false if already starred.
+    #[wasm_bindgen(js_name = starOutput)]
+    pub fn star_output(&mut self, node_id: &str, index: usize, user: &str) -> Result<bool, JsValue> {
+        js_result!(self.inner.star_output(node_id, index, user))
+    }
+
+    /// Removes a user's star from an output. Returns false if there was none.
+    #[wasm_bindgen(js_name = unstarOutput)]
+    pub fn unstar_output(&mut self, node_id: &str, index: usize, user: &str) -> Result<bool, JsValue> {
+        js_result!(self.inner.unstar_output(node_id, index, user))
+    }
+
+    /// Returns the outputs a user starred as `[nodeId, index]` pairs.
+    #[wasm_bindgen(js_name = getStarred)]
+    pub fn get_starred(&mut self, user: &str) -> Result<JsValue, JsValue> {
+        let starred = js_result!(self.inner.get_starred(user))?;
+        Ok(to_js_value(&starred)?)
+    }
+}
+
+// =============================================================================
+// JOB QUEUE METHODS
+// =============================================================================
+
+#[wasm_bindgen]
+impl JsSequenceManager {
+    /// Sets a node to "queued" and appends it to the job queue.
+    pub fn enqueue(&mut self, node_id: &str) -> Result<(), JsValue> {
+        js_result!(self.inner.enqueue(node_id))
+    }
+
+    /// Claims the next queued node for a worker. Returns the node ID, or
+    /// undefined if nothing is waiting.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// const nodeId = manager.claimNext('worker-1', 60_000, Date.now());
+    /// ```
+    #[wasm_bindgen(js_name = claimNext)]
+    pub fn claim_next(
+        &mut self,
+        worker_id: &str,
+        lease_ms: f64,
+        now: f64,
+    ) -> Result<Option<String>, JsValue> {
+        js_result!(self.inner.claim_next(worker_id, lease_ms as i64, now as i64))
+    }
+
+    /// Extends a worker's lease. Returns false if the worker lost the node.
+    #[wasm_bindgen(js_name = renewLease)]
+    pub fn renew_lease(
+        &mut self,
+        node_id: &str,
+        worker_id: &str,
+        lease_ms: f64,
+        now: f64,
+    ) -> Result<bool, JsValue> {
+        js_result!(self.inner.renew_lease(node_id, worker_id, lease_ms as i64, now as i64))
+    }
+
+    /// Marks a node "completed" at `now` and removes it from the queue.
+    pub fn complete(&mut self, node_id: &str, now: f64) -> Result<(), JsValue> {
+        js_result!(self.inner.complete(node_id, now as i64))
+    }
+
+    /// Returns the worker holding a node, or undefined.
+    #[wasm_bindgen(js_name = claimHolder)]
+    pub fn claim_holder(&mut self, node_id: &str, now: f64) -> Result<Option<String>, JsValue> {
+        js_result!(self.inner.claim_holder(node_id, now as i64))
+    }
+
+    /// Returns the IDs of nodes a worker has a claim record on.
+    #[wasm_bindgen(js_name = getClaimedBy)]
+    pub fn get_claimed_by(&mut self, worker_id: &str) -> Result<Array, JsValue> {
+        let ids = js_result!(self.inner.get_claimed_by(worker_id))?;
+        let array = Array::new();
+        for id in ids {
+            array.push(&JsValue::from_str(&id));
+        }
+        Ok(array)
+    }
+
+    /// Removes expired claims and requeues nodes whose worker crashed.
+    /// Returns the IDs of the nodes whose claims expired.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// setInterval(() => manager.expireStaleClaims(Date.now()), 30_000);
+    /// ```
+    #[wasm_bindgen(js_name = expireStaleClaims)]
+    pub fn expire_stale_claims(&mut self, now: f64) -> Result<Array, JsValue> {
+        let ids = js_result!(self.inner.expire_stale_claims(now as i64))?;
+        let array = Array::new();
+        for id in ids {
+            array.push(&JsValue::from_str(&id));
+        }
+        Ok(array)
+    }
+}
+
+// =============================================================================
+// COMMENT METHODS
+// =============================================================================
+
+#[wasm_bindgen]
+impl JsSequenceManager {
+    /// Adds a comment to a generation node.
+    ///
+    /// Set `parent_id` to reply to an existing comment.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.addComment('gen-1', {
+    ///   id: crypto.randomUUID(),
+    ///   author: 'alice@example.com',
+    ///   body: 'Lighting is too flat',
+    ///   created_at: Date.now(),
+    ///   resolved: false,
+    ///   parent_id: null
+    /// });
+    /// ```
+    #[wasm_bindgen(js_name = addComment)]
+    pub fn add_comment(&mut self, node_id: &str, comment: JsValue) -> Result<(), JsValue> {
+        let comment: Comment = from_value(comment)?;
+        js_result!(self.inner.add_comment(node_id, comment))?;
+        Ok(())
+    }
+
+    /// Marks a comment as resolved (or reopens it with `false`).
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.resolveComment('gen-1', 'c-1', true);
+    /// ```
+    #[wasm_bindgen(js_name = resolveComment)]
+    pub fn resolve_comment(&mut self, node_id: &str, comment_id: &str, resolved: bool) -> Result<(), JsValue> {
+        js_result!(self.inner.resolve_comment(node_id, comment_id, resolved))?;
+        Ok(())
+    }
+
+    /// Deletes a comment and all of its replies.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.deleteComment('gen-1', 'c-1');
+    /// ```
+    #[wasm_bindgen(js_name = deleteComment)]
+    pub fn delete_comment(&mut self, node_id: &str, comment_id: &str) -> Result<(), JsValue> {
+        js_result!(self.inner.delete_comment(node_id, comment_id))?;
+        Ok(())
+    }
+
+    /// Returns all comments on a generation node as an array.
+    #[wasm_bindgen(js_name = getComments)]
+    pub fn get_comments(&mut self, node_id: &str) -> Result<JsValue, JsValue> {
+        let comments = js_result!(self.inner.get_comments(node_id))?;
+        Ok(to_js_value(&comments)?)
+    }
+}
+
+// =============================================================================
+// BATCH METHODS
+// =============================================================================
+
+#[wasm_bindgen]
+impl JsSequenceManager {
+    /// Starts a batch. All operations until `commitBatch()` become one change.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.beginBatch();
+    /// manager.moveGeneration(0, 3);
+    /// ids.forEach(id => manager.setStatus(id, 'queued'));
+    /// manager.commitBatch('Reorder and queue');
+    /// ```
+    #[wasm_bindgen(js_name = beginBatch)]
+    pub fn begin_batch(&mut self) {
+        self.inner.begin_batch();
+    }
+
+    /// Commits the current batch as one change with an optional message.
+    ///
+    /// Returns the change hash, or null if nothing was changed.
+    #[wasm_bindgen(js_name = commitBatch)]
+    pub fn commit_batch(&mut self, message: Option<String>) -> Result<JsValue, JsValue> {
+        Ok(match js_result!(self.inner.commit_batch(message.as_deref()))? {
+            Some(hash) => JsValue::from_str(&hash.to_string()),
+            None => JsValue::NULL,
+        })
+    }
+
+    /// Discards all operations since `beginBatch()`.
+    ///
+    /// Returns the number of operations rolled back.
+    #[wasm_bindgen(js_name = rollbackBatch)]
+    pub fn rollback_batch(&mut self) -> usize {
+        self.inner.rollback_batch()
+    }
 }
 
 // =============================================================================
@@ -460,16 +1809,60 @@ impl JsSequenceManager {
         Ok(())
     }
 
-    /// Generates a sync message for changes since their heads.
+    /// Applies only the given changes (hex hashes) from `other`, plus their
+    /// missing dependencies. Returns `{ applied, already_present, not_found }`.
+    #[wasm_bindgen(js_name = cherryPick)]
+    pub fn cherry_pick(
+        &mut self,
+        other: &mut JsSequenceManager,
+        change_hashes: Array,
+    ) -> Result<JsValue, JsValue> {
+        let hashes = js_result!(crate::sync::heads_from_js(&change_hashes))?;
+        let report = js_result!(self.inner.cherry_pick(&mut other.inner, &hashes))?;
+        Ok(to_js_value(&report)?)
+    }
+
+    /// Gets changes since the given heads (for incremental sync).
     ///
-    /// Returns a Uint8Array containing the sync message, or null if no changes.
+    /// Returns a Uint8Array with the changes, or null if there are none.
+    /// Throws if a head is not a 64-character hex string.
     ///
     /// # Arguments
-    /// * `their_heads` - Array of head strings from the remote peer (currently unused, pass [])
+    /// * `their_heads` - Array of hex-encoded head hashes from the remote peer
+    ///   (pass [] for the full history)
     ///
     /// # Example (JavaScript)
     /// ```js
-    /// const syncMsg = manager.generateSyncMessage([]);
+    /// const diff = manager.getChangesSince(remoteHeads);
+    /// if (diff) ws.send(diff);
+    /// ```
+    #[wasm_bindgen(js_name = getChangesSince)]
+    pub fn get_changes_since(&mut self, their_heads: Array) -> Result<JsValue, JsValue> {
+        let heads = js_result!(crate::sync::heads_from_js(&their_heads))?;
+        match self.inner.generate_sync_message(&heads) {
+            Some(bytes) => Ok(Uint8Array::from(&bytes[..]).into()),
+            None => Ok(JsValue::NULL),
+        }
+    }
+
+    /// Applies changes from `getChangesSince` (for incremental sync).
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.applyChanges(await downloadDiff(diffId));
+    /// ```
+    #[wasm_bindgen(js_name = applyChanges)]
+    pub fn apply_changes(&mut self, changes: &[u8]) -> Result<(), JsValue> {
+        js_result!(self.inner.apply_sync_message(changes))
+    }
+
+    /// Generates a sync message for changes since their heads.
+    ///
+    /// Same as `getChangesSince`: returns a Uint8Array, or null if no changes.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// const syncMsg = manager.generateSyncMessage(remoteHeads);
     /// if (syncMsg) {
     ///   // Convert to base64 and send over WebSocket
     ///   const base64 = btoa(String.fromCharCode(...syncMsg));
@@ -477,13 +1870,93 @@ impl JsSequenceManager {
     /// }
     /// ```
     #[wasm_bindgen(js_name = generateSyncMessage)]
-    pub fn generate_sync_message(&mut self, _their_heads: Array) -> Result<JsValue, JsValue> {
-        // TODO: Parse their_heads array and convert to Vec<ChangeHash>
-        // For now, generate sync message from empty heads (full document)
-        match self.inner.generate_sync_message(&[]) {
-            Some(bytes) => Ok(Uint8Array::from(&bytes[..]).into()),
-            None => Ok(JsValue::NULL)
-        }
+    pub fn generate_sync_message(&mut self, their_heads: Array) -> Result<JsValue, JsValue> {
+        self.get_changes_since(their_heads)
+    }
+
+    /// Generates sync messages split into frames of at most `maxBytes`.
+    ///
+    /// Returns an array of Uint8Arrays (empty if there are no changes). Apply
+    /// each frame in order with `applySyncMessage`.
+    ///
+    /// # Arguments
+    /// * `their_heads` - Array of hex-encoded head hashes from the remote peer
+    /// * `max_bytes` - Frame size budget
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// const frames = manager.generateSyncMessagesChunked(remoteHeads, 64 * 1024);
+    /// for (const frame of frames) {
+    ///   ws.send(frame);
+    /// }
+    /// ```
+    #[wasm_bindgen(js_name = generateSyncMessagesChunked)]
+    pub fn generate_sync_messages_chunked(
+        &mut self,
+        their_heads: Array,
+        max_bytes: usize,
+    ) -> Result<Array, JsValue> {
+        let heads = js_result!(crate::sync::heads_from_js(&their_heads))?;
+        let frames = self.inner.generate_sync_messages_chunked(&heads, max_bytes);
+        Ok(crate::sync::frames_to_js(&frames))
+    }
+
+    /// Generates size-bounded sync frames with node structure first and
+    /// output/comment changes last.
+    ///
+    /// Returns `{ frames, skeletonFrames }`. After applying the first
+    /// `skeletonFrames` frames the receiver can render every node; the
+    /// remaining frames fill in outputs and comments.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// const { frames, skeletonFrames } =
+    ///   manager.generateSyncMessagesPrioritized(remoteHeads, 64 * 1024);
+    /// frames.forEach((frame) => ws.send(frame));
+    /// ```
+    #[wasm_bindgen(js_name = generateSyncMessagesPrioritized)]
+    pub fn generate_sync_messages_prioritized(
+        &mut self,
+        their_heads: Array,
+        max_bytes: usize,
+    ) -> Result<JsValue, JsValue> {
+        let heads = js_result!(crate::sync::heads_from_js(&their_heads))?;
+        let prioritized = self.inner.generate_sync_messages_prioritized(&heads, max_bytes);
+        Ok(crate::sync::prioritized_to_js(&prioritized))
+    }
+
+    /// Generates sync chunks of at most `maxBytes`, splitting even single
+    /// oversized changes (e.g. a very long prompt).
+    ///
+    /// Returns an array of Uint8Arrays (empty if there are no changes). The
+    /// receiver passes each chunk to `applySyncChunk`, in any order.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// for (const chunk of manager.generateSyncChunks(remoteHeads, 64 * 1024)) {
+    ///   ws.send(chunk);
+    /// }
+    /// // receiver
+    /// ws.onmessage = (e) => {
+    ///   if (other.applySyncChunk(new Uint8Array(e.data))) render(other.getState());
+    /// };
+    /// ```
+    #[wasm_bindgen(js_name = generateSyncChunks)]
+    pub fn generate_sync_chunks(
+        &mut self,
+        their_heads: Array,
+        max_bytes: usize,
+    ) -> Result<Array, JsValue> {
+        let heads = js_result!(crate::sync::heads_from_js(&their_heads))?;
+        let chunks = self.inner.generate_sync_chunks(&heads, max_bytes);
+        Ok(crate::sync::frames_to_js(&chunks))
+    }
+
+    /// Adds a chunk from `generateSyncChunks`. Returns true once a complete
+    /// message was verified and applied.
+    #[wasm_bindgen(js_name = applySyncChunk)]
+    pub fn apply_sync_chunk(&mut self, chunk: &[u8]) -> Result<bool, JsValue> {
+        js_result!(self.inner.apply_sync_chunk(chunk))
     }
 
     /// Applies a sync message from a peer.
@@ -506,3 +1979,95 @@ impl JsSequenceManager {
         Ok(())
     }
 }
+
+// =============================================================================
+// PRESENCE METHODS
+// =============================================================================
+
+#[wasm_bindgen]
+impl JsSequenceManager {
+    /// Sets this peer's presence and returns the message to broadcast (Uint8Array).
+    ///
+    /// Presence is ephemeral and never stored in the document.
+    ///
+    /// # Arguments
+    /// * `user` - Display name or user ID
+    /// * `focused_node` - ID of the focused generation node (optional)
+    /// * `cursor` - `{ field, anchor, head }` (optional)
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// const msg = manager.setLocalPresence('Alice', 'gen-42', { field: 'prompt', anchor: 7, head: 7 });
+    /// ws.send(msg);
+    /// ```
+    #[wasm_bindgen(js_name = setLocalPresence)]
+    pub fn set_local_presence(
+        &mut self,
+        user: &str,
+        focused_node: Option<String>,
+        cursor: JsValue,
+    ) -> Result<Uint8Array, JsValue> {
+        let cursor: Option<Cursor> = from_value(cursor)?;
+        let bytes = self
+            .inner
+            .set_local_presence(user, focused_node.as_deref(), cursor);
+        Ok(Uint8Array::from(&bytes[..]))
+    }
+
+    /// Clears this peer's presence and returns a "left" message to broadcast.
+    #[wasm_bindgen(js_name = clearLocalPresence)]
+    pub fn clear_local_presence(&mut self) -> Uint8Array {
+        let bytes = self.inner.clear_local_presence();
+        Uint8Array::from(&bytes[..])
+    }
+
+    /// Encodes the current local presence (send periodically as a heartbeat).
+    #[wasm_bindgen(js_name = encodePresence)]
+    pub fn encode_presence(&self) -> Uint8Array {
+        let bytes = self.inner.encode_presence();
+        Uint8Array::from(&bytes[..])
+    }
+
+    /// Applies a presence message received at `now` (ms). Returns true if the peer list changed.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// if (manager.applyPresenceMessage(bytes, Date.now())) renderAvatars(manager.getPeers());
+    /// ```
+    #[wasm_bindgen(js_name = applyPresenceMessage)]
+    pub fn apply_presence_message(&mut self, msg: &[u8], now: f64) -> Result<bool, JsValue> {
+        js_result!(self.inner.apply_presence_message(msg, now as i64))
+    }
+
+    /// Sets how long a silent peer is kept (ms, default 30000).
+    #[wasm_bindgen(js_name = setPeerTimeout)]
+    pub fn set_peer_timeout(&mut self, timeout_ms: f64) {
+        self.inner.set_peer_timeout(timeout_ms as i64);
+    }
+
+    /// Drops silent peers. Returns the removed actor IDs.
+    #[wasm_bindgen(js_name = prunePeers)]
+    pub fn prune_peers(&mut self, now: f64) -> Result<JsValue, JsValue> {
+        Ok(to_js_value(&self.inner.prune_peers(now as i64))?)
+    }
+
+    /// Returns remote peers as
+    /// `[{ actor, clock, last_seen, state: { user, focused_node, cursor } }]`.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// for (const peer of manager.getPeers()) {
+    ///   console.log(`${peer.state.user} is editing ${peer.state.focused_node}`);
+    /// }
+    /// ```
+    #[wasm_bindgen(js_name = getPeers)]
+    pub fn get_peers(&self) -> Result<JsValue, JsValue> {
+        Ok(to_js_value(&self.inner.get_peers())?)
+    }
+
+    /// Forgets a peer by actor ID (e.g., when its connection closes).
+    #[wasm_bindgen(js_name = removePeer)]
+    pub fn remove_peer(&mut self, actor: &str) -> bool {
+        self.inner.remove_peer(actor)
+    }
+}
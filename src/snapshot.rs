@@ -0,0 +1,58 @@
+//! Named snapshots.
+//!
+//! `create_snapshot(name)` records the current heads under a label in the
+//! document's `snapshots` map, so every peer sees the same list.
+//! `restore_snapshot(name)` reads the state at those heads and writes it
+//! back as a new change: history is kept, and the restore syncs and can be
+//! reverted like any other edit. The ACL, actor names and the snapshot list
+//! itself are not rolled back.
+//!
+//! Snapshots point into the change history. After `save_compact()` or
+//! `redact_field()` their heads no longer exist and restoring them fails.
+
+use std::str::FromStr;
+
+use automerge::{AutoCommit, ChangeHash};
+use autosurgeon::{hydrate, Hydrate, Reconcile};
+use serde::{Deserialize, Serialize};
+
+use crate::checkpoint::SyncCheckpoint;
+use crate::error::{CollabError, CollabResult};
+
+/// A labelled point in the document history.
+#[derive(Debug, Clone, Default, Reconcile, Hydrate, Serialize, Deserialize, PartialEq)]
+pub struct NamedSnapshot {
+    /// Hex-encoded heads at creation, sorted.
+    pub heads: Vec<String>,
+    /// Hex actor ID of the peer that created it.
+    pub created_by: String,
+}
+
+impl NamedSnapshot {
+    /// Captures the current heads of `doc`.
+    pub(crate) fn capture(doc: &mut AutoCommit) -> Self {
+        Self {
+            heads: SyncCheckpoint::from_heads(&doc.get_heads()).heads,
+            created_by: doc.get_actor().to_hex_string(),
+        }
+    }
+
+    /// Hydrates the root of `doc` as of this snapshot.
+    pub(crate) fn state<T: Hydrate>(&self, doc: &mut AutoCommit) -> CollabResult<T> {
+        let heads = self
+            .heads
+            .iter()
+            .map(|head| {
+                ChangeHash::from_str(head).map_err(|_| CollabError::invalid_change_hash(head))
+            })
+            .collect::<CollabResult<Vec<_>>>()?;
+        if let Some(missing) = heads.iter().find(|head| doc.get_change_by_hash(head).is_none()) {
+            return Err(CollabError::invalid_change_hash(format!(
+                "{} (not in this document's history)",
+                missing
+            )));
+        }
+        let past = doc.fork_at(&heads)?;
+        Ok(hydrate(&past)?)
+    }
+}
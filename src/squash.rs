@@ -0,0 +1,162 @@
+//! Offline history squashing.
+//!
+//! Old clients committed a change per keystroke, leaving some documents with
+//! hundreds of thousands of tiny changes. `squash_history` rebuilds such a
+//! document with one change per run of same-actor changes that fall within a
+//! time window. The final state is identical. Each consolidated change keeps
+//! the original actor, the run's last timestamp and its last commit message.
+//!
+//! The result is a re-baselined document: its change hashes differ from the
+//! original, and history becomes linear (concurrent edits are replayed in
+//! causal order). Peers holding the old document must reload, not sync.
+//!
+//! The function is generic over the typed root (`DocumentRoot` for sequences,
+//! `StoryboardRoot` for storyboards) because consolidated changes are
+//! produced by hydrating each run's end state and reconciling it into the
+//! new document.
+
+use automerge::{transaction::CommitOptions, AutoCommit};
+use autosurgeon::{hydrate, reconcile, Hydrate, Reconcile};
+use serde::{Deserialize, Serialize};
+
+use crate::error::CollabResult;
+
+/// Summary of what `squash_history` did.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SquashReport {
+    /// Number of changes in the original document.
+    pub changes_before: usize,
+    /// Number of changes in the squashed document.
+    pub changes_after: usize,
+}
+
+/// Rebuilds a document, squashing runs of changes from the same actor whose
+/// timestamps are within `window_ms` of the run's first change.
+///
+/// # Example
+/// ```ignore
+/// let (bytes, report) = squash_history::<DocumentRoot>(&saved, 60_000)?;
+/// ```
+pub fn squash_history<R>(bytes: &[u8], window_ms: i64) -> CollabResult<(Vec<u8>, SquashReport)>
+where
+    R: Hydrate + Reconcile,
+{
+    let mut source = AutoCommit::load(bytes)?;
+    let changes = source.get_changes(&[]);
+
+    let mut replay = AutoCommit::new();
+    let mut target = AutoCommit::new();
+    let mut changes_after = 0;
+
+    let mut start = 0;
+    while start < changes.len() {
+        let actor = changes[start].actor_id().clone();
+        let run_start_ts = changes[start].timestamp();
+        let mut end = start + 1;
+        while end < changes.len()
+            && changes[end].actor_id() == &actor
+            && (changes[end].timestamp() - run_start_ts).abs() <= window_ms
+        {
+            end += 1;
+        }
+
+        let run = &changes[start..end];
+        let mut run_bytes = Vec::new();
+        for change in run {
+            run_bytes.extend(change.raw_bytes());
+        }
+        replay.load_incremental(&run_bytes)?;
+        let state: R = hydrate(&replay)?;
+
+        target.set_actor(actor);
+        reconcile(&mut target, &state)?;
+        let last = &run[run.len() - 1];
+        let mut options = CommitOptions::default().with_time(last.timestamp());
+        if let Some(message) = run.iter().rev().find_map(|c| c.message()) {
+            options = options.with_message(message.clone());
+        }
+        if target.commit_with(options).is_some() {
+            changes_after += 1;
+        }
+
+        start = end;
+    }
+
+    let report = SquashReport {
+        changes_before: changes.len(),
+        changes_after,
+    };
+    Ok((target.save(), report))
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequence::{DocumentRoot, DocumentSeed, GenerationNode, SequenceManager};
+
+    #[test]
+    fn test_squash_keystroke_history() {
+        let seed = DocumentSeed::new().with_node(GenerationNode::new("a", "t2i"));
+        let mut manager = SequenceManager::new_with_seed(seed).unwrap();
+
+        // One change per keystroke, 100ms apart
+        let text = "a red fox";
+        for i in 1..=text.len() {
+            let prompt = text[..i].to_string();
+            manager
+                .update_state_with_message("Edit prompt", 100_000 + i as i64 * 100, |state| {
+                    state.generations.get_mut("a").unwrap().prompt = prompt;
+                })
+                .unwrap();
+        }
+        // A later edit outside the window stays separate
+        manager
+            .update_state_with_message("Set notes", 200_000, |state| {
+                state.generations.get_mut("a").unwrap().notes = "v2".to_string();
+            })
+            .unwrap();
+
+        let (bytes, report) = squash_history::<DocumentRoot>(&manager.save(), 5_000).unwrap();
+        assert_eq!(report.changes_before, text.len() + 2);
+        assert_eq!(report.changes_after, 3);
+
+        let mut squashed = SequenceManager::from_bytes(&bytes).unwrap();
+        assert_eq!(squashed.get_state().unwrap(), manager.get_state().unwrap());
+
+        let history = squashed.get_change_history();
+        assert_eq!(history.len(), 3);
+        assert!(history.iter().all(|c| c.actor == manager.actor_id()));
+        assert_eq!(history[1].message.as_deref(), Some("Edit prompt"));
+        assert_eq!(history[1].timestamp, 100_000 + text.len() as i64 * 100);
+    }
+
+    #[test]
+    fn test_squash_keeps_actors_separate() {
+        let mut alice = SequenceManager::new_with_seed(
+            DocumentSeed::new().with_node(GenerationNode::new("a", "t2i")),
+        )
+        .unwrap();
+        let mut bob = SequenceManager::from_bytes(&alice.save()).unwrap();
+
+        alice
+            .update_state_with_message("A", 100_000, |s| {
+                s.generations.get_mut("a").unwrap().title = "alice".to_string();
+            })
+            .unwrap();
+        bob
+            .update_state_with_message("B", 100_001, |s| {
+                s.generations.get_mut("a").unwrap().notes = "bob".to_string();
+            })
+            .unwrap();
+        alice.merge(&mut bob).unwrap();
+
+        let (bytes, report) = squash_history::<DocumentRoot>(&alice.save(), 60_000).unwrap();
+        assert_eq!(report.changes_after, 3);
+        let mut squashed = SequenceManager::from_bytes(&bytes).unwrap();
+        assert_eq!(squashed.get_state().unwrap(), alice.get_state().unwrap());
+    }
+}
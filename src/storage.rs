@@ -0,0 +1,387 @@
+//! Pluggable document persistence.
+//!
+//! A `DocumentStore` keeps one snapshot plus an append-only change log per
+//! document. `load()` returns the snapshot followed by the logged changes,
+//! which Automerge loads as a single document. Managers with an attached
+//! store (`attach_store()`) append changes as their commit paths run (and on
+//! `flush_store()`), so autosave writes kilobytes per edit instead of the
+//! whole document.
+//!
+//! `FileStore` is the filesystem backend:
+//!
+//! ```text
+//! <root>/<doc_id>.automerge   snapshot
+//! <root>/<doc_id>.changes     change log (appended, cleared by save_snapshot)
+//! ```
+//!
+//! Document IDs are used as file names verbatim, so `FileStore` only accepts
+//! IDs that pass `validate_doc_id()`. Mapping other IDs onto file names would
+//! let two documents share the same files.
+
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use automerge::{AutoCommit, ChangeHash};
+
+use crate::error::{CollabError, CollabResult};
+use crate::naming;
+
+/// Extension of snapshot files written by `FileStore`.
+pub const SNAPSHOT_EXTENSION: &str = "automerge";
+
+/// Extension of change-log files written by `FileStore`.
+pub const CHANGES_EXTENSION: &str = "changes";
+
+/// Maximum document ID length in bytes.
+pub const MAX_DOC_ID_BYTES: usize = naming::MAX_STEM_BYTES;
+
+/// Checks that `doc_id` can be used as a file stem unchanged.
+///
+/// Accepts 1 to `MAX_DOC_ID_BYTES` lowercase ASCII letters, digits, `-` and
+/// `_` (UUIDs pass), excluding Windows device names. Uppercase is refused so
+/// IDs differing only in case can't share files on case-insensitive
+/// filesystems.
+pub fn validate_doc_id(doc_id: &str) -> CollabResult<()> {
+    let valid = !doc_id.is_empty()
+        && doc_id.len() <= MAX_DOC_ID_BYTES
+        && doc_id
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_')
+        && !naming::is_windows_reserved(doc_id);
+    if valid {
+        Ok(())
+    } else {
+        Err(CollabError::invalid_doc_id(doc_id))
+    }
+}
+
+/// Storage backend for Automerge documents.
+pub trait DocumentStore {
+    /// Returns the snapshot followed by all logged changes, or None if the
+    /// document is unknown. Pass the bytes to `from_bytes()`.
+    fn load(&self, doc_id: &str) -> CollabResult<Option<Vec<u8>>>;
+
+    /// Appends encoded changes to the document's change log.
+    fn append_changes(&mut self, doc_id: &str, changes: &[u8]) -> CollabResult<()>;
+
+    /// Replaces the snapshot and clears the change log.
+    fn save_snapshot(&mut self, doc_id: &str, snapshot: &[u8]) -> CollabResult<()>;
+
+    /// Returns the IDs of all stored documents, sorted.
+    fn list(&self) -> CollabResult<Vec<String>>;
+}
+
+// =============================================================================
+// FILESYSTEM BACKEND
+// =============================================================================
+
+/// Stores documents as snapshot + change-log files in one directory.
+///
+/// Every method fails with `InvalidDocId` for IDs rejected by
+/// `validate_doc_id()`.
+#[derive(Debug, Clone)]
+pub struct FileStore {
+    root: PathBuf,
+}
+
+impl FileStore {
+    /// Opens (and creates if needed) a store rooted at `root`.
+    pub fn new(root: impl Into<PathBuf>) -> CollabResult<Self> {
+        let root = root.into();
+        fs::create_dir_all(&root).map_err(|e| io_error(&root, e))?;
+        Ok(Self { root })
+    }
+
+    /// Returns the store's directory.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn snapshot_path(&self, doc_id: &str) -> CollabResult<PathBuf> {
+        self.path(doc_id, SNAPSHOT_EXTENSION)
+    }
+
+    fn changes_path(&self, doc_id: &str) -> CollabResult<PathBuf> {
+        self.path(doc_id, CHANGES_EXTENSION)
+    }
+
+    fn path(&self, doc_id: &str, extension: &str) -> CollabResult<PathBuf> {
+        validate_doc_id(doc_id)?;
+        Ok(self.root.join(format!("{}.{}", doc_id, extension)))
+    }
+}
+
+impl DocumentStore for FileStore {
+    fn load(&self, doc_id: &str) -> CollabResult<Option<Vec<u8>>> {
+        let snapshot = read_if_exists(&self.snapshot_path(doc_id)?)?;
+        let changes = read_if_exists(&self.changes_path(doc_id)?)?;
+        match (snapshot, changes) {
+            (None, None) => Ok(None),
+            (snapshot, changes) => {
+                let mut bytes = snapshot.unwrap_or_default();
+                bytes.extend(changes.unwrap_or_default());
+                Ok(Some(bytes))
+            }
+        }
+    }
+
+    fn append_changes(&mut self, doc_id: &str, changes: &[u8]) -> CollabResult<()> {
+        let path = self.changes_path(doc_id)?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| io_error(&path, e))?;
+        file.write_all(changes).map_err(|e| io_error(&path, e))
+    }
+
+    fn save_snapshot(&mut self, doc_id: &str, snapshot: &[u8]) -> CollabResult<()> {
+        // Write-then-rename so a crash never leaves a truncated snapshot. If we
+        // crash before the log is cleared, the logged changes are already in
+        // the snapshot and loading them again is a no-op.
+        let path = self.snapshot_path(doc_id)?;
+        let tmp = path.with_extension(format!("{}.tmp", SNAPSHOT_EXTENSION));
+        fs::write(&tmp, snapshot).map_err(|e| io_error(&tmp, e))?;
+        fs::rename(&tmp, &path).map_err(|e| io_error(&path, e))?;
+
+        let changes = self.changes_path(doc_id)?;
+        match fs::remove_file(&changes) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(io_error(&changes, e)),
+            _ => Ok(()),
+        }
+    }
+
+    fn list(&self) -> CollabResult<Vec<String>> {
+        let entries = fs::read_dir(&self.root).map_err(|e| io_error(&self.root, e))?;
+        let mut ids = Vec::new();
+        for entry in entries {
+            let path = entry.map_err(|e| io_error(&self.root, e))?.path();
+            let extension = path.extension().and_then(|e| e.to_str());
+            if !matches!(extension, Some(SNAPSHOT_EXTENSION) | Some(CHANGES_EXTENSION)) {
+                continue;
+            }
+            match path.file_stem().and_then(|s| s.to_str()) {
+                Some(stem) if validate_doc_id(stem).is_ok() => ids.push(stem.to_string()),
+                _ => {}
+            }
+        }
+        ids.sort();
+        ids.dedup();
+        Ok(ids)
+    }
+}
+
+fn read_if_exists(path: &Path) -> CollabResult<Option<Vec<u8>>> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(io_error(path, e)),
+    }
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> CollabError {
+    CollabError::storage(format!("{}: {}", path.display(), e))
+}
+
+// =============================================================================
+// MANAGER ATTACHMENT
+// =============================================================================
+
+/// A store attached to a manager, plus what has been written to it.
+pub(crate) struct AttachedStore {
+    store: Box<dyn DocumentStore>,
+    doc_id: String,
+    /// Heads covered by the snapshot and change log.
+    persisted_heads: Vec<ChangeHash>,
+}
+
+impl AttachedStore {
+    /// Attaches `store` by writing a fresh snapshot of `doc`.
+    pub(crate) fn new(
+        mut store: Box<dyn DocumentStore>,
+        doc_id: &str,
+        doc: &mut AutoCommit,
+    ) -> CollabResult<Self> {
+        store.save_snapshot(doc_id, &doc.save())?;
+        Ok(Self {
+            store,
+            doc_id: doc_id.to_string(),
+            persisted_heads: doc.get_heads(),
+        })
+    }
+
+    /// Appends changes not yet persisted. Returns the number of changes
+    /// written. On failure nothing is marked persisted, so the next call
+    /// retries.
+    pub(crate) fn persist(&mut self, doc: &mut AutoCommit) -> CollabResult<usize> {
+        let (bytes, count) = {
+            let changes = doc.get_changes(&self.persisted_heads);
+            let mut bytes = Vec::new();
+            for change in &changes {
+                bytes.extend_from_slice(change.raw_bytes());
+            }
+            (bytes, changes.len())
+        };
+        if count == 0 {
+            return Ok(0);
+        }
+        self.store.append_changes(&self.doc_id, &bytes)?;
+        self.persisted_heads = doc.get_heads();
+        Ok(count)
+    }
+
+    /// Replaces the stored snapshot with `doc` and clears the change log.
+    pub(crate) fn snapshot(&mut self, doc: &mut AutoCommit) -> CollabResult<()> {
+        self.store.save_snapshot(&self.doc_id, &doc.save())?;
+        self.persisted_heads = doc.get_heads();
+        Ok(())
+    }
+
+    pub(crate) fn into_store(self) -> Box<dyn DocumentStore> {
+        self.store
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequence::{GenerationNode, SequenceManager};
+
+    fn temp_store() -> FileStore {
+        let dir = std::env::temp_dir().join(format!("heyocollab-store-{}", uuid::Uuid::new_v4()));
+        FileStore::new(dir).unwrap()
+    }
+
+    #[test]
+    fn test_file_store_snapshot_and_log() {
+        let mut store = temp_store();
+        assert!(store.load("doc-1").unwrap().is_none());
+
+        store.save_snapshot("doc-1", b"snap").unwrap();
+        store.append_changes("doc-1", b"-a").unwrap();
+        store.append_changes("doc-1", b"-b").unwrap();
+        assert_eq!(store.load("doc-1").unwrap().unwrap(), b"snap-a-b");
+
+        store.save_snapshot("doc-1", b"snap2").unwrap();
+        assert_eq!(store.load("doc-1").unwrap().unwrap(), b"snap2");
+
+        store.append_changes("doc-2", b"only-log").unwrap();
+        assert_eq!(store.list().unwrap(), vec!["doc-1", "doc-2"]);
+
+        fs::remove_dir_all(store.root()).unwrap();
+    }
+
+    #[test]
+    fn test_file_store_rejects_unsafe_ids() {
+        let mut store = temp_store();
+        store.append_changes("doc-1", b"dash").unwrap();
+
+        // These all sanitize to "doc-1" and must not reach its files
+        for id in ["doc.1", "doc 1", "doc--1", "-doc-1", "Doc-1", "../doc-1", ""] {
+            assert!(matches!(store.load(id), Err(CollabError::InvalidDocId(_))), "{:?}", id);
+            assert!(store.append_changes(id, b"other").is_err());
+            assert!(store.save_snapshot(id, b"other").is_err());
+        }
+        assert!(validate_doc_id("con").is_err());
+        assert!(validate_doc_id(&"a".repeat(MAX_DOC_ID_BYTES + 1)).is_err());
+
+        store.append_changes("doc_1", b"underscore").unwrap();
+        assert_eq!(store.load("doc-1").unwrap().unwrap(), b"dash");
+        assert_eq!(store.load("doc_1").unwrap().unwrap(), b"underscore");
+
+        // Stray files are not reported as documents
+        fs::write(store.root().join("Stray File.automerge"), b"x").unwrap();
+        assert_eq!(store.list().unwrap(), vec!["doc-1", "doc_1"]);
+
+        fs::remove_dir_all(store.root()).unwrap();
+    }
+
+    #[test]
+    fn test_attached_store_persists_commits() {
+        let store = temp_store();
+        let root = store.root().to_path_buf();
+
+        let mut manager = SequenceManager::new();
+        manager.attach_store("seq-1", Box::new(store.clone())).unwrap();
+        for i in 0..3 {
+            let id = format!("gen-{}", i);
+            manager
+                .update_state_with_message("Add node", i, |state| {
+                    state.generations.insert(id.clone(), GenerationNode::new(&id, "t2i"));
+                })
+                .unwrap();
+        }
+
+        let bytes = store.load("seq-1").unwrap().unwrap();
+        let mut loaded = SequenceManager::from_bytes(&bytes).unwrap();
+        assert_eq!(loaded.get_state().unwrap(), manager.get_state().unwrap());
+        assert!(fs::metadata(root.join("seq-1.changes")).unwrap().len() > 0);
+
+        // Snapshotting folds the log into the snapshot
+        manager.snapshot_store().unwrap();
+        assert!(!root.join("seq-1.changes").exists());
+        let mut loaded = SequenceManager::from_bytes(&store.load("seq-1").unwrap().unwrap()).unwrap();
+        assert_eq!(loaded.get_state().unwrap(), manager.get_state().unwrap());
+
+        assert!(manager.detach_store().is_some());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    /// Forwards to a `FileStore` until `offline` is set.
+    struct FlakyStore {
+        inner: FileStore,
+        offline: std::rc::Rc<std::cell::Cell<bool>>,
+    }
+
+    impl DocumentStore for FlakyStore {
+        fn load(&self, doc_id: &str) -> CollabResult<Option<Vec<u8>>> {
+            self.inner.load(doc_id)
+        }
+
+        fn append_changes(&mut self, doc_id: &str, changes: &[u8]) -> CollabResult<()> {
+            if self.offline.get() {
+                return Err(CollabError::storage("offline"));
+            }
+            self.inner.append_changes(doc_id, changes)
+        }
+
+        fn save_snapshot(&mut self, doc_id: &str, snapshot: &[u8]) -> CollabResult<()> {
+            self.inner.save_snapshot(doc_id, snapshot)
+        }
+
+        fn list(&self) -> CollabResult<Vec<String>> {
+            self.inner.list()
+        }
+    }
+
+    #[test]
+    fn test_store_errors_are_returned_and_retried() {
+        let store = temp_store();
+        let offline = std::rc::Rc::new(std::cell::Cell::new(true));
+        let flaky = FlakyStore { inner: store.clone(), offline: offline.clone() };
+        let mut manager = SequenceManager::new();
+        manager.attach_store("seq-1", Box::new(flaky)).unwrap();
+
+        // Every commit path reports the failure but keeps the change
+        let result = manager.transaction(Some("Add a"), |txn| {
+            txn.create_and_append("a", GenerationNode::new("a", "t2i"))
+        });
+        assert!(matches!(result, Err(CollabError::Storage(_))));
+        manager.begin_batch();
+        manager.create_and_append("b", GenerationNode::new("b", "t2i")).unwrap();
+        assert!(manager.commit_batch(Some("Add b")).is_err());
+        assert_eq!(manager.get_state().unwrap().len(), 2);
+
+        offline.set(false);
+        assert_eq!(manager.flush_store().unwrap(), 2);
+        let mut loaded = SequenceManager::from_bytes(&store.load("seq-1").unwrap().unwrap()).unwrap();
+        assert_eq!(loaded.get_state().unwrap(), manager.get_state().unwrap());
+
+        fs::remove_dir_all(store.root()).unwrap();
+    }
+}
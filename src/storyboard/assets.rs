@@ -0,0 +1,277 @@
+//! Uploaded asset references.
+//!
+//! Uploaded assets (`StoryboardRoot::uploaded_assets`) are used by putting
+//! their `image` (a URL or data URL) or their ID into an image field
+//! elsewhere. `unreferenced_assets()` (behind
+//! `StoryboardManager::gc_unreferenced_assets()`) finds the assets no image
+//! field points at. The fields checked are the thumbnail, entity images and
+//! histories (custom collections included), scene thumbnails, look and
+//! outfit images and histories, and shot images, histories and
+//! `known_assets` reference images. Trashed scenes and shots count too, so
+//! restoring them does not lose images.
+//!
+//! The same fields are deduplicated by `dedup_images()` (`blob-store`
+//! feature) and restored by `inline_blobs()`.
+
+#[cfg(feature = "blob-store")]
+use std::collections::hash_map::Entry;
+use std::collections::HashSet;
+
+#[cfg(feature = "blob-store")]
+use crate::blob::{self, DedupReport};
+
+use super::model::*;
+
+/// Returns the IDs of assets no image field references, sorted.
+pub(crate) fn unreferenced_assets(root: &StoryboardRoot) -> Vec<String> {
+    let mut used = HashSet::new();
+    for_each_image(&mut root.clone(), &mut |image| {
+        used.insert(image.clone());
+    });
+    let mut ids: Vec<String> = root
+        .uploaded_assets
+        .iter()
+        .filter(|(id, asset)| {
+            let by_image = !asset.image.is_empty() && used.contains(&asset.image);
+            !by_image && !used.contains(id.as_str())
+        })
+        .map(|(id, _)| id.clone())
+        .collect();
+    ids.sort_unstable();
+    ids
+}
+
+/// Replaces every image field equal to `old` with `new` (uploaded assets
+/// themselves are left alone). Returns the number of fields changed.
+pub(crate) fn replace_image(root: &mut StoryboardRoot, old: &str, new: &str) -> usize {
+    let mut count = 0;
+    for_each_image(root, &mut |image| {
+        if image == old {
+            *image = new.to_string();
+            count += 1;
+        }
+    });
+    count
+}
+
+/// Replaces base64 data URLs in image fields and uploaded assets with blob
+/// references, storing each payload once in `root.blobs`.
+#[cfg(feature = "blob-store")]
+pub(crate) fn dedup_images(root: &mut StoryboardRoot) -> DedupReport {
+    let mut blobs = std::mem::take(&mut root.blobs);
+    let mut report = DedupReport::default();
+    let mut dedup = |image: &mut String| {
+        let before = image.len();
+        if let Some(added) = intern(&mut blobs, image) {
+            report.fields += 1;
+            report.bytes_before += before;
+            report.bytes_after += image.len() + added;
+            report.blobs += usize::from(added > 0);
+        }
+    };
+    for_each_image(root, &mut dedup);
+    for asset in root.uploaded_assets.values_mut() {
+        dedup(&mut asset.image);
+    }
+    root.blobs = blobs;
+    report
+}
+
+/// Replaces `image` with a blob reference if it is a base64 data URL,
+/// adding the data URL to `blobs` if it is new. Returns None if `image` was
+/// left alone, otherwise the number of bytes added to `blobs`.
+#[cfg(feature = "blob-store")]
+pub(crate) fn intern(
+    blobs: &mut std::collections::HashMap<String, String>,
+    image: &mut String,
+) -> Option<usize> {
+    let (_, bytes) = blob::decode_data_url(image)?;
+    let hash = blob::content_hash(&bytes);
+    let reference = blob::blob_ref(&hash);
+    let added = match blobs.entry(hash) {
+        Entry::Occupied(_) => 0,
+        Entry::Vacant(entry) => {
+            let len = image.len();
+            entry.insert(std::mem::take(image));
+            len
+        }
+    };
+    *image = reference;
+    Some(added)
+}
+
+/// Undoes `dedup_images()`: puts the data URL back into every image field
+/// and uploaded asset holding a reference to `root.blobs`, then empties the
+/// map. References to an external `BlobStore` are kept. Returns the number
+/// of fields changed.
+#[cfg(feature = "blob-store")]
+pub fn inline_blobs(root: &mut StoryboardRoot) -> usize {
+    let blobs = std::mem::take(&mut root.blobs);
+    let mut count = 0;
+    let mut inline = |image: &mut String| {
+        let url = blob::parse_blob_ref(image).and_then(|hash| blobs.get(hash));
+        if let Some(url) = url {
+            *image = url.clone();
+            count += 1;
+        }
+    };
+    for_each_image(root, &mut inline);
+    for asset in root.uploaded_assets.values_mut() {
+        inline(&mut asset.image);
+    }
+    count
+}
+
+/// Calls `f` on every image field listed in the module docs.
+fn for_each_image(root: &mut StoryboardRoot, f: &mut impl FnMut(&mut String)) {
+    opt(&mut root.thumbnail_image, f);
+
+    let stages = &mut root.processing_stages;
+    for c in stages.characters.values_mut() {
+        opt(&mut c.image, f);
+        asset_history(&mut c.history, f);
+    }
+    for p in stages.props.values_mut() {
+        opt(&mut p.image, f);
+        opt(&mut p.original_image, f);
+        asset_history(&mut p.history, f);
+    }
+    for s in stages.sets.values_mut() {
+        opt(&mut s.image, f);
+        asset_history(&mut s.history, f);
+    }
+    for e in stages.custom_collections.values_mut().flat_map(|c| c.entities.values_mut()) {
+        opt(&mut e.image, f);
+        asset_history(&mut e.history, f);
+    }
+
+    let trashed_scenes = root.trash.scenes.values_mut().map(|t| &mut t.scene);
+    for scene in root.scenes.values_mut().chain(trashed_scenes) {
+        scene_images(scene, f);
+    }
+    for trashed in root.trash.shots.values_mut().flat_map(|shots| shots.values_mut()) {
+        shot_images(&mut trashed.shot, f);
+    }
+}
+
+fn scene_images(scene: &mut Scene, f: &mut impl FnMut(&mut String)) {
+    opt(&mut scene.thumbnail, f);
+    for look in scene.character_looks.values_mut() {
+        opt(&mut look.image, f);
+        asset_history(&mut look.history, f);
+    }
+    for outfit in scene.character_outfits.values_mut() {
+        opt(&mut outfit.image, f);
+        asset_history(&mut outfit.history, f);
+    }
+    for look in scene.looks_with_outfit.values_mut() {
+        opt(&mut look.image, f);
+    }
+    for outfit in scene.outfits.values_mut() {
+        opt(&mut outfit.image, f);
+    }
+    for shot in scene.shots.values_mut() {
+        shot_images(shot, f);
+    }
+}
+
+fn shot_images(shot: &mut Shot, f: &mut impl FnMut(&mut String)) {
+    opt(&mut shot.image, f);
+    for entry in &mut shot.history {
+        f(&mut entry.image);
+    }
+    if let Some(known) = &mut shot.known_assets {
+        for c in known.characters.values_mut() {
+            opt(&mut c.looks_with_outfit_image, f);
+            opt(&mut c.looks_image, f);
+            opt(&mut c.outfit_image, f);
+            opt(&mut c.character_image, f);
+        }
+        for asset in known.sets.iter_mut().chain(&mut known.props) {
+            opt(&mut asset.image, f);
+        }
+    }
+}
+
+fn asset_history(history: &mut [AssetHistory], f: &mut impl FnMut(&mut String)) {
+    for entry in history {
+        f(&mut entry.image);
+    }
+}
+
+fn opt(image: &mut Option<String>, f: &mut impl FnMut(&mut String)) {
+    if let Some(image) = image {
+        f(image);
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn asset(id: &str, image: &str) -> UploadedAsset {
+        UploadedAsset {
+            id: id.to_string(),
+            image: image.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_unreferenced_assets() {
+        let mut root = StoryboardRoot::default();
+        let assets = [("a1", "https://cdn/a1.png"), ("a2", "data:a2"), ("a3", "data:a3"), ("a4", "x")];
+        for (id, image) in assets {
+            root.uploaded_assets.insert(id.to_string(), asset(id, image));
+        }
+
+        let mut prop = Prop::new("p1", "Gun");
+        prop.original_image = Some("https://cdn/a1.png".to_string());
+        root.processing_stages.props.insert("p1".to_string(), prop);
+
+        let mut shot = Shot::new("sh1", 1);
+        shot.history.push(ShotHistory::new("h1", "data:a2", ""));
+        let mut scene = Scene::new("s1", 1);
+        scene.shots.insert("sh1".to_string(), shot);
+        root.trash.scenes.insert(
+            "s1".to_string(),
+            TrashedScene {
+                scene,
+                ..Default::default()
+            },
+        );
+
+        // Referenced by ID rather than by image
+        root.thumbnail_image = Some("a4".to_string());
+
+        assert_eq!(unreferenced_assets(&root), vec!["a3"]);
+
+        assert_eq!(replace_image(&mut root, "data:a2", "blob:a2"), 1);
+        assert_eq!(unreferenced_assets(&root), vec!["a2", "a3"]);
+    }
+
+    #[cfg(feature = "blob-store")]
+    #[test]
+    fn test_dedup_and_inline_blobs() {
+        let url = "data:image/png;base64,aGVsbG8=";
+        let mut root = StoryboardRoot::default();
+        root.uploaded_assets.insert("a1".to_string(), asset("a1", url));
+        root.thumbnail_image = Some(url.to_string());
+        let original = root.clone();
+
+        let report = dedup_images(&mut root);
+        assert_eq!((report.fields, report.blobs), (2, 1));
+        assert_eq!(root.blobs.len(), 1);
+        assert_eq!(root.thumbnail_image.as_deref(), Some(root.uploaded_assets["a1"].image.as_str()));
+        assert_eq!(unreferenced_assets(&root), Vec::<String>::new());
+
+        // A second pass finds nothing; inlining restores the original
+        assert_eq!(dedup_images(&mut root).fields, 0);
+        assert_eq!(inline_blobs(&mut root), 2);
+        assert_eq!(root, original);
+    }
+}
@@ -0,0 +1,78 @@
+//! Character deletion with reference handling.
+//!
+//! `delete_characters()` removes only the character, leaving its tag in
+//! scene entity lists, looks, outfits and shots, and its ID in
+//! `characters_present`. `StoryboardManager::delete_characters_cascade()`
+//! takes a `DeletePolicy` saying what happens to those references. Prompt
+//! text mentioning the tag is never rewritten.
+
+use serde::{Deserialize, Serialize};
+
+use super::model::*;
+
+/// What `delete_characters_cascade()` does with references to the character.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DeletePolicy {
+    /// Fail if anything references the character, including prompt text.
+    Deny,
+    /// Remove the references: `characters_present`, scene `known_entities`
+    /// and shot `assets_used`, `assets`, `subject` and `known_assets`.
+    /// Scene looks and outfits for the tag are kept.
+    Detach,
+    /// Like `Detach`, and also delete the character's scene looks, outfits
+    /// and combined look/outfit images.
+    Cascade,
+}
+
+/// Returns the scenes listing `id` in `characters_present`.
+pub(crate) fn scenes_presenting<'a>(root: &'a StoryboardRoot, id: &str) -> Vec<&'a str> {
+    let mut scenes: Vec<&str> = root
+        .scenes
+        .iter()
+        .filter(|(_, scene)| scene.characters_present.iter().any(|c| c == id))
+        .map(|(scene_id, _)| scene_id.as_str())
+        .collect();
+    scenes.sort_unstable();
+    scenes
+}
+
+/// Removes references to the character with `id` and `tag` as `policy`
+/// says. `Deny` removes nothing.
+pub(crate) fn detach_character(
+    root: &mut StoryboardRoot,
+    id: &str,
+    tag: Option<&str>,
+    policy: DeletePolicy,
+) {
+    if policy == DeletePolicy::Deny {
+        return;
+    }
+    for scene in root.scenes.values_mut() {
+        scene.characters_present.retain(|c| c != id);
+        let Some(tag) = tag else {
+            continue;
+        };
+        if let Some(known) = &mut scene.known_entities {
+            known.characters.retain(|entity| entity.tag != tag);
+        }
+        if policy == DeletePolicy::Cascade {
+            scene.character_looks.remove(tag);
+            scene.character_outfits.remove(tag);
+            scene.looks_with_outfit.remove(tag);
+            scene.outfits.remove(tag);
+        }
+        for shot in scene.shots.values_mut() {
+            shot.assets_used.retain(|t| t != tag);
+            if let Some(assets) = &mut shot.assets {
+                assets.retain(|asset| asset.tag != tag);
+            }
+            if shot.subject.as_deref() == Some(tag) {
+                shot.subject = None;
+            }
+            if let Some(known) = &mut shot.known_assets {
+                known.characters.remove(tag);
+            }
+        }
+    }
+}
@@ -0,0 +1,189 @@
+//! Generic entity CRUD.
+//!
+//! Characters, props and sets share one set of operations, written once in
+//! `EntityStore<T>` and reached through `StoryboardManager::entities()`:
+//!
+//! ```ignore
+//! manager.entities::<Character>().create("c1", Character::new("c1", "Richie"))?;
+//! manager.entities::<Prop>().set_image("p1", Some("https://cdn/p1.png"))?;
+//! ```
+//!
+//! A type joins by implementing `StoryboardEntity`. The per-type methods on
+//! `StoryboardManager` (`create_characters()`, `set_props_image()`, ...) are
+//! thin wrappers over the store.
+
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::error::CollabResult;
+use crate::ordered_map::OrderedMap;
+
+use super::manager::StoryboardManager;
+use super::model::*;
+
+/// An entity kept in a `processing_stages` map with an order list.
+pub trait StoryboardEntity: Clone + Serialize + DeserializeOwned {
+    /// Key of the map under `processing_stages`, e.g. "characters".
+    const COLLECTION: &'static str;
+
+    /// Returns the entities with their order.
+    fn entities(stages: &ProcessingStages) -> &OrderedMap<Self>;
+
+    /// Returns the entities with their order, mutably.
+    fn entities_mut(stages: &mut ProcessingStages) -> &mut OrderedMap<Self>;
+}
+
+macro_rules! storyboard_entity {
+    ($entity:ty, $collection:ident) => {
+        impl StoryboardEntity for $entity {
+            const COLLECTION: &'static str = stringify!($collection);
+
+            fn entities(stages: &ProcessingStages) -> &OrderedMap<Self> {
+                &stages.$collection
+            }
+
+            fn entities_mut(stages: &mut ProcessingStages) -> &mut OrderedMap<Self> {
+                &mut stages.$collection
+            }
+        }
+    };
+}
+
+storyboard_entity!(Character, characters);
+storyboard_entity!(Prop, props);
+storyboard_entity!(SetLocation, sets);
+
+/// CRUD and O(1) field setters for one entity type, borrowed from a
+/// `StoryboardManager` by `entities()`.
+pub struct EntityStore<'a, T> {
+    manager: &'a mut StoryboardManager,
+    entity: PhantomData<T>,
+}
+
+impl<'a, T: StoryboardEntity> EntityStore<'a, T> {
+    pub(crate) fn new(manager: &'a mut StoryboardManager) -> Self {
+        Self {
+            manager,
+            entity: PhantomData,
+        }
+    }
+
+    /// Creates (or replaces) an entity and appends it to the order list.
+    pub fn create(&mut self, id: &str, entity: T) -> CollabResult<()> {
+        self.manager.update_state(|state| {
+            T::entities_mut(&mut state.processing_stages).insert(id, entity);
+        })
+    }
+
+    /// Gets an entity by ID.
+    pub fn get(&mut self, id: &str) -> CollabResult<Option<T>> {
+        let state = self.manager.get_state_ref()?;
+        Ok(T::entities(&state.processing_stages).get(id).cloned())
+    }
+
+    /// Returns the entities in order, skipping IDs with no entity.
+    pub fn list(&mut self) -> CollabResult<Vec<T>> {
+        let stages = &self.manager.get_state_ref()?.processing_stages;
+        Ok(T::entities(stages)
+            .iter_ordered()
+            .map(|(_, entity)| entity.clone())
+            .collect())
+    }
+
+    /// Deletes an entity and removes it from the order list.
+    pub fn delete(&mut self, id: &str) -> CollabResult<()> {
+        self.manager.update_state(|state| {
+            T::entities_mut(&mut state.processing_stages).remove(id);
+        })
+    }
+
+    /// Sets the name (O(1)).
+    pub fn set_name(&mut self, id: &str, name: &str) -> CollabResult<()> {
+        self.manager.set_entity_name(T::COLLECTION, id, name)
+    }
+
+    /// Sets the description (O(1)).
+    pub fn set_description(&mut self, id: &str, description: &str) -> CollabResult<()> {
+        self.manager.set_entity_description(T::COLLECTION, id, description)
+    }
+
+    /// Sets the tag (O(1)). Use `StoryboardManager::rename_tag()` to also
+    /// update references.
+    pub fn set_tag(&mut self, id: &str, tag: Option<&str>) -> CollabResult<()> {
+        self.manager.set_entity_tag(T::COLLECTION, id, tag)
+    }
+
+    /// Sets the image_prompt (O(1)).
+    pub fn set_image_prompt(&mut self, id: &str, prompt: &str) -> CollabResult<()> {
+        self.manager.set_entity_image_prompt(T::COLLECTION, id, prompt)
+    }
+
+    /// Sets the caption (O(1)).
+    pub fn set_caption(&mut self, id: &str, caption: Option<&str>) -> CollabResult<()> {
+        self.manager.set_entity_caption(T::COLLECTION, id, caption)
+    }
+
+    /// Sets the enhanced flag (O(1)).
+    pub fn set_enhanced(&mut self, id: &str, enhanced: bool) -> CollabResult<()> {
+        self.manager.set_entity_enhanced(T::COLLECTION, id, enhanced)
+    }
+
+    /// Sets the image field (O(1)).
+    pub fn set_image(&mut self, id: &str, image: Option<&str>) -> CollabResult<()> {
+        self.manager
+            .set_entity_field_opt_str(&Self::path(id), "image", image)
+    }
+
+    /// Sets the generation_status field (O(1)).
+    pub fn set_generation_status(&mut self, id: &str, status: Option<&str>) -> CollabResult<()> {
+        self.manager
+            .set_entity_field_opt_str(&Self::path(id), "generation_status", status)
+    }
+
+    /// Sets the description_status field (O(1)).
+    pub fn set_description_status(&mut self, id: &str, status: Option<&str>) -> CollabResult<()> {
+        self.manager
+            .set_entity_field_opt_str(&Self::path(id), "description_status", status)
+    }
+
+    /// Appends to history (maintains max 20 entries).
+    pub fn append_history(&mut self, id: &str, entry: AssetHistory) -> CollabResult<()> {
+        self.manager.append_to_asset_history(&Self::path(id), entry)
+    }
+
+    fn path(id: &str) -> [&str; 3] {
+        ["processing_stages", T::COLLECTION, id]
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entity_store_matches_wrappers() {
+        let mut manager = StoryboardManager::new();
+        let mut props = manager.entities::<Prop>();
+        props.create("p2", Prop::new("p2", "Lamp")).unwrap();
+        props.create("p1", Prop::new("p1", "Gun")).unwrap();
+        props.set_image("p1", Some("https://cdn/p1.png")).unwrap();
+        props.set_tag("p1", Some("@gun")).unwrap();
+
+        let names: Vec<String> = props.list().unwrap().into_iter().map(|p| p.name).collect();
+        assert_eq!(names, vec!["Lamp", "Gun"]);
+
+        let prop = manager.get_props("p1").unwrap().unwrap();
+        assert_eq!(prop.image.as_deref(), Some("https://cdn/p1.png"));
+        assert_eq!(prop.tag.as_deref(), Some("@gun"));
+
+        manager.entities::<Prop>().delete("p2").unwrap();
+        assert_eq!(manager.get_state().unwrap().processing_stages.props.order(), ["p1"]);
+        assert!(manager.entities::<Character>().get("p1").unwrap().is_none());
+    }
+}
@@ -4,84 +4,111 @@
 //! document and provides:
 //! - High-level operations via autosurgeon (hydrate/reconcile) for bulk updates
 //! - Targeted O(1) updates via direct put operations for high-frequency fields
-//! - Macro-generated CRUD for Character/Prop/Set with identical optimization paths
+//! - Generic CRUD for Character/Prop/Set via `EntityStore` (`entities::<T>()`)
+
+use std::collections::BTreeMap;
 
 use automerge::{
-    transaction::Transactable, AutoCommit, ChangeHash, ObjId, ReadDoc, ScalarValue, Value, ROOT,
+    sync::SyncDoc,
+    transaction::{CommitOptions, Transactable},
+    ActorId, AutoCommit, ChangeHash, ObjId, ObjType, ReadDoc, ScalarValue, Value, ROOT,
 };
-use autosurgeon::{hydrate, reconcile};
+use autosurgeon::{hydrate, hydrate_prop, reconcile, reconcile_insert, reconcile_prop, Reconcile};
 use paste::paste;
 
+use crate::changes::{collect_change_info, field_authors, ChangeInfo, FieldAuthor};
+use crate::bundle::{Bundle, BundleMetadata};
+use crate::checkpoint::{self, SyncCheckpoint};
+use crate::cherry_pick::{self, CherryPickReport};
+use crate::acl::{Acl, Action, Enforcement, PermissionContext, Role};
+use crate::chunk::{self, ChunkAssembler};
+use crate::conflict::{self, ConflictValue};
+use crate::compact::{self, HistoryStats};
+use crate::dirty;
+use crate::comment::{self, Comment};
+#[cfg(feature = "compression")]
+use crate::compression;
+#[cfg(feature = "crypto")]
+use crate::crypto::{self, KeyParams};
+use crate::envelope::{self, SaveOptions};
 use crate::error::{CollabError, CollabResult};
+use crate::link::{DocRef, DocResolver};
+use crate::order_key;
+use crate::ordered_map::OrderedMap;
+use crate::presence::{Awareness, Cursor, PeerPresence};
+#[cfg(feature = "blob-store")]
+use crate::blob::{self, BlobStore, DedupReport, ExternalizeReport};
+use crate::storyboard::assets;
+use crate::storyboard::cascade::{self, DeletePolicy};
+use crate::storyboard::entity_store::{EntityStore, StoryboardEntity};
 use crate::storyboard::model::*;
+use crate::storyboard::obj_cache::ObjCache;
+#[cfg(feature = "pdf-export")]
+use crate::storyboard::pdf::{self, PdfOptions};
+use crate::storyboard::presence::{PresenceMode, StoryboardPresence};
+use crate::storyboard::rename::{self, EntityUsages, TagRenameReport};
+use crate::storyboard::shot_list::{self, ShotListFormat};
+use crate::storyboard::validation;
+use crate::repair::{self, RepairReport};
+use crate::sanitize;
+#[cfg(feature = "script-import")]
+use crate::script;
+use crate::snapshot::NamedSnapshot;
+#[cfg(feature = "search")]
+use crate::search::SearchIndex;
+use crate::storage::{AttachedStore, DocumentStore};
+use crate::sync::{self, PeerState, PrioritizedFrames};
+use crate::validation::ValidationReport;
+use crate::sweep::{StalledItem, SweepConfig, SweepReport, PROCESSING_STATUS, STALLED_STATUS};
+
+/// Keys whose subtrees are sent last by prioritized sync.
+const HEAVY_SYNC_KEYS: &[&str] = &["history", "comments", "uploaded_assets"];
+
+/// Document type recorded in backup bundles.
+const BUNDLE_KIND: &str = "storyboard";
 
 // =============================================================================
-// ENTITY CRUD MACRO
+// ENTITY CRUD WRAPPERS
 // =============================================================================
 
-/// Generates CRUD methods for an entity type with consistent O(1) optimization.
-/// All setters follow identical optimization path: cache invalidate → get ObjId → put/delete
+/// Generates the per-type CRUD methods (`create_characters()`, ...) as thin
+/// wrappers over `EntityStore` (see `entities()`).
 macro_rules! entity_crud {
-    ($entity:ident, $collection:ident, $order:ident) => {
+    ($entity:ident, $collection:ident) => {
         paste! {
             /// Creates a new entity and appends it to the order list.
             pub fn [<create_ $collection:snake>](&mut self, id: &str, entity: $entity) -> CollabResult<()> {
-                self.update_state(|state| {
-                    let id_str = id.to_string();
-                    state.processing_stages.$collection.insert(id_str.clone(), entity);
-                    if !state.processing_stages.$order.contains(&id_str) {
-                        state.processing_stages.$order.push(id_str);
-                    }
-                })
+                self.entities::<$entity>().create(id, entity)
             }
 
             /// Gets an entity by ID.
             pub fn [<get_ $collection:snake>](&mut self, id: &str) -> CollabResult<Option<$entity>> {
-                let state = self.get_state()?;
-                Ok(state.processing_stages.$collection.get(id).cloned())
+                self.entities::<$entity>().get(id)
             }
 
             /// Deletes an entity by ID.
             pub fn [<delete_ $collection:snake>](&mut self, id: &str) -> CollabResult<()> {
-                self.update_state(|state| {
-                    state.processing_stages.$collection.remove(id);
-                    state.processing_stages.$order.retain(|s| s != id);
-                })
+                self.entities::<$entity>().delete(id)
             }
 
             /// Sets the image field (O(1) targeted update).
             pub fn [<set_ $collection:snake _image>](&mut self, id: &str, image: Option<&str>) -> CollabResult<()> {
-                self.set_entity_field_opt_str(
-                    &["processing_stages", stringify!($collection), id],
-                    "image",
-                    image,
-                )
+                self.entities::<$entity>().set_image(id, image)
             }
 
             /// Sets the generation_status field (O(1) targeted update).
             pub fn [<set_ $collection:snake _generation_status>](&mut self, id: &str, status: Option<&str>) -> CollabResult<()> {
-                self.set_entity_field_opt_str(
-                    &["processing_stages", stringify!($collection), id],
-                    "generation_status",
-                    status,
-                )
+                self.entities::<$entity>().set_generation_status(id, status)
             }
 
             /// Sets the description_status field (O(1) targeted update).
             pub fn [<set_ $collection:snake _description_status>](&mut self, id: &str, status: Option<&str>) -> CollabResult<()> {
-                self.set_entity_field_opt_str(
-                    &["processing_stages", stringify!($collection), id],
-                    "description_status",
-                    status,
-                )
+                self.entities::<$entity>().set_description_status(id, status)
             }
 
             /// Appends to history (maintains max 20 entries).
             pub fn [<append_ $collection:snake _history>](&mut self, id: &str, entry: AssetHistory) -> CollabResult<()> {
-                self.append_to_asset_history(
-                    &["processing_stages", stringify!($collection), id],
-                    entry,
-                )
+                self.entities::<$entity>().append_history(id, entry)
             }
         }
     };
@@ -96,11 +123,31 @@ macro_rules! entity_crud {
 /// Uses a hybrid approach:
 /// - `update_state()` for bulk struct operations (uses hydrate/reconcile)
 /// - `set_*_image()`, `set_*_generation_status()` for targeted O(1) updates
-/// - `entity_crud!` macro generates consistent CRUD for Character/Prop/Set
+/// - `entities::<T>()` gives uniform CRUD for Character/Prop/Set
 pub struct StoryboardManager {
     doc: AutoCommit,
     /// Cached hydrated state - invalidated after direct document mutations.
     cached_state: Option<StoryboardRoot>,
+    /// Ephemeral peer presence (not part of the document).
+    awareness: Awareness<StoryboardPresence>,
+    /// Store that receives every committed change (see `attach_store()`).
+    store: Option<AttachedStore>,
+    /// Partially received sync chunks (see `apply_sync_chunk()`).
+    chunks: ChunkAssembler,
+    /// ObjIds resolved by targeted setters, keyed by path. Cleared whenever
+    /// remote changes are applied.
+    obj_cache: ObjCache,
+    /// Local user and ACL enforcement mode (see `crate::acl`).
+    permissions: PermissionContext,
+    /// Denials recorded in warn mode (see `take_permission_warnings()`).
+    permission_warnings: Vec<String>,
+    /// Allows edits while the document is frozen (see `set_admin()`).
+    admin: bool,
+    /// Renumbers scenes and shots on reorder (see `set_auto_renumber()`).
+    auto_renumber: bool,
+    /// Local full-text index over scenes and shots (see `search()`).
+    #[cfg(feature = "search")]
+    search_index: SearchIndex,
 }
 
 impl StoryboardManager {
@@ -113,26 +160,255 @@ impl StoryboardManager {
         let mut doc = AutoCommit::new();
         let root = StoryboardRoot::default();
         reconcile(&mut doc, &root).expect("Failed to initialize document");
+        let awareness = Awareness::new(doc.get_actor().to_hex_string());
         Self {
             doc,
             cached_state: Some(root),
+            awareness,
+            store: None,
+            chunks: ChunkAssembler::new(),
+            obj_cache: ObjCache::default(),
+            permissions: PermissionContext::default(),
+            permission_warnings: Vec::new(),
+            admin: false,
+            auto_renumber: false,
+            #[cfg(feature = "search")]
+            search_index: SearchIndex::new(),
         }
     }
 
+    /// Creates a new StoryboardManager pre-populated from a seed root.
+    ///
+    /// The schema and all seed content (characters, scenes, shots...) are
+    /// written as a single change.
+    pub fn new_with_seed(seed: StoryboardRoot) -> CollabResult<Self> {
+        let mut doc = AutoCommit::new();
+        reconcile(&mut doc, &seed)?;
+        doc.commit_with(CommitOptions::default().with_message("Initialize document"));
+        let awareness = Awareness::new(doc.get_actor().to_hex_string());
+        Ok(Self {
+            doc,
+            cached_state: Some(seed),
+            awareness,
+            store: None,
+            chunks: ChunkAssembler::new(),
+            obj_cache: ObjCache::default(),
+            permissions: PermissionContext::default(),
+            permission_warnings: Vec::new(),
+            admin: false,
+            auto_renumber: false,
+            #[cfg(feature = "search")]
+            search_index: SearchIndex::new(),
+        })
+    }
+
     /// Creates a StoryboardManager from saved binary data.
+    ///
+    /// Reads `save_enveloped()` output as well as bare bytes (see
+    /// `crate::envelope`). With the `compression` feature, gzip output of
+    /// `save_compressed()` is detected and decompressed transparently.
     pub fn from_bytes(bytes: &[u8]) -> CollabResult<Self> {
-        let doc = AutoCommit::load(bytes)?;
+        let doc = AutoCommit::load(&envelope::decode(bytes)?)?;
+        let awareness = Awareness::new(doc.get_actor().to_hex_string());
         Ok(Self {
             doc,
             cached_state: None,
+            awareness,
+            store: None,
+            chunks: ChunkAssembler::new(),
+            obj_cache: ObjCache::default(),
+            permissions: PermissionContext::default(),
+            permission_warnings: Vec::new(),
+            admin: false,
+            auto_renumber: false,
+            #[cfg(feature = "search")]
+            search_index: SearchIndex::new(),
         })
     }
 
+    /// Loads a document saved with `save_encrypted()` or with
+    /// `save_enveloped()` and key params.
+    #[cfg(feature = "crypto")]
+    pub fn from_encrypted_bytes(bytes: &[u8], key_params: &KeyParams) -> CollabResult<Self> {
+        Self::from_bytes(&envelope::decrypt(bytes, key_params)?)
+    }
+
     /// Saves the document to binary format.
     pub fn save(&mut self) -> Vec<u8> {
         self.doc.save()
     }
 
+    /// Saves only the changes made since the last `save()` or
+    /// `save_incremental()`. Returns an empty Vec if nothing changed.
+    ///
+    /// Append the result to a stored snapshot (or pass it to
+    /// `load_incremental()`); autosave loops upload kilobytes instead of the
+    /// whole document.
+    ///
+    /// A document opened with `from_bytes()` has not been saved yet, so the
+    /// first call after loading returns its whole history. Call
+    /// `save_incremental()` once right after loading and discard the result
+    /// to start from the loaded state.
+    pub fn save_incremental(&mut self) -> Vec<u8> {
+        self.doc.save_incremental()
+    }
+
+    /// Saves the changes not covered by `heads` (e.g. the heads recorded at
+    /// the last upload). Returns an empty Vec if there are none.
+    pub fn save_since(&mut self, heads: &[ChangeHash]) -> Vec<u8> {
+        self.generate_sync_message(heads).unwrap_or_default()
+    }
+
+    /// Returns the changes not covered by `checkpoint` (see
+    /// `crate::checkpoint`). Returns an empty Vec if there are none.
+    pub fn changes_since_checkpoint(&mut self, checkpoint: &SyncCheckpoint) -> Vec<u8> {
+        let heads = checkpoint::known_heads(&mut self.doc, checkpoint);
+        self.save_since(&heads)
+    }
+
+    /// Returns a checkpoint at the current heads. Take it right before
+    /// sending `changes_since_checkpoint()` and keep it once the upload is
+    /// acknowledged.
+    pub fn mark_synced(&mut self) -> SyncCheckpoint {
+        SyncCheckpoint::from_heads(&self.get_heads())
+    }
+
+    /// Loads changes produced by `save_incremental()` / `save_since()` into
+    /// this document. Returns the number of operations applied.
+    pub fn load_incremental(&mut self, data: &[u8]) -> CollabResult<usize> {
+        self.cached_state = None;
+        self.obj_cache.clear();
+        let ops = self.doc.load_incremental(data)?;
+        self.flush_store()?;
+        Ok(ops)
+    }
+
+    /// Returns the size of the change history (see `compact_if()`).
+    pub fn history_stats(&mut self) -> HistoryStats {
+        compact::history_stats(&mut self.doc)
+    }
+
+    /// Saves the current state as a fresh document with a single change and
+    /// a new actor, dropping the change history (see `crate::compact`).
+    ///
+    /// The result is a new baseline: peers must reload it instead of syncing.
+    /// This manager keeps its full history.
+    pub fn save_compact(&mut self) -> CollabResult<Vec<u8>> {
+        let state = self.get_state_ref()?;
+        compact::compact_state(state)
+    }
+
+    /// Runs `save_compact()` if `policy` approves the current history stats.
+    ///
+    /// # Example
+    /// ```ignore
+    /// if let Some(bytes) = manager.compact_if(|stats| stats.changes > 10_000)? {
+    ///     store.replace_snapshot(&bytes)?;
+    /// }
+    /// ```
+    pub fn compact_if<F>(&mut self, policy: F) -> CollabResult<Option<Vec<u8>>>
+    where
+        F: FnOnce(&HistoryStats) -> bool,
+    {
+        if policy(&self.history_stats()) {
+            self.save_compact().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Saves the current state as a fresh document like `save_compact()`,
+    /// with every string last written by one of `remove_actors` replaced by
+    /// `[redacted]` (see `crate::sanitize`). Use it to honour erasure
+    /// requests; the result must replace every stored copy of the document.
+    pub fn export_sanitized(&self, remove_actors: &[ActorId]) -> CollabResult<Vec<u8>> {
+        sanitize::export_sanitized(&self.doc, remove_actors)
+    }
+
+    /// Packs the snapshot, the full change history, `checkpoints` and
+    /// metadata (schema version, `doc_id`) into one backup archive (see
+    /// `crate::bundle`).
+    pub fn export_bundle(
+        &mut self,
+        doc_id: &str,
+        checkpoints: &BTreeMap<String, SyncCheckpoint>,
+    ) -> CollabResult<Vec<u8>> {
+        Bundle::from_doc(&mut self.doc, BUNDLE_KIND, doc_id, checkpoints).encode()
+    }
+
+    /// Restores a manager from `export_bundle()` output. Returns the bundle
+    /// metadata with it, including the checkpoints to resume uploads from.
+    pub fn import_bundle(bytes: &[u8]) -> CollabResult<(Self, BundleMetadata)> {
+        let bundle = Bundle::decode(bytes, BUNDLE_KIND)?;
+        let mut manager = Self::from_bytes(&bundle.snapshot)?;
+        manager.load_incremental(&bundle.changes)?;
+        bundle.check_heads(&manager.get_heads())?;
+        Ok((manager, bundle.metadata))
+    }
+
+    /// Attaches a store under `doc_id` and writes a snapshot now (see
+    /// `crate::storage`). Replaces any store attached before.
+    ///
+    /// Changes committed through `update_state_with_message()`, `sweep()`,
+    /// `repair()` and the other single-change operations are appended as
+    /// they commit. `update_state()` and direct setters leave their
+    /// operations pending; call `flush_store()` to append them. If a store
+    /// write fails, the committing call returns the error but the change
+    /// stays in the document, and the next flush retries it.
+    pub fn attach_store(&mut self, doc_id: &str, store: Box<dyn DocumentStore>) -> CollabResult<()> {
+        self.store = Some(AttachedStore::new(store, doc_id, &mut self.doc)?);
+        Ok(())
+    }
+
+    /// Detaches and returns the attached store, if any.
+    pub fn detach_store(&mut self) -> Option<Box<dyn DocumentStore>> {
+        self.store.take().map(AttachedStore::into_store)
+    }
+
+    /// Appends all changes not yet in the attached store, committing any
+    /// pending operations first (see `attach_store()` for which calls do this
+    /// automatically). Returns the number of changes written (0 without a
+    /// store).
+    pub fn flush_store(&mut self) -> CollabResult<usize> {
+        match self.store.as_mut() {
+            Some(store) => store.persist(&mut self.doc),
+            None => Ok(0),
+        }
+    }
+
+    /// Replaces the stored snapshot with the current document and clears the
+    /// change log. Does nothing without a store.
+    pub fn snapshot_store(&mut self) -> CollabResult<()> {
+        if let Some(store) = self.store.as_mut() {
+            store.snapshot(&mut self.doc)?;
+        }
+        Ok(())
+    }
+
+    /// Saves the document as gzip-compressed binary (`level` 0-9).
+    ///
+    /// `from_bytes()` detects and decompresses the result transparently.
+    #[cfg(feature = "compression")]
+    pub fn save_compressed(&mut self, level: u32) -> Vec<u8> {
+        compression::compress(&self.save(), level)
+    }
+
+    /// Saves the document encrypted with AES-256-GCM (see `crate::crypto`).
+    ///
+    /// Use for snapshots kept on untrusted storage; load them with
+    /// `from_encrypted_bytes()` and the same key params.
+    #[cfg(feature = "crypto")]
+    pub fn save_encrypted(&mut self, key_params: &KeyParams) -> CollabResult<Vec<u8>> {
+        crypto::encrypt(&self.save(), key_params)
+    }
+
+    /// Saves the document in the versioned envelope (see `crate::envelope`),
+    /// optionally compressed and encrypted. `from_bytes()` (or
+    /// `from_encrypted_bytes()`) reads the result.
+    pub fn save_enveloped(&mut self, options: &SaveOptions) -> CollabResult<Vec<u8>> {
+        envelope::encode(&self.save(), options)
+    }
+
     /// Returns the current heads (for sync protocol).
     pub fn get_heads(&mut self) -> Vec<ChangeHash> {
         self.doc.get_heads()
@@ -143,46 +419,302 @@ impl StoryboardManager {
         self.doc.get_actor().to_hex_string()
     }
 
+    /// Returns the underlying Automerge document for custom queries.
+    pub fn as_automerge(&self) -> &AutoCommit {
+        &self.doc
+    }
+
+    /// Returns the underlying Automerge document for low-level operations.
+    ///
+    /// Cached state is dropped, so edits made through the returned document
+    /// are seen by later reads. Call `flush_store()` afterwards if a store is
+    /// attached. Writes must keep the document schema intact.
+    pub fn as_automerge_mut(&mut self) -> &mut AutoCommit {
+        self.cached_state = None;
+        self.obj_cache.clear();
+        &mut self.doc
+    }
+
+    /// Consumes the manager and returns the underlying Automerge document
+    /// (e.g. to hand it to automerge-repo storage). Pending operations are
+    /// committed first; presence and any attached store are dropped.
+    pub fn into_automerge(mut self) -> AutoCommit {
+        self.doc.commit();
+        self.doc
+    }
+
     // =========================================================================
     // HIGH-LEVEL OPERATIONS (via Hydrate/Reconcile)
     // =========================================================================
 
     /// Hydrates the entire document state to Rust structs.
     pub fn get_state(&mut self) -> CollabResult<StoryboardRoot> {
-        if let Some(ref cached) = self.cached_state {
-            return Ok(cached.clone());
+        self.get_state_ref().cloned()
+    }
+
+    /// Borrows the hydrated state instead of cloning it, hydrating first if
+    /// the cache is cold.
+    pub fn get_state_ref(&mut self) -> CollabResult<&StoryboardRoot> {
+        if self.cached_state.is_none() {
+            self.cached_state = Some(hydrate(&self.doc)?);
+        }
+        Ok(self.cached_state.as_ref().expect("state just cached"))
+    }
+
+    /// Runs `f` with a borrowed state and returns its result.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let count = manager.with_state(|state| state.scenes.len())?;
+    /// ```
+    pub fn with_state<F, R>(&mut self, f: F) -> CollabResult<R>
+    where
+        F: FnOnce(&StoryboardRoot) -> R,
+    {
+        Ok(f(self.get_state_ref()?))
+    }
+
+    /// Searches scene titles, headers, content and synopses, and shot
+    /// descriptions and prompts. Returns `(path, score)` for entries
+    /// containing every word of `query`, best match first, where `path` is
+    /// `[scene_id]` or `[scene_id, shot_id]`. Only scenes and shots changed
+    /// since the last search are re-indexed.
+    #[cfg(feature = "search")]
+    pub fn search(&mut self, query: &str) -> CollabResult<Vec<(Vec<String>, f64)>> {
+        // Shot keys join the scene and shot IDs with a separator that cannot
+        // appear in either
+        const SEPARATOR: char = '\0';
+        self.get_state_ref()?;
+        let state = self.cached_state.as_ref().expect("get_state_ref() fills the cache");
+        let mut entries = Vec::new();
+        for (scene_id, scene) in &state.scenes {
+            let text = [
+                scene.title.as_str(),
+                scene.header.as_str(),
+                scene.content.as_str(),
+                scene.synopsis.as_deref().unwrap_or_default(),
+            ]
+            .join("\n");
+            entries.push((scene_id.clone(), text));
+            for (shot_id, shot) in &scene.shots {
+                let text = [
+                    shot.title.as_deref().unwrap_or_default(),
+                    shot.visual_description.as_str(),
+                    shot.image_prompt.as_str(),
+                    shot.action.as_deref().unwrap_or_default(),
+                ]
+                .join("\n");
+                entries.push((format!("{}{}{}", scene_id, SEPARATOR, shot_id), text));
+            }
         }
-        let state: StoryboardRoot = hydrate(&self.doc)?;
-        self.cached_state = Some(state.clone());
-        Ok(state)
+        self.search_index.sync(entries);
+        Ok(self
+            .search_index
+            .search(query)
+            .into_iter()
+            .map(|(key, score)| (key.split(SEPARATOR).map(str::to_string).collect(), score))
+            .collect())
     }
 
     /// Applies a function to mutate the state, then reconciles back to the document.
+    ///
+    /// Only the fields, scenes and entities the closure changed are written back.
     pub fn update_state<F>(&mut self, f: F) -> CollabResult<()>
+    where
+        F: FnOnce(&mut StoryboardRoot),
+    {
+        self.check_permission(Action::Edit)?;
+        self.write_state(f)
+    }
+
+    /// `update_state()` without the permission check, for callers that
+    /// check a different action.
+    fn write_state<F>(&mut self, f: F) -> CollabResult<()>
     where
         F: FnOnce(&mut StoryboardRoot),
     {
         let mut state = self.get_state()?;
         f(&mut state);
-        reconcile(&mut self.doc, &state)?;
+        let old = self.cached_state.as_ref().expect("get_state() fills the cache");
+        self.obj_cache.invalidate_removed(old, &state);
+        dirty::reconcile_storyboard(&mut self.doc, old, &state)?;
         self.cached_state = Some(state);
         Ok(())
     }
 
+    /// Like `update_state()`, but records the result as its own change with a
+    /// commit message and timestamp (milliseconds since epoch).
+    pub fn update_state_with_message<F>(
+        &mut self,
+        message: &str,
+        timestamp: i64,
+        f: F,
+    ) -> CollabResult<()>
+    where
+        F: FnOnce(&mut StoryboardRoot),
+    {
+        let options = CommitOptions::default()
+            .with_message(message)
+            .with_time(timestamp);
+        self.commit_as_change(options, |manager| manager.update_state(f))
+    }
+
+    /// Like `update_state()`, but records the result as its own change with
+    /// a commit message, rolling back on failure.
+    fn update_state_as_change<F>(&mut self, message: &str, f: F) -> CollabResult<()>
+    where
+        F: FnOnce(&mut StoryboardRoot),
+    {
+        self.transaction(Some(message), |manager| manager.update_state(f))
+    }
+
+    /// Runs several operations and records them as a single Automerge change.
+    ///
+    /// Any pending operations are committed first so the change only contains
+    /// the work done inside `f`. If `f` returns an error, its operations are
+    /// rolled back and the document is left as it was before the call.
+    ///
+    /// # Example
+    /// ```rust
+    /// use heyocollab::storyboard::{Scene, StoryboardManager};
+    ///
+    /// let mut manager = StoryboardManager::new();
+    /// manager.transaction(Some("Add scenes"), |txn| {
+    ///     txn.create_scene("scene-1", Scene::new("scene-1", 1))?;
+    ///     txn.create_scene("scene-2", Scene::new("scene-2", 2))
+    /// }).unwrap();
+    /// ```
+    pub fn transaction<F, T>(&mut self, message: Option<&str>, f: F) -> CollabResult<T>
+    where
+        F: FnOnce(&mut Self) -> CollabResult<T>,
+    {
+        let options = match message {
+            Some(msg) => CommitOptions::default().with_message(msg),
+            None => CommitOptions::default(),
+        };
+        self.commit_as_change(options, f)
+    }
+
+    /// Commits pending operations, runs `f` and commits its operations with
+    /// `options`, or rolls them back (dropping caches) if it fails.
+    fn commit_as_change<F, T>(&mut self, options: CommitOptions, f: F) -> CollabResult<T>
+    where
+        F: FnOnce(&mut Self) -> CollabResult<T>,
+    {
+        self.doc.commit();
+        match f(self) {
+            Ok(value) => {
+                self.doc.commit_with(options);
+                self.flush_store()?;
+                Ok(value)
+            }
+            Err(e) => {
+                // Rolled-back ops may have created objects
+                self.cached_state = None;
+                self.obj_cache.clear();
+                self.doc.rollback();
+                Err(e)
+            }
+        }
+    }
+
+    /// Returns metadata (hash, actor, message, timestamp) for every change.
+    pub fn get_change_history(&mut self) -> Vec<ChangeInfo> {
+        collect_change_info(&mut self.doc)
+    }
+
+    /// Registers a display name for a hex actor ID.
+    pub fn set_actor_name(&mut self, actor: &str, name: &str) -> CollabResult<()> {
+        self.update_state(|state| {
+            state.actor_names.insert(actor.to_string(), name.to_string());
+        })
+    }
+
+    /// Returns the registered display names keyed by hex actor ID.
+    pub fn get_actor_names(&mut self) -> CollabResult<std::collections::HashMap<String, String>> {
+        Ok(self.get_state_ref()?.actor_names.clone())
+    }
+
+    /// Returns who last changed each field of a shot, keyed by field name.
+    pub fn get_shot_authors(
+        &mut self,
+        scene_id: &str,
+        shot_id: &str,
+    ) -> CollabResult<BTreeMap<String, FieldAuthor>> {
+        let names = self.get_state_ref()?.actor_names.clone();
+        let shot_obj = self.get_shot_obj(scene_id, shot_id)?;
+        field_authors(&mut self.doc, &shot_obj, &names)
+    }
+
+    /// Returns every concurrent value of a shot field with the actor that
+    /// wrote it, winner first.
+    pub fn get_shot_conflicts(
+        &mut self,
+        scene_id: &str,
+        shot_id: &str,
+        field: &str,
+    ) -> CollabResult<Vec<ConflictValue>> {
+        let shot_obj = self.get_shot_obj(scene_id, shot_id)?;
+        conflict::get_conflicts(&self.doc, &shot_obj, field)
+    }
+
+    /// Keeps the value `chosen_actor` wrote to a shot field, replacing the
+    /// concurrent ones.
+    pub fn resolve_shot_conflict(
+        &mut self,
+        scene_id: &str,
+        shot_id: &str,
+        field: &str,
+        chosen_actor: &str,
+    ) -> CollabResult<()> {
+        self.begin_edit(Action::Edit)?;
+        let shot_obj = self.get_shot_obj(scene_id, shot_id)?;
+        // Object values are re-created, so drop cached paths below the shot
+        self.obj_cache.invalidate(&["scenes", scene_id, "shots", shot_id]);
+        conflict::resolve_conflict(&mut self.doc, &shot_obj, field, chosen_actor)
+    }
+
+    /// Returns every concurrent value of an entity field ("characters",
+    /// "props" or "sets") with the actor that wrote it, winner first.
+    pub fn get_entity_conflicts(
+        &mut self,
+        entity_type: &str,
+        id: &str,
+        field: &str,
+    ) -> CollabResult<Vec<ConflictValue>> {
+        let obj = self.get_obj_at_path(&["processing_stages", entity_type, id])?;
+        conflict::get_conflicts(&self.doc, &obj, field)
+    }
+
+    /// Keeps the value `chosen_actor` wrote to an entity field, replacing
+    /// the concurrent ones.
+    pub fn resolve_entity_conflict(
+        &mut self,
+        entity_type: &str,
+        id: &str,
+        field: &str,
+        chosen_actor: &str,
+    ) -> CollabResult<()> {
+        self.begin_edit(Action::Edit)?;
+        let obj = self.get_obj_at_path(&["processing_stages", entity_type, id])?;
+        self.obj_cache.invalidate(&["processing_stages", entity_type, id]);
+        conflict::resolve_conflict(&mut self.doc, &obj, field, chosen_actor)
+    }
+
     // =========================================================================
     // ROOT METADATA OPERATIONS
     // =========================================================================
 
     /// Sets the storyboard title (O(1)).
     pub fn set_title(&mut self, title: &str) -> CollabResult<()> {
-        self.cached_state = None;
+        self.begin_edit(Action::Edit)?;
         self.doc.put(&ROOT, "title", ScalarValue::Str(title.into()))?;
         Ok(())
     }
 
     /// Sets the storyboard description (O(1)).
     pub fn set_description(&mut self, description: &str) -> CollabResult<()> {
-        self.cached_state = None;
+        self.begin_edit(Action::Edit)?;
         self.doc
             .put(&ROOT, "description", ScalarValue::Str(description.into()))?;
         Ok(())
@@ -190,7 +722,7 @@ impl StoryboardManager {
 
     /// Sets the storyboard status (O(1)).
     pub fn set_status(&mut self, status: &str) -> CollabResult<()> {
-        self.cached_state = None;
+        self.begin_edit(Action::Edit)?;
         self.doc
             .put(&ROOT, "status", ScalarValue::Str(status.into()))?;
         Ok(())
@@ -198,47 +730,300 @@ impl StoryboardManager {
 
     /// Sets the current processing stage (O(1)).
     pub fn set_current_stage(&mut self, stage: &str) -> CollabResult<()> {
-        self.cached_state = None;
+        self.begin_edit(Action::Edit)?;
         self.doc
             .put(&ROOT, "current_stage", ScalarValue::Str(stage.into()))?;
         Ok(())
     }
 
+    /// Sets the storyboard thumbnail (O(1)). `None` clears it.
+    pub fn set_thumbnail(&mut self, url: Option<&str>) -> CollabResult<()> {
+        self.begin_edit(Action::Edit)?;
+        match url {
+            Some(v) => self.doc.put(&ROOT, "thumbnail_image", ScalarValue::Str(v.into()))?,
+            None => { self.doc.delete(&ROOT, "thumbnail_image")?; }
+        }
+        Ok(())
+    }
+
     /// Updates the last_updated timestamp (O(1)).
     pub fn touch_last_updated(&mut self, timestamp: i64) -> CollabResult<()> {
-        self.cached_state = None;
+        self.begin_edit(Action::Edit)?;
         self.doc
             .put(&ROOT, "last_updated", ScalarValue::Int(timestamp))?;
         Ok(())
     }
 
     // =========================================================================
-    // ENTITY CRUD (Macro-generated)
+    // ENTITY CRUD
+    // =========================================================================
+
+    /// Returns the CRUD operations for one entity type, e.g.
+    /// `manager.entities::<Character>().create(id, character)`.
+    pub fn entities<T: StoryboardEntity>(&mut self) -> EntityStore<'_, T> {
+        EntityStore::new(self)
+    }
+
+    entity_crud!(Character, characters);
+    entity_crud!(Prop, props);
+    entity_crud!(SetLocation, sets);
+
+    /// Deletes a character and handles references to it according to
+    /// `policy` (see `storyboard::cascade`), as one change. With
+    /// `DeletePolicy::Deny` this fails if the character is referenced.
+    pub fn delete_characters_cascade(&mut self, id: &str, policy: DeletePolicy) -> CollabResult<()> {
+        let state = self.get_state_ref()?;
+        let tag = state
+            .processing_stages
+            .characters
+            .get(id)
+            .ok_or_else(|| CollabError::field_not_found(id))?
+            .tag
+            .clone();
+        if policy == DeletePolicy::Deny {
+            let usages = tag
+                .as_deref()
+                .map(|tag| rename::find_usages(state, tag))
+                .unwrap_or_default();
+            let mut scenes = cascade::scenes_presenting(state, id);
+            scenes.extend(usages.scenes.iter().map(String::as_str));
+            scenes.sort_unstable();
+            scenes.dedup();
+            if !scenes.is_empty() || !usages.is_empty() {
+                return Err(CollabError::schema_violation(format!(
+                    "character '{}' is referenced by {} scene(s) and {} shot(s)",
+                    id,
+                    scenes.len(),
+                    usages.shots.len()
+                )));
+            }
+        }
+
+        self.update_state_as_change(&format!("Delete character {}", id), |state| {
+            cascade::detach_character(state, id, tag.as_deref(), policy);
+            state.processing_stages.characters.remove(id);
+        })
+    }
+
+    // =========================================================================
+    // CUSTOM COLLECTIONS
     // =========================================================================
 
-    entity_crud!(Character, characters, character_order);
-    entity_crud!(Prop, props, prop_order);
-    entity_crud!(SetLocation, sets, set_order);
+    /// Creates an entity in a user-defined collection (created on first use)
+    /// and appends it to the collection's order list.
+    pub fn create_custom_entity(
+        &mut self,
+        collection: &str,
+        id: &str,
+        entity: CustomEntity,
+    ) -> CollabResult<()> {
+        if collection.trim().is_empty() {
+            return Err(CollabError::schema_violation("collection name must not be empty"));
+        }
+        self.update_state(|state| {
+            let collection = state
+                .processing_stages
+                .custom_collections
+                .entry(collection.to_string())
+                .or_default();
+            let id_str = id.to_string();
+            collection.entities.insert(id_str.clone(), entity);
+            if !collection.order.contains(&id_str) {
+                collection.order.push(id_str);
+            }
+        })
+    }
+
+    /// Gets an entity from a user-defined collection.
+    pub fn get_custom_entity(&mut self, collection: &str, id: &str) -> CollabResult<Option<CustomEntity>> {
+        let state = self.get_state_ref()?;
+        Ok(state
+            .processing_stages
+            .custom_collections
+            .get(collection)
+            .and_then(|c| c.entities.get(id))
+            .cloned())
+    }
+
+    /// Returns a user-defined collection.
+    pub fn get_custom_collection(&mut self, collection: &str) -> CollabResult<Option<EntityCollection>> {
+        let state = self.get_state_ref()?;
+        Ok(state.processing_stages.custom_collections.get(collection).cloned())
+    }
+
+    /// Returns the names of the user-defined collections, sorted.
+    pub fn get_custom_collections(&mut self) -> CollabResult<Vec<String>> {
+        let state = self.get_state_ref()?;
+        let mut names: Vec<String> = state.processing_stages.custom_collections.keys().cloned().collect();
+        names.sort_unstable();
+        Ok(names)
+    }
+
+    /// Deletes an entity from a user-defined collection. The (possibly
+    /// empty) collection is kept.
+    pub fn delete_custom_entity(&mut self, collection: &str, id: &str) -> CollabResult<()> {
+        self.update_state(|state| {
+            if let Some(collection) = state.processing_stages.custom_collections.get_mut(collection) {
+                collection.entities.remove(id);
+                collection.order.retain(|s| s != id);
+            }
+        })
+    }
+
+    /// Deletes a user-defined collection with all its entities.
+    pub fn delete_custom_collection(&mut self, collection: &str) -> CollabResult<()> {
+        self.update_state(|state| {
+            state.processing_stages.custom_collections.remove(collection);
+        })
+    }
+
+    /// Replaces the order list of a user-defined collection.
+    pub fn reorder_custom_entities(&mut self, collection: &str, new_order: Vec<String>) -> CollabResult<()> {
+        if !self.get_state_ref()?.processing_stages.custom_collections.contains_key(collection) {
+            return Err(CollabError::field_not_found(collection));
+        }
+        self.update_state(|state| {
+            if let Some(collection) = state.processing_stages.custom_collections.get_mut(collection) {
+                collection.order = new_order;
+            }
+        })
+    }
+
+    /// Sets a custom entity's image field (O(1) targeted update).
+    pub fn set_custom_entity_image(
+        &mut self,
+        collection: &str,
+        id: &str,
+        image: Option<&str>,
+    ) -> CollabResult<()> {
+        self.set_entity_field_opt_str(
+            &["processing_stages", "custom_collections", collection, "entities", id],
+            "image",
+            image,
+        )
+    }
+
+    /// Sets a custom entity's generation_status field (O(1) targeted update).
+    pub fn set_custom_entity_generation_status(
+        &mut self,
+        collection: &str,
+        id: &str,
+        status: Option<&str>,
+    ) -> CollabResult<()> {
+        self.set_entity_field_opt_str(
+            &["processing_stages", "custom_collections", collection, "entities", id],
+            "generation_status",
+            status,
+        )
+    }
+
+    /// Appends to a custom entity's history (maintains max 20 entries).
+    pub fn append_custom_entity_history(
+        &mut self,
+        collection: &str,
+        id: &str,
+        entry: AssetHistory,
+    ) -> CollabResult<()> {
+        self.append_to_asset_history(
+            &["processing_stages", "custom_collections", collection, "entities", id],
+            entry,
+        )
+    }
 
     // =========================================================================
     // SCENE OPERATIONS
     // =========================================================================
 
-    /// Creates a new scene and appends it to the order list.
+    /// Creates a new scene and appends it to the order list. Replacing a
+    /// scene keeps its position.
     pub fn create_scene(&mut self, id: &str, scene: Scene) -> CollabResult<()> {
         self.update_state(|state| {
             let id_str = id.to_string();
-            state.scenes.insert(id_str.clone(), scene);
+            let old = state.scenes.insert(id_str.clone(), scene);
             if !state.scene_order.contains(&id_str) {
                 state.scene_order.push(id_str);
             }
+            if let (Some(old), Some(scene)) = (old, state.scenes.get_mut(id)) {
+                if scene.order_key.is_none() {
+                    scene.order_key = old.order_key;
+                }
+            }
+            state.fill_scene_keys();
         })
     }
 
     /// Gets a scene by ID.
     pub fn get_scene(&mut self, id: &str) -> CollabResult<Option<Scene>> {
-        let state = self.get_state()?;
-        Ok(state.scenes.get(id).cloned())
+        self.hydrate_scene(id)
+    }
+
+    /// Hydrates a single scene (with its shots) without the rest of the
+    /// document. Uses the cached state when it is warm.
+    pub fn hydrate_scene(&mut self, scene_id: &str) -> CollabResult<Option<Scene>> {
+        if let Some(ref cached) = self.cached_state {
+            return Ok(cached.scenes.get(scene_id).cloned());
+        }
+        let scenes_obj = self.get_obj_at_key(&ROOT, "scenes")?;
+        Ok(hydrate_prop(&self.doc, &scenes_obj, scene_id)?)
+    }
+
+    /// Hydrates only the characters, props and sets, skipping scenes,
+    /// shots and uploaded assets.
+    pub fn hydrate_entities_only(&mut self) -> CollabResult<ProcessingStages> {
+        if let Some(ref cached) = self.cached_state {
+            return Ok(cached.processing_stages.clone());
+        }
+        Ok(hydrate_prop(&self.doc, ROOT, "processing_stages")?)
+    }
+
+    /// Copies a scene with all its shots, looks and outfits to `new_id` and
+    /// appends it to the scene order. Shots get fresh IDs (the shot order is
+    /// remapped to match) and the copy is numbered after the last scene.
+    /// Review comments and render job IDs stay with the original.
+    pub fn duplicate_scene(&mut self, scene_id: &str, new_id: &str) -> CollabResult<()> {
+        let state = self.get_state_ref()?;
+        if state.scenes.contains_key(new_id) {
+            return Err(CollabError::schema_violation(format!(
+                "scene '{}' already exists",
+                new_id
+            )));
+        }
+        let mut scene = state
+            .scenes
+            .get(scene_id)
+            .cloned()
+            .ok_or_else(|| CollabError::field_not_found(scene_id))?;
+        let last_number = state.scenes.values().map(|s| s.scene_number).max().unwrap_or(0);
+
+        let new_ids: std::collections::HashMap<String, String> = scene
+            .shots
+            .keys()
+            .map(|id| (id.clone(), uuid::Uuid::new_v4().to_string()))
+            .collect();
+        let shots = scene
+            .shots
+            .iter()
+            .map(|(id, shot)| {
+                let new_shot_id = new_ids[id].clone();
+                let mut shot = shot.clone();
+                shot.id = new_shot_id.clone();
+                shot.job_id = None;
+                shot.comments = None;
+                (new_shot_id, shot)
+            })
+            .collect();
+        let order = scene
+            .shots
+            .order()
+            .iter()
+            .filter_map(|id| new_ids.get(id).cloned())
+            .collect();
+        scene.shots = OrderedMap::from_parts(shots, order);
+        scene.id = new_id.to_string();
+        scene.scene_number = last_number + 1;
+        scene.comments = None;
+
+        self.create_scene(new_id, scene)
     }
 
     /// Deletes a scene by ID.
@@ -249,13 +1034,139 @@ impl StoryboardManager {
         })
     }
 
-    /// Reorders scenes.
+    /// Reorders scenes, renumbering them if auto-renumber is on. With
+    /// fractional order, only the keys of moved scenes change.
     pub fn reorder_scenes(&mut self, new_order: Vec<String>) -> CollabResult<()> {
+        let auto_renumber = self.auto_renumber;
         self.update_state(|state| {
-            state.scene_order = new_order;
+            state.set_scene_order(new_order);
+            if auto_renumber {
+                state.renumber_scenes();
+            }
         })
     }
 
+    /// Sets every `scene_number` to the scene's position in display order
+    /// (1-based), as one change. Returns the number of scenes changed.
+    pub fn renumber_scenes(&mut self) -> CollabResult<usize> {
+        let mut changed = 0;
+        self.update_state_as_change("Renumber scenes", |state| {
+            changed = state.renumber_scenes();
+        })?;
+        Ok(changed)
+    }
+
+    /// Returns the scene IDs in display order (see
+    /// `StoryboardRoot::ordered_scene_ids()`).
+    pub fn get_scene_order(&mut self) -> CollabResult<Vec<String>> {
+        Ok(self.get_state_ref()?.ordered_scene_ids())
+    }
+
+    /// Moves a scene between two neighbours in display order (None at
+    /// either end). With fractional order this writes only the scene's
+    /// order key (O(1)), so it merges with concurrent moves of other scenes;
+    /// it fails if `prev` does not sort before `next`.
+    pub fn move_scene_between(
+        &mut self,
+        scene_id: &str,
+        prev: Option<&str>,
+        next: Option<&str>,
+    ) -> CollabResult<()> {
+        let state = self.get_state_ref()?;
+        let mut keys = [None, None];
+        for (key, id) in keys.iter_mut().zip([prev, next]) {
+            if let Some(id) = id {
+                let scene = state.scenes.get(id).ok_or_else(|| CollabError::field_not_found(id))?;
+                *key = Some(scene.order_key.clone().unwrap_or_default());
+            }
+        }
+        if !state.scenes.contains_key(scene_id) {
+            return Err(CollabError::field_not_found(scene_id));
+        }
+        if !state.fractional_order {
+            let index = prev.map_or(0, |prev| {
+                let mut others = state.scene_order.iter().filter(|s| *s != scene_id);
+                others.position(|s| s == prev).map_or(0, |i| i + 1)
+            });
+            return self.update_state(|state| state.place_scene(scene_id, index));
+        }
+
+        let [prev_key, next_key] = keys;
+        let key = order_key::key_between(prev_key.as_deref(), next_key.as_deref())?;
+        self.begin_edit(Action::Edit)?;
+        let obj = self.get_obj_at_path(&["scenes", scene_id])?;
+        self.doc.put(&obj, "order_key", ScalarValue::Str(key.into()))?;
+        Ok(())
+    }
+
+    /// Switches between list and fractional scene ordering, as one change
+    /// (see `crate::order_key`). Turning it on keys the scenes in their
+    /// current order; turning it off writes the key order to `scene_order`.
+    pub fn set_fractional_order(&mut self, enabled: bool) -> CollabResult<()> {
+        let message = if enabled {
+            "Use fractional scene order"
+        } else {
+            "Use list scene order"
+        };
+        self.update_state_as_change(message, |state| state.use_fractional_order(enabled))
+    }
+
+    /// Makes `reorder_scenes()`, `reorder_shots()` and `move_shot()` also
+    /// renumber what they reorder. Off by default; local to this manager.
+    pub fn set_auto_renumber(&mut self, enabled: bool) {
+        self.auto_renumber = enabled;
+    }
+
+    /// Parses a Fountain screenplay and appends its scenes, numbered after
+    /// the last existing scene, as one change. Speaking characters are
+    /// matched to existing characters by name (case-insensitive); unknown
+    /// ones are created. Returns the new scene IDs in script order.
+    #[cfg(feature = "script-import")]
+    pub fn import_fountain(&mut self, text: &str) -> CollabResult<Vec<String>> {
+        let scenes = script::parse_fountain(text);
+        if scenes.is_empty() {
+            return Ok(Vec::new());
+        }
+        let ids: Vec<String> = scenes
+            .iter()
+            .map(|_| uuid::Uuid::new_v4().to_string())
+            .collect();
+        let scene_ids = ids.clone();
+        self.update_state_as_change("Import Fountain script", move |state| {
+            let last_number = state.scenes.values().map(|s| s.scene_number).max().unwrap_or(0);
+            let mut character_ids: std::collections::HashMap<String, String> = state
+                .processing_stages
+                .characters
+                .values()
+                .map(|c| (c.name.to_lowercase(), c.id.clone()))
+                .collect();
+            for (id, mut scene) in scene_ids.into_iter().zip(scenes) {
+                scene.characters_present = scene
+                    .characters_present
+                    .iter()
+                    .map(|name| {
+                        character_ids
+                            .entry(name.to_lowercase())
+                            .or_insert_with(|| {
+                                let character_id = uuid::Uuid::new_v4().to_string();
+                                let character = Character::new(&character_id, name);
+                                let stages = &mut state.processing_stages;
+                                stages.characters.insert(character_id.clone(), character);
+                                character_id
+                            })
+                            .clone()
+                    })
+                    .collect();
+                scene.id = id.clone();
+                scene.scene_number += last_number;
+                state.scenes.insert(id.clone(), scene);
+                state.scene_order.push(id);
+            }
+            state.fill_scene_keys();
+        })?;
+        Ok(ids)
+    }
+
     /// Sets a character look for a scene (by tag).
     pub fn set_character_look(
         &mut self,
@@ -306,18 +1217,14 @@ impl StoryboardManager {
     pub fn create_shot(&mut self, scene_id: &str, shot_id: &str, shot: Shot) -> CollabResult<()> {
         self.update_state(|state| {
             if let Some(scene) = state.scenes.get_mut(scene_id) {
-                let shot_id_str = shot_id.to_string();
-                scene.shots.insert(shot_id_str.clone(), shot);
-                if !scene.shot_order.contains(&shot_id_str) {
-                    scene.shot_order.push(shot_id_str);
-                }
+                scene.shots.insert(shot_id, shot);
             }
         })
     }
 
     /// Gets a shot by ID from a scene.
     pub fn get_shot(&mut self, scene_id: &str, shot_id: &str) -> CollabResult<Option<Shot>> {
-        let state = self.get_state()?;
+        let state = self.get_state_ref()?;
         Ok(state
             .scenes
             .get(scene_id)
@@ -329,16 +1236,91 @@ impl StoryboardManager {
         self.update_state(|state| {
             if let Some(scene) = state.scenes.get_mut(scene_id) {
                 scene.shots.remove(shot_id);
-                scene.shot_order.retain(|s| s != shot_id);
             }
         })
     }
 
-    /// Reorders shots in a scene.
+    /// Reorders shots in a scene, renumbering them if auto-renumber is on.
+    /// IDs with no shot are skipped; shots left out of `new_order` follow
+    /// in their current order.
     pub fn reorder_shots(&mut self, scene_id: &str, new_order: Vec<String>) -> CollabResult<()> {
+        let auto_renumber = self.auto_renumber;
         self.update_state(|state| {
             if let Some(scene) = state.scenes.get_mut(scene_id) {
-                scene.shot_order = new_order;
+                scene.shots.reorder(new_order);
+                if auto_renumber {
+                    scene.renumber_shots();
+                }
+            }
+        })
+    }
+
+    /// Sets every `shot_number` in a scene to the shot's position in
+    /// `shot_order` (1-based), as one change. Returns the number of shots
+    /// changed.
+    pub fn renumber_shots(&mut self, scene_id: &str) -> CollabResult<usize> {
+        if !self.get_state_ref()?.scenes.contains_key(scene_id) {
+            return Err(CollabError::field_not_found(scene_id));
+        }
+        let mut changed = 0;
+        self.update_state_as_change("Renumber shots", |state| {
+            if let Some(scene) = state.scenes.get_mut(scene_id) {
+                changed = scene.renumber_shots();
+            }
+        })?;
+        Ok(changed)
+    }
+
+    /// Moves a shot to another scene as a single change, removing it and its
+    /// order entry from `from_scene`. The shot is inserted at `position` in
+    /// the target's shot order, or appended if None. Moving within one scene
+    /// just reorders it. With auto-renumber on, both scenes are renumbered.
+    pub fn move_shot(
+        &mut self,
+        from_scene: &str,
+        to_scene: &str,
+        shot_id: &str,
+        position: Option<usize>,
+    ) -> CollabResult<()> {
+        let state = self.get_state_ref()?;
+        let source = state
+            .scenes
+            .get(from_scene)
+            .ok_or_else(|| CollabError::field_not_found(from_scene))?;
+        if !source.shots.contains_key(shot_id) {
+            return Err(CollabError::field_not_found(shot_id));
+        }
+        let target = state
+            .scenes
+            .get(to_scene)
+            .ok_or_else(|| CollabError::field_not_found(to_scene))?;
+        if from_scene != to_scene && target.shots.contains_key(shot_id) {
+            return Err(CollabError::schema_violation(format!(
+                "scene '{}' already has shot '{}'",
+                to_scene, shot_id
+            )));
+        }
+        let target_len = target.shots.order().iter().filter(|id| *id != shot_id).count();
+        let position = position.unwrap_or(target_len);
+        if position > target_len {
+            return Err(CollabError::index_out_of_bounds(position, target_len));
+        }
+
+        let auto_renumber = self.auto_renumber;
+        self.update_state_as_change("Move shot", |state| {
+            let shot = state
+                .scenes
+                .get_mut(from_scene)
+                .and_then(|scene| scene.shots.remove(shot_id));
+            if let (Some(shot), Some(scene)) = (shot, state.scenes.get_mut(to_scene)) {
+                scene.shots.insert_at(position, shot_id, shot);
+            }
+            if auto_renumber {
+                for id in [from_scene, to_scene] {
+                    if let Some(scene) = state.scenes.get_mut(id) {
+                        scene.renumber_shots();
+                    }
+                }
             }
         })
     }
@@ -363,28 +1345,141 @@ impl StoryboardManager {
         self.set_shot_field_opt_str(scene_id, shot_id, "generation_status", status)
     }
 
-    /// Sets the shot image prompt (O(1) targeted update).
-    pub fn set_shot_image_prompt(
+    /// Sets the shot generation status and records when it was set (O(1)).
+    /// Render workers should use this so `sweep()` can detect abandoned jobs.
+    pub fn set_shot_generation_status_at(
         &mut self,
         scene_id: &str,
         shot_id: &str,
-        prompt: &str,
+        status: &str,
+        timestamp: i64,
     ) -> CollabResult<()> {
-        self.cached_state = None;
+        self.begin_edit(Action::Edit)?;
         let shot_obj = self.get_shot_obj(scene_id, shot_id)?;
         self.doc
-            .put(&shot_obj, "image_prompt", ScalarValue::Str(prompt.into()))?;
+            .put(&shot_obj, "generation_status", ScalarValue::Str(status.into()))?;
+        self.doc.put(
+            &shot_obj,
+            "generation_status_updated_at",
+            ScalarValue::Int(timestamp),
+        )?;
         Ok(())
     }
 
-    /// Sets the shot ref_shot_id (O(1) targeted update).
-    pub fn set_shot_ref_shot_id(
+    /// Sets the generation status of many shots as a single change, e.g. for
+    /// every shot of a render job. Takes `(scene_id, shot_id, status)`
+    /// triples; if any shot is missing, none of the statuses are changed.
+    pub fn set_shot_statuses(&mut self, updates: Vec<(String, String, String)>) -> CollabResult<()> {
+        if updates.is_empty() {
+            return Ok(());
+        }
+        self.transaction(Some("Update shot statuses"), |manager| {
+            for (scene_id, shot_id, status) in &updates {
+                manager.set_shot_generation_status(scene_id, shot_id, Some(status))?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Sets the shot render provider (O(1) targeted update).
+    pub fn set_shot_provider(
+        &mut self,
+        scene_id: &str,
+        shot_id: &str,
+        provider: Option<&str>,
+    ) -> CollabResult<()> {
+        self.set_shot_field_opt_str(scene_id, shot_id, "provider", provider)
+    }
+
+    /// Sets the shot render job ID (O(1) targeted update).
+    pub fn set_shot_job_id(
+        &mut self,
+        scene_id: &str,
+        shot_id: &str,
+        job_id: Option<&str>,
+    ) -> CollabResult<()> {
+        self.set_shot_field_opt_str(scene_id, shot_id, "job_id", job_id)
+    }
+
+    /// Finds the (scene ID, shot ID) submitted as `job_id`, if any.
+    pub fn find_shot_by_job_id(&mut self, job_id: &str) -> CollabResult<Option<(String, String)>> {
+        let state = self.get_state_ref()?;
+        for (scene_id, scene) in &state.scenes {
+            for (shot_id, shot) in &scene.shots {
+                if shot.job_id.as_deref() == Some(job_id) {
+                    return Ok(Some((scene_id.clone(), shot_id.clone())));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Finds a shot by ID in any scene. Returns its scene ID and the shot,
+    /// e.g. to resolve a generation node's `storyboard_ref`.
+    pub fn find_shot(&mut self, shot_id: &str) -> CollabResult<Option<(String, Shot)>> {
+        let state = self.get_state_ref()?;
+        Ok(state.scenes.iter().find_map(|(scene_id, scene)| {
+            scene.shots.get(shot_id).map(|shot| (scene_id.clone(), shot.clone()))
+        }))
+    }
+
+    /// Links a shot to the sequence generation node its image came from
+    /// (O(1)). `None` removes the link.
+    pub fn set_shot_generation_ref(
+        &mut self,
+        scene_id: &str,
+        shot_id: &str,
+        reference: Option<&DocRef>,
+    ) -> CollabResult<()> {
+        self.begin_edit(Action::Edit)?;
+        let shot_obj = self.get_shot_obj(scene_id, shot_id)?;
+        match reference {
+            Some(r) => reconcile_prop(&mut self.doc, &shot_obj, "generation_ref", r)?,
+            None => self.doc.delete(&shot_obj, "generation_ref")?,
+        }
+        Ok(())
+    }
+
+    /// Loads the generation node a shot is linked to through `resolver`.
+    /// Returns None if the shot has no link or the resolver cannot find
+    /// its target.
+    pub fn resolve_generation_ref<T>(
+        &mut self,
+        scene_id: &str,
+        shot_id: &str,
+        resolver: &mut impl DocResolver<T>,
+    ) -> CollabResult<Option<T>> {
+        let shot = self
+            .get_shot(scene_id, shot_id)?
+            .ok_or_else(|| CollabError::field_not_found(shot_id))?;
+        match shot.generation_ref {
+            Some(reference) => resolver.resolve(&reference),
+            None => Ok(None),
+        }
+    }
+
+    /// Sets the shot image prompt (O(1) targeted update).
+    pub fn set_shot_image_prompt(
+        &mut self,
+        scene_id: &str,
+        shot_id: &str,
+        prompt: &str,
+    ) -> CollabResult<()> {
+        self.begin_edit(Action::Edit)?;
+        let shot_obj = self.get_shot_obj(scene_id, shot_id)?;
+        self.doc
+            .put(&shot_obj, "image_prompt", ScalarValue::Str(prompt.into()))?;
+        Ok(())
+    }
+
+    /// Sets the shot ref_shot_id (O(1) targeted update).
+    pub fn set_shot_ref_shot_id(
         &mut self,
         scene_id: &str,
         shot_id: &str,
         ref_id: Option<i32>,
     ) -> CollabResult<()> {
-        self.cached_state = None;
+        self.begin_edit(Action::Edit)?;
         let shot_obj = self.get_shot_obj(scene_id, shot_id)?;
         match ref_id {
             Some(v) => self
@@ -397,25 +1492,397 @@ impl StoryboardManager {
         Ok(())
     }
 
-    /// Appends to shot history (maintains max 20 entries).
+    /// Appends to shot history (O(1) list insert, maintains max 20 entries).
     pub fn append_shot_history(
         &mut self,
         scene_id: &str,
         shot_id: &str,
         entry: ShotHistory,
     ) -> CollabResult<()> {
+        self.begin_edit(Action::Edit)?;
+        #[cfg(feature = "blob-store")]
+        let entry = {
+            let mut entry = entry;
+            self.intern_image(&mut entry.image)?;
+            entry
+        };
+        let shot_obj = self.get_shot_obj(scene_id, shot_id)?;
+        self.prepend_history(&shot_obj, &entry)
+    }
+
+    // =========================================================================
+    // TRASH
+    // =========================================================================
+
+    /// Returns the trashed scenes and shots.
+    pub fn get_trash(&mut self) -> CollabResult<Trash> {
+        Ok(self.get_state_ref()?.trash.clone())
+    }
+
+    /// Moves a scene (with its shots) to the trash as a single change.
+    /// `deleted_at` is milliseconds since epoch.
+    pub fn trash_scene(
+        &mut self,
+        id: &str,
+        deleted_at: i64,
+        deleted_by: Option<&str>,
+    ) -> CollabResult<()> {
+        if !self.get_state_ref()?.scenes.contains_key(id) {
+            return Err(CollabError::field_not_found(id));
+        }
+        self.update_state_as_change("Trash scene", |state| {
+            let index = state.scene_order.iter().position(|s| s == id);
+            state.scene_order.retain(|s| s != id);
+            if let Some(scene) = state.scenes.remove(id) {
+                let trashed = TrashedScene {
+                    scene,
+                    index: index.unwrap_or(state.scene_order.len()) as i64,
+                    deleted_at,
+                    deleted_by: deleted_by.map(str::to_string),
+                };
+                state.trash.scenes.insert(id.to_string(), trashed);
+            }
+        })
+    }
+
+    /// Moves a shot to the trash as a single change.
+    pub fn trash_shot(
+        &mut self,
+        scene_id: &str,
+        shot_id: &str,
+        deleted_at: i64,
+        deleted_by: Option<&str>,
+    ) -> CollabResult<()> {
+        let state = self.get_state_ref()?;
+        let scene = state
+            .scenes
+            .get(scene_id)
+            .ok_or_else(|| CollabError::field_not_found(scene_id))?;
+        if !scene.shots.contains_key(shot_id) {
+            return Err(CollabError::field_not_found(shot_id));
+        }
+        self.update_state_as_change("Trash shot", |state| {
+            let Some(scene) = state.scenes.get_mut(scene_id) else {
+                return;
+            };
+            let index = scene.shots.position(shot_id);
+            if let Some(shot) = scene.shots.remove(shot_id) {
+                let trashed = TrashedShot {
+                    shot,
+                    index: index.unwrap_or(scene.shots.order().len()) as i64,
+                    deleted_at,
+                    deleted_by: deleted_by.map(str::to_string),
+                };
+                state
+                    .trash
+                    .shots
+                    .entry(scene_id.to_string())
+                    .or_default()
+                    .insert(shot_id.to_string(), trashed);
+            }
+        })
+    }
+
+    /// Restores a trashed scene at its old position in the scene order.
+    /// Fails if it is not in the trash or a scene with its ID exists.
+    pub fn restore_scene(&mut self, id: &str) -> CollabResult<()> {
+        let state = self.get_state_ref()?;
+        if !state.trash.scenes.contains_key(id) {
+            return Err(CollabError::field_not_found(id));
+        }
+        if state.scenes.contains_key(id) {
+            return Err(CollabError::schema_violation(format!("scene '{}' already exists", id)));
+        }
+        self.update_state_as_change("Restore scene", |state| {
+            if let Some(trashed) = state.trash.scenes.remove(id) {
+                let index = (trashed.index.max(0) as usize).min(state.scene_order.len());
+                state.scene_order.retain(|s| s != id);
+                state.scene_order.insert(index, id.to_string());
+                state.scenes.insert(id.to_string(), trashed.scene);
+                state.fill_scene_keys();
+            }
+        })
+    }
+
+    /// Restores a trashed shot at its old position in its scene's shot
+    /// order. Fails if the scene is missing (restore it first) or already
+    /// has a shot with this ID.
+    pub fn restore_shot(&mut self, scene_id: &str, shot_id: &str) -> CollabResult<()> {
+        let state = self.get_state_ref()?;
+        if !state
+            .trash
+            .shots
+            .get(scene_id)
+            .is_some_and(|shots| shots.contains_key(shot_id))
+        {
+            return Err(CollabError::field_not_found(shot_id));
+        }
+        let scene = state
+            .scenes
+            .get(scene_id)
+            .ok_or_else(|| CollabError::field_not_found(scene_id))?;
+        if scene.shots.contains_key(shot_id) {
+            return Err(CollabError::schema_violation(format!(
+                "scene '{}' already has shot '{}'",
+                scene_id, shot_id
+            )));
+        }
+        self.update_state_as_change("Restore shot", |state| {
+            let Some(shots) = state.trash.shots.get_mut(scene_id) else {
+                return;
+            };
+            let trashed = shots.remove(shot_id);
+            if shots.is_empty() {
+                state.trash.shots.remove(scene_id);
+            }
+            if let (Some(trashed), Some(scene)) = (trashed, state.scenes.get_mut(scene_id)) {
+                let index = trashed.index.max(0) as usize;
+                scene.shots.insert_at(index, shot_id, trashed.shot);
+            }
+        })
+    }
+
+    /// Permanently removes scenes and shots trashed before `older_than`
+    /// (milliseconds since epoch). Returns how many were removed.
+    pub fn empty_trash(&mut self, older_than: i64) -> CollabResult<usize> {
+        let trash = &self.get_state_ref()?.trash;
+        let expired = trash
+            .scenes
+            .values()
+            .filter(|t| t.deleted_at < older_than)
+            .count()
+            + trash
+                .shots
+                .values()
+                .flat_map(std::collections::HashMap::values)
+                .filter(|t| t.deleted_at < older_than)
+                .count();
+        if expired == 0 {
+            return Ok(0);
+        }
+        self.update_state_as_change("Empty trash", |state| {
+            state.trash.scenes.retain(|_, t| t.deleted_at >= older_than);
+            for shots in state.trash.shots.values_mut() {
+                shots.retain(|_, t| t.deleted_at >= older_than);
+            }
+            state.trash.shots.retain(|_, shots| !shots.is_empty());
+        })?;
+        Ok(expired)
+    }
+
+    // =========================================================================
+    // UPLOADED ASSETS
+    // =========================================================================
+
+    /// Adds (or replaces) an uploaded asset. With `blob-store`, a base64
+    /// data-URL image is kept once in the document's blob map.
+    pub fn create_uploaded_asset(&mut self, id: &str, asset: UploadedAsset) -> CollabResult<()> {
         self.update_state(|state| {
-            if let Some(scene) = state.scenes.get_mut(scene_id) {
-                if let Some(shot) = scene.shots.get_mut(shot_id) {
-                    // Prepend new entry
-                    shot.history.insert(0, entry);
-                    // Trim to max 20
-                    if shot.history.len() > 20 {
-                        shot.history.truncate(20);
+            #[cfg(feature = "blob-store")]
+            let asset = {
+                let mut asset = asset;
+                assets::intern(&mut state.blobs, &mut asset.image);
+                asset
+            };
+            state.uploaded_assets.insert(id.to_string(), asset);
+        })
+    }
+
+    /// Gets an uploaded asset by ID.
+    pub fn get_uploaded_asset(&mut self, id: &str) -> CollabResult<Option<UploadedAsset>> {
+        let state = self.get_state_ref()?;
+        Ok(state.uploaded_assets.get(id).cloned())
+    }
+
+    /// Deletes an uploaded asset by ID. Image fields using it are not changed.
+    pub fn delete_uploaded_asset(&mut self, id: &str) -> CollabResult<()> {
+        self.update_state(|state| {
+            state.uploaded_assets.remove(id);
+        })
+    }
+
+    /// Lists uploaded assets, oldest first.
+    pub fn list_uploaded_assets(&mut self) -> CollabResult<Vec<UploadedAsset>> {
+        let state = self.get_state_ref()?;
+        let mut assets: Vec<UploadedAsset> = state.uploaded_assets.values().cloned().collect();
+        assets.sort_by(|a, b| a.uploaded_at.cmp(&b.uploaded_at).then_with(|| a.id.cmp(&b.id)));
+        Ok(assets)
+    }
+
+    /// Deletes the uploaded assets no image field references (see
+    /// `storyboard::assets`), as one change. Returns the deleted IDs.
+    pub fn gc_unreferenced_assets(&mut self) -> CollabResult<Vec<String>> {
+        let unused = assets::unreferenced_assets(self.get_state_ref()?);
+        if unused.is_empty() {
+            return Ok(unused);
+        }
+        let message = format!("Remove {} unreferenced asset(s)", unused.len());
+        self.update_state_as_change(&message, |state| {
+            for id in &unused {
+                state.uploaded_assets.remove(id);
+            }
+        })?;
+        Ok(unused)
+    }
+
+    /// Moves the base64 data-URL payloads of uploaded assets (inline or in
+    /// the document's blob map) into `store` and replaces them with
+    /// `blob:sha256:` references, as one change.
+    /// Image fields holding the same data URL get the reference too (see
+    /// `crate::blob`). Blobs are stored before the document changes, so a
+    /// reference never points at a missing blob.
+    #[cfg(feature = "blob-store")]
+    pub fn externalize_assets(&mut self, store: &mut dyn BlobStore) -> CollabResult<ExternalizeReport> {
+        let state = self.get_state_ref()?;
+        let mut ids: Vec<&String> = state.uploaded_assets.keys().collect();
+        ids.sort_unstable();
+        // (asset ID, data URL, reference, MIME type)
+        let mut moves: Vec<(String, String, String, String)> = Vec::new();
+        for id in ids {
+            let image = &state.uploaded_assets[id].image;
+            // Assets deduplicated into the document's blob map move out too
+            let url = blob::parse_blob_ref(image)
+                .and_then(|hash| state.blobs.get(hash))
+                .unwrap_or(image);
+            let Some((mime, bytes)) = blob::decode_data_url(url) else {
+                continue;
+            };
+            let hash = blob::content_hash(&bytes);
+            store.put(&hash, &bytes)?;
+            moves.push((id.clone(), url.clone(), blob::blob_ref(&hash), mime.to_string()));
+        }
+
+        let mut report = ExternalizeReport::default();
+        if moves.is_empty() {
+            return Ok(report);
+        }
+        let message = format!("Externalize {} asset(s)", moves.len());
+        self.update_state_as_change(&message, |state| {
+            for (id, url, reference, mime) in &moves {
+                report.fields += assets::replace_image(state, url, reference);
+                if let Some(hash) = blob::parse_blob_ref(reference) {
+                    state.blobs.remove(hash);
+                }
+                if let Some(asset) = state.uploaded_assets.get_mut(id) {
+                    asset.image = reference.clone();
+                    if asset.file_type.is_empty() {
+                        asset.file_type = mime.clone();
                     }
                 }
+                report.assets.push(id.clone());
+                report.bytes += url.len();
             }
-        })
+        })?;
+        Ok(report)
+    }
+
+    /// Keeps each distinct base64 data URL in image fields and uploaded
+    /// assets once, in the document's blob map, replacing the fields with
+    /// `blob:sha256:` references, as one change. Migrates documents written
+    /// before deduplication; new assets and history entries are deduplicated
+    /// as they are added.
+    #[cfg(feature = "blob-store")]
+    pub fn dedup_images(&mut self) -> CollabResult<DedupReport> {
+        let mut report = DedupReport::default();
+        self.update_state_as_change("Deduplicate images", |state| {
+            report = assets::dedup_images(state);
+        })?;
+        Ok(report)
+    }
+
+    /// Returns the data URL behind a reference into the document's blob
+    /// map, or `image` unchanged (URLs, data URLs, external blob references).
+    #[cfg(feature = "blob-store")]
+    pub fn resolve_image(&mut self, image: &str) -> CollabResult<String> {
+        let blobs = &self.get_state_ref()?.blobs;
+        let url = blob::parse_blob_ref(image).and_then(|hash| blobs.get(hash));
+        Ok(url.map_or(image, String::as_str).to_string())
+    }
+
+    /// Replaces a base64 data-URL `image` with a reference into the
+    /// document's blob map, adding the payload if it is new (O(1)).
+    #[cfg(feature = "blob-store")]
+    fn intern_image(&mut self, image: &mut String) -> CollabResult<()> {
+        let Some((_, bytes)) = blob::decode_data_url(image) else {
+            return Ok(());
+        };
+        let hash = blob::content_hash(&bytes);
+        let blobs = match self.doc.get(&ROOT, "blobs")? {
+            Some((Value::Object(ObjType::Map), obj)) => obj,
+            _ => self.doc.put_object(&ROOT, "blobs", ObjType::Map)?,
+        };
+        if self.doc.get(&blobs, hash.as_str())?.is_none() {
+            self.doc.put(&blobs, hash.as_str(), ScalarValue::Str(image.as_str().into()))?;
+        }
+        *image = blob::blob_ref(&hash);
+        Ok(())
+    }
+
+    // =========================================================================
+    // SCENE LOCKS
+    // =========================================================================
+
+    /// Claims an advisory lock on a scene for `user` until `now + ttl_ms`,
+    /// renewing it if `user` already holds it. Returns false without
+    /// changing anything while another user holds an active lock. Expired
+    /// locks and locks on deleted scenes are dropped along the way.
+    pub fn claim_scene(
+        &mut self,
+        scene_id: &str,
+        user: &str,
+        ttl_ms: i64,
+        now: i64,
+    ) -> CollabResult<bool> {
+        let state = self.get_state_ref()?;
+        if !state.scenes.contains_key(scene_id) {
+            return Err(CollabError::field_not_found(scene_id));
+        }
+        let claimed_at = match state.scene_locks.get(scene_id) {
+            Some(lock) if lock.is_active(now) && lock.user != user => return Ok(false),
+            Some(lock) if lock.is_active(now) => lock.claimed_at,
+            _ => now,
+        };
+        let lock = SceneLock {
+            user: user.to_string(),
+            claimed_at,
+            expires_at: now + ttl_ms,
+        };
+        self.update_state(|state| {
+            let scenes = &state.scenes;
+            state
+                .scene_locks
+                .retain(|id, lock| lock.is_active(now) && scenes.contains_key(id));
+            state.scene_locks.insert(scene_id.to_string(), lock);
+        })?;
+        Ok(true)
+    }
+
+    /// Releases `user`'s lock on a scene. Returns false if `user` does not
+    /// hold it.
+    pub fn release_scene(&mut self, scene_id: &str, user: &str) -> CollabResult<bool> {
+        let held = self
+            .get_state_ref()?
+            .scene_locks
+            .get(scene_id)
+            .is_some_and(|lock| lock.user == user);
+        if !held {
+            return Ok(false);
+        }
+        self.update_state(|state| {
+            state.scene_locks.remove(scene_id);
+        })?;
+        Ok(true)
+    }
+
+    /// Returns the scene locks still active at `now`, keyed by scene ID.
+    pub fn get_active_locks(&mut self, now: i64) -> CollabResult<BTreeMap<String, SceneLock>> {
+        Ok(self
+            .get_state_ref()?
+            .scene_locks
+            .iter()
+            .filter(|(_, lock)| lock.is_active(now))
+            .map(|(id, lock)| (id.clone(), lock.clone()))
+            .collect())
     }
 
     // =========================================================================
@@ -424,7 +1891,7 @@ impl StoryboardManager {
 
     /// Sets the entity name (O(1)).
     pub fn set_entity_name(&mut self, entity_type: &str, id: &str, name: &str) -> CollabResult<()> {
-        self.cached_state = None;
+        self.begin_edit(Action::Edit)?;
         let obj = self.get_obj_at_path(&["processing_stages", entity_type, id])?;
         self.doc.put(&obj, "name", ScalarValue::Str(name.into()))?;
         Ok(())
@@ -432,7 +1899,7 @@ impl StoryboardManager {
 
     /// Sets the entity description (O(1)).
     pub fn set_entity_description(&mut self, entity_type: &str, id: &str, description: &str) -> CollabResult<()> {
-        self.cached_state = None;
+        self.begin_edit(Action::Edit)?;
         let obj = self.get_obj_at_path(&["processing_stages", entity_type, id])?;
         self.doc.put(&obj, "description", ScalarValue::Str(description.into()))?;
         Ok(())
@@ -445,7 +1912,7 @@ impl StoryboardManager {
 
     /// Sets the entity image_prompt (O(1)).
     pub fn set_entity_image_prompt(&mut self, entity_type: &str, id: &str, prompt: &str) -> CollabResult<()> {
-        self.cached_state = None;
+        self.begin_edit(Action::Edit)?;
         let obj = self.get_obj_at_path(&["processing_stages", entity_type, id])?;
         self.doc.put(&obj, "image_prompt", ScalarValue::Str(prompt.into()))?;
         Ok(())
@@ -458,19 +1925,95 @@ impl StoryboardManager {
 
     /// Sets the entity enhanced flag (O(1)).
     pub fn set_entity_enhanced(&mut self, entity_type: &str, id: &str, enhanced: bool) -> CollabResult<()> {
-        self.cached_state = None;
+        self.begin_edit(Action::Edit)?;
         let obj = self.get_obj_at_path(&["processing_stages", entity_type, id])?;
         self.doc.put(&obj, "enhanced", ScalarValue::Boolean(enhanced))?;
         Ok(())
     }
 
+    /// Sets one character attribute, e.g. ("eye_color", "green") (O(1)).
+    /// Other attributes are untouched, so peers can edit different keys
+    /// concurrently.
+    pub fn set_character_attribute(&mut self, id: &str, key: &str, value: &str) -> CollabResult<()> {
+        self.begin_edit(Action::Edit)?;
+        let obj = self.get_obj_at_path(&["processing_stages", "characters", id])?;
+        let attributes = match self.doc.get(&obj, "attributes")? {
+            Some((Value::Object(ObjType::Map), map)) => map,
+            _ => self.doc.put_object(&obj, "attributes", ObjType::Map)?,
+        };
+        self.doc.put(&attributes, key, ScalarValue::Str(value.into()))?;
+        Ok(())
+    }
+
+    /// Removes one character attribute (O(1)). Missing keys are ignored.
+    pub fn delete_character_attribute(&mut self, id: &str, key: &str) -> CollabResult<()> {
+        self.begin_edit(Action::Edit)?;
+        let obj = self.get_obj_at_path(&["processing_stages", "characters", id])?;
+        if let Some((Value::Object(ObjType::Map), attributes)) = self.doc.get(&obj, "attributes")? {
+            self.doc.delete(&attributes, key)?;
+        }
+        Ok(())
+    }
+
+    /// Returns a character's attributes.
+    pub fn get_character_attributes(
+        &mut self,
+        id: &str,
+    ) -> CollabResult<std::collections::HashMap<String, String>> {
+        let state = self.get_state_ref()?;
+        state
+            .processing_stages
+            .characters
+            .get(id)
+            .map(|c| c.attributes.clone())
+            .ok_or_else(|| CollabError::field_not_found(id))
+    }
+
+    /// Lists the scenes, shots, looks and outfits that reference `tag`
+    /// (the places `rename_tag()` would change), e.g. to warn before
+    /// deleting an entity.
+    pub fn get_entity_usages(&mut self, tag: &str) -> CollabResult<EntityUsages> {
+        let state = self.get_state_ref()?;
+        Ok(rename::find_usages(state, tag))
+    }
+
+    /// Renames an entity tag (e.g. "@richie" to "@rich") together with every
+    /// map key, reference and prompt mention of it, as one change. See
+    /// `storyboard::rename` for the fields covered. Fails if no character,
+    /// prop, set or custom entity has `old`, or another one already has `new`.
+    pub fn rename_tag(&mut self, old: &str, new: &str) -> CollabResult<TagRenameReport> {
+        if new.trim().is_empty() {
+            return Err(CollabError::schema_violation("tag must not be empty"));
+        }
+        if old == new {
+            return Ok(TagRenameReport::default());
+        }
+        let stages = &self.get_state_ref()?.processing_stages;
+        if !rename::tag_in_use(stages, old) {
+            return Err(CollabError::field_not_found(old));
+        }
+        if rename::tag_in_use(stages, new) {
+            return Err(CollabError::schema_violation(format!(
+                "tag '{}' is already in use",
+                new
+            )));
+        }
+
+        let mut report = TagRenameReport::default();
+        let message = format!("Rename tag {} to {}", old, new);
+        self.update_state_as_change(&message, |state| {
+            report = rename::rename_tag(state, old, new);
+        })?;
+        Ok(report)
+    }
+
     // =========================================================================
     // SCENE FIELD SETTERS
     // =========================================================================
 
     /// Sets the scene title (O(1)).
     pub fn set_scene_title(&mut self, scene_id: &str, title: &str) -> CollabResult<()> {
-        self.cached_state = None;
+        self.begin_edit(Action::Edit)?;
         let obj = self.get_obj_at_path(&["scenes", scene_id])?;
         self.doc.put(&obj, "title", ScalarValue::Str(title.into()))?;
         Ok(())
@@ -483,7 +2026,7 @@ impl StoryboardManager {
 
     /// Sets the scene header (O(1)).
     pub fn set_scene_header(&mut self, scene_id: &str, header: &str) -> CollabResult<()> {
-        self.cached_state = None;
+        self.begin_edit(Action::Edit)?;
         let obj = self.get_obj_at_path(&["scenes", scene_id])?;
         self.doc.put(&obj, "header", ScalarValue::Str(header.into()))?;
         Ok(())
@@ -491,7 +2034,7 @@ impl StoryboardManager {
 
     /// Sets the scene content (O(1)).
     pub fn set_scene_content(&mut self, scene_id: &str, content: &str) -> CollabResult<()> {
-        self.cached_state = None;
+        self.begin_edit(Action::Edit)?;
         let obj = self.get_obj_at_path(&["scenes", scene_id])?;
         self.doc.put(&obj, "content", ScalarValue::Str(content.into()))?;
         Ok(())
@@ -504,7 +2047,7 @@ impl StoryboardManager {
 
     /// Sets the scene predicted_shots (O(1)).
     pub fn set_scene_predicted_shots(&mut self, scene_id: &str, predicted_shots: i64) -> CollabResult<()> {
-        self.cached_state = None;
+        self.begin_edit(Action::Edit)?;
         let obj = self.get_obj_at_path(&["scenes", scene_id])?;
         self.doc.put(&obj, "predicted_shots", ScalarValue::Int(predicted_shots))?;
         Ok(())
@@ -515,9 +2058,29 @@ impl StoryboardManager {
         self.set_scene_field_opt_str(scene_id, "reasoning", reasoning)
     }
 
+    /// Sets the scene thumbnail (O(1)). `None` clears it.
+    pub fn set_scene_thumbnail(&mut self, scene_id: &str, thumbnail: Option<&str>) -> CollabResult<()> {
+        self.set_scene_field_opt_str(scene_id, "thumbnail", thumbnail)
+    }
+
+    /// Uses a shot's image as its scene's thumbnail (O(1)). Fails if the
+    /// shot does not exist or has no image.
+    pub fn pick_thumbnail_from_shot(&mut self, scene_id: &str, shot_id: &str) -> CollabResult<()> {
+        let shot = self
+            .get_state_ref()?
+            .scenes
+            .get(scene_id)
+            .and_then(|scene| scene.shots.get(shot_id))
+            .ok_or_else(|| CollabError::field_not_found(shot_id))?;
+        let image = shot.image.clone().ok_or_else(|| {
+            CollabError::schema_violation(format!("shot '{}' has no image", shot_id))
+        })?;
+        self.set_scene_thumbnail(scene_id, Some(&image))
+    }
+
     /// Helper for scene optional string fields.
     fn set_scene_field_opt_str(&mut self, scene_id: &str, key: &str, value: Option<&str>) -> CollabResult<()> {
-        self.cached_state = None;
+        self.begin_edit(Action::Edit)?;
         let obj = self.get_obj_at_path(&["scenes", scene_id])?;
         match value {
             Some(v) => self.doc.put(&obj, key, ScalarValue::Str(v.into()))?,
@@ -532,7 +2095,7 @@ impl StoryboardManager {
 
     /// Sets the shot visual_description (O(1)).
     pub fn set_shot_visual_description(&mut self, scene_id: &str, shot_id: &str, desc: &str) -> CollabResult<()> {
-        self.cached_state = None;
+        self.begin_edit(Action::Edit)?;
         let shot_obj = self.get_shot_obj(scene_id, shot_id)?;
         self.doc.put(&shot_obj, "visual_description", ScalarValue::Str(desc.into()))?;
         Ok(())
@@ -560,7 +2123,7 @@ impl StoryboardManager {
 
     /// Sets the shot size (O(1)).
     pub fn set_shot_size(&mut self, scene_id: &str, shot_id: &str, size: &str) -> CollabResult<()> {
-        self.cached_state = None;
+        self.begin_edit(Action::Edit)?;
         let shot_obj = self.get_shot_obj(scene_id, shot_id)?;
         self.doc.put(&shot_obj, "size", ScalarValue::Str(size.into()))?;
         Ok(())
@@ -568,12 +2131,423 @@ impl StoryboardManager {
 
     /// Sets the shot angle (O(1)).
     pub fn set_shot_angle(&mut self, scene_id: &str, shot_id: &str, angle: &str) -> CollabResult<()> {
-        self.cached_state = None;
+        self.begin_edit(Action::Edit)?;
         let shot_obj = self.get_shot_obj(scene_id, shot_id)?;
         self.doc.put(&shot_obj, "angle", ScalarValue::Str(angle.into()))?;
         Ok(())
     }
 
+    // =========================================================================
+    // NAMED SNAPSHOTS
+    // =========================================================================
+
+    /// Records the current heads under `name`, replacing any snapshot with
+    /// that name (see `crate::snapshot`).
+    pub fn create_snapshot(&mut self, name: &str) -> CollabResult<()> {
+        let snapshot = NamedSnapshot::capture(&mut self.doc);
+        self.update_state(|state| {
+            state.snapshots.insert(name.to_string(), snapshot);
+        })
+    }
+
+    /// Returns the named snapshots, sorted by name.
+    pub fn list_snapshots(&mut self) -> CollabResult<BTreeMap<String, NamedSnapshot>> {
+        let snapshots = &self.get_state_ref()?.snapshots;
+        Ok(snapshots.iter().map(|(name, s)| (name.clone(), s.clone())).collect())
+    }
+
+    /// Deletes a named snapshot. Returns false if it did not exist.
+    pub fn delete_snapshot(&mut self, name: &str) -> CollabResult<bool> {
+        if !self.get_state_ref()?.snapshots.contains_key(name) {
+            return Ok(false);
+        }
+        self.update_state(|state| {
+            state.snapshots.remove(name);
+        })?;
+        Ok(true)
+    }
+
+    /// Writes the state recorded by snapshot `name` back as a new change.
+    /// The ACL, actor names and snapshot list keep their current values.
+    /// Fails with `SnapshotNotFound` for an unknown name and
+    /// `InvalidChangeHash` if its heads were compacted away.
+    pub fn restore_snapshot(&mut self, name: &str) -> CollabResult<()> {
+        let snapshot = self
+            .get_state_ref()?
+            .snapshots
+            .get(name)
+            .cloned()
+            .ok_or_else(|| CollabError::snapshot_not_found(name))?;
+        let mut restored: StoryboardRoot = snapshot.state(&mut self.doc)?;
+        self.update_state_as_change(&format!("Restore snapshot {}", name), |state| {
+            restored.acl = std::mem::take(&mut state.acl);
+            restored.actor_names = std::mem::take(&mut state.actor_names);
+            restored.snapshots = std::mem::take(&mut state.snapshots);
+            *state = restored;
+        })
+    }
+
+    // =========================================================================
+    // PERMISSIONS
+    // =========================================================================
+
+    /// Returns the local user and ACL enforcement mode.
+    pub fn permission_context(&self) -> &PermissionContext {
+        &self.permissions
+    }
+
+    /// Sets the local user and ACL enforcement mode checked before local
+    /// edits. Remote changes are never checked.
+    pub fn set_permission_context(&mut self, context: PermissionContext) {
+        self.permissions = context;
+    }
+
+    /// Returns and clears the denials recorded in warn mode.
+    pub fn take_permission_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.permission_warnings)
+    }
+
+    /// Returns the document's ACL.
+    pub fn get_acl(&mut self) -> CollabResult<Acl> {
+        Ok(self.get_state_ref()?.acl.clone())
+    }
+
+    /// Gives `user_id` exactly `role`. Requires the owner role once the
+    /// document is claimed.
+    pub fn grant_role(&mut self, user_id: &str, role: Role) -> CollabResult<()> {
+        self.check_permission(Action::Manage)?;
+        self.write_state(|state| state.acl.grant(user_id, role))
+    }
+
+    /// Removes every role of `user_id`. Returns false if it had none.
+    pub fn revoke_role(&mut self, user_id: &str) -> CollabResult<bool> {
+        self.check_permission(Action::Manage)?;
+        let mut revoked = false;
+        self.write_state(|state| revoked = state.acl.revoke(user_id))?;
+        Ok(revoked)
+    }
+
+    /// Makes the document read-only: local edits and comments fail with
+    /// `DocumentFrozen` until `unfreeze()`, except from a manager in admin
+    /// mode. Role changes stay possible. Requires the owner role once the
+    /// document is claimed.
+    pub fn freeze(&mut self) -> CollabResult<()> {
+        self.check_permission(Action::Manage)?;
+        self.write_state(|state| state.frozen = true)
+    }
+
+    /// Lifts `freeze()`.
+    pub fn unfreeze(&mut self) -> CollabResult<()> {
+        self.check_permission(Action::Manage)?;
+        self.write_state(|state| state.frozen = false)
+    }
+
+    /// Returns true if the document is frozen.
+    pub fn is_frozen(&self) -> CollabResult<bool> {
+        match &self.cached_state {
+            Some(state) => Ok(state.frozen),
+            None => {
+                let frozen: Option<bool> = hydrate_prop(&self.doc, ROOT, "frozen")?;
+                Ok(frozen.unwrap_or_default())
+            }
+        }
+    }
+
+    /// Enables or disables admin mode, which lets this manager edit a
+    /// frozen document.
+    pub fn set_admin(&mut self, admin: bool) {
+        self.admin = admin;
+    }
+
+    /// Checks `action` against the freeze flag and, according to the
+    /// permission context, the ACL.
+    fn check_permission(&mut self, action: Action) -> CollabResult<()> {
+        if action != Action::Manage && !self.admin && self.is_frozen()? {
+            return Err(CollabError::document_frozen());
+        }
+        if self.permissions.enforcement == Enforcement::Off {
+            return Ok(());
+        }
+        let stored;
+        let acl = match &self.cached_state {
+            Some(state) => &state.acl,
+            None => {
+                let hydrated: Option<Acl> = hydrate_prop(&self.doc, ROOT, "acl")?;
+                stored = hydrated.unwrap_or_default();
+                &stored
+            }
+        };
+        self.permissions
+            .enforce(acl, action, &mut self.permission_warnings)
+    }
+
+    /// Checks `action`, then drops the cached state ahead of a direct write.
+    fn begin_edit(&mut self, action: Action) -> CollabResult<()> {
+        self.check_permission(action)?;
+        self.cached_state = None;
+        Ok(())
+    }
+
+    // =========================================================================
+    // COMMENTS
+    // =========================================================================
+
+    /// Appends a comment to a shot (O(1) list insert, trims to max 200).
+    pub fn add_shot_comment(
+        &mut self,
+        scene_id: &str,
+        shot_id: &str,
+        comment: Comment,
+    ) -> CollabResult<()> {
+        let shot_obj = self.get_shot_obj(scene_id, shot_id)?;
+        self.append_comment(&shot_obj, comment)
+    }
+
+    /// Marks a shot comment as resolved or reopens it (O(1) targeted update).
+    pub fn resolve_shot_comment(
+        &mut self,
+        scene_id: &str,
+        shot_id: &str,
+        comment_id: &str,
+        resolved: bool,
+    ) -> CollabResult<()> {
+        let shot_obj = self.get_shot_obj(scene_id, shot_id)?;
+        self.set_comment_resolved(&shot_obj, comment_id, resolved)
+    }
+
+    /// Appends a comment to a scene (O(1) list insert, trims to max 200).
+    pub fn add_scene_comment(&mut self, scene_id: &str, comment: Comment) -> CollabResult<()> {
+        let scene_obj = self.get_obj_at_path(&["scenes", scene_id])?;
+        self.append_comment(&scene_obj, comment)
+    }
+
+    /// Marks a scene comment as resolved or reopens it (O(1) targeted update).
+    pub fn resolve_scene_comment(
+        &mut self,
+        scene_id: &str,
+        comment_id: &str,
+        resolved: bool,
+    ) -> CollabResult<()> {
+        let scene_obj = self.get_obj_at_path(&["scenes", scene_id])?;
+        self.set_comment_resolved(&scene_obj, comment_id, resolved)
+    }
+
+    // =========================================================================
+    // MAINTENANCE
+    // =========================================================================
+
+    /// Downgrades shots stuck in "processing" longer than the default TTL to "stalled".
+    pub fn sweep(&mut self, now: i64) -> CollabResult<SweepReport> {
+        self.sweep_with_config(now, &SweepConfig::default())
+    }
+
+    /// Downgrades shots stuck in "processing" longer than `config.processing_ttl_ms`
+    /// to "stalled" and removes scene locks that expired before `now` or
+    /// whose scene is gone, as a single change. Shots without a status
+    /// timestamp are skipped.
+    pub fn sweep_with_config(&mut self, now: i64, config: &SweepConfig) -> CollabResult<SweepReport> {
+        let state = self.get_state()?;
+        let mut stale: Vec<(String, String, i64)> = Vec::new();
+        for (scene_id, scene) in &state.scenes {
+            for (shot_id, shot) in &scene.shots {
+                if shot.generation_status.as_deref() != Some(PROCESSING_STATUS) {
+                    continue;
+                }
+                if let Some(since) = shot.generation_status_updated_at {
+                    if config.is_expired(since, now) {
+                        stale.push((scene_id.clone(), shot_id.clone(), since));
+                    }
+                }
+            }
+        }
+        stale.sort();
+        let mut released: Vec<String> = state
+            .scene_locks
+            .iter()
+            .filter(|(id, lock)| !lock.is_active(now) || !state.scenes.contains_key(*id))
+            .map(|(id, _)| id.clone())
+            .collect();
+        released.sort();
+
+        if !stale.is_empty() || !released.is_empty() {
+            self.transaction(Some("Sweep stale statuses"), |manager| {
+                manager.apply_sweep(&stale, &released, now)
+            })?;
+        }
+
+        Ok(SweepReport {
+            stalled: stale
+                .into_iter()
+                .map(|(scene_id, shot_id, since)| StalledItem {
+                    path: vec![scene_id, shot_id],
+                    since,
+                })
+                .collect(),
+            released_locks: released,
+        })
+    }
+
+    /// Writes the stalled statuses and lock removals of one sweep.
+    fn apply_sweep(
+        &mut self,
+        stale: &[(String, String, i64)],
+        released: &[String],
+        now: i64,
+    ) -> CollabResult<()> {
+        for (scene_id, shot_id, _) in stale {
+            self.set_shot_generation_status_at(scene_id, shot_id, STALLED_STATUS, now)?;
+        }
+        if released.is_empty() {
+            return Ok(());
+        }
+        self.begin_edit(Action::Edit)?;
+        if let Some((Value::Object(ObjType::Map), locks)) = self.doc.get(&ROOT, "scene_locks")? {
+            for scene_id in released {
+                self.doc.delete(&locks, scene_id.as_str())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Makes every order list match its map after concurrent edits, as a
+    /// single change. Orphaned scenes and shots are appended by scene/shot
+    /// number, entities by ID. See `crate::repair` for the rules.
+    pub fn repair(&mut self) -> CollabResult<RepairReport> {
+        let mut state = self.get_state()?;
+        let mut report = RepairReport::default();
+
+        repair::repair_order(
+            &mut report,
+            &["scene_order"],
+            &mut state.scene_order,
+            &state.scenes,
+            |scene| scene.scene_number as i64,
+        );
+        let stages = &mut state.processing_stages;
+        stages
+            .characters
+            .repair(&mut report, &["processing_stages", "character_order"], |_| 0);
+        stages
+            .props
+            .repair(&mut report, &["processing_stages", "prop_order"], |_| 0);
+        stages
+            .sets
+            .repair(&mut report, &["processing_stages", "set_order"], |_| 0);
+
+        let mut scene_ids: Vec<String> = state.scenes.keys().cloned().collect();
+        scene_ids.sort();
+        for scene_id in &scene_ids {
+            if let Some(scene) = state.scenes.get_mut(scene_id) {
+                scene.shots.repair(
+                    &mut report,
+                    &["scenes", scene_id.as_str(), "shot_order"],
+                    |shot| shot.shot_number as i64,
+                );
+            }
+        }
+
+        if !report.is_empty() {
+            self.update_state_as_change("Repair order lists", |s| *s = state)?;
+        }
+        Ok(report)
+    }
+
+    /// Checks the document for inconsistencies without modifying it.
+    ///
+    /// Reports broken order lists, forward `ref_shot_id` references, tags
+    /// that match no entity, over-long histories, etc. See
+    /// `storyboard::validation` for the list of issue codes.
+    pub fn validate(&mut self) -> CollabResult<ValidationReport> {
+        let state = self.get_state_ref()?;
+        Ok(validation::validate_storyboard(state))
+    }
+
+    /// Renders a shot list (scene/shot numbers, size, angle, description,
+    /// status, image URL) as CSV or Markdown. See `storyboard::shot_list`.
+    pub fn export_shot_list(&mut self, format: ShotListFormat) -> CollabResult<String> {
+        let state = self.get_state_ref()?;
+        Ok(shot_list::render(state, format))
+    }
+
+    /// Renders a paginated PDF contact sheet of scene headings and shot
+    /// thumbnails with captions. See `storyboard::pdf`.
+    #[cfg(feature = "pdf-export")]
+    pub fn export_pdf(&mut self, options: &PdfOptions) -> CollabResult<Vec<u8>> {
+        let state = self.get_state_ref()?;
+        Ok(pdf::render(state, options))
+    }
+
+    // =========================================================================
+    // PRESENCE (ephemeral, not stored in the document)
+    // =========================================================================
+
+    /// Sets this peer's scene/shot focus and returns the message to broadcast.
+    pub fn set_local_presence(
+        &mut self,
+        user: &str,
+        scene_id: Option<&str>,
+        shot_id: Option<&str>,
+        mode: PresenceMode,
+        cursor: Option<Cursor>,
+    ) -> Vec<u8> {
+        self.awareness.set_local(StoryboardPresence {
+            user: user.to_string(),
+            scene_id: scene_id.map(String::from),
+            shot_id: shot_id.map(String::from),
+            mode,
+            cursor,
+        })
+    }
+
+    /// Clears this peer's presence and returns a "left" message to broadcast.
+    pub fn clear_local_presence(&mut self) -> Vec<u8> {
+        self.awareness.clear_local()
+    }
+
+    /// Encodes the current local presence (send periodically as a heartbeat).
+    pub fn encode_presence(&self) -> Vec<u8> {
+        self.awareness.encode()
+    }
+
+    /// Applies a presence message received at `now` (ms since epoch).
+    /// Returns true if peers changed.
+    pub fn apply_presence_message(&mut self, msg: &[u8], now: i64) -> CollabResult<bool> {
+        self.awareness.apply_at(msg, now)
+    }
+
+    /// Sets how long a silent peer is kept before `prune_peers()` drops it.
+    pub fn set_peer_timeout(&mut self, timeout_ms: i64) {
+        self.awareness.set_peer_timeout_ms(timeout_ms);
+    }
+
+    /// Drops peers silent longer than the peer timeout. Returns their actor IDs.
+    pub fn prune_peers(&mut self, now: i64) -> Vec<String> {
+        self.awareness.prune(now)
+    }
+
+    /// Returns the latest presence of each remote peer.
+    pub fn get_peers(&self) -> Vec<PeerPresence<StoryboardPresence>> {
+        self.awareness.peers()
+    }
+
+    /// Returns the peers focused on a specific shot.
+    pub fn get_shot_peers(
+        &self,
+        scene_id: &str,
+        shot_id: &str,
+    ) -> Vec<PeerPresence<StoryboardPresence>> {
+        self.awareness
+            .peers()
+            .into_iter()
+            .filter(|p| p.state.is_on_shot(scene_id, shot_id))
+            .collect()
+    }
+
+    /// Forgets a peer by actor ID (e.g., on disconnect).
+    pub fn remove_peer(&mut self, actor: &str) -> bool {
+        self.awareness.remove_peer(actor)
+    }
+
     // =========================================================================
     // SYNC OPERATIONS
     // =========================================================================
@@ -581,10 +2555,26 @@ impl StoryboardManager {
     /// Merges another document into this one.
     pub fn merge(&mut self, other: &mut Self) -> CollabResult<()> {
         self.cached_state = None;
+        self.obj_cache.clear();
         self.doc.merge(&mut other.doc)?;
+        self.flush_store()?;
         Ok(())
     }
 
+    /// Applies only the given changes from `other`, plus whichever of their
+    /// dependencies this document lacks (see `crate::cherry_pick`).
+    pub fn cherry_pick(
+        &mut self,
+        other: &mut Self,
+        change_hashes: &[ChangeHash],
+    ) -> CollabResult<CherryPickReport> {
+        self.cached_state = None;
+        self.obj_cache.clear();
+        let report = cherry_pick::cherry_pick(&mut self.doc, &mut other.doc, change_hashes)?;
+        self.flush_store()?;
+        Ok(report)
+    }
+
     /// Generates sync message for incremental sync.
     /// Returns None if there are no changes since their_heads.
     pub fn generate_sync_message(&mut self, their_heads: &[ChangeHash]) -> Option<Vec<u8>> {
@@ -599,25 +2589,95 @@ impl StoryboardManager {
         Some(bytes)
     }
 
+    /// Like `generate_sync_message()`, but splits the payload into frames of
+    /// at most `max_bytes` that can be applied one by one (see `crate::sync`).
+    pub fn generate_sync_messages_chunked(
+        &mut self,
+        their_heads: &[ChangeHash],
+        max_bytes: usize,
+    ) -> Vec<Vec<u8>> {
+        sync::chunked_changes(&mut self.doc, their_heads, max_bytes)
+    }
+
+    /// Like `generate_sync_messages_chunked()`, but sends scenes, shots and
+    /// entities before history, comment and uploaded-asset changes (see
+    /// `crate::sync`).
+    pub fn generate_sync_messages_prioritized(
+        &mut self,
+        their_heads: &[ChangeHash],
+        max_bytes: usize,
+    ) -> PrioritizedFrames {
+        sync::prioritized_changes(&mut self.doc, their_heads, max_bytes, HEAVY_SYNC_KEYS)
+    }
+
+    /// Generates the next Automerge sync protocol message for `peer`.
+    /// Returns None when there is nothing to send until the peer replies.
+    ///
+    /// Unlike `generate_sync_message()`, the protocol negotiates what each
+    /// side is missing, so neither side needs to know the other's heads.
+    pub fn generate_protocol_message(&mut self, peer: &mut PeerState) -> Option<Vec<u8>> {
+        self.doc
+            .sync()
+            .generate_sync_message(peer)
+            .map(|message| message.encode())
+    }
+
+    /// Applies an Automerge sync protocol message received from `peer`.
+    pub fn receive_protocol_message(&mut self, peer: &mut PeerState, message: &[u8]) -> CollabResult<()> {
+        let message = automerge::sync::Message::decode(message)
+            .map_err(|e| CollabError::serialization(format!("Invalid sync message: {}", e)))?;
+        self.cached_state = None;
+        self.obj_cache.clear();
+        self.doc.sync().receive_sync_message(peer, message)?;
+        self.flush_store()?;
+        Ok(())
+    }
+
     /// Applies sync message from peer.
     pub fn apply_sync_message(&mut self, msg: &[u8]) -> CollabResult<()> {
         self.cached_state = None;
+        self.obj_cache.clear();
         self.doc.load_incremental(msg)?;
+        self.flush_store()?;
         Ok(())
     }
 
+    /// Generates the changes since `their_heads` as one message cut into
+    /// chunks of at most `max_bytes` (see `crate::chunk`). Unlike
+    /// `generate_sync_messages_chunked()`, this also splits single changes
+    /// larger than the limit. Returns an empty Vec if there are no changes.
+    pub fn generate_sync_chunks(&mut self, their_heads: &[ChangeHash], max_bytes: usize) -> Vec<Vec<u8>> {
+        match self.generate_sync_message(their_heads) {
+            Some(message) => chunk::split_message(&message, max_bytes),
+            None => Vec::new(),
+        }
+    }
+
+    /// Adds a chunk from `generate_sync_chunks()`. Once the last chunk of a
+    /// message arrives, the message is verified and applied; returns true
+    /// if that happened.
+    pub fn apply_sync_chunk(&mut self, chunk: &[u8]) -> CollabResult<bool> {
+        match self.chunks.push(chunk)? {
+            Some(message) => {
+                self.apply_sync_message(&message)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
     // =========================================================================
     // INTERNAL HELPERS - O(1) OPERATIONS
     // =========================================================================
 
     /// O(1) string field setter for entity types.
-    fn set_entity_field_opt_str(
+    pub(crate) fn set_entity_field_opt_str(
         &mut self,
         path: &[&str],
         key: &str,
         value: Option<&str>,
     ) -> CollabResult<()> {
-        self.cached_state = None;
+        self.begin_edit(Action::Edit)?;
         let obj = self.get_obj_at_path(path)?;
         match value {
             Some(v) => self.doc.put(&obj, key, ScalarValue::Str(v.into()))?,
@@ -636,7 +2696,7 @@ impl StoryboardManager {
         key: &str,
         value: Option<&str>,
     ) -> CollabResult<()> {
-        self.cached_state = None;
+        self.begin_edit(Action::Edit)?;
         let shot_obj = self.get_shot_obj(scene_id, shot_id)?;
         match value {
             Some(v) => self.doc.put(&shot_obj, key, ScalarValue::Str(v.into()))?,
@@ -647,64 +2707,116 @@ impl StoryboardManager {
         Ok(())
     }
 
-    /// Appends to asset history with max 20 limit.
-    fn append_to_asset_history(&mut self, path: &[&str], entry: AssetHistory) -> CollabResult<()> {
-        // For simplicity, use update_state. Could be optimized to direct list ops later.
-        let path_vec: Vec<String> = path.iter().map(|s| s.to_string()).collect();
+    /// Appends to the asset history of the entity at `path` (O(1) list
+    /// insert, maintains max 20 entries).
+    pub(crate) fn append_to_asset_history(&mut self, path: &[&str], entry: AssetHistory) -> CollabResult<()> {
+        self.begin_edit(Action::Edit)?;
+        #[cfg(feature = "blob-store")]
+        let entry = {
+            let mut entry = entry;
+            self.intern_image(&mut entry.image)?;
+            entry
+        };
+        let obj = self.get_obj_at_path(path)?;
+        self.prepend_history(&obj, &entry)
+    }
+
+    /// Inserts `entry` at the front of the `history` list of `parent`,
+    /// creating the list if needed, and deletes entries beyond `MAX_HISTORY`
+    /// in one ranged splice. Only the list is touched, so this stays O(1) in
+    /// document size.
+    fn prepend_history<T: Reconcile>(&mut self, parent: &ObjId, entry: &T) -> CollabResult<()> {
+        let list = match self.doc.get(parent, "history")? {
+            Some((Value::Object(ObjType::List), obj)) => obj,
+            _ => self.doc.put_object(parent, "history", ObjType::List)?,
+        };
+        reconcile_insert(&mut self.doc, list.clone(), 0, entry)?;
+
+        let len = self.doc.length(&list);
+        if len > MAX_HISTORY {
+            let excess = (len - MAX_HISTORY) as isize;
+            self.doc.splice(&list, MAX_HISTORY, excess, std::iter::empty())?;
+        }
+        Ok(())
+    }
 
-        self.update_state(move |state| {
-            // Navigate to the entity based on path
-            // Path format: ["processing_stages", "characters", "{id}"]
-            if path_vec.len() >= 3 && path_vec[0] == "processing_stages" {
-                let collection = &path_vec[1];
-                let id = &path_vec[2];
+    /// Appends to the `comments` list of `parent`, creating the list if needed.
+    /// Only the thread itself is read; the rest of the document is untouched.
+    fn append_comment(&mut self, parent: &ObjId, comment: Comment) -> CollabResult<()> {
+        self.begin_edit(Action::Comment)?;
+        let existing: Option<Vec<Comment>> = hydrate_prop(&self.doc, parent, "comments")?;
+        let mut comments = existing.unwrap_or_default();
+        comment::validate_new_comment(&comments, &comment)?;
+
+        let list = match self.doc.get(parent, "comments")? {
+            Some((Value::Object(ObjType::List), obj)) => obj,
+            _ => self.doc.put_object(parent, "comments", ObjType::List)?,
+        };
+        let len = self.doc.length(&list);
+        reconcile_insert(&mut self.doc, list.clone(), len, &comment)?;
+
+        // Trim the oldest threads beyond the max, last index first
+        let id = comment.id.clone();
+        comments.push(comment);
+        for index in comment::trim_indices(&comments, &id).into_iter().rev() {
+            self.doc.delete(&list, index)?;
+        }
+        Ok(())
+    }
 
-                match collection.as_str() {
-                    "characters" => {
-                        if let Some(entity) = state.processing_stages.characters.get_mut(id) {
-                            entity.history.insert(0, entry);
-                            if entity.history.len() > 20 {
-                                entity.history.truncate(20);
-                            }
-                        }
-                    }
-                    "props" => {
-                        if let Some(entity) = state.processing_stages.props.get_mut(id) {
-                            entity.history.insert(0, entry);
-                            if entity.history.len() > 20 {
-                                entity.history.truncate(20);
-                            }
-                        }
-                    }
-                    "sets" => {
-                        if let Some(entity) = state.processing_stages.sets.get_mut(id) {
-                            entity.history.insert(0, entry);
-                            if entity.history.len() > 20 {
-                                entity.history.truncate(20);
-                            }
-                        }
-                    }
-                    _ => {}
-                }
+    /// Sets `resolved` on a single comment in the `comments` list of `parent`.
+    fn set_comment_resolved(
+        &mut self,
+        parent: &ObjId,
+        comment_id: &str,
+        resolved: bool,
+    ) -> CollabResult<()> {
+        self.begin_edit(Action::Comment)?;
+        let existing: Option<Vec<Comment>> = hydrate_prop(&self.doc, parent, "comments")?;
+        let index = existing
+            .unwrap_or_default()
+            .iter()
+            .position(|c| c.id == comment_id)
+            .ok_or_else(|| CollabError::comment_not_found(comment_id))?;
+
+        let list = self.get_obj_at_key(parent, "comments")?;
+        let comment_obj = match self.doc.get(&list, index)? {
+            Some((Value::Object(_), obj)) => obj,
+            _ => {
+                return Err(CollabError::schema_violation(format!(
+                    "comment '{}' is not an object",
+                    comment_id
+                )))
             }
-        })
+        };
+        self.doc
+            .put(&comment_obj, "resolved", ScalarValue::Boolean(resolved))?;
+        Ok(())
     }
 
-    /// Gets ObjId at a path.
-    fn get_obj_at_path(&self, path: &[&str]) -> CollabResult<ObjId> {
-        let mut current = ROOT;
-        for key in path {
-            current = self.get_obj_at_key(&current, key)?;
+    /// Gets ObjId at a path, starting from the longest cached prefix and
+    /// caching every object resolved on the way.
+    fn get_obj_at_path(&mut self, path: &[&str]) -> CollabResult<ObjId> {
+        let mut depth = path.len();
+        let mut current = loop {
+            if depth == 0 {
+                break ROOT;
+            }
+            if let Some(obj) = self.obj_cache.get(&path[..depth]) {
+                break obj;
+            }
+            depth -= 1;
+        };
+        for end in depth + 1..=path.len() {
+            current = self.get_obj_at_key(&current, path[end - 1])?;
+            self.obj_cache.insert(&path[..end], current.clone());
         }
         Ok(current)
     }
 
     /// Gets ObjId for a shot.
-    fn get_shot_obj(&self, scene_id: &str, shot_id: &str) -> CollabResult<ObjId> {
-        let scenes_obj = self.get_obj_at_key(&ROOT, "scenes")?;
-        let scene_obj = self.get_obj_at_key(&scenes_obj, scene_id)?;
-        let shots_obj = self.get_obj_at_key(&scene_obj, "shots")?;
-        self.get_obj_at_key(&shots_obj, shot_id)
+    fn get_shot_obj(&mut self, scene_id: &str, shot_id: &str) -> CollabResult<ObjId> {
+        self.get_obj_at_path(&["scenes", scene_id, "shots", shot_id])
     }
 
     /// Gets an object ID at a map key.
@@ -734,6 +2846,7 @@ impl Default for StoryboardManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::comment::MAX_COMMENTS;
 
     #[test]
     fn test_new_manager() {
@@ -743,6 +2856,20 @@ mod tests {
         assert!(state.processing_stages.characters.is_empty());
     }
 
+    #[test]
+    fn test_new_with_seed_single_change() {
+        let mut seed = StoryboardRoot::new("sb-1").with_title("New project");
+        seed.scenes.insert("scene-1".to_string(), Scene::new("scene-1", 1));
+        seed.scene_order.push("scene-1".to_string());
+
+        let mut manager = StoryboardManager::new_with_seed(seed).unwrap();
+        assert_eq!(manager.get_change_history().len(), 1);
+
+        let state = manager.get_state().unwrap();
+        assert_eq!(state.title, "New project");
+        assert_eq!(state.scene_order, vec!["scene-1"]);
+    }
+
     #[test]
     fn test_create_character() {
         let mut manager = StoryboardManager::new();
@@ -755,6 +2882,25 @@ mod tests {
         assert_eq!(retrieved.unwrap().name, "John");
     }
 
+    #[test]
+    fn test_partial_hydration() {
+        let mut manager = StoryboardManager::new();
+        manager.create_characters("c1", Character::new("c1", "Alice")).unwrap();
+        for i in 0..3 {
+            let id = format!("scene-{}", i);
+            manager.create_scene(&id, Scene::new(&id, i + 1)).unwrap();
+        }
+
+        let mut loaded = StoryboardManager::from_bytes(&manager.save()).unwrap();
+        let scene = loaded.hydrate_scene("scene-1").unwrap().unwrap();
+        assert_eq!(scene, manager.get_scene("scene-1").unwrap().unwrap());
+        assert!(loaded.hydrate_scene("missing").unwrap().is_none());
+
+        let stages = loaded.hydrate_entities_only().unwrap();
+        assert_eq!(stages.characters["c1"].name, "Alice");
+        assert!(loaded.cached_state.is_none());
+    }
+
     #[test]
     fn test_create_prop() {
         let mut manager = StoryboardManager::new();
@@ -857,6 +3003,647 @@ mod tests {
         assert_eq!(retrieved.ref_shot_id, Some(-1));
     }
 
+    #[cfg(feature = "search")]
+    #[test]
+    fn test_search_scenes_and_shots() {
+        let mut manager = StoryboardManager::new();
+        let scene = Scene::new("scene-1", 1).with_header("EXT. HARBOR - NIGHT").with_content("Rain on the docks.");
+        manager.create_scene("scene-1", scene).unwrap();
+        let mut shot = Shot::new("shot-1", 1).with_image_prompt("Lighthouse beam");
+        shot.visual_description = "Wide shot of the harbor in the rain".to_string();
+        manager.create_shot("scene-1", "shot-1", shot).unwrap();
+
+        let paths: Vec<Vec<String>> = manager.search("rain").unwrap().into_iter().map(|(p, _)| p).collect();
+        assert_eq!(paths.len(), 2);
+        assert!(paths.contains(&vec!["scene-1".to_string(), "shot-1".to_string()]));
+
+        let hits = manager.search("lighthouse harbor").unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].0, vec!["scene-1", "shot-1"]);
+    }
+
+    #[cfg(feature = "script-import")]
+    #[test]
+    fn test_import_fountain_appends_scenes() {
+        let mut manager = StoryboardManager::new();
+        manager.create_scene("scene-1", Scene::new("scene-1", 1)).unwrap();
+        manager.create_characters("char-1", Character::new("char-1", "Richie")).unwrap();
+
+        let script = "INT. OFFICE - DAY\n\nRICHIE\nMorning.\n\nMAYA\nIs it?\n\n\
+                      EXT. ROOF - NIGHT\n\nWind.\n";
+        let ids = manager.import_fountain(script).unwrap();
+        assert_eq!(ids.len(), 2);
+
+        let state = manager.get_state().unwrap();
+        assert_eq!(state.scene_order[1..], ids[..]);
+        let office = &state.scenes[&ids[0]];
+        assert_eq!(office.scene_number, 2);
+        assert_eq!(office.header, "INT. OFFICE - DAY");
+        assert_eq!(office.characters_present[0], "char-1");
+        let maya = &state.processing_stages.characters[&office.characters_present[1]];
+        assert_eq!(maya.name, "MAYA");
+        assert_eq!(state.processing_stages.characters.order().len(), 2);
+        assert_eq!(state.scenes[&ids[1]].scene_number, 3);
+
+        assert!(manager.import_fountain("No headings here.").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_delete_characters_cascade_policies() {
+        let setup = || {
+            let mut manager = StoryboardManager::new();
+            manager.create_characters("c1", Character::new("c1", "Richie").with_tag("@richie")).unwrap();
+            let mut scene = Scene::new("s1", 1);
+            scene.characters_present = vec!["c1".to_string()];
+            scene.character_looks.insert("@richie".to_string(), CharacterLook::default());
+            let mut shot = Shot::new("sh1", 1);
+            shot.assets_used = vec!["@richie".to_string(), "@maya".to_string()];
+            shot.subject = Some("@richie".to_string());
+            scene.shots.insert("sh1".to_string(), shot);
+            manager.create_scene("s1", scene).unwrap();
+            manager
+        };
+
+        let mut manager = setup();
+        let err = manager.delete_characters_cascade("c1", DeletePolicy::Deny).unwrap_err();
+        assert!(err.to_string().contains("1 scene(s) and 1 shot(s)"));
+        assert!(manager.get_characters("c1").unwrap().is_some());
+
+        manager.delete_characters_cascade("c1", DeletePolicy::Detach).unwrap();
+        let state = manager.get_state().unwrap();
+        assert!(state.processing_stages.characters.order().is_empty());
+        let scene = &state.scenes["s1"];
+        assert!(scene.characters_present.is_empty());
+        assert!(scene.character_looks.contains_key("@richie"));
+        assert_eq!(scene.shots["sh1"].assets_used, vec!["@maya"]);
+        assert_eq!(scene.shots["sh1"].subject, None);
+
+        let mut manager = setup();
+        manager.delete_characters_cascade("c1", DeletePolicy::Cascade).unwrap();
+        assert!(manager.get_state().unwrap().scenes["s1"].character_looks.is_empty());
+        assert!(manager.delete_characters_cascade("c1", DeletePolicy::Cascade).is_err());
+    }
+
+    #[test]
+    fn test_custom_collection_crud() {
+        let mut manager = StoryboardManager::new();
+        let car = CustomEntity::new("v1", "Batmobile").with_tag("@batmobile");
+        manager.create_custom_entity("vehicles", "v1", car).unwrap();
+        manager.create_custom_entity("vehicles", "v2", CustomEntity::new("v2", "Tumbler")).unwrap();
+        assert!(manager.create_custom_entity(" ", "x", CustomEntity::default()).is_err());
+
+        manager.set_custom_entity_image("vehicles", "v1", Some("https://cdn/v1.png")).unwrap();
+        for i in 0..25 {
+            let entry = AssetHistory::new(format!("h-{}", i), format!("img-{}", i), "prompt");
+            manager.append_custom_entity_history("vehicles", "v1", entry).unwrap();
+        }
+        manager.reorder_custom_entities("vehicles", vec!["v2".to_string(), "v1".to_string()]).unwrap();
+        assert!(manager.reorder_custom_entities("weapons", Vec::new()).is_err());
+        manager.rename_tag("@batmobile", "@bat").unwrap();
+
+        let mut loaded = StoryboardManager::from_bytes(&manager.save()).unwrap();
+        assert_eq!(loaded.get_custom_collections().unwrap(), vec!["vehicles"]);
+        let car = loaded.get_custom_entity("vehicles", "v1").unwrap().unwrap();
+        assert_eq!(car.image.as_deref(), Some("https://cdn/v1.png"));
+        assert_eq!(car.tag.as_deref(), Some("@bat"));
+        assert_eq!(car.history.len(), 20);
+        assert_eq!(car.history[0].id, "h-24");
+        let vehicles = loaded.get_custom_collection("vehicles").unwrap().unwrap();
+        assert_eq!(vehicles.order, vec!["v2", "v1"]);
+
+        loaded.delete_custom_entity("vehicles", "v2").unwrap();
+        assert_eq!(loaded.get_custom_collection("vehicles").unwrap().unwrap().order, vec!["v1"]);
+        loaded.delete_custom_collection("vehicles").unwrap();
+        assert!(loaded.get_custom_collections().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rename_tag_single_change() {
+        let mut manager = StoryboardManager::new();
+        manager.create_characters("c1", Character::new("c1", "Richie").with_tag("@richie")).unwrap();
+        manager.create_characters("c2", Character::new("c2", "Maya").with_tag("@maya")).unwrap();
+        manager.create_scene("s1", Scene::new("s1", 1)).unwrap();
+        let look = CharacterLook {
+            description: "Tired".to_string(),
+            ..Default::default()
+        };
+        manager.set_character_look("s1", "@richie", look).unwrap();
+
+        assert!(matches!(manager.rename_tag("@ghost", "@g"), Err(CollabError::FieldNotFound(_))));
+        assert!(manager.rename_tag("@richie", "@maya").is_err());
+
+        let report = manager.rename_tag("@richie", "@rich").unwrap();
+        assert_eq!(report.paths.len(), 2);
+        let history = manager.get_change_history();
+        assert_eq!(history.last().unwrap().message.as_deref(), Some("Rename tag @richie to @rich"));
+
+        let state = manager.get_state().unwrap();
+        assert_eq!(state.processing_stages.characters["c1"].tag.as_deref(), Some("@rich"));
+        let looks = &state.scenes["s1"].character_looks;
+        assert_eq!(looks.keys().collect::<Vec<_>>(), vec!["@rich"]);
+        assert_eq!(looks["@rich"].description, "Tired");
+    }
+
+    #[test]
+    fn test_duplicate_scene_deep_copy() {
+        let mut manager = StoryboardManager::new();
+        manager.create_scene("scene-1", Scene::new("scene-1", 1).with_title("Opening")).unwrap();
+        manager.create_shot("scene-1", "shot-1", Shot::new("shot-1", 1).with_image_prompt("Wide")).unwrap();
+        manager.create_shot("scene-1", "shot-2", Shot::new("shot-2", 2)).unwrap();
+        let look = CharacterLook {
+            description: "Tired".to_string(),
+            ..Default::default()
+        };
+        manager.set_character_look("scene-1", "@richie", look).unwrap();
+
+        manager.duplicate_scene("scene-1", "scene-2").unwrap();
+        let state = manager.get_state().unwrap();
+        assert_eq!(state.scene_order, vec!["scene-1", "scene-2"]);
+        let (original, copy) = (&state.scenes["scene-1"], &state.scenes["scene-2"]);
+        assert_eq!(copy.id, "scene-2");
+        assert_eq!(copy.scene_number, 2);
+        assert_eq!(copy.title, "Opening");
+        assert_eq!(copy.character_looks["@richie"].description, "Tired");
+
+        // Shots are copied in order under new IDs
+        assert_eq!(copy.shots.order().len(), 2);
+        assert!(copy.shots.order().iter().all(|id| !original.shots.contains_key(id)));
+        let first = &copy.shots[&copy.shots.order()[0]];
+        assert_eq!(first.id, copy.shots.order()[0]);
+        assert_eq!(first.image_prompt, "Wide");
+        assert_eq!(original.shots.order(), ["shot-1", "shot-2"]);
+
+        assert!(manager.duplicate_scene("scene-1", "scene-2").is_err());
+        assert!(manager.duplicate_scene("missing", "scene-3").is_err());
+    }
+
+    #[test]
+    fn test_move_shot_between_scenes() {
+        let mut manager = StoryboardManager::new();
+        for scene_id in ["scene-1", "scene-2"] {
+            manager.create_scene(scene_id, Scene::new(scene_id, 1)).unwrap();
+        }
+        let shot = Shot::new("shot-1", 1).with_image_prompt("Close-up");
+        manager.create_shot("scene-1", "shot-1", shot).unwrap();
+        manager.create_shot("scene-2", "shot-a", Shot::new("shot-a", 1)).unwrap();
+        manager.create_shot("scene-2", "shot-b", Shot::new("shot-b", 2)).unwrap();
+        let changes = manager.get_change_history().len();
+
+        manager.move_shot("scene-1", "scene-2", "shot-1", Some(1)).unwrap();
+        assert_eq!(manager.get_change_history().len(), changes + 1);
+        let state = manager.get_state().unwrap();
+        assert!(state.scenes["scene-1"].shots.is_empty());
+        assert!(state.scenes["scene-1"].shots.order().is_empty());
+        assert_eq!(state.scenes["scene-2"].shots.order(), ["shot-a", "shot-1", "shot-b"]);
+        assert_eq!(state.scenes["scene-2"].shots["shot-1"].image_prompt, "Close-up");
+
+        // Within one scene it reorders; bad input changes nothing
+        manager.move_shot("scene-2", "scene-2", "shot-1", None).unwrap();
+        let scene = manager.get_scene("scene-2").unwrap().unwrap();
+        assert_eq!(scene.shots.order(), ["shot-a", "shot-b", "shot-1"]);
+        assert!(manager.move_shot("scene-2", "scene-1", "missing", None).is_err());
+        assert!(manager.move_shot("scene-2", "scene-1", "shot-1", Some(5)).is_err());
+    }
+
+    #[test]
+    fn test_transaction_rolls_back_on_error() {
+        let mut manager = StoryboardManager::new();
+        manager.create_scene("scene-1", Scene::new("scene-1", 1)).unwrap();
+        manager.create_shot("scene-1", "shot-1", Shot::new("shot-1", 1)).unwrap();
+        let changes = manager.get_change_history().len();
+
+        let result = manager.transaction(Some("Add and fail"), |txn| {
+            txn.create_scene("scene-2", Scene::new("scene-2", 2))?;
+            txn.set_shot_image("scene-1", "shot-1", Some("a.png"))?;
+            txn.set_shot_image("scene-1", "missing", Some("b.png"))
+        });
+        assert!(result.is_err());
+        assert_eq!(manager.get_change_history().len(), changes);
+        assert!(manager.get_scene("scene-2").unwrap().is_none());
+        let shot = manager.get_shot("scene-1", "shot-1").unwrap().unwrap();
+        assert_eq!(shot.image, None);
+
+        let scene = Scene::new("scene-2", 2);
+        manager.transaction(Some("Add scene"), |txn| txn.create_scene("scene-2", scene)).unwrap();
+        assert_eq!(manager.get_change_history().len(), changes + 1);
+        assert!(manager.get_scene("scene-2").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_renumber_shots_and_scenes() {
+        let mut manager = StoryboardManager::new();
+        for (scene_id, number) in [("scene-1", 4), ("scene-2", 9)] {
+            manager.create_scene(scene_id, Scene::new(scene_id, number)).unwrap();
+        }
+        for (shot_id, number) in [("shot-a", 1), ("shot-b", 2), ("shot-c", 3)] {
+            manager.create_shot("scene-1", shot_id, Shot::new(shot_id, number)).unwrap();
+        }
+        let order = vec!["shot-c".to_string(), "shot-a".to_string(), "shot-b".to_string()];
+        manager.reorder_shots("scene-1", order).unwrap();
+        let changes = manager.get_change_history().len();
+
+        assert_eq!(manager.renumber_shots("scene-1").unwrap(), 3);
+        assert_eq!(manager.renumber_scenes().unwrap(), 2);
+        assert_eq!(manager.get_change_history().len(), changes + 2);
+        let state = manager.get_state().unwrap();
+        let numbers: Vec<i32> = ["shot-a", "shot-b", "shot-c"]
+            .iter()
+            .map(|id| state.scenes["scene-1"].shots[*id].shot_number)
+            .collect();
+        assert_eq!(numbers, vec![2, 3, 1]);
+        assert_eq!(state.scenes["scene-2"].scene_number, 2);
+        assert_eq!(manager.renumber_shots("scene-1").unwrap(), 0);
+        assert!(manager.renumber_shots("missing").is_err());
+
+        // Auto-renumber follows reorders and moves
+        manager.set_auto_renumber(true);
+        manager.reorder_scenes(vec!["scene-2".to_string(), "scene-1".to_string()]).unwrap();
+        manager.move_shot("scene-1", "scene-2", "shot-c", None).unwrap();
+        let state = manager.get_state().unwrap();
+        assert_eq!(state.scenes["scene-2"].scene_number, 1);
+        assert_eq!(state.scenes["scene-1"].scene_number, 2);
+        assert_eq!(state.scenes["scene-1"].shots["shot-a"].shot_number, 1);
+        assert_eq!(state.scenes["scene-2"].shots["shot-c"].shot_number, 1);
+    }
+
+    #[test]
+    fn test_fractional_scene_order() {
+        let mut manager = StoryboardManager::new();
+        for id in ["scene-1", "scene-2", "scene-3"] {
+            manager.create_scene(id, Scene::new(id, 1)).unwrap();
+        }
+        manager.set_fractional_order(true).unwrap();
+        let ids = |list: &[&str]| list.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+
+        // Concurrent moves of different scenes both survive the merge
+        let mut peer = StoryboardManager::from_bytes(&manager.save()).unwrap();
+        manager.move_scene_between("scene-3", None, Some("scene-1")).unwrap();
+        peer.move_scene_between("scene-1", Some("scene-2"), None).unwrap();
+        manager.merge(&mut peer).unwrap();
+        assert_eq!(manager.get_scene_order().unwrap(), ids(&["scene-3", "scene-2", "scene-1"]));
+        let state = manager.get_state().unwrap();
+        assert_eq!(state.scene_order, ids(&["scene-1", "scene-2", "scene-3"]));
+
+        // New scenes go last; reorders rekey only what moved
+        manager.create_scene("scene-4", Scene::new("scene-4", 4)).unwrap();
+        let key = |manager: &mut StoryboardManager, id: &str| {
+            manager.get_scene(id).unwrap().unwrap().order_key
+        };
+        let key_2 = key(&mut manager, "scene-2");
+        manager.reorder_scenes(ids(&["scene-4", "scene-3", "scene-2", "scene-1"])).unwrap();
+        assert_eq!(key(&mut manager, "scene-2"), key_2);
+        assert!(manager.move_scene_between("scene-2", Some("scene-1"), Some("scene-4")).is_err());
+
+        // Switching back writes the display order to the list
+        manager.set_fractional_order(false).unwrap();
+        let state = manager.get_state().unwrap();
+        assert_eq!(state.scene_order, ids(&["scene-4", "scene-3", "scene-2", "scene-1"]));
+        assert!(state.scenes.values().all(|scene| scene.order_key.is_none()));
+        manager.move_scene_between("scene-1", None, Some("scene-4")).unwrap();
+        assert_eq!(manager.get_scene_order().unwrap()[0], "scene-1");
+    }
+
+    #[test]
+    fn test_scene_locks() {
+        let mut manager = StoryboardManager::new();
+        manager.create_scene("scene-3", Scene::new("scene-3", 3)).unwrap();
+
+        assert!(manager.claim_scene("scene-3", "bob", 1_000, 100).unwrap());
+        assert!(!manager.claim_scene("scene-3", "alice", 1_000, 500).unwrap());
+        // Renewal keeps the original claim time
+        assert!(manager.claim_scene("scene-3", "bob", 1_000, 900).unwrap());
+        let mut loaded = StoryboardManager::from_bytes(&manager.save()).unwrap();
+        let locks = loaded.get_active_locks(1_000).unwrap();
+        assert_eq!((locks["scene-3"].claimed_at, locks["scene-3"].expires_at), (100, 1_900));
+
+        assert!(loaded.get_active_locks(1_900).unwrap().is_empty());
+        assert!(loaded.claim_scene("scene-3", "alice", 1_000, 2_000).unwrap());
+        assert!(!loaded.release_scene("scene-3", "bob").unwrap());
+        assert!(loaded.release_scene("scene-3", "alice").unwrap());
+        assert!(loaded.claim_scene("missing", "alice", 1_000, 2_000).is_err());
+    }
+
+    #[test]
+    fn test_sweep_releases_stale_locks() {
+        let mut manager = StoryboardManager::new();
+        for id in ["scene-1", "scene-2", "scene-3"] {
+            manager.create_scene(id, Scene::new(id, 1)).unwrap();
+        }
+        manager.claim_scene("scene-1", "bob", 1_000, 0).unwrap();
+        manager.claim_scene("scene-2", "alice", 10_000, 0).unwrap();
+        manager.claim_scene("scene-3", "carol", 10_000, 0).unwrap();
+        manager.delete_scene("scene-3").unwrap();
+        let changes = manager.get_change_history().len();
+
+        let report = manager.sweep(5_000).unwrap();
+        assert!(report.stalled.is_empty());
+        assert_eq!(report.released_locks, vec!["scene-1", "scene-3"]);
+        assert_eq!(manager.get_change_history().len(), changes + 1);
+        let locks = manager.get_state().unwrap().scene_locks;
+        assert_eq!(locks.keys().collect::<Vec<_>>(), vec!["scene-2"]);
+        assert!(manager.sweep(5_000).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_freeze_blocks_local_edits() {
+        let mut manager = StoryboardManager::new();
+        manager.create_scene("scene-1", Scene::new("scene-1", 1)).unwrap();
+        manager.freeze().unwrap();
+
+        let mut loaded = StoryboardManager::from_bytes(&manager.save()).unwrap();
+        assert!(loaded.is_frozen().unwrap());
+        assert!(matches!(
+            loaded.create_scene("scene-2", Scene::new("scene-2", 2)),
+            Err(CollabError::DocumentFrozen)
+        ));
+        assert!(matches!(
+            loaded.set_scene_title("scene-1", "Renamed"),
+            Err(CollabError::DocumentFrozen)
+        ));
+
+        loaded.set_admin(true);
+        loaded.set_scene_title("scene-1", "Renamed").unwrap();
+        loaded.set_admin(false);
+        loaded.unfreeze().unwrap();
+        loaded.create_scene("scene-2", Scene::new("scene-2", 2)).unwrap();
+    }
+
+    #[test]
+    fn test_trash_and_restore() {
+        let mut manager = StoryboardManager::new();
+        for (i, scene_id) in ["scene-1", "scene-2", "scene-3"].into_iter().enumerate() {
+            manager.create_scene(scene_id, Scene::new(scene_id, i as i32 + 1)).unwrap();
+        }
+        for shot_id in ["shot-a", "shot-b"] {
+            manager.create_shot("scene-1", shot_id, Shot::new(shot_id, 1)).unwrap();
+        }
+
+        manager.trash_shot("scene-1", "shot-a", 100, Some("alice")).unwrap();
+        manager.trash_scene("scene-2", 200, None).unwrap();
+        let mut loaded = StoryboardManager::from_bytes(&manager.save()).unwrap();
+        let state = loaded.get_state().unwrap();
+        assert_eq!(state.scene_order, vec!["scene-1", "scene-3"]);
+        assert_eq!(state.scenes["scene-1"].shots.order(), ["shot-b"]);
+        let trashed = &state.trash.shots["scene-1"]["shot-a"];
+        assert_eq!((trashed.deleted_at, trashed.deleted_by.as_deref()), (100, Some("alice")));
+
+        // Restores go back to their old positions
+        loaded.restore_scene("scene-2").unwrap();
+        loaded.restore_shot("scene-1", "shot-a").unwrap();
+        let state = loaded.get_state().unwrap();
+        assert_eq!(state.scene_order, vec!["scene-1", "scene-2", "scene-3"]);
+        assert_eq!(state.scenes["scene-1"].shots.order(), ["shot-a", "shot-b"]);
+        assert!(state.trash.is_empty());
+        assert!(loaded.restore_scene("scene-2").is_err());
+
+        // Only entries older than the cutoff are emptied
+        loaded.trash_scene("scene-2", 200, None).unwrap();
+        loaded.trash_scene("scene-3", 300, None).unwrap();
+        assert_eq!(loaded.empty_trash(250).unwrap(), 1);
+        let trash = loaded.get_trash().unwrap();
+        assert_eq!(trash.scenes.keys().collect::<Vec<_>>(), vec!["scene-3"]);
+    }
+
+    #[test]
+    fn test_shot_status_batch_is_one_change() {
+        let mut manager = StoryboardManager::new();
+        manager.create_scene("scene-1", Scene::new("scene-1", 1)).unwrap();
+        for i in 1..=3 {
+            let id = format!("shot-{}", i);
+            manager.create_shot("scene-1", &id, Shot::new(&id, i)).unwrap();
+        }
+        let changes = manager.get_change_history().len();
+
+        let updates = (1..=3)
+            .map(|i| ("scene-1".to_string(), format!("shot-{}", i), "processing".to_string()))
+            .collect();
+        manager.set_shot_statuses(updates).unwrap();
+        assert_eq!(manager.get_change_history().len(), changes + 1);
+        let shot = manager.get_shot("scene-1", "shot-3").unwrap().unwrap();
+        assert_eq!(shot.generation_status, Some("processing".to_string()));
+
+        // A missing shot leaves every status untouched
+        let updates = vec![
+            ("scene-1".to_string(), "shot-1".to_string(), "completed".to_string()),
+            ("scene-1".to_string(), "missing".to_string(), "completed".to_string()),
+        ];
+        assert!(manager.set_shot_statuses(updates).is_err());
+        let shot = manager.get_shot("scene-1", "shot-1").unwrap().unwrap();
+        assert_eq!(shot.generation_status, Some("processing".to_string()));
+    }
+
+    #[test]
+    fn test_obj_cache_follows_deletes_and_merges() {
+        let mut manager = StoryboardManager::new();
+        manager.create_scene("scene-1", Scene::new("scene-1", 1)).unwrap();
+        manager.create_shot("scene-1", "shot-1", Shot::new("shot-1", 1)).unwrap();
+        manager.set_shot_generation_status("scene-1", "shot-1", Some("processing")).unwrap();
+
+        // A recreated shot is a new object; the cached one must not be reused
+        manager.delete_shot("scene-1", "shot-1").unwrap();
+        assert!(manager.set_shot_generation_status("scene-1", "shot-1", Some("x")).is_err());
+        manager.create_shot("scene-1", "shot-1", Shot::new("shot-1", 1)).unwrap();
+        manager.set_shot_generation_status("scene-1", "shot-1", Some("completed")).unwrap();
+        let shot = manager.get_shot("scene-1", "shot-1").unwrap().unwrap();
+        assert_eq!(shot.generation_status, Some("completed".to_string()));
+
+        // A scene deleted by a peer is no longer reachable after merging
+        let mut peer = StoryboardManager::from_bytes(&manager.save()).unwrap();
+        peer.delete_scene("scene-1").unwrap();
+        manager.merge(&mut peer).unwrap();
+        assert!(manager.set_shot_generation_status("scene-1", "shot-1", Some("x")).is_err());
+    }
+
+    #[test]
+    fn test_presence_per_shot_and_timeout() {
+        let mut alice = StoryboardManager::new();
+        let mut bob = StoryboardManager::new();
+        let mut viewer = StoryboardManager::new();
+        viewer.set_peer_timeout(5_000);
+
+        let msg = alice.set_local_presence(
+            "Alice",
+            Some("scene-1"),
+            Some("shot-2"),
+            PresenceMode::Editing,
+            None,
+        );
+        viewer.apply_presence_message(&msg, 1_000).unwrap();
+        let msg = bob.set_local_presence("Bob", Some("scene-1"), None, PresenceMode::Viewing, None);
+        viewer.apply_presence_message(&msg, 4_000).unwrap();
+
+        let on_shot = viewer.get_shot_peers("scene-1", "shot-2");
+        assert_eq!(on_shot.len(), 1);
+        assert_eq!(on_shot[0].state.user, "Alice");
+        assert_eq!(on_shot[0].state.mode, PresenceMode::Editing);
+
+        assert_eq!(viewer.prune_peers(6_500), vec![alice.actor_id()]);
+        assert_eq!(viewer.get_peers().len(), 1);
+    }
+
+    #[test]
+    fn test_shot_render_job_fields() {
+        let mut manager = StoryboardManager::new();
+        manager.create_scene("scene-1", Scene::new("scene-1", 1)).unwrap();
+        manager
+            .create_shot("scene-1", "shot-1", Shot::new("shot-1", 1))
+            .unwrap();
+
+        manager
+            .set_shot_provider("scene-1", "shot-1", Some("fal"))
+            .unwrap();
+        manager
+            .set_shot_job_id("scene-1", "shot-1", Some("job-9"))
+            .unwrap();
+
+        let shot = manager.get_shot("scene-1", "shot-1").unwrap().unwrap();
+        assert_eq!(shot.provider.as_deref(), Some("fal"));
+        assert_eq!(
+            manager.find_shot_by_job_id("job-9").unwrap(),
+            Some(("scene-1".to_string(), "shot-1".to_string()))
+        );
+        assert_eq!(manager.find_shot_by_job_id("job-0").unwrap(), None);
+    }
+
+    #[test]
+    fn test_cross_document_links() {
+        let mut sequence = crate::SequenceManager::new();
+        let node = crate::GenerationNode::new("gen-1", "t2i").with_prompt("Wide harbor");
+        sequence.create_and_append("gen-1", node).unwrap();
+        let mut manager = StoryboardManager::new();
+        manager.create_scene("scene-1", Scene::new("scene-1", 1)).unwrap();
+        manager.create_shot("scene-1", "shot-1", Shot::new("shot-1", 1)).unwrap();
+
+        let gen_ref = DocRef::new("seq-1", "gen-1");
+        manager.set_shot_generation_ref("scene-1", "shot-1", Some(&gen_ref)).unwrap();
+        sequence.set_storyboard_ref("gen-1", Some(&DocRef::new("sb-1", "shot-1"))).unwrap();
+
+        // Shot -> generation node
+        let mut nodes = |r: &DocRef| {
+            assert_eq!(r.doc_id, "seq-1");
+            sequence.get_node(&r.node_id)
+        };
+        let node = manager.resolve_generation_ref("scene-1", "shot-1", &mut nodes).unwrap();
+        assert_eq!(node.unwrap().prompt, "Wide harbor");
+
+        // Generation node -> shot, after a reload
+        let mut loaded = StoryboardManager::from_bytes(&manager.save()).unwrap();
+        let mut shots = |r: &DocRef| loaded.find_shot(&r.node_id);
+        let (scene_id, shot) = sequence.resolve_storyboard_ref("gen-1", &mut shots).unwrap().unwrap();
+        assert_eq!(scene_id, "scene-1");
+        assert_eq!(shot.generation_ref, Some(gen_ref));
+
+        manager.set_shot_generation_ref("scene-1", "shot-1", None).unwrap();
+        let mut never = |_: &DocRef| -> CollabResult<Option<()>> { panic!("no link to resolve") };
+        assert_eq!(manager.resolve_generation_ref("scene-1", "shot-1", &mut never).unwrap(), None);
+        assert!(manager.resolve_generation_ref("scene-1", "missing", &mut never).is_err());
+        assert!(manager.find_shot("missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_shot_and_scene_comments() {
+        let mut manager = StoryboardManager::new();
+        manager.create_scene("scene-1", Scene::new("scene-1", 1)).unwrap();
+        manager
+            .create_shot("scene-1", "shot-1", Shot::new("shot-1", 1))
+            .unwrap();
+
+        manager
+            .add_shot_comment(
+                "scene-1",
+                "shot-1",
+                Comment::new("c1", "alice", "@richie looks off-model").with_mentions_from_body(),
+            )
+            .unwrap();
+        manager
+            .add_shot_comment(
+                "scene-1",
+                "shot-1",
+                Comment::new("c2", "bob", "Fixing").with_parent("c1"),
+            )
+            .unwrap();
+        manager
+            .resolve_shot_comment("scene-1", "shot-1", "c1", true)
+            .unwrap();
+        manager
+            .add_scene_comment("scene-1", Comment::new("s1", "carol", "Pacing is slow"))
+            .unwrap();
+
+        let bytes = manager.save();
+        let mut loaded = StoryboardManager::from_bytes(&bytes).unwrap();
+        let shot = loaded.get_shot("scene-1", "shot-1").unwrap().unwrap();
+        let comments = shot.comments.unwrap();
+        assert_eq!(comments.len(), 2);
+        assert!(comments[0].resolved);
+        assert_eq!(comments[0].mentions, vec!["@richie"]);
+        assert_eq!(comments[1].parent_id.as_deref(), Some("c1"));
+        let scene = loaded.get_scene("scene-1").unwrap().unwrap();
+        assert_eq!(scene.comments.unwrap()[0].body, "Pacing is slow");
+
+        assert!(matches!(
+            loaded.resolve_scene_comment("scene-1", "missing", true),
+            Err(CollabError::CommentNotFound(_))
+        ));
+        assert!(loaded
+            .add_shot_comment("scene-1", "shot-1", Comment::new("c1", "x", "dup"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_shot_comments_trimmed_to_max() {
+        let mut manager = StoryboardManager::new();
+        manager.create_scene("scene-1", Scene::new("scene-1", 1)).unwrap();
+        manager
+            .create_shot("scene-1", "shot-1", Shot::new("shot-1", 1))
+            .unwrap();
+        for i in 0..MAX_COMMENTS + 3 {
+            manager
+                .add_shot_comment("scene-1", "shot-1", Comment::new(format!("c{}", i), "a", "b"))
+                .unwrap();
+        }
+        let shot = manager.get_shot("scene-1", "shot-1").unwrap().unwrap();
+        let comments = shot.comments.unwrap();
+        assert_eq!(comments.len(), MAX_COMMENTS);
+        assert_eq!(comments[0].id, "c3");
+
+        // A reply keeps its (oldest) thread; the next thread goes instead
+        manager
+            .add_shot_comment("scene-1", "shot-1", Comment::new("r", "a", "b").with_parent("c3"))
+            .unwrap();
+        let comments = manager.get_shot("scene-1", "shot-1").unwrap().unwrap().comments.unwrap();
+        let ids: Vec<&str> = comments.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids.len(), MAX_COMMENTS);
+        assert_eq!(ids[..2], ["c3", "c5"]);
+        assert_eq!(ids.last(), Some(&"r"));
+    }
+
+    #[test]
+    fn test_sweep_stalls_old_processing_shots() {
+        let mut manager = StoryboardManager::new();
+        manager.create_scene("scene-1", Scene::new("scene-1", 1)).unwrap();
+        manager.create_shot("scene-1", "shot-1", Shot::new("shot-1", 1)).unwrap();
+        manager.create_shot("scene-1", "shot-2", Shot::new("shot-2", 2)).unwrap();
+
+        manager
+            .set_shot_generation_status_at("scene-1", "shot-1", "processing", 1_000)
+            .unwrap();
+        manager
+            .set_shot_generation_status_at("scene-1", "shot-2", "processing", 9_000)
+            .unwrap();
+
+        let report = manager
+            .sweep_with_config(10_000, &SweepConfig::new(5_000))
+            .unwrap();
+        assert_eq!(report.stalled.len(), 1);
+        assert_eq!(report.stalled[0].path, vec!["scene-1", "shot-1"]);
+
+        let shot1 = manager.get_shot("scene-1", "shot-1").unwrap().unwrap();
+        let shot2 = manager.get_shot("scene-1", "shot-2").unwrap().unwrap();
+        assert_eq!(shot1.generation_status.as_deref(), Some("stalled"));
+        assert_eq!(shot1.generation_status_updated_at, Some(10_000));
+        assert_eq!(shot2.generation_status.as_deref(), Some("processing"));
+    }
+
     #[test]
     fn test_history_append() {
         let mut manager = StoryboardManager::new();
@@ -879,6 +3666,167 @@ mod tests {
         assert_eq!(retrieved.history[0].id, "h-24");
     }
 
+    #[test]
+    fn test_character_attributes_merge_per_key() {
+        let mut manager = StoryboardManager::new();
+        manager.create_characters("c1", Character::new("c1", "Richie")).unwrap();
+        let mut peer = StoryboardManager::from_bytes(&manager.save()).unwrap();
+
+        manager.set_character_attribute("c1", "age", "40").unwrap();
+        manager.set_character_attribute("c1", "height", "180cm").unwrap();
+        peer.set_character_attribute("c1", "eye_color", "green").unwrap();
+        manager.merge(&mut peer).unwrap();
+        manager.delete_character_attribute("c1", "height").unwrap();
+        manager.delete_character_attribute("c1", "missing").unwrap();
+
+        let attributes = manager.get_character_attributes("c1").unwrap();
+        assert_eq!(attributes.len(), 2);
+        assert_eq!(attributes["age"], "40");
+        assert_eq!(attributes["eye_color"], "green");
+        assert!(manager.get_character_attributes("c2").is_err());
+        assert!(manager.set_character_attribute("c2", "age", "1").is_err());
+    }
+
+    #[test]
+    fn test_gc_unreferenced_assets() {
+        let mut manager = StoryboardManager::new();
+        for (id, uploaded_at) in [("a2", 2), ("a1", 1)] {
+            let asset = UploadedAsset {
+                id: id.to_string(),
+                image: format!("https://cdn/{}.png", id),
+                uploaded_at,
+                ..Default::default()
+            };
+            manager.create_uploaded_asset(id, asset).unwrap();
+        }
+        let assets = manager.list_uploaded_assets().unwrap();
+        assert_eq!(assets.iter().map(|a| a.id.as_str()).collect::<Vec<_>>(), vec!["a1", "a2"]);
+
+        manager.create_characters("c1", Character::new("c1", "Richie")).unwrap();
+        manager.set_characters_image("c1", Some("https://cdn/a1.png")).unwrap();
+
+        assert_eq!(manager.gc_unreferenced_assets().unwrap(), vec!["a2"]);
+        let history = manager.get_change_history();
+        assert_eq!(history.last().unwrap().message.as_deref(), Some("Remove 1 unreferenced asset(s)"));
+        assert!(manager.get_uploaded_asset("a2").unwrap().is_none());
+        assert!(manager.gc_unreferenced_assets().unwrap().is_empty());
+
+        manager.delete_uploaded_asset("a1").unwrap();
+        assert!(manager.list_uploaded_assets().unwrap().is_empty());
+    }
+
+    #[cfg(feature = "blob-store")]
+    #[test]
+    fn test_externalize_assets() {
+        use crate::blob::MemoryBlobStore;
+
+        let url = "data:image/png;base64,aGVsbG8=";
+        let mut manager = StoryboardManager::new();
+        let asset = UploadedAsset {
+            id: "a1".to_string(),
+            image: url.to_string(),
+            ..Default::default()
+        };
+        manager.create_uploaded_asset("a1", asset).unwrap();
+        manager.create_characters("c1", Character::new("c1", "Richie")).unwrap();
+        manager.set_characters_image("c1", Some(url)).unwrap();
+
+        let mut store = MemoryBlobStore::new();
+        let report = manager.externalize_assets(&mut store).unwrap();
+        assert_eq!(report.assets, vec!["a1"]);
+        assert_eq!(report.fields, 1);
+        assert_eq!(report.bytes, url.len());
+        assert_eq!(store.len(), 1);
+
+        let asset = manager.get_uploaded_asset("a1").unwrap().unwrap();
+        assert!(asset.image.starts_with(blob::BLOB_REF_PREFIX));
+        assert_eq!(asset.file_type, "image/png");
+        assert_eq!(blob::resolve_blob(&store, &asset.image).unwrap().unwrap(), b"hello");
+        let character = manager.get_characters("c1").unwrap().unwrap();
+        assert_eq!(character.image, Some(asset.image));
+
+        // Still referenced after the rewrite; a second pass finds nothing
+        assert!(manager.gc_unreferenced_assets().unwrap().is_empty());
+        assert!(manager.externalize_assets(&mut store).unwrap().assets.is_empty());
+        assert!(manager.get_state().unwrap().blobs.is_empty());
+    }
+
+    #[cfg(feature = "blob-store")]
+    #[test]
+    fn test_dedup_images() {
+        use base64::Engine;
+
+        let payload = base64::engine::general_purpose::STANDARD.encode([7u8; 1024]);
+        let url = format!("data:image/png;base64,{}", payload);
+        let url = url.as_str();
+        let mut manager = StoryboardManager::new();
+        manager.create_characters("c1", Character::new("c1", "Richie")).unwrap();
+        manager.set_characters_image("c1", Some(url)).unwrap();
+        manager.create_props("p1", Prop::new("p1", "Gun")).unwrap();
+        manager.set_props_image("p1", Some(url)).unwrap();
+
+        // Existing documents are migrated in one pass
+        let report = manager.dedup_images().unwrap();
+        assert_eq!((report.fields, report.blobs), (2, 1));
+        assert_eq!(report.bytes_before, 2 * url.len());
+        assert!(report.saved_bytes() > url.len() / 2);
+        let reference = manager.get_characters("c1").unwrap().unwrap().image.unwrap();
+        assert!(reference.starts_with(blob::BLOB_REF_PREFIX));
+        assert_eq!(manager.resolve_image(&reference).unwrap(), url);
+        assert_eq!(manager.resolve_image("https://cdn/a.png").unwrap(), "https://cdn/a.png");
+
+        // New history entries reuse the stored payload
+        let entry = AssetHistory::new("h1", url, "prompt");
+        manager.append_characters_history("c1", entry).unwrap();
+        let state = manager.get_state().unwrap();
+        assert_eq!(state.blobs.len(), 1);
+        assert_eq!(state.processing_stages.characters["c1"].history[0].image, reference);
+    }
+
+    #[test]
+    fn test_thumbnails() {
+        let mut manager = StoryboardManager::new();
+        manager.create_scene("scene-1", Scene::new("scene-1", 1)).unwrap();
+        manager.create_shot("scene-1", "shot-1", Shot::new("shot-1", 1)).unwrap();
+        manager.create_shot("scene-1", "shot-2", Shot::new("shot-2", 2)).unwrap();
+        manager.set_shot_image("scene-1", "shot-1", Some("https://cdn/s1.png")).unwrap();
+
+        manager.set_thumbnail(Some("https://cdn/cover.png")).unwrap();
+        manager.pick_thumbnail_from_shot("scene-1", "shot-1").unwrap();
+        assert!(manager.pick_thumbnail_from_shot("scene-1", "shot-2").is_err());
+        assert!(manager.pick_thumbnail_from_shot("scene-1", "missing").is_err());
+
+        let state = manager.get_state().unwrap();
+        assert_eq!(state.thumbnail_image.as_deref(), Some("https://cdn/cover.png"));
+        assert_eq!(state.scenes["scene-1"].thumbnail.as_deref(), Some("https://cdn/s1.png"));
+
+        manager.set_thumbnail(None).unwrap();
+        manager.set_scene_thumbnail("scene-1", None).unwrap();
+        let state = manager.get_state().unwrap();
+        assert!(state.thumbnail_image.is_none());
+        assert!(state.scenes["scene-1"].thumbnail.is_none());
+    }
+
+    #[test]
+    fn test_shot_history_append_in_place() {
+        let mut manager = StoryboardManager::new();
+        manager.create_scene("scene-1", Scene::new("scene-1", 1)).unwrap();
+        manager.create_shot("scene-1", "shot-1", Shot::new("shot-1", 1)).unwrap();
+
+        for i in 0..25 {
+            let entry = ShotHistory::new(format!("h-{}", i), format!("img-{}", i), "prompt");
+            manager.append_shot_history("scene-1", "shot-1", entry).unwrap();
+        }
+
+        let shot = manager.get_shot("scene-1", "shot-1").unwrap().unwrap();
+        assert_eq!(shot.history.len(), 20);
+        assert_eq!(shot.history[0].id, "h-24");
+        assert_eq!(shot.history[19].id, "h-5");
+        assert!(manager
+            .append_shot_history("scene-1", "missing", ShotHistory::default())
+            .is_err());
+    }
+
     #[test]
     fn test_save_and_load() {
         let mut manager = StoryboardManager::new();
@@ -892,6 +3840,22 @@ mod tests {
         assert!(state.processing_stages.characters.contains_key("char-1"));
     }
 
+    #[test]
+    fn test_update_state_with_message() {
+        let mut manager = StoryboardManager::new();
+        manager
+            .update_state_with_message("Bulk import", 42, |state| {
+                state.title = "Imported".to_string();
+            })
+            .unwrap();
+
+        let history = manager.get_change_history();
+        let last = history.last().unwrap();
+        assert_eq!(last.message.as_deref(), Some("Bulk import"));
+        assert_eq!(last.timestamp, 42);
+        assert_eq!(manager.get_state().unwrap().title, "Imported");
+    }
+
     #[test]
     fn test_merge_documents() {
         let mut base = StoryboardManager::new();
@@ -978,6 +3942,77 @@ mod tests {
         assert_eq!(state2.scenes.len(), 1);
     }
 
+    #[test]
+    fn test_validate_reports_inconsistencies() {
+        let mut manager = StoryboardManager::new();
+        manager
+            .update_state(|state| {
+                let stages = &mut state.processing_stages;
+                stages.characters.insert(
+                    "c1".to_string(),
+                    Character::new("c1", "Ann").with_tag("@ann"),
+                );
+
+                let mut scene = Scene::new("s1", 1);
+                let mut shot_1 = Shot::new("sh1", 1);
+                shot_1.assets_used = vec!["@ann".to_string(), "@ghost".to_string()];
+                let mut shot_2 = Shot::new("sh2", 2);
+                shot_2.ref_shot_id = Some(3);
+                let shots = [("sh1".to_string(), shot_1), ("sh2".to_string(), shot_2)];
+                scene.shots = OrderedMap::from_parts(
+                    shots.into_iter().collect(),
+                    vec!["sh1".to_string(), "sh9".to_string()],
+                );
+                state.scenes.insert("s1".to_string(), scene);
+                state.scene_order = vec!["s1".to_string(), "s1".to_string()];
+            })
+            .unwrap();
+
+        let report = manager.validate().unwrap();
+        assert!(!report.is_valid());
+        assert!(report.has_code("duplicate_order_entry"));
+        assert!(report.has_code("missing_shot"));
+        assert!(report.has_code("unordered_shot")); // sh2
+        assert!(report.has_code("forward_ref_shot"));
+        let dangling: Vec<_> = report
+            .issues
+            .iter()
+            .filter(|i| i.code == "dangling_tag")
+            .collect();
+        assert_eq!(dangling.len(), 1);
+        assert!(dangling[0].message.contains("@ghost"));
+
+        // A consistent document passes
+        let mut clean = StoryboardManager::new();
+        assert!(clean.validate().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_repair_fixes_order_lists() {
+        let mut manager = StoryboardManager::new();
+        manager
+            .update_state(|state| {
+                let mut scene = Scene::new("s1", 1);
+                let shots = [("sh3", 3), ("sh1", 1), ("sh2", 2)]
+                    .into_iter()
+                    .map(|(id, n)| (id.to_string(), Shot::new(id, n)))
+                    .collect();
+                scene.shots = OrderedMap::from_parts(shots, vec!["sh2".into(), "gone".into()]);
+                state.scenes.insert("s1".to_string(), scene);
+                state.scene_order = vec!["s1".into(), "s1".into()];
+            })
+            .unwrap();
+
+        let report = manager.repair().unwrap();
+        assert!(!report.is_empty());
+        assert!(manager.validate().unwrap().is_empty());
+
+        let state = manager.get_state().unwrap();
+        assert_eq!(state.scene_order, vec!["s1"]);
+        assert_eq!(state.scenes["s1"].shots.order(), ["sh2", "sh1", "sh3"]);
+        assert!(manager.repair().unwrap().is_empty());
+    }
+
     #[test]
     fn test_legend_character_access() {
         let bytes = std::fs::read("src/bin/json2automerge/examples/legend.automerge").unwrap();
@@ -985,10 +4020,10 @@ mod tests {
         let state = manager.get_state().unwrap();
 
         // Verify character order is preserved
-        assert_eq!(state.processing_stages.character_order.len(), 2);
+        assert_eq!(state.processing_stages.characters.order().len(), 2);
 
         // Access characters by order
-        for char_id in &state.processing_stages.character_order {
+        for char_id in state.processing_stages.characters.order() {
             let character = state.processing_stages.characters.get(char_id).unwrap();
             assert!(!character.name.is_empty());
             assert!(!character.id.is_empty());
@@ -1006,10 +4041,10 @@ mod tests {
         let scene = state.scenes.get(scene_id).unwrap();
 
         // Verify shot order
-        assert_eq!(scene.shot_order.len(), 10);
+        assert_eq!(scene.shots.order().len(), 10);
 
         // Access shots by order
-        for (i, shot_id) in scene.shot_order.iter().enumerate() {
+        for (i, shot_id) in scene.shots.order().iter().enumerate() {
             let shot = scene.shots.get(shot_id).unwrap();
             assert_eq!(shot.shot_number, (i + 1) as i32);
         }
@@ -3,16 +3,43 @@
 //! This module provides:
 //! - `model`: Data structures for storyboard (Character, Prop, SetLocation, Scene, Shot)
 //! - `manager`: StoryboardManager with CRUD operations and O(1) targeted updates
+//! - `assets`: Uploaded asset references behind `StoryboardManager::gc_unreferenced_assets()`
+//! - `cascade`: Reference handling behind `StoryboardManager::delete_characters_cascade()`
+//! - `entity_store`: Generic entity CRUD behind `StoryboardManager::entities()`
+//! - `presence`: Ephemeral scene/shot focus shared between peers
+//! - `rename`: Tag renaming and usage lookup (`rename_tag()`, `get_entity_usages()`)
+//! - `shot_list`: CSV/Markdown shot lists behind `StoryboardManager::export_shot_list()`
+//! - `pdf`: PDF contact sheets behind `StoryboardManager::export_pdf()` (`pdf-export` feature)
+//! - `validation`: Consistency checks behind `StoryboardManager::validate()`
 //! - `wasm`: WASM bindings for browser usage (JsStoryboardManager)
 
+pub mod assets;
+pub mod cascade;
+pub mod entity_store;
 pub mod manager;
 pub mod model;
+mod obj_cache;
+pub mod presence;
+pub mod rename;
+pub mod shot_list;
+
+#[cfg(feature = "pdf-export")]
+pub mod pdf;
+pub mod validation;
 
 #[cfg(feature = "wasm")]
 pub mod wasm;
 
+pub use cascade::DeletePolicy;
+pub use entity_store::{EntityStore, StoryboardEntity};
 pub use manager::StoryboardManager;
 pub use model::*;
+pub use presence::{PresenceMode, StoryboardPresence};
+pub use rename::{EntityUsages, TagRenameReport};
+pub use shot_list::ShotListFormat;
+
+#[cfg(feature = "pdf-export")]
+pub use pdf::PdfOptions;
 
 #[cfg(feature = "wasm")]
 pub use wasm::JsStoryboardManager;
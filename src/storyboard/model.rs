@@ -3,10 +3,22 @@
 //! These structs map to the TypeScript types in `storyboard.ts`.
 //! Using autosurgeon derives for automatic CRDT serialization.
 
-use autosurgeon::{Hydrate, Reconcile};
+use autosurgeon::reconcile::{MapReconciler, NoKey};
+use autosurgeon::{hydrate_prop, Hydrate, HydrateError, ReadDoc, Reconcile, Reconciler};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::acl::Acl;
+use crate::comment::Comment;
+use crate::link::DocRef;
+use crate::order_key::{self, Keyed};
+use crate::ordered_map::{ordered_map_serde, OrderedMap};
+use crate::raw::{hydrate_optional, hydrate_unknown_fields, reconcile_unknown_fields, RawFields};
+use crate::snapshot::NamedSnapshot;
+
+/// Maximum number of history entries kept per shot or entity.
+pub const MAX_HISTORY: usize = 20;
+
 // =============================================================================
 // DOCUMENT ROOT
 // =============================================================================
@@ -56,14 +68,56 @@ pub struct StoryboardRoot {
     /// Scene ordering (scene IDs)
     pub scene_order: Vec<String>,
 
+    /// Order scenes by `Scene::order_key`; `scene_order` then only lists
+    /// them (see `crate::order_key`)
+    #[serde(default)]
+    #[autosurgeon(missing = "Default::default")]
+    pub fractional_order: bool,
+
     /// Scene data keyed by scene ID
     pub scenes: HashMap<String, Scene>,
 
     /// Uploaded assets keyed by asset ID
     pub uploaded_assets: HashMap<String, UploadedAsset>,
 
+    /// Image data URLs stored once, keyed by content hash and referenced
+    /// from image fields as "blob:sha256:<hash>" (see `crate::blob`)
+    #[serde(default)]
+    #[autosurgeon(missing = "Default::default")]
+    pub blobs: HashMap<String, String>,
+
     /// Metadata
     pub metadata: StoryboardMetadata,
+
+    /// Trashed scenes and shots, restorable until the trash is emptied
+    #[serde(default)]
+    #[autosurgeon(missing = "Default::default")]
+    pub trash: Trash,
+
+    /// Display names keyed by hex actor ID, for change attribution
+    #[serde(default)]
+    #[autosurgeon(missing = "Default::default")]
+    pub actor_names: HashMap<String, String>,
+
+    /// Users by role (see `crate::acl`)
+    #[serde(default)]
+    #[autosurgeon(missing = "Default::default")]
+    pub acl: Acl,
+
+    /// Named points in the history, keyed by name (see `crate::snapshot`)
+    #[serde(default)]
+    #[autosurgeon(missing = "Default::default")]
+    pub snapshots: HashMap<String, NamedSnapshot>,
+
+    /// Advisory scene locks keyed by scene ID (see `claim_scene()`)
+    #[serde(default)]
+    #[autosurgeon(missing = "Default::default")]
+    pub scene_locks: HashMap<String, SceneLock>,
+
+    /// Read-only flag set by `freeze()`; local edits fail while it is set
+    #[serde(default)]
+    #[autosurgeon(missing = "Default::default")]
+    pub frozen: bool,
 }
 
 impl StoryboardRoot {
@@ -77,6 +131,63 @@ impl StoryboardRoot {
         }
     }
 
+    /// Returns the scene IDs in display order: `scene_order`, or sorted by
+    /// order key if `fractional_order` is set.
+    pub fn ordered_scene_ids(&self) -> Vec<String> {
+        if self.fractional_order {
+            order_key::sorted(&self.scene_order, &self.scenes)
+        } else {
+            self.scene_order.clone()
+        }
+    }
+
+    /// Puts the scenes in `order` in that order.
+    pub(crate) fn set_scene_order(&mut self, order: Vec<String>) {
+        if self.fractional_order {
+            order_key::reorder(&mut self.scenes, &order);
+        } else {
+            self.scene_order = order;
+        }
+    }
+
+    /// Puts `id` at `index` in display order (clamped), adding it to
+    /// `scene_order` if missing.
+    pub(crate) fn place_scene(&mut self, id: &str, index: usize) {
+        if self.fractional_order {
+            if !self.scene_order.iter().any(|s| s == id) {
+                self.scene_order.push(id.to_string());
+            }
+            order_key::place(&self.scene_order, &mut self.scenes, id, index);
+        } else {
+            self.scene_order.retain(|s| s != id);
+            let index = index.min(self.scene_order.len());
+            self.scene_order.insert(index, id.to_string());
+        }
+    }
+
+    /// Keys the scenes added to `scene_order` without one (fractional order
+    /// only).
+    pub(crate) fn fill_scene_keys(&mut self) {
+        if self.fractional_order {
+            order_key::fill(&self.scene_order, &mut self.scenes);
+        }
+    }
+
+    /// Switches scene ordering (see `crate::order_key`).
+    pub(crate) fn use_fractional_order(&mut self, enabled: bool) {
+        if self.fractional_order != enabled {
+            order_key::migrate(&mut self.scene_order, &mut self.scenes, enabled);
+            self.fractional_order = enabled;
+        }
+    }
+
+    /// Numbers scenes 1, 2, ... in display order. Returns the number of
+    /// scenes whose `scene_number` changed.
+    pub fn renumber_scenes(&mut self) -> usize {
+        let order = self.ordered_scene_ids();
+        renumber(&order, &mut self.scenes, |scene| &mut scene.scene_number)
+    }
+
     /// Builder: Set title.
     pub fn with_title(mut self, title: impl Into<String>) -> Self {
         self.title = title.into();
@@ -114,22 +225,122 @@ pub struct StoryboardMetadata {
 
 /// Processing stages container for characters, props, and sets.
 /// Maps to TypeScript `ProcessingStages` interface.
-#[derive(Debug, Clone, Default, Reconcile, Hydrate, Serialize, Deserialize, PartialEq)]
+/// Note: Reconcile and Hydrate are implemented manually because each
+/// `OrderedMap` is stored as a map plus an order list (`characters` and
+/// `character_order`, ...).
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct ProcessingStages {
-    /// Character entities keyed by ID
-    pub characters: HashMap<String, Character>,
-    /// Character ordering (IDs)
-    pub character_order: Vec<String>,
+    /// Character entities keyed by ID, with their order
+    #[serde(flatten, with = "character_fields")]
+    pub characters: OrderedMap<Character>,
+
+    /// Prop entities keyed by ID, with their order
+    #[serde(flatten, with = "prop_fields")]
+    pub props: OrderedMap<Prop>,
+
+    /// Set/Location entities keyed by ID, with their order
+    #[serde(flatten, with = "set_fields")]
+    pub sets: OrderedMap<SetLocation>,
+
+    /// User-defined collections (e.g. "vehicles") keyed by name
+    #[serde(default)]
+    pub custom_collections: HashMap<String, EntityCollection>,
+}
+
+ordered_map_serde!(character_fields, "characters", "character_order");
+ordered_map_serde!(prop_fields, "props", "prop_order");
+ordered_map_serde!(set_fields, "sets", "set_order");
+
+impl Reconcile for ProcessingStages {
+    type Key<'a> = NoKey;
+
+    fn reconcile<R: Reconciler>(&self, reconciler: R) -> Result<(), R::Error> {
+        let mut m = reconciler.map()?;
+        self.characters
+            .reconcile_fields(&mut m, "characters", "character_order")?;
+        self.props.reconcile_fields(&mut m, "props", "prop_order")?;
+        self.sets.reconcile_fields(&mut m, "sets", "set_order")?;
+        m.put("custom_collections", &self.custom_collections)?;
+        Ok(())
+    }
+}
+
+impl Hydrate for ProcessingStages {
+    fn hydrate_map<D: ReadDoc>(doc: &D, obj: &automerge::ObjId) -> Result<Self, HydrateError> {
+        // Missing in documents saved before custom collections existed
+        let custom_collections: Option<HashMap<String, EntityCollection>> =
+            hydrate_prop(doc, obj, "custom_collections")?;
+        Ok(ProcessingStages {
+            characters: OrderedMap::hydrate_fields(doc, obj, "characters", "character_order")?,
+            props: OrderedMap::hydrate_fields(doc, obj, "props", "prop_order")?,
+            sets: OrderedMap::hydrate_fields(doc, obj, "sets", "set_order")?,
+            custom_collections: custom_collections.unwrap_or_default(),
+        })
+    }
+}
+
+// =============================================================================
+// CUSTOM COLLECTIONS
+// =============================================================================
+
+/// A user-defined entity collection such as "vehicles" or "VFX elements".
+#[derive(Debug, Clone, Default, Reconcile, Hydrate, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct EntityCollection {
+    /// Entities keyed by ID
+    pub entities: HashMap<String, CustomEntity>,
+    /// Entity ordering (IDs)
+    pub order: Vec<String>,
+}
+
+/// Entity in a custom collection, with the same generation state as a prop.
+#[derive(Debug, Clone, Default, Reconcile, Hydrate, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct CustomEntity {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub image_prompt: String,
+    /// Free-form fields specific to the collection (e.g. "make", "color")
+    pub attributes: HashMap<String, String>,
+
+    /// Tag reference (e.g., "@batmobile")
+    pub tag: Option<String>,
+    /// One-line visual description
+    pub caption: Option<String>,
+    /// Image URL
+    pub image: Option<String>,
+    pub enhanced: Option<bool>,
+    pub generation_id: Option<String>,
+    pub generation_status: Option<String>,
+    pub description_status: Option<String>,
+    pub description_error: Option<String>,
+    pub lora_model_id: Option<String>,
+    /// History of previous images (max 20)
+    pub history: Vec<AssetHistory>,
+}
+
+impl CustomEntity {
+    /// Creates a new CustomEntity with the given ID and name.
+    pub fn new(id: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            ..Default::default()
+        }
+    }
 
-    /// Prop entities keyed by ID
-    pub props: HashMap<String, Prop>,
-    /// Prop ordering (IDs)
-    pub prop_order: Vec<String>,
+    /// Builder: Set description.
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
 
-    /// Set/Location entities keyed by ID
-    pub sets: HashMap<String, SetLocation>,
-    /// Set ordering (IDs)
-    pub set_order: Vec<String>,
+    /// Builder: Set tag.
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
 }
 
 // =============================================================================
@@ -304,7 +515,9 @@ impl SetLocation {
 
 /// Scene with shots and per-character looks/outfits.
 /// Maps to TypeScript `Scene` interface.
-#[derive(Debug, Clone, Default, Reconcile, Hydrate, Serialize, Deserialize, PartialEq)]
+/// Note: Reconcile and Hydrate are implemented manually because `shots` is
+/// stored as the `shots` map and `shot_order`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 #[serde(default)]
 pub struct Scene {
     pub id: String,
@@ -351,10 +564,83 @@ pub struct Scene {
     /// Legacy outfits map (backward compat)
     pub outfits: HashMap<String, OutfitEntry>,
 
-    /// Shot ordering (shot IDs)
-    pub shot_order: Vec<String>,
-    /// Shot data keyed by shot ID
-    pub shots: HashMap<String, Shot>,
+    /// Shot data keyed by shot ID, in shot order
+    #[serde(flatten, with = "shot_fields")]
+    pub shots: OrderedMap<Shot>,
+
+    /// Scene-level review comments (max 200, oldest dropped first)
+    pub comments: Option<Vec<Comment>>,
+    /// Gallery thumbnail (URL or data URL)
+    pub thumbnail: Option<String>,
+    /// Fractional index key, used when the storyboard has
+    /// `fractional_order` set
+    pub order_key: Option<String>,
+}
+
+ordered_map_serde!(shot_fields, "shots", "shot_order");
+
+impl Reconcile for Scene {
+    type Key<'a> = NoKey;
+
+    fn reconcile<R: Reconciler>(&self, reconciler: R) -> Result<(), R::Error> {
+        let mut m = reconciler.map()?;
+        m.put("id", &self.id)?;
+        m.put("scene_number", self.scene_number)?;
+        m.put("title", &self.title)?;
+        m.put("header", &self.header)?;
+        m.put("content", &self.content)?;
+        m.put("visual_density_score", self.visual_density_score)?;
+        m.put("predicted_shots", self.predicted_shots)?;
+        m.put("reasoning", &self.reasoning)?;
+        m.put("characters_present", &self.characters_present)?;
+        m.put("set_ref", &self.set_ref)?;
+        m.put("synopsis", &self.synopsis)?;
+        m.put("time", &self.time)?;
+        m.put("raw_text", &self.raw_text)?;
+        m.put("looks_description", &self.looks_description)?;
+        m.put("outfit_description", &self.outfit_description)?;
+        m.put("known_entities", &self.known_entities)?;
+        m.put("character_looks", &self.character_looks)?;
+        m.put("character_outfits", &self.character_outfits)?;
+        m.put("looks_with_outfit", &self.looks_with_outfit)?;
+        m.put("outfits", &self.outfits)?;
+        self.shots.reconcile_fields(&mut m, "shots", "shot_order")?;
+        m.put("comments", &self.comments)?;
+        m.put("thumbnail", &self.thumbnail)?;
+        m.put("order_key", &self.order_key)?;
+        Ok(())
+    }
+}
+
+impl Hydrate for Scene {
+    fn hydrate_map<D: ReadDoc>(doc: &D, obj: &automerge::ObjId) -> Result<Self, HydrateError> {
+        Ok(Scene {
+            id: hydrate_prop(doc, obj, "id")?,
+            scene_number: hydrate_prop(doc, obj, "scene_number")?,
+            title: hydrate_prop(doc, obj, "title")?,
+            header: hydrate_prop(doc, obj, "header")?,
+            content: hydrate_prop(doc, obj, "content")?,
+            visual_density_score: hydrate_prop(doc, obj, "visual_density_score")?,
+            predicted_shots: hydrate_prop(doc, obj, "predicted_shots")?,
+            reasoning: hydrate_prop(doc, obj, "reasoning")?,
+            characters_present: hydrate_prop(doc, obj, "characters_present")?,
+            set_ref: hydrate_prop(doc, obj, "set_ref")?,
+            synopsis: hydrate_prop(doc, obj, "synopsis")?,
+            time: hydrate_prop(doc, obj, "time")?,
+            raw_text: hydrate_prop(doc, obj, "raw_text")?,
+            looks_description: hydrate_prop(doc, obj, "looks_description")?,
+            outfit_description: hydrate_prop(doc, obj, "outfit_description")?,
+            known_entities: hydrate_prop(doc, obj, "known_entities")?,
+            character_looks: hydrate_prop(doc, obj, "character_looks")?,
+            character_outfits: hydrate_prop(doc, obj, "character_outfits")?,
+            looks_with_outfit: hydrate_prop(doc, obj, "looks_with_outfit")?,
+            outfits: hydrate_prop(doc, obj, "outfits")?,
+            shots: OrderedMap::hydrate_fields(doc, obj, "shots", "shot_order")?,
+            comments: hydrate_prop(doc, obj, "comments")?,
+            thumbnail: hydrate_prop(doc, obj, "thumbnail")?,
+            order_key: hydrate_prop(doc, obj, "order_key")?,
+        })
+    }
 }
 
 impl Scene {
@@ -367,6 +653,13 @@ impl Scene {
         }
     }
 
+    /// Numbers shots 1, 2, ... in `shot_order`. Returns the number of shots
+    /// whose `shot_number` changed.
+    pub fn renumber_shots(&mut self) -> usize {
+        let (order, shots) = self.shots.parts_mut();
+        renumber(order, shots, |shot| &mut shot.shot_number)
+    }
+
     /// Builder: Set title.
     pub fn with_title(mut self, title: impl Into<String>) -> Self {
         self.title = title.into();
@@ -456,7 +749,8 @@ pub struct OutfitEntry {
 
 /// Shot with visual continuity references.
 /// Maps to TypeScript `Shot` interface.
-#[derive(Debug, Clone, Default, Reconcile, Hydrate, Serialize, Deserialize, PartialEq)]
+/// Reconcile and Hydrate are manual so fields from newer clients survive in `extra`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 #[serde(default)]
 pub struct Shot {
     pub id: String,
@@ -473,6 +767,14 @@ pub struct Shot {
     pub image: Option<String>,
     /// Current generation status
     pub generation_status: Option<String>,
+    /// When generation_status was last set (ms since epoch), used by `sweep()`
+    pub generation_status_updated_at: Option<i64>,
+    /// External render provider for the current job (e.g., "fal")
+    pub provider: Option<String>,
+    /// Job ID in the provider's render queue
+    pub job_id: Option<String>,
+    /// Sequence generation node the image came from
+    pub generation_ref: Option<DocRef>,
 
     /// Phase 2 fields
     pub assets: Option<Vec<AssetRef>>,
@@ -498,6 +800,13 @@ pub struct Shot {
 
     /// History for undo (max 20 items)
     pub history: Vec<ShotHistory>,
+
+    /// Review comments (max 200, oldest dropped first)
+    pub comments: Option<Vec<Comment>>,
+
+    /// Fields from a newer schema, preserved as-is
+    #[serde(flatten)]
+    pub extra: RawFields,
 }
 
 impl Shot {
@@ -529,6 +838,108 @@ impl Shot {
     }
 }
 
+/// Field names written by this version; anything else goes to `extra`.
+const SHOT_FIELDS: &[&str] = &[
+    "id",
+    "shot_number",
+    "image_prompt",
+    "size",
+    "angle",
+    "visual_description",
+    "assets_used",
+    "image",
+    "generation_status",
+    "generation_status_updated_at",
+    "provider",
+    "job_id",
+    "generation_ref",
+    "assets",
+    "environment",
+    "action",
+    "camera",
+    "additional_instructions",
+    "known_assets",
+    "title",
+    "visual_prompt",
+    "camera_type",
+    "camera_angle",
+    "subject",
+    "ref_shot_id",
+    "history",
+    "comments",
+];
+
+impl Reconcile for Shot {
+    type Key<'a> = NoKey;
+
+    fn reconcile<R: Reconciler>(&self, mut reconciler: R) -> Result<(), R::Error> {
+        let mut m = reconciler.map()?;
+        m.put("id", &self.id)?;
+        m.put("shot_number", self.shot_number)?;
+        m.put("image_prompt", &self.image_prompt)?;
+        m.put("size", &self.size)?;
+        m.put("angle", &self.angle)?;
+        m.put("visual_description", &self.visual_description)?;
+        m.put("assets_used", &self.assets_used)?;
+        m.put("image", &self.image)?;
+        m.put("generation_status", &self.generation_status)?;
+        m.put("generation_status_updated_at", self.generation_status_updated_at)?;
+        m.put("provider", &self.provider)?;
+        m.put("job_id", &self.job_id)?;
+        m.put("generation_ref", &self.generation_ref)?;
+        m.put("assets", &self.assets)?;
+        m.put("environment", &self.environment)?;
+        m.put("action", &self.action)?;
+        m.put("camera", &self.camera)?;
+        m.put("additional_instructions", &self.additional_instructions)?;
+        m.put("known_assets", &self.known_assets)?;
+        m.put("title", &self.title)?;
+        m.put("visual_prompt", &self.visual_prompt)?;
+        m.put("camera_type", &self.camera_type)?;
+        m.put("camera_angle", &self.camera_angle)?;
+        m.put("subject", &self.subject)?;
+        m.put("ref_shot_id", self.ref_shot_id)?;
+        m.put("history", &self.history)?;
+        m.put("comments", &self.comments)?;
+        reconcile_unknown_fields(&mut m, &self.extra)
+    }
+}
+
+impl Hydrate for Shot {
+    fn hydrate_map<D: ReadDoc>(doc: &D, obj: &automerge::ObjId) -> Result<Self, HydrateError> {
+        Ok(Shot {
+            id: hydrate_prop(doc, obj, "id")?,
+            shot_number: hydrate_prop(doc, obj, "shot_number")?,
+            image_prompt: hydrate_prop(doc, obj, "image_prompt")?,
+            size: hydrate_prop(doc, obj, "size")?,
+            angle: hydrate_prop(doc, obj, "angle")?,
+            visual_description: hydrate_prop(doc, obj, "visual_description")?,
+            assets_used: hydrate_prop(doc, obj, "assets_used")?,
+            image: hydrate_optional(doc, obj, "image")?,
+            generation_status: hydrate_optional(doc, obj, "generation_status")?,
+            generation_status_updated_at: hydrate_optional(doc, obj, "generation_status_updated_at")?,
+            provider: hydrate_optional(doc, obj, "provider")?,
+            job_id: hydrate_optional(doc, obj, "job_id")?,
+            generation_ref: hydrate_optional(doc, obj, "generation_ref")?,
+            assets: hydrate_optional(doc, obj, "assets")?,
+            environment: hydrate_optional(doc, obj, "environment")?,
+            action: hydrate_optional(doc, obj, "action")?,
+            camera: hydrate_optional(doc, obj, "camera")?,
+            additional_instructions: hydrate_optional(doc, obj, "additional_instructions")?,
+            known_assets: hydrate_optional(doc, obj, "known_assets")?,
+            title: hydrate_optional(doc, obj, "title")?,
+            visual_prompt: hydrate_optional(doc, obj, "visual_prompt")?,
+            camera_type: hydrate_optional(doc, obj, "camera_type")?,
+            camera_angle: hydrate_optional(doc, obj, "camera_angle")?,
+            subject: hydrate_optional(doc, obj, "subject")?,
+            ref_shot_id: hydrate_optional(doc, obj, "ref_shot_id")?,
+            history: hydrate_prop(doc, obj, "history")?,
+            comments: hydrate_optional(doc, obj, "comments")?,
+            extra: hydrate_unknown_fields(doc, obj, SHOT_FIELDS)?,
+        })
+    }
+}
+
 /// Asset reference with tag and name.
 #[derive(Debug, Clone, Default, Reconcile, Hydrate, Serialize, Deserialize, PartialEq)]
 pub struct AssetRef {
@@ -658,6 +1069,104 @@ pub struct UploadedAsset {
     pub uploaded_at: i64,
 }
 
+// =============================================================================
+// TRASH
+// =============================================================================
+
+/// Scenes and shots removed with `trash_scene()` / `trash_shot()`.
+#[derive(Debug, Clone, Default, Reconcile, Hydrate, Serialize, Deserialize, PartialEq)]
+pub struct Trash {
+    /// Trashed scenes keyed by scene ID
+    pub scenes: HashMap<String, TrashedScene>,
+    /// Trashed shots keyed by scene ID, then shot ID
+    pub shots: HashMap<String, HashMap<String, TrashedShot>>,
+}
+
+impl Keyed for Scene {
+    fn order_key(&self) -> Option<&str> {
+        self.order_key.as_deref()
+    }
+
+    fn set_order_key(&mut self, key: Option<String>) {
+        self.order_key = key;
+    }
+}
+
+impl Trash {
+    /// Returns true if nothing is in the trash.
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty() && self.shots.values().all(HashMap::is_empty)
+    }
+}
+
+/// Numbers the items in `order` 1, 2, ..., skipping IDs with no item.
+/// Returns how many numbers changed.
+fn renumber<T>(
+    order: &[String],
+    items: &mut HashMap<String, T>,
+    number: impl Fn(&mut T) -> &mut i32,
+) -> usize {
+    let mut changed = 0;
+    let mut next = 0;
+    for id in order {
+        let Some(item) = items.get_mut(id) else {
+            continue;
+        };
+        next += 1;
+        let current = number(item);
+        if *current != next {
+            *current = next;
+            changed += 1;
+        }
+    }
+    changed
+}
+
+/// A trashed scene with the position it had in the scene order.
+#[derive(Debug, Clone, Default, Reconcile, Hydrate, Serialize, Deserialize, PartialEq)]
+pub struct TrashedScene {
+    pub scene: Scene,
+    /// Index in `scene_order` when trashed
+    pub index: i64,
+    /// Timestamp when trashed (milliseconds since epoch)
+    pub deleted_at: i64,
+    pub deleted_by: Option<String>,
+}
+
+/// A trashed shot with the position it had in its scene's shot order.
+#[derive(Debug, Clone, Default, Reconcile, Hydrate, Serialize, Deserialize, PartialEq)]
+pub struct TrashedShot {
+    pub shot: Shot,
+    /// Index in the scene's `shot_order` when trashed
+    pub index: i64,
+    /// Timestamp when trashed (milliseconds since epoch)
+    pub deleted_at: i64,
+    pub deleted_by: Option<String>,
+}
+
+// =============================================================================
+// SCENE LOCKS
+// =============================================================================
+
+/// An advisory "being edited" marker on a scene. Nothing is enforced; the
+/// lock only tells other users who is working on the scene until it expires.
+#[derive(Debug, Clone, Default, Reconcile, Hydrate, Serialize, Deserialize, PartialEq)]
+pub struct SceneLock {
+    /// User ID of the holder
+    pub user: String,
+    /// Timestamp when first claimed (milliseconds since epoch)
+    pub claimed_at: i64,
+    /// Timestamp after which the lock is ignored (milliseconds since epoch)
+    pub expires_at: i64,
+}
+
+impl SceneLock {
+    /// Returns true if the lock has not expired at `now`.
+    pub fn is_active(&self, now: i64) -> bool {
+        now < self.expires_at
+    }
+}
+
 // =============================================================================
 // TESTS
 // =============================================================================
@@ -0,0 +1,178 @@
+//! ObjId cache for targeted storyboard setters.
+//!
+//! Setters like `set_shot_generation_status()` resolve
+//! `scenes → {scene} → shots → {shot}` before writing. During batch
+//! generation the same few shots are updated over and over, so
+//! `StoryboardManager` remembers resolved ObjIds by path in a small LRU.
+//!
+//! An ObjId stays valid until its object is deleted. The manager therefore
+//! drops the paths of removed scenes, shots and entities after
+//! `update_state()`, and clears the whole cache whenever remote changes are
+//! applied (merge, load, sync) or a transaction is rolled back.
+
+use std::collections::HashMap;
+
+use automerge::ObjId;
+
+use super::model::StoryboardRoot;
+
+/// Paths kept before the least recently used one is evicted.
+pub(crate) const DEFAULT_OBJ_CACHE_CAPACITY: usize = 512;
+
+/// Separates path segments in cache keys; cannot appear in a document key
+/// written by this crate.
+const SEPARATOR: char = '\0';
+
+/// LRU map from document path to ObjId.
+#[derive(Debug)]
+pub(crate) struct ObjCache {
+    entries: HashMap<String, (ObjId, u64)>,
+    capacity: usize,
+    /// Last-use counter; the entry with the lowest value is evicted first.
+    tick: u64,
+}
+
+impl ObjCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            capacity: capacity.max(1),
+            tick: 0,
+        }
+    }
+
+    /// Returns the cached ObjId at `path`, marking it as recently used.
+    pub(crate) fn get(&mut self, path: &[&str]) -> Option<ObjId> {
+        self.tick += 1;
+        let tick = self.tick;
+        self.entries.get_mut(&key(path)).map(|(obj, used)| {
+            *used = tick;
+            obj.clone()
+        })
+    }
+
+    /// Caches `obj` at `path`, evicting the least recently used path if full.
+    pub(crate) fn insert(&mut self, path: &[&str], obj: ObjId) {
+        let key = key(path);
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            if let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(key, _)| key.clone())
+            {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.tick += 1;
+        self.entries.insert(key, (obj, self.tick));
+    }
+
+    /// Drops `path` and every path below it.
+    pub(crate) fn invalidate(&mut self, path: &[&str]) {
+        let prefix = key(path);
+        self.entries.retain(|key, _| {
+            !(key == &prefix
+                || (key.starts_with(&prefix) && key[prefix.len()..].starts_with(SEPARATOR)))
+        });
+    }
+
+    /// Drops every cached path.
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Drops the paths of scenes, shots, entities and custom collections
+    /// present in `old` but not in `new`.
+    pub(crate) fn invalidate_removed(&mut self, old: &StoryboardRoot, new: &StoryboardRoot) {
+        if self.entries.is_empty() {
+            return;
+        }
+        for (scene_id, scene) in &old.scenes {
+            match new.scenes.get(scene_id) {
+                None => self.invalidate(&["scenes", scene_id.as_str()]),
+                Some(new_scene) => {
+                    for shot_id in scene.shots.keys() {
+                        if !new_scene.shots.contains_key(shot_id) {
+                            self.invalidate(&["scenes", scene_id.as_str(), "shots", shot_id.as_str()]);
+                        }
+                    }
+                }
+            }
+        }
+        let (old_stages, new_stages) = (&old.processing_stages, &new.processing_stages);
+        for id in old_stages.characters.keys() {
+            if !new_stages.characters.contains_key(id) {
+                self.invalidate(&["processing_stages", "characters", id.as_str()]);
+            }
+        }
+        for id in old_stages.props.keys() {
+            if !new_stages.props.contains_key(id) {
+                self.invalidate(&["processing_stages", "props", id.as_str()]);
+            }
+        }
+        for id in old_stages.sets.keys() {
+            if !new_stages.sets.contains_key(id) {
+                self.invalidate(&["processing_stages", "sets", id.as_str()]);
+            }
+        }
+        for (name, collection) in &old_stages.custom_collections {
+            let name = name.as_str();
+            let Some(new_collection) = new_stages.custom_collections.get(name) else {
+                self.invalidate(&["processing_stages", "custom_collections", name]);
+                continue;
+            };
+            for id in collection.entities.keys() {
+                if !new_collection.entities.contains_key(id) {
+                    let id = id.as_str();
+                    self.invalidate(&["processing_stages", "custom_collections", name, "entities", id]);
+                }
+            }
+        }
+    }
+}
+
+impl Default for ObjCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_OBJ_CACHE_CAPACITY)
+    }
+}
+
+fn key(path: &[&str]) -> String {
+    let mut key = String::new();
+    for (i, segment) in path.iter().enumerate() {
+        if i > 0 {
+            key.push(SEPARATOR);
+        }
+        key.push_str(segment);
+    }
+    key
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use automerge::ROOT;
+
+    #[test]
+    fn test_lru_eviction_and_prefix_invalidation() {
+        let mut cache = ObjCache::new(2);
+        cache.insert(&["scenes", "s1"], ROOT);
+        cache.insert(&["scenes", "s2"], ROOT);
+        assert!(cache.get(&["scenes", "s1"]).is_some());
+
+        // s2 is least recently used
+        cache.insert(&["scenes", "s1", "shots"], ROOT);
+        assert!(cache.get(&["scenes", "s2"]).is_none());
+
+        // "s1" covers its children but not "s10"
+        cache.insert(&["scenes", "s10"], ROOT);
+        cache.invalidate(&["scenes", "s1"]);
+        assert!(cache.get(&["scenes", "s1", "shots"]).is_none());
+        assert!(cache.get(&["scenes", "s10"]).is_some());
+    }
+}
@@ -0,0 +1,556 @@
+//! PDF contact sheet export.
+//!
+//! `render()` (behind `StoryboardManager::export_pdf()`) lays a storyboard
+//! out on paginated sheets: a heading per scene, then a grid of shot
+//! thumbnails captioned with the shot number, size, angle and visual
+//! description. Images are never fetched. Pass the bytes of shot images in
+//! `PdfOptions::images` (keyed by URL) to embed them; other shots get a
+//! placeholder box showing the URL. Only baseline/progressive JPEG (grey or
+//! RGB) can be embedded, as the PDF stores it unchanged; other formats also
+//! get a placeholder.
+//!
+//! The PDF (1.4) is written directly using the standard Helvetica fonts, so
+//! text outside Latin-1 is shown as "?". Caption widths are estimated from
+//! an average glyph width, not measured.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+use super::model::*;
+
+/// Page margin in points.
+const MARGIN: f64 = 36.0;
+/// Space between thumbnails and after each scene, in points.
+const GAP: f64 = 12.0;
+const TITLE_SIZE: f64 = 14.0;
+const HEADING_SIZE: f64 = 11.0;
+const CAPTION_SIZE: f64 = 8.0;
+const CAPTION_LINE: f64 = 10.0;
+/// Caption lines under each thumbnail (one title line, the rest description).
+const CAPTION_LINES: usize = 3;
+/// Average Helvetica glyph width as a fraction of the font size.
+const AVG_GLYPH_WIDTH: f64 = 0.52;
+
+/// Layout options for `StoryboardManager::export_pdf()`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct PdfOptions {
+    /// Heading on every page (defaults to the storyboard title)
+    pub title: Option<String>,
+    /// Page width in points (default A4 landscape)
+    pub page_width: f64,
+    /// Page height in points
+    pub page_height: f64,
+    /// Thumbnails per row
+    pub columns: usize,
+    /// JPEG bytes of shot images keyed by image URL (not serialized)
+    #[serde(skip)]
+    pub images: HashMap<String, Vec<u8>>,
+}
+
+impl Default for PdfOptions {
+    fn default() -> Self {
+        Self {
+            title: None,
+            page_width: 842.0,
+            page_height: 595.0,
+            columns: 3,
+            images: HashMap::new(),
+        }
+    }
+}
+
+/// A JPEG embedded as an image XObject.
+struct Jpeg<'a> {
+    data: &'a [u8],
+    width: u32,
+    height: u32,
+    components: u8,
+}
+
+/// Renders the contact sheet of `root` as PDF bytes.
+pub fn render(root: &StoryboardRoot, options: &PdfOptions) -> Vec<u8> {
+    let mut sheet = Sheet::new(root, options);
+    for scene in root.ordered_scene_ids().iter().filter_map(|id| root.scenes.get(id)) {
+        sheet.scene(scene);
+    }
+    sheet.finish()
+}
+
+/// Page layout state.
+struct Sheet<'a> {
+    options: &'a PdfOptions,
+    title: &'a str,
+    cell_width: f64,
+    thumb_height: f64,
+    /// Content stream of each page
+    pages: Vec<String>,
+    /// Top of the free space on the current page
+    y: f64,
+    images: Vec<Jpeg<'a>>,
+    /// Image index by URL (None if the bytes could not be embedded)
+    image_index: HashMap<&'a str, Option<usize>>,
+}
+
+impl<'a> Sheet<'a> {
+    fn new(root: &'a StoryboardRoot, options: &'a PdfOptions) -> Self {
+        let columns = options.columns.max(1) as f64;
+        let cell_width = (options.page_width - 2.0 * MARGIN - GAP * (columns - 1.0)) / columns;
+        // Keep at least a heading and one row on a page
+        let max_thumb = options.page_height
+            - 2.0 * MARGIN
+            - TITLE_SIZE
+            - 2.0 * GAP
+            - HEADING_SIZE
+            - CAPTION_LINES as f64 * CAPTION_LINE;
+        let mut sheet = Sheet {
+            options,
+            title: options.title.as_deref().unwrap_or(&root.title),
+            cell_width,
+            thumb_height: (cell_width * 9.0 / 16.0).min(max_thumb).max(0.0),
+            pages: Vec::new(),
+            y: 0.0,
+            images: Vec::new(),
+            image_index: HashMap::new(),
+        };
+        sheet.new_page();
+        sheet
+    }
+
+    fn row_height(&self) -> f64 {
+        self.thumb_height + CAPTION_LINES as f64 * CAPTION_LINE + GAP
+    }
+
+    fn new_page(&mut self) {
+        let number = self.pages.len() + 1;
+        let top = self.options.page_height - MARGIN;
+        let mut content = String::new();
+        text(&mut content, "F2", TITLE_SIZE, MARGIN, top - TITLE_SIZE, self.title);
+        let label = format!("Page {}", number);
+        let label_x = self.options.page_width - MARGIN - text_width(&label, CAPTION_SIZE);
+        text(&mut content, "F1", CAPTION_SIZE, label_x, top - TITLE_SIZE, &label);
+        self.pages.push(content);
+        self.y = top - TITLE_SIZE - GAP;
+    }
+
+    fn scene(&mut self, scene: &'a Scene) {
+        let heading_height = HEADING_SIZE + GAP / 2.0;
+        if self.y - heading_height - self.row_height() < MARGIN {
+            self.new_page();
+        }
+        let heading = if scene.header.is_empty() {
+            format!("Scene {}", scene.scene_number)
+        } else {
+            format!("Scene {}: {}", scene.scene_number, scene.header)
+        };
+        let heading = fit(&heading, self.options.page_width - 2.0 * MARGIN, HEADING_SIZE);
+        let y = self.y - HEADING_SIZE;
+        text(self.page(), "F2", HEADING_SIZE, MARGIN, y, &heading);
+        self.y -= heading_height;
+
+        let shots: Vec<&Shot> = scene.shots.iter_ordered().map(|(_, shot)| shot).collect();
+        if shots.is_empty() {
+            let y = self.y - CAPTION_SIZE;
+            text(self.page(), "F1", CAPTION_SIZE, MARGIN, y, "No shots");
+            self.y -= CAPTION_LINE + GAP;
+            return;
+        }
+        for row in shots.chunks(self.options.columns.max(1)) {
+            if self.y - self.row_height() < MARGIN {
+                self.new_page();
+            }
+            for (column, shot) in row.iter().enumerate() {
+                let x = MARGIN + column as f64 * (self.cell_width + GAP);
+                self.shot(shot, x);
+            }
+            self.y -= self.row_height();
+        }
+    }
+
+    /// Draws a thumbnail and its caption with the top left corner at (x, self.y).
+    fn shot(&mut self, shot: &'a Shot, x: f64) {
+        let (width, height) = (self.cell_width, self.thumb_height);
+        let bottom = self.y - height;
+        let image = shot.image.as_deref().filter(|url| !url.is_empty());
+        let embedded = image.and_then(|url| self.embed(url));
+        let mut out = String::new();
+        match embedded {
+            Some(index) => {
+                let jpeg = &self.images[index];
+                let scale = (width / jpeg.width as f64).min(height / jpeg.height as f64);
+                let (w, h) = (jpeg.width as f64 * scale, jpeg.height as f64 * scale);
+                let (ix, iy) = (x + (width - w) / 2.0, bottom + (height - h) / 2.0);
+                let _ = writeln!(
+                    out,
+                    "q {:.2} 0 0 {:.2} {:.2} {:.2} cm /Im{} Do Q",
+                    w, h, ix, iy, index
+                );
+            }
+            None => {
+                let _ = writeln!(
+                    out,
+                    "q 0.93 g {:.2} {:.2} {:.2} {:.2} re f Q",
+                    x, bottom, width, height
+                );
+                let label = fit(image.unwrap_or("No image"), width - 8.0, CAPTION_SIZE);
+                let _ = writeln!(out, "q 0.4 g");
+                text(&mut out, "F1", CAPTION_SIZE, x + 4.0, bottom + height / 2.0, &label);
+                let _ = writeln!(out, "Q");
+            }
+        }
+        let _ = writeln!(
+            out,
+            "q 0.6 G 0.5 w {:.2} {:.2} {:.2} {:.2} re S Q",
+            x, bottom, width, height
+        );
+
+        let mut title = format!("Shot {}", shot.shot_number);
+        for part in [&shot.size, &shot.angle] {
+            if !part.is_empty() {
+                title.push_str(" \u{b7} ");
+                title.push_str(part);
+            }
+        }
+        let mut y = bottom - CAPTION_LINE;
+        text(&mut out, "F2", CAPTION_SIZE, x, y, &fit(&title, width, CAPTION_SIZE));
+        for line in wrap(&shot.visual_description, width, CAPTION_LINES - 1) {
+            y -= CAPTION_LINE;
+            text(&mut out, "F1", CAPTION_SIZE, x, y, &line);
+        }
+        self.page().push_str(&out);
+    }
+
+    /// Returns the XObject index for `url`, if its bytes are an embeddable JPEG.
+    fn embed(&mut self, url: &'a str) -> Option<usize> {
+        if let Some(&index) = self.image_index.get(url) {
+            return index;
+        }
+        let options = self.options;
+        let index = options
+            .images
+            .get(url)
+            .and_then(|data| jpeg(data))
+            .map(|jpeg| {
+                self.images.push(jpeg);
+                self.images.len() - 1
+            });
+        self.image_index.insert(url, index);
+        index
+    }
+
+    fn page(&mut self) -> &mut String {
+        // new() always creates the first page
+        self.pages.last_mut().expect("sheet has a page")
+    }
+
+    /// Writes the document: catalog, page tree, fonts, shared resources,
+    /// images, then each page with its content stream.
+    fn finish(self) -> Vec<u8> {
+        let mut pdf = PdfWriter::new();
+        let first_image = 6;
+        let first_page = first_image + self.images.len();
+        let page_ids: Vec<String> = (0..self.pages.len())
+            .map(|i| format!("{} 0 R", first_page + 2 * i))
+            .collect();
+
+        pdf.object("<< /Type /Catalog /Pages 2 0 R >>");
+        pdf.object(&format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            page_ids.join(" "),
+            page_ids.len()
+        ));
+        for font in ["Helvetica", "Helvetica-Bold"] {
+            pdf.object(&format!(
+                "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+                font
+            ));
+        }
+        let xobjects: String = (0..self.images.len())
+            .map(|i| format!(" /Im{} {} 0 R", i, first_image + i))
+            .collect();
+        pdf.object(&format!(
+            "<< /Font << /F1 3 0 R /F2 4 0 R >> /XObject <<{} >> >>",
+            xobjects
+        ));
+        for jpeg in &self.images {
+            let color_space = if jpeg.components == 1 { "DeviceGray" } else { "DeviceRGB" };
+            let dict = format!(
+                "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /{} \
+                 /BitsPerComponent 8 /Filter /DCTDecode",
+                jpeg.width, jpeg.height, color_space
+            );
+            pdf.stream(&dict, jpeg.data);
+        }
+        for (i, content) in self.pages.iter().enumerate() {
+            pdf.object(&format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] /Resources 5 0 R \
+                 /Contents {} 0 R >>",
+                self.options.page_width,
+                self.options.page_height,
+                first_page + 2 * i + 1
+            ));
+            pdf.stream("", content.as_bytes());
+        }
+        pdf.finish()
+    }
+}
+
+/// Sequential object writer that records offsets for the xref table.
+struct PdfWriter {
+    out: Vec<u8>,
+    offsets: Vec<usize>,
+}
+
+impl PdfWriter {
+    fn new() -> Self {
+        Self {
+            out: b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec(),
+            offsets: Vec::new(),
+        }
+    }
+
+    fn begin(&mut self) {
+        self.offsets.push(self.out.len());
+        let header = format!("{} 0 obj\n", self.offsets.len());
+        self.out.extend_from_slice(header.as_bytes());
+    }
+
+    fn object(&mut self, body: &str) {
+        self.begin();
+        self.out.extend_from_slice(body.as_bytes());
+        self.out.extend_from_slice(b"\nendobj\n");
+    }
+
+    fn stream(&mut self, dict: &str, data: &[u8]) {
+        self.begin();
+        let dict = if dict.is_empty() {
+            format!("<< /Length {} >>\nstream\n", data.len())
+        } else {
+            format!("<< {} /Length {} >>\nstream\n", dict, data.len())
+        };
+        self.out.extend_from_slice(dict.as_bytes());
+        self.out.extend_from_slice(data);
+        self.out.extend_from_slice(b"\nendstream\nendobj\n");
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let xref = self.out.len();
+        let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", self.offsets.len() + 1);
+        for offset in &self.offsets {
+            let _ = writeln!(table, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(
+            table,
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            self.offsets.len() + 1,
+            xref
+        );
+        self.out.extend_from_slice(table.as_bytes());
+        self.out
+    }
+}
+
+/// Appends a single-line text operator.
+fn text(out: &mut String, font: &str, size: f64, x: f64, y: f64, value: &str) {
+    // Writing to a String cannot fail
+    let _ = writeln!(
+        out,
+        "BT /{} {} Tf {:.2} {:.2} Td ({}) Tj ET",
+        font,
+        size,
+        x,
+        y,
+        escape(value)
+    );
+}
+
+/// Escapes a string literal, writing Latin-1 as octal and anything else as "?".
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' '..='~' => out.push(c),
+            '\u{a0}'..='\u{ff}' => {
+                let _ = write!(out, "\\{:03o}", c as u32);
+            }
+            c if c.is_whitespace() => out.push(' '),
+            _ => out.push('?'),
+        }
+    }
+    out
+}
+
+fn text_width(value: &str, size: f64) -> f64 {
+    value.chars().count() as f64 * size * AVG_GLYPH_WIDTH
+}
+
+/// Truncates `value` with "..." to fit `width`.
+fn fit(value: &str, width: f64, size: f64) -> String {
+    let max = (width / (size * AVG_GLYPH_WIDTH)) as usize;
+    if value.chars().count() <= max {
+        return value.to_string();
+    }
+    let kept: String = value.chars().take(max.saturating_sub(3)).collect();
+    format!("{}...", kept.trim_end())
+}
+
+/// Word-wraps `value` to at most `max_lines` lines of `width`, ending the
+/// last one with "..." if text was left over.
+fn wrap(value: &str, width: f64, max_lines: usize) -> Vec<String> {
+    let max = ((width / (CAPTION_SIZE * AVG_GLYPH_WIDTH)) as usize).max(1);
+    let mut lines: Vec<String> = Vec::new();
+    for word in value.split_whitespace() {
+        let full = lines.len() == max_lines;
+        match lines.last_mut() {
+            Some(line) if line.chars().count() + 1 + word.chars().count() <= max => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ if full => {
+                let last = lines.pop().unwrap_or_default();
+                lines.push(fit(&format!("{} {}", last, word), width - 1.0, CAPTION_SIZE));
+                if let Some(line) = lines.last_mut() {
+                    if !line.ends_with("...") {
+                        line.push_str("...");
+                    }
+                }
+                break;
+            }
+            _ => lines.push(fit(word, width, CAPTION_SIZE)),
+        }
+    }
+    lines
+}
+
+/// Reads the size and component count from a JPEG's frame header.
+fn jpeg(data: &[u8]) -> Option<Jpeg<'_>> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    let mut i = 2;
+    while i + 4 <= data.len() {
+        if data[i] != 0xFF {
+            return None;
+        }
+        let marker = data[i + 1];
+        if marker == 0xFF {
+            // Fill byte
+            i += 1;
+            continue;
+        }
+        let length = u16::from_be_bytes([data[i + 2], data[i + 3]]) as usize;
+        // SOF0-SOF15, except DHT (C4), JPG (C8) and DAC (CC)
+        if (0xC0..=0xCF).contains(&marker) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+            let frame = data.get(i + 4..i + 2 + length)?;
+            let height = u16::from_be_bytes([*frame.get(1)?, *frame.get(2)?]) as u32;
+            let width = u16::from_be_bytes([*frame.get(3)?, *frame.get(4)?]) as u32;
+            let components = *frame.get(5)?;
+            // Arithmetic, lossless and hierarchical coding are not widely
+            // supported by viewers; CMYK needs an inverted decode array
+            let supported = matches!(marker, 0xC0..=0xC2) && matches!(components, 1 | 3);
+            return (supported && width > 0 && height > 0).then_some(Jpeg {
+                data,
+                width,
+                height,
+                components,
+            });
+        }
+        i += 2 + length;
+    }
+    None
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// JPEG header with a 32x16 RGB baseline frame (no scan data).
+    const JPEG: &[u8] = &[
+        0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00, 0xFF, 0xC0, 0x00, 0x11, 0x08, 0x00, 0x10,
+        0x00, 0x20, 0x03, 0x01, 0x22, 0x00, 0x02, 0x11, 0x01, 0x03, 0x11, 0x01, 0xFF, 0xD9,
+    ];
+
+    fn root(shots: usize) -> StoryboardRoot {
+        let mut scene = Scene::new("scene-1", 1).with_header("INT. CAFÉ (BACK) - DAY");
+        for n in 1..=shots {
+            let id = format!("shot-{}", n);
+            let mut shot = Shot::new(&id, n as i32);
+            shot.size = "WIDE".to_string();
+            shot.image = Some(format!("https://cdn/{}.jpg", n));
+            shot.visual_description = "A long description of the room ".repeat(5);
+            scene.shots.insert(id, shot);
+        }
+        let mut root = StoryboardRoot {
+            title: "Café".to_string(),
+            ..Default::default()
+        };
+        root.scenes.insert("scene-1".to_string(), scene);
+        root.scene_order.push("scene-1".to_string());
+        root
+    }
+
+    fn contains(pdf: &[u8], needle: &str) -> usize {
+        pdf.windows(needle.len()).filter(|w| *w == needle.as_bytes()).count()
+    }
+
+    #[test]
+    fn test_pdf_structure_and_images() {
+        let mut options = PdfOptions::default();
+        options.images.insert("https://cdn/1.jpg".to_string(), JPEG.to_vec());
+        options.images.insert("https://cdn/2.jpg".to_string(), b"\x89PNG".to_vec());
+        let pdf = render(&root(3), &options);
+
+        assert!(pdf.starts_with(b"%PDF-1.4\n"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+        assert_eq!(contains(&pdf, "/Type /Page "), 1);
+        assert_eq!(contains(&pdf, "/Filter /DCTDecode"), 1);
+        assert_eq!(contains(&pdf, "/Width 32 /Height 16"), 1);
+        assert_eq!(contains(&pdf, "/Im0 Do"), 1);
+        // The PNG and the missing image get placeholders with their URL
+        assert_eq!(contains(&pdf, "(https://cdn/2.jpg) Tj"), 1);
+        assert_eq!(contains(&pdf, "(https://cdn/3.jpg) Tj"), 1);
+        assert_eq!(contains(&pdf, "(Scene 1: INT. CAF\\311 \\(BACK\\) - DAY) Tj"), 1);
+
+        // Every xref entry points at its object
+        let text = String::from_utf8_lossy(&pdf);
+        let xref = text.rfind("\nxref\n").unwrap() + 1;
+        let offsets: Vec<usize> = text[xref..]
+            .lines()
+            .skip(3)
+            .take_while(|line| line.ends_with(" n "))
+            .map(|line| line[..10].parse().unwrap())
+            .collect();
+        assert_eq!(offsets.len(), 5 + 1 + 2);
+        for (i, offset) in offsets.iter().enumerate() {
+            assert!(pdf[*offset..].starts_with(format!("{} 0 obj\n", i + 1).as_bytes()));
+        }
+    }
+
+    #[test]
+    fn test_pdf_paginates() {
+        let pdf = render(&root(20), &PdfOptions::default());
+        let pages = contains(&pdf, "/Type /Page ");
+        assert!(pages > 1);
+        assert_eq!(contains(&pdf, &format!("/Count {}", pages)), 1);
+        assert_eq!(contains(&pdf, &format!("(Page {}) Tj", pages)), 1);
+        assert_eq!(contains(&pdf, "(Shot 20 \\267 WIDE) Tj"), 1);
+    }
+
+    #[test]
+    fn test_wrap_caption() {
+        let lines = wrap("one two three four five six", 40.0, 2);
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].ends_with("..."));
+        assert!(wrap("", 40.0, 2).is_empty());
+    }
+}
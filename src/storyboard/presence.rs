@@ -0,0 +1,36 @@
+//! Presence state broadcast by storyboard peers.
+
+use serde::{Deserialize, Serialize};
+
+use crate::presence::Cursor;
+
+/// Whether a peer is looking at or changing its focused item.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PresenceMode {
+    #[default]
+    Viewing,
+    Editing,
+}
+
+/// Which scene/shot a storyboard peer is on (for per-shot avatars).
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StoryboardPresence {
+    /// Display name or user ID.
+    pub user: String,
+    /// Focused scene ID.
+    pub scene_id: Option<String>,
+    /// Focused shot ID (within `scene_id`).
+    pub shot_id: Option<String>,
+    /// Viewing or editing.
+    pub mode: PresenceMode,
+    /// Text cursor within the focused shot or scene.
+    pub cursor: Option<Cursor>,
+}
+
+impl StoryboardPresence {
+    /// Returns true if this peer is focused on the given shot.
+    pub fn is_on_shot(&self, scene_id: &str, shot_id: &str) -> bool {
+        self.scene_id.as_deref() == Some(scene_id) && self.shot_id.as_deref() == Some(shot_id)
+    }
+}
@@ -0,0 +1,392 @@
+//! Entity tag renaming.
+//!
+//! Characters, props, sets and custom collection entities are referenced
+//! by tag (e.g. "@richie") all over a storyboard. `StoryboardManager::rename_tag()` rewrites, in one
+//! change:
+//!
+//! - the entity's own `tag`,
+//! - scene `character_looks`, `character_outfits`, `looks_with_outfit` and
+//!   `outfits` keys, `known_entities` tags and `set_ref`,
+//! - shot `assets_used`, `assets` and `subject`, and `known_assets` keys
+//!   and tags,
+//! - mentions in prompts: entity, look and outfit `image_prompt`,
+//!   `LooksWithOutfit::prompt`, and shot `image_prompt`, `visual_prompt`
+//!   and `visual_description`.
+//!
+//! Mentions are matched as whole tags, so renaming "@rich" leaves
+//! "@richie" alone. History entries and the trash keep the old tag.
+//!
+//! `StoryboardManager::get_entity_usages()` reports the same places, grouped
+//! by scene, shot, look and outfit, so a UI can warn before deleting.
+
+use std::collections::{BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use super::model::*;
+
+/// Locations changed by `StoryboardManager::rename_tag()`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TagRenameReport {
+    /// Sorted paths, e.g. `["scenes", "s1", "character_looks", "@richie"]`
+    /// for a renamed key or `["scenes", "s1", "shots", "sh1", "image_prompt"]`.
+    pub paths: Vec<Vec<String>>,
+}
+
+impl TagRenameReport {
+    /// Returns true if nothing referenced the tag.
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+}
+
+/// Where a tag is referenced, from `StoryboardManager::get_entity_usages()`.
+/// All lists are sorted.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EntityUsages {
+    /// Scenes referencing the tag directly: `known_entities`, `set_ref`,
+    /// look/outfit keys or prompts (shots are listed separately)
+    pub scenes: Vec<String>,
+    /// Shots referencing the tag, as (scene ID, shot ID)
+    pub shots: Vec<(String, String)>,
+    /// Scenes with a look for the tag
+    pub looks: Vec<String>,
+    /// Scenes with an outfit (or combined look and outfit) for the tag
+    pub outfits: Vec<String>,
+    /// Other entities whose `image_prompt` mentions the tag, as
+    /// (collection, ID); custom entities use their collection name
+    pub entities: Vec<(String, String)>,
+}
+
+impl EntityUsages {
+    /// Returns true if nothing references the tag.
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty() && self.shots.is_empty() && self.entities.is_empty()
+    }
+}
+
+/// Finds every reference to `tag` outside the entity's own `tag` field.
+pub(crate) fn find_usages(root: &StoryboardRoot, tag: &str) -> EntityUsages {
+    // Renaming a tag to itself changes nothing but still reports every
+    // place a real rename would touch
+    let report = rename_tag(&mut root.clone(), tag, tag);
+
+    let mut scenes = BTreeSet::new();
+    let mut shots = BTreeSet::new();
+    let mut looks = BTreeSet::new();
+    let mut outfits = BTreeSet::new();
+    let mut entities = BTreeSet::new();
+    let mut owners = BTreeSet::new();
+    for path in &report.paths {
+        let path: Vec<&str> = path.iter().map(String::as_str).collect();
+        match path[..] {
+            ["processing_stages", collection, id, "tag"] => {
+                owners.insert((collection.to_string(), id.to_string()));
+            }
+            ["processing_stages", collection, id, "image_prompt"] => {
+                entities.insert((collection.to_string(), id.to_string()));
+            }
+            ["processing_stages", "custom_collections", collection, "entities", id, field] => {
+                let entity = (collection.to_string(), id.to_string());
+                if field == "tag" {
+                    owners.insert(entity);
+                } else {
+                    entities.insert(entity);
+                }
+            }
+            ["scenes", scene_id, "shots", shot_id, ..] => {
+                shots.insert((scene_id.to_string(), shot_id.to_string()));
+            }
+            ["scenes", scene_id, field, ..] => {
+                scenes.insert(scene_id.to_string());
+                let keyed_by_tag = path.get(3) == Some(&tag);
+                match field {
+                    "character_looks" if keyed_by_tag => {
+                        looks.insert(scene_id.to_string());
+                    }
+                    "character_outfits" | "looks_with_outfit" | "outfits" if keyed_by_tag => {
+                        outfits.insert(scene_id.to_string());
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    entities.retain(|entity| !owners.contains(entity));
+    EntityUsages {
+        scenes: scenes.into_iter().collect(),
+        shots: shots.into_iter().collect(),
+        looks: looks.into_iter().collect(),
+        outfits: outfits.into_iter().collect(),
+        entities: entities.into_iter().collect(),
+    }
+}
+
+/// Returns true if a character, prop, set or custom entity has `tag`.
+pub(crate) fn tag_in_use(stages: &ProcessingStages, tag: &str) -> bool {
+    let tag = Some(tag);
+    stages.characters.values().any(|c| c.tag.as_deref() == tag)
+        || stages.props.values().any(|p| p.tag.as_deref() == tag)
+        || stages.sets.values().any(|s| s.tag.as_deref() == tag)
+        || stages
+            .custom_collections
+            .values()
+            .flat_map(|c| c.entities.values())
+            .any(|e| e.tag.as_deref() == tag)
+}
+
+/// Replaces `old` with `new` everywhere listed in the module docs.
+pub(crate) fn rename_tag(root: &mut StoryboardRoot, old: &str, new: &str) -> TagRenameReport {
+    let mut r = Renamer {
+        old,
+        new,
+        paths: Vec::new(),
+    };
+
+    let stages = &mut root.processing_stages;
+    for (id, c) in stages.characters.iter_mut() {
+        r.entity(&["processing_stages", "characters", id.as_str()], &mut c.tag, &mut c.image_prompt);
+    }
+    for (id, p) in stages.props.iter_mut() {
+        r.entity(&["processing_stages", "props", id.as_str()], &mut p.tag, &mut p.image_prompt);
+    }
+    for (id, s) in stages.sets.iter_mut() {
+        r.entity(&["processing_stages", "sets", id.as_str()], &mut s.tag, &mut s.image_prompt);
+    }
+    for (name, collection) in stages.custom_collections.iter_mut() {
+        for (id, e) in collection.entities.iter_mut() {
+            let (name, id) = (name.as_str(), id.as_str());
+            let path = ["processing_stages", "custom_collections", name, "entities", id];
+            r.entity(&path, &mut e.tag, &mut e.image_prompt);
+        }
+    }
+
+    for (scene_id, scene) in root.scenes.iter_mut() {
+        let path = ["scenes", scene_id.as_str()];
+        r.key(&path, "character_looks", &mut scene.character_looks);
+        r.key(&path, "character_outfits", &mut scene.character_outfits);
+        r.key(&path, "looks_with_outfit", &mut scene.looks_with_outfit);
+        r.key(&path, "outfits", &mut scene.outfits);
+        for (tag, look) in scene.character_looks.iter_mut() {
+            let field = at(&path, &["character_looks", tag.as_str(), "image_prompt"]);
+            r.opt_text(&field, &mut look.image_prompt);
+        }
+        for (tag, outfit) in scene.character_outfits.iter_mut() {
+            let field = at(&path, &["character_outfits", tag.as_str(), "image_prompt"]);
+            r.opt_text(&field, &mut outfit.image_prompt);
+        }
+        for (tag, combined) in scene.looks_with_outfit.iter_mut() {
+            let field = at(&path, &["looks_with_outfit", tag.as_str(), "prompt"]);
+            r.opt_text(&field, &mut combined.prompt);
+        }
+        if scene.set_ref.as_deref() == Some(old) {
+            scene.set_ref = Some(new.to_string());
+            r.record(&at(&path, &["set_ref"]));
+        }
+        if let Some(known) = &mut scene.known_entities {
+            let refs = known.characters.iter_mut().chain(&mut known.sets).chain(&mut known.props);
+            for entity in refs {
+                r.tag(&at(&path, &["known_entities"]), &mut entity.tag);
+            }
+        }
+
+        for (shot_id, shot) in scene.shots.iter_mut() {
+            let path = ["scenes", scene_id.as_str(), "shots", shot_id.as_str()];
+            let field = |name| at(&path, &[name]);
+            for tag in shot.assets_used.iter_mut() {
+                r.tag(&field("assets_used"), tag);
+            }
+            for asset in shot.assets.iter_mut().flatten() {
+                r.tag(&field("assets"), &mut asset.tag);
+            }
+            if shot.subject.as_deref() == Some(old) {
+                shot.subject = Some(new.to_string());
+                r.record(&field("subject"));
+            }
+            if let Some(known) = &mut shot.known_assets {
+                r.key(&field("known_assets"), "characters", &mut known.characters);
+                for asset in known.sets.iter_mut().chain(&mut known.props) {
+                    r.tag(&field("known_assets"), &mut asset.tag);
+                }
+            }
+            r.text(&field("image_prompt"), &mut shot.image_prompt);
+            r.opt_text(&field("visual_prompt"), &mut shot.visual_prompt);
+            r.text(&field("visual_description"), &mut shot.visual_description);
+        }
+    }
+
+    r.paths.sort();
+    r.paths.dedup();
+    TagRenameReport { paths: r.paths }
+}
+
+struct Renamer<'a> {
+    old: &'a str,
+    new: &'a str,
+    paths: Vec<Vec<String>>,
+}
+
+impl Renamer<'_> {
+    fn record(&mut self, path: &[&str]) {
+        self.paths.push(path.iter().map(|p| p.to_string()).collect());
+    }
+
+    fn entity(&mut self, path: &[&str], tag: &mut Option<String>, prompt: &mut String) {
+        if tag.as_deref() == Some(self.old) {
+            *tag = Some(self.new.to_string());
+            self.record(&at(path, &["tag"]));
+        }
+        self.text(&at(path, &["image_prompt"]), prompt);
+    }
+
+    /// Replaces a tag that must match exactly.
+    fn tag(&mut self, path: &[&str], tag: &mut String) {
+        if tag == self.old {
+            *tag = self.new.to_string();
+            self.record(path);
+        }
+    }
+
+    /// Moves the entry keyed by the old tag to the new one, replacing any
+    /// stale entry already under the new tag.
+    fn key<T>(&mut self, path: &[&str], field: &str, map: &mut HashMap<String, T>) {
+        if let Some(value) = map.remove(self.old) {
+            map.insert(self.new.to_string(), value);
+            self.record(&at(path, &[field, self.old]));
+        }
+    }
+
+    fn text(&mut self, path: &[&str], text: &mut String) {
+        if let Some(replaced) = replace_mentions(text, self.old, self.new) {
+            *text = replaced;
+            self.record(path);
+        }
+    }
+
+    fn opt_text(&mut self, path: &[&str], text: &mut Option<String>) {
+        if let Some(text) = text {
+            self.text(path, text);
+        }
+    }
+}
+
+/// Appends `rest` to `base`.
+fn at<'p>(base: &[&'p str], rest: &[&'p str]) -> Vec<&'p str> {
+    base.iter().chain(rest).copied().collect()
+}
+
+/// Replaces whole-tag mentions of `old` in `text`, or returns None if there
+/// are none.
+fn replace_mentions(text: &str, old: &str, new: &str) -> Option<String> {
+    let is_tag_char = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '@');
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    let mut found = false;
+    for (start, _) in text.match_indices(old) {
+        let end = start + old.len();
+        let before = text[..start].chars().next_back();
+        let after = text[end..].chars().next();
+        if before.is_some_and(is_tag_char) || after.is_some_and(is_tag_char) {
+            continue;
+        }
+        out.push_str(&text[last..start]);
+        out.push_str(new);
+        last = end;
+        found = true;
+    }
+    if !found {
+        return None;
+    }
+    out.push_str(&text[last..]);
+    Some(out)
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replace_mentions_whole_tags() {
+        assert_eq!(
+            replace_mentions("@rich meets @richie, then @rich's car", "@rich", "@ric"),
+            Some("@ric meets @richie, then @ric's car".to_string())
+        );
+        assert_eq!(replace_mentions("email@rich.com", "@rich", "@ric"), None);
+        assert_eq!(replace_mentions("@richie", "@rich", "@ric"), None);
+    }
+
+    #[test]
+    fn test_rename_tag_everywhere() {
+        let mut root = StoryboardRoot::default();
+        let richie = Character::new("c1", "Richie").with_tag("@richie");
+        root.processing_stages.characters.insert("c1".to_string(), richie);
+
+        let mut scene = Scene::new("s1", 1);
+        scene.character_looks.insert(
+            "@richie".to_string(),
+            CharacterLook {
+                image_prompt: Some("@richie, tired".to_string()),
+                ..Default::default()
+            },
+        );
+        scene.known_entities = Some(KnownEntities {
+            characters: vec![EntityRef {
+                tag: "@richie".to_string(),
+                name: "Richie".to_string(),
+            }],
+            ..Default::default()
+        });
+        let mut shot = Shot::new("sh1", 1).with_image_prompt("Close on @richie");
+        shot.assets_used = vec!["@richie".to_string(), "@maya".to_string()];
+        shot.subject = Some("@richie".to_string());
+        shot.known_assets = Some(ShotKnownAssets {
+            characters: HashMap::from([("@richie".to_string(), ShotCharacterRef::default())]),
+            ..Default::default()
+        });
+        scene.shots.insert("sh1".to_string(), shot);
+        root.scenes.insert("s1".to_string(), scene);
+
+        let report = rename_tag(&mut root, "@richie", "@rich");
+        let paths: Vec<String> = report.paths.iter().map(|p| p.join("/")).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "processing_stages/characters/c1/tag",
+                "scenes/s1/character_looks/@rich/image_prompt",
+                "scenes/s1/character_looks/@richie",
+                "scenes/s1/known_entities",
+                "scenes/s1/shots/sh1/assets_used",
+                "scenes/s1/shots/sh1/image_prompt",
+                "scenes/s1/shots/sh1/known_assets/characters/@richie",
+                "scenes/s1/shots/sh1/subject",
+            ]
+        );
+
+        let scene = &root.scenes["s1"];
+        assert_eq!(scene.character_looks["@rich"].image_prompt.as_deref(), Some("@rich, tired"));
+        let shot = &scene.shots["sh1"];
+        assert_eq!(shot.assets_used, vec!["@rich", "@maya"]);
+        assert_eq!(shot.image_prompt, "Close on @rich");
+        assert!(shot.known_assets.as_ref().unwrap().characters.contains_key("@rich"));
+        assert!(rename_tag(&mut root, "@richie", "@rich").is_empty());
+
+        let mut prop = Prop::new("p1", "Mug");
+        prop.image_prompt = "The mug @rich holds".to_string();
+        root.processing_stages.props.insert("p1".to_string(), prop);
+        let before = root.clone();
+        let usages = find_usages(&root, "@rich");
+        assert_eq!(root, before);
+        assert_eq!(usages.scenes, vec!["s1"]);
+        assert_eq!(usages.shots, vec![("s1".to_string(), "sh1".to_string())]);
+        assert_eq!(usages.looks, vec!["s1"]);
+        assert!(usages.outfits.is_empty());
+        assert_eq!(usages.entities, vec![("props".to_string(), "p1".to_string())]);
+        assert_eq!(find_usages(&root, "@maya").shots.len(), 1);
+        assert!(find_usages(&root, "@nobody").is_empty());
+    }
+}
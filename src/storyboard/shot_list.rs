@@ -0,0 +1,212 @@
+//! Shot list export.
+//!
+//! `render()` (behind `StoryboardManager::export_shot_list()`) lists every
+//! shot in scene and shot order with its size, angle, visual description,
+//! generation status and image URL, as CSV for spreadsheets or as Markdown
+//! (one table per scene). Scenes and shots missing from their order lists
+//! are left out, as in the editor.
+
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CollabError, CollabResult};
+
+use super::model::*;
+
+/// Output format of `StoryboardManager::export_shot_list()`.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ShotListFormat {
+    /// Comma-separated values (RFC 4180 quoting), one row per shot.
+    #[default]
+    Csv,
+    /// Markdown with a heading and table per scene.
+    Markdown,
+}
+
+impl ShotListFormat {
+    /// Returns the format name ("csv" or "markdown").
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ShotListFormat::Csv => "csv",
+            ShotListFormat::Markdown => "markdown",
+        }
+    }
+
+    /// Returns the usual file extension for the format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ShotListFormat::Csv => "csv",
+            ShotListFormat::Markdown => "md",
+        }
+    }
+}
+
+impl fmt::Display for ShotListFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ShotListFormat {
+    type Err = CollabError;
+
+    fn from_str(s: &str) -> CollabResult<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Ok(ShotListFormat::Csv),
+            "markdown" | "md" => Ok(ShotListFormat::Markdown),
+            _ => Err(CollabError::serialization(format!("unknown shot list format '{}'", s))),
+        }
+    }
+}
+
+/// Renders the shot list of `root` in `format`.
+pub fn render(root: &StoryboardRoot, format: ShotListFormat) -> String {
+    match format {
+        ShotListFormat::Csv => render_csv(root),
+        ShotListFormat::Markdown => render_markdown(root),
+    }
+}
+
+/// Ordered scenes, skipping IDs with no scene.
+fn ordered_scenes(root: &StoryboardRoot) -> impl Iterator<Item = &Scene> {
+    let order = root.ordered_scene_ids();
+    order.into_iter().filter_map(|id| root.scenes.get(&id))
+}
+
+/// Ordered shots of a scene, skipping IDs with no shot.
+fn ordered_shots(scene: &Scene) -> impl Iterator<Item = &Shot> {
+    scene.shots.iter_ordered().map(|(_, shot)| shot)
+}
+
+fn render_csv(root: &StoryboardRoot) -> String {
+    let mut out =
+        String::from("Scene,Scene Header,Shot,Size,Angle,Description,Status,Image\n");
+    for scene in ordered_scenes(root) {
+        for shot in ordered_shots(scene) {
+            let cells = [
+                scene.scene_number.to_string(),
+                csv_cell(&scene.header),
+                shot.shot_number.to_string(),
+                csv_cell(&shot.size),
+                csv_cell(&shot.angle),
+                csv_cell(&shot.visual_description),
+                csv_cell(shot.generation_status.as_deref().unwrap_or_default()),
+                csv_cell(shot.image.as_deref().unwrap_or_default()),
+            ];
+            out.push_str(&cells.join(","));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn render_markdown(root: &StoryboardRoot) -> String {
+    let mut out = String::new();
+    if !root.title.is_empty() {
+        // Writing to a String cannot fail
+        let _ = writeln!(out, "# {}\n", root.title);
+    }
+    for scene in ordered_scenes(root) {
+        let _ = write!(out, "## Scene {}", scene.scene_number);
+        if !scene.header.is_empty() {
+            let _ = write!(out, ": {}", scene.header);
+        }
+        out.push_str("\n\n");
+
+        out.push_str("| Shot | Size | Angle | Description | Status | Image |\n");
+        out.push_str("|---:|---|---|---|---|---|\n");
+        for shot in ordered_shots(scene) {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} | {} | {} |",
+                shot.shot_number,
+                markdown_cell(&shot.size),
+                markdown_cell(&shot.angle),
+                markdown_cell(&shot.visual_description),
+                markdown_cell(shot.generation_status.as_deref().unwrap_or_default()),
+                markdown_cell(shot.image.as_deref().unwrap_or_default()),
+            );
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Quotes a CSV field if it contains a separator, quote or line break.
+fn csv_cell(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Escapes pipes and turns line breaks into `<br>` so a value stays in its cell.
+fn markdown_cell(value: &str) -> String {
+    value
+        .trim()
+        .replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace('\n', "<br>")
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ordered_map::OrderedMap;
+
+    fn root() -> StoryboardRoot {
+        let mut shot = Shot::new("shot-1", 1);
+        shot.size = "WIDE".to_string();
+        shot.angle = "LOW".to_string();
+        shot.visual_description = "Rain, then \"thunder\"\nover the docks | pier".to_string();
+        shot.generation_status = Some("success".to_string());
+        shot.image = Some("https://cdn/1.png".to_string());
+
+        let mut scene = Scene::new("scene-1", 1).with_header("EXT. HARBOR - NIGHT");
+        let shots = [("shot-1", shot), ("shot-2", Shot::new("shot-2", 2))]
+            .into_iter()
+            .map(|(id, shot)| (id.to_string(), shot))
+            .collect();
+        scene.shots =
+            OrderedMap::from_parts(shots, vec!["shot-1".to_string(), "missing".to_string()]);
+
+        let mut root = StoryboardRoot {
+            title: "Harbor".to_string(),
+            ..Default::default()
+        };
+        root.scenes.insert("scene-1".to_string(), scene);
+        root.scene_order = vec!["scene-1".to_string()];
+        root
+    }
+
+    #[test]
+    fn test_csv_shot_list() {
+        let csv = render(&root(), ShotListFormat::Csv);
+        assert_eq!(
+            csv,
+            "Scene,Scene Header,Shot,Size,Angle,Description,Status,Image\n\
+             1,EXT. HARBOR - NIGHT,1,WIDE,LOW,\"Rain, then \"\"thunder\"\"\nover the docks | pier\",\
+             success,https://cdn/1.png\n"
+        );
+    }
+
+    #[test]
+    fn test_markdown_shot_list() {
+        let md = render(&root(), ShotListFormat::Markdown);
+        assert!(md.starts_with("# Harbor\n\n## Scene 1: EXT. HARBOR - NIGHT\n\n"));
+        assert!(md.contains(
+            "| 1 | WIDE | LOW | Rain, then \"thunder\"<br>over the docks \\| pier | success | https://cdn/1.png |\n"
+        ));
+        assert_eq!(md.matches("\n| 2 |").count(), 0);
+        assert_eq!("md".parse::<ShotListFormat>().unwrap(), ShotListFormat::Markdown);
+        assert!("pdf".parse::<ShotListFormat>().is_err());
+    }
+}
@@ -0,0 +1,271 @@
+//! Consistency checks for storyboard documents.
+//!
+//! See `StoryboardManager::validate()`. Issue codes:
+//!
+//! | Code | Severity | Meaning |
+//! |------|----------|---------|
+//! | `missing_scene` / `missing_shot` / `missing_entity` | error | Order list references an ID with no item |
+//! | `duplicate_order_entry` | error | ID appears more than once in an order list |
+//! | `forward_ref_shot` | error | `ref_shot_id` is not lower than the shot's own number |
+//! | `unordered_scene` / `unordered_shot` / `unordered_entity` | warning | Item missing from its order list |
+//! | `id_mismatch` | warning | Map key differs from the item's `id` |
+//! | `dangling_ref_shot` | warning | `ref_shot_id` names a shot number not in the scene |
+//! | `dangling_tag` | warning | Tag does not match any character, prop, set or custom entity |
+//! | `dangling_set_ref` | warning | `set_ref` matches no set ID or tag |
+//! | `history_over_cap` | warning | History longer than `MAX_HISTORY` |
+
+use std::collections::{HashMap, HashSet};
+
+use crate::validation::ValidationReport;
+
+use super::model::*;
+
+/// Runs every check against a hydrated storyboard.
+pub(crate) fn validate_storyboard(root: &StoryboardRoot) -> ValidationReport {
+    let mut report = ValidationReport::new();
+    let stages = &root.processing_stages;
+
+    check_order(
+        &mut report,
+        &[],
+        ("scene_order", &root.scene_order[..]),
+        ("scenes", &root.scenes),
+        "scene",
+    );
+    check_order(
+        &mut report,
+        &["processing_stages"],
+        ("character_order", stages.characters.order()),
+        ("characters", &stages.characters),
+        "entity",
+    );
+    check_order(
+        &mut report,
+        &["processing_stages"],
+        ("prop_order", stages.props.order()),
+        ("props", &stages.props),
+        "entity",
+    );
+    check_order(
+        &mut report,
+        &["processing_stages"],
+        ("set_order", stages.sets.order()),
+        ("sets", &stages.sets),
+        "entity",
+    );
+    for (name, collection) in sorted(&stages.custom_collections) {
+        check_order(
+            &mut report,
+            &["processing_stages", "custom_collections", name],
+            ("order", &collection.order[..]),
+            ("entities", &collection.entities),
+            "entity",
+        );
+    }
+
+    for (key, c) in sorted(&stages.characters) {
+        check_entity(&mut report, "characters", key, &c.id, c.history.len());
+    }
+    for (key, p) in sorted(&stages.props) {
+        check_entity(&mut report, "props", key, &p.id, p.history.len());
+    }
+    for (key, s) in sorted(&stages.sets) {
+        check_entity(&mut report, "sets", key, &s.id, s.history.len());
+    }
+
+    let tags: HashSet<&str> = stages
+        .characters
+        .values()
+        .filter_map(|c| c.tag.as_deref())
+        .chain(stages.props.values().filter_map(|p| p.tag.as_deref()))
+        .chain(stages.sets.values().filter_map(|s| s.tag.as_deref()))
+        .chain(
+            stages
+                .custom_collections
+                .values()
+                .flat_map(|c| c.entities.values())
+                .filter_map(|e| e.tag.as_deref()),
+        )
+        .collect();
+
+    for (scene_key, scene) in sorted(&root.scenes) {
+        check_scene(&mut report, scene_key, scene, stages, &tags);
+    }
+
+    report
+}
+
+fn check_scene(
+    report: &mut ValidationReport,
+    scene_key: &str,
+    scene: &Scene,
+    stages: &ProcessingStages,
+    tags: &HashSet<&str>,
+) {
+    if scene.id != scene_key {
+        report.warning(
+            "id_mismatch",
+            &["scenes", scene_key],
+            format!("scene stored under '{}' has id '{}'", scene_key, scene.id),
+        );
+    }
+
+    check_order(
+        report,
+        &["scenes", scene_key],
+        ("shot_order", scene.shots.order()),
+        ("shots", &scene.shots),
+        "shot",
+    );
+
+    if let Some(set_ref) = scene.set_ref.as_deref() {
+        let found = stages.sets.contains_key(set_ref)
+            || stages.sets.values().any(|s| s.tag.as_deref() == Some(set_ref));
+        if !found {
+            report.warning(
+                "dangling_set_ref",
+                &["scenes", scene_key, "set_ref"],
+                format!("set_ref '{}' matches no set", set_ref),
+            );
+        }
+    }
+
+    if let Some(known) = &scene.known_entities {
+        for entity in known.characters.iter().chain(&known.sets).chain(&known.props) {
+            check_tag(report, &["scenes", scene_key, "known_entities"], &entity.tag, tags);
+        }
+    }
+
+    let shot_numbers: HashSet<i32> = scene.shots.values().map(|s| s.shot_number).collect();
+    for (shot_key, shot) in sorted(&scene.shots) {
+        let path = ["scenes", scene_key, "shots", shot_key];
+        if shot.id != shot_key {
+            report.warning(
+                "id_mismatch",
+                &path,
+                format!("shot stored under '{}' has id '{}'", shot_key, shot.id),
+            );
+        }
+
+        if let Some(ref_id) = shot.ref_shot_id {
+            if ref_id >= 0 && ref_id >= shot.shot_number {
+                report.error(
+                    "forward_ref_shot",
+                    &path,
+                    format!(
+                        "shot {} references shot {} (must be earlier)",
+                        shot.shot_number, ref_id
+                    ),
+                );
+            } else if ref_id >= 0 && !shot_numbers.contains(&ref_id) {
+                report.warning(
+                    "dangling_ref_shot",
+                    &path,
+                    format!("shot {} references missing shot {}", shot.shot_number, ref_id),
+                );
+            }
+        }
+
+        for tag in &shot.assets_used {
+            check_tag(report, &path, tag, tags);
+        }
+        for asset in shot.assets.iter().flatten() {
+            check_tag(report, &path, &asset.tag, tags);
+        }
+        if let Some(subject) = shot.subject.as_deref() {
+            check_tag(report, &path, subject, tags);
+        }
+
+        if shot.history.len() > MAX_HISTORY {
+            report.warning(
+                "history_over_cap",
+                &path,
+                format!("{} history entries (max {})", shot.history.len(), MAX_HISTORY),
+            );
+        }
+    }
+}
+
+fn check_entity(
+    report: &mut ValidationReport,
+    collection: &str,
+    key: &str,
+    id: &str,
+    history_len: usize,
+) {
+    let path = ["processing_stages", collection, key];
+    if id != key {
+        report.warning(
+            "id_mismatch",
+            &path,
+            format!("entity stored under '{}' has id '{}'", key, id),
+        );
+    }
+    if history_len > MAX_HISTORY {
+        report.warning(
+            "history_over_cap",
+            &path,
+            format!("{} history entries (max {})", history_len, MAX_HISTORY),
+        );
+    }
+}
+
+/// Checks an order list against its map in both directions.
+fn check_order<T>(
+    report: &mut ValidationReport,
+    parent: &[&str],
+    (order_field, order): (&str, &[String]),
+    (items_field, items): (&str, &HashMap<String, T>),
+    kind: &str,
+) {
+    let mut seen = HashSet::new();
+    for id in order {
+        let mut path = parent.to_vec();
+        path.extend([order_field, id.as_str()]);
+        if !seen.insert(id.as_str()) {
+            report.error(
+                "duplicate_order_entry",
+                &path,
+                format!("'{}' appears more than once in {}", id, order_field),
+            );
+        } else if !items.contains_key(id) {
+            report.error(
+                &format!("missing_{}", kind),
+                &path,
+                format!("{} references missing {} '{}'", order_field, kind, id),
+            );
+        }
+    }
+
+    let mut unordered: Vec<&String> = items
+        .keys()
+        .filter(|k| !seen.contains(k.as_str()))
+        .collect();
+    unordered.sort();
+    for id in unordered {
+        let mut path = parent.to_vec();
+        path.extend([items_field, id.as_str()]);
+        report.warning(
+            &format!("unordered_{}", kind),
+            &path,
+            format!("{} '{}' is not in {}", kind, id, order_field),
+        );
+    }
+}
+
+fn check_tag(report: &mut ValidationReport, path: &[&str], tag: &str, tags: &HashSet<&str>) {
+    if !tag.is_empty() && !tags.contains(tag) {
+        report.warning(
+            "dangling_tag",
+            path,
+            format!("tag '{}' matches no character, prop or set", tag),
+        );
+    }
+}
+
+/// Map entries sorted by key, for deterministic reports.
+fn sorted<T>(map: &HashMap<String, T>) -> Vec<(&str, &T)> {
+    let mut entries: Vec<(&str, &T)> = map.iter().map(|(k, v)| (k.as_str(), v)).collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    entries
+}
@@ -3,14 +3,25 @@
 //! This module provides JavaScript-friendly wrappers around the
 //! StoryboardManager for use in browser environments.
 
+use std::collections::BTreeMap;
+
 use automerge::ChangeHash;
 use js_sys::{Array, Uint8Array};
 use serde::Serialize;
 use serde_wasm_bindgen::{from_value, Serializer};
 use wasm_bindgen::prelude::*;
 
+use crate::acl::{PermissionContext, Role};
+use crate::checkpoint::SyncCheckpoint;
+use crate::comment::Comment;
+use crate::link::DocRef;
+use crate::presence::Cursor;
+use crate::storyboard::entity_store::StoryboardEntity;
 use crate::storyboard::manager::StoryboardManager;
 use crate::storyboard::model::*;
+use crate::storyboard::presence::PresenceMode;
+use crate::storyboard::shot_list::ShotListFormat;
+use crate::sweep::SweepConfig;
 use crate::CollabError;
 
 /// Serialize a value to JsValue with HashMaps as plain JS objects (not Map).
@@ -25,7 +36,7 @@ fn to_js_value<T: Serialize>(value: &T) -> Result<JsValue, serde_wasm_bindgen::E
 /// Helper macro for Result conversion
 macro_rules! js_result {
     ($expr:expr) => {
-        $expr.map_err(|e: CollabError| JsValue::from_str(&e.to_string()))
+        $expr.map_err(|e: CollabError| JsValue::from(e))
     };
 }
 
@@ -39,7 +50,7 @@ macro_rules! js_result {
 /// that can be used from JavaScript/TypeScript in the browser.
 #[wasm_bindgen]
 pub struct JsStoryboardManager {
-    inner: StoryboardManager,
+    pub(crate) inner: StoryboardManager,
 }
 
 #[wasm_bindgen]
@@ -61,6 +72,14 @@ impl JsStoryboardManager {
         }
     }
 
+    /// Creates a new manager pre-populated from a seed storyboard, in a single change.
+    #[wasm_bindgen(js_name = withSeed)]
+    pub fn with_seed(seed: JsValue) -> Result<JsStoryboardManager, JsValue> {
+        let seed: StoryboardRoot = from_value(seed)?;
+        let inner = js_result!(StoryboardManager::new_with_seed(seed))?;
+        Ok(JsStoryboardManager { inner })
+    }
+
     /// Loads from binary bytes (Uint8Array).
     ///
     /// # Example (JavaScript)
@@ -74,6 +93,15 @@ impl JsStoryboardManager {
         Ok(JsStoryboardManager { inner })
     }
 
+    /// Loads a snapshot saved with `saveEncrypted()` (`keyParams`: `{ email, createdAt }`).
+    #[cfg(feature = "crypto")]
+    #[wasm_bindgen(js_name = fromEncryptedBytes)]
+    pub fn from_encrypted_bytes(bytes: &[u8], key_params: JsValue) -> Result<JsStoryboardManager, JsValue> {
+        let key_params: crate::crypto::KeyParams = from_value(key_params)?;
+        let inner = js_result!(StoryboardManager::from_encrypted_bytes(bytes, &key_params))?;
+        Ok(JsStoryboardManager { inner })
+    }
+
     /// Saves to binary bytes (returns Uint8Array).
     ///
     /// # Example (JavaScript)
@@ -86,12 +114,200 @@ impl JsStoryboardManager {
         Uint8Array::from(&bytes[..])
     }
 
+    /// Saves only the changes since the last `toBytes()` / `saveIncremental()`.
+    #[wasm_bindgen(js_name = saveIncremental)]
+    pub fn save_incremental(&mut self) -> Uint8Array {
+        let bytes = self.inner.save_incremental();
+        Uint8Array::from(&bytes[..])
+    }
+
+    /// Loads changes produced by `saveIncremental()`.
+    #[wasm_bindgen(js_name = loadIncremental)]
+    pub fn load_incremental(&mut self, data: &[u8]) -> Result<usize, JsValue> {
+        js_result!(self.inner.load_incremental(data))
+    }
+
+    /// Returns the changes not covered by a `{ heads }` checkpoint from
+    /// `markSynced()` (or null before the first upload).
+    #[wasm_bindgen(js_name = changesSinceCheckpoint)]
+    pub fn changes_since_checkpoint(&mut self, checkpoint: JsValue) -> Result<Uint8Array, JsValue> {
+        let checkpoint: Option<SyncCheckpoint> = from_value(checkpoint)?;
+        let bytes = self
+            .inner
+            .changes_since_checkpoint(&checkpoint.unwrap_or_default());
+        Ok(Uint8Array::from(&bytes[..]))
+    }
+
+    /// Returns a `{ heads }` checkpoint at the current heads. Store it once
+    /// the upload from `changesSinceCheckpoint()` is acknowledged.
+    #[wasm_bindgen(js_name = markSynced)]
+    pub fn mark_synced(&mut self) -> Result<JsValue, JsValue> {
+        Ok(to_js_value(&self.inner.mark_synced())?)
+    }
+
+    /// Saves gzip-compressed binary bytes (level 0-9); `fromBytes` reads them back.
+    #[cfg(feature = "compression")]
+    #[wasm_bindgen(js_name = saveCompressed)]
+    pub fn save_compressed(&mut self, level: u32) -> Uint8Array {
+        let bytes = self.inner.save_compressed(level);
+        Uint8Array::from(&bytes[..])
+    }
+
+    /// Saves an AES-256-GCM encrypted snapshot (returns Uint8Array).
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// const bytes = manager.saveEncrypted({ email: user.email, createdAt: board.createdAt });
+    /// ```
+    #[cfg(feature = "crypto")]
+    #[wasm_bindgen(js_name = saveEncrypted)]
+    pub fn save_encrypted(&mut self, key_params: JsValue) -> Result<Uint8Array, JsValue> {
+        let key_params: crate::crypto::KeyParams = from_value(key_params)?;
+        let bytes = js_result!(self.inner.save_encrypted(&key_params))?;
+        Ok(Uint8Array::from(&bytes[..]))
+    }
+
     /// Gets the actor ID for this document instance.
     #[wasm_bindgen(js_name = actorId)]
     pub fn actor_id(&self) -> String {
         self.inner.actor_id()
     }
 
+    /// Gets metadata for every change in the document history.
+    #[wasm_bindgen(js_name = getChangeHistory)]
+    pub fn get_change_history(&mut self) -> Result<JsValue, JsValue> {
+        Ok(to_js_value(&self.inner.get_change_history())?)
+    }
+
+    /// Registers a display name for a hex actor ID.
+    #[wasm_bindgen(js_name = setActorName)]
+    pub fn set_actor_name(&mut self, actor: &str, name: &str) -> Result<(), JsValue> {
+        js_result!(self.inner.set_actor_name(actor, name))
+    }
+
+    /// Gets the registered display names keyed by hex actor ID.
+    #[wasm_bindgen(js_name = getActorNames)]
+    pub fn get_actor_names(&mut self) -> Result<JsValue, JsValue> {
+        let names = js_result!(self.inner.get_actor_names())?;
+        Ok(to_js_value(&names)?)
+    }
+
+    /// Gets `{ actor, name?, timestamp }` of the last writer of each shot field.
+    #[wasm_bindgen(js_name = getShotAuthors)]
+    pub fn get_shot_authors(&mut self, scene_id: &str, shot_id: &str) -> Result<JsValue, JsValue> {
+        let authors = js_result!(self.inner.get_shot_authors(scene_id, shot_id))?;
+        Ok(to_js_value(&authors)?)
+    }
+
+    /// Gets every concurrent value (`{ actor, value }`, winner first) of a
+    /// shot field.
+    #[wasm_bindgen(js_name = getShotConflicts)]
+    pub fn get_shot_conflicts(
+        &mut self,
+        scene_id: &str,
+        shot_id: &str,
+        field: &str,
+    ) -> Result<JsValue, JsValue> {
+        let conflicts = js_result!(self.inner.get_shot_conflicts(scene_id, shot_id, field))?;
+        Ok(to_js_value(&conflicts)?)
+    }
+
+    /// Keeps the value `chosenActor` wrote to a shot field.
+    #[wasm_bindgen(js_name = resolveShotConflict)]
+    pub fn resolve_shot_conflict(
+        &mut self,
+        scene_id: &str,
+        shot_id: &str,
+        field: &str,
+        chosen_actor: &str,
+    ) -> Result<(), JsValue> {
+        js_result!(self
+            .inner
+            .resolve_shot_conflict(scene_id, shot_id, field, chosen_actor))
+    }
+
+    /// Gets every concurrent value of an entity field.
+    #[wasm_bindgen(js_name = getEntityConflicts)]
+    pub fn get_entity_conflicts(
+        &mut self,
+        entity_type: &str,
+        id: &str,
+        field: &str,
+    ) -> Result<JsValue, JsValue> {
+        let conflicts = js_result!(self.inner.get_entity_conflicts(entity_type, id, field))?;
+        Ok(to_js_value(&conflicts)?)
+    }
+
+    /// Keeps the value `chosenActor` wrote to an entity field.
+    #[wasm_bindgen(js_name = resolveEntityConflict)]
+    pub fn resolve_entity_conflict(
+        &mut self,
+        entity_type: &str,
+        id: &str,
+        field: &str,
+        chosen_actor: &str,
+    ) -> Result<(), JsValue> {
+        js_result!(self
+            .inner
+            .resolve_entity_conflict(entity_type, id, field, chosen_actor))
+    }
+
+    /// Returns `{ changes, ops, actors, change_bytes }` for the change history.
+    #[wasm_bindgen(js_name = historyStats)]
+    pub fn history_stats(&mut self) -> Result<JsValue, JsValue> {
+        Ok(to_js_value(&self.inner.history_stats())?)
+    }
+
+    /// Saves the current state as a fresh document without change history.
+    #[wasm_bindgen(js_name = saveCompact)]
+    pub fn save_compact(&mut self) -> Result<Uint8Array, JsValue> {
+        let bytes = js_result!(self.inner.save_compact())?;
+        Ok(Uint8Array::from(&bytes[..]))
+    }
+
+    /// Saves the document in the versioned envelope (returns Uint8Array).
+    /// `options`: `{ compressionLevel?, keyParams? }`; `fromBytes` (or
+    /// `fromEncryptedBytes` with key params) reads the result, and
+    /// `detectFormat()` reports its flags.
+    #[wasm_bindgen(js_name = saveEnveloped)]
+    pub fn save_enveloped(&mut self, options: JsValue) -> Result<Uint8Array, JsValue> {
+        let options: Option<crate::envelope::SaveOptions> = from_value(options)?;
+        let bytes = js_result!(self.inner.save_enveloped(&options.unwrap_or_default()))?;
+        Ok(Uint8Array::from(&bytes[..]))
+    }
+
+    /// Packs the document, its full history, upload checkpoints
+    /// (`{ name: checkpoint }`, optional) and metadata into one backup
+    /// archive (returns Uint8Array).
+    #[wasm_bindgen(js_name = exportBundle)]
+    pub fn export_bundle(&mut self, doc_id: &str, checkpoints: JsValue) -> Result<Uint8Array, JsValue> {
+        let checkpoints: Option<BTreeMap<String, SyncCheckpoint>> = from_value(checkpoints)?;
+        let bytes = js_result!(self.inner.export_bundle(doc_id, &checkpoints.unwrap_or_default()))?;
+        Ok(Uint8Array::from(&bytes[..]))
+    }
+
+    /// Restores a manager from `exportBundle()` output.
+    /// Returns `{ manager, metadata }`; `metadata.checkpoints` holds the
+    /// exported upload checkpoints.
+    #[wasm_bindgen(js_name = importBundle)]
+    pub fn import_bundle(bytes: &[u8]) -> Result<JsValue, JsValue> {
+        let (inner, metadata) = js_result!(StoryboardManager::import_bundle(bytes))?;
+        let result = js_sys::Object::new();
+        js_sys::Reflect::set(&result, &"metadata".into(), &to_js_value(&metadata)?)?;
+        js_sys::Reflect::set(&result, &"manager".into(), &JsStoryboardManager { inner }.into())?;
+        Ok(result.into())
+    }
+
+    /// Saves the current state as a fresh document with every string last
+    /// written by one of `actorIds` (hex) redacted (returns Uint8Array).
+    /// The result must replace every stored copy of the document.
+    #[wasm_bindgen(js_name = exportSanitized)]
+    pub fn export_sanitized(&self, actor_ids: Array) -> Result<Uint8Array, JsValue> {
+        let actors = crate::sequence::wasm::decode_actors(&actor_ids)?;
+        let bytes = js_result!(self.inner.export_sanitized(&actors))?;
+        Ok(Uint8Array::from(&bytes[..]))
+    }
+
     /// Gets the current heads (for sync protocol).
     #[wasm_bindgen(js_name = getHeads)]
     pub fn get_heads(&mut self) -> Array {
@@ -120,6 +336,29 @@ impl JsStoryboardManager {
         Ok(to_js_value(&state)?)
     }
 
+    /// Searches scene text and shot descriptions. Returns `[path, score]`
+    /// pairs, best match first, where `path` is `[sceneId]` or
+    /// `[sceneId, shotId]`.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// const hits = manager.search('harbor night');
+    /// ```
+    #[cfg(feature = "search")]
+    #[wasm_bindgen(js_name = search)]
+    pub fn search(&mut self, query: &str) -> Result<JsValue, JsValue> {
+        let hits = js_result!(self.inner.search(query))?;
+        Ok(to_js_value(&hits)?)
+    }
+
+    /// Gets only the characters, props and sets (`processing_stages`)
+    /// without hydrating scenes and shots.
+    #[wasm_bindgen(js_name = getEntities)]
+    pub fn get_entities(&mut self) -> Result<JsValue, JsValue> {
+        let stages = js_result!(self.inner.hydrate_entities_only())?;
+        Ok(to_js_value(&stages)?)
+    }
+
     // =========================================================================
     // ROOT OPERATIONS
     // =========================================================================
@@ -148,6 +387,12 @@ impl JsStoryboardManager {
         js_result!(self.inner.set_current_stage(stage))
     }
 
+    /// Sets the storyboard thumbnail (pass null to clear).
+    #[wasm_bindgen(js_name = setThumbnail)]
+    pub fn set_thumbnail(&mut self, url: Option<String>) -> Result<(), JsValue> {
+        js_result!(self.inner.set_thumbnail(url.as_deref()))
+    }
+
     /// Updates the last_updated timestamp.
     #[wasm_bindgen(js_name = touchLastUpdated)]
     pub fn touch_last_updated(&mut self, timestamp: i64) -> Result<(), JsValue> {
@@ -171,27 +416,25 @@ impl JsStoryboardManager {
     /// ```
     #[wasm_bindgen(js_name = createCharacter)]
     pub fn create_character(&mut self, id: &str, character: JsValue) -> Result<(), JsValue> {
-        let character: Character = from_value(character)?;
-        js_result!(self.inner.create_characters(id, character))
+        self.create_entity::<Character>(id, character)
     }
 
     /// Gets a character by ID.
     #[wasm_bindgen(js_name = getCharacter)]
     pub fn get_character(&mut self, id: &str) -> Result<JsValue, JsValue> {
-        let character = js_result!(self.inner.get_characters(id))?;
-        Ok(to_js_value(&character)?)
+        self.get_entity::<Character>(id)
     }
 
     /// Deletes a character by ID.
     #[wasm_bindgen(js_name = deleteCharacter)]
     pub fn delete_character(&mut self, id: &str) -> Result<(), JsValue> {
-        js_result!(self.inner.delete_characters(id))
+        self.delete_entity::<Character>(id)
     }
 
     /// Sets the character image (O(1)).
     #[wasm_bindgen(js_name = setCharacterImage)]
     pub fn set_character_image(&mut self, id: &str, image: Option<String>) -> Result<(), JsValue> {
-        js_result!(self.inner.set_characters_image(id, image.as_deref()))
+        js_result!(self.inner.entities::<Character>().set_image(id, image.as_deref()))
     }
 
     /// Sets the character generation status (O(1)).
@@ -203,7 +446,8 @@ impl JsStoryboardManager {
     ) -> Result<(), JsValue> {
         js_result!(self
             .inner
-            .set_characters_generation_status(id, status.as_deref()))
+            .entities::<Character>()
+            .set_generation_status(id, status.as_deref()))
     }
 
     /// Sets the character description status (O(1)).
@@ -215,50 +459,69 @@ impl JsStoryboardManager {
     ) -> Result<(), JsValue> {
         js_result!(self
             .inner
-            .set_characters_description_status(id, status.as_deref()))
+            .entities::<Character>()
+            .set_description_status(id, status.as_deref()))
     }
 
     /// Appends to character history.
     #[wasm_bindgen(js_name = appendCharacterHistory)]
     pub fn append_character_history(&mut self, id: &str, entry: JsValue) -> Result<(), JsValue> {
-        let entry: AssetHistory = from_value(entry)?;
-        js_result!(self.inner.append_characters_history(id, entry))
+        self.append_entity_history::<Character>(id, entry)
     }
 
     /// Sets the character name (O(1)).
     #[wasm_bindgen(js_name = setCharacterName)]
     pub fn set_character_name(&mut self, id: &str, name: &str) -> Result<(), JsValue> {
-        js_result!(self.inner.set_entity_name("characters", id, name))
+        js_result!(self.inner.entities::<Character>().set_name(id, name))
     }
 
     /// Sets the character description (O(1)).
     #[wasm_bindgen(js_name = setCharacterDescription)]
     pub fn set_character_description(&mut self, id: &str, description: &str) -> Result<(), JsValue> {
-        js_result!(self.inner.set_entity_description("characters", id, description))
+        js_result!(self.inner.entities::<Character>().set_description(id, description))
     }
 
     /// Sets the character tag (O(1)).
     #[wasm_bindgen(js_name = setCharacterTag)]
     pub fn set_character_tag(&mut self, id: &str, tag: Option<String>) -> Result<(), JsValue> {
-        js_result!(self.inner.set_entity_tag("characters", id, tag.as_deref()))
+        js_result!(self.inner.entities::<Character>().set_tag(id, tag.as_deref()))
     }
 
     /// Sets the character image_prompt (O(1)).
     #[wasm_bindgen(js_name = setCharacterImagePrompt)]
     pub fn set_character_image_prompt(&mut self, id: &str, prompt: &str) -> Result<(), JsValue> {
-        js_result!(self.inner.set_entity_image_prompt("characters", id, prompt))
+        js_result!(self.inner.entities::<Character>().set_image_prompt(id, prompt))
     }
 
     /// Sets the character caption (O(1)).
     #[wasm_bindgen(js_name = setCharacterCaption)]
     pub fn set_character_caption(&mut self, id: &str, caption: Option<String>) -> Result<(), JsValue> {
-        js_result!(self.inner.set_entity_caption("characters", id, caption.as_deref()))
+        js_result!(self.inner.entities::<Character>().set_caption(id, caption.as_deref()))
     }
 
     /// Sets the character enhanced flag (O(1)).
     #[wasm_bindgen(js_name = setCharacterEnhanced)]
     pub fn set_character_enhanced(&mut self, id: &str, enhanced: bool) -> Result<(), JsValue> {
-        js_result!(self.inner.set_entity_enhanced("characters", id, enhanced))
+        js_result!(self.inner.entities::<Character>().set_enhanced(id, enhanced))
+    }
+
+    /// Sets one character attribute, e.g. ("eye_color", "green") (O(1)).
+    #[wasm_bindgen(js_name = setCharacterAttribute)]
+    pub fn set_character_attribute(&mut self, id: &str, key: &str, value: &str) -> Result<(), JsValue> {
+        js_result!(self.inner.set_character_attribute(id, key, value))
+    }
+
+    /// Removes one character attribute (O(1)).
+    #[wasm_bindgen(js_name = deleteCharacterAttribute)]
+    pub fn delete_character_attribute(&mut self, id: &str, key: &str) -> Result<(), JsValue> {
+        js_result!(self.inner.delete_character_attribute(id, key))
+    }
+
+    /// Gets a character's attributes as a plain object.
+    #[wasm_bindgen(js_name = getCharacterAttributes)]
+    pub fn get_character_attributes(&mut self, id: &str) -> Result<JsValue, JsValue> {
+        let attributes = js_result!(self.inner.get_character_attributes(id))?;
+        Ok(to_js_value(&attributes)?)
     }
 
     // =========================================================================
@@ -268,27 +531,25 @@ impl JsStoryboardManager {
     /// Creates a new prop.
     #[wasm_bindgen(js_name = createProp)]
     pub fn create_prop(&mut self, id: &str, prop: JsValue) -> Result<(), JsValue> {
-        let prop: Prop = from_value(prop)?;
-        js_result!(self.inner.create_props(id, prop))
+        self.create_entity::<Prop>(id, prop)
     }
 
     /// Gets a prop by ID.
     #[wasm_bindgen(js_name = getProp)]
     pub fn get_prop(&mut self, id: &str) -> Result<JsValue, JsValue> {
-        let prop = js_result!(self.inner.get_props(id))?;
-        Ok(to_js_value(&prop)?)
+        self.get_entity::<Prop>(id)
     }
 
     /// Deletes a prop by ID.
     #[wasm_bindgen(js_name = deleteProp)]
     pub fn delete_prop(&mut self, id: &str) -> Result<(), JsValue> {
-        js_result!(self.inner.delete_props(id))
+        self.delete_entity::<Prop>(id)
     }
 
     /// Sets the prop image (O(1)).
     #[wasm_bindgen(js_name = setPropImage)]
     pub fn set_prop_image(&mut self, id: &str, image: Option<String>) -> Result<(), JsValue> {
-        js_result!(self.inner.set_props_image(id, image.as_deref()))
+        js_result!(self.inner.entities::<Prop>().set_image(id, image.as_deref()))
     }
 
     /// Sets the prop generation status (O(1)).
@@ -300,50 +561,50 @@ impl JsStoryboardManager {
     ) -> Result<(), JsValue> {
         js_result!(self
             .inner
-            .set_props_generation_status(id, status.as_deref()))
+            .entities::<Prop>()
+            .set_generation_status(id, status.as_deref()))
     }
 
     /// Appends to prop history.
     #[wasm_bindgen(js_name = appendPropHistory)]
     pub fn append_prop_history(&mut self, id: &str, entry: JsValue) -> Result<(), JsValue> {
-        let entry: AssetHistory = from_value(entry)?;
-        js_result!(self.inner.append_props_history(id, entry))
+        self.append_entity_history::<Prop>(id, entry)
     }
 
     /// Sets the prop name (O(1)).
     #[wasm_bindgen(js_name = setPropName)]
     pub fn set_prop_name(&mut self, id: &str, name: &str) -> Result<(), JsValue> {
-        js_result!(self.inner.set_entity_name("props", id, name))
+        js_result!(self.inner.entities::<Prop>().set_name(id, name))
     }
 
     /// Sets the prop description (O(1)).
     #[wasm_bindgen(js_name = setPropDescription)]
     pub fn set_prop_description(&mut self, id: &str, description: &str) -> Result<(), JsValue> {
-        js_result!(self.inner.set_entity_description("props", id, description))
+        js_result!(self.inner.entities::<Prop>().set_description(id, description))
     }
 
     /// Sets the prop tag (O(1)).
     #[wasm_bindgen(js_name = setPropTag)]
     pub fn set_prop_tag(&mut self, id: &str, tag: Option<String>) -> Result<(), JsValue> {
-        js_result!(self.inner.set_entity_tag("props", id, tag.as_deref()))
+        js_result!(self.inner.entities::<Prop>().set_tag(id, tag.as_deref()))
     }
 
     /// Sets the prop image_prompt (O(1)).
     #[wasm_bindgen(js_name = setPropImagePrompt)]
     pub fn set_prop_image_prompt(&mut self, id: &str, prompt: &str) -> Result<(), JsValue> {
-        js_result!(self.inner.set_entity_image_prompt("props", id, prompt))
+        js_result!(self.inner.entities::<Prop>().set_image_prompt(id, prompt))
     }
 
     /// Sets the prop caption (O(1)).
     #[wasm_bindgen(js_name = setPropCaption)]
     pub fn set_prop_caption(&mut self, id: &str, caption: Option<String>) -> Result<(), JsValue> {
-        js_result!(self.inner.set_entity_caption("props", id, caption.as_deref()))
+        js_result!(self.inner.entities::<Prop>().set_caption(id, caption.as_deref()))
     }
 
     /// Sets the prop enhanced flag (O(1)).
     #[wasm_bindgen(js_name = setPropEnhanced)]
     pub fn set_prop_enhanced(&mut self, id: &str, enhanced: bool) -> Result<(), JsValue> {
-        js_result!(self.inner.set_entity_enhanced("props", id, enhanced))
+        js_result!(self.inner.entities::<Prop>().set_enhanced(id, enhanced))
     }
 
     // =========================================================================
@@ -353,27 +614,25 @@ impl JsStoryboardManager {
     /// Creates a new set/location.
     #[wasm_bindgen(js_name = createSet)]
     pub fn create_set(&mut self, id: &str, set_loc: JsValue) -> Result<(), JsValue> {
-        let set_loc: SetLocation = from_value(set_loc)?;
-        js_result!(self.inner.create_sets(id, set_loc))
+        self.create_entity::<SetLocation>(id, set_loc)
     }
 
     /// Gets a set by ID.
     #[wasm_bindgen(js_name = getSet)]
     pub fn get_set(&mut self, id: &str) -> Result<JsValue, JsValue> {
-        let set_loc = js_result!(self.inner.get_sets(id))?;
-        Ok(to_js_value(&set_loc)?)
+        self.get_entity::<SetLocation>(id)
     }
 
     /// Deletes a set by ID.
     #[wasm_bindgen(js_name = deleteSet)]
     pub fn delete_set(&mut self, id: &str) -> Result<(), JsValue> {
-        js_result!(self.inner.delete_sets(id))
+        self.delete_entity::<SetLocation>(id)
     }
 
     /// Sets the set image (O(1)).
     #[wasm_bindgen(js_name = setSetImage)]
     pub fn set_set_image(&mut self, id: &str, image: Option<String>) -> Result<(), JsValue> {
-        js_result!(self.inner.set_sets_image(id, image.as_deref()))
+        js_result!(self.inner.entities::<SetLocation>().set_image(id, image.as_deref()))
     }
 
     /// Sets the set generation status (O(1)).
@@ -385,50 +644,203 @@ impl JsStoryboardManager {
     ) -> Result<(), JsValue> {
         js_result!(self
             .inner
-            .set_sets_generation_status(id, status.as_deref()))
+            .entities::<SetLocation>()
+            .set_generation_status(id, status.as_deref()))
     }
 
     /// Appends to set history.
     #[wasm_bindgen(js_name = appendSetHistory)]
     pub fn append_set_history(&mut self, id: &str, entry: JsValue) -> Result<(), JsValue> {
-        let entry: AssetHistory = from_value(entry)?;
-        js_result!(self.inner.append_sets_history(id, entry))
+        self.append_entity_history::<SetLocation>(id, entry)
     }
 
     /// Sets the set name (O(1)).
     #[wasm_bindgen(js_name = setSetName)]
     pub fn set_set_name(&mut self, id: &str, name: &str) -> Result<(), JsValue> {
-        js_result!(self.inner.set_entity_name("sets", id, name))
+        js_result!(self.inner.entities::<SetLocation>().set_name(id, name))
     }
 
     /// Sets the set description (O(1)).
     #[wasm_bindgen(js_name = setSetDescription)]
     pub fn set_set_description(&mut self, id: &str, description: &str) -> Result<(), JsValue> {
-        js_result!(self.inner.set_entity_description("sets", id, description))
+        js_result!(self.inner.entities::<SetLocation>().set_description(id, description))
     }
 
     /// Sets the set tag (O(1)).
     #[wasm_bindgen(js_name = setSetTag)]
     pub fn set_set_tag(&mut self, id: &str, tag: Option<String>) -> Result<(), JsValue> {
-        js_result!(self.inner.set_entity_tag("sets", id, tag.as_deref()))
+        js_result!(self.inner.entities::<SetLocation>().set_tag(id, tag.as_deref()))
     }
 
     /// Sets the set image_prompt (O(1)).
     #[wasm_bindgen(js_name = setSetImagePrompt)]
     pub fn set_set_image_prompt(&mut self, id: &str, prompt: &str) -> Result<(), JsValue> {
-        js_result!(self.inner.set_entity_image_prompt("sets", id, prompt))
+        js_result!(self.inner.entities::<SetLocation>().set_image_prompt(id, prompt))
     }
 
     /// Sets the set caption (O(1)).
     #[wasm_bindgen(js_name = setSetCaption)]
     pub fn set_set_caption(&mut self, id: &str, caption: Option<String>) -> Result<(), JsValue> {
-        js_result!(self.inner.set_entity_caption("sets", id, caption.as_deref()))
+        js_result!(self.inner.entities::<SetLocation>().set_caption(id, caption.as_deref()))
     }
 
     /// Sets the set enhanced flag (O(1)).
     #[wasm_bindgen(js_name = setSetEnhanced)]
     pub fn set_set_enhanced(&mut self, id: &str, enhanced: bool) -> Result<(), JsValue> {
-        js_result!(self.inner.set_entity_enhanced("sets", id, enhanced))
+        js_result!(self.inner.entities::<SetLocation>().set_enhanced(id, enhanced))
+    }
+
+    // =========================================================================
+    // CUSTOM COLLECTION OPERATIONS
+    // =========================================================================
+
+    /// Creates an entity in a custom collection (e.g. "vehicles").
+    #[wasm_bindgen(js_name = createCustomEntity)]
+    pub fn create_custom_entity(
+        &mut self,
+        collection: &str,
+        id: &str,
+        entity: JsValue,
+    ) -> Result<(), JsValue> {
+        let entity: CustomEntity = from_value(entity)?;
+        js_result!(self.inner.create_custom_entity(collection, id, entity))
+    }
+
+    /// Gets an entity from a custom collection.
+    #[wasm_bindgen(js_name = getCustomEntity)]
+    pub fn get_custom_entity(&mut self, collection: &str, id: &str) -> Result<JsValue, JsValue> {
+        let entity = js_result!(self.inner.get_custom_entity(collection, id))?;
+        Ok(to_js_value(&entity)?)
+    }
+
+    /// Gets a custom collection (entities and order).
+    #[wasm_bindgen(js_name = getCustomCollection)]
+    pub fn get_custom_collection(&mut self, collection: &str) -> Result<JsValue, JsValue> {
+        let collection = js_result!(self.inner.get_custom_collection(collection))?;
+        Ok(to_js_value(&collection)?)
+    }
+
+    /// Lists the custom collection names.
+    #[wasm_bindgen(js_name = getCustomCollections)]
+    pub fn get_custom_collections(&mut self) -> Result<Array, JsValue> {
+        let names = js_result!(self.inner.get_custom_collections())?;
+        Ok(names.iter().map(|name| JsValue::from_str(name)).collect())
+    }
+
+    /// Deletes an entity from a custom collection.
+    #[wasm_bindgen(js_name = deleteCustomEntity)]
+    pub fn delete_custom_entity(&mut self, collection: &str, id: &str) -> Result<(), JsValue> {
+        js_result!(self.inner.delete_custom_entity(collection, id))
+    }
+
+    /// Deletes a custom collection with all its entities.
+    #[wasm_bindgen(js_name = deleteCustomCollection)]
+    pub fn delete_custom_collection(&mut self, collection: &str) -> Result<(), JsValue> {
+        js_result!(self.inner.delete_custom_collection(collection))
+    }
+
+    /// Reorders the entities of a custom collection.
+    #[wasm_bindgen(js_name = reorderCustomEntities)]
+    pub fn reorder_custom_entities(&mut self, collection: &str, new_order: Array) -> Result<(), JsValue> {
+        let order: Vec<String> = new_order
+            .iter()
+            .filter_map(|v| v.as_string())
+            .collect();
+        js_result!(self.inner.reorder_custom_entities(collection, order))
+    }
+
+    /// Sets a custom entity image (O(1)).
+    #[wasm_bindgen(js_name = setCustomEntityImage)]
+    pub fn set_custom_entity_image(
+        &mut self,
+        collection: &str,
+        id: &str,
+        image: Option<String>,
+    ) -> Result<(), JsValue> {
+        js_result!(self
+            .inner
+            .set_custom_entity_image(collection, id, image.as_deref()))
+    }
+
+    /// Sets a custom entity generation status (O(1)).
+    #[wasm_bindgen(js_name = setCustomEntityGenerationStatus)]
+    pub fn set_custom_entity_generation_status(
+        &mut self,
+        collection: &str,
+        id: &str,
+        status: Option<String>,
+    ) -> Result<(), JsValue> {
+        js_result!(self
+            .inner
+            .set_custom_entity_generation_status(collection, id, status.as_deref()))
+    }
+
+    /// Appends to custom entity history.
+    #[wasm_bindgen(js_name = appendCustomEntityHistory)]
+    pub fn append_custom_entity_history(
+        &mut self,
+        collection: &str,
+        id: &str,
+        entry: JsValue,
+    ) -> Result<(), JsValue> {
+        let entry: AssetHistory = from_value(entry)?;
+        js_result!(self.inner.append_custom_entity_history(collection, id, entry))
+    }
+
+    // =========================================================================
+    // UPLOADED ASSET OPERATIONS
+    // =========================================================================
+
+    /// Adds (or replaces) an uploaded asset.
+    #[wasm_bindgen(js_name = createUploadedAsset)]
+    pub fn create_uploaded_asset(&mut self, id: &str, asset: JsValue) -> Result<(), JsValue> {
+        let asset: UploadedAsset = from_value(asset)?;
+        js_result!(self.inner.create_uploaded_asset(id, asset))
+    }
+
+    /// Gets an uploaded asset by ID.
+    #[wasm_bindgen(js_name = getUploadedAsset)]
+    pub fn get_uploaded_asset(&mut self, id: &str) -> Result<JsValue, JsValue> {
+        let asset = js_result!(self.inner.get_uploaded_asset(id))?;
+        Ok(to_js_value(&asset)?)
+    }
+
+    /// Deletes an uploaded asset by ID.
+    #[wasm_bindgen(js_name = deleteUploadedAsset)]
+    pub fn delete_uploaded_asset(&mut self, id: &str) -> Result<(), JsValue> {
+        js_result!(self.inner.delete_uploaded_asset(id))
+    }
+
+    /// Lists uploaded assets, oldest first.
+    #[wasm_bindgen(js_name = listUploadedAssets)]
+    pub fn list_uploaded_assets(&mut self) -> Result<JsValue, JsValue> {
+        let assets = js_result!(self.inner.list_uploaded_assets())?;
+        Ok(to_js_value(&assets)?)
+    }
+
+    /// Deletes uploaded assets no image field references (returns their IDs).
+    #[wasm_bindgen(js_name = gcUnreferencedAssets)]
+    pub fn gc_unreferenced_assets(&mut self) -> Result<Array, JsValue> {
+        let ids = js_result!(self.inner.gc_unreferenced_assets())?;
+        Ok(ids.iter().map(|id| JsValue::from_str(id)).collect())
+    }
+
+    /// Stores each distinct inline image once in the document and replaces
+    /// the image fields with references. Returns
+    /// `{ fields, blobs, bytes_before, bytes_after }`.
+    #[cfg(feature = "blob-store")]
+    #[wasm_bindgen(js_name = dedupImages)]
+    pub fn dedup_images(&mut self) -> Result<JsValue, JsValue> {
+        let report = js_result!(self.inner.dedup_images())?;
+        Ok(to_js_value(&report)?)
+    }
+
+    /// Returns the data URL behind a `blob:sha256:` reference stored in the
+    /// document, or the image unchanged.
+    #[cfg(feature = "blob-store")]
+    #[wasm_bindgen(js_name = resolveImage)]
+    pub fn resolve_image(&mut self, image: &str) -> Result<String, JsValue> {
+        js_result!(self.inner.resolve_image(image))
     }
 
     // =========================================================================
@@ -442,13 +854,20 @@ impl JsStoryboardManager {
         js_result!(self.inner.create_scene(id, scene))
     }
 
-    /// Gets a scene by ID.
+    /// Gets a scene by ID, hydrating only that scene.
     #[wasm_bindgen(js_name = getScene)]
     pub fn get_scene(&mut self, id: &str) -> Result<JsValue, JsValue> {
         let scene = js_result!(self.inner.get_scene(id))?;
         Ok(to_js_value(&scene)?)
     }
 
+    /// Copies a scene with its shots, looks and outfits to `newId`.
+    /// Shots get fresh IDs; the copy is appended to the scene order.
+    #[wasm_bindgen(js_name = duplicateScene)]
+    pub fn duplicate_scene(&mut self, scene_id: &str, new_id: &str) -> Result<(), JsValue> {
+        js_result!(self.inner.duplicate_scene(scene_id, new_id))
+    }
+
     /// Deletes a scene by ID.
     #[wasm_bindgen(js_name = deleteScene)]
     pub fn delete_scene(&mut self, id: &str) -> Result<(), JsValue> {
@@ -465,6 +884,55 @@ impl JsStoryboardManager {
         js_result!(self.inner.reorder_scenes(order))
     }
 
+    /// Returns the scene IDs in display order.
+    #[wasm_bindgen(js_name = getSceneOrder)]
+    pub fn get_scene_order(&mut self) -> Result<Array, JsValue> {
+        let ids = js_result!(self.inner.get_scene_order())?;
+        Ok(ids.iter().map(|id| JsValue::from_str(id)).collect())
+    }
+
+    /// Moves a scene between two neighbours in display order (null at
+    /// either end). With fractional order only the scene's key is written.
+    #[wasm_bindgen(js_name = moveSceneBetween)]
+    pub fn move_scene_between(
+        &mut self,
+        scene_id: &str,
+        prev: Option<String>,
+        next: Option<String>,
+    ) -> Result<(), JsValue> {
+        js_result!(self
+            .inner
+            .move_scene_between(scene_id, prev.as_deref(), next.as_deref()))
+    }
+
+    /// Switches between list and fractional scene ordering.
+    #[wasm_bindgen(js_name = setFractionalOrder)]
+    pub fn set_fractional_order(&mut self, enabled: bool) -> Result<(), JsValue> {
+        js_result!(self.inner.set_fractional_order(enabled))
+    }
+
+    /// Sets every scene number to the scene's position in the scene order,
+    /// as one change. Returns the number of scenes changed.
+    #[wasm_bindgen(js_name = renumberScenes)]
+    pub fn renumber_scenes(&mut self) -> Result<usize, JsValue> {
+        js_result!(self.inner.renumber_scenes())
+    }
+
+    /// Makes reorderScenes, reorderShots and moveShot also renumber what
+    /// they reorder (off by default).
+    #[wasm_bindgen(js_name = setAutoRenumber)]
+    pub fn set_auto_renumber(&mut self, enabled: bool) {
+        self.inner.set_auto_renumber(enabled);
+    }
+
+    /// Appends the scenes of a Fountain screenplay (returns the new scene IDs).
+    #[cfg(feature = "script-import")]
+    #[wasm_bindgen(js_name = importFountain)]
+    pub fn import_fountain(&mut self, text: &str) -> Result<Array, JsValue> {
+        let ids = js_result!(self.inner.import_fountain(text))?;
+        Ok(ids.iter().map(|id| JsValue::from_str(id)).collect())
+    }
+
     /// Sets a character look for a scene.
     #[wasm_bindgen(js_name = setCharacterLook)]
     pub fn set_character_look(
@@ -543,6 +1011,18 @@ impl JsStoryboardManager {
         js_result!(self.inner.set_scene_reasoning(scene_id, reasoning.as_deref()))
     }
 
+    /// Sets the scene thumbnail (O(1), pass null to clear).
+    #[wasm_bindgen(js_name = setSceneThumbnail)]
+    pub fn set_scene_thumbnail(&mut self, scene_id: &str, thumbnail: Option<String>) -> Result<(), JsValue> {
+        js_result!(self.inner.set_scene_thumbnail(scene_id, thumbnail.as_deref()))
+    }
+
+    /// Uses a shot's image as its scene's thumbnail (O(1)).
+    #[wasm_bindgen(js_name = pickThumbnailFromShot)]
+    pub fn pick_thumbnail_from_shot(&mut self, scene_id: &str, shot_id: &str) -> Result<(), JsValue> {
+        js_result!(self.inner.pick_thumbnail_from_shot(scene_id, shot_id))
+    }
+
     // =========================================================================
     // SHOT OPERATIONS
     // =========================================================================
@@ -582,6 +1062,202 @@ impl JsStoryboardManager {
         js_result!(self.inner.reorder_shots(scene_id, order))
     }
 
+    /// Sets every shot number in a scene to the shot's position in its shot
+    /// order, as one change. Returns the number of shots changed.
+    #[wasm_bindgen(js_name = renumberShots)]
+    pub fn renumber_shots(&mut self, scene_id: &str) -> Result<usize, JsValue> {
+        js_result!(self.inner.renumber_shots(scene_id))
+    }
+
+    /// Moves a shot to another scene as a single change, inserting it at
+    /// `position` in the target's shot order (appended if omitted).
+    #[wasm_bindgen(js_name = moveShot)]
+    pub fn move_shot(
+        &mut self,
+        from_scene: &str,
+        to_scene: &str,
+        shot_id: &str,
+        position: Option<u32>,
+    ) -> Result<(), JsValue> {
+        js_result!(self.inner.move_shot(
+            from_scene,
+            to_scene,
+            shot_id,
+            position.map(|p| p as usize)
+        ))
+    }
+
+    /// Sets the local user and ACL enforcement mode.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.setPermissionContext({ user_id: user.id, enforcement: 'deny' });
+    /// ```
+    #[wasm_bindgen(js_name = setPermissionContext)]
+    pub fn set_permission_context(&mut self, context: JsValue) -> Result<(), JsValue> {
+        let context: PermissionContext = from_value(context)?;
+        self.inner.set_permission_context(context);
+        Ok(())
+    }
+
+    /// Returns and clears the denials recorded in warn mode.
+    #[wasm_bindgen(js_name = takePermissionWarnings)]
+    pub fn take_permission_warnings(&mut self) -> Array {
+        self.inner
+            .take_permission_warnings()
+            .into_iter()
+            .map(|w| JsValue::from_str(&w))
+            .collect()
+    }
+
+    /// Gets the ACL (`{ owners, editors, commenters }` keyed by user ID).
+    #[wasm_bindgen(js_name = getAcl)]
+    pub fn get_acl(&mut self) -> Result<JsValue, JsValue> {
+        let acl = js_result!(self.inner.get_acl())?;
+        Ok(to_js_value(&acl)?)
+    }
+
+    /// Gives a user exactly `role` ('owner' | 'editor' | 'commenter').
+    #[wasm_bindgen(js_name = grantRole)]
+    pub fn grant_role(&mut self, user_id: &str, role: JsValue) -> Result<(), JsValue> {
+        let role: Role = from_value(role)?;
+        js_result!(self.inner.grant_role(user_id, role))
+    }
+
+    /// Removes every role of a user. Returns false if it had none.
+    #[wasm_bindgen(js_name = revokeRole)]
+    pub fn revoke_role(&mut self, user_id: &str) -> Result<bool, JsValue> {
+        js_result!(self.inner.revoke_role(user_id))
+    }
+
+    /// Makes the document read-only; edits then throw an error with
+    /// `code: "DOCUMENT_FROZEN"` until `unfreeze()`.
+    #[wasm_bindgen]
+    pub fn freeze(&mut self) -> Result<(), JsValue> {
+        js_result!(self.inner.freeze())
+    }
+
+    /// Lifts `freeze()`.
+    #[wasm_bindgen]
+    pub fn unfreeze(&mut self) -> Result<(), JsValue> {
+        js_result!(self.inner.unfreeze())
+    }
+
+    /// Returns true if the document is frozen.
+    #[wasm_bindgen(js_name = isFrozen)]
+    pub fn is_frozen(&self) -> Result<bool, JsValue> {
+        js_result!(self.inner.is_frozen())
+    }
+
+    /// Enables or disables admin mode, which allows edits while frozen.
+    #[wasm_bindgen(js_name = setAdmin)]
+    pub fn set_admin(&mut self, admin: bool) {
+        self.inner.set_admin(admin);
+    }
+
+    /// Gets the trashed scenes and shots.
+    #[wasm_bindgen(js_name = getTrash)]
+    pub fn get_trash(&mut self) -> Result<JsValue, JsValue> {
+        let trash = js_result!(self.inner.get_trash())?;
+        Ok(to_js_value(&trash)?)
+    }
+
+    /// Moves a scene (with its shots) to the trash.
+    #[wasm_bindgen(js_name = trashScene)]
+    pub fn trash_scene(
+        &mut self,
+        id: &str,
+        deleted_at: i64,
+        deleted_by: Option<String>,
+    ) -> Result<(), JsValue> {
+        js_result!(self.inner.trash_scene(id, deleted_at, deleted_by.as_deref()))
+    }
+
+    /// Moves a shot to the trash.
+    #[wasm_bindgen(js_name = trashShot)]
+    pub fn trash_shot(
+        &mut self,
+        scene_id: &str,
+        shot_id: &str,
+        deleted_at: i64,
+        deleted_by: Option<String>,
+    ) -> Result<(), JsValue> {
+        js_result!(self
+            .inner
+            .trash_shot(scene_id, shot_id, deleted_at, deleted_by.as_deref()))
+    }
+
+    /// Restores a trashed scene at its old position.
+    #[wasm_bindgen(js_name = restoreScene)]
+    pub fn restore_scene(&mut self, id: &str) -> Result<(), JsValue> {
+        js_result!(self.inner.restore_scene(id))
+    }
+
+    /// Restores a trashed shot at its old position in its scene.
+    #[wasm_bindgen(js_name = restoreShot)]
+    pub fn restore_shot(&mut self, scene_id: &str, shot_id: &str) -> Result<(), JsValue> {
+        js_result!(self.inner.restore_shot(scene_id, shot_id))
+    }
+
+    /// Permanently removes entries trashed before `olderThan`.
+    /// Returns how many were removed.
+    #[wasm_bindgen(js_name = emptyTrash)]
+    pub fn empty_trash(&mut self, older_than: i64) -> Result<usize, JsValue> {
+        js_result!(self.inner.empty_trash(older_than))
+    }
+
+    /// Claims an advisory lock on a scene for `user` until `now + ttlMs`.
+    /// Returns false while another user holds an active lock.
+    #[wasm_bindgen(js_name = claimScene)]
+    pub fn claim_scene(
+        &mut self,
+        scene_id: &str,
+        user: &str,
+        ttl_ms: i64,
+        now: i64,
+    ) -> Result<bool, JsValue> {
+        js_result!(self.inner.claim_scene(scene_id, user, ttl_ms, now))
+    }
+
+    /// Releases `user`'s lock on a scene. Returns false if `user` does not hold it.
+    #[wasm_bindgen(js_name = releaseScene)]
+    pub fn release_scene(&mut self, scene_id: &str, user: &str) -> Result<bool, JsValue> {
+        js_result!(self.inner.release_scene(scene_id, user))
+    }
+
+    /// Gets the scene locks active at `now` (`{ [sceneId]: { user, claimed_at, expires_at } }`).
+    #[wasm_bindgen(js_name = getActiveLocks)]
+    pub fn get_active_locks(&mut self, now: i64) -> Result<JsValue, JsValue> {
+        let locks = js_result!(self.inner.get_active_locks(now))?;
+        Ok(to_js_value(&locks)?)
+    }
+
+    /// Records the current state under `name` (replacing a snapshot with
+    /// that name).
+    #[wasm_bindgen(js_name = createSnapshot)]
+    pub fn create_snapshot(&mut self, name: &str) -> Result<(), JsValue> {
+        js_result!(self.inner.create_snapshot(name))
+    }
+
+    /// Gets the named snapshots (`{ [name]: { heads, created_by } }`).
+    #[wasm_bindgen(js_name = listSnapshots)]
+    pub fn list_snapshots(&mut self) -> Result<JsValue, JsValue> {
+        let snapshots = js_result!(self.inner.list_snapshots())?;
+        Ok(to_js_value(&snapshots)?)
+    }
+
+    /// Deletes a named snapshot. Returns false if it did not exist.
+    #[wasm_bindgen(js_name = deleteSnapshot)]
+    pub fn delete_snapshot(&mut self, name: &str) -> Result<bool, JsValue> {
+        js_result!(self.inner.delete_snapshot(name))
+    }
+
+    /// Restores the state recorded by snapshot `name` as a new change.
+    #[wasm_bindgen(js_name = restoreSnapshot)]
+    pub fn restore_snapshot(&mut self, name: &str) -> Result<(), JsValue> {
+        js_result!(self.inner.restore_snapshot(name))
+    }
+
     /// Sets the shot image (O(1)).
     #[wasm_bindgen(js_name = setShotImage)]
     pub fn set_shot_image(
@@ -608,6 +1284,114 @@ impl JsStoryboardManager {
             .set_shot_generation_status(scene_id, shot_id, status.as_deref()))
     }
 
+    /// Sets the shot generation status and records when it was set (O(1)).
+    #[wasm_bindgen(js_name = setShotGenerationStatusAt)]
+    pub fn set_shot_generation_status_at(
+        &mut self,
+        scene_id: &str,
+        shot_id: &str,
+        status: &str,
+        timestamp: f64,
+    ) -> Result<(), JsValue> {
+        js_result!(self.inner.set_shot_generation_status_at(
+            scene_id,
+            shot_id,
+            status,
+            timestamp as i64
+        ))
+    }
+
+    /// Sets the generation status of many shots as a single change.
+    /// `updates` is an array of `[sceneId, shotId, status]`.
+    #[wasm_bindgen(js_name = setShotStatusBatch)]
+    pub fn set_shot_status_batch(&mut self, updates: JsValue) -> Result<(), JsValue> {
+        let updates: Vec<(String, String, String)> = from_value(updates)?;
+        js_result!(self.inner.set_shot_statuses(updates))
+    }
+
+    /// Sets the shot render provider (O(1)).
+    #[wasm_bindgen(js_name = setShotProvider)]
+    pub fn set_shot_provider(
+        &mut self,
+        scene_id: &str,
+        shot_id: &str,
+        provider: Option<String>,
+    ) -> Result<(), JsValue> {
+        js_result!(self
+            .inner
+            .set_shot_provider(scene_id, shot_id, provider.as_deref()))
+    }
+
+    /// Sets the shot render job ID (O(1)).
+    #[wasm_bindgen(js_name = setShotJobId)]
+    pub fn set_shot_job_id(
+        &mut self,
+        scene_id: &str,
+        shot_id: &str,
+        job_id: Option<String>,
+    ) -> Result<(), JsValue> {
+        js_result!(self
+            .inner
+            .set_shot_job_id(scene_id, shot_id, job_id.as_deref()))
+    }
+
+    /// Finds the shot submitted as `job_id`. Returns `[sceneId, shotId]` or null.
+    #[wasm_bindgen(js_name = findShotByJobId)]
+    pub fn find_shot_by_job_id(&mut self, job_id: &str) -> Result<JsValue, JsValue> {
+        let found = js_result!(self.inner.find_shot_by_job_id(job_id))?;
+        Ok(to_js_value(&found)?)
+    }
+
+    /// Finds a shot by ID in any scene. Returns `[sceneId, shot]` or null.
+    #[wasm_bindgen(js_name = findShot)]
+    pub fn find_shot(&mut self, shot_id: &str) -> Result<JsValue, JsValue> {
+        let found = js_result!(self.inner.find_shot(shot_id))?;
+        Ok(to_js_value(&found)?)
+    }
+
+    /// Links a shot to the sequence generation node its image came from
+    /// (O(1)); null removes the link.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// manager.setShotGenerationRef('scene-1', 'shot-3', { doc_id: 'sequence-2', node_id: 'gen-1' });
+    /// ```
+    #[wasm_bindgen(js_name = setShotGenerationRef)]
+    pub fn set_shot_generation_ref(
+        &mut self,
+        scene_id: &str,
+        shot_id: &str,
+        reference: JsValue,
+    ) -> Result<(), JsValue> {
+        let reference: Option<DocRef> = from_value(reference)?;
+        js_result!(self
+            .inner
+            .set_shot_generation_ref(scene_id, shot_id, reference.as_ref()))
+    }
+
+    /// Calls `resolver` with the shot's generation link and returns its
+    /// result, or null if the shot has no link.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// const node = manager.resolveGenerationRef('scene-1', 'shot-3', (ref) =>
+    ///   sequences.get(ref.doc_id)?.getNode(ref.node_id) ?? null);
+    /// ```
+    #[wasm_bindgen(js_name = resolveGenerationRef)]
+    pub fn resolve_generation_ref(
+        &mut self,
+        scene_id: &str,
+        shot_id: &str,
+        resolver: &js_sys::Function,
+    ) -> Result<JsValue, JsValue> {
+        let shot = js_result!(self.inner.get_shot(scene_id, shot_id))?
+            .ok_or_else(|| JsValue::from(CollabError::field_not_found(shot_id)))?;
+        match shot.generation_ref {
+            Some(reference) => resolver.call1(&JsValue::NULL, &to_js_value(&reference)?),
+            None => Ok(JsValue::NULL),
+        }
+    }
+
     /// Sets the shot image prompt (O(1)).
     #[wasm_bindgen(js_name = setShotImagePrompt)]
     pub fn set_shot_image_prompt(
@@ -721,6 +1505,222 @@ impl JsStoryboardManager {
         js_result!(self.inner.set_shot_angle(scene_id, shot_id, angle))
     }
 
+    // =========================================================================
+    // COMMENT OPERATIONS
+    // =========================================================================
+
+    /// Appends a comment to a shot (O(1)).
+    ///
+    /// `comment` is `{ id, author, body, created_at, resolved, parent_id, mentions }`.
+    #[wasm_bindgen(js_name = addShotComment)]
+    pub fn add_shot_comment(
+        &mut self,
+        scene_id: &str,
+        shot_id: &str,
+        comment: JsValue,
+    ) -> Result<(), JsValue> {
+        let comment: Comment = from_value(comment)?;
+        js_result!(self.inner.add_shot_comment(scene_id, shot_id, comment))
+    }
+
+    /// Marks a shot comment as resolved or reopens it (O(1)).
+    #[wasm_bindgen(js_name = resolveShotComment)]
+    pub fn resolve_shot_comment(
+        &mut self,
+        scene_id: &str,
+        shot_id: &str,
+        comment_id: &str,
+        resolved: bool,
+    ) -> Result<(), JsValue> {
+        js_result!(self
+            .inner
+            .resolve_shot_comment(scene_id, shot_id, comment_id, resolved))
+    }
+
+    /// Appends a comment to a scene (O(1)).
+    #[wasm_bindgen(js_name = addSceneComment)]
+    pub fn add_scene_comment(&mut self, scene_id: &str, comment: JsValue) -> Result<(), JsValue> {
+        let comment: Comment = from_value(comment)?;
+        js_result!(self.inner.add_scene_comment(scene_id, comment))
+    }
+
+    /// Marks a scene comment as resolved or reopens it (O(1)).
+    #[wasm_bindgen(js_name = resolveSceneComment)]
+    pub fn resolve_scene_comment(
+        &mut self,
+        scene_id: &str,
+        comment_id: &str,
+        resolved: bool,
+    ) -> Result<(), JsValue> {
+        js_result!(self
+            .inner
+            .resolve_scene_comment(scene_id, comment_id, resolved))
+    }
+
+    // =========================================================================
+    // MAINTENANCE
+    // =========================================================================
+
+    /// Downgrades shots stuck in "processing" to "stalled" and removes
+    /// expired scene locks.
+    ///
+    /// Returns a report
+    /// `{ stalled: [{ path: [sceneId, shotId], since }], released_locks: [sceneId] }`.
+    /// `ttl_ms` defaults to 10 minutes.
+    #[wasm_bindgen]
+    pub fn sweep(&mut self, now: f64, ttl_ms: Option<f64>) -> Result<JsValue, JsValue> {
+        let config = match ttl_ms {
+            Some(ttl) => SweepConfig::new(ttl as i64),
+            None => SweepConfig::default(),
+        };
+        let report = js_result!(self.inner.sweep_with_config(now as i64, &config))?;
+        Ok(to_js_value(&report)?)
+    }
+
+    /// Makes all order lists match their maps after concurrent edits.
+    ///
+    /// Returns `{ actions: [{ path, id, kind }] }`.
+    #[wasm_bindgen]
+    pub fn repair(&mut self) -> Result<JsValue, JsValue> {
+        let report = js_result!(self.inner.repair())?;
+        Ok(to_js_value(&report)?)
+    }
+
+    /// Checks the document for inconsistencies (for pre-save checks).
+    ///
+    /// Returns `{ issues: [{ severity: "error" | "warning", code, path, message }] }`.
+    #[wasm_bindgen]
+    pub fn validate(&mut self) -> Result<JsValue, JsValue> {
+        let report = js_result!(self.inner.validate())?;
+        Ok(to_js_value(&report)?)
+    }
+
+    /// Deletes a character, handling references per `policy`
+    /// ("deny" | "detach" | "cascade").
+    #[wasm_bindgen(js_name = deleteCharactersCascade)]
+    pub fn delete_characters_cascade(&mut self, id: &str, policy: JsValue) -> Result<(), JsValue> {
+        let policy: crate::storyboard::DeletePolicy = from_value(policy)?;
+        js_result!(self.inner.delete_characters_cascade(id, policy))
+    }
+
+    /// Lists where a tag is referenced. Returns `{ scenes, shots: [sceneId,
+    /// shotId][], looks, outfits, entities: [collection, id][] }`.
+    #[wasm_bindgen(js_name = getEntityUsages)]
+    pub fn get_entity_usages(&mut self, tag: &str) -> Result<JsValue, JsValue> {
+        let usages = js_result!(self.inner.get_entity_usages(tag))?;
+        Ok(to_js_value(&usages)?)
+    }
+
+    /// Renames an entity tag and every reference to it.
+    /// Returns `{ paths: string[][] }` listing the changed locations.
+    #[wasm_bindgen(js_name = renameTag)]
+    pub fn rename_tag(&mut self, old: &str, new: &str) -> Result<JsValue, JsValue> {
+        let report = js_result!(self.inner.rename_tag(old, new))?;
+        Ok(to_js_value(&report)?)
+    }
+
+    /// Renders a shot list as text. `format` is "csv" or "markdown".
+    #[wasm_bindgen(js_name = exportShotList)]
+    pub fn export_shot_list(&mut self, format: &str) -> Result<String, JsValue> {
+        let format: ShotListFormat = js_result!(format.parse())?;
+        js_result!(self.inner.export_shot_list(format))
+    }
+
+    /// Renders a PDF contact sheet (returns Uint8Array). `options` is
+    /// `{ title?, page_width?, page_height?, columns? }`; images are shown
+    /// as placeholders.
+    #[cfg(feature = "pdf-export")]
+    #[wasm_bindgen(js_name = exportPdf)]
+    pub fn export_pdf(&mut self, options: JsValue) -> Result<Uint8Array, JsValue> {
+        let options: crate::storyboard::pdf::PdfOptions = if options.is_undefined() {
+            Default::default()
+        } else {
+            from_value(options)?
+        };
+        let bytes = js_result!(self.inner.export_pdf(&options))?;
+        Ok(Uint8Array::from(&bytes[..]))
+    }
+
+    // =========================================================================
+    // PRESENCE
+    // =========================================================================
+
+    /// Sets this peer's focus and returns the message to broadcast (Uint8Array).
+    ///
+    /// `mode` is "viewing" or "editing"; `cursor` is `{ field, anchor, head }` or null.
+    #[wasm_bindgen(js_name = setLocalPresence)]
+    pub fn set_local_presence(
+        &mut self,
+        user: &str,
+        scene_id: Option<String>,
+        shot_id: Option<String>,
+        mode: &str,
+        cursor: JsValue,
+    ) -> Result<Uint8Array, JsValue> {
+        let mode = match mode {
+            "viewing" => PresenceMode::Viewing,
+            "editing" => PresenceMode::Editing,
+            other => return Err(JsValue::from_str(&format!("Unknown presence mode: {}", other))),
+        };
+        let cursor: Option<Cursor> = from_value(cursor)?;
+        let bytes = self.inner.set_local_presence(
+            user,
+            scene_id.as_deref(),
+            shot_id.as_deref(),
+            mode,
+            cursor,
+        );
+        Ok(Uint8Array::from(&bytes[..]))
+    }
+
+    /// Clears this peer's presence and returns a "left" message to broadcast.
+    #[wasm_bindgen(js_name = clearLocalPresence)]
+    pub fn clear_local_presence(&mut self) -> Uint8Array {
+        Uint8Array::from(&self.inner.clear_local_presence()[..])
+    }
+
+    /// Encodes the current local presence (send periodically as a heartbeat).
+    #[wasm_bindgen(js_name = encodePresence)]
+    pub fn encode_presence(&self) -> Uint8Array {
+        Uint8Array::from(&self.inner.encode_presence()[..])
+    }
+
+    /// Applies a presence message received at `now` (ms). Returns true if peers changed.
+    #[wasm_bindgen(js_name = applyPresenceMessage)]
+    pub fn apply_presence_message(&mut self, msg: &[u8], now: f64) -> Result<bool, JsValue> {
+        js_result!(self.inner.apply_presence_message(msg, now as i64))
+    }
+
+    /// Sets how long a silent peer is kept (ms, default 30000).
+    #[wasm_bindgen(js_name = setPeerTimeout)]
+    pub fn set_peer_timeout(&mut self, timeout_ms: f64) {
+        self.inner.set_peer_timeout(timeout_ms as i64);
+    }
+
+    /// Drops silent peers. Returns the removed actor IDs.
+    #[wasm_bindgen(js_name = prunePeers)]
+    pub fn prune_peers(&mut self, now: f64) -> Result<JsValue, JsValue> {
+        Ok(to_js_value(&self.inner.prune_peers(now as i64))?)
+    }
+
+    /// Returns all remote peers as `[{ actor, clock, last_seen, state }]`.
+    #[wasm_bindgen(js_name = getPeers)]
+    pub fn get_peers(&self) -> Result<JsValue, JsValue> {
+        Ok(to_js_value(&self.inner.get_peers())?)
+    }
+
+    /// Returns the peers focused on a shot (for per-shot avatars).
+    #[wasm_bindgen(js_name = getShotPeers)]
+    pub fn get_shot_peers(&self, scene_id: &str, shot_id: &str) -> Result<JsValue, JsValue> {
+        Ok(to_js_value(&self.inner.get_shot_peers(scene_id, shot_id))?)
+    }
+
+    /// Forgets a peer by actor ID (e.g., when its connection closes).
+    #[wasm_bindgen(js_name = removePeer)]
+    pub fn remove_peer(&mut self, actor: &str) -> bool {
+        self.inner.remove_peer(actor)
+    }
+
     // =========================================================================
     // SYNC OPERATIONS
     // =========================================================================
@@ -731,6 +1731,19 @@ impl JsStoryboardManager {
         js_result!(self.inner.merge(&mut other.inner))
     }
 
+    /// Applies only the given changes (hex hashes) from `other`, plus their
+    /// missing dependencies. Returns `{ applied, already_present, not_found }`.
+    #[wasm_bindgen(js_name = cherryPick)]
+    pub fn cherry_pick(
+        &mut self,
+        other: &mut JsStoryboardManager,
+        change_hashes: Array,
+    ) -> Result<JsValue, JsValue> {
+        let hashes = js_result!(crate::sync::heads_from_js(&change_hashes))?;
+        let report = js_result!(self.inner.cherry_pick(&mut other.inner, &hashes))?;
+        Ok(to_js_value(&report)?)
+    }
+
     /// Gets changes since the given heads (for incremental sync).
     ///
     /// Takes an array of hex-encoded change hashes and returns the diff bytes
@@ -787,6 +1800,64 @@ impl JsStoryboardManager {
         js_result!(self.inner.apply_sync_message(changes))
     }
 
+    /// Gets changes since the given heads as frames of at most `maxBytes`.
+    ///
+    /// Use for first sync of large boards on slow connections; apply each
+    /// frame in order with `applyChanges`.
+    ///
+    /// # Example (JavaScript)
+    /// ```js
+    /// for (const frame of manager.getChangesSinceChunked(heads, 64 * 1024)) {
+    ///   ws.send(frame);
+    /// }
+    /// ```
+    #[wasm_bindgen(js_name = getChangesSinceChunked)]
+    pub fn get_changes_since_chunked(
+        &mut self,
+        their_heads: Array,
+        max_bytes: usize,
+    ) -> Result<Array, JsValue> {
+        let heads = js_result!(crate::sync::heads_from_js(&their_heads))?;
+        let frames = self.inner.generate_sync_messages_chunked(&heads, max_bytes);
+        Ok(crate::sync::frames_to_js(&frames))
+    }
+
+    /// Gets changes since the given heads as `{ frames, skeletonFrames }`,
+    /// with scenes, shots and entities before history and uploaded assets.
+    ///
+    /// Render the board once the first `skeletonFrames` frames are applied.
+    #[wasm_bindgen(js_name = getChangesSincePrioritized)]
+    pub fn get_changes_since_prioritized(
+        &mut self,
+        their_heads: Array,
+        max_bytes: usize,
+    ) -> Result<JsValue, JsValue> {
+        let heads = js_result!(crate::sync::heads_from_js(&their_heads))?;
+        let prioritized = self.inner.generate_sync_messages_prioritized(&heads, max_bytes);
+        Ok(crate::sync::prioritized_to_js(&prioritized))
+    }
+
+    /// Gets changes since the given heads as chunks of at most `maxBytes`,
+    /// splitting even single oversized changes. Feed every chunk to
+    /// `applySyncChunk` on the receiving side, in any order.
+    #[wasm_bindgen(js_name = generateSyncChunks)]
+    pub fn generate_sync_chunks(
+        &mut self,
+        their_heads: Array,
+        max_bytes: usize,
+    ) -> Result<Array, JsValue> {
+        let heads = js_result!(crate::sync::heads_from_js(&their_heads))?;
+        let chunks = self.inner.generate_sync_chunks(&heads, max_bytes);
+        Ok(crate::sync::frames_to_js(&chunks))
+    }
+
+    /// Adds a chunk from `generateSyncChunks`. Returns true once a complete
+    /// message was verified and applied.
+    #[wasm_bindgen(js_name = applySyncChunk)]
+    pub fn apply_sync_chunk(&mut self, chunk: &[u8]) -> Result<bool, JsValue> {
+        js_result!(self.inner.apply_sync_chunk(chunk))
+    }
+
     /// Generates a sync message for changes since their heads.
     /// @deprecated Use getChangesSince instead
     #[wasm_bindgen(js_name = generateSyncMessage)]
@@ -802,6 +1873,37 @@ impl JsStoryboardManager {
     }
 }
 
+// =============================================================================
+// GENERIC ENTITY HELPERS
+// =============================================================================
+
+/// Shared bodies of the character, prop and set bindings (`wasm_bindgen`
+/// cannot export generic methods).
+impl JsStoryboardManager {
+    fn create_entity<T: StoryboardEntity>(&mut self, id: &str, entity: JsValue) -> Result<(), JsValue> {
+        let entity: T = from_value(entity)?;
+        js_result!(self.inner.entities::<T>().create(id, entity))
+    }
+
+    fn get_entity<T: StoryboardEntity>(&mut self, id: &str) -> Result<JsValue, JsValue> {
+        let entity = js_result!(self.inner.entities::<T>().get(id))?;
+        Ok(to_js_value(&entity)?)
+    }
+
+    fn delete_entity<T: StoryboardEntity>(&mut self, id: &str) -> Result<(), JsValue> {
+        js_result!(self.inner.entities::<T>().delete(id))
+    }
+
+    fn append_entity_history<T: StoryboardEntity>(
+        &mut self,
+        id: &str,
+        entry: JsValue,
+    ) -> Result<(), JsValue> {
+        let entry: AssetHistory = from_value(entry)?;
+        js_result!(self.inner.entities::<T>().append_history(id, entry))
+    }
+}
+
 impl Default for JsStoryboardManager {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,88 @@
+//! Stale-status sweeping shared by the sequence and storyboard managers.
+//!
+//! Render workers set a "processing" status and are expected to replace it
+//! with "completed" or "failed". A crashed worker never does, leaving the item
+//! stuck forever. `sweep(now)` downgrades such items to "stalled" once their
+//! status is older than a configurable TTL.
+//!
+//! On storyboards the same change also removes scene locks that expired
+//! before `now` or whose scene no longer exists, so a document nobody claims
+//! scenes in again does not keep stale locks forever.
+
+use serde::{Deserialize, Serialize};
+
+/// Status that `sweep()` looks for.
+pub const PROCESSING_STATUS: &str = "processing";
+
+/// Status that stale items are downgraded to.
+pub const STALLED_STATUS: &str = "stalled";
+
+/// Configuration for `sweep()`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SweepConfig {
+    /// How long an item may stay "processing" before it is considered stalled (ms).
+    pub processing_ttl_ms: i64,
+}
+
+impl SweepConfig {
+    /// Creates a config with the given processing TTL in milliseconds.
+    pub fn new(processing_ttl_ms: i64) -> Self {
+        Self { processing_ttl_ms }
+    }
+
+    /// Returns true if a "processing" status set at `updated_at` has expired at `now`.
+    pub fn is_expired(&self, updated_at: i64, now: i64) -> bool {
+        now - updated_at > self.processing_ttl_ms
+    }
+}
+
+impl Default for SweepConfig {
+    /// Ten minutes.
+    fn default() -> Self {
+        Self::new(10 * 60 * 1000)
+    }
+}
+
+/// An item whose status was downgraded by `sweep()`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StalledItem {
+    /// Location of the item, e.g. `["gen-1"]` or `["scene-1", "shot-3"]`.
+    pub path: Vec<String>,
+    /// When the item entered "processing" (ms since epoch).
+    pub since: i64,
+}
+
+/// Summary of what `sweep()` changed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SweepReport {
+    /// Items downgraded from "processing" to "stalled".
+    pub stalled: Vec<StalledItem>,
+    /// Scene IDs whose expired or orphaned lock was removed (storyboards
+    /// only), sorted.
+    #[serde(default)]
+    pub released_locks: Vec<String>,
+}
+
+impl SweepReport {
+    /// Returns true if the sweep changed nothing.
+    pub fn is_empty(&self) -> bool {
+        self.stalled.is_empty() && self.released_locks.is_empty()
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_expired() {
+        let config = SweepConfig::new(1000);
+        assert!(!config.is_expired(5000, 5500));
+        assert!(!config.is_expired(5000, 6000));
+        assert!(config.is_expired(5000, 6001));
+    }
+}
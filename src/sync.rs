@@ -0,0 +1,448 @@
+//! Size-bounded sync frames.
+//!
+//! A catch-up sync message is the concatenation of every missing change, so
+//! the first sync of a large document can be several megabytes. Chunked
+//! generation packs whole changes into frames of at most `max_bytes` each.
+//! Frames are produced in causal order and each one is a valid sync message
+//! on its own: receivers apply them one by one with `apply_sync_message()`
+//! and can render progress between frames.
+//!
+//! A single change larger than `max_bytes` cannot be split and is sent as a
+//! frame of its own.
+//!
+//! Prioritized generation additionally reorders the missing changes so that
+//! structural/metadata changes come before changes that only touch heavy
+//! subtrees (history, outputs, comments, uploaded assets). A joining client
+//! can render the board skeleton after the first `skeleton_frames` frames
+//! while the media history streams in. The order is always a valid causal
+//! order: a structural change that depends on a heavy one still waits for it,
+//! so the gain is largest when media was written concurrently (e.g. by a
+//! render worker) rather than interleaved in one peer's linear history.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use automerge::{AutoCommit, ChangeHash, ObjId, ObjType, ReadDoc, Value, ROOT};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CollabError, CollabResult};
+use crate::sequence::SequenceManager;
+#[cfg(feature = "storyboard")]
+use crate::storyboard::StoryboardManager;
+
+/// Per-peer state of the Automerge sync protocol. Keep one per connection
+/// and start fresh after a reconnect.
+pub use automerge::sync::State as PeerState;
+
+/// Smallest accepted frame budget; smaller values are raised to this.
+pub const MIN_FRAME_BYTES: usize = 1024;
+
+/// Packs the changes missing from `their_heads` into frames of at most
+/// `max_bytes` (except for single oversized changes).
+pub(crate) fn chunked_changes(
+    doc: &mut AutoCommit,
+    their_heads: &[ChangeHash],
+    max_bytes: usize,
+) -> Vec<Vec<u8>> {
+    let changes = doc.get_changes(their_heads);
+    let mut packer = FramePacker::new(max_bytes);
+    for change in changes {
+        packer.push(change.raw_bytes());
+    }
+    packer.finish()
+}
+
+/// A document that can take part in the Automerge sync protocol.
+///
+/// Implemented by both managers so transports (e.g. `crate::client`) can be
+/// written once.
+pub trait SyncDocument {
+    /// See `SequenceManager::generate_protocol_message()`.
+    fn generate_protocol_message(&mut self, peer: &mut PeerState) -> Option<Vec<u8>>;
+
+    /// See `SequenceManager::receive_protocol_message()`.
+    fn receive_protocol_message(&mut self, peer: &mut PeerState, message: &[u8]) -> CollabResult<()>;
+
+    /// Returns the current heads.
+    fn heads(&mut self) -> Vec<ChangeHash>;
+}
+
+macro_rules! impl_sync_document {
+    ($manager:ty) => {
+        impl SyncDocument for $manager {
+            fn generate_protocol_message(&mut self, peer: &mut PeerState) -> Option<Vec<u8>> {
+                <$manager>::generate_protocol_message(self, peer)
+            }
+
+            fn receive_protocol_message(
+                &mut self,
+                peer: &mut PeerState,
+                message: &[u8],
+            ) -> CollabResult<()> {
+                <$manager>::receive_protocol_message(self, peer, message)
+            }
+
+            fn heads(&mut self) -> Vec<ChangeHash> {
+                self.get_heads()
+            }
+        }
+    };
+}
+
+impl_sync_document!(SequenceManager);
+#[cfg(feature = "storyboard")]
+impl_sync_document!(StoryboardManager);
+
+/// Frames produced by prioritized sync generation.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PrioritizedFrames {
+    /// Frames in send order.
+    pub frames: Vec<Vec<u8>>,
+    /// Number of leading frames that contain no heavy-only change. Once these
+    /// are applied the receiver has the complete document structure.
+    pub skeleton_frames: usize,
+}
+
+impl PrioritizedFrames {
+    /// Returns true if there is nothing to send.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+/// Like `chunked_changes()`, but sends structural changes before changes
+/// that only touch subtrees stored under one of `heavy_keys`.
+pub(crate) fn prioritized_changes(
+    doc: &mut AutoCommit,
+    their_heads: &[ChangeHash],
+    max_bytes: usize,
+    heavy_keys: &[&str],
+) -> PrioritizedFrames {
+    let mut heavy_objects = HashSet::new();
+    collect_heavy_objects(doc, &ROOT, heavy_keys, false, &mut heavy_objects);
+
+    let changes = doc.get_changes(their_heads);
+    let index: HashMap<ChangeHash, usize> = changes
+        .iter()
+        .enumerate()
+        .map(|(i, change)| (change.hash(), i))
+        .collect();
+
+    // A change is heavy if every op it contains targets a heavy object
+    let heavy: Vec<bool> = changes
+        .iter()
+        .map(|change| {
+            let ops = change.decode().operations;
+            !ops.is_empty()
+                && ops
+                    .iter()
+                    .all(|op| heavy_objects.contains(&op.obj.to_string()))
+        })
+        .collect();
+
+    // Topological sort that prefers structural changes, ties broken by the
+    // original (causal) position
+    let mut waiting = vec![0usize; changes.len()];
+    let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); changes.len()];
+    for (i, change) in changes.iter().enumerate() {
+        for dep in change.deps() {
+            if let Some(&j) = index.get(dep) {
+                waiting[i] += 1;
+                dependents[j].push(i);
+            }
+        }
+    }
+
+    let mut ready_structural = BTreeSet::new();
+    let mut ready_heavy = BTreeSet::new();
+    for (i, &count) in waiting.iter().enumerate() {
+        if count == 0 {
+            if heavy[i] {
+                ready_heavy.insert(i);
+            } else {
+                ready_structural.insert(i);
+            }
+        }
+    }
+
+    let mut packer = FramePacker::new(max_bytes);
+    let mut skeleton_frames = None;
+    while let Some(i) = ready_structural
+        .pop_first()
+        .or_else(|| ready_heavy.pop_first())
+    {
+        if heavy[i] && skeleton_frames.is_none() {
+            packer.flush();
+            skeleton_frames = Some(packer.frames.len());
+        }
+        packer.push(changes[i].raw_bytes());
+
+        for &j in &dependents[i] {
+            waiting[j] -= 1;
+            if waiting[j] == 0 {
+                if heavy[j] {
+                    ready_heavy.insert(j);
+                } else {
+                    ready_structural.insert(j);
+                }
+            }
+        }
+    }
+
+    let frames = packer.finish();
+    PrioritizedFrames {
+        skeleton_frames: skeleton_frames.unwrap_or(frames.len()),
+        frames,
+    }
+}
+
+/// Collects the IDs of all objects below `obj` that sit under a heavy key.
+fn collect_heavy_objects(
+    doc: &AutoCommit,
+    obj: &ObjId,
+    heavy_keys: &[&str],
+    heavy: bool,
+    out: &mut HashSet<String>,
+) {
+    let children: Vec<(bool, ObjId)> = match doc.object_type(obj) {
+        Ok(ObjType::Map) | Ok(ObjType::Table) => doc
+            .keys(obj)
+            .filter_map(|key| {
+                let child = child_object(doc.get(obj, key.as_str()))?;
+                Some((heavy || heavy_keys.contains(&key.as_str()), child))
+            })
+            .collect(),
+        Ok(ObjType::List) => (0..doc.length(obj))
+            .filter_map(|i| Some((heavy, child_object(doc.get(obj, i))?)))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    for (child_heavy, child) in children {
+        if child_heavy {
+            out.insert(child.to_string());
+        }
+        collect_heavy_objects(doc, &child, heavy_keys, child_heavy, out);
+    }
+}
+
+fn child_object(
+    entry: Result<Option<(Value<'_>, ObjId)>, automerge::AutomergeError>,
+) -> Option<ObjId> {
+    match entry {
+        Ok(Some((Value::Object(_), id))) => Some(id),
+        _ => None,
+    }
+}
+
+/// Accumulates raw changes into frames of at most `max_bytes`.
+struct FramePacker {
+    max_bytes: usize,
+    frames: Vec<Vec<u8>>,
+    current: Vec<u8>,
+}
+
+impl FramePacker {
+    fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes: max_bytes.max(MIN_FRAME_BYTES),
+            frames: Vec::new(),
+            current: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, raw: &[u8]) {
+        if !self.current.is_empty() && self.current.len() + raw.len() > self.max_bytes {
+            self.flush();
+        }
+        self.current.extend_from_slice(raw);
+    }
+
+    fn flush(&mut self) {
+        if !self.current.is_empty() {
+            self.frames.push(std::mem::take(&mut self.current));
+        }
+    }
+
+    fn finish(mut self) -> Vec<Vec<u8>> {
+        self.flush();
+        self.frames
+    }
+}
+
+/// Parses a hex-encoded change hash.
+pub fn parse_change_hash(hex: &str) -> CollabResult<ChangeHash> {
+    hex.parse().map_err(|_| CollabError::invalid_change_hash(hex))
+}
+
+/// Parses hex-encoded change hashes, failing on the first malformed entry.
+#[cfg(feature = "wasm")]
+pub(crate) fn heads_from_js(heads: &js_sys::Array) -> CollabResult<Vec<ChangeHash>> {
+    heads
+        .iter()
+        .map(|v| match v.as_string() {
+            Some(hex) => parse_change_hash(&hex),
+            None => Err(CollabError::invalid_change_hash(format!("{:?}", v))),
+        })
+        .collect()
+}
+
+/// Converts frames into a JS array of Uint8Arrays.
+#[cfg(feature = "wasm")]
+pub(crate) fn frames_to_js(frames: &[Vec<u8>]) -> js_sys::Array {
+    frames
+        .iter()
+        .map(|frame| wasm_bindgen::JsValue::from(js_sys::Uint8Array::from(&frame[..])))
+        .collect()
+}
+
+/// Converts prioritized frames into `{ frames: Uint8Array[], skeletonFrames }`.
+#[cfg(feature = "wasm")]
+pub(crate) fn prioritized_to_js(prioritized: &PrioritizedFrames) -> wasm_bindgen::JsValue {
+    let obj = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&obj, &"frames".into(), &frames_to_js(&prioritized.frames));
+    let _ = js_sys::Reflect::set(
+        &obj,
+        &"skeletonFrames".into(),
+        &(prioritized.skeleton_frames as u32).into(),
+    );
+    obj.into()
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequence::{GenerationNode, OutputAsset};
+
+    #[test]
+    fn test_chunked_sync_frames_apply_incrementally() {
+        let mut source = SequenceManager::new();
+        let mut target = SequenceManager::from_bytes(&source.save()).unwrap();
+        let their_heads = target.get_heads();
+
+        // One change per node, ~250 bytes each
+        for i in 0..40 {
+            let id = format!("node-{}", i);
+            let node = GenerationNode::new(&id, "t2i").with_prompt("x".repeat(200));
+            source
+                .update_state_with_message("Add node", i, |state| {
+                    state.generations.insert(id.clone(), node);
+                })
+                .unwrap();
+        }
+
+        let full = source.generate_sync_message(&their_heads).unwrap();
+        let frames = source.generate_sync_messages_chunked(&their_heads, 2048);
+        assert!(frames.len() > 1);
+        assert!(frames.iter().all(|f| f.len() <= 2048));
+        assert_eq!(frames.iter().map(Vec::len).sum::<usize>(), full.len());
+
+        for frame in &frames {
+            target.apply_sync_message(frame).unwrap();
+        }
+        assert_eq!(target.get_state().unwrap(), source.get_state().unwrap());
+
+        // Nothing missing -> no frames
+        let heads = source.get_heads();
+        assert!(source.generate_sync_messages_chunked(&heads, 2048).is_empty());
+    }
+
+    #[test]
+    fn test_prioritized_sync_sends_structure_first() {
+        let mut source = SequenceManager::new();
+        source
+            .update_state_with_message("Add node", 0, |state| {
+                state
+                    .generations
+                    .insert("node-0".to_string(), GenerationNode::new("node-0", "t2i"));
+            })
+            .unwrap();
+        let mut target = SequenceManager::from_bytes(&source.save()).unwrap();
+        let their_heads = target.get_heads();
+
+        // A render worker attaches outputs concurrently with structural edits
+        let mut worker = SequenceManager::from_bytes(&source.save()).unwrap();
+        for i in 0..10 {
+            worker
+                .update_state_with_message("Add output", i, |state| {
+                    let node = state.generations.get_mut("node-0").unwrap();
+                    node.outputs
+                        .push(OutputAsset::new(format!("https://cdn/{}/{}", i, "x".repeat(200))));
+                })
+                .unwrap();
+        }
+        for i in 1..10 {
+            let id = format!("node-{}", i);
+            source
+                .update_state_with_message("Add node", i, |state| {
+                    state
+                        .generations
+                        .insert(id.clone(), GenerationNode::new(&id, "t2i"));
+                })
+                .unwrap();
+        }
+        source.merge(&mut worker).unwrap();
+
+        let prioritized = source.generate_sync_messages_prioritized(&their_heads, 1024);
+        assert!(prioritized.skeleton_frames >= 1);
+        assert!(prioritized.skeleton_frames < prioritized.frames.len());
+
+        for frame in &prioritized.frames[..prioritized.skeleton_frames] {
+            target.apply_sync_message(frame).unwrap();
+        }
+        let skeleton = target.get_state().unwrap();
+        assert_eq!(skeleton.generations.order().len(), 10);
+        assert!(skeleton.generations["node-0"].outputs.is_empty());
+
+        for frame in &prioritized.frames[prioritized.skeleton_frames..] {
+            target.apply_sync_message(frame).unwrap();
+        }
+        assert_eq!(target.get_state().unwrap(), source.get_state().unwrap());
+    }
+
+    #[test]
+    fn test_parse_change_hash() {
+        let mut manager = SequenceManager::new();
+        let head = manager.get_heads()[0];
+        assert_eq!(parse_change_hash(&head.to_string()).unwrap(), head);
+        for bad in [String::new(), "abc".to_string(), "zz".repeat(32), "ab".repeat(31)] {
+            assert!(matches!(
+                parse_change_hash(&bad),
+                Err(CollabError::InvalidChangeHash(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_protocol_sync_converges() {
+        let mut alice = SequenceManager::new();
+        let mut bob = SequenceManager::from_bytes(&alice.save()).unwrap();
+        alice.create_and_append("a", GenerationNode::new("a", "t2i")).unwrap();
+        bob.create_and_append("b", GenerationNode::new("b", "t2i")).unwrap();
+
+        let mut alice_peer = PeerState::new();
+        let mut bob_peer = PeerState::new();
+        for _ in 0..10 {
+            let to_bob = alice.generate_protocol_message(&mut alice_peer);
+            let to_alice = bob.generate_protocol_message(&mut bob_peer);
+            if to_bob.is_none() && to_alice.is_none() {
+                break;
+            }
+            if let Some(message) = to_bob {
+                bob.receive_protocol_message(&mut bob_peer, &message).unwrap();
+            }
+            if let Some(message) = to_alice {
+                alice.receive_protocol_message(&mut alice_peer, &message).unwrap();
+            }
+        }
+
+        assert_eq!(alice.get_heads(), bob.get_heads());
+        assert_eq!(alice.get_state().unwrap(), bob.get_state().unwrap());
+        assert!(alice
+            .receive_protocol_message(&mut alice_peer, b"garbage")
+            .is_err());
+    }
+}
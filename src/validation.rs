@@ -0,0 +1,109 @@
+//! Structured validation results shared by the document managers.
+//!
+//! Validation never modifies a document; it reports inconsistencies so the
+//! UI can warn before saving (or a repair step can fix them). Errors mark
+//! data that is broken (e.g. an order list pointing at a missing item);
+//! warnings mark data that is suspicious but still usable.
+
+use serde::{Deserialize, Serialize};
+
+/// How serious a validation issue is.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A single validation finding.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    /// Stable machine-readable code, e.g. "missing_scene".
+    pub code: String,
+    /// Location of the issue, e.g. `["scene-1", "shot_order", "shot-9"]`.
+    pub path: Vec<String>,
+    /// Human-readable description.
+    pub message: String,
+}
+
+/// All findings of a validation run.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ValidationReport {
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// Creates an empty report.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns true if there are no errors (warnings are allowed).
+    pub fn is_valid(&self) -> bool {
+        !self.issues.iter().any(|i| i.severity == Severity::Error)
+    }
+
+    /// Returns true if there are no issues at all.
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Returns the issues with `Severity::Error`.
+    pub fn errors(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues.iter().filter(|i| i.severity == Severity::Error)
+    }
+
+    /// Returns the issues with `Severity::Warning`.
+    pub fn warnings(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues.iter().filter(|i| i.severity == Severity::Warning)
+    }
+
+    /// Returns true if any issue has the given code.
+    pub fn has_code(&self, code: &str) -> bool {
+        self.issues.iter().any(|i| i.code == code)
+    }
+
+    pub(crate) fn error(&mut self, code: &str, path: &[&str], message: impl Into<String>) {
+        self.push(Severity::Error, code, path, message.into());
+    }
+
+    pub(crate) fn warning(&mut self, code: &str, path: &[&str], message: impl Into<String>) {
+        self.push(Severity::Warning, code, path, message.into());
+    }
+
+    fn push(&mut self, severity: Severity, code: &str, path: &[&str], message: String) {
+        self.issues.push(ValidationIssue {
+            severity,
+            code: code.to_string(),
+            path: path.iter().map(|p| p.to_string()).collect(),
+            message,
+        });
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_severity() {
+        let mut report = ValidationReport::new();
+        assert!(report.is_valid() && report.is_empty());
+
+        report.warning("unordered_scene", &["scenes", "s1"], "not in order");
+        assert!(report.is_valid());
+        report.error("missing_scene", &["scene_order", "s2"], "missing");
+        assert!(!report.is_valid());
+        assert_eq!(report.errors().count(), 1);
+        assert_eq!(report.warnings().count(), 1);
+        assert!(report.has_code("missing_scene"));
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["issues"][1]["severity"], "error");
+    }
+}